@@ -134,6 +134,12 @@ enum Subcommand {
     /// Resume a previous interactive session (picker by default; use --last to continue the most recent).
     Resume(ResumeCommand),
 
+    /// Import conversations from an OpenAI export or a Markdown chat log as resumable sessions.
+    Import(ImportCommand),
+
+    /// Search and inspect recorded sessions.
+    Sessions(SessionsCommand),
+
     /// Internal: generate TypeScript protocol bindings.
     #[clap(hide = true)]
     GenerateTs(GenerateTsCommand),
@@ -177,6 +183,56 @@ struct ResumeCommand {
     config_overrides: TuiCli,
 }
 
+#[derive(Debug, Parser)]
+struct ImportCommand {
+    /// `conversations.json` from an OpenAI data export, or a Markdown chat log.
+    #[arg(value_name = "FILE")]
+    path: PathBuf,
+
+    /// Input format (`openai` or `markdown`); detected from the file when omitted.
+    #[arg(long = "format", value_name = "FORMAT")]
+    format: Option<code_core::conversation_import::ImportFormat>,
+
+    /// Working directory recorded for the imported sessions (defaults to the current directory).
+    #[arg(long = "cd", short = 'C', value_name = "DIR")]
+    cwd: Option<PathBuf>,
+}
+
+#[derive(Debug, Parser)]
+struct SessionsCommand {
+    #[command(subcommand)]
+    cmd: SessionsSubcommand,
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum SessionsSubcommand {
+    /// Find sessions whose user or assistant messages contain every word of QUERY.
+    Search(SessionsSearchCommand),
+}
+
+#[derive(Debug, Parser)]
+struct SessionsSearchCommand {
+    /// Words to look for; end a word with `*` to match a prefix.
+    #[arg(value_name = "QUERY", required = true, num_args = 1..)]
+    query: Vec<String>,
+
+    /// Only search sessions recorded in this working directory.
+    #[arg(long = "cd", short = 'C', value_name = "DIR")]
+    cwd: Option<PathBuf>,
+
+    /// Only search sessions carrying this tag.
+    #[arg(long = "tag", value_name = "TAG")]
+    tag: Option<String>,
+
+    /// Maximum number of sessions to list.
+    #[arg(long = "limit", value_name = "N", default_value_t = 20)]
+    limit: usize,
+
+    /// Print the hits as JSON.
+    #[arg(long = "json", default_value_t = false)]
+    json: bool,
+}
+
 #[derive(Debug, Parser)]
 struct DebugArgs {
     #[command(subcommand)]
@@ -381,6 +437,14 @@ async fn cli_main(code_linux_sandbox_exe: Option<PathBuf>) -> anyhow::Result<()>
                 );
             }
         }
+        Some(Subcommand::Import(import_cli)) => {
+            import_main(import_cli, root_config_overrides.clone()).await?;
+        }
+        Some(Subcommand::Sessions(SessionsCommand {
+            cmd: SessionsSubcommand::Search(search_cli),
+        })) => {
+            sessions_search_main(search_cli).await?;
+        }
         Some(Subcommand::Login(mut login_cli)) => {
             prepend_config_flags(
                 &mut login_cli.config_overrides,
@@ -470,7 +534,7 @@ async fn cli_main(code_linux_sandbox_exe: Option<PathBuf>) -> anyhow::Result<()>
             order_replay_main(args)?;
         }
         Some(Subcommand::Doctor) => {
-            doctor_main().await?;
+            doctor_main(root_config_overrides.clone()).await?;
         }
         Some(Subcommand::Preview(args)) => {
             preview_main(args).await?;
@@ -601,6 +665,124 @@ fn apply_resume_directives(
     Ok(())
 }
 
+async fn import_main(
+    args: ImportCommand,
+    config_overrides: CliConfigOverrides,
+) -> anyhow::Result<()> {
+    use code_core::config::Config;
+    use code_core::config::ConfigOverrides;
+
+    let overrides = config_overrides
+        .parse_overrides()
+        .map_err(anyhow::Error::msg)?;
+    let config = Config::load_with_cli_overrides(overrides, ConfigOverrides::default())?;
+    let cipher = code_core::at_rest::cipher_for(config.encrypt_at_rest, &config.code_home)?;
+    let cwd = match args.cwd {
+        Some(dir) => dir,
+        None => std::env::current_dir().context("failed to resolve current directory")?,
+    };
+    let cwd = cwd
+        .canonicalize()
+        .with_context(|| format!("failed to resolve {}", cwd.display()))?;
+    let report = code_core::conversation_import::import_file(
+        &config.code_home,
+        cipher.as_deref(),
+        &cwd,
+        &args.path,
+        args.format,
+    )
+    .await?;
+    for skipped in &report.skipped {
+        let title = skipped.title.as_deref().unwrap_or("(untitled)");
+        eprintln!(
+            "Skipped conversation {} ({title}): {}",
+            skipped.index, skipped.reason
+        );
+    }
+    let sessions = report.sessions;
+    for session in &sessions {
+        let title = session.title.as_deref().unwrap_or("(untitled)");
+        println!(
+            "Imported {} ({} messages): {title}",
+            session.session_id, session.message_count
+        );
+    }
+    if let [session] = sessions.as_slice() {
+        println!(
+            "To continue this session, run {} resume {}",
+            resume_command_name(),
+            session.session_id
+        );
+    } else {
+        println!(
+            "Imported {} sessions; run {} resume to pick one.",
+            sessions.len(),
+            resume_command_name()
+        );
+    }
+    Ok(())
+}
+
+async fn sessions_search_main(args: SessionsSearchCommand) -> anyhow::Result<()> {
+    let code_home =
+        code_core::config::find_code_home().context("failed to locate Codex home directory")?;
+    let cwd = args
+        .cwd
+        .map(|dir| {
+            dir.canonicalize()
+                .with_context(|| format!("failed to resolve {}", dir.display()))
+        })
+        .transpose()?;
+    let query = SessionQuery {
+        cwd,
+        tag: args.tag,
+        limit: Some(args.limit),
+        ..SessionQuery::default()
+    };
+    let hits = SessionCatalog::new(code_home)
+        .search(&args.query.join(" "), &query)
+        .await
+        .context("failed to search sessions")?;
+
+    if args.json {
+        let hits: Vec<serde_json::Value> = hits
+            .iter()
+            .map(|hit| {
+                serde_json::json!({
+                    "session_id": hit.session_id,
+                    "timestamp": hit.timestamp,
+                    "role": hit.role,
+                    "snippet": hit.snippet,
+                    "score": hit.score,
+                    "cwd": hit.entry.cwd_real,
+                    "last_event_at": hit.entry.last_event_at,
+                })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&hits)?);
+        return Ok(());
+    }
+
+    if hits.is_empty() {
+        println!("No sessions match.");
+        return Ok(());
+    }
+    for hit in &hits {
+        println!(
+            "{}  {}  {}",
+            hit.session_id,
+            hit.timestamp,
+            hit.entry.cwd_real.display()
+        );
+        println!("    {}: {}", hit.role, hit.snippet);
+    }
+    println!(
+        "To continue a session, run {} resume <SESSION_ID>",
+        resume_command_name()
+    );
+    Ok(())
+}
+
 fn resolve_resume_path(session_id: Option<&str>, last: bool) -> anyhow::Result<Option<PathBuf>> {
     if session_id.is_none() && !last {
         return Ok(None);
@@ -630,6 +812,7 @@ fn resolve_resume_path(session_id: Option<&str>, last: bool) -> anyhow::Result<O
                 min_user_messages: 1,
                 include_archived: false,
                 include_deleted: false,
+                tag: None,
                 limit: Some(1),
             };
             let entry = catalog
@@ -1043,7 +1226,7 @@ async fn preview_main(args: PreviewArgs) -> anyhow::Result<()> {
     bail!("No recognized artifact content found.")
 }
 
-async fn doctor_main() -> anyhow::Result<()> {
+async fn doctor_main(config_overrides: CliConfigOverrides) -> anyhow::Result<()> {
     use std::env;
     use std::process::Stdio;
     use tokio::process::Command;
@@ -1177,6 +1360,8 @@ async fn doctor_main() -> anyhow::Result<()> {
         println!("npm prefix -g: {}", npm_prefix);
     }
 
+    print_doctor_proxies(config_overrides);
+
     println!("\nIf versions differ, remove older installs and keep one package manager:");
     println!("  - Bun: bun remove -g @just-every/code");
     println!("  - npm/pnpm: npm uninstall -g @just-every/code");
@@ -1185,6 +1370,47 @@ async fn doctor_main() -> anyhow::Result<()> {
 
     Ok(())
 }
+/// Prints the proxy each model provider's requests go through.
+fn print_doctor_proxies(config_overrides: CliConfigOverrides) {
+    use code_core::config::Config;
+    use code_core::config::ConfigOverrides;
+    use code_core::http_client::resolve_proxy;
+
+    println!("\nProxy:");
+    println!("  default: {}", resolve_proxy(None));
+    let config = config_overrides
+        .parse_overrides()
+        .map_err(anyhow::Error::msg)
+        .and_then(|overrides| {
+            Config::load_with_cli_overrides(overrides, ConfigOverrides::default())
+                .map_err(anyhow::Error::from)
+        });
+    let config = match config {
+        Ok(config) => config,
+        Err(err) => {
+            println!("  (could not load config to check provider proxies: {err})");
+            return;
+        }
+    };
+    println!(
+        "  active provider {}: {}",
+        config.model_provider_id,
+        resolve_proxy(config.model_provider.proxy.as_deref())
+    );
+    let mut overridden: Vec<_> = config
+        .model_providers
+        .iter()
+        .filter(|(id, provider)| provider.proxy.is_some() && **id != config.model_provider_id)
+        .collect();
+    overridden.sort_by_key(|(id, _)| id.as_str());
+    for (id, provider) in overridden {
+        println!(
+            "  provider {id}: {}",
+            resolve_proxy(provider.proxy.as_deref())
+        );
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
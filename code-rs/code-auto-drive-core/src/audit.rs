@@ -1,13 +1,66 @@
 //! Audit logger for tracking Auto Drive operations.
 //!
 //! This module provides comprehensive logging of all operations performed
-//! during Auto Drive sessions for security and debugging purposes.
-
+//! during Auto Drive sessions for security and debugging purposes. Each CLI
+//! turn records the model, sandbox policy, and approval policy it ran with so
+//! reviews of unattended runs can tell what privileges produced each change.
+//!
+//! The log file is append-only and tamper-evident: every entry carries the
+//! SHA-256 of the entry before it (`prev_hash`) and of itself (`hash`), so
+//! editing, reordering, or removing an entry breaks the chain from that point
+//! on. [`verify_audit_log`] checks a file. Dropping entries from the end is
+//! only detectable against a separately recorded final hash. With
+//! `encrypt_at_rest`, each line is sealed with the at-rest cipher; hashes
+//! cover the plaintext entries.
+
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
 use std::path::PathBuf;
+use std::sync::Arc;
 
+use anyhow::Context;
 use chrono::DateTime;
 use chrono::Utc;
+use code_common::summarize_sandbox_policy;
+use code_core::at_rest;
+use code_core::at_rest::AtRestCipher;
+use code_core::config::Config;
 use serde::Serialize;
+use serde_json::Value;
+use sha2::Digest;
+use sha2::Sha256;
+
+use crate::auto_coordinator::AutoCoordinatorEvent;
+
+const AUDIT_LOG_SUBDIR: &str = "auto_drive/audit";
+
+/// `prev_hash` of the first entry in a log.
+pub const AUDIT_GENESIS_HASH: &str =
+    "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Returns the audit log path for `session_id` under `code_home`.
+pub fn audit_log_path(code_home: &Path, session_id: &str) -> PathBuf {
+    code_home
+        .join(AUDIT_LOG_SUBDIR)
+        .join(format!("{session_id}.jsonl"))
+}
+
+/// Where the audit log of `session_id` is written: `auto_drive.audit_path`
+/// when set, otherwise [`audit_log_path`]. `None` while
+/// `auto_drive.audit_enabled` is off.
+pub fn configured_audit_log_path(config: &Config, session_id: &str) -> Option<PathBuf> {
+    let settings = &config.auto_drive;
+    if !settings.audit_enabled {
+        return None;
+    }
+    Some(
+        settings
+            .audit_path
+            .clone()
+            .unwrap_or_else(|| audit_log_path(&config.code_home, session_id)),
+    )
+}
 
 /// An entry in the audit log.
 #[derive(Clone, Debug, Serialize)]
@@ -20,6 +73,10 @@ pub struct AuditEntry {
     pub outcome: AuditOutcome,
     /// Optional additional context.
     pub context: Option<String>,
+    /// `hash` of the previous entry, or [`AUDIT_GENESIS_HASH`].
+    pub prev_hash: String,
+    /// Hex SHA-256 of this entry's JSON without the `hash` field.
+    pub hash: String,
 }
 
 /// Types of operations that can be audited.
@@ -44,6 +101,15 @@ pub enum AuditOperation {
     SessionStart { goal: String },
     /// Session ended.
     SessionEnd { turns: usize, success: bool },
+    /// A CLI turn was submitted with these privileges.
+    CliTurn {
+        turn: usize,
+        privileges: TurnPrivileges,
+    },
+    /// The coordinator made a decision.
+    CoordinatorDecision { seq: u64, status: String },
+    /// A diagnostic alert was raised, e.g. a loop or goal drift.
+    DiagnosticAlert { alert: String },
     /// Session was migrated to recover a stuck task.
     SessionMigration {
         from_session: String,
@@ -53,6 +119,42 @@ pub enum AuditOperation {
     },
 }
 
+/// Effective model and policies a CLI turn ran with.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct TurnPrivileges {
+    pub model: String,
+    pub sandbox_policy: String,
+    pub approval_policy: String,
+}
+
+impl TurnPrivileges {
+    /// Privileges of a turn in a session started from `config`.
+    /// `model_override` is the model the turn was switched to with
+    /// `Op::SetNextTurnModel`, if any.
+    pub fn for_turn(config: &Config, model_override: Option<&str>) -> Self {
+        Self {
+            model: model_override.unwrap_or(&config.model).to_string(),
+            sandbox_policy: summarize_sandbox_policy(&config.sandbox_policy),
+            approval_policy: config.approval_policy.to_string(),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        format!(
+            "model {}, sandbox {}, approval {}",
+            self.model, self.sandbox_policy, self.approval_policy
+        )
+    }
+}
+
+/// Consecutive CLI turns that ran with the same privileges.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct TurnPrivilegesSpan {
+    pub first_turn: usize,
+    pub last_turn: usize,
+    pub privileges: TurnPrivileges,
+}
+
 /// Actions that can be performed on files.
 #[derive(Clone, Debug, Serialize)]
 pub enum FileAction {
@@ -95,6 +197,9 @@ pub struct AuditSummary {
     pub file_modifications: usize,
     pub network_accesses: usize,
     pub agent_dispatches: usize,
+    pub cli_turns: usize,
+    /// Privileges of every CLI turn, with consecutive identical turns merged.
+    pub turn_privileges: Vec<TurnPrivilegesSpan>,
 }
 
 /// Export format for audit logs.
@@ -108,7 +213,9 @@ pub enum ExportFormat {
 pub struct AuditLogger {
     session_id: String,
     entries: Vec<AuditEntry>,
+    last_hash: String,
     log_path: Option<PathBuf>,
+    cipher: Option<Arc<AtRestCipher>>,
     workspace_root: Option<PathBuf>,
     network_allowlist: Vec<String>,
 }
@@ -119,18 +226,32 @@ impl AuditLogger {
         Self {
             session_id: session_id.to_string(),
             entries: Vec::new(),
+            last_hash: AUDIT_GENESIS_HASH.to_string(),
             log_path: None,
+            cipher: None,
             workspace_root: None,
             network_allowlist: Vec::new(),
         }
     }
 
-    /// Sets the log file path.
-    pub fn with_log_path(mut self, path: PathBuf) -> Self {
-        self.log_path = Some(path);
+    /// Seals log file lines with `cipher`. Set it before
+    /// [`Self::with_log_path`] so the chain of a sealed file can continue.
+    pub fn with_at_rest_cipher(mut self, cipher: Option<Arc<AtRestCipher>>) -> Self {
+        self.cipher = cipher;
         self
     }
 
+    /// Sets the log file path. Entries continue the chain of an existing
+    /// file; fails when its last entry cannot be opened or has no hash, so a
+    /// damaged or foreign log never gets a fresh chain appended.
+    pub fn with_log_path(mut self, path: PathBuf) -> anyhow::Result<Self> {
+        if let Some(hash) = last_logged_hash(&path, self.cipher.as_deref())? {
+            self.last_hash = hash;
+        }
+        self.log_path = Some(path);
+        Ok(self)
+    }
+
     /// Sets the workspace root for path validation.
     pub fn with_workspace_root(mut self, root: PathBuf) -> Self {
         self.workspace_root = Some(root);
@@ -155,12 +276,16 @@ impl AuditLogger {
         outcome: AuditOutcome,
         context: Option<String>,
     ) {
-        let entry = AuditEntry {
+        let mut entry = AuditEntry {
             timestamp: Utc::now(),
             operation,
             outcome,
             context,
+            prev_hash: self.last_hash.clone(),
+            hash: String::new(),
         };
+        entry.hash = entry_hash(&entry);
+        self.last_hash = entry.hash.clone();
 
         tracing::debug!(
             session_id = %self.session_id,
@@ -169,9 +294,76 @@ impl AuditLogger {
             "Audit log entry"
         );
 
+        if let Some(path) = &self.log_path
+            && let Err(err) = append_entry(path, &entry, self.cipher.as_deref())
+        {
+            tracing::warn!("failed to write audit log {}: {err:#}", path.display());
+        }
         self.entries.push(entry);
     }
 
+    /// Records the coordinator events compliance reviews need: decisions,
+    /// agent launches, and budget and diagnostic alerts. CLI prompts are
+    /// recorded with their turn by the caller that submits them.
+    pub fn observe(&mut self, event: &AutoCoordinatorEvent) {
+        match event {
+            AutoCoordinatorEvent::Decision {
+                seq,
+                status,
+                status_title,
+                status_sent_to_user,
+                agents,
+                ..
+            } => {
+                let summary = [status_title, status_sent_to_user]
+                    .into_iter()
+                    .flatten()
+                    .map(String::as_str)
+                    .collect::<Vec<_>>()
+                    .join(": ");
+                self.log_with_context(
+                    AuditOperation::CoordinatorDecision {
+                        seq: *seq,
+                        status: format!("{status:?}"),
+                    },
+                    AuditOutcome::Success,
+                    (!summary.is_empty()).then_some(summary),
+                );
+                for (index, agent) in agents.iter().enumerate() {
+                    self.log_with_context(
+                        AuditOperation::AgentDispatch {
+                            agent_id: dispatch_agent_id(*seq, index),
+                            write_access: agent.write,
+                        },
+                        AuditOutcome::Success,
+                        Some(agent.prompt.clone()),
+                    );
+                }
+            }
+            AutoCoordinatorEvent::BudgetAlert {
+                alert_type,
+                message,
+            } => self.log_with_context(
+                AuditOperation::BudgetWarning {
+                    alert: format!("{alert_type:?}"),
+                },
+                AuditOutcome::Success,
+                Some(message.clone()),
+            ),
+            AutoCoordinatorEvent::DiagnosticAlert {
+                alert_type,
+                message,
+            } => self.log_with_context(
+                AuditOperation::DiagnosticAlert {
+                    alert: format!("{alert_type:?}"),
+                },
+                AuditOutcome::Success,
+                Some(message.clone()),
+            ),
+            _ => {}
+        }
+    }
+
     /// Validates that a file path is within the workspace.
     pub fn validate_file_path(&self, path: &PathBuf) -> Result<(), String> {
         let Some(workspace) = &self.workspace_root else {
@@ -232,6 +424,17 @@ impl AuditLogger {
                 AuditOperation::FileModification { .. } => summary.file_modifications += 1,
                 AuditOperation::NetworkAccess { .. } => summary.network_accesses += 1,
                 AuditOperation::AgentDispatch { .. } => summary.agent_dispatches += 1,
+                AuditOperation::CliTurn { turn, privileges } => {
+                    summary.cli_turns += 1;
+                    match summary.turn_privileges.last_mut() {
+                        Some(span) if span.privileges == *privileges => span.last_turn = *turn,
+                        _ => summary.turn_privileges.push(TurnPrivilegesSpan {
+                            first_turn: *turn,
+                            last_turn: *turn,
+                            privileges: privileges.clone(),
+                        }),
+                    }
+                }
                 _ => {}
             }
         }
@@ -258,6 +461,11 @@ impl AuditLogger {
                         AuditOperation::SessionStart { .. } => "session_start".to_string(),
                         AuditOperation::SessionEnd { .. } => "session_end".to_string(),
                         AuditOperation::SessionMigration { .. } => "session_migration".to_string(),
+                        AuditOperation::CliTurn { turn, .. } => format!("cli_turn:{turn}"),
+                        AuditOperation::CoordinatorDecision { status, .. } => {
+                            format!("decision:{status}")
+                        }
+                        AuditOperation::DiagnosticAlert { alert } => format!("diagnostic:{alert}"),
                     };
                     let outcome = match &entry.outcome {
                         AuditOutcome::Success => "success".to_string(),
@@ -322,9 +530,132 @@ impl AuditLogger {
     }
 }
 
+/// Audit id of the `index`th agent launched by decision `seq`.
+pub fn dispatch_agent_id(seq: u64, index: usize) -> String {
+    format!("decision-{seq}-agent-{}", index + 1)
+}
+
+/// Result of checking an audit log's hash chain.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ChainVerification {
+    /// Every entry links to the one before it. Record `last_hash` elsewhere
+    /// to detect entries later removed from the end.
+    Valid { entries: usize, last_hash: String },
+    /// The chain breaks at this 1-based line.
+    Broken { line: usize, reason: String },
+}
+
+/// Checks the hash chain of the audit log at `path`, opening lines sealed
+/// with the at-rest key of `code_home`.
+pub fn verify_audit_log(path: &Path, code_home: &Path) -> anyhow::Result<ChainVerification> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    let mut opened = String::with_capacity(contents.len());
+    for line in contents.lines() {
+        let line = at_rest::open_line_in(code_home, line)
+            .with_context(|| format!("failed to decrypt {}", path.display()))?;
+        opened.push_str(&line);
+        opened.push('\n');
+    }
+    Ok(verify_audit_chain(&opened))
+}
+
+/// Checks the hash chain of audit log `contents`.
+pub fn verify_audit_chain(contents: &str) -> ChainVerification {
+    let mut expected_prev = AUDIT_GENESIS_HASH.to_string();
+    let mut entries = 0;
+    for (index, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let broken = |reason: &str| ChainVerification::Broken {
+            line: index + 1,
+            reason: reason.to_string(),
+        };
+        let Ok(mut value) = serde_json::from_str::<Value>(line) else {
+            return broken("not a JSON entry");
+        };
+        let Some(Value::String(hash)) =
+            value.as_object_mut().and_then(|entry| entry.remove("hash"))
+        else {
+            return broken("entry has no hash");
+        };
+        if value.get("prev_hash").and_then(Value::as_str) != Some(expected_prev.as_str()) {
+            return broken("prev_hash does not match the previous entry");
+        }
+        if sha256_hex(&value) != hash {
+            return broken("hash does not match the entry's contents");
+        }
+        expected_prev = hash;
+        entries += 1;
+    }
+    ChainVerification::Valid {
+        entries,
+        last_hash: expected_prev,
+    }
+}
+
+fn entry_hash(entry: &AuditEntry) -> String {
+    let mut value = serde_json::to_value(entry).unwrap_or(Value::Null);
+    if let Some(fields) = value.as_object_mut() {
+        fields.remove("hash");
+    }
+    sha256_hex(&value)
+}
+
+fn sha256_hex(value: &Value) -> String {
+    format!("{:x}", Sha256::digest(value.to_string().as_bytes()))
+}
+
+/// `hash` of the last entry already in the file at `path`; `None` when the
+/// file is missing or empty.
+fn last_logged_hash(path: &Path, cipher: Option<&AtRestCipher>) -> anyhow::Result<Option<String>> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => {
+            return Err(err).with_context(|| format!("failed to read {}", path.display()));
+        }
+    };
+    let Some(line) = contents.lines().rev().find(|line| !line.trim().is_empty()) else {
+        return Ok(None);
+    };
+    let line = match cipher {
+        Some(cipher) => cipher
+            .open_line(line)
+            .with_context(|| format!("failed to decrypt {}", path.display()))?,
+        None => line.to_string(),
+    };
+    let value: Value = serde_json::from_str(&line)
+        .with_context(|| format!("last entry of {} is not JSON", path.display()))?;
+    let hash = value
+        .get("hash")
+        .and_then(Value::as_str)
+        .with_context(|| format!("last entry of {} has no hash", path.display()))?;
+    Ok(Some(hash.to_string()))
+}
+
+fn append_entry(
+    path: &Path,
+    entry: &AuditEntry,
+    cipher: Option<&AtRestCipher>,
+) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut line = serde_json::to_string(entry)?;
+    if let Some(cipher) = cipher {
+        line = cipher.seal_line(&line)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{line}")?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use pretty_assertions::assert_eq;
 
     #[test]
     fn test_log_operation() {
@@ -375,6 +706,220 @@ mod tests {
         assert_eq!(summary.file_modifications, 1);
     }
 
+    #[test]
+    fn audit_file_follows_audit_settings() {
+        let code_home = tempfile::tempdir().unwrap();
+        let mut config = Config::load_from_base_config_with_overrides(
+            code_core::config::ConfigToml::default(),
+            code_core::config::ConfigOverrides {
+                cwd: Some(code_home.path().to_path_buf()),
+                ..Default::default()
+            },
+            code_home.path().to_path_buf(),
+        )
+        .unwrap();
+        assert_eq!(configured_audit_log_path(&config, "s1"), None);
+
+        config.auto_drive.audit_enabled = true;
+        assert_eq!(
+            configured_audit_log_path(&config, "s1"),
+            Some(audit_log_path(code_home.path(), "s1"))
+        );
+
+        let custom = code_home.path().join("audit.jsonl");
+        config.auto_drive.audit_path = Some(custom.clone());
+        assert_eq!(configured_audit_log_path(&config, "s1"), Some(custom));
+
+        config.model = "gpt-5".to_string();
+        assert_eq!(
+            TurnPrivileges::for_turn(&config, Some("gpt-5-mini")).model,
+            "gpt-5-mini"
+        );
+        assert_eq!(TurnPrivileges::for_turn(&config, None).model, "gpt-5");
+    }
+
+    #[test]
+    fn test_turn_privileges_summary_and_log_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = audit_log_path(dir.path(), "s1");
+        let mut logger = AuditLogger::new("s1").with_log_path(path.clone()).unwrap();
+        let privileges = |sandbox: &str| TurnPrivileges {
+            model: "gpt-5".to_string(),
+            sandbox_policy: sandbox.to_string(),
+            approval_policy: "never".to_string(),
+        };
+        for (turn, sandbox) in [(1, "read-only"), (2, "read-only"), (3, "workspace-write")] {
+            logger.log(
+                AuditOperation::CliTurn {
+                    turn,
+                    privileges: privileges(sandbox),
+                },
+                AuditOutcome::Success,
+            );
+        }
+
+        let summary = logger.generate_summary();
+        assert_eq!(summary.cli_turns, 3);
+        assert_eq!(
+            summary.turn_privileges,
+            vec![
+                TurnPrivilegesSpan {
+                    first_turn: 1,
+                    last_turn: 2,
+                    privileges: privileges("read-only"),
+                },
+                TurnPrivilegesSpan {
+                    first_turn: 3,
+                    last_turn: 3,
+                    privileges: privileges("workspace-write"),
+                },
+            ]
+        );
+        let written = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(written.lines().count(), 3);
+        assert!(written.contains("\"sandbox_policy\":\"workspace-write\""));
+    }
+
+    #[test]
+    fn test_hash_chain_detects_tampering() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = audit_log_path(dir.path(), "s1");
+        let mut logger = AuditLogger::new("s1").with_log_path(path.clone()).unwrap();
+        logger.log(
+            AuditOperation::SessionStart {
+                goal: "Fix the parser".to_string(),
+            },
+            AuditOutcome::Success,
+        );
+        logger.log(
+            AuditOperation::CoordinatorDecision {
+                seq: 1,
+                status: "Continue".to_string(),
+            },
+            AuditOutcome::Success,
+        );
+        // A second logger on the same file continues the chain.
+        let mut resumed = AuditLogger::new("s1").with_log_path(path.clone()).unwrap();
+        resumed.log(
+            AuditOperation::SessionEnd {
+                turns: 1,
+                success: true,
+            },
+            AuditOutcome::Success,
+        );
+
+        let written = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            verify_audit_log(&path, dir.path()).unwrap(),
+            ChainVerification::Valid {
+                entries: 3,
+                last_hash: resumed.entries()[0].hash.clone(),
+            }
+        );
+
+        let edited = written.replace("Fix the parser", "Delete the repo");
+        assert_eq!(
+            verify_audit_chain(&edited),
+            ChainVerification::Broken {
+                line: 1,
+                reason: "hash does not match the entry's contents".to_string(),
+            }
+        );
+        let lines: Vec<&str> = written.lines().collect();
+        let removed = [lines[0], lines[2]].join("\n");
+        assert_eq!(
+            verify_audit_chain(&removed),
+            ChainVerification::Broken {
+                line: 2,
+                reason: "prev_hash does not match the previous entry".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn sealed_log_hides_entries_and_still_verifies() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = audit_log_path(dir.path(), "s1");
+        let cipher = at_rest::cipher_for(true, dir.path()).unwrap();
+        let mut logger = AuditLogger::new("s1")
+            .with_at_rest_cipher(cipher.clone())
+            .with_log_path(path.clone())
+            .unwrap();
+        logger.log(
+            AuditOperation::SessionStart {
+                goal: "Fix the parser".to_string(),
+            },
+            AuditOutcome::Success,
+        );
+        let mut resumed = AuditLogger::new("s1")
+            .with_at_rest_cipher(cipher)
+            .with_log_path(path.clone())
+            .unwrap();
+        resumed.log(
+            AuditOperation::SessionEnd {
+                turns: 1,
+                success: true,
+            },
+            AuditOutcome::Success,
+        );
+
+        assert!(
+            !std::fs::read_to_string(&path)
+                .unwrap()
+                .contains("Fix the parser")
+        );
+        assert_eq!(
+            verify_audit_log(&path, dir.path()).unwrap(),
+            ChainVerification::Valid {
+                entries: 2,
+                last_hash: resumed.entries()[0].hash.clone(),
+            }
+        );
+    }
+
+    #[test]
+    fn damaged_log_is_not_continued() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = audit_log_path(dir.path(), "s1");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+
+        std::fs::write(&path, "\n").unwrap();
+        assert!(AuditLogger::new("s1").with_log_path(path.clone()).is_ok());
+
+        std::fs::write(&path, "{\"prev_hash\":\"0\",\"hash\":\"ab\n").unwrap();
+        assert!(AuditLogger::new("s1").with_log_path(path.clone()).is_err());
+
+        std::fs::write(&path, "{\"prev_hash\":\"0\"}\n").unwrap();
+        assert!(AuditLogger::new("s1").with_log_path(path.clone()).is_err());
+
+        let cipher = at_rest::cipher_for(true, dir.path()).unwrap();
+        let other_home = tempfile::tempdir().unwrap();
+        let other_cipher = at_rest::cipher_for(true, other_home.path()).unwrap();
+        let mut logger = AuditLogger::new("s1")
+            .with_at_rest_cipher(other_cipher)
+            .with_log_path(dir.path().join("sealed.jsonl"))
+            .unwrap();
+        logger.log(
+            AuditOperation::SessionStart {
+                goal: "Fix the parser".to_string(),
+            },
+            AuditOutcome::Success,
+        );
+        assert!(
+            AuditLogger::new("s1")
+                .with_at_rest_cipher(cipher)
+                .with_log_path(dir.path().join("sealed.jsonl"))
+                .is_err()
+        );
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("sealed.jsonl"))
+                .unwrap()
+                .lines()
+                .count(),
+            1
+        );
+    }
+
     #[test]
     fn test_validate_file_path() {
         let temp_dir = std::env::temp_dir();
@@ -14,10 +14,10 @@ use code_core::codex::compact::sanitize_items_for_compact;
 use code_core::content_items_to_text;
 use code_core::model_family::derive_default_model_family;
 use code_core::model_family::find_family_for_model;
+use code_core::tokenizer::Tokenizer;
 use code_protocol::models::ContentItem;
 use code_protocol::models::ResponseItem;
 
-const BYTES_PER_TOKEN: usize = 4;
 const MAX_TRANSCRIPT_BYTES: usize = 32_000;
 const MAX_COMMANDS_IN_SUMMARY: usize = 5;
 const MAX_ACTION_LINES: usize = 5;
@@ -114,7 +114,10 @@ fn message_text(item: &ResponseItem) -> Option<String> {
     }
 }
 
-pub(crate) fn compute_slice_bounds(conversation: &[ResponseItem]) -> Option<(usize, usize)> {
+pub(crate) fn compute_slice_bounds(
+    conversation: &[ResponseItem],
+    tokenizer: Tokenizer,
+) -> Option<(usize, usize)> {
     let goal_idx = conversation
         .iter()
         .position(|item| matches!(item, ResponseItem::Message { role, .. } if role == "user"))?;
@@ -124,7 +127,10 @@ pub(crate) fn compute_slice_bounds(conversation: &[ResponseItem]) -> Option<(usi
     }
 
     let after_goal = &conversation[goal_idx + 1..];
-    let token_counts: Vec<usize> = after_goal.iter().map(estimate_item_tokens).collect();
+    let token_counts: Vec<usize> = after_goal
+        .iter()
+        .map(|item| tokenizer.count_item(item))
+        .collect();
     let total_tokens: usize = token_counts.iter().sum();
     let mut midpoint = goal_idx + 1;
 
@@ -492,54 +498,6 @@ fn advance_to_turn_boundary(items: &[ResponseItem], start_idx: usize) -> usize {
     idx
 }
 
-pub(crate) fn estimate_item_tokens(item: &ResponseItem) -> usize {
-    let byte_count = match item {
-        ResponseItem::Message { content, .. } => content
-            .iter()
-            .map(|chunk| match chunk {
-                ContentItem::InputText { text } | ContentItem::OutputText { text } => text.len(),
-                ContentItem::InputImage { image_url } => image_url.len() / 10,
-            })
-            .sum(),
-        ResponseItem::FunctionCall {
-            name, arguments, ..
-        } => name.len() + arguments.len(),
-        ResponseItem::FunctionCallOutput { output, .. } => output.content.len(),
-        ResponseItem::CustomToolCall { name, input, .. } => name.len() + input.len(),
-        ResponseItem::CustomToolCallOutput { output, .. } => output.len(),
-        ResponseItem::Reasoning {
-            summary, content, ..
-        } => {
-            summary
-                .iter()
-                .map(|s| match s {
-                    code_protocol::models::ReasoningItemReasoningSummary::SummaryText { text } => {
-                        text.len()
-                    }
-                })
-                .sum::<usize>()
-                + content
-                    .as_ref()
-                    .map(|segments| {
-                        segments
-                            .iter()
-                            .map(|segment| match segment {
-                                code_protocol::models::ReasoningItemContent::ReasoningText {
-                                    text,
-                                }
-                                | code_protocol::models::ReasoningItemContent::Text { text } => {
-                                    text.len()
-                                }
-                            })
-                            .sum::<usize>()
-                    })
-                    .unwrap_or(0)
-        }
-        _ => 0,
-    };
-    byte_count.div_ceil(BYTES_PER_TOKEN)
-}
-
 fn plain_message(role: &str, text: String) -> ResponseItem {
     ResponseItem::Message {
         id: None,
@@ -583,7 +541,8 @@ mod tests {
             user_message("Step 3"),
         ];
 
-        let (start, end) = compute_slice_bounds(&conversation).expect("bounds");
+        let (start, end) =
+            compute_slice_bounds(&conversation, Tokenizer::Heuristic).expect("bounds");
         assert_eq!(start, 2);
         assert_eq!(end, 5);
     }
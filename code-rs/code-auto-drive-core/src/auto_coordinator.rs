@@ -2,6 +2,7 @@ use std::collections::VecDeque;
 use std::process::Command;
 use std::sync::Arc;
 use std::sync::Mutex;
+use std::sync::Weak;
 use std::sync::mpsc::Receiver;
 use std::sync::mpsc::Sender;
 use std::sync::mpsc::{self};
@@ -21,7 +22,9 @@ use code_core::TextFormat;
 use code_core::agent_defaults::build_model_guide_description;
 use code_core::codex::compact::resolve_compact_prompt_text;
 use code_core::config::Config;
+use code_core::config_types::AutoDriveGoalChanges;
 use code_core::config_types::AutoDriveSettings;
+use code_core::config_types::CoordinatorPromptMode;
 use code_core::config_types::ReasoningEffort;
 use code_core::config_types::TextVerbosity;
 use code_core::config_types::UiLocale;
@@ -30,16 +33,20 @@ use code_core::error::CodexErr;
 use code_core::model_family::derive_default_model_family;
 use code_core::model_family::find_family_for_model;
 use code_core::openai_model_info::get_model_info;
+use code_core::project_doc::discover_coordinator_prompt_paths;
 use code_core::project_doc::read_auto_drive_docs;
+use code_core::protocol::InputItem;
 use code_core::protocol::SandboxPolicy;
 use code_core::protocol::TokenUsage;
 use code_core::slash_commands::get_enabled_agents;
+use code_core::tokenizer::Tokenizer;
 use code_protocol::models::ContentItem;
 use code_protocol::models::ReasoningItemContent;
 use code_protocol::models::ResponseItem;
 use futures::StreamExt;
 use reqwest::StatusCode;
 use serde::Deserialize;
+use serde::Serialize;
 use serde_json::Value;
 use serde_json::json;
 use serde_json::{self};
@@ -52,31 +59,55 @@ use crate::auto_compact::apply_compaction;
 use crate::auto_compact::build_checkpoint_summary;
 use crate::auto_compact::compact_with_endpoint;
 use crate::auto_compact::compute_slice_bounds;
-use crate::auto_compact::estimate_item_tokens;
+use crate::budget::BudgetAlert;
+use crate::budget::BudgetConfig;
+use crate::budget::BudgetController;
+use crate::budget::BudgetSnapshot;
+use crate::coordinator_trace::CoordinatorTrace;
 use crate::coordinator_user_schema::parse_user_turn_reply;
 use crate::coordinator_user_schema::user_turn_schema;
+use crate::decision_latency::DecisionLatencySlo;
+use crate::decision_tape::DecisionTape;
+use crate::decision_tape::TapeEntry;
 #[cfg(feature = "dev-faults")]
 use crate::faults::FaultScope;
 #[cfg(feature = "dev-faults")]
 use crate::faults::fault_to_error;
 #[cfg(feature = "dev-faults")]
 use crate::faults::next_fault;
+use crate::loop_detector::LoopDetector;
+use crate::model_failover::ModelFailover;
 use crate::retry::RetryDecision;
 use crate::retry::RetryError;
 use crate::retry::RetryOptions;
 use crate::retry::retry_with_backoff;
+use crate::role_channel::RoleMessage;
+use crate::role_channel::RoleTurns;
+use crate::selective_tests::SelectiveTestRunner;
+use crate::selective_tests::selective_test_message;
+use crate::selective_tests::selective_test_summary;
+use crate::session_metrics::AttributedUsage;
 use crate::session_metrics::SessionMetrics;
+use crate::session_metrics::UsageSource;
+use crate::simple_loop::SimpleLoop;
+use crate::simple_loop::SimpleLoopStep;
+use crate::stop_conditions::StopConditions;
+use crate::turn_checks::TurnChecker;
+use crate::turn_checks::TurnChecks;
+use crate::turn_review::TurnReviewer;
+use crate::turn_review::timing_label;
+use crate::turn_routing::CliModelRouter;
+use crate::webhook::WebhookNotifier;
 use chrono::DateTime;
 use chrono::Local;
 use chrono::Utc;
 use code_common::elapsed::format_duration;
-use rand::Rng;
 
-const RATE_LIMIT_BUFFER: Duration = Duration::from_secs(5);
-const RATE_LIMIT_JITTER_MAX: Duration = Duration::from_secs(3);
-const MAX_RETRY_ELAPSED: Duration = Duration::from_secs(7 * 24 * 60 * 60);
 const MAX_DECISION_RECOVERY_ATTEMPTS: u32 = 3;
 const MESSAGE_LIMIT_FALLBACK: usize = 120;
+/// Lowest `auto_drive.compact_threshold` honored; smaller values would
+/// compact on every decision.
+pub const MIN_COMPACT_THRESHOLD: f64 = 0.1;
 const DEBUG_JSON_MAX_CHARS: usize = 1200;
 const CLI_PROMPT_MIN_CHARS: usize = 4;
 const CLI_PROMPT_MAX_CHARS: usize = 600;
@@ -88,6 +119,8 @@ struct AutoCoordinatorCancelled;
 pub const MODEL_SLUG: &str = "gpt-5.1";
 const USER_TURN_SCHEMA_NAME: &str = "auto_coordinator_user_turn";
 const COORDINATOR_PROMPT: &str = include_str!("../../core/prompt_coordinator.md");
+/// User-level coordinator prompt override, read from `CODE_HOME`.
+const CUSTOM_COORDINATOR_PROMPT_FILENAME: &str = "prompt_coordinator.md";
 
 const ALL_TEXT_VERBOSITY: &[TextVerbosity] = &[
     TextVerbosity::Low,
@@ -121,38 +154,87 @@ impl AutoCoordinatorEventSender {
         tracing::debug!(target: "auto_drive::coordinator", event = event.kind(), "dispatch coordinator event");
         (self.inner)(event);
     }
+
+    /// Calls `observer` with every event before this sender handles it.
+    fn tee<F>(self, observer: F) -> Self
+    where
+        F: Fn(&AutoCoordinatorEvent) + Send + Sync + 'static,
+    {
+        let inner = self.inner;
+        Self {
+            inner: Arc::new(move |event| {
+                observer(&event);
+                inner(event);
+            }),
+        }
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AutoTurnCliAction {
     pub prompt: String,
     pub context: Option<String>,
     pub suppress_ui_context: bool,
+    /// Operator attachments (screenshots, files) to submit with the prompt.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<InputItem>,
+    /// Independent workstreams to run concurrently in separate CLI sessions
+    /// before `prompt` merges and verifies their results.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub workstreams: Vec<AutoTurnWorkstream>,
+    /// How demanding the turn is; set when CLI model routing is enabled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub complexity: Option<TurnComplexity>,
+    /// Model to run this turn on instead of the session model.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutoTurnWorkstream {
+    pub id: String,
+    pub prompt: String,
+    pub context: Option<String>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum AutoTurnAgentsTiming {
     Parallel,
     Blocking,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AutoTurnAgentsAction {
     pub prompt: String,
     pub context: Option<String>,
     pub write: bool,
     pub write_requested: Option<bool>,
     pub models: Option<Vec<String>>,
+    /// Seconds the agent may run before it is cancelled and its partial
+    /// output is collected. Resolved from the decision payload, falling back
+    /// to `auto_drive.agent_timeout_seconds`.
+    pub timeout_seconds: Option<u64>,
+}
+
+/// Estimated size of the coordinator history around a compaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompactionEstimate {
+    pub before_tokens: u64,
+    pub after_tokens: u64,
+    /// Projected prompt size at which history is compacted automatically;
+    /// `None` when the model's context window is unknown.
+    pub threshold_tokens: Option<u64>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum AutoCoordinatorStatus {
     Continue,
     Success,
     Failed,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
 pub enum AutoCoordinatorEvent {
     Decision {
         seq: u64,
@@ -164,6 +246,9 @@ pub enum AutoCoordinatorEvent {
         agents_timing: Option<AutoTurnAgentsTiming>,
         agents: Vec<AutoTurnAgentsAction>,
         transcript: Vec<ResponseItem>,
+        /// Budget consumed so far and the runway left when this decision was made.
+        #[serde(default)]
+        budget_snapshot: BudgetSnapshot,
     },
     Thinking {
         delta: String,
@@ -175,6 +260,9 @@ pub enum AutoCoordinatorEvent {
     UserReply {
         user_response: Option<String>,
         cli_command: Option<String>,
+        /// Attachments from the operator's prompt, to submit with `cli_command`.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        attachments: Vec<InputItem>,
     },
     TokenMetrics {
         total_usage: TokenUsage,
@@ -182,10 +270,18 @@ pub enum AutoCoordinatorEvent {
         turn_count: u32,
         duplicate_items: u32,
         replay_updates: u32,
+        /// Tokens spent so far per decision on the coordinator, the CLI
+        /// turn, and each agent.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        breakdown: Vec<AttributedUsage>,
     },
     CompactedHistory {
         conversation: Vec<ResponseItem>,
         show_notice: bool,
+        /// Token estimates around the compaction; `None` when the history
+        /// was only resynced.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        estimate: Option<CompactionEstimate>,
     },
     StopAck,
     // Enhanced Auto Drive events
@@ -213,10 +309,28 @@ pub enum AutoCoordinatorEvent {
     InterventionRequired {
         reason: String,
     },
+    /// Routine decisions moved to another coordinator model.
+    CoordinatorModelSwitched {
+        from: String,
+        to: String,
+        reason: String,
+    },
+    /// The coordinator model failed for good and the run moved to the next
+    /// entry in `auto_drive.model_fallbacks`.
+    CoordinatorFailover {
+        from: String,
+        to: String,
+        reason: String,
+    },
+    /// Every coordinator model is unavailable; the run continues in the
+    /// simple loop, sending the CLI a templated continue prompt each turn.
+    CoordinatorDegraded {
+        reason: String,
+    },
 }
 
 /// Type of diagnostic alert for UI display.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum DiagnosticAlertType {
     LoopDetected,
     GoalDrift,
@@ -225,12 +339,13 @@ pub enum DiagnosticAlertType {
 }
 
 /// Type of budget alert for UI display.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum BudgetAlertType {
     TokenWarning,
     TokenExceeded,
     TurnLimitReached,
     DurationExceeded,
+    CostExceeded,
 }
 
 impl AutoCoordinatorEvent {
@@ -248,13 +363,16 @@ impl AutoCoordinatorEvent {
             Self::DiagnosticAlert { .. } => "diagnostic_alert",
             Self::BudgetAlert { .. } => "budget_alert",
             Self::InterventionRequired { .. } => "intervention_required",
+            Self::CoordinatorModelSwitched { .. } => "coordinator_model_switched",
+            Self::CoordinatorFailover { .. } => "coordinator_failover",
+            Self::CoordinatorDegraded { .. } => "coordinator_degraded",
         }
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct AutoCoordinatorHandle {
-    pub tx: Sender<AutoCoordinatorCommand>,
+    tx: Arc<Sender<AutoCoordinatorCommand>>,
     cancel_token: CancellationToken,
 }
 
@@ -277,11 +395,49 @@ pub enum AutoCoordinatorCommand {
     HandleUserPrompt {
         _prompt: String,
         conversation: Vec<ResponseItem>,
+        /// Images or files dropped in by the operator. They ride with the next
+        /// CLI turn and are mentioned to the coordinator.
+        attachments: Vec<InputItem>,
     },
     AckDecision {
         seq: u64,
     },
+    /// Releases the decision held by an `InterventionRequired` event: the
+    /// first write under `confirm_first_write`, or a goal amendment under
+    /// `goal_changes = "confirm"`, which is adopted.
+    ApproveHeldDecision,
+    /// Cumulative usage of the CLI agent, counted against the token budget,
+    /// and the run's estimated spend, counted against the cost budget. Send
+    /// it before the `UpdateConversation` that follows a CLI turn.
+    ReportUsage {
+        cli_tokens: u64,
+        cost_usd: Option<f64>,
+    },
+    /// Tokens the CLI turn and agents of a decision used, by source. The
+    /// coordinator attributes its own usage.
+    ReportTurnUsage(Vec<AttributedUsage>),
+    /// Compacts the history before the next decision, whatever its size.
+    CompactNow,
+    /// Stops issuing decisions. A decision already requested, and the CLI
+    /// turn it starts, still finish; the conversation updates that follow
+    /// are kept until `Resume`.
+    Pause,
+    /// Picks up from the conversation kept while paused.
+    Resume,
     Stop,
+    /// Results of the checks run after a CLI turn. Sent by the coordinator
+    /// to itself.
+    #[doc(hidden)]
+    TurnChecked(TurnChecks),
+}
+
+/// A decision held by an intervention request until the operator answers.
+struct HeldDecision {
+    event: AutoCoordinatorEvent,
+    /// Amended goal adopted on approval.
+    goal: Option<String>,
+    /// Held at the first-write checkpoint.
+    write: bool,
 }
 
 #[derive(Clone)]
@@ -295,6 +451,7 @@ struct PendingDecision {
     agents_timing: Option<AutoTurnAgentsTiming>,
     agents: Vec<AutoTurnAgentsAction>,
     transcript: Vec<ResponseItem>,
+    budget_snapshot: BudgetSnapshot,
 }
 
 impl PendingDecision {
@@ -309,11 +466,12 @@ impl PendingDecision {
             agents_timing: self.agents_timing,
             agents: self.agents,
             transcript: self.transcript,
+            budget_snapshot: self.budget_snapshot,
         }
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum TurnComplexity {
     Low,
@@ -330,6 +488,10 @@ pub struct TurnConfig {
     pub complexity: Option<TurnComplexity>,
     #[serde(default)]
     pub text_format_override: Option<code_core::TextFormat>,
+    /// Sampling settings for the turn, e.g. a fixed seed for evaluation
+    /// runs.
+    #[serde(default)]
+    pub sampling: Option<code_core::config_types::SamplingParams>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
@@ -372,7 +534,6 @@ impl Default for ReviewTiming {
     }
 }
 
-#[allow(dead_code)]
 #[derive(Debug, Clone, Deserialize)]
 pub struct ReviewStrategy {
     #[serde(default)]
@@ -428,9 +589,54 @@ mod tests {
     use super::*;
     use anyhow::anyhow;
     use code_core::agent_defaults::DEFAULT_AGENT_NAMES;
+    use code_core::error::RetryAfter;
     use code_core::error::RetryLimitReachedError;
     use serde_json::json;
 
+    #[test]
+    fn exceeded_budgets_describe_the_limit() {
+        let cost = describe_budget_alert(&BudgetAlert::CostExceeded {
+            spent_usd: 2.004,
+            limit_usd: 2.0,
+        });
+        assert_eq!(
+            cost,
+            Some((
+                BudgetAlertType::CostExceeded,
+                "cost limit reached ($2.00 of $2.00)".to_string()
+            ))
+        );
+        let backpressure = describe_budget_alert(&BudgetAlert::BackpressureWarning {
+            queue_size: 9,
+            limit: 10,
+        });
+        assert_eq!(backpressure, None);
+    }
+
+    #[test]
+    fn custom_coordinator_prompt_merges_or_replaces() {
+        let merged = compose_coordinator_prompt(
+            " built-in\n",
+            Some("org rules"),
+            CoordinatorPromptMode::Merge,
+        );
+        assert_eq!(merged.as_deref(), Some("built-in\n\norg rules"));
+        let replaced = compose_coordinator_prompt(
+            "built-in",
+            Some("org rules"),
+            CoordinatorPromptMode::Replace,
+        );
+        assert_eq!(replaced.as_deref(), Some("org rules"));
+        // An empty override never blanks the prompt.
+        let empty =
+            compose_coordinator_prompt("built-in", Some("  "), CoordinatorPromptMode::Replace);
+        assert_eq!(empty.as_deref(), Some("built-in"));
+        assert_eq!(
+            compose_coordinator_prompt("", None, CoordinatorPromptMode::Merge),
+            None
+        );
+    }
+
     #[test]
     fn turn_descriptor_defaults_to_normal_mode() {
         let value = json!({});
@@ -543,6 +749,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn stream_errors_with_a_retry_hint_wait_as_rate_limits() {
+        let err = CodexErr::Stream(
+            "[bedrock] throttled: Too many requests".to_string(),
+            Some(RetryAfter::from_duration(
+                Duration::from_secs(30),
+                chrono::Utc::now(),
+            )),
+            None,
+        );
+
+        match classify_model_error(&anyhow!(err)) {
+            RetryDecision::RateLimited { wait_until, reason } => {
+                assert!(wait_until > Instant::now() + Duration::from_secs(25));
+                assert!(reason.contains("throttled"));
+            }
+            other => panic!("expected rate limit, got {other:?}"),
+        }
+    }
+
     #[test]
     fn retry_limit_marked_retryable_is_retried() {
         let err = CodexErr::RetryLimit(RetryLimitReachedError {
@@ -648,6 +874,384 @@ mod tests {
         assert!(description.contains("primary coding goal"));
     }
 
+    #[test]
+    fn schema_requires_write_intent_only_for_first_write_checkpoint() {
+        let schema = build_schema(&Vec::new(), SchemaFeatures::default());
+        assert!(schema["properties"].get("cli_writes_files").is_none());
+
+        let features = SchemaFeatures {
+            include_write_intent: true,
+            ..SchemaFeatures::default()
+        };
+        let schema = build_schema(&Vec::new(), features);
+        assert_eq!(schema["properties"]["cli_writes_files"]["type"], "boolean");
+        let required = schema["required"].as_array().expect("required array");
+        assert!(required.contains(&json!("cli_writes_files")));
+
+        let raw = r#"{
+            "finish_status": "continue",
+            "status_title": "Fixing",
+            "status_sent_to_user": "Applying the fix.",
+            "prompt_sent_to_cli": "Apply the planned fix",
+            "cli_writes_files": true
+        }"#;
+        let (decision, _) = parse_decision(raw).expect("parse decision");
+        assert!(decision.cli_writes_files);
+    }
+
+    #[test]
+    fn cli_complexity_is_requested_only_with_model_routing() {
+        let mut settings = AutoDriveSettings::default();
+        let schema = build_schema(&Vec::new(), SchemaFeatures::from_auto_settings(&settings));
+        assert!(schema["properties"].get("cli_complexity").is_none());
+
+        settings.cli_model_routing.low = Some("gpt-5.1-codex-mini".to_string());
+        let schema = build_schema(&Vec::new(), SchemaFeatures::from_auto_settings(&settings));
+        let required = schema["required"].as_array().expect("required array");
+        assert!(required.contains(&json!("cli_complexity")));
+
+        let raw = r#"{
+            "finish_status": "continue",
+            "status_title": "Testing",
+            "status_sent_to_user": "Running the test suite.",
+            "prompt_sent_to_cli": "Run cargo test and report failures",
+            "cli_complexity": "LOW"
+        }"#;
+        let (decision, _) = parse_decision(raw).expect("parse decision");
+        let cli = decision.cli.expect("cli action");
+        assert_eq!(cli.complexity, Some(TurnComplexity::Low));
+    }
+
+    #[test]
+    fn replayed_decisions_drive_the_loop_without_the_model() {
+        let code_home = tempfile::tempdir().unwrap();
+        let tape_dir = tempfile::tempdir().unwrap();
+        let config = replay_config(code_home.path(), tape_dir.path());
+        let (handle, event_rx) = start_replay_coordinator(config);
+
+        let mut statuses = Vec::new();
+        while let Ok(event) = event_rx.recv_timeout(Duration::from_secs(30)) {
+            let AutoCoordinatorEvent::Decision { seq, status, .. } = event else {
+                continue;
+            };
+            statuses.push(status);
+            if status != AutoCoordinatorStatus::Continue {
+                break;
+            }
+            // The planning seed needs no model call; answer it with a CLI
+            // turn so the next decision comes from the tape.
+            answer_with_cli_turn(&handle, seq);
+        }
+        let _ = handle.send(AutoCoordinatorCommand::Stop);
+        assert_eq!(
+            statuses,
+            vec![
+                AutoCoordinatorStatus::Continue,
+                AutoCoordinatorStatus::Success
+            ]
+        );
+    }
+
+    #[test]
+    fn paused_loop_holds_decisions_until_resumed() {
+        let code_home = tempfile::tempdir().unwrap();
+        let tape_dir = tempfile::tempdir().unwrap();
+        let config = replay_config(code_home.path(), tape_dir.path());
+        let (handle, event_rx) = start_replay_coordinator(config);
+
+        let next_decision = |timeout: Duration| loop {
+            match event_rx.recv_timeout(timeout) {
+                Ok(AutoCoordinatorEvent::Decision { seq, status, .. }) => {
+                    break Some((seq, status));
+                }
+                Ok(_) => {}
+                Err(_) => break None,
+            }
+        };
+        let (seed_seq, _) = next_decision(Duration::from_secs(30)).unwrap();
+        handle.send(AutoCoordinatorCommand::Pause).unwrap();
+        answer_with_cli_turn(&handle, seed_seq);
+        assert_eq!(next_decision(Duration::from_millis(500)), None);
+
+        handle.send(AutoCoordinatorCommand::Resume).unwrap();
+        assert_eq!(
+            next_decision(Duration::from_secs(30)).map(|(_, status)| status),
+            Some(AutoCoordinatorStatus::Success)
+        );
+        let _ = handle.send(AutoCoordinatorCommand::Stop);
+    }
+
+    /// Config whose coordinator replays a single successful decision.
+    fn replay_config(code_home: &std::path::Path, tape_dir: &std::path::Path) -> Config {
+        let recorder = DecisionTape::record(tape_dir).unwrap();
+        recorder.record_response(&TapeEntry {
+            model_slug: "gpt-5".to_string(),
+            conversation_len: 1,
+            output_text: r#"{"finish_status":"finish_success","status_title":"Done","status_sent_to_user":"The parser is fixed.","prompt_sent_to_cli":null}"#.to_string(),
+            response_items: Vec::new(),
+            token_usage: None,
+        });
+        let mut config = Config::load_from_base_config_with_overrides(
+            code_core::config::ConfigToml::default(),
+            code_core::config::ConfigOverrides {
+                cwd: Some(code_home.to_path_buf()),
+                ..Default::default()
+            },
+            code_home.to_path_buf(),
+        )
+        .unwrap();
+        config.auto_drive.replay_decisions = Some(tape_dir.to_path_buf());
+        config
+    }
+
+    fn start_replay_coordinator(
+        config: Config,
+    ) -> (
+        AutoCoordinatorHandle,
+        std::sync::mpsc::Receiver<AutoCoordinatorEvent>,
+    ) {
+        let (event_tx, event_rx) = std::sync::mpsc::channel();
+        let sender = AutoCoordinatorEventSender::new(move |event| {
+            let _ = event_tx.send(event);
+        });
+        let handle = start_auto_coordinator(
+            sender,
+            "Fix the parser".to_string(),
+            Vec::new(),
+            config,
+            false,
+            false,
+        )
+        .unwrap();
+        (handle, event_rx)
+    }
+
+    fn answer_with_cli_turn(handle: &AutoCoordinatorHandle, seq: u64) {
+        handle
+            .send(AutoCoordinatorCommand::AckDecision { seq })
+            .unwrap();
+        handle
+            .send(AutoCoordinatorCommand::UpdateConversation(vec![
+                make_message("assistant", "Plan: fix the tokenizer.".to_string()),
+            ]))
+            .unwrap();
+    }
+
+    #[test]
+    fn role_turn_brief_names_the_role_and_relays_handoffs() {
+        use code_core::config_types::AutoDriveRole;
+
+        let roles = vec![
+            AutoDriveRole {
+                name: "architect".to_string(),
+                model: None,
+                prompt: Some("Plan the change; never write code.".to_string()),
+            },
+            AutoDriveRole {
+                name: "implementer".to_string(),
+                model: Some("gpt-5.1-codex".to_string()),
+                prompt: None,
+            },
+        ];
+        let mut turns = RoleTurns::from_settings(&roles).unwrap();
+        assert_eq!(
+            role_turn_brief(&mut turns),
+            "You are the architect coordinator. Coordinator roles take turns deciding the next CLI turn; this decision is yours.\nPlan the change; never write code."
+        );
+
+        turns.hand_off(role_handoff_note(Some("Split the parser"), None));
+        let brief = role_turn_brief(&mut turns);
+        assert!(brief.starts_with("You are the implementer coordinator."));
+        assert!(
+            brief.ends_with("\nNotes from the other coordinators:\n- architect: Split the parser")
+        );
+        assert_eq!(
+            role_handoff_note(None, None),
+            "Handed over without a CLI turn."
+        );
+    }
+
+    #[test]
+    fn review_requests_are_offered_only_with_turn_reviews() {
+        let mut settings = AutoDriveSettings::default();
+        let schema = build_schema(&Vec::new(), SchemaFeatures::from_auto_settings(&settings));
+        assert!(schema["properties"].get("review").is_none());
+
+        settings.turn_reviews = true;
+        let schema = build_schema(&Vec::new(), SchemaFeatures::from_auto_settings(&settings));
+        assert_eq!(
+            schema["properties"]["review"]["properties"]["timing"]["enum"],
+            json!(["immediate", "pre_write"])
+        );
+        let required = schema["required"].as_array().expect("required array");
+        assert!(required.contains(&json!("review")));
+
+        let raw = r#"{
+            "finish_status": "continue",
+            "status_title": "Refactoring",
+            "status_sent_to_user": "Reworking the retry loop.",
+            "prompt_sent_to_cli": "Rewrite the retry loop with jittered backoff",
+            "review": {"timing": "immediate", "scope_hint": "retry backoff"}
+        }"#;
+        let (decision, _) = parse_decision(raw).expect("parse decision");
+        let review = decision.review.expect("review requested");
+        assert_eq!(review.timing, ReviewTiming::Immediate);
+        assert_eq!(review.scope_hint.as_deref(), Some("retry backoff"));
+
+        let raw = r#"{
+            "finish_status": "finish_success",
+            "status_title": "Done",
+            "status_sent_to_user": "All done.",
+            "prompt_sent_to_cli": null,
+            "review": {"timing": "pre_write", "scope_hint": null}
+        }"#;
+        let (decision, _) = parse_decision(raw).expect("parse decision");
+        assert!(decision.review.is_none());
+    }
+
+    #[test]
+    fn workstreams_follow_cli_worker_pool_size() {
+        let mut settings = AutoDriveSettings {
+            parallel_instances: 3,
+            ..AutoDriveSettings::default()
+        };
+        let schema = build_schema(&Vec::new(), SchemaFeatures::from_auto_settings(&settings));
+        assert!(schema["properties"].get("workstreams").is_none());
+
+        settings.cli_workers = true;
+        let schema = build_schema(&Vec::new(), SchemaFeatures::from_auto_settings(&settings));
+        assert_eq!(schema["properties"]["workstreams"]["maxItems"], 3);
+        let required = schema["required"].as_array().expect("required array");
+        assert!(required.contains(&json!("workstreams")));
+
+        let raw = r#"{
+            "finish_status": "continue",
+            "status_title": "Splitting",
+            "status_sent_to_user": "Porting both crates at once.",
+            "prompt_sent_to_cli": "Merge the workstream results and run the tests",
+            "workstreams": [
+                {"prompt": "Port the parser crate", "context": "Owns parser/"},
+                {"prompt": "Port the lexer crate", "context": null}
+            ]
+        }"#;
+        let (decision, _) = parse_decision(raw).expect("parse decision");
+        let cli = decision.cli.expect("cli action");
+        let ids: Vec<&str> = cli.workstreams.iter().map(|ws| ws.id.as_str()).collect();
+        assert_eq!(ids, vec!["ws-1", "ws-2"]);
+        assert_eq!(cli.workstreams[0].context.as_deref(), Some("Owns parser/"));
+        assert_eq!(cli.workstreams[1].context, None);
+    }
+
+    #[test]
+    fn goal_amendments_follow_the_goal_changes_policy() {
+        use AutoDriveGoalChanges::*;
+
+        let current = "Fix the parser";
+        assert_eq!(
+            classify_goal_change(Some("Fix the parser "), current, false, Confirm),
+            GoalChange::Unchanged
+        );
+        assert_eq!(
+            classify_goal_change(None, current, false, Confirm),
+            GoalChange::Unchanged
+        );
+        let amended = "Replace the parser with a generated one";
+        assert_eq!(
+            classify_goal_change(Some(amended), current, false, Auto),
+            GoalChange::Adopt(amended.to_string())
+        );
+        assert_eq!(
+            classify_goal_change(Some(amended), current, false, Confirm),
+            GoalChange::Confirm(amended.to_string())
+        );
+        assert_eq!(
+            classify_goal_change(Some(amended), current, false, Reject),
+            GoalChange::Reject(amended.to_string())
+        );
+        // Deriving the first goal from history is not an amendment.
+        assert_eq!(
+            classify_goal_change(Some(amended), "", true, Reject),
+            GoalChange::Adopt(amended.to_string())
+        );
+
+        let features = SchemaFeatures {
+            include_goal_amendment: true,
+            ..SchemaFeatures::default()
+        };
+        let schema = build_schema(&Vec::new(), features);
+        assert_eq!(
+            schema["properties"]["goal"]["type"],
+            json!(["string", "null"])
+        );
+    }
+
+    #[test]
+    fn first_write_checkpoint_reason_lists_plan_and_writes() {
+        let cli = AutoTurnCliAction {
+            prompt: "Apply the planned fix".to_string(),
+            context: None,
+            suppress_ui_context: false,
+            attachments: Vec::new(),
+            workstreams: Vec::new(),
+            complexity: None,
+            model: None,
+        };
+        let agents = vec![
+            AutoTurnAgentsAction {
+                prompt: "Port the fix to v2".to_string(),
+                context: None,
+                write: true,
+                write_requested: Some(true),
+                models: None,
+                timeout_seconds: None,
+            },
+            AutoTurnAgentsAction {
+                prompt: "Review the diff".to_string(),
+                context: None,
+                write: false,
+                write_requested: Some(false),
+                models: None,
+                timeout_seconds: None,
+            },
+        ];
+
+        let reason = first_write_checkpoint_reason(Some("Fix the parser."), Some(&cli), &agents);
+
+        assert_eq!(
+            reason,
+            "Approve the first write turn before Auto Drive continues.\n\
+             Plan: Fix the parser.\n\
+             CLI: Apply the planned fix\n\
+             Write agent: Port the fix to v2"
+        );
+    }
+
+    #[test]
+    fn attachments_note_names_each_attachment() {
+        let attachments = vec![
+            InputItem::LocalImage {
+                path: std::path::PathBuf::from("/tmp/shots/failing.png"),
+            },
+            InputItem::Image {
+                image_url: "data:image/png;base64,AAAA".to_string(),
+            },
+        ];
+
+        assert_eq!(
+            attachments_note(&attachments),
+            "Operator attached for the next CLI turn: failing.png (image), inline image. \
+             The CLI will receive them with its next prompt."
+        );
+
+        let event = AutoCoordinatorEvent::UserReply {
+            user_response: None,
+            cli_command: Some("Fix the layout".to_string()),
+            attachments,
+        };
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["attachments"][0]["type"], "local_image");
+    }
+
     #[test]
     fn developer_message_uses_bootstrap_instructions_when_deriving_goal() {
         let (_, _intro_bootstrap, primary_bootstrap) =
@@ -695,6 +1299,38 @@ mod tests {
         assert_eq!(agent.models, Some(vec!["codex-plan".to_string()]));
     }
 
+    #[test]
+    fn parse_decision_agent_timeout_overrides_default() {
+        let raw = r#"{
+            "finish_status": "continue",
+            "status_title": "Dispatching",
+            "status_sent_to_user": "Queued agents.",
+            "prompt_sent_to_cli": "Merge agent results",
+            "agents": {
+                "timing": "parallel",
+                "list": [
+                    {"prompt": "Profile the hot loop", "write": false, "context": null, "models": null, "timeout_seconds": 90},
+                    {"prompt": "Survey prior art", "write": false, "context": null, "models": null, "timeout_seconds": null}
+                ]
+            }
+        }"#;
+
+        let (decision, _) = parse_decision(raw).expect("parse decision with timeouts");
+        let timeouts: Vec<Option<u64>> = decision
+            .agents
+            .iter()
+            .map(|action| agent_action_to_event(action, Some(300)).timeout_seconds)
+            .collect();
+        assert_eq!(timeouts, vec![Some(90), Some(300)]);
+
+        let unbounded: Vec<Option<u64>> = decision
+            .agents
+            .iter()
+            .map(|action| agent_action_to_event(action, None).timeout_seconds)
+            .collect();
+        assert_eq!(unbounded, vec![Some(90), None]);
+    }
+
     #[test]
     fn parse_decision_new_schema_array_backcompat() {
         let raw = r#"{
@@ -791,6 +1427,33 @@ mod tests {
         }
     }
 
+    #[test]
+    fn quota_and_auth_failures_make_the_coordinator_unavailable() {
+        assert!(coordinator_unavailable(&anyhow!(CodexErr::QuotaExceeded)));
+        assert!(coordinator_unavailable(
+            &anyhow!(CodexErr::UsageNotIncluded).context("coordinator fallback failed")
+        ));
+        assert!(!coordinator_unavailable(&anyhow!(CodexErr::Timeout)));
+    }
+
+    #[test]
+    fn only_errors_the_model_cannot_recover_from_trigger_failover() {
+        assert!(
+            model_failover_reason(&anyhow!(CodexErr::QuotaExceeded))
+                .is_some_and(|reason| reason.starts_with("coordinator model unavailable"))
+        );
+        assert!(
+            model_failover_reason(&anyhow!(CodexErr::RetryLimit(RetryLimitReachedError {
+                status: StatusCode::BAD_REQUEST,
+                request_id: None,
+                retryable: false,
+            })))
+            .is_some_and(|reason| reason.starts_with("fatal model error"))
+        );
+        assert!(model_failover_reason(&anyhow!(CodexErr::Timeout)).is_none());
+        assert!(model_failover_reason(&anyhow!("coordinator stream ended early")).is_none());
+    }
+
     #[test]
     fn push_unique_guidance_trims_and_dedupes() {
         let mut guidance = vec!["Keep CLI prompts short".to_string()];
@@ -803,8 +1466,22 @@ mod tests {
 
     #[test]
     fn compaction_triggers_when_projected_exceeds_threshold() {
-        assert!(should_compact("gpt-5.1", 220_000, 10_000, 0, true));
-        assert!(!should_compact("gpt-5.1", 100_000, 10_000, 0, true));
+        assert!(should_compact("gpt-5.1", 220_000, 10_000, 0, true, 0.8));
+        assert!(!should_compact("gpt-5.1", 100_000, 10_000, 0, true, 0.8));
+    }
+
+    #[test]
+    fn compaction_threshold_is_configurable() {
+        let default = compaction_threshold_tokens("gpt-5.1", 0.8).unwrap();
+        let lowered = compaction_threshold_tokens("gpt-5.1", 0.6).unwrap();
+        assert!(lowered < default);
+        assert!(should_compact("gpt-5.1", lowered, 0, 0, true, 0.6));
+        assert!(!should_compact("gpt-5.1", lowered, 0, 0, true, 0.8));
+        assert_eq!(
+            compaction_threshold_tokens("gpt-5.1", 0.0),
+            compaction_threshold_tokens("gpt-5.1", MIN_COMPACT_THRESHOLD)
+        );
+        assert_eq!(compaction_threshold_tokens("unknown-model", 0.8), None);
     }
 
     #[test]
@@ -815,6 +1492,7 @@ mod tests {
             0,
             MESSAGE_LIMIT_FALLBACK,
             false,
+            0.8,
         ));
         assert!(!should_compact(
             "unknown-model",
@@ -822,6 +1500,7 @@ mod tests {
             0,
             MESSAGE_LIMIT_FALLBACK.saturating_sub(1),
             false,
+            0.8,
         ));
     }
 
@@ -833,6 +1512,7 @@ mod tests {
             4_000,
             MESSAGE_LIMIT_FALLBACK,
             false,
+            0.8,
         ));
     }
 
@@ -844,6 +1524,7 @@ mod tests {
             0,
             MESSAGE_LIMIT_FALLBACK,
             true,
+            0.8,
         ));
     }
 }
@@ -863,6 +1544,21 @@ struct CoordinatorDecisionNew {
     agents: Option<AgentsField>,
     #[serde(default)]
     goal: Option<String>,
+    #[serde(default)]
+    cli_writes_files: Option<bool>,
+    #[serde(default)]
+    workstreams: Option<Vec<WorkstreamPayload>>,
+    #[serde(default)]
+    cli_complexity: Option<String>,
+    #[serde(default)]
+    review: Option<ReviewStrategy>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WorkstreamPayload {
+    prompt: String,
+    #[serde(default)]
+    context: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -882,6 +1578,8 @@ struct AgentPayload {
     write: Option<bool>,
     #[serde(default)]
     models: Option<Vec<String>>,
+    #[serde(default)]
+    timeout_seconds: Option<u64>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -946,6 +1644,10 @@ struct ParsedCoordinatorDecision {
     agents_timing: Option<AutoTurnAgentsTiming>,
     agents: Vec<AgentAction>,
     goal: Option<String>,
+    /// Whether the coordinator expects the CLI prompt to modify files.
+    cli_writes_files: bool,
+    /// Review requested for the CLI turn.
+    review: Option<ReviewStrategy>,
     response_items: Vec<ResponseItem>,
     token_usage: Option<TokenUsage>,
     model_slug: String,
@@ -956,6 +1658,8 @@ struct CliAction {
     prompt: String,
     context: Option<String>,
     suppress_ui_context: bool,
+    workstreams: Vec<AutoTurnWorkstream>,
+    complexity: Option<TurnComplexity>,
 }
 
 #[derive(Debug, Clone)]
@@ -964,6 +1668,7 @@ struct AgentAction {
     context: Option<String>,
     write: Option<bool>,
     models: Option<Vec<String>>,
+    timeout_seconds: Option<u64>,
 }
 
 struct DecisionFailure {
@@ -997,8 +1702,14 @@ pub fn start_auto_coordinator(
         );
     }
 
+    let event_tx = match WebhookNotifier::from_settings(&config.auto_drive) {
+        Some(webhook) => event_tx.tee(move |event| webhook.notify(event)),
+        None => event_tx,
+    };
+
     let (cmd_tx, cmd_rx) = mpsc::channel();
-    let thread_tx = cmd_tx;
+    let thread_tx = Arc::new(cmd_tx);
+    let loop_tx = Arc::downgrade(&thread_tx);
     let cancel_token = CancellationToken::new();
     let thread_cancel = cancel_token.clone();
 
@@ -1015,6 +1726,7 @@ pub fn start_auto_coordinator(
             conversation,
             config,
             cmd_rx,
+            loop_tx,
             debug_enabled,
             thread_cancel,
             derive_goal_from_history,
@@ -1041,6 +1753,7 @@ fn run_auto_loop(
     initial_conversation: Vec<ResponseItem>,
     config: Config,
     cmd_rx: Receiver<AutoCoordinatorCommand>,
+    cmd_tx: Weak<Sender<AutoCoordinatorCommand>>,
     debug_enabled: bool,
     cancel_token: CancellationToken,
     derive_goal_from_history: bool,
@@ -1065,6 +1778,7 @@ fn run_auto_loop(
         .unwrap_or(TextVerbosity::Medium);
     let compact_prompt_text =
         resolve_compact_prompt_text(config.compact_prompt_override.as_deref());
+    let compact_threshold = config.auto_drive.compact_threshold;
 
     let preferred_auth = if config.using_chatgpt_auth {
         code_protocol::mcp_protocol::AuthMode::ChatGPT
@@ -1086,7 +1800,7 @@ fn run_auto_loop(
     let active_agent_names = get_enabled_agents(&config.agents);
     let client = Arc::new(ModelClient::new(
         config.clone(),
-        Some(auth_mgr),
+        Some(auth_mgr.clone()),
         None,
         model_provider,
         config.model_reasoning_effort,
@@ -1146,6 +1860,7 @@ fn run_auto_loop(
         schema_features.include_goal_field = true;
     }
     let include_agents = schema_features.include_agents;
+    let agent_timeout_seconds = config.auto_drive.agent_timeout_seconds;
 
     // Read parallel_instances configuration for same-model concurrent execution
     let parallel_instances = config.auto_drive.parallel_instances.clamp(1, 5);
@@ -1160,6 +1875,52 @@ fn run_auto_loop(
     let mut decision_seq: u64 = 0;
     let mut pending_ack_seq: Option<u64> = None;
     let mut queued_updates: VecDeque<Vec<ResponseItem>> = VecDeque::new();
+    // With `confirm_first_write`, the first decision that writes is held
+    // until the operator sends `ApproveHeldDecision`, or replaced by a replan
+    // when the operator answers with `HandleUserPrompt` instead. Goal
+    // amendments under `goal_changes = "confirm"` are held the same way.
+    let mut write_approved = !config.auto_drive.confirm_first_write;
+    let mut held_decision: Option<HeldDecision> = None;
+    let mut budget = BudgetController::new();
+    budget.configure(BudgetConfig {
+        token_budget: config.auto_drive.token_budget,
+        turn_limit: config.auto_drive.turn_limit,
+        duration_limit: config
+            .auto_drive
+            .duration_limit_seconds
+            .map(Duration::from_secs),
+        cost_limit_usd: config.auto_drive.cost_budget_usd,
+    });
+    budget.start();
+    let mut budget_warning_sent = false;
+    let turn_reviewer = TurnReviewer::from_config(&config, auth_mgr).map(Arc::new);
+    let mut loop_detector = LoopDetector::from_settings(&config.auto_drive);
+    let mut turn_checker = TurnChecker::new(
+        cmd_tx,
+        StopConditions::from_config(&config),
+        SelectiveTestRunner::from_config(&config),
+        turn_reviewer.clone(),
+        loop_detector.as_ref().map(|_| config.cwd.clone()),
+    );
+    let mut role_turns = RoleTurns::from_settings(&config.auto_drive.roles);
+    let retry_options = RetryOptions::from_settings(&config.auto_drive.retry);
+    let trace = CoordinatorTrace::start(&config, &goal_text);
+    let tape = DecisionTape::from_settings(&config.auto_drive)?;
+    if let Some(tape) = tape.as_ref() {
+        let mode = if tape.is_replay() {
+            "replaying"
+        } else {
+            "recording"
+        };
+        event_tx.send(AutoCoordinatorEvent::Action {
+            message: format!("{mode} coordinator decisions: {}", tape.dir().display()),
+        });
+    }
+    if let Some(trace) = trace.as_ref() {
+        event_tx.send(AutoCoordinatorEvent::Action {
+            message: format!("coordinator trace: {}", trace.dir().display()),
+        });
+    }
     if !derive_goal_from_history
         && let Some(seed) = build_initial_planning_seed(&goal_text, include_agents)
     {
@@ -1168,6 +1929,10 @@ fn run_auto_loop(
             prompt: seed.cli_prompt.clone(),
             context: Some(seed.goal_message.clone()),
             suppress_ui_context: true,
+            attachments: Vec::new(),
+            workstreams: Vec::new(),
+            complexity: None,
+            model: None,
         };
         let event = AutoCoordinatorEvent::Decision {
             seq: decision_seq,
@@ -1179,6 +1944,7 @@ fn run_auto_loop(
             agents_timing: seed.agents_timing,
             agents: Vec::new(),
             transcript: vec![transcript_item],
+            budget_snapshot: budget.snapshot(),
         };
         event_tx.send(event);
         pending_ack_seq = Some(decision_seq);
@@ -1195,7 +1961,20 @@ fn run_auto_loop(
     let mut consecutive_decision_failures: u32 = 0;
     let mut session_metrics = SessionMetrics::default();
     let mut active_model_slug = config.model.clone();
+    let mut latency_slo = DecisionLatencySlo::from_settings(&config.auto_drive, &config.model);
+    let mut model_failover = ModelFailover::from_settings(&config.auto_drive, &config.model);
+    let cli_router = CliModelRouter::from_settings(&config.auto_drive);
+    // Goal the simple loop templates its prompt with, and the loop itself
+    // once the coordinator is unavailable.
+    let mut current_goal = goal_text.clone();
+    let mut simple_loop: Option<SimpleLoop> = None;
     let mut prev_compact_summary: Option<String> = None;
+    // Set by `CompactNow`; cleared once the next history is compacted.
+    let mut compact_requested = false;
+    // Set by `Pause`; conversations wait in the queue until `Resume`.
+    let mut paused = false;
+    // Operator attachments waiting for the next CLI turn.
+    let mut pending_attachments: Vec<InputItem> = Vec::new();
 
     loop {
         if stopped {
@@ -1204,7 +1983,9 @@ fn run_auto_loop(
 
         let mut next_conversation: Option<Vec<ResponseItem>> = None;
 
-        if let Some(conv) = pending_conversation.take() {
+        if paused {
+            // Nothing new is requested until `Resume`.
+        } else if let Some(conv) = pending_conversation.take() {
             if let Some(pending_seq) = pending_ack_seq {
                 tracing::debug!(target: "auto_drive::coordinator", pending_seq, "queueing conversation until ack");
                 queued_updates.push_back(conv);
@@ -1223,6 +2004,21 @@ fn run_auto_loop(
                 continue;
             }
 
+            if let Some(simple) = simple_loop.as_mut() {
+                let step = simple.next_step(&conv);
+                let failed = matches!(step, SimpleLoopStep::Finished { success: false, .. });
+                decision_seq = decision_seq.wrapping_add(1);
+                pending_ack_seq = Some(decision_seq);
+                event_tx.send(simple_loop_decision(
+                    decision_seq,
+                    step,
+                    std::mem::take(&mut pending_attachments),
+                    budget.snapshot(),
+                ));
+                stopped = failed;
+                continue;
+            }
+
             let mut conv = filter_popular_commands(conv);
             match maybe_compact(
                 &runtime,
@@ -1233,6 +2029,8 @@ fn run_auto_loop(
                 prev_compact_summary.as_deref(),
                 &active_model_slug,
                 &compact_prompt_text,
+                compact_threshold,
+                std::mem::take(&mut compact_requested),
             ) {
                 CompactionResult::Completed { summary_text } => {
                     prev_compact_summary = summary_text;
@@ -1241,19 +2039,87 @@ fn run_auto_loop(
             }
             let developer_intro = base_developer_intro.as_str();
             let mut retry_conversation = Some(conv.clone());
-            match request_coordinator_decision(
+            let mut role_model = None;
+            if let Some(turns) = role_turns.as_mut() {
+                role_model = turns.current().model.clone();
+                conv.push(make_message("developer", role_turn_brief(turns)));
+            }
+            let decision_model = role_model.clone().unwrap_or_else(|| {
+                latency_slo
+                    .routine_model()
+                    .unwrap_or(&active_model_slug)
+                    .to_string()
+            });
+            let mut answered_by = decision_model.clone();
+            let decision_started = Instant::now();
+            let mut decision_result = request_coordinator_decision(
                 &runtime,
                 client.as_ref(),
                 developer_intro,
                 &primary_goal_message,
                 coordinator_prompt_message.as_deref(),
                 &schema,
-                conv,
+                conv.clone(),
                 auto_instructions.as_deref(),
                 &event_tx,
                 &cancel_token,
-                &active_model_slug,
-            ) {
+                &retry_options,
+                trace.as_ref(),
+                tape.as_ref(),
+                &decision_model,
+            );
+            if let Ok(decision) = decision_result.as_ref() {
+                if let Some(switch) = latency_slo.record(decision_started.elapsed()) {
+                    event_tx.send(AutoCoordinatorEvent::CoordinatorModelSwitched {
+                        from: active_model_slug.clone(),
+                        to: switch.fast_model,
+                        reason: format!(
+                            "p95 decision latency {:.1}s exceeded the {:.1}s SLO for {} decisions in a row; finish and failure judgments stay with {active_model_slug}",
+                            switch.p95.as_secs_f64(),
+                            switch.slo.as_secs_f64(),
+                            switch.breach_turns
+                        ),
+                    });
+                }
+                // Only the configured model, or the role's own, may end the run.
+                if role_model.is_none()
+                    && decision_model != active_model_slug
+                    && !matches!(decision.status, AutoCoordinatorStatus::Continue)
+                {
+                    debug!(
+                        fast_model = %decision_model,
+                        status = ?decision.status,
+                        "re-requesting a terminal decision from the coordinator model"
+                    );
+                    if let Some(usage) = decision.token_usage.as_ref() {
+                        session_metrics.record_turn(usage);
+                        session_metrics.attribute(
+                            decision_seq.wrapping_add(1),
+                            UsageSource::Coordinator,
+                            usage,
+                        );
+                        budget.record_usage(usage.blended_total(), true);
+                    }
+                    answered_by = active_model_slug.clone();
+                    decision_result = request_coordinator_decision(
+                        &runtime,
+                        client.as_ref(),
+                        developer_intro,
+                        &primary_goal_message,
+                        coordinator_prompt_message.as_deref(),
+                        &schema,
+                        conv,
+                        auto_instructions.as_deref(),
+                        &event_tx,
+                        &cancel_token,
+                        &retry_options,
+                        trace.as_ref(),
+                        tape.as_ref(),
+                        &active_model_slug,
+                    );
+                }
+            }
+            match decision_result {
                 Ok(ParsedCoordinatorDecision {
                     status,
                     status_title,
@@ -1262,6 +2128,8 @@ fn run_auto_loop(
                     cli,
                     mut agents_timing,
                     mut agents,
+                    cli_writes_files,
+                    review,
                     mut response_items,
                     token_usage,
                     model_slug,
@@ -1269,49 +2137,167 @@ fn run_auto_loop(
                     retry_conversation.take();
                     if let Some(usage) = token_usage.as_ref() {
                         session_metrics.record_turn(usage);
+                        session_metrics.attribute(
+                            decision_seq.wrapping_add(1),
+                            UsageSource::Coordinator,
+                            usage,
+                        );
                         emit_auto_drive_metrics(&event_tx, &session_metrics);
                     }
-                    active_model_slug = model_slug;
+                    budget.record_usage(
+                        token_usage.as_ref().map_or(0, TokenUsage::blended_total),
+                        true,
+                    );
+                    // A fallback from the configured model sticks; answers
+                    // from the fast model do not replace it.
+                    if answered_by == active_model_slug {
+                        active_model_slug = model_slug;
+                    }
                     if !include_agents {
                         agents_timing = None;
                         agents.clear();
                     }
                     consecutive_decision_failures = 0;
-                    if let Some(goal_text) = goal
-                        .as_ref()
-                        .map(|value| value.trim())
-                        .filter(|value| !value.is_empty())
-                    {
-                        primary_goal_message = format!("**Primary Goal**\n{goal_text}");
-                        if schema_features.include_goal_field {
-                            schema_features.include_goal_field = false;
-                            schema = build_schema(&active_agent_names, schema_features);
+                    // The goal the decision event reports, and an amendment
+                    // that waits for the operator.
+                    let mut goal = goal;
+                    let mut goal_amendment = None;
+                    match classify_goal_change(
+                        goal.as_deref(),
+                        &current_goal,
+                        schema_features.include_goal_field,
+                        config.auto_drive.goal_changes,
+                    ) {
+                        GoalChange::Unchanged => {}
+                        GoalChange::Adopt(goal_text) => {
+                            primary_goal_message = format!("**Primary Goal**\n{goal_text}");
+                            current_goal = goal_text;
+                            if schema_features.include_goal_field {
+                                schema_features.include_goal_field = false;
+                                schema = build_schema(&active_agent_names, schema_features);
+                            }
+                        }
+                        GoalChange::Confirm(goal_text) => goal_amendment = Some(goal_text),
+                        GoalChange::Reject(goal_text) => {
+                            event_tx.send(AutoCoordinatorEvent::Action {
+                                message: format!("goal change rejected: {goal_text}"),
+                            });
+                            goal = None;
                         }
                     }
                     decision_seq = decision_seq.wrapping_add(1);
                     let current_seq = decision_seq;
                     if matches!(status, AutoCoordinatorStatus::Continue) {
+                        if let Some(detector) = loop_detector.as_mut()
+                            && let Some(action) = cli.as_ref()
+                        {
+                            detector.record_prompt(&action.prompt);
+                        }
+                        let mut cli_event = cli.as_ref().map(|action| {
+                            let mut event = AutoTurnCliAction {
+                                attachments: std::mem::take(&mut pending_attachments),
+                                ..cli_action_to_event(action)
+                            };
+                            if let Some(router) = cli_router.as_ref() {
+                                router.route(&mut event);
+                            }
+                            event
+                        });
+                        if let Some(strategy) = review.as_ref()
+                            && strategy.timing != ReviewTiming::PostTurn
+                            && let Some(reviewer) = turn_reviewer.as_ref()
+                        {
+                            event_tx.send(AutoCoordinatorEvent::Action {
+                                message: format!(
+                                    "review ({}) requested from {}",
+                                    timing_label(strategy.timing),
+                                    reviewer.model()
+                                ),
+                            });
+                            let review = runtime.block_on(async {
+                                tokio::select! {
+                                    review = reviewer.before_turn(strategy) => review,
+                                    _ = cancel_token.cancelled() => None,
+                                }
+                            });
+                            if let Some(review) = review {
+                                event_tx.send(AutoCoordinatorEvent::Action {
+                                    message: review.summary(),
+                                });
+                                if let Some(event) = cli_event.as_mut() {
+                                    let findings = review.message();
+                                    event.context = Some(match event.context.take() {
+                                        Some(context) => format!("{context}\n\n{findings}"),
+                                        None => findings,
+                                    });
+                                }
+                            }
+                        }
+                        let agent_events: Vec<AutoTurnAgentsAction> = agents
+                            .iter()
+                            .map(|action| {
+                                agent_action_to_event_with_write_guard(
+                                    action,
+                                    git_repo_present,
+                                    agent_timeout_seconds,
+                                )
+                            })
+                            .collect();
+                        let writes =
+                            cli_writes_files || agent_events.iter().any(|agent| agent.write);
+                        let holds_write = !write_approved && writes;
+                        let checkpoint_reason = [
+                            goal_amendment.as_deref().map(goal_change_checkpoint_reason),
+                            holds_write.then(|| {
+                                first_write_checkpoint_reason(
+                                    status_sent_to_user.as_deref(),
+                                    cli_event.as_ref(),
+                                    &agent_events,
+                                )
+                            }),
+                        ]
+                        .into_iter()
+                        .flatten()
+                        .reduce(|goal, write| format!("{goal}\n\n{write}"));
+                        if let Some(turns) = role_turns.as_mut() {
+                            let from = turns.current().name.clone();
+                            let note =
+                                role_handoff_note(status_title.as_deref(), cli_event.as_ref());
+                            let to = &turns.hand_off(note).name;
+                            event_tx.send(AutoCoordinatorEvent::Action {
+                                message: format!("coordinator role: {from} → {to}"),
+                            });
+                        }
                         let event = AutoCoordinatorEvent::Decision {
                             seq: current_seq,
                             status,
                             status_title: status_title.clone(),
                             status_sent_to_user: status_sent_to_user.clone(),
                             goal: goal.clone(),
-                            cli: cli.as_ref().map(cli_action_to_event),
+                            cli: cli_event,
                             agents_timing,
-                            agents: agents
-                                .iter()
-                                .map(|action| {
-                                    agent_action_to_event_with_write_guard(action, git_repo_present)
-                                })
-                                .collect(),
+                            agents: agent_events,
                             transcript: std::mem::take(&mut response_items),
+                            budget_snapshot: budget.snapshot(),
                         };
                         pending_ack_seq = Some(current_seq);
-                        event_tx.send(event);
+                        if let Some(reason) = checkpoint_reason {
+                            held_decision = Some(HeldDecision {
+                                event,
+                                goal: goal_amendment,
+                                write: holds_write,
+                            });
+                            event_tx.send(AutoCoordinatorEvent::InterventionRequired { reason });
+                        } else {
+                            event_tx.send(event);
+                        }
                         continue;
                     }
 
+                    // A run that is ending has no use for an amended goal.
+                    if goal_amendment.is_some() {
+                        goal = None;
+                    }
                     let decision_event = PendingDecision {
                         seq: current_seq,
                         status,
@@ -1323,10 +2309,15 @@ fn run_auto_loop(
                         agents: agents
                             .iter()
                             .map(|action| {
-                                agent_action_to_event_with_write_guard(action, git_repo_present)
+                                agent_action_to_event_with_write_guard(
+                                    action,
+                                    git_repo_present,
+                                    agent_timeout_seconds,
+                                )
                             })
                             .collect(),
                         transcript: response_items,
+                        budget_snapshot: budget.snapshot(),
                     };
 
                     let should_stop =
@@ -1369,6 +2360,15 @@ fn run_auto_loop(
                                 "auto coordinator decision validation failed (attempt {}/{}): {:#}",
                                 attempt, MAX_DECISION_RECOVERY_ATTEMPTS, error
                             );
+                            if let Some(trace) = trace.as_ref() {
+                                trace.record_recovery(
+                                    attempt,
+                                    MAX_DECISION_RECOVERY_ATTEMPTS,
+                                    &error,
+                                    &recoverable.summary,
+                                    recoverable.guidance.as_deref(),
+                                );
+                            }
                             let raw_excerpt = if already_shared_raw {
                                 None
                             } else {
@@ -1412,6 +2412,7 @@ fn run_auto_loop(
                                 event_tx.send(AutoCoordinatorEvent::CompactedHistory {
                                     conversation: conv.clone(),
                                     show_notice: false,
+                                    estimate: None,
                                 });
                             }
                             // Show a user-facing action entry in the Auto Drive card (does not go to the model).
@@ -1427,6 +2428,51 @@ fn run_auto_loop(
                         );
                     }
                     consecutive_decision_failures = 0;
+                    let failover_reason = if classify_recoverable_decision_error(&error).is_some() {
+                        Some(format!(
+                            "{} invalid decisions in a row: {error}",
+                            MAX_DECISION_RECOVERY_ATTEMPTS + 1
+                        ))
+                    } else {
+                        model_failover_reason(&error)
+                    };
+                    // Failures of the fast model leave the chain alone.
+                    if answered_by == active_model_slug
+                        && let Some(reason) = failover_reason
+                        && let Some(next_model) = model_failover.next(&active_model_slug)
+                    {
+                        warn!(
+                            "auto coordinator switching from {active_model_slug} to {next_model}: {reason}"
+                        );
+                        event_tx.send(AutoCoordinatorEvent::CoordinatorFailover {
+                            from: active_model_slug.clone(),
+                            to: next_model.clone(),
+                            reason,
+                        });
+                        active_model_slug = next_model;
+                        pending_conversation = retry_conversation.take();
+                        continue;
+                    }
+                    if coordinator_unavailable(&error)
+                        && let Some(mut simple) =
+                            SimpleLoop::new(&current_goal, config.auto_drive.simple_loop_max_turns)
+                    {
+                        warn!("auto coordinator unavailable; continuing in simple loop: {error:#}");
+                        event_tx.send(AutoCoordinatorEvent::CoordinatorDegraded {
+                            reason: error.to_string(),
+                        });
+                        let prompt = simple.start();
+                        simple_loop = Some(simple);
+                        decision_seq = decision_seq.wrapping_add(1);
+                        pending_ack_seq = Some(decision_seq);
+                        event_tx.send(simple_loop_decision(
+                            decision_seq,
+                            SimpleLoopStep::Continue { prompt },
+                            std::mem::take(&mut pending_attachments),
+                            budget.snapshot(),
+                        ));
+                        continue;
+                    }
                     decision_seq = decision_seq.wrapping_add(1);
                     let current_seq = decision_seq;
                     let event = AutoCoordinatorEvent::Decision {
@@ -1439,6 +2485,7 @@ fn run_auto_loop(
                         agents_timing: None,
                         agents: Vec::new(),
                         transcript: Vec::new(),
+                        budget_snapshot: budget.snapshot(),
                     };
                     pending_ack_seq = Some(current_seq);
                     event_tx.send(event);
@@ -1463,9 +2510,33 @@ fn run_auto_loop(
             Ok(AutoCoordinatorCommand::HandleUserPrompt {
                 _prompt,
                 conversation,
+                attachments,
             }) => {
+                if held_decision.take().is_some() {
+                    // The operator answered the checkpoint with guidance; the
+                    // held decision, and any goal amendment, is replanned
+                    // around it.
+                    tracing::debug!(target: "auto_drive::coordinator", "held decision replaced by operator guidance");
+                    pending_ack_seq = None;
+                }
+                if simple_loop.is_some() {
+                    // Without a coordinator the operator's message goes
+                    // straight to the CLI.
+                    let mut queued = std::mem::take(&mut pending_attachments);
+                    queued.extend(attachments);
+                    event_tx.send(AutoCoordinatorEvent::UserReply {
+                        user_response: None,
+                        cli_command: Some(_prompt),
+                        attachments: queued,
+                    });
+                    continue;
+                }
                 let developer_intro = base_developer_intro.as_str();
                 let mut updated_conversation = conversation.clone();
+                if !attachments.is_empty() {
+                    updated_conversation.push(make_message("user", attachments_note(&attachments)));
+                    pending_attachments.extend(attachments);
+                }
                 let schema = user_turn_schema();
                 match request_user_turn_decision(
                     &runtime,
@@ -1478,6 +2549,9 @@ fn run_auto_loop(
                     auto_instructions.as_deref(),
                     &event_tx,
                     &cancel_token,
+                    &retry_options,
+                    trace.as_ref(),
+                    tape.as_ref(),
                     &active_model_slug,
                 ) {
                     Ok((user_response, cli_command)) => {
@@ -1486,9 +2560,15 @@ fn run_auto_loop(
                                 .push(make_message("assistant", response_text.clone()));
                         }
                         pending_conversation = Some(updated_conversation);
+                        let attachments = if cli_command.is_some() {
+                            std::mem::take(&mut pending_attachments)
+                        } else {
+                            Vec::new()
+                        };
                         event_tx.send(AutoCoordinatorEvent::UserReply {
                             user_response,
                             cli_command,
+                            attachments,
                         });
                     }
                     Err(failure) => {
@@ -1512,14 +2592,106 @@ fn run_auto_loop(
                         event_tx.send(AutoCoordinatorEvent::UserReply {
                             user_response: Some(format!("Coordinator error: {error}")),
                             cli_command: None,
+                            attachments: Vec::new(),
+                        });
+                    }
+                }
+            }
+            Ok(AutoCoordinatorCommand::UpdateConversation(conv)) => {
+                requests_completed = requests_completed.saturating_add(1);
+                consecutive_decision_failures = 0;
+                if let Some(alert) = budget.check_budget()
+                    && let Some((alert_type, message)) = describe_budget_alert(&alert)
+                {
+                    if budget.should_pause() {
+                        event_tx.send(AutoCoordinatorEvent::BudgetAlert {
+                            alert_type,
+                            message: message.clone(),
+                        });
+                        decision_seq = decision_seq.wrapping_add(1);
+                        let current_seq = decision_seq;
+                        event_tx.send(AutoCoordinatorEvent::Decision {
+                            seq: current_seq,
+                            status: AutoCoordinatorStatus::Failed,
+                            status_title: Some("Budget exhausted".to_string()),
+                            status_sent_to_user: Some(format!("Stopping: {message}.")),
+                            goal: None,
+                            cli: None,
+                            agents_timing: None,
+                            agents: Vec::new(),
+                            transcript: Vec::new(),
+                            budget_snapshot: budget.snapshot(),
+                        });
+                        pending_ack_seq = Some(current_seq);
+                        pending_conversation = None;
+                        queued_updates.clear();
+                        stopped = true;
+                        continue;
+                    }
+                    if !budget_warning_sent {
+                        budget_warning_sent = true;
+                        event_tx.send(AutoCoordinatorEvent::BudgetAlert {
+                            alert_type,
+                            message,
                         });
                     }
                 }
+                turn_checker.submit(&runtime, conv);
             }
-            Ok(AutoCoordinatorCommand::UpdateConversation(conv)) => {
-                requests_completed = requests_completed.saturating_add(1);
-                consecutive_decision_failures = 0;
-                let filtered = filter_popular_commands(conv);
+            Ok(AutoCoordinatorCommand::TurnChecked(checks)) => {
+                turn_checker.finished(&runtime);
+                if let Some(evaluation) = checks.stop {
+                    if evaluation.all_met() {
+                        decision_seq = decision_seq.wrapping_add(1);
+                        let current_seq = decision_seq;
+                        event_tx.send(AutoCoordinatorEvent::Decision {
+                            seq: current_seq,
+                            status: AutoCoordinatorStatus::Success,
+                            status_title: Some("Stop conditions met".to_string()),
+                            status_sent_to_user: Some(format!(
+                                "All stop conditions hold: {}.",
+                                evaluation.met.join(", ")
+                            )),
+                            goal: None,
+                            cli: None,
+                            agents_timing: None,
+                            agents: Vec::new(),
+                            transcript: Vec::new(),
+                            budget_snapshot: budget.snapshot(),
+                        });
+                        pending_ack_seq = Some(current_seq);
+                        pending_conversation = None;
+                        queued_updates.clear();
+                        turn_checker.cancel();
+                        continue;
+                    }
+                    tracing::debug!(target: "auto_drive::coordinator", unmet = ?evaluation.unmet, "stop conditions not yet met");
+                }
+                let mut filtered = filter_popular_commands(checks.conversation);
+                // The simple loop sends the same templated prompt on purpose
+                // and has no coordinator to steer.
+                if simple_loop.is_none()
+                    && let Some(detector) = loop_detector.as_mut()
+                    && let Some(finding) = detector.observe_turn(&filtered, checks.fingerprint)
+                {
+                    event_tx.send(AutoCoordinatorEvent::DiagnosticAlert {
+                        alert_type: finding.alert_type,
+                        message: finding.message,
+                    });
+                    filtered.push(make_message("developer", finding.correction));
+                }
+                if let Some(results) = checks.tests {
+                    event_tx.send(AutoCoordinatorEvent::Action {
+                        message: selective_test_summary(&results),
+                    });
+                    filtered.push(make_message("developer", selective_test_message(&results)));
+                }
+                for review in checks.reviews {
+                    event_tx.send(AutoCoordinatorEvent::Action {
+                        message: review.summary(),
+                    });
+                    filtered.push(make_message("developer", review.message()));
+                }
                 if let Some(pending_seq) = pending_ack_seq {
                     tracing::debug!(target: "auto_drive::coordinator", pending_seq, "queueing update while awaiting ack");
                     session_metrics.record_replay();
@@ -1531,7 +2703,57 @@ fn run_auto_loop(
                     pending_conversation = Some(filtered);
                 }
             }
+            Ok(AutoCoordinatorCommand::ReportUsage {
+                cli_tokens,
+                cost_usd,
+            }) => {
+                budget.record_cli_usage(cli_tokens, cost_usd);
+            }
+            Ok(AutoCoordinatorCommand::ReportTurnUsage(entries)) => {
+                for entry in entries {
+                    session_metrics.attribute(entry.seq, entry.source, &entry.usage);
+                }
+                emit_auto_drive_metrics(&event_tx, &session_metrics);
+            }
+            Ok(AutoCoordinatorCommand::CompactNow) => {
+                compact_requested = true;
+                event_tx.send(AutoCoordinatorEvent::Action {
+                    message: "History will be compacted before the next decision.".to_string(),
+                });
+            }
+            Ok(AutoCoordinatorCommand::Pause) => {
+                if !paused {
+                    paused = true;
+                    event_tx.send(AutoCoordinatorEvent::Action {
+                        message: "Paused: no new decisions until resumed.".to_string(),
+                    });
+                }
+            }
+            Ok(AutoCoordinatorCommand::Resume) => {
+                if paused {
+                    paused = false;
+                    event_tx.send(AutoCoordinatorEvent::Action {
+                        message: "Resumed.".to_string(),
+                    });
+                }
+            }
+            Ok(AutoCoordinatorCommand::ApproveHeldDecision) => {
+                if let Some(held) = held_decision.take() {
+                    tracing::debug!(target: "auto_drive::coordinator", "held decision approved");
+                    write_approved |= held.write;
+                    if let Some(goal_text) = held.goal {
+                        event_tx.send(AutoCoordinatorEvent::Action {
+                            message: format!("goal changed: {goal_text}"),
+                        });
+                        primary_goal_message = format!("**Primary Goal**\n{goal_text}");
+                        current_goal = goal_text;
+                    }
+                    event_tx.send(held.event);
+                }
+            }
             Ok(AutoCoordinatorCommand::Stop) | Err(_) => {
+                held_decision = None;
+                turn_checker.cancel();
                 stopped = true;
                 event_tx.send(AutoCoordinatorEvent::StopAck);
                 pending_ack_seq = None;
@@ -1543,6 +2765,45 @@ fn run_auto_loop(
     Ok(())
 }
 
+/// UI-facing type and message for a budget alert; `None` for alerts the
+/// coordinator's own budget never raises.
+fn describe_budget_alert(alert: &BudgetAlert) -> Option<(BudgetAlertType, String)> {
+    match alert {
+        BudgetAlert::TokenWarning {
+            used,
+            limit,
+            percentage,
+        } => Some((
+            BudgetAlertType::TokenWarning,
+            format!("{used} of {limit} tokens used ({percentage:.0}%)"),
+        )),
+        BudgetAlert::TokenExceeded { used, limit } => Some((
+            BudgetAlertType::TokenExceeded,
+            format!("token budget exhausted ({used} of {limit} tokens)"),
+        )),
+        BudgetAlert::TurnLimitReached { count, limit } => Some((
+            BudgetAlertType::TurnLimitReached,
+            format!("turn limit reached ({count} of {limit} turns)"),
+        )),
+        BudgetAlert::DurationExceeded { elapsed, limit } => Some((
+            BudgetAlertType::DurationExceeded,
+            format!(
+                "time limit reached ({}s of {}s)",
+                elapsed.as_secs(),
+                limit.as_secs()
+            ),
+        )),
+        BudgetAlert::CostExceeded {
+            spent_usd,
+            limit_usd,
+        } => Some((
+            BudgetAlertType::CostExceeded,
+            format!("cost limit reached (${spent_usd:.2} of ${limit_usd:.2})"),
+        )),
+        BudgetAlert::BackpressureWarning { .. } | BudgetAlert::BackpressureExceeded { .. } => None,
+    }
+}
+
 fn filter_popular_commands(items: Vec<ResponseItem>) -> Vec<ResponseItem> {
     items
         .into_iter()
@@ -1561,13 +2822,51 @@ fn is_popular_commands_message(item: &ResponseItem) -> bool {
         _ => false,
     }
 }
-fn read_coordinator_prompt(_config: &Config) -> Option<String> {
-    let trimmed = COORDINATOR_PROMPT.trim();
-    if trimmed.is_empty() {
-        None
-    } else {
-        Some(trimmed.to_string())
+/// Built-in coordinator prompt combined with `$CODE_HOME/prompt_coordinator.md`
+/// and any project `AUTO_COORDINATOR.md` files, per
+/// `auto_drive.coordinator_prompt_mode`.
+fn read_coordinator_prompt(config: &Config) -> Option<String> {
+    let mut paths = vec![config.code_home.join(CUSTOM_COORDINATOR_PROMPT_FILENAME)];
+    match discover_coordinator_prompt_paths(config) {
+        Ok(project_paths) => paths.extend(project_paths),
+        Err(err) => warn!("failed to discover AUTO_COORDINATOR.md files: {err:#}"),
     }
+    let custom: Vec<String> = paths
+        .iter()
+        .filter_map(|path| match std::fs::read_to_string(path) {
+            Ok(text) => Some(text.trim().to_string()).filter(|text| !text.is_empty()),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+            Err(err) => {
+                warn!(
+                    "failed to read coordinator prompt {}: {err:#}",
+                    path.display()
+                );
+                None
+            }
+        })
+        .collect();
+    let custom = (!custom.is_empty()).then(|| custom.join("\n\n"));
+    compose_coordinator_prompt(
+        COORDINATOR_PROMPT,
+        custom.as_deref(),
+        config.auto_drive.coordinator_prompt_mode,
+    )
+}
+
+fn compose_coordinator_prompt(
+    built_in: &str,
+    custom: Option<&str>,
+    mode: CoordinatorPromptMode,
+) -> Option<String> {
+    let built_in = built_in.trim();
+    let prompt = match (custom.map(str::trim), mode) {
+        (Some(custom), CoordinatorPromptMode::Replace) if !custom.is_empty() => custom.to_string(),
+        (Some(custom), CoordinatorPromptMode::Merge) if !custom.is_empty() => {
+            format!("{built_in}\n\n{custom}").trim().to_string()
+        }
+        _ => built_in.to_string(),
+    };
+    (!prompt.is_empty()).then_some(prompt)
 }
 
 fn build_developer_message(
@@ -1669,6 +2968,13 @@ fn run_git_command<const N: usize>(args: [&str; N]) -> Option<String> {
 struct SchemaFeatures {
     include_agents: bool,
     include_goal_field: bool,
+    /// Optional `goal` through which the coordinator proposes amendments.
+    include_goal_amendment: bool,
+    include_write_intent: bool,
+    /// Upper bound on CLI workstreams per turn; 0 omits the field.
+    max_workstreams: u8,
+    include_cli_complexity: bool,
+    include_review: bool,
 }
 
 impl SchemaFeatures {
@@ -1676,6 +2982,15 @@ impl SchemaFeatures {
         Self {
             include_agents: settings.agents_enabled,
             include_goal_field: false,
+            include_goal_amendment: settings.goal_changes == AutoDriveGoalChanges::Confirm,
+            include_write_intent: settings.confirm_first_write,
+            max_workstreams: if settings.cli_workers && settings.parallel_instances > 1 {
+                settings.parallel_instances.min(5)
+            } else {
+                0
+            },
+            include_cli_complexity: settings.cli_model_routing.is_enabled(),
+            include_review: settings.turn_reviews,
         }
     }
 }
@@ -1685,6 +3000,11 @@ impl Default for SchemaFeatures {
         Self {
             include_agents: true,
             include_goal_field: false,
+            include_goal_amendment: false,
+            include_write_intent: false,
+            max_workstreams: 0,
+            include_cli_complexity: false,
+            include_review: false,
         }
     }
 }
@@ -1737,6 +3057,17 @@ fn build_schema(active_agents: &[String], features: SchemaFeatures) -> Value {
             }),
         );
         required.push(Value::String("goal".to_string()));
+    } else if features.include_goal_amendment {
+        properties.insert(
+            "goal".to_string(),
+            json!({
+                "type": ["string", "null"],
+                "minLength": 4,
+                "maxLength": 200,
+                "description": "Null unless the primary goal itself must change, e.g. the work so far shows it rests on a wrong premise. Give the complete amended goal; it only takes effect once the user approves it, and this turn runs under the current goal."
+            }),
+        );
+        required.push(Value::String("goal".to_string()));
     }
 
     properties.insert(
@@ -1775,6 +3106,82 @@ fn build_schema(active_agents: &[String], features: SchemaFeatures) -> Value {
     );
     required.push(Value::String("prompt_sent_to_cli".to_string()));
 
+    if features.include_write_intent {
+        properties.insert(
+            "cli_writes_files".to_string(),
+            json!({
+                "type": "boolean",
+                "description": "True when prompt_sent_to_cli asks the CLI to create, edit, or delete files. Planning, research, and read-only verification turns are false."
+            }),
+        );
+        required.push(Value::String("cli_writes_files".to_string()));
+    }
+
+    if features.include_cli_complexity {
+        properties.insert(
+            "cli_complexity".to_string(),
+            json!({
+                "type": ["string", "null"],
+                "enum": ["low", "medium", "high", null],
+                "description": "How demanding prompt_sent_to_cli is; simpler turns run on a cheaper model. low: mechanical steps such as running tests, formatting, committing, or a small single-file edit. medium: ordinary feature or fix work. high: design, multi-file refactors, or debugging an unclear failure. Null when finish_status is not 'continue'."
+            }),
+        );
+        required.push(Value::String("cli_complexity".to_string()));
+    }
+
+    if features.include_review {
+        properties.insert(
+            "review".to_string(),
+            json!({
+                "type": ["object", "null"],
+                "description": "Ask for a code review by a separate read-only reviewer; its findings reach you before your next decision. immediate: review the changes prompt_sent_to_cli makes, as soon as the CLI turn ends. Use after risky or wide-reaching edits. pre_write: review the uncommitted changes already in the workspace before the CLI writes more; the findings are also passed to the CLI with this prompt. Null for no review, which should be most turns.",
+                "additionalProperties": false,
+                "properties": {
+                    "timing": {
+                        "type": "string",
+                        "enum": ["immediate", "pre_write"]
+                    },
+                    "scope_hint": {
+                        "type": ["string", "null"],
+                        "description": "What the reviewer should focus on, e.g. 'the new retry backoff in client.rs'. Null for a general review."
+                    }
+                },
+                "required": ["timing", "scope_hint"]
+            }),
+        );
+        required.push(Value::String("review".to_string()));
+    }
+
+    if features.max_workstreams > 0 {
+        properties.insert(
+            "workstreams".to_string(),
+            json!({
+                "type": ["array", "null"],
+                "maxItems": features.max_workstreams,
+                "description": "Independent workstreams the CLI runs concurrently in separate sessions before prompt_sent_to_cli. Use only when the work splits into parts that touch disjoint files; each session starts fresh, so the context must carry everything it needs. prompt_sent_to_cli then runs in the main session with every workstream's result attached, to merge and verify the work. Null for a single-session turn.",
+                "items": {
+                    "type": "object",
+                    "additionalProperties": false,
+                    "properties": {
+                        "prompt": {
+                            "type": "string",
+                            "minLength": 8,
+                            "maxLength": 400,
+                            "description": "Outcome-oriented instruction for this workstream."
+                        },
+                        "context": {
+                            "type": ["string", "null"],
+                            "maxLength": 1500,
+                            "description": "Background details, including the files this workstream owns."
+                        }
+                    },
+                    "required": ["prompt", "context"]
+                }
+            }),
+        );
+        required.push(Value::String("workstreams".to_string()));
+    }
+
     if features.include_agents {
         properties.insert(
             "agents".to_string(),
@@ -1810,9 +3217,13 @@ fn build_schema(active_agents: &[String], features: SchemaFeatures) -> Value {
                                     "maxLength": 400,
                                     "description": "Outcome-oriented instruction (what to produce)."
                                 },
-                                "models": models_request_property
+                                "models": models_request_property,
+                                "timeout_seconds": {
+                                    "type": ["integer", "null"],
+                                    "description": "Optional seconds before the agent is cancelled and its partial output is returned. Use null for the configured default."
+                                }
                             },
-                            "required": ["prompt", "context", "write", "models"]
+                            "required": ["prompt", "context", "write", "models", "timeout_seconds"]
                         },
                         "description": "Up to 3 batches per turn with up to 4 agents in each. Use agents whenever it will help to source a variety of opinions when planning/researching or when there a mulitple workstreams which can be extecuted at once. Instruct the agent to carefully merge in the results of the agents work. Another great reason to use agents is that it helps to split the work up in small batches with a new context history - this speeds up work and dramatically improve focus. Having said that, the CLI has to be responible for merging in the results and producing the final product, so you need to balance the work given to the agents vs work given to the CLI at each step."
                     },
@@ -1855,6 +3266,9 @@ fn request_coordinator_decision(
     auto_instructions: Option<&str>,
     event_tx: &AutoCoordinatorEventSender,
     cancel_token: &CancellationToken,
+    retry_options: &RetryOptions,
+    trace: Option<&CoordinatorTrace>,
+    tape: Option<&DecisionTape>,
     preferred_model_slug: &str,
 ) -> Result<ParsedCoordinatorDecision, DecisionFailure> {
     let RequestStreamResult {
@@ -1873,6 +3287,9 @@ fn request_coordinator_decision(
         auto_instructions,
         event_tx,
         cancel_token,
+        retry_options,
+        trace,
+        tape,
         preferred_model_slug,
     )
     .map_err(|err| DecisionFailure::new(err, "coordinator_decision", None))?;
@@ -1883,7 +3300,14 @@ fn request_coordinator_decision(
             Some(output_text),
         ));
     }
-    let (mut decision, value) = parse_decision(&output_text).map_err(|err| {
+    let parsed = parse_decision(&output_text);
+    if let Some(trace) = trace {
+        trace.record_parse(
+            "coordinator_decision",
+            parsed.as_ref().map(|(_, value)| value.clone()),
+        );
+    }
+    let (mut decision, value) = parsed.map_err(|err| {
         DecisionFailure::new(err, "coordinator_decision", Some(output_text.clone()))
     })?;
     debug!("[Auto coordinator] model decision: {:?}", value);
@@ -1904,6 +3328,9 @@ fn request_decision(
     auto_instructions: Option<&str>,
     event_tx: &AutoCoordinatorEventSender,
     cancel_token: &CancellationToken,
+    retry_options: &RetryOptions,
+    trace: Option<&CoordinatorTrace>,
+    tape: Option<&DecisionTape>,
     preferred_model_slug: &str,
 ) -> Result<RequestStreamResult> {
     match request_decision_with_model(
@@ -1917,6 +3344,9 @@ fn request_decision(
         auto_instructions,
         event_tx,
         cancel_token,
+        retry_options,
+        trace,
+        tape,
         preferred_model_slug,
     ) {
         Ok(result) => Ok(result),
@@ -1946,6 +3376,9 @@ fn request_decision(
                     auto_instructions,
                     event_tx,
                     cancel_token,
+                    retry_options,
+                    trace,
+                    tape,
                     &fallback_slug,
                 )
                 .map_err(|fallback_err| {
@@ -1989,6 +3422,9 @@ fn request_user_turn_decision(
     auto_instructions: Option<&str>,
     event_tx: &AutoCoordinatorEventSender,
     cancel_token: &CancellationToken,
+    retry_options: &RetryOptions,
+    trace: Option<&CoordinatorTrace>,
+    tape: Option<&DecisionTape>,
     preferred_model_slug: &str,
 ) -> Result<(Option<String>, Option<String>), DecisionFailure> {
     let result = request_decision(
@@ -2002,17 +3438,28 @@ fn request_user_turn_decision(
         auto_instructions,
         event_tx,
         cancel_token,
+        retry_options,
+        trace,
+        tape,
         preferred_model_slug,
     )
     .map_err(|err| DecisionFailure::new(err, "auto_coordinator_user_turn", None))?;
-    let (user_response, cli_command) =
-        parse_user_turn_reply(&result.output_text).map_err(|err| {
-            DecisionFailure::new(
-                err,
-                "auto_coordinator_user_turn",
-                Some(result.output_text.clone()),
-            )
-        })?;
+    let parsed = parse_user_turn_reply(&result.output_text);
+    if let Some(trace) = trace {
+        trace.record_parse(
+            "auto_coordinator_user_turn",
+            parsed.as_ref().map(|(user_response, cli_command)| {
+                json!({ "user_response": user_response, "cli_command": cli_command })
+            }),
+        );
+    }
+    let (user_response, cli_command) = parsed.map_err(|err| {
+        DecisionFailure::new(
+            err,
+            "auto_coordinator_user_turn",
+            Some(result.output_text.clone()),
+        )
+    })?;
     Ok((user_response, cli_command))
 }
 
@@ -2027,6 +3474,9 @@ fn request_decision_with_model(
     auto_instructions: Option<&str>,
     event_tx: &AutoCoordinatorEventSender,
     cancel_token: &CancellationToken,
+    retry_options: &RetryOptions,
+    trace: Option<&CoordinatorTrace>,
+    tape: Option<&DecisionTape>,
     model_slug: &str,
 ) -> Result<RequestStreamResult> {
     let developer_intro = developer_intro.to_string();
@@ -2038,7 +3488,8 @@ fn request_decision_with_model(
     let tx = event_tx.clone();
     let cancel = cancel_token.clone();
     let classify = |error: &anyhow::Error| classify_model_error(error);
-    let options = RetryOptions::with_defaults(MAX_RETRY_ELAPSED);
+    let options = retry_options.clone();
+    let conversation_len = conversation.len();
 
     let result = runtime.block_on(async move {
         retry_with_backoff(
@@ -2062,83 +3513,34 @@ fn request_decision_with_model(
                         let err = fault_to_error(fault);
                         return Err(err);
                     }
-                    let mut stream = client.stream(&prompt).await?;
-                    let mut out = String::new();
-                    let mut response_items: Vec<ResponseItem> = Vec::new();
-                    let mut reasoning_delta_accumulator = String::new();
-                    let mut saw_output_text_delta = false;
-                    let mut token_usage: Option<TokenUsage> = None;
-                    while let Some(ev) = stream.next().await {
-                        match ev {
-                            Ok(ResponseEvent::OutputTextDelta { delta, .. }) => {
-                                out.push_str(&delta);
-                                saw_output_text_delta = true;
-                            }
-                            Ok(ResponseEvent::OutputItemDone { item, .. }) => {
-                                if let ResponseItem::Message { content, .. } = &item
-                                    && !saw_output_text_delta {
-                                        for c in content {
-                                            if let ContentItem::OutputText { text } = c {
-                                                out.push_str(text);
-                                            }
-                                        }
-                                    }
-                                if matches!(item, ResponseItem::Reasoning { .. }) {
-                                    reasoning_delta_accumulator.clear();
-                                }
-                                response_items.push(item);
-                                saw_output_text_delta = false;
-                            }
-                            Ok(ResponseEvent::ReasoningSummaryDelta {
-                                delta,
-                                summary_index,
-                                ..
-                            }) => {
-                                let cleaned = strip_role_prefix(&delta);
-                                reasoning_delta_accumulator.push_str(cleaned);
-                                let message = cleaned.to_string();
-                                tx_inner.send(AutoCoordinatorEvent::Thinking {
-                                    delta: message,
-                                    summary_index,
-                                });
-                            }
-                            Ok(ResponseEvent::ReasoningContentDelta { delta, .. }) => {
-                                let cleaned = strip_role_prefix(&delta);
-                                reasoning_delta_accumulator.push_str(cleaned);
-                                let message = cleaned.to_string();
-                                tx_inner.send(AutoCoordinatorEvent::Thinking {
-                                    delta: message,
-                                    summary_index: None,
-                                });
-                            }
-                            Ok(ResponseEvent::Completed { token_usage: usage, .. }) => {
-                                token_usage = usage;
-                                break;
-                            }
-                            Err(err) => return Err(err.into()),
-                            _ => {}
-                        }
-                    }
-                    if !reasoning_delta_accumulator.trim().is_empty()
-                        && !response_items
-                            .iter()
-                            .any(|item| matches!(item, ResponseItem::Reasoning { .. }))
-                    {
-                        response_items.push(ResponseItem::Reasoning {
-                            id: String::new(),
-                            summary: Vec::new(),
-                            content: Some(vec![ReasoningItemContent::ReasoningText {
-                                text: reasoning_delta_accumulator.trim().to_string(),
-                            }]),
-                            encrypted_content: None,
+                    let result = match tape.and_then(DecisionTape::next_response) {
+                        Some(replayed) => replayed.map(|entry| RequestStreamResult {
+                            output_text: entry.output_text,
+                            response_items: entry.response_items,
+                            token_usage: entry.token_usage,
+                            model_slug: entry.model_slug,
+                        }),
+                        None => stream_decision(client, &prompt, &tx_inner, model_slug).await,
+                    };
+                    if let (Some(tape), Ok(output)) = (tape, result.as_ref()) {
+                        tape.record_response(&TapeEntry {
+                            model_slug: output.model_slug.clone(),
+                            conversation_len,
+                            output_text: output.output_text.clone(),
+                            response_items: output.response_items.clone(),
+                            token_usage: output.token_usage.clone(),
                         });
                     }
-                    Ok(RequestStreamResult {
-                        output_text: out,
-                        response_items,
-                        token_usage,
-                        model_slug: model_slug.to_string(),
-                    })
+                    if let Some(trace) = trace {
+                        trace.record_request(
+                            &prompt,
+                            conversation_len,
+                            result.as_ref().map(|output| {
+                                (output.output_text.as_str(), output.response_items.as_slice())
+                            }),
+                        );
+                    }
+                    result
                 }
             },
             classify,
@@ -2197,9 +3599,100 @@ fn request_decision_with_model(
             "auto coordinator retry window exceeded after {}",
             format_duration(elapsed)
         ))),
+        Err(RetryError::Exhausted { reason, last_error }) => {
+            Err(last_error.context(format!("auto coordinator retries stopped: {reason}")))
+        }
     }
 }
 
+async fn stream_decision(
+    client: &ModelClient,
+    prompt: &Prompt,
+    tx: &AutoCoordinatorEventSender,
+    model_slug: &str,
+) -> Result<RequestStreamResult> {
+    let mut stream = client.stream(prompt).await?;
+    let mut out = String::new();
+    let mut response_items: Vec<ResponseItem> = Vec::new();
+    let mut reasoning_delta_accumulator = String::new();
+    let mut saw_output_text_delta = false;
+    let mut token_usage: Option<TokenUsage> = None;
+    while let Some(ev) = stream.next().await {
+        match ev {
+            Ok(ResponseEvent::OutputTextDelta { delta, .. }) => {
+                out.push_str(&delta);
+                saw_output_text_delta = true;
+            }
+            Ok(ResponseEvent::OutputItemDone { item, .. }) => {
+                if let ResponseItem::Message { content, .. } = &item
+                    && !saw_output_text_delta
+                {
+                    for c in content {
+                        if let ContentItem::OutputText { text } = c {
+                            out.push_str(text);
+                        }
+                    }
+                }
+                if matches!(item, ResponseItem::Reasoning { .. }) {
+                    reasoning_delta_accumulator.clear();
+                }
+                response_items.push(item);
+                saw_output_text_delta = false;
+            }
+            Ok(ResponseEvent::ReasoningSummaryDelta {
+                delta,
+                summary_index,
+                ..
+            }) => {
+                let cleaned = strip_role_prefix(&delta);
+                reasoning_delta_accumulator.push_str(cleaned);
+                let message = cleaned.to_string();
+                tx.send(AutoCoordinatorEvent::Thinking {
+                    delta: message,
+                    summary_index,
+                });
+            }
+            Ok(ResponseEvent::ReasoningContentDelta { delta, .. }) => {
+                let cleaned = strip_role_prefix(&delta);
+                reasoning_delta_accumulator.push_str(cleaned);
+                let message = cleaned.to_string();
+                tx.send(AutoCoordinatorEvent::Thinking {
+                    delta: message,
+                    summary_index: None,
+                });
+            }
+            Ok(ResponseEvent::Completed {
+                token_usage: usage, ..
+            }) => {
+                token_usage = usage;
+                break;
+            }
+            Err(err) => return Err(err.into()),
+            _ => {}
+        }
+    }
+    if !reasoning_delta_accumulator.trim().is_empty()
+        && !response_items
+            .iter()
+            .any(|item| matches!(item, ResponseItem::Reasoning { .. }))
+    {
+        response_items.push(ResponseItem::Reasoning {
+            id: String::new(),
+            summary: Vec::new(),
+            content: Some(vec![ReasoningItemContent::ReasoningText {
+                text: reasoning_delta_accumulator.trim().to_string(),
+            }]),
+            encrypted_content: None,
+        });
+    }
+    Ok(RequestStreamResult {
+        output_text: out,
+        response_items,
+        token_usage,
+        model_slug: model_slug.to_string(),
+    })
+}
+
 fn build_user_turn_prompt(
     developer_intro: &str,
     primary_goal: &str,
@@ -2270,11 +3763,23 @@ fn should_retry_with_default_model(err: &anyhow::Error) -> bool {
 pub(crate) fn classify_model_error(error: &anyhow::Error) -> RetryDecision {
     if let Some(code_err) = find_in_chain::<CodexErr>(error) {
         match code_err {
-            CodexErr::Stream(message, _, _) => {
+            CodexErr::Stream(message, Some(retry_after), _) => {
+                return RetryDecision::RateLimited {
+                    wait_until: compute_rate_limit_wait(retry_after.delay),
+                    reason: format!("rate limited: {message}"),
+                };
+            }
+            CodexErr::Stream(message, None, _) => {
                 return RetryDecision::RetryAfterBackoff {
                     reason: format!("model stream error: {message}"),
                 };
             }
+            CodexErr::ProviderUnavailable(err) => {
+                return RetryDecision::RateLimited {
+                    wait_until: compute_rate_limit_wait(err.retry_in),
+                    reason: format!("provider unavailable: {err}"),
+                };
+            }
             CodexErr::Timeout => {
                 return RetryDecision::RetryAfterBackoff {
                     reason: "model request timed out".to_string(),
@@ -2476,24 +3981,42 @@ fn extract_reset_at(value: &serde_json::Value) -> Option<Instant> {
     Some(compute_rate_limit_wait(duration))
 }
 
+/// When the provider says the limit resets; `retry_with_backoff` adds the
+/// configured buffer on top.
 fn compute_rate_limit_wait(base: Duration) -> Instant {
-    let mut wait = if base > Duration::ZERO {
-        base
-    } else {
-        Duration::ZERO
-    };
-    wait += RATE_LIMIT_BUFFER;
-    wait += random_jitter(RATE_LIMIT_JITTER_MAX);
-    Instant::now() + wait
+    Instant::now() + base
+}
+
+/// Errors no retry fixes for the rest of the run: the coordinator account is
+/// out of quota or can no longer authenticate. Only a model from
+/// `auto_drive.model_fallbacks` may still get through.
+fn coordinator_unavailable(error: &anyhow::Error) -> bool {
+    match find_in_chain::<CodexErr>(error) {
+        Some(
+            CodexErr::QuotaExceeded
+            | CodexErr::UsageNotIncluded
+            | CodexErr::UsageLimitReached(_)
+            | CodexErr::AuthRefreshPermanent(_),
+        ) => true,
+        Some(CodexErr::UnexpectedStatus(err)) => matches!(
+            err.status,
+            StatusCode::UNAUTHORIZED | StatusCode::PAYMENT_REQUIRED | StatusCode::FORBIDDEN
+        ),
+        _ => false,
+    }
 }
 
-fn random_jitter(max: Duration) -> Duration {
-    if max.is_zero() {
-        return Duration::ZERO;
+/// Why a failed decision should move the coordinator to its next fallback
+/// model, or `None` when the error is not the model's fault.
+fn model_failover_reason(error: &anyhow::Error) -> Option<String> {
+    if coordinator_unavailable(error) {
+        return Some(format!("coordinator model unavailable: {error}"));
+    }
+    find_in_chain::<CodexErr>(error)?;
+    match classify_model_error(error) {
+        RetryDecision::Fatal(_) => Some(format!("fatal model error: {error}")),
+        RetryDecision::RetryAfterBackoff { .. } | RetryDecision::RateLimited { .. } => None,
     }
-    let mut rng = rand::rng();
-    let jitter = rng.random_range(0.0..max.as_secs_f64());
-    Duration::from_secs_f64(jitter)
 }
 
 fn find_in_chain<T: std::error::Error + 'static>(error: &anyhow::Error) -> Option<&T> {
@@ -2657,6 +4180,15 @@ fn parse_decision(raw: &str) -> Result<(ParsedCoordinatorDecision, Value)> {
     }
 }
 
+fn parse_turn_complexity(value: &str) -> Option<TurnComplexity> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "low" => Some(TurnComplexity::Low),
+        "medium" => Some(TurnComplexity::Medium),
+        "high" => Some(TurnComplexity::High),
+        _ => None,
+    }
+}
+
 fn parse_finish_status(finish_status: &str) -> Result<AutoCoordinatorStatus> {
     let normalized = finish_status.trim().to_ascii_lowercase();
     match normalized.as_str() {
@@ -2679,6 +4211,10 @@ fn convert_decision_new(
         prompt_sent_to_cli,
         agents: agent_payloads,
         goal,
+        cli_writes_files,
+        workstreams: workstream_payloads,
+        cli_complexity,
+        review,
     } = decision;
 
     let mut status_title = clean_optional(status_title);
@@ -2703,11 +4239,21 @@ fn convert_decision_new(
         (AutoCoordinatorStatus::Continue, Some(prompt)) => {
             let prompt = clean_required(&prompt, "prompt_sent_to_cli")?;
             ensure_cli_prompt_length(&prompt)?;
+            let mut workstreams = Vec::new();
+            for (index, payload) in workstream_payloads.into_iter().flatten().enumerate() {
+                workstreams.push(AutoTurnWorkstream {
+                    id: format!("ws-{}", index + 1),
+                    prompt: clean_required(&payload.prompt, "workstreams[*].prompt")?,
+                    context: clean_optional(payload.context),
+                });
+            }
 
             Some(CliAction {
                 prompt,
                 context: None,
                 suppress_ui_context: false,
+                workstreams,
+                complexity: cli_complexity.as_deref().and_then(parse_turn_complexity),
             })
         }
         (AutoCoordinatorStatus::Continue, None) => {
@@ -2730,6 +4276,7 @@ fn convert_decision_new(
                         context,
                         write,
                         models,
+                        timeout_seconds,
                     } = payload;
                     let prompt = clean_required(&prompt, "agents[*].prompt")?;
                     agent_actions.push(AgentAction {
@@ -2737,6 +4284,7 @@ fn convert_decision_new(
                         context: clean_optional(context),
                         write,
                         models: clean_models(models),
+                        timeout_seconds: timeout_seconds.filter(|secs| *secs > 0),
                     });
                 }
             }
@@ -2756,6 +4304,7 @@ fn convert_decision_new(
                         context,
                         write,
                         models,
+                        timeout_seconds,
                     } = payload;
                     let prompt = clean_required(&prompt, "agents.requests[*].prompt")?;
                     let models = clean_models(models).or_else(|| batch_models.clone());
@@ -2764,12 +4313,16 @@ fn convert_decision_new(
                         context: clean_optional(context),
                         write,
                         models,
+                        timeout_seconds: timeout_seconds.filter(|secs| *secs > 0),
                     });
                 }
             }
         }
     }
 
+    // A review is of a CLI turn; without one there is nothing to review.
+    let review = review.filter(|_| cli.is_some());
+
     Ok(ParsedCoordinatorDecision {
         status,
         status_title,
@@ -2778,6 +4331,8 @@ fn convert_decision_new(
         agents_timing,
         agents: agent_actions,
         goal,
+        cli_writes_files: cli_writes_files.unwrap_or(false),
+        review,
         response_items: Vec::new(),
         token_usage: None,
         model_slug: MODEL_SLUG.to_string(),
@@ -2807,6 +4362,8 @@ fn convert_decision_legacy(
             prompt: clean_required(&prompt, "cli_prompt")?,
             context,
             suppress_ui_context: false,
+            workstreams: Vec::new(),
+            complexity: None,
         }),
         (AutoCoordinatorStatus::Continue, None) => {
             return Err(anyhow!(
@@ -2817,6 +4374,8 @@ fn convert_decision_legacy(
             prompt: clean_required(&prompt, "cli_prompt")?,
             context,
             suppress_ui_context: false,
+            workstreams: Vec::new(),
+            complexity: None,
         }),
         (_, None) => None,
     };
@@ -2829,6 +4388,8 @@ fn convert_decision_legacy(
         agents_timing: None,
         agents: Vec::new(),
         goal,
+        cli_writes_files: false,
+        review: None,
         response_items: Vec::new(),
         token_usage: None,
         model_slug: MODEL_SLUG.to_string(),
@@ -2905,29 +4466,218 @@ fn ensure_cli_prompt_length(prompt: &str) -> Result<()> {
     Ok(())
 }
 
+/// Text shown when the first write turn is held for approval: the plan the
+/// coordinator reported plus the changes it is about to request.
+fn first_write_checkpoint_reason(
+    status_sent_to_user: Option<&str>,
+    cli: Option<&AutoTurnCliAction>,
+    agents: &[AutoTurnAgentsAction],
+) -> String {
+    let mut reason = "Approve the first write turn before Auto Drive continues.".to_string();
+    if let Some(plan) = status_sent_to_user {
+        reason.push_str(&format!("\nPlan: {plan}"));
+    }
+    if let Some(cli) = cli {
+        reason.push_str(&format!("\nCLI: {}", cli.prompt));
+    }
+    for agent in agents.iter().filter(|agent| agent.write) {
+        reason.push_str(&format!("\nWrite agent: {}", agent.prompt));
+    }
+    reason
+}
+
+/// What a decision's `goal` means for the goal being worked on.
+#[derive(Debug, PartialEq)]
+enum GoalChange {
+    Unchanged,
+    Adopt(String),
+    /// Held for the operator under `goal_changes = "confirm"`.
+    Confirm(String),
+    /// Ignored under `goal_changes = "reject"`.
+    Reject(String),
+}
+
+/// The first goal of a run derived from history is adopted whatever the
+/// policy; later ones that differ from `current` are amendments.
+fn classify_goal_change(
+    proposed: Option<&str>,
+    current: &str,
+    deriving_goal: bool,
+    policy: AutoDriveGoalChanges,
+) -> GoalChange {
+    let Some(goal) = proposed.map(str::trim).filter(|goal| !goal.is_empty()) else {
+        return GoalChange::Unchanged;
+    };
+    if deriving_goal {
+        return GoalChange::Adopt(goal.to_string());
+    }
+    if goal == current.trim() {
+        return GoalChange::Unchanged;
+    }
+    match policy {
+        AutoDriveGoalChanges::Auto => GoalChange::Adopt(goal.to_string()),
+        AutoDriveGoalChanges::Confirm => GoalChange::Confirm(goal.to_string()),
+        AutoDriveGoalChanges::Reject => GoalChange::Reject(goal.to_string()),
+    }
+}
+
+fn goal_change_checkpoint_reason(goal: &str) -> String {
+    format!(
+        "Approve the coordinator's amended goal before Auto Drive continues.\nNew goal: {goal}\nReply with guidance instead to keep the current goal."
+    )
+}
+
+/// Developer message that tells the coordinator which role it plays this
+/// turn and what the other roles left for it.
+fn role_turn_brief(turns: &mut RoleTurns) -> String {
+    let role = turns.current().clone();
+    let mut brief = format!(
+        "You are the {} coordinator. Coordinator roles take turns deciding the next CLI turn; this decision is yours.",
+        role.name
+    );
+    if let Some(prompt) = role.prompt.as_deref().map(str::trim)
+        && !prompt.is_empty()
+    {
+        brief.push_str(&format!("\n{prompt}"));
+    }
+    let notes: Vec<String> = turns
+        .inbox()
+        .iter()
+        .filter_map(|message| match message {
+            RoleMessage::Handoff {
+                from_role, note, ..
+            } => Some(format!("- {from_role}: {note}")),
+            RoleMessage::Guidance { content, .. } => Some(format!("- guidance: {content}")),
+            RoleMessage::Clarification {
+                from_role,
+                question,
+                ..
+            } => Some(format!("- {from_role} asks: {question}")),
+            _ => None,
+        })
+        .collect();
+    if !notes.is_empty() {
+        brief.push_str("\nNotes from the other coordinators:\n");
+        brief.push_str(&notes.join("\n"));
+    }
+    brief
+}
+
+/// What a role tells the next one when its turn ends.
+fn role_handoff_note(status_title: Option<&str>, cli: Option<&AutoTurnCliAction>) -> String {
+    match (status_title.map(str::trim), cli) {
+        (Some(title), Some(cli)) if !title.is_empty() => {
+            format!("{title}. Asked the CLI: {}", cli.prompt)
+        }
+        (_, Some(cli)) => format!("Asked the CLI: {}", cli.prompt),
+        (Some(title), None) if !title.is_empty() => title.to_string(),
+        _ => "Handed over without a CLI turn.".to_string(),
+    }
+}
+
+/// Decision event for a simple-loop step; the title marks the degradation in
+/// every UI.
+fn simple_loop_decision(
+    seq: u64,
+    step: SimpleLoopStep,
+    attachments: Vec<InputItem>,
+    budget_snapshot: BudgetSnapshot,
+) -> AutoCoordinatorEvent {
+    let (status, status_sent_to_user, cli) = match step {
+        SimpleLoopStep::Continue { prompt } => (
+            AutoCoordinatorStatus::Continue,
+            "Coordinator unavailable; sending the CLI a templated continue prompt.".to_string(),
+            Some(AutoTurnCliAction {
+                prompt,
+                context: None,
+                suppress_ui_context: false,
+                attachments,
+                workstreams: Vec::new(),
+                complexity: None,
+                model: None,
+            }),
+        ),
+        SimpleLoopStep::Finished { success, summary } => (
+            if success {
+                AutoCoordinatorStatus::Success
+            } else {
+                AutoCoordinatorStatus::Failed
+            },
+            summary,
+            None,
+        ),
+    };
+    AutoCoordinatorEvent::Decision {
+        seq,
+        status,
+        status_title: Some("Simple loop (coordinator unavailable)".to_string()),
+        status_sent_to_user: Some(status_sent_to_user),
+        goal: None,
+        cli,
+        agents_timing: None,
+        agents: Vec::new(),
+        transcript: Vec::new(),
+        budget_snapshot,
+    }
+}
+
 fn cli_action_to_event(action: &CliAction) -> AutoTurnCliAction {
     AutoTurnCliAction {
         prompt: action.prompt.clone(),
         context: action.context.clone(),
         suppress_ui_context: action.suppress_ui_context,
+        attachments: Vec::new(),
+        workstreams: action.workstreams.clone(),
+        complexity: action.complexity,
+        model: None,
     }
 }
 
-fn agent_action_to_event(action: &AgentAction) -> AutoTurnAgentsAction {
+/// Coordinator-facing note describing attachments queued for the next CLI turn.
+fn attachments_note(attachments: &[InputItem]) -> String {
+    let labels: Vec<String> = attachments
+        .iter()
+        .map(|item| match item {
+            InputItem::LocalImage { path } | InputItem::EphemeralImage { path, .. } => {
+                let name = path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| path.display().to_string());
+                format!("{name} (image)")
+            }
+            InputItem::Image { .. } => "inline image".to_string(),
+            InputItem::Text { .. } => "text snippet".to_string(),
+            _ => "attachment".to_string(),
+        })
+        .collect();
+    format!(
+        "Operator attached for the next CLI turn: {}. The CLI will receive them with its next prompt.",
+        labels.join(", ")
+    )
+}
+
+fn agent_action_to_event(
+    action: &AgentAction,
+    default_timeout_seconds: Option<u64>,
+) -> AutoTurnAgentsAction {
     AutoTurnAgentsAction {
         prompt: action.prompt.clone(),
         context: action.context.clone(),
         write: action.write.unwrap_or(false),
         write_requested: action.write,
         models: action.models.clone(),
+        timeout_seconds: action
+            .timeout_seconds
+            .or(default_timeout_seconds.filter(|secs| *secs > 0)),
     }
 }
 
 fn agent_action_to_event_with_write_guard(
     action: &AgentAction,
     allow_write: bool,
+    default_timeout_seconds: Option<u64>,
 ) -> AutoTurnAgentsAction {
-    let mut event = agent_action_to_event(action);
+    let mut event = agent_action_to_event(action, default_timeout_seconds);
     if !allow_write && event.write {
         event.write = false;
     }
@@ -3016,6 +4766,7 @@ fn emit_auto_drive_metrics(event_tx: &AutoCoordinatorEventSender, metrics: &Sess
         turn_count: metrics.turn_count(),
         duplicate_items: metrics.duplicate_items(),
         replay_updates: metrics.replay_updates(),
+        breakdown: metrics.attributed().to_vec(),
     };
     event_tx.send(event);
 }
@@ -3025,6 +4776,9 @@ enum CompactionResult {
     Completed { summary_text: Option<String> },
 }
 
+/// Compacts `conversation` once its projected size crosses `threshold` of
+/// the model's token limit, or unconditionally when `force` is set.
+#[allow(clippy::too_many_arguments)]
 fn maybe_compact(
     runtime: &tokio::runtime::Runtime,
     client: &ModelClient,
@@ -3034,26 +4788,34 @@ fn maybe_compact(
     prev_summary: Option<&str>,
     model_slug: &str,
     compact_prompt: &str,
+    threshold: f64,
+    force: bool,
 ) -> CompactionResult {
-    let transcript_tokens: u64 = conversation
-        .iter()
-        .map(|item| estimate_item_tokens(item) as u64)
-        .sum();
+    let tokenizer = Tokenizer::for_model(model_slug);
+    let transcript_tokens = estimate_transcript_tokens(conversation, tokenizer);
     let estimated_next = metrics.estimated_next_prompt_tokens();
     let message_count = conversation.len();
     let has_recorded_turns = metrics.turn_count() > 0;
 
-    if !should_compact(
-        model_slug,
-        transcript_tokens,
-        estimated_next,
-        message_count,
-        has_recorded_turns,
-    ) {
+    if !force
+        && !should_compact(
+            model_slug,
+            transcript_tokens,
+            estimated_next,
+            message_count,
+            has_recorded_turns,
+            threshold,
+        )
+    {
         return CompactionResult::Skipped;
     }
+    let estimate_after = |conversation: &[ResponseItem]| CompactionEstimate {
+        before_tokens: transcript_tokens,
+        after_tokens: estimate_transcript_tokens(conversation, tokenizer),
+        threshold_tokens: compaction_threshold_tokens(model_slug, threshold),
+    };
 
-    let Some(bounds) = compute_slice_bounds(conversation) else {
+    let Some(bounds) = compute_slice_bounds(conversation, tokenizer) else {
         return CompactionResult::Skipped;
     };
 
@@ -3071,6 +4833,7 @@ fn maybe_compact(
             event_tx.send(AutoCoordinatorEvent::CompactedHistory {
                 conversation: conversation.clone(),
                 show_notice: true,
+                estimate: Some(estimate_after(conversation)),
             });
             event_tx.send(AutoCoordinatorEvent::Thinking {
                 delta: format!(
@@ -3129,6 +4892,7 @@ fn maybe_compact(
     event_tx.send(AutoCoordinatorEvent::CompactedHistory {
         conversation: conversation.clone(),
         show_notice: true,
+        estimate: Some(estimate_after(conversation)),
     });
 
     let removed = slice.len();
@@ -3151,43 +4915,31 @@ fn maybe_compact(
 
 /// Determine if compaction should occur based on token usage.
 ///
-/// Uses 80% of the model's max_context as the threshold.
-/// Returns true if `session_total + estimated_next >= 0.8 * model_context_window`.
+/// Returns true if `transcript_tokens + estimated_next >= threshold * token_limit`,
+/// where the token limit is the model's auto-compact limit or its context
+/// window. `threshold` is `auto_drive.compact_threshold` (0.8 by default).
 ///
 /// # Arguments
 /// * `model_slug` - The model identifier to look up context limits
-/// * `session_total` - Total tokens used in the session so far
+/// * `transcript_tokens` - Estimated tokens in the current conversation
 /// * `estimated_next` - Estimated tokens for the next turn
 /// * `message_count` - Number of messages in the current conversation (fallback heuristic)
+/// * `threshold` - Share of the token limit that triggers compaction
 pub fn should_compact(
     model_slug: &str,
     transcript_tokens: u64,
     estimated_next: u64,
     message_count: usize,
     has_recorded_turns: bool,
+    threshold: f64,
 ) -> bool {
-    // Get model family to look up model info
-    let family = find_family_for_model(model_slug)
-        .unwrap_or_else(|| derive_default_model_family(model_slug));
-
-    if let Some(model_info) = get_model_info(&family) {
-        let token_limit = model_info
-            .auto_compact_token_limit
-            .and_then(|limit| (limit > 0).then_some(limit as u64))
-            .unwrap_or(model_info.context_window);
-        if token_limit > 0 {
-            let threshold = (token_limit as f64 * 0.8) as u64;
-            let projected_total = transcript_tokens.saturating_add(estimated_next);
-            if projected_total >= threshold {
-                return true;
-            }
-
-            // When we have an explicit token budget for the model, rely on it and
-            // skip the fallback message-count heuristic. This avoids runaway
-            // compaction loops when restarting Auto Drive with a large but still
-            // token-safe transcript.
-            return false;
-        }
+    if let Some(threshold_tokens) = compaction_threshold_tokens(model_slug, threshold) {
+        let projected_total = transcript_tokens.saturating_add(estimated_next);
+        // When we have an explicit token budget for the model, rely on it and
+        // skip the fallback message-count heuristic. This avoids runaway
+        // compaction loops when restarting Auto Drive with a large but still
+        // token-safe transcript.
+        return projected_total >= threshold_tokens;
     }
 
     if has_recorded_turns {
@@ -3197,6 +4949,24 @@ pub fn should_compact(
     fallback_message_limit(message_count)
 }
 
+/// Token count at which `should_compact` fires for `model_slug`; `None`
+/// when the model's token limit is unknown.
+pub fn compaction_threshold_tokens(model_slug: &str, threshold: f64) -> Option<u64> {
+    let family = find_family_for_model(model_slug)
+        .unwrap_or_else(|| derive_default_model_family(model_slug));
+    let model_info = get_model_info(&family)?;
+    let token_limit = model_info
+        .auto_compact_token_limit
+        .and_then(|limit| (limit > 0).then_some(limit as u64))
+        .unwrap_or(model_info.context_window);
+    let threshold = threshold.clamp(MIN_COMPACT_THRESHOLD, 1.0);
+    (token_limit > 0).then(|| (token_limit as f64 * threshold) as u64)
+}
+
+fn estimate_transcript_tokens(conversation: &[ResponseItem], tokenizer: Tokenizer) -> u64 {
+    tokenizer.count_items(conversation) as u64
+}
+
 fn fallback_message_limit(message_count: usize) -> bool {
     message_count >= MESSAGE_LIMIT_FALLBACK
 }
@@ -1,14 +1,12 @@
 use std::collections::VecDeque;
 
 use code_core::protocol::TokenUsage;
+use code_core::tokenizer::Tokenizer;
 use code_protocol::models::ContentItem;
 use code_protocol::models::ResponseItem;
 
 use crate::session_metrics::SessionMetrics;
 
-/// Token estimation: 4 bytes per token (same as core/truncate.rs)
-const BYTES_PER_TOKEN: usize = 4;
-
 /// Maintains the Auto Drive conversation transcript between coordinator turns.
 ///
 /// `converted` mirrors what we previously derived from UI history and is used
@@ -212,11 +210,12 @@ impl AutoDriveHistory {
 
     /// Perform compaction by selecting a slice after the goal message (first user message),
     /// finding the 50% token midpoint, advancing to the end of a turn boundary, and replacing
-    /// the slice with a compact summary item.
+    /// the slice with a compact summary item. Tokens are counted with `tokenizer`.
     ///
     /// Returns `Ok(true)` if compaction was performed, `Ok(false)` if skipped, or an error.
     pub fn compact_slice(
         &mut self,
+        tokenizer: Tokenizer,
         summarizer: impl FnOnce(&[ResponseItem]) -> String,
     ) -> Result<bool, String> {
         // Find the goal message (first user message)
@@ -237,7 +236,7 @@ impl AutoDriveHistory {
 
         // Calculate total tokens after the goal message
         let items_after_goal = &self.converted[goal_idx + 1..];
-        let total_tokens = estimate_tokens(items_after_goal);
+        let total_tokens = tokenizer.count_items(items_after_goal);
 
         // We need a reasonable amount of content to compact
         if total_tokens < 1000 {
@@ -250,7 +249,7 @@ impl AutoDriveHistory {
         let mut midpoint_idx = goal_idx + 1;
 
         for (i, item) in items_after_goal.iter().enumerate() {
-            accumulated_tokens += estimate_item_tokens(item);
+            accumulated_tokens += tokenizer.count_item(item);
             if accumulated_tokens >= target_tokens {
                 midpoint_idx = goal_idx + 1 + i;
                 break;
@@ -334,64 +333,6 @@ fn content_text(content: &NormalizedContent) -> Option<&str> {
     }
 }
 
-/// Estimate the total tokens for a slice of ResponseItems.
-fn estimate_tokens(items: &[ResponseItem]) -> usize {
-    items.iter().map(estimate_item_tokens).sum()
-}
-
-/// Estimate tokens for a single ResponseItem.
-/// Uses byte count divided by BYTES_PER_TOKEN (4) as fallback, same as core/truncate.rs.
-fn estimate_item_tokens(item: &ResponseItem) -> usize {
-    let byte_count =
-        match item {
-            ResponseItem::Message { content, .. } => {
-                content
-                    .iter()
-                    .map(|c| match c {
-                        ContentItem::InputText { text } | ContentItem::OutputText { text } => {
-                            text.len()
-                        }
-                        ContentItem::InputImage { image_url } => image_url.len() / 10, // images are less token-heavy
-                    })
-                    .sum()
-            }
-            ResponseItem::FunctionCall {
-                name, arguments, ..
-            } => name.len() + arguments.len(),
-            ResponseItem::FunctionCallOutput { output, .. } => output.content.len(),
-            ResponseItem::CustomToolCall { name, input, .. } => name.len() + input.len(),
-            ResponseItem::CustomToolCallOutput { output, .. } => output.len(),
-            ResponseItem::Reasoning {
-                summary, content, ..
-            } => {
-                summary
-                    .iter()
-                    .map(|s| match s {
-                        code_protocol::models::ReasoningItemReasoningSummary::SummaryText {
-                            text,
-                        } => text.len(),
-                    })
-                    .sum::<usize>()
-                    + content
-                        .as_ref()
-                        .map(|c| {
-                            c.iter()
-                                .map(|item| {
-                                    match item {
-                    code_protocol::models::ReasoningItemContent::ReasoningText { text } |
-                    code_protocol::models::ReasoningItemContent::Text { text } => text.len(),
-                }
-                                })
-                                .sum()
-                        })
-                        .unwrap_or(0)
-            }
-            // Catch-all for other types: Other, LocalShellCall, WebSearchCall, etc.
-            _ => 0,
-        };
-    byte_count.div_ceil(BYTES_PER_TOKEN)
-}
-
 /// Advance from the given index to the end of the current turn boundary.
 /// A turn boundary ends when we see a user message (the start of the next turn).
 fn advance_to_turn_boundary(items: &[ResponseItem], start_idx: usize) -> usize {
@@ -449,7 +390,7 @@ mod tests {
         let mut history = AutoDriveHistory::new();
         history.converted = vec![make_assistant_message("Hello")];
 
-        let result = history.compact_slice(|_| "SUMMARY".to_string());
+        let result = history.compact_slice(Tokenizer::Heuristic, |_| "SUMMARY".to_string());
         assert!(!result.unwrap_or(false)); // Should skip compaction
     }
 
@@ -461,7 +402,7 @@ mod tests {
             make_assistant_message("Response 1"),
         ];
 
-        let result = history.compact_slice(|_| "SUMMARY".to_string());
+        let result = history.compact_slice(Tokenizer::Heuristic, |_| "SUMMARY".to_string());
         assert!(!result.unwrap_or(false)); // Should skip compaction
     }
 
@@ -478,7 +419,9 @@ mod tests {
             make_user_message("Turn 3"),
         ];
 
-        let result = history.compact_slice(|items| format!("Compacted {} items", items.len()));
+        let result = history.compact_slice(Tokenizer::Heuristic, |items| {
+            format!("Compacted {} items", items.len())
+        });
 
         assert!(result.unwrap_or(false)); // Compaction should occur
 
@@ -557,7 +500,7 @@ mod tests {
             make_assistant_message("How are you?"), // ~12 chars / 4 = ~3 tokens
         ];
 
-        let tokens = estimate_tokens(&items);
+        let tokens = Tokenizer::Heuristic.count_items(&items);
         assert!(tokens > 0);
         assert!(tokens < 100); // Reasonable estimate
     }
@@ -2,11 +2,18 @@
 //!
 //! 提供 foreman 兼容的 Feature 结构体、加载/保存、验证更新，以及根据 git diff
 //! 粗粒度推导受影响特性。
+//!
+//! 另提供 `code exec --auto-backlog` 使用的目标清单（YAML）：按顺序执行的
+//! 目标、各目标的预算、向后续目标传递的摘要上下文，以及逐目标的状态报告。
 
+use std::collections::HashSet;
+use std::fmt::Write as _;
 use std::fs;
 use std::path::Path;
 use std::path::PathBuf;
 
+use anyhow::Context;
+use chrono::DateTime;
 use chrono::Utc;
 use serde::Deserialize;
 use serde::Serialize;
@@ -202,6 +209,285 @@ struct FeatureList {
     pub features: Vec<Feature>,
 }
 
+/// 摘要中每个目标最终消息保留的最大字符数。
+const CARRY_FORWARD_MESSAGE_CHARS: usize = 400;
+
+/// `code exec --auto-backlog` 的目标清单。
+///
+/// YAML 可以是目标列表，也可以是带 `goals` 的映射；列表项可以直接写目标文本：
+///
+/// ```yaml
+/// continue_on_failure: false
+/// goals:
+///   - Fix the flaky parser test
+///   - id: docs
+///     goal: Document the new flag
+///     max_tokens: 200000
+///     max_cost: 2.5
+///     max_duration: 1800
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct GoalBacklog {
+    pub goals: Vec<BacklogGoal>,
+    /// 某个目标失败后是否继续执行后续目标；默认停止并跳过其余目标。
+    pub continue_on_failure: bool,
+}
+
+/// 清单中的单个目标及其预算；未设置的预算沿用命令行或配置。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BacklogGoal {
+    /// 报告与检查点使用的标识；缺省为 `goal-<序号>`。
+    #[serde(default)]
+    pub id: String,
+    pub goal: String,
+    #[serde(default)]
+    pub max_tokens: Option<u64>,
+    #[serde(default)]
+    pub max_cost: Option<f64>,
+    /// 秒。
+    #[serde(default)]
+    pub max_duration: Option<u64>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum GoalBacklogFile {
+    List(Vec<BacklogGoalEntry>),
+    Map {
+        goals: Vec<BacklogGoalEntry>,
+        #[serde(default)]
+        continue_on_failure: bool,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum BacklogGoalEntry {
+    Text(String),
+    Goal(BacklogGoal),
+}
+
+impl GoalBacklog {
+    /// 从 YAML 文件加载并校验：至少一个目标、目标文本非空、标识不重复。
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let data = fs::read_to_string(path)
+            .with_context(|| format!("failed to read backlog {}", path.display()))?;
+        Self::parse(&data).with_context(|| format!("invalid backlog {}", path.display()))
+    }
+
+    pub fn parse(data: &str) -> anyhow::Result<Self> {
+        let (entries, continue_on_failure) = match serde_yaml::from_str(data)? {
+            GoalBacklogFile::List(goals) => (goals, false),
+            GoalBacklogFile::Map {
+                goals,
+                continue_on_failure,
+            } => (goals, continue_on_failure),
+        };
+        if entries.is_empty() {
+            anyhow::bail!("backlog has no goals");
+        }
+
+        let mut seen = HashSet::new();
+        let mut goals = Vec::with_capacity(entries.len());
+        for (index, entry) in entries.into_iter().enumerate() {
+            let mut goal = match entry {
+                BacklogGoalEntry::Text(goal) => BacklogGoal {
+                    goal,
+                    ..Default::default()
+                },
+                BacklogGoalEntry::Goal(goal) => goal,
+            };
+            goal.goal = goal.goal.trim().to_string();
+            goal.id = goal.id.trim().to_string();
+            if goal.id.is_empty() {
+                goal.id = format!("goal-{}", index + 1);
+            }
+            if goal.goal.is_empty() {
+                anyhow::bail!("goal {} is empty", goal.id);
+            }
+            if !seen.insert(goal.id.clone()) {
+                anyhow::bail!("duplicate goal id {}", goal.id);
+            }
+            goals.push(goal);
+        }
+        Ok(Self {
+            goals,
+            continue_on_failure,
+        })
+    }
+}
+
+/// 单个目标的执行结果。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GoalStatus {
+    Succeeded,
+    Failed,
+    /// 前序目标失败或触发运行上限，未执行。
+    Skipped,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GoalResult {
+    pub id: String,
+    pub goal: String,
+    pub status: GoalStatus,
+    /// 与 exec JSON 输出一致的失败原因。
+    #[serde(default)]
+    pub failure_reason: Option<String>,
+    #[serde(default)]
+    pub cli_turns: usize,
+    /// 协调器使用的 token 总数。
+    #[serde(default)]
+    pub coordinator_tokens: u64,
+    #[serde(default)]
+    pub final_message: Option<String>,
+    /// 该目标运行报告（JSON）的路径。
+    #[serde(default)]
+    pub report: Option<PathBuf>,
+}
+
+impl GoalResult {
+    pub fn skipped(goal: &BacklogGoal) -> Self {
+        Self {
+            id: goal.id.clone(),
+            goal: goal.goal.clone(),
+            status: GoalStatus::Skipped,
+            failure_reason: None,
+            cli_turns: 0,
+            coordinator_tokens: 0,
+            final_message: None,
+            report: None,
+        }
+    }
+}
+
+/// 传给下一个目标协调器的摘要：已完成目标的状态与最终消息（截断）。
+/// 尚无已执行目标时返回 `None`。
+pub fn carry_forward_context(results: &[GoalResult]) -> Option<String> {
+    let finished: Vec<&GoalResult> = results
+        .iter()
+        .filter(|result| result.status != GoalStatus::Skipped)
+        .collect();
+    if finished.is_empty() {
+        return None;
+    }
+    let mut out = String::from(
+        "Earlier goals from this backlog already ran in this workspace. Build on their results:",
+    );
+    for result in finished {
+        let status = match result.status {
+            GoalStatus::Succeeded => "succeeded",
+            GoalStatus::Failed => "failed",
+            GoalStatus::Skipped => "skipped",
+        };
+        let _ = write!(
+            out,
+            "\n- {} ({status}): {}",
+            result.id,
+            first_line(&result.goal)
+        );
+        if let Some(message) = result
+            .final_message
+            .as_deref()
+            .map(str::trim)
+            .filter(|message| !message.is_empty())
+        {
+            let summary: String = message.chars().take(CARRY_FORWARD_MESSAGE_CHARS).collect();
+            let ellipsis = if summary.len() < message.len() {
+                "…"
+            } else {
+                ""
+            };
+            let _ = write!(out, "\n  Outcome: {}{ellipsis}", summary.replace('\n', " "));
+        }
+    }
+    Some(out)
+}
+
+/// 整个清单的逐目标状态报告。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BacklogReport {
+    pub backlog: PathBuf,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub goals: Vec<GoalResult>,
+}
+
+impl BacklogReport {
+    pub fn new(backlog: impl Into<PathBuf>) -> Self {
+        Self {
+            backlog: backlog.into(),
+            started_at: Utc::now(),
+            finished_at: None,
+            goals: Vec::new(),
+        }
+    }
+
+    pub fn success(&self) -> bool {
+        self.goals
+            .iter()
+            .all(|goal| goal.status == GoalStatus::Succeeded)
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "# Auto Drive backlog {}", self.backlog.display());
+        let _ = writeln!(out);
+        let _ = writeln!(out, "- Started: {}", self.started_at.to_rfc3339());
+        if let Some(finished_at) = self.finished_at {
+            let _ = writeln!(out, "- Finished: {}", finished_at.to_rfc3339());
+        }
+        let _ = writeln!(out);
+        let _ = writeln!(
+            out,
+            "| Goal | Status | CLI turns | Coordinator tokens | Report |"
+        );
+        let _ = writeln!(out, "| --- | --- | ---: | ---: | --- |");
+        for goal in &self.goals {
+            let status = match (goal.status, goal.failure_reason.as_deref()) {
+                (GoalStatus::Failed, Some(reason)) => format!("failed ({reason})"),
+                (GoalStatus::Failed, None) => "failed".to_string(),
+                (GoalStatus::Succeeded, _) => "succeeded".to_string(),
+                (GoalStatus::Skipped, _) => "skipped".to_string(),
+            };
+            let report = goal
+                .report
+                .as_ref()
+                .map(|path| path.display().to_string())
+                .unwrap_or_default();
+            let _ = writeln!(
+                out,
+                "| {}: {} | {status} | {} | {} | {report} |",
+                goal.id,
+                first_line(&goal.goal),
+                goal.cli_turns,
+                goal.coordinator_tokens
+            );
+        }
+        out
+    }
+
+    /// 写入 `backlog-<时间戳>.json` 与同名 `.md`，返回两者路径。
+    pub fn write(&mut self, dir: &Path) -> anyhow::Result<(PathBuf, PathBuf)> {
+        self.finished_at = Some(Utc::now());
+        fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create report dir {}", dir.display()))?;
+        let stem = format!("backlog-{}", self.started_at.format("%Y%m%dT%H%M%SZ"));
+        let json_path = dir.join(format!("{stem}.json"));
+        let markdown_path = dir.join(format!("{stem}.md"));
+        fs::write(&json_path, serde_json::to_vec_pretty(self)?)
+            .with_context(|| format!("failed to write {}", json_path.display()))?;
+        fs::write(&markdown_path, self.to_markdown())
+            .with_context(|| format!("failed to write {}", markdown_path.display()))?;
+        Ok((json_path, markdown_path))
+    }
+}
+
+fn first_line(text: &str) -> &str {
+    text.trim().lines().next().unwrap_or_default()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -291,4 +577,65 @@ mod tests {
         assert!(ids.contains(&"F-1".to_string()));
         assert!(ids.contains(&"F-2".to_string()));
     }
+
+    #[test]
+    fn goal_backlog_accepts_lists_and_maps_and_rejects_duplicates() {
+        let backlog = GoalBacklog::parse(
+            "continue_on_failure: true\ngoals:\n  - Fix the parser\n  - id: docs\n    goal: Document it\n    max_tokens: 1000\n",
+        )
+        .unwrap();
+        assert_eq!(
+            backlog,
+            GoalBacklog {
+                goals: vec![
+                    BacklogGoal {
+                        id: "goal-1".to_string(),
+                        goal: "Fix the parser".to_string(),
+                        ..Default::default()
+                    },
+                    BacklogGoal {
+                        id: "docs".to_string(),
+                        goal: "Document it".to_string(),
+                        max_tokens: Some(1000),
+                        ..Default::default()
+                    },
+                ],
+                continue_on_failure: true,
+            }
+        );
+        let list = GoalBacklog::parse("- one\n- two\n").unwrap();
+        assert_eq!(list.goals.len(), 2);
+        assert!(!list.continue_on_failure);
+
+        assert!(GoalBacklog::parse("goals: []\n").is_err());
+        assert!(GoalBacklog::parse("- id: a\n  goal: x\n- id: a\n  goal: y\n").is_err());
+    }
+
+    #[test]
+    fn carry_forward_summarizes_finished_goals() {
+        let mut failed = GoalResult::skipped(&BacklogGoal {
+            id: "b".to_string(),
+            goal: "Second\nwith detail".to_string(),
+            ..Default::default()
+        });
+        failed.status = GoalStatus::Failed;
+        let mut succeeded = GoalResult::skipped(&BacklogGoal {
+            id: "a".to_string(),
+            goal: "First".to_string(),
+            ..Default::default()
+        });
+        succeeded.status = GoalStatus::Succeeded;
+        succeeded.final_message = Some("Done.\nAll green.".to_string());
+        let skipped = GoalResult::skipped(&BacklogGoal {
+            id: "c".to_string(),
+            goal: "Third".to_string(),
+            ..Default::default()
+        });
+
+        assert_eq!(carry_forward_context(&[skipped.clone()]), None);
+        assert_eq!(
+            carry_forward_context(&[succeeded, failed, skipped]).unwrap(),
+            "Earlier goals from this backlog already ran in this workspace. Build on their results:\n- a (succeeded): First\n  Outcome: Done. All green.\n- b (failed): Second"
+        );
+    }
 }
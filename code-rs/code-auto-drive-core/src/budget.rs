@@ -6,6 +6,9 @@
 use std::time::Duration;
 use std::time::Instant;
 
+use serde::Deserialize;
+use serde::Serialize;
+
 /// Configuration for budget limits.
 #[derive(Clone, Debug, Default)]
 pub struct BudgetConfig {
@@ -15,6 +18,8 @@ pub struct BudgetConfig {
     pub turn_limit: Option<u32>,
     /// Maximum duration allowed.
     pub duration_limit: Option<Duration>,
+    /// Maximum estimated spend in US dollars.
+    pub cost_limit_usd: Option<f64>,
 }
 
 /// Current resource usage statistics.
@@ -28,6 +33,20 @@ pub struct ResourceUsage {
     pub elapsed: Duration,
 }
 
+/// Point-in-time budget consumption attached to coordinator decisions.
+///
+/// `*_remaining` fields are `None` when the corresponding limit is not
+/// configured.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BudgetSnapshot {
+    pub tokens_used: u64,
+    pub tokens_remaining: Option<u64>,
+    pub turns_used: u32,
+    pub turns_remaining: Option<u32>,
+    pub elapsed_seconds: u64,
+    pub remaining_seconds: Option<u64>,
+}
+
 /// Alerts emitted when budget thresholds are reached.
 #[derive(Clone, Debug)]
 pub enum BudgetAlert {
@@ -43,6 +62,8 @@ pub enum BudgetAlert {
     TurnLimitReached { count: u32, limit: u32 },
     /// Duration limit has been exceeded.
     DurationExceeded { elapsed: Duration, limit: Duration },
+    /// Estimated spend has reached the cost limit.
+    CostExceeded { spent_usd: f64, limit_usd: f64 },
     /// Session pool queue is approaching backpressure threshold.
     BackpressureWarning { queue_size: i32, limit: i32 },
     /// Session pool queue exceeded backpressure threshold.
@@ -54,6 +75,10 @@ pub struct BudgetController {
     config: BudgetConfig,
     current_usage: ResourceUsage,
     started_at: Option<Instant>,
+    /// Cumulative tokens reported by the CLI agent being driven.
+    cli_tokens: u64,
+    /// Estimated spend of the whole run, when the caller can price it.
+    cost_usd: Option<f64>,
 }
 
 impl BudgetController {
@@ -63,6 +88,8 @@ impl BudgetController {
             config: BudgetConfig::default(),
             current_usage: ResourceUsage::default(),
             started_at: None,
+            cli_tokens: 0,
+            cost_usd: None,
         }
     }
 
@@ -87,24 +114,39 @@ impl BudgetController {
         }
     }
 
-    /// Checks budget status and returns any alerts.
+    /// Records the CLI agent's cumulative token count and the run's
+    /// estimated spend so far. Both replace the previously reported values.
+    pub fn record_cli_usage(&mut self, tokens: u64, cost_usd: Option<f64>) {
+        self.cli_tokens = tokens;
+        self.cost_usd = cost_usd;
+    }
+
+    /// Tokens spent by the coordinator and the CLI agent together.
+    fn tokens_used(&self) -> u64 {
+        self.current_usage.total_tokens + self.cli_tokens
+    }
+
+    /// Checks budget status and returns any alerts. Exceeded limits take
+    /// precedence over the token warning.
     pub fn check_budget(&self) -> Option<BudgetAlert> {
         // Check token budget
         if let Some(limit) = self.config.token_budget {
-            let used = self.current_usage.total_tokens;
-            let percentage = used as f32 / limit as f32 * 100.0;
-
+            let used = self.tokens_used();
             if used >= limit {
                 return Some(BudgetAlert::TokenExceeded { used, limit });
-            } else if percentage >= 80.0 {
-                return Some(BudgetAlert::TokenWarning {
-                    used,
-                    limit,
-                    percentage,
-                });
             }
         }
 
+        // Check cost limit
+        if let (Some(limit_usd), Some(spent_usd)) = (self.config.cost_limit_usd, self.cost_usd)
+            && spent_usd >= limit_usd
+        {
+            return Some(BudgetAlert::CostExceeded {
+                spent_usd,
+                limit_usd,
+            });
+        }
+
         // Check turn limit
         if let Some(limit) = self.config.turn_limit
             && self.current_usage.turns_completed >= limit
@@ -123,6 +165,18 @@ impl BudgetController {
             }
         }
 
+        if let Some(limit) = self.config.token_budget {
+            let used = self.tokens_used();
+            let percentage = used as f32 / limit as f32 * 100.0;
+            if percentage >= 80.0 {
+                return Some(BudgetAlert::TokenWarning {
+                    used,
+                    limit,
+                    percentage,
+                });
+            }
+        }
+
         None
     }
 
@@ -131,7 +185,7 @@ impl BudgetController {
         let remaining_tokens = self
             .config
             .token_budget
-            .map(|limit| limit.saturating_sub(self.current_usage.total_tokens))
+            .map(|limit| limit.saturating_sub(self.tokens_used()))
             .unwrap_or(u64::MAX);
 
         let remaining_turns = self
@@ -163,6 +217,7 @@ impl BudgetController {
             Some(BudgetAlert::TokenExceeded { .. })
                 | Some(BudgetAlert::TurnLimitReached { .. })
                 | Some(BudgetAlert::DurationExceeded { .. })
+                | Some(BudgetAlert::CostExceeded { .. })
         )
     }
 
@@ -171,10 +226,36 @@ impl BudgetController {
         &self.current_usage
     }
 
+    /// Returns used and remaining budget as of now.
+    pub fn snapshot(&self) -> BudgetSnapshot {
+        let used = &self.current_usage;
+        let elapsed = self.started_at.map(|s| s.elapsed()).unwrap_or_default();
+        let tokens_used = self.tokens_used();
+        BudgetSnapshot {
+            tokens_used,
+            tokens_remaining: self
+                .config
+                .token_budget
+                .map(|limit| limit.saturating_sub(tokens_used)),
+            turns_used: used.turns_completed,
+            turns_remaining: self
+                .config
+                .turn_limit
+                .map(|limit| limit.saturating_sub(used.turns_completed)),
+            elapsed_seconds: elapsed.as_secs(),
+            remaining_seconds: self
+                .config
+                .duration_limit
+                .map(|limit| limit.saturating_sub(elapsed).as_secs()),
+        }
+    }
+
     /// Resets the controller state.
     pub fn reset(&mut self) {
         self.current_usage = ResourceUsage::default();
         self.started_at = None;
+        self.cli_tokens = 0;
+        self.cost_usd = None;
     }
 }
 
@@ -225,6 +306,31 @@ mod tests {
         assert!(controller.should_pause());
     }
 
+    #[test]
+    fn test_cli_usage_and_cost_count_toward_limits() {
+        let mut controller = BudgetController::new();
+        controller.configure(BudgetConfig {
+            token_budget: Some(1000),
+            cost_limit_usd: Some(0.5),
+            ..Default::default()
+        });
+
+        controller.record_usage(100, true);
+        controller.record_cli_usage(750, Some(0.2));
+        assert!(matches!(
+            controller.check_budget(),
+            Some(BudgetAlert::TokenWarning { used: 850, .. })
+        ));
+
+        controller.record_cli_usage(780, Some(0.5));
+        assert!(matches!(
+            controller.check_budget(),
+            Some(BudgetAlert::CostExceeded { .. })
+        ));
+        assert!(controller.should_pause());
+        assert_eq!(controller.snapshot().tokens_remaining, Some(120));
+    }
+
     #[test]
     fn test_turn_limit() {
         let mut controller = BudgetController::new();
@@ -259,6 +365,25 @@ mod tests {
         assert_eq!(remaining.turns_completed, 8);
     }
 
+    #[test]
+    fn test_snapshot_reports_used_and_remaining() {
+        let mut controller = BudgetController::new();
+        controller.configure(BudgetConfig {
+            token_budget: Some(1000),
+            turn_limit: Some(4),
+            ..Default::default()
+        });
+
+        controller.record_usage(300, true);
+
+        let snapshot = controller.snapshot();
+        assert_eq!(snapshot.tokens_used, 300);
+        assert_eq!(snapshot.tokens_remaining, Some(700));
+        assert_eq!(snapshot.turns_used, 1);
+        assert_eq!(snapshot.turns_remaining, Some(3));
+        assert_eq!(snapshot.remaining_seconds, None);
+    }
+
     #[test]
     fn test_reset() {
         let mut controller = BudgetController::new();
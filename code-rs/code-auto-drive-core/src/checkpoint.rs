@@ -3,6 +3,7 @@
 //! This module provides functionality to save and restore Auto Drive sessions,
 //! enabling recovery from interruptions without losing progress.
 
+use std::path::Path;
 use std::path::PathBuf;
 use std::time::Duration;
 
@@ -18,6 +19,13 @@ use code_protocol::models::ResponseItem;
 
 use crate::AutoRunPhase;
 
+const CHECKPOINT_SUBDIR: &str = "auto_drive/checkpoints";
+
+/// Returns the default checkpoint directory under `code_home`.
+pub fn default_checkpoint_dir(code_home: &Path) -> PathBuf {
+    code_home.join(CHECKPOINT_SUBDIR)
+}
+
 /// Token usage statistics for a checkpoint.
 #[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
 pub struct TokenUsage {
@@ -77,6 +85,7 @@ pub struct CheckpointSummary {
     pub session_id: String,
     pub goal_preview: String,
     pub turns_completed: usize,
+    pub phase: CheckpointPhase,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -200,6 +209,7 @@ impl CheckpointManager {
                     session_id: checkpoint.session_id,
                     goal_preview: checkpoint.goal.chars().take(100).collect(),
                     turns_completed: checkpoint.turns_completed,
+                    phase: checkpoint.phase,
                     created_at: checkpoint.created_at,
                     updated_at: checkpoint.updated_at,
                 });
@@ -212,6 +222,19 @@ impl CheckpointManager {
         Ok(summaries)
     }
 
+    /// Restores the most recently updated checkpoint of a run that has not
+    /// completed.
+    pub fn restore_latest(&self) -> Result<Option<AutoDriveCheckpoint>> {
+        match self
+            .list_recoverable()?
+            .into_iter()
+            .find(|summary| summary.phase != CheckpointPhase::Completed)
+        {
+            Some(summary) => self.restore(&summary.session_id),
+            None => Ok(None),
+        }
+    }
+
     /// Cleans up checkpoints older than the specified age.
     pub fn cleanup(&self, max_age: Duration) -> Result<usize> {
         let mut removed = 0;
@@ -344,4 +367,29 @@ mod tests {
         let summaries = manager.list_recoverable().unwrap();
         assert_eq!(summaries.len(), 2);
     }
+
+    #[test]
+    fn test_restore_latest_skips_completed() {
+        let (mut manager, _temp) = create_test_manager();
+
+        let mut interrupted = manager.create("Goal 1", "session-1").unwrap();
+        manager
+            .update(
+                &mut interrupted,
+                Vec::new(),
+                3,
+                TokenUsage::default(),
+                &AutoRunPhase::Active,
+            )
+            .unwrap();
+
+        let mut completed = manager.create("Goal 2", "session-2").unwrap();
+        completed.phase = CheckpointPhase::Completed;
+        completed.updated_at = interrupted.updated_at + chrono::Duration::seconds(1);
+        manager.save(&completed).unwrap();
+
+        let latest = manager.restore_latest().unwrap().unwrap();
+        assert_eq!(latest.session_id, "session-1");
+        assert_eq!(latest.turns_completed, 3);
+    }
 }
@@ -0,0 +1,577 @@
+//! Commit message and pull request body generation for Auto Drive runs.
+//!
+//! A [`ChangeSummary`] collects the goal, the coordinator's key decisions
+//! (the prompts it handed to the CLI agent), the files that were changed, and
+//! the test commands that ran. It is built from an [`AutoDriveHistory`]
+//! snapshot and can be topped up from session events when the history only
+//! carries the coordinator's side of the conversation, as in `code exec`.
+//! [`generate_commit_message`] renders it as a conventional-commit message
+//! and a Markdown PR body without another model call.
+//!
+//! [`AutoDriveHistory`]: crate::AutoDriveHistory
+
+use std::path::Path;
+
+use code_core::protocol::EventMsg;
+use code_core::protocol::ExecCommandBeginEvent;
+use code_core::protocol::FileChange;
+use code_core::protocol::PatchApplyBeginEvent;
+use code_protocol::models::ContentItem;
+use code_protocol::models::LocalShellAction;
+use code_protocol::models::ResponseItem;
+
+/// Longest commit subject line, including the type and scope.
+const MAX_SUBJECT_CHARS: usize = 72;
+/// Longest single decision line kept in the message.
+const MAX_DECISION_CHARS: usize = 120;
+
+/// Command prefixes that count as running tests.
+const TEST_COMMANDS: &[&str] = &[
+    "cargo test",
+    "cargo nextest",
+    "cargo insta test",
+    "go test",
+    "pytest",
+    "python -m pytest",
+    "python3 -m pytest",
+    "npm test",
+    "npm run test",
+    "pnpm test",
+    "pnpm run test",
+    "yarn test",
+    "bun test",
+    "npx jest",
+    "npx vitest",
+    "jest",
+    "vitest",
+    "just test",
+    "make test",
+    "make check",
+    "mvn test",
+    "gradle test",
+    "./gradlew test",
+    "dotnet test",
+    "bundle exec rspec",
+    "rspec",
+];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChangeSummary {
+    pub goal: String,
+    pub decisions: Vec<String>,
+    pub files_changed: Vec<String>,
+    pub tests_run: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitMessage {
+    /// `type(scope): description`.
+    pub subject: String,
+    /// Commit body without the subject; empty when there is nothing to add.
+    pub body: String,
+    /// Markdown description for a pull request.
+    pub pr_body: String,
+}
+
+impl CommitMessage {
+    /// Subject and body joined the way `git commit -F` expects.
+    pub fn full_message(&self) -> String {
+        if self.body.is_empty() {
+            self.subject.clone()
+        } else {
+            format!("{}\n\n{}", self.subject, self.body)
+        }
+    }
+}
+
+impl ChangeSummary {
+    pub fn new(goal: &str) -> Self {
+        Self {
+            goal: goal.trim().to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// Collects decisions, patched files, and test commands from a raw
+    /// history snapshot.
+    pub fn from_history(goal: &str, history: &[ResponseItem]) -> Self {
+        let mut summary = Self::new(goal);
+        for item in history {
+            match item {
+                ResponseItem::Message { role, content, .. } if role == "user" => {
+                    let text = content.iter().find_map(|content| match content {
+                        ContentItem::InputText { text } => Some(text.as_str()),
+                        _ => None,
+                    });
+                    if let Some(text) = text {
+                        summary.record_decision(text);
+                    }
+                }
+                ResponseItem::FunctionCall {
+                    name, arguments, ..
+                } => match name.as_str() {
+                    "apply_patch" => {
+                        let patch = serde_json::from_str::<serde_json::Value>(arguments)
+                            .ok()
+                            .and_then(|args| args.get("input")?.as_str().map(str::to_string))
+                            .unwrap_or_else(|| arguments.clone());
+                        summary.record_patch(&patch);
+                    }
+                    "shell" | "container.exec" => {
+                        if let Some(command) = shell_command_from_arguments(arguments) {
+                            summary.record_command(&command);
+                        }
+                    }
+                    _ => {}
+                },
+                ResponseItem::CustomToolCall { name, input, .. } if name == "apply_patch" => {
+                    summary.record_patch(input);
+                }
+                ResponseItem::LocalShellCall {
+                    action: LocalShellAction::Exec(action),
+                    ..
+                } => summary.record_command(&action.command.join(" ")),
+                _ => {}
+            }
+        }
+        summary
+    }
+
+    /// Records patches and commands from a session event. Paths under `cwd`
+    /// are kept relative to it.
+    pub fn observe(&mut self, msg: &EventMsg, cwd: &Path) {
+        match msg {
+            EventMsg::PatchApplyBegin(PatchApplyBeginEvent { changes, .. }) => {
+                let mut paths: Vec<&Path> = changes
+                    .iter()
+                    .flat_map(|(path, change)| match change {
+                        FileChange::Update {
+                            move_path: Some(dest),
+                            ..
+                        } => vec![path.as_path(), dest.as_path()],
+                        _ => vec![path.as_path()],
+                    })
+                    .collect();
+                paths.sort();
+                for path in paths {
+                    let relative = path.strip_prefix(cwd).unwrap_or(path);
+                    self.record_file(&relative.to_string_lossy());
+                }
+            }
+            EventMsg::ExecCommandBegin(ExecCommandBeginEvent { command, .. }) => {
+                self.record_command(&command.join(" "));
+            }
+            _ => {}
+        }
+    }
+
+    /// Keeps the first line of a CLI prompt as a decision.
+    pub fn record_decision(&mut self, prompt: &str) {
+        let Some(line) = prompt
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !line.starts_with('<'))
+        else {
+            return;
+        };
+        let line = truncate_chars(line, MAX_DECISION_CHARS);
+        if !self.decisions.contains(&line) {
+            self.decisions.push(line);
+        }
+    }
+
+    /// Records the files named in an `apply_patch` envelope.
+    pub fn record_patch(&mut self, patch: &str) {
+        for line in patch.lines() {
+            let path = [
+                "*** Add File: ",
+                "*** Update File: ",
+                "*** Delete File: ",
+                "*** Move to: ",
+            ]
+            .iter()
+            .find_map(|marker| line.strip_prefix(marker));
+            if let Some(path) = path {
+                self.record_file(path.trim());
+            }
+        }
+    }
+
+    /// Records `command` when it runs tests.
+    pub fn record_command(&mut self, command: &str) {
+        let command = unwrap_shell(command);
+        if is_test_command(command) && !self.tests_run.iter().any(|seen| seen == command) {
+            self.tests_run.push(command.to_string());
+        }
+    }
+
+    /// Adds what `other` recorded that this summary does not have yet.
+    pub fn merge(&mut self, other: ChangeSummary) {
+        for decision in other.decisions {
+            if !self.decisions.contains(&decision) {
+                self.decisions.push(decision);
+            }
+        }
+        for path in other.files_changed {
+            self.record_file(&path);
+        }
+        for command in other.tests_run {
+            self.record_command(&command);
+        }
+    }
+
+    fn record_file(&mut self, path: &str) {
+        if !path.is_empty() && !self.files_changed.iter().any(|seen| seen == path) {
+            self.files_changed.push(path.to_string());
+        }
+    }
+}
+
+/// Renders `summary` as a conventional-commit message and a PR body.
+pub fn generate_commit_message(summary: &ChangeSummary) -> CommitMessage {
+    let kind = commit_type(summary);
+    let prefix = match common_scope(&summary.files_changed) {
+        Some(scope) => format!("{kind}({scope}): "),
+        None => format!("{kind}: "),
+    };
+    let full_description = describe_goal(&summary.goal);
+    let description = truncate_words(
+        &full_description,
+        MAX_SUBJECT_CHARS.saturating_sub(prefix.chars().count()),
+    );
+    let subject = format!("{prefix}{description}");
+
+    let mut sections: Vec<String> = Vec::new();
+    // Keep the goal itself when the subject could not carry all of it.
+    if summary.goal.trim().lines().count() > 1 || description != full_description {
+        sections.push(summary.goal.clone());
+    }
+    if !summary.decisions.is_empty() {
+        sections.push(bullets("Decisions:", &summary.decisions, str::to_string));
+    }
+    if !summary.files_changed.is_empty() {
+        sections.push(bullets(
+            "Files changed:",
+            &summary.files_changed,
+            str::to_string,
+        ));
+    }
+    if !summary.tests_run.is_empty() {
+        sections.push(bullets("Tests run:", &summary.tests_run, str::to_string));
+    }
+    let body = sections.join("\n\n");
+
+    let mut pr_body = format!("## Summary\n\n{}\n", summary.goal);
+    if !summary.decisions.is_empty() {
+        pr_body.push('\n');
+        pr_body.push_str(&bullets(
+            "## Key decisions\n",
+            &summary.decisions,
+            str::to_string,
+        ));
+        pr_body.push('\n');
+    }
+    if !summary.files_changed.is_empty() {
+        pr_body.push('\n');
+        pr_body.push_str(&bullets(
+            "## Files changed\n",
+            &summary.files_changed,
+            |path| format!("`{path}`"),
+        ));
+        pr_body.push('\n');
+    }
+    pr_body.push_str("\n## Testing\n\n");
+    if summary.tests_run.is_empty() {
+        pr_body.push_str("No test commands were run.\n");
+    } else {
+        for command in &summary.tests_run {
+            pr_body.push_str(&format!("- `{command}`\n"));
+        }
+    }
+
+    CommitMessage {
+        subject,
+        body,
+        pr_body,
+    }
+}
+
+fn bullets(heading: &str, items: &[String], render: impl Fn(&str) -> String) -> String {
+    let mut out = heading.to_string();
+    for item in items {
+        out.push_str(&format!("\n- {}", render(item)));
+    }
+    out
+}
+
+fn commit_type(summary: &ChangeSummary) -> &'static str {
+    let files = &summary.files_changed;
+    if !files.is_empty() && files.iter().all(|path| is_doc_path(path)) {
+        return "docs";
+    }
+    if !files.is_empty() && files.iter().all(|path| is_test_path(path)) {
+        return "test";
+    }
+    let goal = summary.goal.to_ascii_lowercase();
+    let first_word = goal.split_whitespace().next().unwrap_or_default();
+    match first_word {
+        "fix" | "fixes" | "fixed" | "repair" | "resolve" => "fix",
+        "refactor" | "restructure" | "rename" | "simplify" | "clean" | "cleanup" => "refactor",
+        "document" | "docs" => "docs",
+        "test" | "tests" => "test",
+        "speed" | "optimize" | "optimise" => "perf",
+        _ if goal.contains("bug") || goal.contains("crash") || goal.contains("broken") => "fix",
+        _ => "feat",
+    }
+}
+
+fn is_doc_path(path: &str) -> bool {
+    let lower = path.to_ascii_lowercase();
+    lower.ends_with(".md") || lower.ends_with(".txt") || lower.starts_with("docs/")
+}
+
+fn is_test_path(path: &str) -> bool {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    path.split('/')
+        .any(|part| part == "tests" || part == "__tests__")
+        || name.starts_with("test_")
+        || name.contains("_test.")
+        || name.contains(".test.")
+        || name.contains(".spec.")
+}
+
+/// Deepest directory shared by every file, skipping generic names like `src`.
+fn common_scope(files: &[String]) -> Option<String> {
+    let mut dirs = files.iter().map(|path| {
+        let mut parts: Vec<&str> = path.split('/').filter(|part| !part.is_empty()).collect();
+        parts.pop();
+        parts
+    });
+    let mut common = dirs.next()?;
+    for parts in dirs {
+        let shared = common
+            .iter()
+            .zip(&parts)
+            .take_while(|(left, right)| left == right)
+            .count();
+        common.truncate(shared);
+    }
+    common
+        .into_iter()
+        .rev()
+        .find(|part| !matches!(*part, "src" | "lib" | "tests" | "test" | "." | ".."))
+        .map(str::to_string)
+}
+
+/// First line of the goal as an imperative, lower-case description.
+fn describe_goal(goal: &str) -> String {
+    let line = goal
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .unwrap_or("update project");
+    let line = line.trim_end_matches(['.', '!', ':']);
+    let mut chars = line.chars();
+    match chars.next() {
+        Some(first) => {
+            let rest = chars.as_str();
+            let keep_case = rest.chars().next().is_some_and(char::is_uppercase);
+            if keep_case {
+                line.to_string()
+            } else {
+                format!("{}{rest}", first.to_lowercase())
+            }
+        }
+        None => "update project".to_string(),
+    }
+}
+
+/// Strips a `bash -lc '...'` style wrapper.
+fn unwrap_shell(command: &str) -> &str {
+    let command = command.trim();
+    for wrapper in ["bash -lc ", "bash -c ", "sh -c ", "zsh -lc ", "zsh -c "] {
+        if let Some(inner) = command.strip_prefix(wrapper) {
+            return inner.trim().trim_matches(['\'', '"']).trim();
+        }
+    }
+    command
+}
+
+fn is_test_command(command: &str) -> bool {
+    command
+        .split("&&")
+        .flat_map(|part| part.split(';'))
+        .map(str::trim)
+        .any(|part| {
+            TEST_COMMANDS.iter().any(|prefix| {
+                part == *prefix
+                    || part
+                        .strip_prefix(prefix)
+                        .is_some_and(|rest| rest.starts_with(' '))
+            })
+        })
+}
+
+fn shell_command_from_arguments(arguments: &str) -> Option<String> {
+    let args: serde_json::Value = serde_json::from_str(arguments).ok()?;
+    let command = args.get("command")?;
+    match command {
+        serde_json::Value::String(command) => Some(command.clone()),
+        serde_json::Value::Array(parts) => Some(
+            parts
+                .iter()
+                .filter_map(serde_json::Value::as_str)
+                .collect::<Vec<_>>()
+                .join(" "),
+        ),
+        _ => None,
+    }
+}
+
+fn truncate_chars(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let mut out: String = text.chars().take(max.saturating_sub(1)).collect();
+    out.push('…');
+    out
+}
+
+/// Cuts `text` at a word boundary so it fits in `max` characters.
+fn truncate_words(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let mut out = String::new();
+    for word in text.split_whitespace() {
+        let next = if out.is_empty() {
+            word.chars().count()
+        } else {
+            out.chars().count() + 1 + word.chars().count()
+        };
+        if next > max {
+            break;
+        }
+        if !out.is_empty() {
+            out.push(' ');
+        }
+        out.push_str(word);
+    }
+    if out.is_empty() {
+        text.chars().take(max).collect()
+    } else {
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    fn user(text: &str) -> ResponseItem {
+        ResponseItem::Message {
+            id: None,
+            role: "user".to_string(),
+            content: vec![ContentItem::InputText {
+                text: text.to_string(),
+            }],
+        }
+    }
+
+    fn call(name: &str, arguments: serde_json::Value) -> ResponseItem {
+        ResponseItem::FunctionCall {
+            id: None,
+            name: name.to_string(),
+            arguments: arguments.to_string(),
+            call_id: "call-1".to_string(),
+        }
+    }
+
+    #[test]
+    fn summarizes_history_into_commit_and_pr_body() {
+        let history = vec![
+            user("Add a retry flag to the exec command.\nKeep the default unchanged."),
+            call(
+                "apply_patch",
+                serde_json::json!({
+                    "input": "*** Begin Patch\n*** Update File: code-rs/exec/src/cli.rs\n@@\n*** Add File: code-rs/exec/src/retry.rs\n+x\n*** End Patch"
+                }),
+            ),
+            call(
+                "shell",
+                serde_json::json!({ "command": ["bash", "-lc", "cargo test -p code-exec"] }),
+            ),
+            call("shell", serde_json::json!({ "command": ["rg", "retry"] })),
+            user("Add a retry flag to the exec command.\nAgain."),
+        ];
+
+        let summary = ChangeSummary::from_history("Add --retries to exec", &history);
+        assert_eq!(
+            summary,
+            ChangeSummary {
+                goal: "Add --retries to exec".to_string(),
+                decisions: vec!["Add a retry flag to the exec command.".to_string()],
+                files_changed: vec![
+                    "code-rs/exec/src/cli.rs".to_string(),
+                    "code-rs/exec/src/retry.rs".to_string(),
+                ],
+                tests_run: vec!["cargo test -p code-exec".to_string()],
+            }
+        );
+
+        let message = generate_commit_message(&summary);
+        assert_eq!(message.subject, "feat(exec): add --retries to exec");
+        assert_eq!(
+            message.full_message(),
+            "feat(exec): add --retries to exec\n\n\
+             Decisions:\n- Add a retry flag to the exec command.\n\n\
+             Files changed:\n- code-rs/exec/src/cli.rs\n- code-rs/exec/src/retry.rs\n\n\
+             Tests run:\n- cargo test -p code-exec"
+        );
+        assert_eq!(
+            message.pr_body,
+            "## Summary\n\nAdd --retries to exec\n\n\
+             ## Key decisions\n\n- Add a retry flag to the exec command.\n\n\
+             ## Files changed\n\n- `code-rs/exec/src/cli.rs`\n- `code-rs/exec/src/retry.rs`\n\n\
+             ## Testing\n\n- `cargo test -p code-exec`\n"
+        );
+    }
+
+    #[test]
+    fn observes_session_events_and_picks_commit_type() {
+        let mut summary = ChangeSummary::new("Fix the broken link in the README");
+        let mut changes = HashMap::new();
+        changes.insert(
+            PathBuf::from("/repo/README.md"),
+            FileChange::Add {
+                content: String::new(),
+            },
+        );
+        summary.observe(
+            &EventMsg::PatchApplyBegin(PatchApplyBeginEvent {
+                call_id: "call-1".to_string(),
+                auto_approved: true,
+                changes,
+            }),
+            Path::new("/repo"),
+        );
+        summary.observe(
+            &EventMsg::ExecCommandBegin(ExecCommandBeginEvent {
+                call_id: "call-2".to_string(),
+                command: vec!["npm".to_string(), "test".to_string()],
+                cwd: PathBuf::from("/repo"),
+                parsed_cmd: Vec::new(),
+            }),
+            Path::new("/repo"),
+        );
+
+        assert_eq!(summary.files_changed, vec!["README.md".to_string()]);
+        assert_eq!(summary.tests_run, vec!["npm test".to_string()]);
+        let message = generate_commit_message(&summary);
+        assert_eq!(message.subject, "docs: fix the broken link in the README");
+        assert!(message.pr_body.ends_with("## Testing\n\n- `npm test`\n"));
+    }
+}
@@ -2,6 +2,7 @@ use std::time::Duration;
 use std::time::Instant;
 
 use code_common::elapsed::format_duration;
+use code_core::protocol::InputItem;
 use code_core::protocol::ReviewContextMetadata;
 use code_core::protocol::ReviewOutputEvent;
 use code_git_tooling::GhostCommit;
@@ -339,6 +340,10 @@ pub struct AutoDriveController {
     pub qa_automation_enabled: bool,
     pub pending_agent_actions: Vec<AutoTurnAgentsAction>,
     pub pending_agent_timing: Option<AutoTurnAgentsTiming>,
+    /// Operator attachments submitted with the next CLI prompt.
+    pub pending_cli_attachments: Vec<InputItem>,
+    /// Model the next CLI prompt runs on instead of the session model.
+    pub pending_cli_model: Option<String>,
     pub continue_mode: AutoContinueMode,
     pub started_at: Option<Instant>,
     pub turns_completed: usize,
@@ -643,6 +648,8 @@ impl AutoDriveController {
         self.suppress_next_cli_display = false;
         self.pending_agent_actions.clear();
         self.pending_agent_timing = None;
+        self.pending_cli_attachments.clear();
+        self.pending_cli_model = None;
         let delay = Self::auto_restart_delay(pending_attempt);
         self.apply_phase(AutoRunPhase::TransientRecovery {
             backoff_ms: delay.as_millis() as u64,
@@ -0,0 +1,227 @@
+//! Coordinator transparency for `code exec --debug-coordinator`.
+//!
+//! Every coordinator request of a run is written to its own directory under
+//! `$CODE_HOME/debug_logs/auto_coordinator/<run-id>`:
+//!
+//! - `run.json`: the goal and coordinator model.
+//! - `NNNN-request.json`: the developer messages, response schema, and
+//!   conversation slice sent to the model, plus its raw output or the stream
+//!   error.
+//! - `NNNN-parse.json`: whether the output parsed into a decision, and the
+//!   parsed value or the error.
+//! - `NNNN-recovery.json`: how `classify_recoverable_decision_error` read a
+//!   failed decision before it was retried.
+//!
+//! Files are numbered by request, so a failed parse, its recovery note, and
+//! the retried request sit next to each other. Artifacts go through
+//! [`DebugLogger`], so they are sealed when `encrypt_at_rest` is on.
+
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+
+use chrono::Local;
+use code_core::Prompt;
+use code_core::config::Config;
+use code_core::debug_logger::DebugLogger;
+use code_protocol::models::ContentItem;
+use code_protocol::models::ResponseItem;
+use serde_json::Value;
+use serde_json::json;
+
+const TRACE_TAG: &str = "auto_coordinator";
+
+pub(crate) struct CoordinatorTrace {
+    logger: DebugLogger,
+    tag: String,
+    dir: PathBuf,
+    requests: AtomicU64,
+}
+
+impl CoordinatorTrace {
+    /// `None` unless `auto_drive.debug_coordinator` is set and the trace
+    /// directory can be written.
+    pub fn start(config: &Config, goal: &str) -> Option<Self> {
+        if !config.auto_drive.debug_coordinator {
+            return None;
+        }
+        let logger = match code_core::at_rest::cipher_for(config.encrypt_at_rest, &config.code_home)
+            .and_then(|cipher| Ok(DebugLogger::new(true)?.with_at_rest_cipher(cipher)))
+        {
+            Ok(logger) => logger,
+            Err(err) => {
+                tracing::warn!("coordinator trace disabled: {err}");
+                return None;
+            }
+        };
+        let tag = format!("{TRACE_TAG}/{}", Local::now().format("%Y%m%d_%H%M%S_%3f"));
+        let run = json!({
+            "started_at": Local::now().to_rfc3339(),
+            "goal": goal,
+            "model": config.model,
+        });
+        let dir = match logger.log_artifact(&tag, "run.json", &run) {
+            Ok(Some(path)) => path.parent().map(Path::to_path_buf)?,
+            Ok(None) => return None,
+            Err(err) => {
+                tracing::warn!("coordinator trace disabled: {err}");
+                return None;
+            }
+        };
+        Some(Self {
+            logger,
+            tag,
+            dir,
+            requests: AtomicU64::new(0),
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Records one request to the model; `conversation_len` trailing items
+    /// of the prompt input are the conversation slice.
+    pub fn record_request(
+        &self,
+        prompt: &Prompt,
+        conversation_len: usize,
+        outcome: Result<(&str, &[ResponseItem]), &anyhow::Error>,
+    ) {
+        let seq = self.requests.fetch_add(1, Ordering::Relaxed) + 1;
+        self.write(
+            seq,
+            "request",
+            request_payload(prompt, conversation_len, outcome),
+        );
+    }
+
+    /// Records how the output of the latest request parsed.
+    pub fn record_parse(&self, schema_label: &str, outcome: Result<Value, &anyhow::Error>) {
+        let payload = match outcome {
+            Ok(value) => json!({ "schema": schema_label, "ok": true, "value": value }),
+            Err(err) => json!({ "schema": schema_label, "ok": false, "error": format!("{err:#}") }),
+        };
+        self.write(self.requests.load(Ordering::Relaxed), "parse", payload);
+    }
+
+    /// Records the recovery applied to the latest failed decision.
+    pub fn record_recovery(
+        &self,
+        attempt: u32,
+        max_attempts: u32,
+        error: &anyhow::Error,
+        summary: &str,
+        guidance: Option<&str>,
+    ) {
+        let payload = json!({
+            "attempt": attempt,
+            "max_attempts": max_attempts,
+            "error": format!("{error:#}"),
+            "summary": summary,
+            "guidance": guidance,
+        });
+        self.write(self.requests.load(Ordering::Relaxed), "recovery", payload);
+    }
+
+    fn write(&self, seq: u64, kind: &str, mut payload: Value) {
+        payload["timestamp"] = Value::String(Local::now().to_rfc3339());
+        let name = format!("{seq:04}-{kind}.json");
+        if let Err(err) = self.logger.log_artifact(&self.tag, &name, &payload) {
+            tracing::warn!("failed to write coordinator trace {name}: {err}");
+        }
+    }
+}
+
+fn request_payload(
+    prompt: &Prompt,
+    conversation_len: usize,
+    outcome: Result<(&str, &[ResponseItem]), &anyhow::Error>,
+) -> Value {
+    let split = prompt.input.len().saturating_sub(conversation_len);
+    let (preamble, conversation) = prompt.input.split_at(split);
+    let developer_messages: Vec<String> = prompt
+        .prepend_developer_messages
+        .iter()
+        .cloned()
+        .chain(preamble.iter().filter_map(developer_text))
+        .collect();
+    let mut payload = json!({
+        "model": prompt.model_override,
+        "developer_messages": developer_messages,
+        "schema": prompt.text_format,
+        "conversation": conversation,
+    });
+    match outcome {
+        Ok((output_text, response_items)) => {
+            payload["output_text"] = Value::String(output_text.to_string());
+            payload["response_items"] = json!(response_items);
+        }
+        Err(err) => payload["error"] = Value::String(format!("{err:#}")),
+    }
+    payload
+}
+
+fn developer_text(item: &ResponseItem) -> Option<String> {
+    let ResponseItem::Message { role, content, .. } = item else {
+        return None;
+    };
+    if role != "developer" {
+        return None;
+    }
+    let text: Vec<&str> = content
+        .iter()
+        .filter_map(|content| match content {
+            ContentItem::InputText { text } | ContentItem::OutputText { text } => {
+                Some(text.as_str())
+            }
+            _ => None,
+        })
+        .collect();
+    Some(text.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auto_coordinator::make_message;
+    use code_core::TextFormat;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn request_payload_separates_developer_messages_from_the_conversation() {
+        let mut prompt = Prompt::default();
+        prompt
+            .prepend_developer_messages
+            .push("Coordinator prompt".to_string());
+        prompt.input = vec![
+            make_message("developer", "Intro".to_string()),
+            make_message("developer", "Goal".to_string()),
+            make_message("user", "Run the tests".to_string()),
+            make_message("assistant", "{\"finish_status\":".to_string()),
+        ];
+        prompt.text_format = Some(TextFormat {
+            r#type: "json_schema".to_string(),
+            name: Some("auto_coordinator_flow".to_string()),
+            strict: Some(true),
+            schema: Some(json!({ "type": "object" })),
+        });
+        prompt.model_override = Some("gpt-5".to_string());
+
+        let payload = request_payload(&prompt, 2, Ok(("{\"finish_status\"", &[])));
+        assert_eq!(
+            payload["developer_messages"],
+            json!(["Coordinator prompt", "Intro", "Goal"])
+        );
+        assert_eq!(payload["conversation"].as_array().map(Vec::len), Some(2));
+        assert_eq!(payload["schema"]["name"], "auto_coordinator_flow");
+        assert_eq!(payload["output_text"], "{\"finish_status\"");
+        assert_eq!(payload["model"], "gpt-5");
+
+        let error = anyhow::anyhow!("stream closed");
+        let payload = request_payload(&prompt, 2, Err(&error));
+        assert_eq!(payload["error"], "stream closed");
+        assert!(payload.get("output_text").is_none());
+    }
+}
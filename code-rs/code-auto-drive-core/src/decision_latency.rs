@@ -0,0 +1,163 @@
+//! Coordinator decision latency SLO.
+//!
+//! Every successful coordinator decision records its wall-clock latency in a
+//! sliding window. When `auto_drive.decision_latency_slo_ms` is set and the
+//! window's p95 stays above it for `decision_latency_breach_turns` decisions in
+//! a row, routine decisions move to `auto_drive.fast_model`. The switch lasts
+//! for the rest of the run; the fast model's own latency is still tracked but
+//! never switches back.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use code_core::config_types::AutoDriveSettings;
+
+/// Decisions kept for the p95 estimate.
+const LATENCY_WINDOW: usize = 20;
+
+/// The coordinator moved routine decisions to the fast model.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct LatencySwitch {
+    pub fast_model: String,
+    pub p95: Duration,
+    pub slo: Duration,
+    pub breach_turns: u32,
+}
+
+#[derive(Debug)]
+pub(crate) struct DecisionLatencySlo {
+    slo: Option<Duration>,
+    fast_model: Option<String>,
+    breach_turns: u32,
+    samples: VecDeque<Duration>,
+    consecutive_breaches: u32,
+    switched: bool,
+}
+
+impl DecisionLatencySlo {
+    /// Tracking is disabled without an SLO, and switching is disabled when
+    /// `fast_model` is unset or is the coordinator model itself.
+    pub fn from_settings(settings: &AutoDriveSettings, coordinator_model: &str) -> Self {
+        let fast_model = settings
+            .fast_model
+            .as_deref()
+            .map(str::trim)
+            .filter(|model| !model.is_empty() && !model.eq_ignore_ascii_case(coordinator_model))
+            .map(str::to_string);
+        Self {
+            slo: settings.decision_latency_slo_ms.map(Duration::from_millis),
+            fast_model,
+            breach_turns: settings.decision_latency_breach_turns.max(1),
+            samples: VecDeque::with_capacity(LATENCY_WINDOW),
+            consecutive_breaches: 0,
+            switched: false,
+        }
+    }
+
+    /// Model for routine decisions once the SLO has been breached.
+    pub fn routine_model(&self) -> Option<&str> {
+        self.fast_model.as_deref().filter(|_| self.switched)
+    }
+
+    /// Records one decision's latency and returns the switch it triggers.
+    pub fn record(&mut self, latency: Duration) -> Option<LatencySwitch> {
+        let slo = self.slo?;
+        if self.samples.len() == LATENCY_WINDOW {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(latency);
+        let p95 = self.p95()?;
+        if p95 <= slo {
+            self.consecutive_breaches = 0;
+            return None;
+        }
+        self.consecutive_breaches = self.consecutive_breaches.saturating_add(1);
+        if self.switched || self.consecutive_breaches < self.breach_turns {
+            return None;
+        }
+        let fast_model = self.fast_model.clone()?;
+        self.switched = true;
+        Some(LatencySwitch {
+            fast_model,
+            p95,
+            slo,
+            breach_turns: self.breach_turns,
+        })
+    }
+
+    /// Nearest-rank 95th percentile of the window.
+    fn p95(&self) -> Option<Duration> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        let mut sorted: Vec<Duration> = self.samples.iter().copied().collect();
+        sorted.sort();
+        let rank = (sorted.len() * 95).div_ceil(100);
+        sorted.get(rank.saturating_sub(1)).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn settings(
+        slo_ms: Option<u64>,
+        fast_model: Option<&str>,
+        breach_turns: u32,
+    ) -> AutoDriveSettings {
+        AutoDriveSettings {
+            decision_latency_slo_ms: slo_ms,
+            fast_model: fast_model.map(str::to_string),
+            decision_latency_breach_turns: breach_turns,
+            ..AutoDriveSettings::default()
+        }
+    }
+
+    #[test]
+    fn switches_after_consecutive_breaches_only_once() {
+        let mut slo = DecisionLatencySlo::from_settings(
+            &settings(Some(5_000), Some("gpt-5.1-codex-mini"), 3),
+            "gpt-5.1",
+        );
+        assert_eq!(slo.record(Duration::from_secs(9)), None);
+        assert_eq!(slo.record(Duration::from_secs(9)), None);
+        // A fast decision does not pull p95 back under the SLO.
+        assert_eq!(
+            slo.record(Duration::from_secs(1)),
+            Some(LatencySwitch {
+                fast_model: "gpt-5.1-codex-mini".to_string(),
+                p95: Duration::from_secs(9),
+                slo: Duration::from_secs(5),
+                breach_turns: 3,
+            })
+        );
+        assert_eq!(slo.routine_model(), Some("gpt-5.1-codex-mini"));
+        assert_eq!(slo.record(Duration::from_secs(9)), None);
+    }
+
+    #[test]
+    fn recovering_resets_the_streak_and_missing_settings_disable_switching() {
+        let mut slo =
+            DecisionLatencySlo::from_settings(&settings(Some(5_000), Some("fast"), 25), "strong");
+        slo.record(Duration::from_secs(9));
+        assert_eq!(slo.consecutive_breaches, 1);
+        // One slow decision dominates the p95 of a short window; the streak
+        // resets once enough fast decisions follow.
+        for _ in 0..LATENCY_WINDOW {
+            assert_eq!(slo.record(Duration::from_secs(1)), None);
+        }
+        assert_eq!(slo.consecutive_breaches, 0);
+        assert_eq!(slo.routine_model(), None);
+
+        let mut no_fast_model =
+            DecisionLatencySlo::from_settings(&settings(Some(1), Some("strong"), 1), "strong");
+        let mut no_slo =
+            DecisionLatencySlo::from_settings(&settings(None, Some("fast"), 1), "strong");
+        for _ in 0..5 {
+            assert_eq!(no_fast_model.record(Duration::from_secs(9)), None);
+            assert_eq!(no_slo.record(Duration::from_secs(9)), None);
+        }
+    }
+}
@@ -0,0 +1,207 @@
+//! Record and replay of coordinator model responses.
+//!
+//! With `auto_drive.record_decisions` (`code exec --record-decisions DIR`)
+//! every coordinator response is written to `DIR/NNNN.json` in request
+//! order. With `auto_drive.replay_decisions` the coordinator reads those
+//! files back in the same order instead of calling the model, so a run's
+//! decisions, and the state transitions they drive, repeat exactly without
+//! network access. Replay does not check that the requests match the
+//! recording; a run that diverges simply receives the next recorded
+//! response, and one that outlives the recording fails its next decision.
+//!
+//! Only coordinator decisions are taped. History compaction and turn reviews
+//! still call the model.
+
+use std::collections::VecDeque;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+
+use anyhow::Context;
+use anyhow::Result;
+use anyhow::anyhow;
+use code_core::config_types::AutoDriveSettings;
+use code_core::protocol::TokenUsage;
+use code_protocol::models::ResponseItem;
+use serde::Deserialize;
+use serde::Serialize;
+
+/// One recorded coordinator response.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct TapeEntry {
+    pub model_slug: String,
+    /// Items of the request's conversation, to spot where a replay diverged.
+    #[serde(default)]
+    pub conversation_len: usize,
+    pub output_text: String,
+    #[serde(default)]
+    pub response_items: Vec<ResponseItem>,
+    #[serde(default)]
+    pub token_usage: Option<TokenUsage>,
+}
+
+pub(crate) enum DecisionTape {
+    Record {
+        dir: PathBuf,
+        recorded: AtomicU64,
+    },
+    Replay {
+        dir: PathBuf,
+        entries: Mutex<VecDeque<TapeEntry>>,
+        replayed: AtomicU64,
+    },
+}
+
+impl DecisionTape {
+    /// Replay wins when both directories are set.
+    pub fn from_settings(settings: &AutoDriveSettings) -> Result<Option<Self>> {
+        if let Some(dir) = settings.replay_decisions.as_deref() {
+            return Self::replay(dir).map(Some);
+        }
+        settings
+            .record_decisions
+            .as_deref()
+            .map(Self::record)
+            .transpose()
+    }
+
+    pub fn record(dir: &Path) -> Result<Self> {
+        fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+        Ok(Self::Record {
+            dir: dir.to_path_buf(),
+            recorded: AtomicU64::new(0),
+        })
+    }
+
+    pub fn replay(dir: &Path) -> Result<Self> {
+        let mut files: Vec<PathBuf> = fs::read_dir(dir)
+            .with_context(|| format!("failed to read decision tape {}", dir.display()))?
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        files.sort();
+        let entries = files
+            .iter()
+            .map(|path| {
+                let bytes =
+                    fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+                serde_json::from_slice(&bytes)
+                    .with_context(|| format!("invalid decision tape entry {}", path.display()))
+            })
+            .collect::<Result<VecDeque<TapeEntry>>>()?;
+        Ok(Self::Replay {
+            dir: dir.to_path_buf(),
+            entries: Mutex::new(entries),
+            replayed: AtomicU64::new(0),
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        match self {
+            Self::Record { dir, .. } | Self::Replay { dir, .. } => dir,
+        }
+    }
+
+    pub fn is_replay(&self) -> bool {
+        matches!(self, Self::Replay { .. })
+    }
+
+    /// The next recorded response when replaying; `None` when recording.
+    pub fn next_response(&self) -> Option<Result<TapeEntry>> {
+        let Self::Replay {
+            dir,
+            entries,
+            replayed,
+        } = self
+        else {
+            return None;
+        };
+        let next = entries
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .pop_front();
+        Some(match next {
+            Some(entry) => {
+                replayed.fetch_add(1, Ordering::Relaxed);
+                Ok(entry)
+            }
+            None => Err(anyhow!(
+                "decision tape {} exhausted after {} responses",
+                dir.display(),
+                replayed.load(Ordering::Relaxed)
+            )),
+        })
+    }
+
+    /// Writes `entry` as the next response when recording.
+    pub fn record_response(&self, entry: &TapeEntry) {
+        let Self::Record { dir, recorded } = self else {
+            return;
+        };
+        let seq = recorded.fetch_add(1, Ordering::Relaxed) + 1;
+        let path = dir.join(format!("{seq:04}.json"));
+        let written = serde_json::to_vec_pretty(entry)
+            .map_err(anyhow::Error::from)
+            .and_then(|bytes| fs::write(&path, bytes).map_err(anyhow::Error::from));
+        if let Err(err) = written {
+            tracing::warn!("failed to record decision {}: {err:#}", path.display());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn entry(output_text: &str) -> TapeEntry {
+        TapeEntry {
+            model_slug: "gpt-5".to_string(),
+            conversation_len: 3,
+            output_text: output_text.to_string(),
+            response_items: Vec::new(),
+            token_usage: Some(TokenUsage {
+                input_tokens: 1200,
+                output_tokens: 80,
+                ..TokenUsage::default()
+            }),
+        }
+    }
+
+    #[test]
+    fn replays_recorded_responses_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let recorder = DecisionTape::record(dir.path()).unwrap();
+        assert!(recorder.next_response().is_none());
+        recorder.record_response(&entry("{\"finish_status\":\"continue\"}"));
+        recorder.record_response(&entry("{\"finish_status\":\"finish_success\"}"));
+        fs::write(dir.path().join("notes.txt"), "ignored").unwrap();
+
+        let settings = AutoDriveSettings {
+            record_decisions: Some(dir.path().join("unused")),
+            replay_decisions: Some(dir.path().to_path_buf()),
+            ..AutoDriveSettings::default()
+        };
+        let replay = DecisionTape::from_settings(&settings).unwrap().unwrap();
+        assert!(replay.is_replay());
+        assert_eq!(
+            replay.next_response().unwrap().unwrap(),
+            entry("{\"finish_status\":\"continue\"}")
+        );
+        assert_eq!(
+            replay.next_response().unwrap().unwrap(),
+            entry("{\"finish_status\":\"finish_success\"}")
+        );
+        let exhausted = replay.next_response().unwrap().unwrap_err();
+        assert!(
+            exhausted
+                .to_string()
+                .ends_with("exhausted after 2 responses")
+        );
+        assert!(!dir.path().join("unused").exists());
+    }
+}
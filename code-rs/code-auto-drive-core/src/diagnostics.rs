@@ -107,6 +107,13 @@ pub enum DiagnosticAlert {
     },
     /// Parallel execution configured with low concurrency.
     LowConcurrency { max_concurrent_agents: i32 },
+    /// Agent task cancelled after exceeding its timeout.
+    AgentTimedOut {
+        session_id: String,
+        task_id: String,
+        elapsed_ms: i64,
+        partial_output: String,
+    },
 }
 
 /// Summary report of diagnostic findings.
@@ -342,6 +349,33 @@ impl DiagnosticsEngine {
     }
 }
 
+/// Jaccard similarity of the word `n`-grams of two texts, in `0.0..=1.0`.
+///
+/// Case and punctuation are ignored. Texts shorter than `n` words are
+/// compared word by word instead.
+pub fn ngram_similarity(a: &str, b: &str, n: usize) -> f32 {
+    let words = |text: &str| -> Vec<String> {
+        text.split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(str::to_lowercase)
+            .collect()
+    };
+    let words_a = words(a);
+    let words_b = words(b);
+    if words_a.is_empty() || words_b.is_empty() {
+        return 0.0;
+    }
+    let n = n.max(1).min(words_a.len()).min(words_b.len());
+    let grams = |words: &[String]| -> std::collections::HashSet<String> {
+        words.windows(n).map(|gram| gram.join(" ")).collect()
+    };
+    let grams_a = grams(&words_a);
+    let grams_b = grams(&words_b);
+    let intersection = grams_a.intersection(&grams_b).count();
+    let union = grams_a.union(&grams_b).count();
+    intersection as f32 / union as f32
+}
+
 impl Default for DiagnosticsEngine {
     fn default() -> Self {
         Self::new()
@@ -452,6 +486,23 @@ mod tests {
         assert!(alert.is_some());
     }
 
+    #[test]
+    fn test_ngram_similarity() {
+        let a = "Run cargo test for the parser crate and fix failures";
+        assert_eq!(ngram_similarity(a, a, 3), 1.0);
+        assert_eq!(ngram_similarity(a, "", 3), 0.0);
+        assert!(
+            ngram_similarity(
+                a,
+                "run cargo test for the parser crate, and fix the failures",
+                3
+            ) > 0.5
+        );
+        assert!(ngram_similarity(a, "Write the release notes for 2.0", 3) < 0.1);
+        // Shorter than n words: compared word by word.
+        assert_eq!(ngram_similarity("cargo test", "Cargo test!", 3), 1.0);
+    }
+
     #[test]
     fn test_generate_report() {
         let mut engine = DiagnosticsEngine::new();
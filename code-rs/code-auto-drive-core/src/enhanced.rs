@@ -30,7 +30,7 @@ use crate::intervention::InterventionHandler;
 use crate::intervention::InterventionReason;
 use crate::parallel_execution::ParallelModel;
 use crate::parallel_execution::ParallelRole;
-use crate::parallel_execution::execute_parallel_roles;
+use crate::parallel_execution::execute_parallel_roles_streaming;
 use crate::parallel_execution::merge_parallel_results;
 use crate::progress::AutoDrivePhase;
 use crate::progress::CompactionNotification;
@@ -63,7 +63,9 @@ use crate::telemetry::TurnOutcome;
 use anyhow::anyhow;
 use code_core::config_types::AutoDriveSettings;
 use std::sync::Arc;
+use std::time::Duration;
 use std::time::Instant;
+use tokio::sync::mpsc;
 
 /// Configuration for enhanced Auto Drive features.
 #[derive(Clone, Debug)]
@@ -141,6 +143,7 @@ impl EnhancedConfig {
                     token_budget,
                     turn_limit,
                     duration_limit: duration_limit_seconds.map(std::time::Duration::from_secs),
+                    cost_limit_usd: None,
                 })
             } else {
                 None
@@ -181,6 +184,24 @@ pub enum EnhancedEvent {
     HistoryCompacted(CompactionNotification),
 }
 
+/// Resolves after `limit`, or never when no timeout is configured.
+async fn sleep_for_timeout(limit: Option<Duration>) {
+    match limit {
+        Some(limit) => tokio::time::sleep(limit).await,
+        None => std::future::pending::<()>().await,
+    }
+}
+
+/// Joins each role's streamed output into `[role]` sections.
+fn render_partial_output(partial: &[(String, String)]) -> String {
+    partial
+        .iter()
+        .filter(|(_, text)| !text.trim().is_empty())
+        .map(|(role, text)| format!("[{role}]\n{}", text.trim()))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
 fn parallel_role_from_name(name: &str) -> Option<ParallelRole> {
     match name {
         "Coordinator" => Some(ParallelRole::Coordinator),
@@ -220,6 +241,8 @@ pub struct EnhancedCoordinator {
     pipeline_task_id: Option<String>,
     turns_since_checkpoint: u32,
     pub session_pool: Option<Arc<SessionPool>>,
+    /// Per-agent timeout from `auto_drive.agent_timeout_seconds`.
+    agent_timeout: Option<Duration>,
     pending_events: Vec<EnhancedEvent>,
 }
 
@@ -255,11 +278,15 @@ impl EnhancedCoordinator {
         }
 
         let audit = if config.audit_enabled {
-            if let Some(path) = &config.audit_path {
-                Some(AuditLogger::new("session").with_log_path(path.clone()))
-            } else {
-                Some(AuditLogger::new("session"))
-            }
+            let logged = config.audit_path.as_ref().and_then(|path| {
+                AuditLogger::new("session")
+                    .with_log_path(path.clone())
+                    .inspect_err(|err| {
+                        tracing::warn!("audit log {} not written: {err:#}", path.display())
+                    })
+                    .ok()
+            });
+            Some(logged.unwrap_or_else(|| AuditLogger::new("session")))
         } else {
             None
         };
@@ -328,6 +355,10 @@ impl EnhancedCoordinator {
             goal: None,
             pipeline_task_id: None,
             turns_since_checkpoint: 0,
+            agent_timeout: auto_settings
+                .agent_timeout_seconds
+                .filter(|secs| *secs > 0)
+                .map(Duration::from_secs),
             session_pool,
             pending_events,
         }
@@ -548,7 +579,8 @@ impl EnhancedCoordinator {
         );
 
         let session_id = if let Some(pool) = self.session_pool.clone() {
-            let pool_task = PoolTask::new(task_id.clone(), base_prompt.clone());
+            let pool_task = PoolTask::new(task_id.clone(), base_prompt.clone())
+                .with_timeout(self.agent_timeout);
             pool.submit(pool_task)
                 .await
                 .map_err(|err| anyhow!(err.to_string()))?;
@@ -565,8 +597,44 @@ impl EnhancedCoordinator {
             "direct".to_string()
         };
 
-        let results =
-            execute_parallel_roles(client, roles, &base_prompt, model, max_agents).await?;
+        let cancel_token = match &self.session_pool {
+            Some(pool) => pool.cancellation_token(&session_id).await,
+            None => None,
+        }
+        .unwrap_or_default();
+        let (output_tx, mut output_rx) = mpsc::unbounded_channel();
+        let run = execute_parallel_roles_streaming(
+            client,
+            roles,
+            &base_prompt,
+            model,
+            max_agents,
+            Some(output_tx),
+        );
+        tokio::pin!(run);
+        let deadline = sleep_for_timeout(self.agent_timeout);
+        tokio::pin!(deadline);
+        let mut partial: Vec<(String, String)> = Vec::new();
+        let results = loop {
+            tokio::select! {
+                results = &mut run => break results?,
+                Some((role, chunk)) = output_rx.recv() => {
+                    match partial.iter_mut().find(|(name, _)| *name == role) {
+                        Some((_, text)) => text.push_str(&chunk),
+                        None => partial.push((role, chunk)),
+                    }
+                }
+                _ = cancel_token.cancelled() => {
+                    return Ok(self.fail_cancelled_stage(&task_id).await);
+                }
+                _ = &mut deadline => {
+                    let partial = render_partial_output(&partial);
+                    return Ok(self
+                        .fail_timed_out_stage(&task_id, &session_id, start, partial)
+                        .await);
+                }
+            }
+        };
 
         for result in &results {
             let message = RoleMessage::WorkComplete {
@@ -645,6 +713,58 @@ impl EnhancedCoordinator {
         Ok(last_action)
     }
 
+    /// Cancels a stage that exceeded its agent timeout, surfacing the partial
+    /// output through a single diagnostic alert.
+    async fn fail_timed_out_stage(
+        &mut self,
+        task_id: &str,
+        session_id: &str,
+        start: Instant,
+        partial: String,
+    ) -> StageAction {
+        if let Some(pool) = &self.session_pool {
+            // Hand the partial output to the pool so the timed-out result it
+            // publishes carries it too.
+            pool.record_partial(session_id, &partial).await;
+            pool.expire_task(task_id).await;
+        }
+        let elapsed_ms = i64::try_from(start.elapsed().as_millis()).unwrap_or(i64::MAX);
+        self.pending_events.push(EnhancedEvent::DiagnosticAlert(
+            DiagnosticAlert::AgentTimedOut {
+                session_id: session_id.to_string(),
+                task_id: task_id.to_string(),
+                elapsed_ms,
+                partial_output: partial,
+            },
+        ));
+        let error = format!("agent task {task_id} timed out after {elapsed_ms}ms");
+        self.fail_stage(task_id, error).await
+    }
+
+    /// Fails a stage whose task the pool cancelled, for example on shutdown
+    /// or when a health check expired it. The pool reports its own timeouts,
+    /// so no alert is raised here.
+    async fn fail_cancelled_stage(&mut self, task_id: &str) -> StageAction {
+        let error = format!("agent task {task_id} was cancelled");
+        self.fail_stage(task_id, error).await
+    }
+
+    async fn fail_stage(&mut self, task_id: &str, error: String) -> StageAction {
+        let _ = self
+            .role_channel
+            .broadcast(RoleMessage::ErrorOccurred {
+                role: "pool".to_string(),
+                task_id: Some(task_id.to_string()),
+                error: error.clone(),
+            })
+            .await;
+        self.poll_pool_events().await;
+        StageAction::Fail {
+            role: "pool".to_string(),
+            error,
+        }
+    }
+
     /// Collects pool health and backpressure events into pending events and audit log.
     pub async fn poll_pool_events(&mut self) {
         if let Some(pool) = &self.session_pool {
@@ -683,6 +803,17 @@ impl EnhancedCoordinator {
                 ));
             }
 
+            for result in report.timed_out {
+                self.pending_events.push(EnhancedEvent::DiagnosticAlert(
+                    DiagnosticAlert::AgentTimedOut {
+                        session_id: result.session_id,
+                        task_id: result.task_id,
+                        elapsed_ms: i64::try_from(result.duration.as_millis()).unwrap_or(i64::MAX),
+                        partial_output: result.content,
+                    },
+                ));
+            }
+
             for migration in report.migrations {
                 self.pending_events.push(EnhancedEvent::DiagnosticAlert(
                     DiagnosticAlert::SessionMigrated {
@@ -942,6 +1073,7 @@ mod tests {
     use crate::task_pipeline::StageAction;
     use code_core::Prompt;
     use code_core::ResponseEvent;
+    use futures::StreamExt;
     use futures::stream;
     use std::future::Future;
     use std::pin::Pin;
@@ -988,6 +1120,28 @@ mod tests {
         }
     }
 
+    /// Streams a little output, then never completes.
+    struct StallingModel;
+
+    impl ParallelModel for StallingModel {
+        fn stream_prompt(
+            &self,
+            _prompt: Prompt,
+        ) -> Pin<Box<dyn Future<Output = anyhow::Result<ParallelResponseStream>> + Send + '_>>
+        {
+            Box::pin(async move {
+                let events = stream::iter(vec![Ok(ResponseEvent::OutputTextDelta {
+                    delta: "halfway".to_string(),
+                    item_id: None,
+                    sequence_number: None,
+                    output_index: None,
+                })])
+                .chain(stream::pending());
+                Ok(Box::pin(events) as ParallelResponseStream)
+            })
+        }
+    }
+
     #[test]
     fn test_enhanced_coordinator_lifecycle() {
         let mut coord = EnhancedCoordinator::new(
@@ -1072,6 +1226,43 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn timed_out_stage_keeps_partial_output_and_alerts_once() {
+        let mut settings = AutoDriveSettings::default();
+        settings.parallel_instances = 8;
+        let mut coord = EnhancedCoordinator::new(EnhancedConfig::default(), &settings);
+        coord.agent_timeout = Some(Duration::from_millis(50));
+        coord.start_session("Goal", "session-timeout");
+
+        let pool = coord.session_pool.clone().expect("session pool created");
+        pool.warmup().await;
+
+        let action = coord
+            .run_active_stage(Arc::new(StallingModel), "gpt-5.1")
+            .await
+            .expect("stage executed");
+        assert!(matches!(action, StageAction::Fail { .. }));
+
+        let alerts: Vec<String> = coord
+            .take_events()
+            .into_iter()
+            .filter_map(|event| match event {
+                EnhancedEvent::DiagnosticAlert(DiagnosticAlert::AgentTimedOut {
+                    partial_output,
+                    ..
+                }) => Some(partial_output),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(alerts.len(), 1);
+        assert!(alerts[0].contains("halfway"));
+
+        let result = pool.next_result().await.unwrap();
+        assert!(result.timed_out);
+        assert!(!result.success);
+        assert!(result.content.contains("halfway"));
+    }
+
     #[tokio::test]
     async fn poll_pool_events_emits_stuck_alert() {
         let mut settings = AutoDriveSettings::default();
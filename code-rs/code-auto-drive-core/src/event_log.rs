@@ -0,0 +1,289 @@
+//! Recorded Auto Drive event stream used by `code exec auto replay`.
+//!
+//! The audit and progress logs only keep summaries, so a run cannot be
+//! reconstructed from them alone. With `auto_drive.event_log_enabled`, every
+//! [`AutoCoordinatorEvent`] and every conversation [`Event`] handed to the
+//! event processor of an exec Auto Drive session is appended to
+//! `$CODE_HOME/auto_drive/events/<session>.jsonl` together with its offset from
+//! the start of the run. Lines are sealed with the at-rest cipher when
+//! `encrypt_at_rest` is set, and only the newest `auto_drive.event_log_keep`
+//! logs are kept. Replaying the file feeds the same events back through the
+//! renderers without any model calls.
+
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::fs::{self};
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Instant;
+use std::time::SystemTime;
+
+use anyhow::Context;
+use code_core::at_rest::AtRestCipher;
+use code_core::protocol::Event;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::AutoCoordinatorEvent;
+
+const EVENT_LOG_SUBDIR: &str = "auto_drive/events";
+
+/// One recorded event.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutoEventRecord {
+    /// Milliseconds since the recording started.
+    pub offset_ms: i64,
+    pub entry: AutoEventLogEntry,
+}
+
+/// Source of a recorded event.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "source", content = "event", rename_all = "snake_case")]
+pub enum AutoEventLogEntry {
+    Coordinator(AutoCoordinatorEvent),
+    Conversation(Event),
+}
+
+/// Returns the event log path for `session_id` under `code_home`.
+pub fn event_log_path(code_home: &Path, session_id: &str) -> PathBuf {
+    code_home
+        .join(EVENT_LOG_SUBDIR)
+        .join(format!("{session_id}.jsonl"))
+}
+
+/// Deletes all but the `keep` most recently modified event logs under
+/// `code_home`, returning how many were removed.
+pub fn prune_event_logs(code_home: &Path, keep: usize) -> std::io::Result<usize> {
+    let dir = code_home.join(EVENT_LOG_SUBDIR);
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(err) => return Err(err),
+    };
+    let mut logs: Vec<(SystemTime, PathBuf)> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "jsonl"))
+        .filter_map(|path| {
+            let modified = fs::metadata(&path).and_then(|meta| meta.modified()).ok()?;
+            Some((modified, path))
+        })
+        .collect();
+    logs.sort_by(|a, b| b.0.cmp(&a.0));
+
+    let mut removed = 0;
+    for (_, path) in logs.into_iter().skip(keep) {
+        match fs::remove_file(&path) {
+            Ok(()) => removed += 1,
+            Err(err) => tracing::warn!(
+                "failed to remove auto drive event log {}: {err}",
+                path.display()
+            ),
+        }
+    }
+    Ok(removed)
+}
+
+/// Appends events to a session's event log.
+pub struct AutoEventLogWriter {
+    started_at: Instant,
+    file: Mutex<File>,
+    cipher: Option<Arc<AtRestCipher>>,
+}
+
+impl AutoEventLogWriter {
+    /// Creates (or truncates) the log at `path`, sealing every line with
+    /// `cipher` when present.
+    pub fn create(path: &Path, cipher: Option<Arc<AtRestCipher>>) -> anyhow::Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(path)
+            .with_context(|| format!("failed to open auto drive event log {}", path.display()))?;
+        Ok(Self {
+            started_at: Instant::now(),
+            file: Mutex::new(file),
+            cipher,
+        })
+    }
+
+    pub fn record_coordinator(&self, event: &AutoCoordinatorEvent) {
+        self.record(AutoEventLogEntry::Coordinator(event.clone()));
+    }
+
+    pub fn record_conversation(&self, event: &Event) {
+        self.record(AutoEventLogEntry::Conversation(event.clone()));
+    }
+
+    fn record(&self, entry: AutoEventLogEntry) {
+        let record = AutoEventRecord {
+            offset_ms: i64::try_from(self.started_at.elapsed().as_millis()).unwrap_or(i64::MAX),
+            entry,
+        };
+        let line = match serde_json::to_string(&record) {
+            Ok(line) => line,
+            Err(err) => {
+                tracing::warn!("failed to serialize auto drive event: {err}");
+                return;
+            }
+        };
+        let line = match self.cipher.as_deref() {
+            Some(cipher) => match cipher.seal_line(&line) {
+                Ok(sealed) => sealed,
+                Err(err) => {
+                    tracing::warn!("failed to seal auto drive event: {err}");
+                    return;
+                }
+            },
+            None => line,
+        };
+        if let Ok(mut file) = self.file.lock()
+            && let Err(err) = writeln!(file, "{line}")
+        {
+            tracing::warn!("failed to write auto drive event log: {err}");
+        }
+    }
+}
+
+/// Reads every record from an event log, opening sealed lines with `cipher`
+/// and skipping lines that fail to parse.
+pub fn read_event_log(
+    path: &Path,
+    cipher: Option<&AtRestCipher>,
+) -> anyhow::Result<Vec<AutoEventRecord>> {
+    let file = File::open(path)
+        .with_context(|| format!("failed to open auto drive event log {}", path.display()))?;
+    let mut records = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let line = match cipher {
+            Some(cipher) => cipher
+                .open_line(&line)
+                .with_context(|| format!("failed to decrypt {}", path.display()))?,
+            None => line,
+        };
+        match serde_json::from_str::<AutoEventRecord>(&line) {
+            Ok(record) => records.push(record),
+            Err(err) => tracing::warn!("skipping malformed auto drive event: {err}"),
+        }
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AutoCoordinatorStatus;
+    use crate::AutoTurnCliAction;
+    use code_core::protocol::EventMsg;
+    use pretty_assertions::assert_eq;
+    use tempfile::tempdir;
+
+    #[test]
+    fn round_trips_coordinator_and_conversation_events() {
+        let dir = tempdir().unwrap();
+        let path = event_log_path(dir.path(), "session-1");
+        let writer = AutoEventLogWriter::create(&path, None).unwrap();
+
+        writer.record_coordinator(&AutoCoordinatorEvent::Decision {
+            seq: 1,
+            status: AutoCoordinatorStatus::Continue,
+            status_title: Some("Planning".to_string()),
+            status_sent_to_user: None,
+            goal: None,
+            cli: Some(AutoTurnCliAction {
+                prompt: "run tests".to_string(),
+                context: None,
+                suppress_ui_context: false,
+                attachments: Vec::new(),
+                workstreams: Vec::new(),
+                complexity: None,
+                model: None,
+            }),
+            agents_timing: None,
+            agents: Vec::new(),
+            transcript: Vec::new(),
+            budget_snapshot: Default::default(),
+        });
+        writer.record_conversation(&Event {
+            id: "1".to_string(),
+            event_seq: 0,
+            msg: EventMsg::TaskStarted,
+            order: None,
+        });
+
+        let records = read_event_log(&path, None).unwrap();
+        let sources: Vec<String> = records
+            .iter()
+            .map(|record| {
+                serde_json::to_value(&record.entry).unwrap()["source"]
+                    .as_str()
+                    .unwrap()
+                    .to_string()
+            })
+            .collect();
+        assert_eq!(sources, vec!["coordinator", "conversation"]);
+        let AutoEventLogEntry::Coordinator(AutoCoordinatorEvent::Decision { cli, .. }) =
+            &records[0].entry
+        else {
+            panic!("expected decision");
+        };
+        assert_eq!(cli.as_ref().map(|c| c.prompt.as_str()), Some("run tests"));
+    }
+
+    #[test]
+    fn sealed_log_hides_events_and_reads_back() {
+        let dir = tempdir().unwrap();
+        let path = event_log_path(dir.path(), "session-2");
+        let cipher = Arc::new(AtRestCipher::from_key_bytes(&[9u8; 32]).unwrap());
+        let writer = AutoEventLogWriter::create(&path, Some(cipher.clone())).unwrap();
+        writer.record_conversation(&Event {
+            id: "1".to_string(),
+            event_seq: 0,
+            msg: EventMsg::AgentMessage(code_core::protocol::AgentMessageEvent {
+                message: "secret plan".to_string(),
+            }),
+            order: None,
+        });
+
+        let raw = fs::read_to_string(&path).unwrap();
+        assert!(!raw.contains("secret plan"));
+        let records = read_event_log(&path, Some(&cipher)).unwrap();
+        assert_eq!(records.len(), 1);
+        assert!(read_event_log(&path, None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn prune_keeps_newest_logs() {
+        let dir = tempdir().unwrap();
+        for (index, id) in ["old", "mid", "new"].iter().enumerate() {
+            let path = event_log_path(dir.path(), id);
+            AutoEventLogWriter::create(&path, None).unwrap();
+            let modified =
+                SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(index as u64 + 1);
+            File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(modified)
+                .unwrap();
+        }
+
+        assert_eq!(prune_event_logs(dir.path(), 2).unwrap(), 1);
+        assert!(!event_log_path(dir.path(), "old").exists());
+        assert!(event_log_path(dir.path(), "mid").exists());
+        assert!(event_log_path(dir.path(), "new").exists());
+    }
+}
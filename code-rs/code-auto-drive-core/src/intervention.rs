@@ -29,6 +29,7 @@ pub enum BudgetAlertKind {
     TokenExceeded,
     TurnLimitReached,
     DurationExceeded,
+    CostExceeded,
     BackpressureWarning,
     BackpressureExceeded,
 }
@@ -40,6 +41,7 @@ impl From<&BudgetAlert> for BudgetAlertKind {
             BudgetAlert::TokenExceeded { .. } => Self::TokenExceeded,
             BudgetAlert::TurnLimitReached { .. } => Self::TurnLimitReached,
             BudgetAlert::DurationExceeded { .. } => Self::DurationExceeded,
+            BudgetAlert::CostExceeded { .. } => Self::CostExceeded,
             BudgetAlert::BackpressureWarning { .. } => Self::BackpressureWarning,
             BudgetAlert::BackpressureExceeded { .. } => Self::BackpressureExceeded,
         }
@@ -57,6 +59,7 @@ pub enum DiagnosticAlertKind {
     SessionStuck,
     SessionMigrated,
     LowConcurrency,
+    AgentTimedOut,
 }
 
 impl From<&DiagnosticAlert> for DiagnosticAlertKind {
@@ -70,6 +73,7 @@ impl From<&DiagnosticAlert> for DiagnosticAlertKind {
             DiagnosticAlert::SessionStuck { .. } => Self::SessionStuck,
             DiagnosticAlert::SessionMigrated { .. } => Self::SessionMigrated,
             DiagnosticAlert::LowConcurrency { .. } => Self::LowConcurrency,
+            DiagnosticAlert::AgentTimedOut { .. } => Self::AgentTimedOut,
         }
     }
 }
@@ -228,6 +232,7 @@ impl InterventionHandler {
                     BudgetAlertKind::TokenExceeded => "Token budget exceeded".to_string(),
                     BudgetAlertKind::TurnLimitReached => "Turn limit reached".to_string(),
                     BudgetAlertKind::DurationExceeded => "Duration limit exceeded".to_string(),
+                    BudgetAlertKind::CostExceeded => "Cost limit exceeded".to_string(),
                     BudgetAlertKind::BackpressureWarning => {
                         "Session pool backpressure warning".to_string()
                     }
@@ -246,6 +251,7 @@ impl InterventionHandler {
                     DiagnosticAlertKind::LowConcurrency => {
                         "Parallel execution concurrency is below target".to_string()
                     }
+                    DiagnosticAlertKind::AgentTimedOut => "Agent timed out".to_string(),
                 },
             }),
             InterventionState::EditingPrompt { .. } => Some("Editing prompt...".to_string()),
@@ -3,35 +3,54 @@ mod auto_coordinator;
 mod auto_drive_history;
 mod controller;
 mod coordinator_router;
+mod coordinator_trace;
 mod coordinator_user_schema;
+mod decision_latency;
+mod decision_tape;
+mod loop_detector;
+mod model_failover;
 pub mod parallel_execution;
 mod retry;
+mod sandboxed_shell;
 mod session_metrics;
+mod simple_loop;
+mod stop_conditions;
+mod turn_checks;
+mod turn_review;
+mod turn_routing;
+mod webhook;
 
 // Enhanced Auto Drive feature modules
 pub mod audit;
 pub mod backlog;
 pub mod budget;
 pub mod checkpoint;
+pub mod commit_message;
 pub mod compaction;
 pub mod diagnostics;
 pub mod enhanced;
+pub mod event_log;
 pub mod intervention;
 pub mod progress;
 pub mod progress_log;
 pub mod retry_enhanced;
 pub mod role_channel;
+pub mod run_report;
+pub mod run_scheduler;
 pub mod scheduler;
 pub mod selective_tests;
 pub mod session_pool;
 pub mod task_pipeline;
 pub mod telemetry;
+pub mod turn_changes;
 
 #[cfg(feature = "dev-faults")]
 mod faults;
 
 #[cfg(test)]
 mod property_tests;
+#[cfg(test)]
+mod test_support;
 
 pub use auto_coordinator::AutoCoordinatorCommand;
 pub use auto_coordinator::AutoCoordinatorEvent;
@@ -41,8 +60,11 @@ pub use auto_coordinator::AutoCoordinatorStatus;
 pub use auto_coordinator::AutoTurnAgentsAction;
 pub use auto_coordinator::AutoTurnAgentsTiming;
 pub use auto_coordinator::AutoTurnCliAction;
+pub use auto_coordinator::AutoTurnWorkstream;
 pub use auto_coordinator::BudgetAlertType;
+pub use auto_coordinator::CompactionEstimate;
 pub use auto_coordinator::DiagnosticAlertType;
+pub use auto_coordinator::MIN_COMPACT_THRESHOLD;
 pub use auto_coordinator::MODEL_SLUG;
 pub use auto_coordinator::TurnComplexity;
 pub use auto_coordinator::TurnConfig;
@@ -73,4 +95,7 @@ pub use coordinator_router::CoordinatorRouterResponse;
 pub use coordinator_router::route_user_message;
 pub use coordinator_user_schema::parse_user_turn_reply;
 pub use coordinator_user_schema::user_turn_schema;
+pub use session_metrics::AttributedUsage;
 pub use session_metrics::SessionMetrics;
+pub use session_metrics::UsageSource;
+pub use session_metrics::agent_prompt_hash;
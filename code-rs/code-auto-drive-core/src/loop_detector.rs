@@ -0,0 +1,258 @@
+//! Loop and goal-drift detection for the coordinator loop.
+//!
+//! After every CLI turn the coordinator records the prompt it sent, the CLI's
+//! reply, and a fingerprint of the workspace taken by the turn checks (see
+//! [`crate::turn_checks`]) so git never runs on the coordinator thread. When
+//! the last `loop_threshold` prompts or replies are near-identical, or twice
+//! that many turns pass without a file change, the detector reports a
+//! finding. The coordinator surfaces it as a diagnostic alert and adds the
+//! finding's correction to the conversation as a developer message, so the
+//! next decision changes course.
+
+use std::collections::VecDeque;
+use std::hash::DefaultHasher;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::path::Path;
+use std::process::Command;
+
+use code_core::config_types::AutoDriveSettings;
+use code_protocol::models::ResponseItem;
+
+use crate::auto_coordinator::DiagnosticAlertType;
+use crate::diagnostics::ngram_similarity;
+use crate::simple_loop::last_cli_reply;
+
+/// Word n-gram size used to compare prompts and replies.
+const NGRAM_SIZE: usize = 3;
+
+/// Similarity at which two prompts or replies count as near-identical.
+const NEAR_IDENTICAL_SIMILARITY: f32 = 0.8;
+
+/// Something the detector noticed about the last few turns.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct LoopFinding {
+    pub alert_type: DiagnosticAlertType,
+    /// Shown to the operator.
+    pub message: String,
+    /// Developer message that steers the coordinator's next decision.
+    pub correction: String,
+}
+
+#[derive(Debug)]
+pub(crate) struct LoopDetector {
+    threshold: usize,
+    prompts: VecDeque<String>,
+    replies: VecDeque<String>,
+    last_fingerprint: Option<u64>,
+    turns_without_changes: usize,
+}
+
+impl LoopDetector {
+    /// `None` when diagnostics are disabled. The first observed turn sets
+    /// the workspace baseline.
+    pub fn from_settings(settings: &AutoDriveSettings) -> Option<Self> {
+        settings.diagnostics_enabled.then(|| Self {
+            threshold: (settings.loop_threshold as usize).max(2),
+            prompts: VecDeque::new(),
+            replies: VecDeque::new(),
+            last_fingerprint: None,
+            turns_without_changes: 0,
+        })
+    }
+
+    /// Records the prompt the coordinator sent to the CLI.
+    pub fn record_prompt(&mut self, prompt: &str) {
+        push_bounded(&mut self.prompts, prompt.to_string(), self.threshold);
+    }
+
+    /// Records a finished CLI turn and the workspace fingerprint taken after
+    /// it, and returns at most one finding; the history behind a finding is
+    /// cleared so it is not reported every turn.
+    pub fn observe_turn(
+        &mut self,
+        conversation: &[ResponseItem],
+        fingerprint: Option<u64>,
+    ) -> Option<LoopFinding> {
+        if let Some(reply) = last_cli_reply(conversation) {
+            push_bounded(&mut self.replies, reply, self.threshold);
+        }
+        self.observe_changes(fingerprint);
+        self.check()
+    }
+
+    fn observe_changes(&mut self, fingerprint: Option<u64>) {
+        if fingerprint.is_some() && fingerprint == self.last_fingerprint {
+            self.turns_without_changes += 1;
+        } else {
+            self.turns_without_changes = 0;
+        }
+        self.last_fingerprint = fingerprint;
+    }
+
+    fn check(&mut self) -> Option<LoopFinding> {
+        let threshold = self.threshold;
+        if all_near_identical(&self.prompts, threshold) {
+            self.prompts.clear();
+            return Some(LoopFinding {
+                alert_type: DiagnosticAlertType::LoopDetected,
+                message: format!("Near-identical CLI prompt sent {threshold} turns in a row"),
+                correction: format!(
+                    "Auto Drive diagnostics: you sent the CLI nearly the same prompt {threshold} turns in a row and the work is not converging. Do not repeat it. Work out why the earlier attempts failed, then take a different approach, narrow the task, or finish as failed if the goal is blocked."
+                ),
+            });
+        }
+        if all_near_identical(&self.replies, threshold) {
+            self.replies.clear();
+            return Some(LoopFinding {
+                alert_type: DiagnosticAlertType::RepetitiveResponse,
+                message: format!(
+                    "CLI replied with near-identical output {threshold} turns in a row"
+                ),
+                correction: format!(
+                    "Auto Drive diagnostics: the CLI has replied with nearly the same output {threshold} turns in a row, so the current approach is stuck. Read that output for the underlying blocker and give the CLI a different instruction that addresses it."
+                ),
+            });
+        }
+        let stall_turns = threshold * 2;
+        if self.turns_without_changes >= stall_turns {
+            self.turns_without_changes = 0;
+            return Some(LoopFinding {
+                alert_type: DiagnosticAlertType::GoalDrift,
+                message: format!("{stall_turns} CLI turns without any file changes"),
+                correction: format!(
+                    "Auto Drive diagnostics: {stall_turns} CLI turns have passed without any change to the files in the workspace. Re-read the primary goal and direct the CLI to make concrete progress on it, or finish if the goal is already met."
+                ),
+            });
+        }
+        None
+    }
+}
+
+fn push_bounded(window: &mut VecDeque<String>, text: String, capacity: usize) {
+    if window.len() >= capacity {
+        window.pop_front();
+    }
+    window.push_back(text);
+}
+
+/// True once the window is full and every entry is near-identical to the
+/// newest one.
+fn all_near_identical(window: &VecDeque<String>, threshold: usize) -> bool {
+    let Some(newest) = window.back() else {
+        return false;
+    };
+    window.len() >= threshold
+        && window
+            .iter()
+            .all(|text| ngram_similarity(text, newest, NGRAM_SIZE) >= NEAR_IDENTICAL_SIMILARITY)
+}
+
+/// Hash of the workspace's uncommitted state plus `HEAD`, including the size
+/// and modification time of untracked files; `None` outside a git repository.
+/// Runs git, so call it off the coordinator thread.
+pub(crate) fn workspace_fingerprint(cwd: &Path) -> Option<u64> {
+    let git = |args: &[&str]| {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(cwd)
+            .output()
+            .ok()?;
+        output.status.success().then_some(output.stdout)
+    };
+    let mut hasher = DefaultHasher::new();
+    git(&["rev-parse", "HEAD"])?.hash(&mut hasher);
+    git(&["status", "--porcelain"])?.hash(&mut hasher);
+    git(&["diff", "HEAD"])?.hash(&mut hasher);
+    let untracked = git(&["ls-files", "--others", "--exclude-standard", "-z"])?;
+    for path in untracked
+        .split(|byte| *byte == 0)
+        .filter(|path| !path.is_empty())
+    {
+        let path = String::from_utf8_lossy(path);
+        if let Ok(metadata) = std::fs::metadata(cwd.join(path.as_ref())) {
+            metadata.len().hash(&mut hasher);
+            metadata.modified().ok().hash(&mut hasher);
+        }
+    }
+    Some(hasher.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auto_coordinator::make_message;
+    use crate::test_support::committed_repo;
+    use pretty_assertions::assert_eq;
+
+    fn detector(threshold: usize) -> LoopDetector {
+        LoopDetector {
+            threshold,
+            prompts: VecDeque::new(),
+            replies: VecDeque::new(),
+            last_fingerprint: Some(1),
+            turns_without_changes: 0,
+        }
+    }
+
+    #[test]
+    fn repeated_prompts_and_replies_are_reported_once() {
+        let mut detector = detector(3);
+        for attempt in ["first", "second", "third"] {
+            detector.record_prompt(&format!(
+                "Run cargo test -p parser and fix the failing snapshot test ({attempt})"
+            ));
+        }
+        let finding = detector.check().expect("prompt loop");
+        assert_eq!(finding.alert_type, DiagnosticAlertType::LoopDetected);
+        assert!(detector.check().is_none());
+
+        let conversation = vec![make_message(
+            "assistant",
+            "The snapshot test still fails with the same error in parser.rs".to_string(),
+        )];
+        for _ in 0..3 {
+            push_bounded(
+                &mut detector.replies,
+                last_cli_reply(&conversation).expect("reply"),
+                3,
+            );
+        }
+        let finding = detector.check().expect("reply loop");
+        assert_eq!(finding.alert_type, DiagnosticAlertType::RepetitiveResponse);
+    }
+
+    #[test]
+    fn turns_without_file_changes_count_as_drift() {
+        let mut detector = detector(2);
+        detector.record_prompt("Survey the crate layout");
+        detector.record_prompt("Write the tokenizer module");
+        for _ in 0..3 {
+            detector.observe_changes(Some(1));
+            assert!(detector.check().is_none());
+        }
+        detector.observe_changes(Some(1));
+        let finding = detector.check().expect("stall");
+        assert_eq!(finding.alert_type, DiagnosticAlertType::GoalDrift);
+        assert_eq!(finding.message, "4 CLI turns without any file changes");
+
+        detector.observe_changes(Some(2));
+        assert_eq!(detector.turns_without_changes, 0);
+        // Outside a git repository the stall check never fires.
+        detector.observe_changes(None);
+        detector.observe_changes(None);
+        assert_eq!(detector.turns_without_changes, 0);
+    }
+
+    #[test]
+    fn untracked_edits_change_the_fingerprint() {
+        let repo = committed_repo();
+        std::fs::write(repo.path().join("notes.txt"), "draft").unwrap();
+        let before = workspace_fingerprint(repo.path());
+        assert!(before.is_some());
+        assert_eq!(workspace_fingerprint(repo.path()), before);
+
+        std::fs::write(repo.path().join("notes.txt"), "second draft").unwrap();
+        assert_ne!(workspace_fingerprint(repo.path()), before);
+    }
+}
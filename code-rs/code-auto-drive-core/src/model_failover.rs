@@ -0,0 +1,73 @@
+//! Coordinator model failover chain.
+//!
+//! `auto_drive.model_fallbacks` lists models the coordinator switches to, in
+//! order, when the active model fails in a way retrying it will not fix: a
+//! fatal model error, exhausted quota or auth, or decisions that keep failing
+//! schema validation. Each entry is tried once; when the chain runs out the
+//! run degrades to the simple loop or fails as before.
+
+use std::collections::VecDeque;
+
+use code_core::config_types::AutoDriveSettings;
+
+#[derive(Debug)]
+pub(crate) struct ModelFailover {
+    remaining: VecDeque<String>,
+}
+
+impl ModelFailover {
+    /// Blank entries, duplicates, and the coordinator model itself are
+    /// dropped from the chain.
+    pub fn from_settings(settings: &AutoDriveSettings, coordinator_model: &str) -> Self {
+        let mut remaining: VecDeque<String> = VecDeque::new();
+        for model in settings.model_fallbacks.iter().map(|model| model.trim()) {
+            if model.is_empty()
+                || model.eq_ignore_ascii_case(coordinator_model)
+                || remaining
+                    .iter()
+                    .any(|seen| seen.eq_ignore_ascii_case(model))
+            {
+                continue;
+            }
+            remaining.push_back(model.to_string());
+        }
+        Self { remaining }
+    }
+
+    /// Next model to try instead of `failed_model`, skipping entries equal to
+    /// it (the active model may have changed through other fallbacks).
+    pub fn next(&mut self, failed_model: &str) -> Option<String> {
+        while let Some(model) = self.remaining.pop_front() {
+            if !model.eq_ignore_ascii_case(failed_model) {
+                return Some(model);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn walks_the_configured_chain_once() {
+        let settings = AutoDriveSettings {
+            model_fallbacks: vec![
+                "gpt-5.1".to_string(),
+                " ".to_string(),
+                "gpt-5.1-codex-max".to_string(),
+                "GPT-5.1".to_string(),
+                "gpt-5-codex".to_string(),
+            ],
+            ..AutoDriveSettings::default()
+        };
+        let mut failover = ModelFailover::from_settings(&settings, "gpt-5-codex");
+
+        assert_eq!(failover.next("gpt-5-codex").as_deref(), Some("gpt-5.1"));
+        // The request-level default-model fallback already landed on the next
+        // entry, so it is skipped.
+        assert_eq!(failover.next("gpt-5.1-codex-max"), None);
+    }
+}
@@ -16,6 +16,7 @@ use futures::Stream;
 use futures::StreamExt;
 use futures::future::join_all;
 use tokio::sync::Semaphore;
+use tokio::sync::mpsc;
 
 use code_protocol::models::ContentItem;
 use code_protocol::models::ResponseItem;
@@ -335,6 +336,28 @@ pub async fn execute_parallel_roles(
     base_prompt: &str,
     model: &str,
     max_concurrent_agents: i32,
+) -> Result<Vec<ParallelResult>> {
+    execute_parallel_roles_streaming(
+        client,
+        roles,
+        base_prompt,
+        model,
+        max_concurrent_agents,
+        None,
+    )
+    .await
+}
+
+/// Like [`execute_parallel_roles`], but also sends each role's output text to
+/// `output` as `(role name, chunk)` while it streams, so callers that cancel
+/// the run still have the partial output.
+pub async fn execute_parallel_roles_streaming(
+    client: Arc<dyn ParallelModel>,
+    roles: Vec<ParallelRole>,
+    base_prompt: &str,
+    model: &str,
+    max_concurrent_agents: i32,
+    output: Option<mpsc::UnboundedSender<(String, String)>>,
 ) -> Result<Vec<ParallelResult>> {
     if roles.is_empty() {
         return Ok(Vec::new());
@@ -347,6 +370,7 @@ pub async fn execute_parallel_roles(
         let semaphore = semaphore.clone();
         let base_prompt = base_prompt.to_string();
         let model = model.to_string();
+        let output = output.clone();
 
         async move {
             let permit = match semaphore.acquire_owned().await {
@@ -365,18 +389,25 @@ pub async fn execute_parallel_roles(
             prompt.model_override = Some(model);
 
             let mut response = String::new();
+            let role_name = role.name();
+            let mut push_text = |text: &str| {
+                response.push_str(text);
+                if let Some(output) = &output {
+                    let _ = output.send((role_name.clone(), text.to_string()));
+                }
+            };
             let result = match client.stream_prompt(prompt).await {
                 Ok(mut stream) => {
                     while let Some(event) = stream.next().await {
                         match event {
                             Ok(ResponseEvent::OutputTextDelta { delta, .. }) => {
-                                response.push_str(&delta);
+                                push_text(&delta);
                             }
                             Ok(ResponseEvent::OutputItemDone { item, .. }) => {
                                 if let ResponseItem::Message { content, .. } = item {
                                     for item in content {
                                         if let ContentItem::OutputText { text } = item {
-                                            response.push_str(&text);
+                                            push_text(&text);
                                         }
                                     }
                                 }
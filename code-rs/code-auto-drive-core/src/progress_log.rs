@@ -10,6 +10,8 @@ use std::path::PathBuf;
 
 use chrono::Utc;
 
+use crate::turn_changes::TurnChanges;
+
 /// 进度类型。
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ProgressType {
@@ -58,6 +60,29 @@ impl ProgressEntry {
     }
 }
 
+impl From<&TurnChanges> for ProgressEntry {
+    /// 某个协调器决策所触发的 CLI 轮次改动了哪些文件。
+    fn from(changes: &TurnChanges) -> Self {
+        let files = changes
+            .files
+            .iter()
+            .map(|file| format!("{} {}", file.change.marker(), file.path))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let note = match changes.patch_path.as_ref() {
+            Some(path) => format!("{files}; patch {}", path.display()),
+            None => files,
+        };
+        Self::new(
+            ProgressType::Change,
+            format!("decision {}", changes.seq),
+            "-",
+            changes.summary(),
+            note,
+        )
+    }
+}
+
 /// 进度日志器。
 pub struct ProgressLogger {
     path: PathBuf,
@@ -2,6 +2,8 @@ use std::time::Duration;
 use std::time::Instant;
 
 use anyhow::Error;
+use code_common::elapsed::format_duration;
+use code_core::config_types::AutoDriveRetrySettings;
 use rand::Rng;
 use rand::SeedableRng;
 use rand::rngs::StdRng;
@@ -9,22 +11,35 @@ use tokio::time;
 use tokio_util::sync::CancellationToken;
 use tracing::warn;
 
+/// Upper bound of the random delay added to rate-limit waits.
+const RATE_LIMIT_JITTER_MAX: Duration = Duration::from_secs(3);
+
 #[derive(Debug, Clone)]
 pub(crate) struct RetryOptions {
     pub base_delay: Duration,
     pub factor: f64,
     pub max_delay: Duration,
     pub max_elapsed: Duration,
+    /// Attempts before giving up; `None` retries until `max_elapsed`.
+    pub max_attempts: Option<u32>,
+    /// Randomize backoff delays and rate-limit waits.
+    pub jitter: bool,
+    /// Added to the reset time a rate-limited provider announces.
+    pub rate_limit_buffer: Duration,
     pub jitter_seed: Option<u64>,
 }
 
 impl RetryOptions {
-    pub fn with_defaults(max_elapsed: Duration) -> Self {
+    /// Options from `[auto_drive.retry]`.
+    pub fn from_settings(settings: &AutoDriveRetrySettings) -> Self {
         Self {
-            base_delay: Duration::from_secs(1),
+            base_delay: Duration::from_millis(settings.base_delay_ms),
             factor: 2.0,
-            max_delay: Duration::from_secs(15 * 60),
-            max_elapsed,
+            max_delay: Duration::from_secs(settings.max_delay_seconds),
+            max_elapsed: Duration::from_secs(settings.max_elapsed_seconds),
+            max_attempts: settings.max_attempts.filter(|attempts| *attempts > 0),
+            jitter: settings.jitter,
+            rate_limit_buffer: Duration::from_secs(settings.rate_limit_buffer_seconds),
             jitter_seed: None,
         }
     }
@@ -56,6 +71,10 @@ pub(crate) enum RetryError {
         elapsed: Duration,
         last_error: Error,
     },
+    /// Gave up before the retry window ran out: attempts were used up, or a
+    /// rate limit resets after the window ends.
+    #[error("{reason}")]
+    Exhausted { reason: String, last_error: Error },
     #[error(transparent)]
     Fatal(Error),
 }
@@ -100,16 +119,40 @@ where
                         last_error: error,
                     });
                 }
+                if let Some(max_attempts) = options.max_attempts
+                    && attempt >= max_attempts
+                {
+                    return Err(RetryError::Exhausted {
+                        reason: format!("gave up after {attempt} attempts"),
+                        last_error: error,
+                    });
+                }
 
                 match classify(&error) {
                     RetryDecision::Fatal(fatal) => return Err(RetryError::Fatal(fatal)),
                     RetryDecision::RateLimited { wait_until, reason } => {
+                        let jitter = if options.jitter {
+                            random_delay(RATE_LIMIT_JITTER_MAX, &mut rng)
+                        } else {
+                            Duration::ZERO
+                        };
+                        let wait_until = wait_until + options.rate_limit_buffer + jitter;
                         let now = Instant::now();
                         if wait_until <= now {
                             warn!(attempt, elapsed = ?elapsed, "{reason}; retrying immediately");
                             continue;
                         }
                         let sleep = wait_until.duration_since(now);
+                        if elapsed + sleep > options.max_elapsed {
+                            return Err(RetryError::Exhausted {
+                                reason: format!(
+                                    "{reason}; the limit resets in {}, after the {} retry window",
+                                    format_duration(sleep),
+                                    format_duration(options.max_elapsed)
+                                ),
+                                last_error: error,
+                            });
+                        }
                         warn!(attempt, elapsed = ?elapsed, wait = ?sleep, resume_at = ?wait_until, "{reason}");
                         status_cb(RetryStatus {
                             attempt,
@@ -157,13 +200,19 @@ fn compute_delay(options: &RetryOptions, attempt: u32, rng: &mut StdRng) -> Dura
     let exponent = attempt.saturating_sub(1) as i32;
     let factor = options.factor.powi(exponent);
     let base = options.base_delay.as_secs_f64() * factor;
-    let capped = base.min(options.max_delay.as_secs_f64());
-    if capped <= f64::EPSILON {
-        return Duration::ZERO;
+    let capped = Duration::from_secs_f64(base.min(options.max_delay.as_secs_f64()));
+    if options.jitter {
+        random_delay(capped, rng)
+    } else {
+        capped
     }
+}
 
-    let jitter = rng.random_range(0.0..capped);
-    Duration::from_secs_f64(jitter)
+fn random_delay(max: Duration, rng: &mut StdRng) -> Duration {
+    if max.as_secs_f64() <= f64::EPSILON {
+        return Duration::ZERO;
+    }
+    Duration::from_secs_f64(rng.random_range(0.0..max.as_secs_f64()))
 }
 
 async fn wait_with_cancel(
@@ -179,3 +228,61 @@ async fn wait_with_cancel(
         _ = cancel.cancelled() => Err(RetryError::Aborted),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+    use pretty_assertions::assert_eq;
+
+    fn options(settings: AutoDriveRetrySettings) -> RetryOptions {
+        RetryOptions {
+            jitter_seed: Some(7),
+            ..RetryOptions::from_settings(&settings)
+        }
+    }
+
+    #[tokio::test]
+    async fn attempts_and_rate_limits_beyond_the_window_fail_fast() {
+        let cancel = CancellationToken::new();
+        let mut attempts = 0;
+        let result: Result<(), RetryError> = retry_with_backoff(
+            || {
+                attempts += 1;
+                async { Err(anyhow!("server error")) }
+            },
+            |_| RetryDecision::RetryAfterBackoff {
+                reason: "server error".to_string(),
+            },
+            options(AutoDriveRetrySettings {
+                max_attempts: Some(3),
+                base_delay_ms: 0,
+                ..AutoDriveRetrySettings::default()
+            }),
+            &cancel,
+            |_| {},
+        )
+        .await;
+        assert_eq!(attempts, 3);
+        assert!(matches!(
+            result,
+            Err(RetryError::Exhausted { reason, .. }) if reason == "gave up after 3 attempts"
+        ));
+
+        let result: Result<(), RetryError> = retry_with_backoff(
+            || async { Err(anyhow!("429")) },
+            |_| RetryDecision::RateLimited {
+                wait_until: Instant::now() + Duration::from_secs(3600),
+                reason: "rate limited".to_string(),
+            },
+            options(AutoDriveRetrySettings {
+                max_elapsed_seconds: 600,
+                ..AutoDriveRetrySettings::default()
+            }),
+            &cancel,
+            |_| panic!("must not wait out the rate limit"),
+        )
+        .await;
+        assert!(matches!(result, Err(RetryError::Exhausted { .. })));
+    }
+}
@@ -1,8 +1,13 @@
 //! Role communication channel for inter-role messaging.
 //!
 //! Enables roles to communicate during task execution for better coordination.
+//! [`RoleTurns`] builds on it to let the coordinator roles configured in
+//! `auto_drive.roles` take alternating turns driving one conversation, each
+//! handing a note to the next when its turn ends.
 
 use std::collections::HashMap;
+
+use code_core::config_types::AutoDriveRole;
 use tokio::sync::mpsc;
 
 /// Message types between roles
@@ -63,6 +68,12 @@ pub enum RoleMessage {
         from_stage: Option<String>,
         stage: String,
     },
+    /// Coordinator role ends its turn and passes the conversation on
+    Handoff {
+        from_role: String,
+        to_role: String,
+        note: String,
+    },
 }
 
 /// Sender handle for a role
@@ -118,6 +129,17 @@ impl RoleChannelHub {
         }
     }
 
+    /// Sends a message to a specific role without waiting for buffer space
+    pub fn try_send_to(&self, role: &str, msg: RoleMessage) -> Result<(), &'static str> {
+        let Some(tx) = self.channels.get(role) else {
+            return Err("Role not found");
+        };
+        tx.try_send(msg).map_err(|err| match err {
+            mpsc::error::TrySendError::Full(_) => "Channel full",
+            mpsc::error::TrySendError::Closed(_) => "Channel closed",
+        })
+    }
+
     /// Checks if a role is registered
     pub fn has_role(&self, role: &str) -> bool {
         self.channels.contains_key(role)
@@ -138,6 +160,83 @@ impl Default for RoleChannelHub {
     }
 }
 
+/// Coordinator roles taking alternating turns on one conversation.
+///
+/// Each role has an inbox on a [`RoleChannelHub`]. Ending a turn sends a
+/// [`RoleMessage::Handoff`] to the next role, which reads it, together with
+/// anything else sent to it, when its turn starts.
+pub struct RoleTurns {
+    roles: Vec<AutoDriveRole>,
+    hub: RoleChannelHub,
+    inboxes: Vec<RoleReceiver>,
+    /// Messages the current role has received during its turn.
+    received: Vec<RoleMessage>,
+    current: usize,
+}
+
+impl RoleTurns {
+    /// `None` unless at least two roles with distinct, non-empty names are
+    /// configured.
+    pub fn from_settings(roles: &[AutoDriveRole]) -> Option<Self> {
+        let roles: Vec<AutoDriveRole> = roles
+            .iter()
+            .filter(|role| !role.name.trim().is_empty())
+            .cloned()
+            .collect();
+        let mut hub = RoleChannelHub::new(roles.len().max(1) * 4);
+        let mut inboxes = Vec::with_capacity(roles.len());
+        for role in &roles {
+            if hub.has_role(&role.name) {
+                return None;
+            }
+            inboxes.push(hub.register(role.name.clone()));
+        }
+        (roles.len() >= 2).then_some(Self {
+            roles,
+            hub,
+            inboxes,
+            received: Vec::new(),
+            current: 0,
+        })
+    }
+
+    /// The role whose turn it is.
+    pub fn current(&self) -> &AutoDriveRole {
+        &self.roles[self.current]
+    }
+
+    /// Messages for the current role, including ones that arrived since the
+    /// last call. They stay available until the role hands off.
+    pub fn inbox(&mut self) -> &[RoleMessage] {
+        while let Ok(message) = self.inboxes[self.current].try_recv() {
+            self.received.push(message);
+        }
+        &self.received
+    }
+
+    /// Sends `msg` to `role`'s inbox.
+    pub fn send_to(&self, role: &str, msg: RoleMessage) -> Result<(), &'static str> {
+        self.hub.try_send_to(role, msg)
+    }
+
+    /// Ends the current role's turn, leaves `note` for the next role, and
+    /// returns it.
+    pub fn hand_off(&mut self, note: impl Into<String>) -> &AutoDriveRole {
+        let next = (self.current + 1) % self.roles.len();
+        let message = RoleMessage::Handoff {
+            from_role: self.roles[self.current].name.clone(),
+            to_role: self.roles[next].name.clone(),
+            note: note.into(),
+        };
+        if let Err(err) = self.hub.try_send_to(&self.roles[next].name, message) {
+            tracing::warn!("coordinator handoff dropped: {err}");
+        }
+        self.received.clear();
+        self.current = next;
+        self.current()
+    }
+}
+
 /// Helper struct for building coordination messages
 pub struct CoordinationBuilder;
 
@@ -222,6 +321,7 @@ impl CoordinationBuilder {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use pretty_assertions::assert_eq;
     use proptest::prelude::*;
     use tokio::runtime::Runtime;
 
@@ -284,6 +384,46 @@ mod tests {
         assert_eq!(received, msg);
     }
 
+    fn role(name: &str) -> AutoDriveRole {
+        AutoDriveRole {
+            name: name.to_string(),
+            model: None,
+            prompt: None,
+        }
+    }
+
+    #[test]
+    fn roles_alternate_and_receive_handoff_notes() {
+        assert!(RoleTurns::from_settings(&[role("architect")]).is_none());
+        assert!(RoleTurns::from_settings(&[role("architect"), role("architect")]).is_none());
+
+        let mut turns =
+            RoleTurns::from_settings(&[role("architect"), role(" "), role("implementer")]).unwrap();
+        assert_eq!(turns.current().name, "architect");
+        assert!(turns.inbox().is_empty());
+
+        let next = turns.hand_off("Split the parser into lexer and grammar modules");
+        assert_eq!(next.name, "implementer");
+        turns
+            .send_to(
+                "implementer",
+                CoordinationBuilder::guidance("implementer", "Keep the public API"),
+            )
+            .unwrap();
+        let handoff = RoleMessage::Handoff {
+            from_role: "architect".to_string(),
+            to_role: "implementer".to_string(),
+            note: "Split the parser into lexer and grammar modules".to_string(),
+        };
+        assert_eq!(turns.inbox()[0], handoff);
+        assert_eq!(turns.inbox().len(), 2);
+
+        assert_eq!(turns.hand_off("Lexer extracted").name, "architect");
+        assert_eq!(turns.inbox().len(), 1);
+        assert_eq!(turns.hand_off("Looks right").name, "implementer");
+        assert_eq!(turns.inbox().len(), 1);
+    }
+
     proptest! {
         #[test]
         fn property_role_message_delivery(role in ".{1,10}", result in ".{1,20}") {
@@ -0,0 +1,556 @@
+//! End-of-run report for an exec Auto Drive session.
+//!
+//! The event log can replay a run but is a raw stream, and the audit log only
+//! keeps per-operation summaries. The report condenses a run for review: every
+//! coordinator decision (status, title, the prompt sent to the CLI, the agents
+//! launched), the files each decision's CLI turn changed, the token metrics
+//! reported after each coordinator turn, history compactions, alerts, and the
+//! final outcome. exec writes it as
+//! `$CODE_HOME/auto_drive/reports/<session>.json` with a Markdown rendering
+//! next to it.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use chrono::DateTime;
+use chrono::Utc;
+use code_core::protocol::TokenUsage;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::AutoCoordinatorEvent;
+use crate::AutoCoordinatorStatus;
+use crate::AutoTurnAgentsTiming;
+use crate::session_metrics::AttributedUsage;
+use crate::session_metrics::UsageSource;
+use crate::turn_changes::TurnChanges;
+
+const RUN_REPORT_SUBDIR: &str = "auto_drive/reports";
+
+/// Directory that holds run reports under `code_home`.
+pub fn default_report_dir(code_home: &Path) -> PathBuf {
+    code_home.join(RUN_REPORT_SUBDIR)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunReport {
+    pub session_id: String,
+    pub goal: String,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub decisions: Vec<ReportDecision>,
+    /// Files changed by the CLI turn of each decision, keyed by its `seq`.
+    #[serde(default)]
+    pub turn_changes: Vec<TurnChanges>,
+    pub token_turns: Vec<ReportTokenTurn>,
+    /// Token usage per decision, split between the coordinator, the CLI
+    /// turn, and each agent.
+    #[serde(default)]
+    pub usage_breakdown: Vec<AttributedUsage>,
+    pub compactions: Vec<ReportCompaction>,
+    /// Diagnostic and budget alerts, intervention requests, and coordinator
+    /// model switches, in order.
+    pub alerts: Vec<String>,
+    pub outcome: Option<RunOutcome>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportDecision {
+    pub seq: u64,
+    pub at: DateTime<Utc>,
+    pub status: AutoCoordinatorStatus,
+    pub status_title: Option<String>,
+    pub status_sent_to_user: Option<String>,
+    /// Prompt the coordinator sent to the CLI agent.
+    pub cli_prompt: Option<String>,
+    pub agents_timing: Option<AutoTurnAgentsTiming>,
+    pub agents: Vec<ReportAgent>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportAgent {
+    pub prompt: String,
+    pub write: bool,
+    pub models: Option<Vec<String>>,
+}
+
+/// Coordinator token usage after one of its turns.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportTokenTurn {
+    pub turn: u32,
+    pub last_turn: TokenUsage,
+    pub total: TokenUsage,
+    pub duplicate_items: u32,
+    pub replay_updates: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportCompaction {
+    pub at: DateTime<Utc>,
+    /// Sequence number of the last decision before the compaction.
+    pub after_decision: Option<u64>,
+    /// Items left in the conversation afterwards.
+    pub items: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunOutcome {
+    pub success: bool,
+    /// Machine-readable failure reason, as in exec's JSON output.
+    pub failure_reason: Option<String>,
+    pub cli_turns: usize,
+    pub final_message: Option<String>,
+}
+
+impl RunReport {
+    pub fn new(session_id: impl Into<String>, goal: impl Into<String>) -> Self {
+        Self {
+            session_id: session_id.into(),
+            goal: goal.into(),
+            started_at: Utc::now(),
+            finished_at: None,
+            decisions: Vec::new(),
+            turn_changes: Vec::new(),
+            token_turns: Vec::new(),
+            usage_breakdown: Vec::new(),
+            compactions: Vec::new(),
+            alerts: Vec::new(),
+            outcome: None,
+        }
+    }
+
+    /// Records the parts of a coordinator event the report keeps.
+    pub fn observe(&mut self, event: &AutoCoordinatorEvent) {
+        match event {
+            AutoCoordinatorEvent::Decision {
+                seq,
+                status,
+                status_title,
+                status_sent_to_user,
+                cli,
+                agents_timing,
+                agents,
+                ..
+            } => self.decisions.push(ReportDecision {
+                seq: *seq,
+                at: Utc::now(),
+                status: *status,
+                status_title: status_title.clone(),
+                status_sent_to_user: status_sent_to_user.clone(),
+                cli_prompt: cli.as_ref().map(|cli| cli.prompt.clone()),
+                agents_timing: *agents_timing,
+                agents: agents
+                    .iter()
+                    .map(|agent| ReportAgent {
+                        prompt: agent.prompt.clone(),
+                        write: agent.write,
+                        models: agent.models.clone(),
+                    })
+                    .collect(),
+            }),
+            AutoCoordinatorEvent::TokenMetrics {
+                total_usage,
+                last_turn_usage,
+                turn_count,
+                duplicate_items,
+                replay_updates,
+                breakdown,
+            } => {
+                self.usage_breakdown = breakdown.clone();
+                let turn = ReportTokenTurn {
+                    turn: *turn_count,
+                    last_turn: last_turn_usage.clone(),
+                    total: total_usage.clone(),
+                    duplicate_items: *duplicate_items,
+                    replay_updates: *replay_updates,
+                };
+                // Metrics may be re-sent for the same turn; keep the latest.
+                match self.token_turns.last_mut() {
+                    Some(last) if last.turn == turn.turn => *last = turn,
+                    _ => self.token_turns.push(turn),
+                }
+            }
+            AutoCoordinatorEvent::CompactedHistory { conversation, .. } => {
+                self.compactions.push(ReportCompaction {
+                    at: Utc::now(),
+                    after_decision: self.decisions.last().map(|decision| decision.seq),
+                    items: conversation.len(),
+                });
+            }
+            AutoCoordinatorEvent::DiagnosticAlert {
+                alert_type,
+                message,
+            } => self
+                .alerts
+                .push(format!("diagnostic ({alert_type:?}): {message}")),
+            AutoCoordinatorEvent::BudgetAlert {
+                alert_type,
+                message,
+            } => self
+                .alerts
+                .push(format!("budget ({alert_type:?}): {message}")),
+            AutoCoordinatorEvent::InterventionRequired { reason } => {
+                self.alerts.push(format!("intervention required: {reason}"));
+            }
+            AutoCoordinatorEvent::CoordinatorModelSwitched { from, to, reason } => {
+                self.alerts
+                    .push(format!("coordinator model {from} -> {to}: {reason}"));
+            }
+            AutoCoordinatorEvent::CoordinatorFailover { from, to, reason } => {
+                self.alerts
+                    .push(format!("coordinator failover {from} -> {to}: {reason}"));
+            }
+            AutoCoordinatorEvent::CoordinatorDegraded { reason } => {
+                self.alerts
+                    .push(format!("coordinator unavailable, simple loop: {reason}"));
+            }
+            AutoCoordinatorEvent::Thinking { .. }
+            | AutoCoordinatorEvent::Action { .. }
+            | AutoCoordinatorEvent::UserReply { .. }
+            | AutoCoordinatorEvent::StopAck
+            | AutoCoordinatorEvent::CheckpointSaved { .. }
+            | AutoCoordinatorEvent::CheckpointRestored { .. } => {}
+        }
+    }
+
+    pub fn record_turn_changes(&mut self, changes: TurnChanges) {
+        self.turn_changes.push(changes);
+    }
+
+    pub fn finish(&mut self, outcome: RunOutcome) {
+        self.finished_at = Some(Utc::now());
+        self.outcome = Some(outcome);
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "# Auto Drive run {}", self.session_id);
+        let _ = writeln!(out);
+        let _ = writeln!(out, "**Goal:** {}", self.goal.trim());
+        let _ = writeln!(out);
+        let _ = writeln!(out, "- Started: {}", self.started_at.to_rfc3339());
+        if let Some(finished_at) = self.finished_at {
+            let _ = writeln!(out, "- Finished: {}", finished_at.to_rfc3339());
+        }
+        if let Some(outcome) = self.outcome.as_ref() {
+            let result = match outcome.failure_reason.as_deref() {
+                _ if outcome.success => "success".to_string(),
+                Some(reason) => format!("failed ({reason})"),
+                None => "failed".to_string(),
+            };
+            let _ = writeln!(out, "- Outcome: {result}");
+            let _ = writeln!(out, "- CLI turns: {}", outcome.cli_turns);
+        }
+        if let Some(last) = self.token_turns.last() {
+            let _ = writeln!(
+                out,
+                "- Coordinator tokens: {} ({} input, {} output)",
+                last.total.total_tokens, last.total.input_tokens, last.total.output_tokens
+            );
+        }
+
+        let _ = writeln!(out);
+        let _ = writeln!(out, "## Decisions");
+        let _ = writeln!(out);
+        if self.decisions.is_empty() {
+            let _ = writeln!(out, "None.");
+        }
+        for decision in &self.decisions {
+            let title = decision.status_title.as_deref().unwrap_or("(untitled)");
+            let _ = writeln!(out, "### {}. {title} ({:?})", decision.seq, decision.status);
+            let _ = writeln!(out);
+            if let Some(status) = decision.status_sent_to_user.as_deref() {
+                let _ = writeln!(out, "{}", status.trim());
+                let _ = writeln!(out);
+            }
+            if let Some(prompt) = decision.cli_prompt.as_deref() {
+                let _ = writeln!(out, "CLI prompt:");
+                let _ = writeln!(out);
+                let _ = writeln!(out, "```text\n{}\n```", prompt.trim());
+                let _ = writeln!(out);
+            }
+            if !decision.agents.is_empty() {
+                let timing = decision
+                    .agents_timing
+                    .map(|timing| format!(" ({timing:?})"))
+                    .unwrap_or_default();
+                let _ = writeln!(out, "Agents{timing}:");
+                let _ = writeln!(out);
+                for agent in &decision.agents {
+                    let mode = if agent.write { "write" } else { "read-only" };
+                    let models = agent
+                        .models
+                        .as_ref()
+                        .map(|models| format!(", {}", models.join(", ")))
+                        .unwrap_or_default();
+                    let _ = writeln!(out, "- [{mode}{models}] {}", first_line(&agent.prompt));
+                }
+                let _ = writeln!(out);
+            }
+            for changes in self
+                .turn_changes
+                .iter()
+                .filter(|changes| changes.seq == decision.seq)
+            {
+                let _ = writeln!(out, "Changes: {}", changes.summary());
+                let _ = writeln!(out);
+                for file in &changes.files {
+                    let _ = writeln!(out, "- {} {}", file.change.marker(), file.path);
+                }
+                if let Some(path) = changes.patch_path.as_ref() {
+                    let _ = writeln!(out);
+                    let _ = writeln!(out, "Patch: `{}`", path.display());
+                }
+                let _ = writeln!(out);
+            }
+        }
+
+        if !self.token_turns.is_empty() {
+            let _ = writeln!(out, "## Coordinator tokens per turn");
+            let _ = writeln!(out);
+            let _ = writeln!(out, "| Turn | Input | Cached | Output | Running total |");
+            let _ = writeln!(out, "| ---: | ---: | ---: | ---: | ---: |");
+            for turn in &self.token_turns {
+                let _ = writeln!(
+                    out,
+                    "| {} | {} | {} | {} | {} |",
+                    turn.turn,
+                    turn.last_turn.input_tokens,
+                    turn.last_turn.cached_input_tokens,
+                    turn.last_turn.output_tokens,
+                    turn.total.total_tokens
+                );
+            }
+            let _ = writeln!(out);
+        }
+
+        if !self.usage_breakdown.is_empty() {
+            let _ = writeln!(out, "## Tokens by source");
+            let _ = writeln!(out);
+            let _ = writeln!(out, "| Decision | Source | Input | Output | Total |");
+            let _ = writeln!(out, "| ---: | --- | ---: | ---: | ---: |");
+            for entry in &self.usage_breakdown {
+                let source = match &entry.source {
+                    UsageSource::Coordinator => "coordinator".to_string(),
+                    UsageSource::CliTurn => "CLI turn".to_string(),
+                    UsageSource::Agent { prompt_hash } => format!("agent `{prompt_hash}`"),
+                };
+                let _ = writeln!(
+                    out,
+                    "| {} | {source} | {} | {} | {} |",
+                    entry.seq,
+                    entry.usage.input_tokens,
+                    entry.usage.output_tokens,
+                    entry.usage.total_tokens
+                );
+            }
+            let _ = writeln!(out);
+        }
+
+        if !self.compactions.is_empty() {
+            let _ = writeln!(out, "## Compactions");
+            let _ = writeln!(out);
+            for compaction in &self.compactions {
+                let after = compaction
+                    .after_decision
+                    .map(|seq| format!(" after decision {seq}"))
+                    .unwrap_or_default();
+                let _ = writeln!(
+                    out,
+                    "- {}{after}: {} items kept",
+                    compaction.at.to_rfc3339(),
+                    compaction.items
+                );
+            }
+            let _ = writeln!(out);
+        }
+
+        if !self.alerts.is_empty() {
+            let _ = writeln!(out, "## Alerts");
+            let _ = writeln!(out);
+            for alert in &self.alerts {
+                let _ = writeln!(out, "- {alert}");
+            }
+            let _ = writeln!(out);
+        }
+
+        if let Some(message) = self
+            .outcome
+            .as_ref()
+            .and_then(|outcome| outcome.final_message.as_deref())
+        {
+            let _ = writeln!(out, "## Final message");
+            let _ = writeln!(out);
+            let _ = writeln!(out, "{}", message.trim());
+        }
+        out
+    }
+
+    /// Writes `<session>.json` and `<session>.md` into `dir` and returns
+    /// their paths.
+    pub fn write(&self, dir: &Path) -> anyhow::Result<(PathBuf, PathBuf)> {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create report dir {}", dir.display()))?;
+        let json_path = dir.join(format!("{}.json", self.session_id));
+        let markdown_path = dir.join(format!("{}.md", self.session_id));
+        fs::write(&json_path, serde_json::to_vec_pretty(self)?)
+            .with_context(|| format!("failed to write {}", json_path.display()))?;
+        fs::write(&markdown_path, self.to_markdown())
+            .with_context(|| format!("failed to write {}", markdown_path.display()))?;
+        Ok((json_path, markdown_path))
+    }
+}
+
+fn first_line(text: &str) -> &str {
+    text.trim().lines().next().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AutoTurnAgentsAction;
+    use crate::AutoTurnCliAction;
+    use crate::budget::BudgetSnapshot;
+    use crate::turn_changes::ChangedFile;
+    use crate::turn_changes::FileChange;
+    use pretty_assertions::assert_eq;
+
+    fn cli_decision(seq: u64, title: &str) -> AutoCoordinatorEvent {
+        AutoCoordinatorEvent::Decision {
+            seq,
+            status: AutoCoordinatorStatus::Continue,
+            status_title: Some(title.to_string()),
+            status_sent_to_user: None,
+            goal: None,
+            cli: Some(AutoTurnCliAction {
+                prompt: format!("{title} now"),
+                context: None,
+                suppress_ui_context: false,
+                attachments: Vec::new(),
+                workstreams: Vec::new(),
+                complexity: None,
+                model: None,
+            }),
+            agents_timing: None,
+            agents: Vec::new(),
+            transcript: Vec::new(),
+            budget_snapshot: BudgetSnapshot::default(),
+        }
+    }
+
+    fn metrics(turn: u32, total: u64) -> AutoCoordinatorEvent {
+        let usage = |tokens: u64| TokenUsage {
+            total_tokens: tokens,
+            ..TokenUsage::default()
+        };
+        AutoCoordinatorEvent::TokenMetrics {
+            total_usage: usage(total),
+            last_turn_usage: TokenUsage::default(),
+            turn_count: turn,
+            duplicate_items: 0,
+            replay_updates: 0,
+            breakdown: vec![
+                AttributedUsage {
+                    seq: 1,
+                    source: UsageSource::Coordinator,
+                    usage: usage(total / 2),
+                },
+                AttributedUsage {
+                    seq: 1,
+                    source: UsageSource::Agent {
+                        prompt_hash: "3f2a9c0d1b7e".to_string(),
+                    },
+                    usage: usage(total / 2),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn records_decisions_metrics_compactions_and_outcome() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut report = RunReport::new("run-1", "Fix the parser");
+        report.observe(&cli_decision(1, "Add tests"));
+        report.record_turn_changes(TurnChanges {
+            seq: 1,
+            files: vec![ChangedFile {
+                path: "tests/parser.rs".to_string(),
+                change: FileChange::Added,
+            }],
+            insertions: Some(12),
+            deletions: Some(0),
+            patch_path: Some(PathBuf::from("run-1/decision-1.patch")),
+        });
+        report.observe(&metrics(1, 100));
+        report.observe(&metrics(1, 120));
+        report.observe(&AutoCoordinatorEvent::CompactedHistory {
+            conversation: Vec::new(),
+            show_notice: false,
+            estimate: None,
+        });
+        let agents = vec![AutoTurnAgentsAction {
+            prompt: "Review the diff\nin detail".to_string(),
+            context: None,
+            write: false,
+            write_requested: None,
+            models: Some(vec!["claude".to_string()]),
+            timeout_seconds: None,
+        }];
+        report.observe(&AutoCoordinatorEvent::Decision {
+            seq: 2,
+            status: AutoCoordinatorStatus::Success,
+            status_title: Some("Done".to_string()),
+            status_sent_to_user: Some("All tests pass.".to_string()),
+            goal: None,
+            cli: None,
+            agents_timing: Some(AutoTurnAgentsTiming::Blocking),
+            agents,
+            transcript: Vec::new(),
+            budget_snapshot: BudgetSnapshot::default(),
+        });
+        report.observe(&metrics(2, 300));
+        report.observe(&AutoCoordinatorEvent::StopAck);
+        report.finish(RunOutcome {
+            success: true,
+            failure_reason: None,
+            cli_turns: 1,
+            final_message: Some("Parser fixed.".to_string()),
+        });
+
+        assert_eq!(report.decisions.len(), 2);
+        assert_eq!(
+            report.decisions[0].cli_prompt.as_deref(),
+            Some("Add tests now")
+        );
+        assert_eq!(
+            report
+                .token_turns
+                .iter()
+                .map(|turn| (turn.turn, turn.total.total_tokens))
+                .collect::<Vec<_>>(),
+            vec![(1, 120), (2, 300)]
+        );
+        assert_eq!(report.compactions[0].after_decision, Some(1));
+
+        let markdown = report.to_markdown();
+        assert!(markdown.contains("- Outcome: success"));
+        assert!(markdown.contains("### 2. Done (Success)"));
+        assert!(markdown.contains(
+            "Changes: 1 file changed (+12 -0)\n\n- A tests/parser.rs\n\nPatch: `run-1/decision-1.patch`"
+        ));
+        assert!(markdown.contains("- [read-only, claude] Review the diff\n"));
+        assert!(markdown.contains("| 2 | 0 | 0 | 0 | 300 |"));
+        assert!(markdown.contains("| 1 | agent `3f2a9c0d1b7e` | 0 | 0 | 150 |"));
+
+        let (json_path, markdown_path) = report.write(dir.path()).unwrap();
+        let parsed: RunReport =
+            serde_json::from_str(&fs::read_to_string(json_path).unwrap()).unwrap();
+        assert_eq!(parsed.decisions.len(), 2);
+        assert_eq!(parsed.turn_changes, report.turn_changes);
+        assert_eq!(parsed.usage_breakdown, report.usage_breakdown);
+        assert_eq!(fs::read_to_string(markdown_path).unwrap(), markdown);
+    }
+}
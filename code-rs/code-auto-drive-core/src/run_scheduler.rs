@@ -0,0 +1,284 @@
+//! Deferred Auto Drive runs.
+//!
+//! `code exec --auto ... --schedule WHEN` (or `auto_drive.schedule`) queues a
+//! run instead of starting it, and `code exec scheduler run` starts the runs
+//! that are due. Each queued run is a JSON file under
+//! `$CODE_HOME/auto_drive/schedule` holding the original exec arguments, so
+//! the run gets every flag it was queued with: budgets, checkpoints, and
+//! output options. Unlike [`crate::scheduler`], which orders agents within a
+//! turn, this module deals in whole runs and wall-clock time.
+//!
+//! `WHEN` is `HH:MM` (the next time the local clock shows it), `daily HH:MM`
+//! (every day at that local time), or an RFC 3339 timestamp.
+
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use anyhow::Result;
+use anyhow::bail;
+use chrono::DateTime;
+use chrono::Duration;
+use chrono::Local;
+use chrono::NaiveTime;
+use chrono::TimeZone;
+use chrono::Utc;
+use serde::Deserialize;
+use serde::Serialize;
+
+const SCHEDULE_SUBDIR: &str = "auto_drive/schedule";
+const DAILY_PREFIX: &str = "daily ";
+const JOB_EXTENSION: &str = "json";
+const CLAIMED_EXTENSION: &str = "running";
+
+/// Schedule value that runs immediately, overriding `auto_drive.schedule`.
+pub const SCHEDULE_NOW: &str = "now";
+
+/// Directory that holds queued runs under `code_home`.
+pub fn default_schedule_dir(code_home: &Path) -> PathBuf {
+    code_home.join(SCHEDULE_SUBDIR)
+}
+
+/// When `spec` next fires after `now`.
+pub fn next_run_after(spec: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>> {
+    let spec = spec.trim();
+    if let Ok(at) = DateTime::parse_from_rfc3339(spec) {
+        return Ok(at.with_timezone(&Utc));
+    }
+    let time = spec.strip_prefix(DAILY_PREFIX).unwrap_or(spec).trim();
+    let Ok(time) = NaiveTime::parse_from_str(time, "%H:%M") else {
+        bail!("invalid schedule `{spec}`: expected HH:MM, `daily HH:MM`, or an RFC 3339 timestamp");
+    };
+    let local_now = now.with_timezone(&Local);
+    let mut day = local_now.date_naive();
+    loop {
+        // A time skipped by a DST change falls through to the next day.
+        if let Some(at) = Local
+            .from_local_datetime(&day.and_time(time))
+            .earliest()
+            .filter(|at| *at > local_now)
+        {
+            return Ok(at.with_timezone(&Utc));
+        }
+        day += Duration::days(1);
+    }
+}
+
+/// True when `spec` fires every day rather than once.
+pub fn is_recurring(spec: &str) -> bool {
+    spec.trim().starts_with(DAILY_PREFIX)
+}
+
+/// A queued `code exec` Auto Drive run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduledRun {
+    pub id: String,
+    pub goal: String,
+    /// The `WHEN` the run was queued with.
+    pub schedule: String,
+    pub next_run_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    /// Directory `code exec` was started from; relative arguments resolve
+    /// against it.
+    pub launch_dir: PathBuf,
+    /// Arguments to `code exec`, without `--schedule`.
+    pub args: Vec<String>,
+    /// Prompt that was piped on stdin, replayed to the run.
+    #[serde(default)]
+    pub stdin: Option<String>,
+    #[serde(default)]
+    pub last_run: Option<ScheduledRunResult>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduledRunResult {
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    /// `None` when the run was killed by a signal.
+    pub exit_code: Option<i32>,
+}
+
+impl ScheduledRun {
+    pub fn new(
+        goal: impl Into<String>,
+        schedule: impl Into<String>,
+        launch_dir: PathBuf,
+        args: Vec<String>,
+        stdin: Option<String>,
+    ) -> Result<Self> {
+        let schedule = schedule.into();
+        let now = Utc::now();
+        Ok(Self {
+            id: uuid::Uuid::new_v4().simple().to_string()[..8].to_string(),
+            goal: goal.into(),
+            next_run_at: next_run_after(&schedule, now)?,
+            schedule,
+            created_at: now,
+            launch_dir,
+            args,
+            stdin,
+            last_run: None,
+        })
+    }
+
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        self.next_run_at <= now
+    }
+}
+
+/// The queued runs in a schedule directory.
+#[derive(Debug, Clone)]
+pub struct RunQueue {
+    dir: PathBuf,
+}
+
+impl RunQueue {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn add(&self, run: &ScheduledRun) -> Result<PathBuf> {
+        fs::create_dir_all(&self.dir)
+            .with_context(|| format!("failed to create {}", self.dir.display()))?;
+        let path = self.job_path(&run.id, JOB_EXTENSION);
+        fs::write(&path, serde_json::to_vec_pretty(run)?)
+            .with_context(|| format!("failed to write {}", path.display()))?;
+        Ok(path)
+    }
+
+    /// Queued runs that are not currently running, soonest first. Files that
+    /// do not parse are skipped.
+    pub fn list(&self) -> Result<Vec<ScheduledRun>> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => {
+                return Err(err).with_context(|| format!("failed to read {}", self.dir.display()));
+            }
+        };
+        let mut runs: Vec<ScheduledRun> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == JOB_EXTENSION))
+            .filter_map(|path| fs::read(&path).ok())
+            .filter_map(|bytes| serde_json::from_slice(&bytes).ok())
+            .collect();
+        runs.sort_by_key(|run| run.next_run_at);
+        Ok(runs)
+    }
+
+    /// Marks `run` as running so a second scheduler skips it. `false` when
+    /// another scheduler claimed it first.
+    pub fn claim(&self, run: &ScheduledRun) -> Result<bool> {
+        let claimed = self.job_path(&run.id, CLAIMED_EXTENSION);
+        match fs::rename(self.job_path(&run.id, JOB_EXTENSION), &claimed) {
+            Ok(()) => Ok(true),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(false),
+            Err(err) => Err(err).with_context(|| format!("failed to claim {}", claimed.display())),
+        }
+    }
+
+    /// Records the result of a claimed run. Recurring runs are queued again
+    /// for their next time; one-off runs leave the queue.
+    pub fn complete(&self, mut run: ScheduledRun, result: ScheduledRunResult) -> Result<()> {
+        let claimed = self.job_path(&run.id, CLAIMED_EXTENSION);
+        if is_recurring(&run.schedule) {
+            run.next_run_at = next_run_after(&run.schedule, result.finished_at)?;
+            run.last_run = Some(result);
+            self.add(&run)?;
+        }
+        fs::remove_file(&claimed).with_context(|| format!("failed to remove {}", claimed.display()))
+    }
+
+    /// Removes a queued run; `false` when no run has that id.
+    pub fn cancel(&self, id: &str) -> Result<bool> {
+        let path = self.job_path(id, JOB_EXTENSION);
+        match fs::remove_file(&path) {
+            Ok(()) => Ok(true),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(false),
+            Err(err) => Err(err).with_context(|| format!("failed to remove {}", path.display())),
+        }
+    }
+
+    fn job_path(&self, id: &str, extension: &str) -> PathBuf {
+        self.dir.join(format!("{id}.{extension}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parses_schedules_relative_to_now() {
+        let now = Utc::now();
+        let absolute = next_run_after("2031-05-01T02:00:00Z", now).unwrap();
+        assert_eq!(absolute.to_rfc3339(), "2031-05-01T02:00:00+00:00");
+
+        for spec in ["02:00", "daily 02:00"] {
+            let next = next_run_after(spec, now).unwrap();
+            assert!(next > now && next - now <= Duration::days(1) + Duration::hours(1));
+            assert_eq!(
+                next.with_timezone(&Local).format("%H:%M").to_string(),
+                "02:00"
+            );
+        }
+        assert!(is_recurring("daily 02:00"));
+        assert!(!is_recurring("02:00"));
+        assert!(next_run_after("tomorrow", now).is_err());
+    }
+
+    #[test]
+    fn queue_claims_and_requeues_recurring_runs() {
+        let dir = tempfile::tempdir().unwrap();
+        let queue = RunQueue::new(dir.path());
+        let mut once = ScheduledRun::new(
+            "Fix the parser",
+            "02:00",
+            PathBuf::from("/repo"),
+            vec!["--auto".to_string(), "Fix the parser".to_string()],
+            None,
+        )
+        .unwrap();
+        once.next_run_at = Utc::now() - Duration::minutes(1);
+        let daily = ScheduledRun::new(
+            "Triage issues",
+            "daily 03:00",
+            PathBuf::from("/repo"),
+            vec!["--auto".to_string()],
+            Some("Triage issues".to_string()),
+        )
+        .unwrap();
+        queue.add(&once).unwrap();
+        queue.add(&daily).unwrap();
+
+        let listed = queue.list().unwrap();
+        assert_eq!(listed, vec![once.clone(), daily.clone()]);
+        assert!(listed[0].is_due(Utc::now()));
+        assert!(!listed[1].is_due(Utc::now()));
+
+        assert!(queue.claim(&once).unwrap());
+        assert!(!queue.claim(&once).unwrap());
+        assert_eq!(queue.list().unwrap(), vec![daily.clone()]);
+        let result = ScheduledRunResult {
+            started_at: Utc::now(),
+            finished_at: Utc::now(),
+            exit_code: Some(0),
+        };
+        queue.complete(once, result.clone()).unwrap();
+        assert_eq!(queue.list().unwrap(), vec![daily.clone()]);
+
+        assert!(queue.claim(&daily).unwrap());
+        queue.complete(daily.clone(), result.clone()).unwrap();
+        let requeued = queue.list().unwrap();
+        assert_eq!(requeued.len(), 1);
+        assert_eq!(requeued[0].last_run, Some(result));
+
+        assert!(queue.cancel(&daily.id).unwrap());
+        assert!(!queue.cancel(&daily.id).unwrap());
+        assert_eq!(queue.list().unwrap(), Vec::new());
+    }
+}
@@ -0,0 +1,74 @@
+//! Shell commands Auto Drive runs on its own behalf (stop conditions,
+//! selective tests) under the session's sandbox policy.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use code_core::config::Config;
+use code_core::error::Result as CodexResult;
+use code_core::exec::ExecParams;
+use code_core::exec::ExecToolCallOutput;
+use code_core::exec::SandboxType;
+use code_core::exec::process_exec_tool_call;
+use code_core::exec_env::create_env;
+use code_core::protocol::SandboxPolicy;
+
+/// Runs `sh -c` (`cmd /C` on Windows) commands in the session's working
+/// directory, sandboxed like the CLI's own commands.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SandboxedShell {
+    pub cwd: PathBuf,
+    pub sandbox_policy: SandboxPolicy,
+    pub code_linux_sandbox_exe: Option<PathBuf>,
+    pub env: HashMap<String, String>,
+}
+
+impl SandboxedShell {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            cwd: config.cwd.clone(),
+            sandbox_policy: config.sandbox_policy.clone(),
+            code_linux_sandbox_exe: config.code_linux_sandbox_exe.clone(),
+            env: create_env(&config.shell_environment_policy),
+        }
+    }
+
+    /// Runs `command`, killing it once `timeout` elapses.
+    pub async fn run(&self, command: &str, timeout: Duration) -> CodexResult<ExecToolCallOutput> {
+        let shell = if cfg!(windows) {
+            vec!["cmd".to_string(), "/C".to_string()]
+        } else {
+            vec!["sh".to_string(), "-c".to_string()]
+        };
+        let params = ExecParams {
+            command: shell.into_iter().chain([command.to_string()]).collect(),
+            cwd: self.cwd.clone(),
+            timeout_ms: Some(timeout.as_millis() as u64),
+            env: self.env.clone(),
+            with_escalated_permissions: None,
+            justification: None,
+        };
+        process_exec_tool_call(
+            params,
+            self.sandbox_type(),
+            &self.sandbox_policy,
+            &self.cwd,
+            &self.code_linux_sandbox_exe,
+            None,
+        )
+        .await
+    }
+
+    fn sandbox_type(&self) -> SandboxType {
+        if matches!(self.sandbox_policy, SandboxPolicy::DangerFullAccess) {
+            SandboxType::None
+        } else if cfg!(target_os = "macos") {
+            SandboxType::MacosSeatbelt
+        } else if cfg!(target_os = "linux") && self.code_linux_sandbox_exe.is_some() {
+            SandboxType::LinuxSeccomp
+        } else {
+            SandboxType::None
+        }
+    }
+}
@@ -3,14 +3,33 @@
 //! - 从 git diff 解析变更路径
 //! - 将变更映射到 Backlog 特性及测试需求
 //! - 根据 TDD 模式生成测试计划，支持 sandbox 下跳过网络/e2e
+//! - 按 `[auto_drive.selective_tests]` 的 glob 规则，在每个改动了文件的 CLI
+//!   轮次后于会话沙箱内只运行相关测试，并把结果交给协调器的下一次决策。
+//!   改动路径相对轮次开始前的工作区快照计算，之前轮次或运行前已有的改动
+//!   不会再次触发测试
 
 use std::env;
 use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use code_core::config::Config;
+use code_core::protocol::SandboxPolicy;
+use wildmatch::WildMatch;
 
 use crate::backlog::BacklogManager;
 use crate::backlog::Feature;
 use crate::backlog::TddMode;
 use crate::backlog::VerificationResult;
+use crate::sandboxed_shell::SandboxedShell;
+use crate::turn_changes::ghost_snapshot;
+use crate::turn_changes::git_diff;
+
+/// 未配置 `timeout_seconds` 时单个测试命令的超时。
+const DEFAULT_TEST_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// 交给协调器的失败输出最多保留的字符数（取末尾）。
+const MAX_FAILURE_OUTPUT_CHARS: usize = 2_000;
 
 /// 测试计划类型。
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -107,6 +126,141 @@ impl TestCommandResult {
     }
 }
 
+/// 每轮 CLI 之后按 glob 规则挑选并运行测试。
+#[derive(Debug)]
+pub(crate) struct SelectiveTestRunner {
+    shell: SandboxedShell,
+    rules: Vec<(WildMatch, String)>,
+    timeout: Duration,
+    /// 下一轮开始前工作区的 ghost commit；`None` 表示不在 git 仓库中。
+    baseline: Mutex<Option<String>>,
+}
+
+impl SelectiveTestRunner {
+    /// 未配置规则，或只读沙箱下 CLI 无法写文件时返回 `None`。
+    pub fn from_config(config: &Config) -> Option<Self> {
+        let settings = &config.auto_drive.selective_tests;
+        let rules: Vec<(WildMatch, String)> = settings
+            .rules
+            .iter()
+            .filter(|rule| !rule.glob.trim().is_empty() && !rule.command.trim().is_empty())
+            .map(|rule| {
+                (
+                    WildMatch::new(rule.glob.trim()),
+                    rule.command.trim().to_string(),
+                )
+            })
+            .collect();
+        if rules.is_empty() || matches!(config.sandbox_policy, SandboxPolicy::ReadOnly) {
+            return None;
+        }
+        Some(Self {
+            shell: SandboxedShell::from_config(config),
+            rules,
+            timeout: settings
+                .timeout_seconds
+                .map_or(DEFAULT_TEST_TIMEOUT, Duration::from_secs),
+            baseline: Mutex::new(ghost_snapshot(&config.cwd)),
+        })
+    }
+
+    /// 与改动路径匹配的命令，按规则顺序去重。
+    pub fn commands_for(&self, changed: &[PathBuf]) -> Vec<String> {
+        let mut commands: Vec<String> = Vec::new();
+        for (glob, command) in &self.rules {
+            let matched = changed
+                .iter()
+                .any(|path| glob.matches(&path.to_string_lossy().replace('\\', "/")));
+            if matched && !commands.contains(command) {
+                commands.push(command.clone());
+            }
+        }
+        commands
+    }
+
+    /// 运行与本轮改动相关的测试，并把当前工作区记为下一轮的快照；没有需要
+    /// 运行的命令时返回 `None`。
+    pub async fn run_after_turn(&self) -> Option<Vec<TestCommandResult>> {
+        let changed = self.changed_since_baseline().await?;
+        let commands = self.commands_for(&changed);
+        if commands.is_empty() {
+            return None;
+        }
+        let mut results = Vec::with_capacity(commands.len());
+        for command in commands {
+            results.push(self.run_command(command).await);
+        }
+        Some(results)
+    }
+
+    /// 相对上一快照改动的路径（含未跟踪文件）。git 命令在阻塞线程池中运行。
+    async fn changed_since_baseline(&self) -> Option<Vec<PathBuf>> {
+        let cwd = self.shell.cwd.clone();
+        let after = tokio::task::spawn_blocking(move || ghost_snapshot(&cwd))
+            .await
+            .ok()??;
+        let before = self.baseline.lock().ok()?.replace(after.clone())?;
+        let cwd = self.shell.cwd.clone();
+        let diff =
+            tokio::task::spawn_blocking(move || git_diff(&cwd, &["--name-only"], &before, &after))
+                .await
+                .ok()??;
+        Some(parse_git_diff_output(&diff))
+    }
+
+    async fn run_command(&self, command: String) -> TestCommandResult {
+        match self.shell.run(&command, self.timeout).await {
+            Ok(output) if output.exit_code == 0 && !output.timed_out => {
+                TestCommandResult::success(command)
+            }
+            Ok(output) => {
+                let mut text = output.aggregated_output.text;
+                if output.timed_out {
+                    text.push_str("\n(timed out)");
+                }
+                TestCommandResult::failure(command, tail(&text, MAX_FAILURE_OUTPUT_CHARS))
+            }
+            Err(err) => TestCommandResult::failure(command, err.to_string()),
+        }
+    }
+}
+
+/// 交给协调器的测试结果说明（developer 消息）。
+pub(crate) fn selective_test_message(results: &[TestCommandResult]) -> String {
+    let mut lines = vec![
+        "Auto Drive ran the tests selected for the files changed in the last CLI turn:".to_string(),
+    ];
+    for result in results {
+        let status = if result.passed { "passed" } else { "FAILED" };
+        lines.push(format!("- `{}`: {status}", result.command));
+        if let Some(output) = result.output.as_deref().filter(|_| !result.passed) {
+            lines.push(format!("```\n{}\n```", output.trim_end()));
+        }
+    }
+    if results.iter().any(|result| !result.passed) {
+        lines.push("Have the CLI fix these failures before moving on to new work.".to_string());
+    }
+    lines.join("\n")
+}
+
+/// 状态栏摘要，如 `selective tests: 1 passed, 1 failed`。
+pub(crate) fn selective_test_summary(results: &[TestCommandResult]) -> String {
+    let failed = results.iter().filter(|result| !result.passed).count();
+    format!(
+        "selective tests: {} passed, {failed} failed",
+        results.len() - failed
+    )
+}
+
+fn tail(text: &str, max_chars: usize) -> String {
+    let count = text.chars().count();
+    if count <= max_chars {
+        return text.to_string();
+    }
+    let kept: String = text.chars().skip(count - max_chars).collect();
+    format!("...{kept}")
+}
+
 /// 根据测试计划与执行结果生成验证结果（包含严格 TDD 检查）。
 pub fn verification_result_for_feature(
     feature: &Feature,
@@ -169,7 +323,69 @@ mod tests {
     use super::*;
     use crate::backlog::Feature;
     use crate::backlog::TestRequirements;
+    use crate::test_support::committed_repo;
     use pretty_assertions::assert_eq;
+    use std::collections::HashMap;
+
+    #[test]
+    fn changed_paths_select_matching_rules_once() {
+        let changed = parse_git_diff_output("core/src/exec.rs\ncore/tests/new.rs\ndocs/new.md\n");
+        let rule = |glob: &str, command: &str| (WildMatch::new(glob), command.to_string());
+        let runner = SelectiveTestRunner {
+            shell: SandboxedShell {
+                cwd: PathBuf::new(),
+                sandbox_policy: SandboxPolicy::DangerFullAccess,
+                code_linux_sandbox_exe: None,
+                env: HashMap::new(),
+            },
+            rules: vec![
+                rule("core/*.rs", "cargo test -p code-core"),
+                rule("core/tests/*", "cargo test -p code-core"),
+                rule("tui/*", "cargo test -p code-tui"),
+                rule("docs/*.md", "./scripts/check-docs.sh"),
+            ],
+            timeout: DEFAULT_TEST_TIMEOUT,
+            baseline: Mutex::new(None),
+        };
+        assert_eq!(
+            runner.commands_for(&changed),
+            vec![
+                "cargo test -p code-core".to_string(),
+                "./scripts/check-docs.sh".to_string(),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn only_changes_made_since_the_snapshot_select_tests() {
+        let repo = committed_repo();
+        // Dirty before the run starts: must not select its tests.
+        std::fs::write(repo.path().join("README.md"), "edited earlier").unwrap();
+
+        let runner = SelectiveTestRunner {
+            shell: SandboxedShell {
+                cwd: repo.path().to_path_buf(),
+                sandbox_policy: SandboxPolicy::DangerFullAccess,
+                code_linux_sandbox_exe: None,
+                env: HashMap::new(),
+            },
+            rules: vec![(WildMatch::new("*.md"), "exit 0".to_string())],
+            timeout: DEFAULT_TEST_TIMEOUT,
+            baseline: Mutex::new(ghost_snapshot(repo.path())),
+        };
+        std::fs::write(repo.path().join("lib.rs"), "fn main() {}").unwrap();
+        assert_eq!(
+            runner.changed_since_baseline().await,
+            Some(vec![PathBuf::from("lib.rs")])
+        );
+        assert_eq!(runner.run_after_turn().await, None);
+
+        std::fs::write(repo.path().join("NOTES.md"), "notes").unwrap();
+        assert_eq!(
+            runner.run_after_turn().await,
+            Some(vec![TestCommandResult::success("exit 0")])
+        );
+    }
 
     #[test]
     fn parse_git_diff_to_paths() {
@@ -1,8 +1,44 @@
 use std::collections::VecDeque;
 
 use code_core::protocol::TokenUsage;
+use serde::Deserialize;
+use serde::Serialize;
+use sha2::Digest;
+use sha2::Sha256;
 
 const DEFAULT_PROMPT_ESTIMATE: u64 = 4_000;
+/// Hex digits of the prompt digest that identify an agent.
+const PROMPT_HASH_LEN: usize = 12;
+
+/// What a share of the run's tokens was spent on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum UsageSource {
+    /// The coordinator request(s) that produced the decision.
+    Coordinator,
+    /// The CLI turn the decision started.
+    CliTurn,
+    /// An agent or workstream spawned for the decision, identified by
+    /// [`agent_prompt_hash`] of its prompt.
+    Agent { prompt_hash: String },
+}
+
+/// Tokens spent on one source of one decision.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttributedUsage {
+    pub seq: u64,
+    pub source: UsageSource,
+    pub usage: TokenUsage,
+}
+
+/// Short, stable key for an agent prompt; whitespace around it is ignored.
+pub fn agent_prompt_hash(prompt: &str) -> String {
+    Sha256::digest(prompt.trim().as_bytes())
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<String>()[..PROMPT_HASH_LEN]
+        .to_string()
+}
 
 #[derive(Debug, Clone)]
 pub struct SessionMetrics {
@@ -13,6 +49,8 @@ pub struct SessionMetrics {
     duplicate_items: u32,
     recent_prompt_tokens: VecDeque<u64>,
     window: usize,
+    /// Usage per decision and source, in the order it was first reported.
+    attributed: Vec<AttributedUsage>,
 }
 
 impl Default for SessionMetrics {
@@ -31,6 +69,7 @@ impl SessionMetrics {
             duplicate_items: 0,
             recent_prompt_tokens: VecDeque::with_capacity(window),
             window: window.max(1),
+            attributed: Vec::new(),
         }
     }
 
@@ -41,6 +80,30 @@ impl SessionMetrics {
         self.push_prompt_observation(usage.non_cached_input());
     }
 
+    /// Adds `usage` to what decision `seq` spent on `source`; the running
+    /// total is left alone.
+    pub fn attribute(&mut self, seq: u64, source: UsageSource, usage: &TokenUsage) {
+        if usage.is_zero() {
+            return;
+        }
+        match self
+            .attributed
+            .iter_mut()
+            .find(|entry| entry.seq == seq && entry.source == source)
+        {
+            Some(entry) => entry.usage.add_assign(usage),
+            None => self.attributed.push(AttributedUsage {
+                seq,
+                source,
+                usage: usage.clone(),
+            }),
+        }
+    }
+
+    pub fn attributed(&self) -> &[AttributedUsage] {
+        &self.attributed
+    }
+
     pub fn sync_absolute(&mut self, total: TokenUsage, last: TokenUsage, turn_count: u32) {
         self.running_total = total;
         self.last_turn = last.clone();
@@ -168,6 +231,38 @@ mod tests {
         assert_eq!(metrics.replay_updates(), 0);
     }
 
+    #[test]
+    fn attribute_merges_usage_per_decision_and_source() {
+        let mut metrics = SessionMetrics::default();
+        let agent = UsageSource::Agent {
+            prompt_hash: agent_prompt_hash("Review the diff"),
+        };
+        metrics.attribute(1, UsageSource::Coordinator, &usage(1_000, 100));
+        metrics.attribute(1, UsageSource::CliTurn, &usage(20_000, 2_000));
+        metrics.attribute(1, agent.clone(), &usage(5_000, 500));
+        metrics.attribute(1, UsageSource::Coordinator, &usage(800, 50));
+        metrics.attribute(2, UsageSource::CliTurn, &usage(0, 0));
+
+        let attributed: Vec<(u64, &UsageSource, u64)> = metrics
+            .attributed()
+            .iter()
+            .map(|entry| (entry.seq, &entry.source, entry.usage.total_tokens))
+            .collect();
+        assert_eq!(
+            attributed,
+            vec![
+                (1, &UsageSource::Coordinator, 1_950),
+                (1, &UsageSource::CliTurn, 22_000),
+                (1, &agent, 5_500),
+            ]
+        );
+        assert_eq!(
+            agent_prompt_hash(" Review the diff\n"),
+            agent_prompt_hash("Review the diff")
+        );
+        assert_eq!(agent_prompt_hash("Review the diff").len(), PROMPT_HASH_LEN);
+    }
+
     #[test]
     fn record_replay_increments_counter() {
         let mut metrics = SessionMetrics::default();
@@ -14,6 +14,7 @@ use tokio::sync::OwnedSemaphorePermit;
 use tokio::sync::RwLock;
 use tokio::sync::Semaphore;
 use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
 
 use crate::budget::BudgetAlert;
@@ -83,6 +84,8 @@ pub struct PoolTask {
     pub retries: i32,
     /// Creation timestamp
     pub created_at: Instant,
+    /// Maximum run time before the task is cancelled. None means no limit.
+    pub timeout: Option<Duration>,
 }
 
 impl PoolTask {
@@ -93,6 +96,7 @@ impl PoolTask {
             priority: 1,
             retries: 0,
             created_at: Instant::now(),
+            timeout: None,
         }
     }
 
@@ -100,6 +104,11 @@ impl PoolTask {
         self.priority = priority;
         self
     }
+
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout.filter(|limit| !limit.is_zero());
+        self
+    }
 }
 
 /// Result from a completed session task
@@ -117,6 +126,9 @@ pub struct TaskResult {
     pub tokens_used: i64,
     /// Execution duration
     pub duration: Duration,
+    /// True when the task was cancelled because it exceeded its timeout.
+    /// `content` then holds whatever partial output had been recorded.
+    pub timed_out: bool,
 }
 
 /// Information about a session in the pool
@@ -130,6 +142,10 @@ struct SessionInfo {
     tokens_used: i64,
     _errors: i32,
     permit: Option<OwnedSemaphorePermit>,
+    /// Cancelled when the current task times out or the pool shuts down.
+    cancel_token: CancellationToken,
+    /// Output streamed so far for the current task.
+    partial_output: String,
 }
 
 impl SessionInfo {
@@ -143,8 +159,20 @@ impl SessionInfo {
             tokens_used: 0,
             _errors: 0,
             permit: None,
+            cancel_token: CancellationToken::new(),
+            partial_output: String::new(),
         }
     }
+
+    /// Arms the session for a new task with a fresh cancellation token.
+    fn start_task(&mut self, task: PoolTask, permit: Option<OwnedSemaphorePermit>) {
+        self.state = SessionState::Running;
+        self.current_task = Some(task);
+        self.started_at = Some(Instant::now());
+        self.permit = permit;
+        self.cancel_token = CancellationToken::new();
+        self.partial_output.clear();
+    }
 }
 
 /// Priority queue for tasks
@@ -230,6 +258,8 @@ pub struct PoolMetrics {
     pub backpressure_warnings: i32,
     /// Number of backpressure rejections emitted
     pub backpressure_rejections: i32,
+    /// Tasks cancelled after exceeding their timeout
+    pub timeout_count: i32,
 }
 
 /// Errors that can occur while operating the session pool
@@ -269,6 +299,8 @@ pub struct HealthReport {
     pub stuck_sessions: Vec<(String, i64, Option<String>)>,
     /// Migration events triggered by stuck sessions.
     pub migrations: Vec<MigrationEvent>,
+    /// Tasks cancelled because they exceeded their timeout.
+    pub timed_out: Vec<TaskResult>,
 }
 
 /// The session pool manager
@@ -476,18 +508,12 @@ impl SessionPool {
                 .iter_mut()
                 .find(|(_, session)| session.state == SessionState::Idle)
             {
-                session.state = SessionState::Running;
-                session.current_task = Some(task.clone());
-                session.started_at = Some(Instant::now());
-                session.permit = permit.take();
+                session.start_task(task.clone(), permit.take());
                 session_id = Some(id.clone());
             } else if sessions.len() < self.config.max_sessions.max(1) as usize {
                 let id = Uuid::new_v4().to_string();
                 let mut info = SessionInfo::new(id.clone());
-                info.state = SessionState::Running;
-                info.current_task = Some(task.clone());
-                info.started_at = Some(Instant::now());
-                info.permit = permit.take();
+                info.start_task(task.clone(), permit.take());
                 sessions.insert(id.clone(), info);
                 session_id = Some(id);
             }
@@ -526,6 +552,114 @@ impl SessionPool {
             .map(|(id, _)| id.clone())
     }
 
+    /// Returns the cancellation token for the task running on a session.
+    /// Executors should stop work and report partial output once it fires.
+    pub async fn cancellation_token(&self, session_id: &str) -> Option<CancellationToken> {
+        let sessions = self.sessions.read().await;
+        sessions
+            .get(session_id)
+            .filter(|session| session.current_task.is_some())
+            .map(|session| session.cancel_token.clone())
+    }
+
+    /// Appends streamed output for the task running on a session so it can be
+    /// returned if the task is cancelled before completing.
+    pub async fn record_partial(&self, session_id: &str, chunk: &str) {
+        let mut sessions = self.sessions.write().await;
+        if let Some(session) = sessions.get_mut(session_id)
+            && session.current_task.is_some()
+        {
+            session.partial_output.push_str(chunk);
+        }
+    }
+
+    /// Cancels the task running on a session, releasing the session and
+    /// emitting a failed [`TaskResult`] that carries the partial output.
+    async fn cancel_session(&self, session_id: &str, timed_out: bool) -> Option<TaskResult> {
+        let result = {
+            let mut sessions = self.sessions.write().await;
+            let session = sessions.get_mut(session_id)?;
+            let task = session.current_task.take()?;
+            session.cancel_token.cancel();
+            let duration = session
+                .started_at
+                .take()
+                .map(|started| started.elapsed())
+                .unwrap_or_default();
+            session.state = SessionState::Idle;
+            session.permit.take();
+            TaskResult {
+                task_id: task.id,
+                session_id: session_id.to_string(),
+                success: false,
+                content: std::mem::take(&mut session.partial_output),
+                tokens_used: 0,
+                duration,
+                timed_out,
+            }
+        };
+
+        {
+            let mut metrics = self.metrics.write().await;
+            metrics.tasks_failed = metrics.tasks_failed.saturating_add(1);
+            metrics.failure_count = metrics.failure_count.saturating_add(1);
+            if timed_out {
+                metrics.timeout_count = metrics.timeout_count.saturating_add(1);
+            }
+        }
+
+        let _ = self.result_tx.send(result.clone()).await;
+        self.refresh_metrics().await;
+        let _ = self.dispatch_from_queue().await;
+        Some(result)
+    }
+
+    /// Cancels a running task by ID. Returns the partial result, or None if
+    /// the task is not currently running.
+    pub async fn cancel_task(&self, task_id: &str) -> Option<TaskResult> {
+        let session_id = self.session_for_task(task_id).await?;
+        self.cancel_session(&session_id, false).await
+    }
+
+    /// Cancels a running task that exceeded its deadline. Returns the
+    /// timed-out result, or None if the task is not currently running.
+    pub async fn expire_task(&self, task_id: &str) -> Option<TaskResult> {
+        let session_id = self.session_for_task(task_id).await?;
+        self.cancel_session(&session_id, true).await
+    }
+
+    /// Cancels every running task whose timeout has elapsed.
+    pub async fn expire_timed_out(&self) -> Vec<TaskResult> {
+        let now = Instant::now();
+        let expired: Vec<String> = {
+            let sessions = self.sessions.read().await;
+            sessions
+                .iter()
+                .filter(|(_, session)| {
+                    let limit = session.current_task.as_ref().and_then(|task| task.timeout);
+                    matches!(
+                        (limit, session.started_at),
+                        (Some(limit), Some(started)) if now.duration_since(started) >= limit
+                    )
+                })
+                .map(|(id, _)| id.clone())
+                .collect()
+        };
+
+        let mut results = Vec::new();
+        for session_id in expired {
+            if let Some(result) = self.cancel_session(&session_id, true).await {
+                tracing::warn!(
+                    session_id = %session_id,
+                    task_id = %result.task_id,
+                    "Session task timed out; cancelled with partial output"
+                );
+                results.push(result);
+            }
+        }
+        results
+    }
+
     /// Marks a session as complete and returns it to the idle pool.
     pub async fn complete_session(
         &self,
@@ -657,6 +791,7 @@ impl SessionPool {
                     ),
                     tokens_used: 0,
                     duration: Duration::from_millis(0),
+                    timed_out: false,
                 };
                 let _ = self.result_tx.send(result).await;
                 continue;
@@ -711,8 +846,9 @@ impl SessionPool {
         alert.take()
     }
 
-    /// Checks session health and handles stuck sessions
+    /// Checks session health, cancels timed-out tasks and handles stuck sessions
     pub async fn health_check(&self) -> HealthReport {
+        let timed_out = self.expire_timed_out().await;
         let now = Instant::now();
         let mut found_stuck = false;
         let mut slow_sessions = Vec::new();
@@ -775,6 +911,7 @@ impl SessionPool {
             slow_sessions,
             stuck_sessions,
             migrations,
+            timed_out,
         }
     }
 
@@ -783,10 +920,11 @@ impl SessionPool {
         let mut shutdown = self.shutdown.write().await;
         *shutdown = true;
 
-        // Mark all sessions as shutting down
+        // Mark all sessions as shutting down and cancel in-flight work
         let mut sessions = self.sessions.write().await;
         for session in sessions.values_mut() {
             session.state = SessionState::ShuttingDown;
+            session.cancel_token.cancel();
         }
     }
 
@@ -900,6 +1038,7 @@ mod tests {
             content: "done".to_string(),
             tokens_used: 10,
             duration: Duration::from_millis(50),
+            timed_out: false,
         };
         pool.complete_session(&session_id, result).await.unwrap();
 
@@ -1136,6 +1275,69 @@ mod tests {
         assert!(report.stuck_sessions.is_empty());
     }
 
+    #[tokio::test]
+    async fn timed_out_task_is_cancelled_with_partial_output() {
+        let pool = SessionPool::new(SessionPoolConfig::default());
+        let task =
+            PoolTask::new("task-timeout", "work").with_timeout(Some(Duration::from_millis(10)));
+        let session_id = pool.dispatch_task(task).await.unwrap();
+        let token = pool.cancellation_token(&session_id).await.unwrap();
+        pool.record_partial(&session_id, "halfway").await;
+
+        {
+            let mut sessions = pool.sessions.write().await;
+            let session = sessions.get_mut(&session_id).unwrap();
+            session.started_at = Some(Instant::now() - Duration::from_millis(50));
+        }
+
+        let report = pool.health_check().await;
+        assert_eq!(report.timed_out.len(), 1);
+        let result = &report.timed_out[0];
+        assert_eq!(result.task_id, "task-timeout");
+        assert_eq!(result.content, "halfway");
+        assert!(result.timed_out);
+        assert!(!result.success);
+        assert!(token.is_cancelled());
+
+        let metrics = pool.metrics().await;
+        assert_eq!(metrics.timeout_count, 1);
+        assert_eq!(metrics.tasks_failed, 1);
+        assert_eq!(
+            pool.try_next_result().await.unwrap().task_id,
+            "task-timeout"
+        );
+    }
+
+    #[tokio::test]
+    async fn task_without_timeout_is_not_expired() {
+        let pool = SessionPool::new(SessionPoolConfig::default());
+        let session_id = pool
+            .dispatch_task(PoolTask::new("task-open", "work"))
+            .await
+            .unwrap();
+        {
+            let mut sessions = pool.sessions.write().await;
+            let session = sessions.get_mut(&session_id).unwrap();
+            session.started_at = Some(Instant::now() - Duration::from_secs(60));
+        }
+
+        assert!(pool.expire_timed_out().await.is_empty());
+        assert!(pool.session_for_task("task-open").await.is_some());
+    }
+
+    #[tokio::test]
+    async fn shutdown_cancels_running_tasks() {
+        let pool = SessionPool::new(SessionPoolConfig::default());
+        let session_id = pool
+            .dispatch_task(PoolTask::new("task-run", "work"))
+            .await
+            .unwrap();
+        let token = pool.cancellation_token(&session_id).await.unwrap();
+
+        pool.shutdown().await;
+        assert!(token.is_cancelled());
+    }
+
     proptest! {
         #[test]
         fn min_sessions_invariant(min in 1i32..6i32) {
@@ -0,0 +1,153 @@
+//! Simple loop used when every coordinator model is unavailable.
+//!
+//! Once the coordinator fails with an error no retry can fix (quota, usage
+//! limit, permanent auth failure), Auto Drive keeps the run alive by sending
+//! the CLI a templated continue prompt each turn instead of asking the model
+//! what to do. The CLI ends the loop by putting [`SIMPLE_LOOP_DONE_MARKER`] in
+//! its reply; otherwise the loop fails after `simple_loop_max_turns` turns.
+
+use code_protocol::models::ContentItem;
+use code_protocol::models::ResponseItem;
+
+/// Line the CLI adds to its final reply once the goal is complete.
+pub(crate) const SIMPLE_LOOP_DONE_MARKER: &str = "AUTO_DRIVE_GOAL_COMPLETE";
+
+/// What the simple loop does after a CLI turn.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum SimpleLoopStep {
+    Continue { prompt: String },
+    Finished { success: bool, summary: String },
+}
+
+#[derive(Debug)]
+pub(crate) struct SimpleLoop {
+    goal: String,
+    max_turns: u32,
+    turns: u32,
+}
+
+impl SimpleLoop {
+    /// `None` when `max_turns` is 0, which disables the fallback, or when
+    /// there is no goal to template the prompt with yet.
+    pub fn new(goal: &str, max_turns: u32) -> Option<Self> {
+        let goal = goal.trim();
+        (max_turns > 0 && !goal.is_empty()).then(|| Self {
+            goal: goal.to_string(),
+            max_turns,
+            turns: 0,
+        })
+    }
+
+    /// Prompt for the first CLI turn after the coordinator became
+    /// unavailable.
+    pub fn start(&mut self) -> String {
+        self.turns = 1;
+        self.prompt()
+    }
+
+    /// Decides the next step from the conversation after a CLI turn. The
+    /// goal is complete once the reply has a line holding only the marker.
+    pub fn next_step(&mut self, conversation: &[ResponseItem]) -> SimpleLoopStep {
+        if last_cli_reply(conversation).is_some_and(|reply| {
+            reply
+                .lines()
+                .any(|line| line.trim() == SIMPLE_LOOP_DONE_MARKER)
+        }) {
+            return SimpleLoopStep::Finished {
+                success: true,
+                summary: "The CLI reported the goal complete.".to_string(),
+            };
+        }
+        if self.turns >= self.max_turns {
+            return SimpleLoopStep::Finished {
+                success: false,
+                summary: format!(
+                    "Stopped after {} simple-loop turn(s) without the CLI reporting the goal complete.",
+                    self.max_turns
+                ),
+            };
+        }
+        self.turns += 1;
+        SimpleLoopStep::Continue {
+            prompt: self.prompt(),
+        }
+    }
+
+    fn prompt(&self) -> String {
+        format!(
+            "Continue working toward the goal on your own; the Auto Drive coordinator is unavailable.\n\nGoal: {}\n\nReview what is done, take the next concrete step, and verify it. When the goal is fully complete and verified, end your reply with a line containing only {SIMPLE_LOOP_DONE_MARKER}.",
+            self.goal
+        )
+    }
+}
+
+/// Text of the CLI's latest reply in the coordinator conversation.
+pub(crate) fn last_cli_reply(conversation: &[ResponseItem]) -> Option<String> {
+    conversation.iter().rev().find_map(|item| match item {
+        ResponseItem::Message { role, content, .. } if role == "assistant" => Some(
+            content
+                .iter()
+                .filter_map(|part| match part {
+                    ContentItem::OutputText { text } => Some(text.as_str()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+        ),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auto_coordinator::make_message;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn continues_until_the_cli_reports_completion_or_turns_run_out() {
+        assert!(SimpleLoop::new("Fix the parser", 0).is_none());
+        assert!(SimpleLoop::new("  ", 2).is_none());
+
+        let mut simple = SimpleLoop::new("Fix the parser", 2).expect("enabled");
+        let first = simple.start();
+        assert!(first.contains("Goal: Fix the parser"));
+
+        let working = vec![make_message("assistant", "Fixed one case.".to_string())];
+        assert_eq!(
+            simple.next_step(&working),
+            SimpleLoopStep::Continue { prompt: first }
+        );
+        assert_eq!(
+            simple.next_step(&working),
+            SimpleLoopStep::Finished {
+                success: false,
+                summary: "Stopped after 2 simple-loop turn(s) without the CLI reporting the goal complete."
+                    .to_string(),
+            }
+        );
+
+        let quoted = vec![make_message(
+            "assistant",
+            format!("I will reply with {SIMPLE_LOOP_DONE_MARKER} once the tests pass."),
+        )];
+        let mut fresh = SimpleLoop::new("Fix the parser", 2).expect("enabled");
+        fresh.start();
+        assert!(matches!(
+            fresh.next_step(&quoted),
+            SimpleLoopStep::Continue { .. }
+        ));
+
+        let done = vec![make_message(
+            "assistant",
+            format!("All tests pass.\n{SIMPLE_LOOP_DONE_MARKER}"),
+        )];
+        assert_eq!(
+            simple.next_step(&done),
+            SimpleLoopStep::Finished {
+                success: true,
+                summary: "The CLI reported the goal complete.".to_string(),
+            }
+        );
+    }
+}
@@ -0,0 +1,230 @@
+//! Objective stop conditions for Auto Drive (`[auto_drive.stop_when]`).
+//!
+//! The coordinator evaluates the configured conditions after every turn.
+//! Once all of them hold, the run finishes successfully without waiting for
+//! the coordinator model to declare success. Commands run under the session's
+//! sandbox policy and count as failing once they exceed `timeout_seconds`.
+
+use std::path::Path;
+use std::time::Duration;
+
+use code_core::config::Config;
+use code_core::config_types::AutoDriveStopWhen;
+
+use crate::sandboxed_shell::SandboxedShell;
+
+/// Limit for each condition command when `timeout_seconds` is unset.
+const DEFAULT_COMMAND_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Resolved stop conditions for one run.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct StopConditions {
+    shell: SandboxedShell,
+    timeout: Duration,
+    tests_passing: bool,
+    /// Configured or detected; when `tests_passing` is set without one the
+    /// condition never holds.
+    test_command: Option<String>,
+    file_exists: Option<String>,
+    command_succeeds: Option<String>,
+}
+
+/// Outcome of one evaluation, as human-readable condition labels.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct StopEvaluation {
+    pub met: Vec<String>,
+    pub unmet: Vec<String>,
+}
+
+impl StopEvaluation {
+    pub fn all_met(&self) -> bool {
+        self.unmet.is_empty() && !self.met.is_empty()
+    }
+}
+
+impl StopConditions {
+    /// Returns `None` when no condition is configured.
+    pub fn from_config(config: &Config) -> Option<Self> {
+        Self::from_settings(
+            &config.auto_drive.stop_when,
+            SandboxedShell::from_config(config),
+        )
+    }
+
+    fn from_settings(stop_when: &AutoDriveStopWhen, shell: SandboxedShell) -> Option<Self> {
+        if stop_when.is_empty() {
+            return None;
+        }
+        let test_command = stop_when
+            .test_command
+            .clone()
+            .filter(|command| !command.trim().is_empty())
+            .or_else(|| detect_test_command(&shell.cwd).map(str::to_string));
+        Some(Self {
+            shell,
+            timeout: stop_when
+                .timeout_seconds
+                .map_or(DEFAULT_COMMAND_TIMEOUT, Duration::from_secs),
+            tests_passing: stop_when.tests_passing,
+            test_command,
+            file_exists: stop_when.file_exists.clone(),
+            command_succeeds: stop_when.command_succeeds.clone(),
+        })
+    }
+
+    /// Checks every condition, cheapest first.
+    pub async fn evaluate(&self) -> StopEvaluation {
+        let mut evaluation = StopEvaluation::default();
+        let mut record = |holds: bool, label: String| {
+            if holds {
+                evaluation.met.push(label);
+            } else {
+                evaluation.unmet.push(label);
+            }
+        };
+
+        if let Some(path) = self.file_exists.as_deref() {
+            record(
+                self.shell.cwd.join(path).exists(),
+                format!("file `{path}` exists"),
+            );
+        }
+        if let Some(command) = self.command_succeeds.as_deref() {
+            record(
+                self.command_succeeds_in_cwd(command).await,
+                format!("`{command}` succeeds"),
+            );
+        }
+        if self.tests_passing {
+            match self.test_command.as_deref() {
+                Some(command) => record(
+                    self.command_succeeds_in_cwd(command).await,
+                    format!("tests pass (`{command}`)"),
+                ),
+                None => record(false, "tests pass (no test command detected)".to_string()),
+            }
+        }
+        evaluation
+    }
+
+    async fn command_succeeds_in_cwd(&self, command: &str) -> bool {
+        // Sandbox denials come back as errors; a command that ran out of time
+        // fails whatever its exit code.
+        match self.shell.run(command, self.timeout).await {
+            Ok(output) if output.timed_out => {
+                tracing::warn!("stop condition `{command}` timed out");
+                false
+            }
+            Ok(output) => output.exit_code == 0,
+            Err(err) => {
+                tracing::warn!("failed to run stop condition `{command}`: {err}");
+                false
+            }
+        }
+    }
+}
+
+/// Test command implied by the project's manifest files.
+fn detect_test_command(cwd: &Path) -> Option<&'static str> {
+    const MANIFESTS: [(&str, &str); 5] = [
+        ("Cargo.toml", "cargo test"),
+        ("package.json", "npm test"),
+        ("go.mod", "go test ./..."),
+        ("pyproject.toml", "python -m pytest"),
+        ("pytest.ini", "python -m pytest"),
+    ];
+    MANIFESTS
+        .iter()
+        .find(|(manifest, _)| cwd.join(manifest).is_file())
+        .map(|(_, command)| *command)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use code_core::protocol::SandboxPolicy;
+    use pretty_assertions::assert_eq;
+    use std::collections::HashMap;
+
+    fn shell(cwd: &Path) -> SandboxedShell {
+        SandboxedShell {
+            cwd: cwd.to_path_buf(),
+            sandbox_policy: SandboxPolicy::DangerFullAccess,
+            code_linux_sandbox_exe: None,
+            env: HashMap::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn all_conditions_must_hold() {
+        let dir = tempfile::tempdir().unwrap();
+        let stop_when = AutoDriveStopWhen {
+            file_exists: Some("CHANGELOG.md".to_string()),
+            command_succeeds: Some("exit 0".to_string()),
+            ..Default::default()
+        };
+        let conditions = StopConditions::from_settings(&stop_when, shell(dir.path())).unwrap();
+
+        let evaluation = conditions.evaluate().await;
+        assert_eq!(
+            evaluation,
+            StopEvaluation {
+                met: vec!["`exit 0` succeeds".to_string()],
+                unmet: vec!["file `CHANGELOG.md` exists".to_string()],
+            }
+        );
+        assert!(!evaluation.all_met());
+
+        std::fs::write(dir.path().join("CHANGELOG.md"), "").unwrap();
+        assert!(conditions.evaluate().await.all_met());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn slow_command_times_out_as_unmet() {
+        let dir = tempfile::tempdir().unwrap();
+        let stop_when = AutoDriveStopWhen {
+            command_succeeds: Some("sleep 5".to_string()),
+            timeout_seconds: Some(1),
+            ..Default::default()
+        };
+        let conditions = StopConditions::from_settings(&stop_when, shell(dir.path())).unwrap();
+
+        let started = std::time::Instant::now();
+        let evaluation = conditions.evaluate().await;
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(evaluation.unmet, vec!["`sleep 5` succeeds".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn tests_passing_uses_detected_or_configured_command() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut stop_when = AutoDriveStopWhen {
+            tests_passing: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            StopConditions::from_settings(&stop_when, shell(dir.path()))
+                .unwrap()
+                .evaluate()
+                .await
+                .unmet,
+            vec!["tests pass (no test command detected)".to_string()]
+        );
+
+        std::fs::write(dir.path().join("go.mod"), "module example").unwrap();
+        let conditions = StopConditions::from_settings(&stop_when, shell(dir.path())).unwrap();
+        assert_eq!(conditions.test_command.as_deref(), Some("go test ./..."));
+
+        stop_when.test_command = Some("exit 0".to_string());
+        let conditions = StopConditions::from_settings(&stop_when, shell(dir.path())).unwrap();
+        assert_eq!(
+            conditions.evaluate().await.met,
+            vec!["tests pass (`exit 0`)".to_string()]
+        );
+        assert_eq!(
+            StopConditions::from_settings(&AutoDriveStopWhen::default(), shell(dir.path())),
+            None
+        );
+    }
+}
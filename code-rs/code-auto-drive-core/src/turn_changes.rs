@@ -0,0 +1,363 @@
+//! Per-turn change capture for exec Auto Drive runs.
+//!
+//! Before each CLI turn the workspace is snapshotted, and afterwards the
+//! delta is recorded against the coordinator decision that started the turn.
+//! In a git repository the snapshot is a ghost commit of the working tree, so
+//! the delta is an exact patch even when the tree was already dirty; the
+//! patch is written next to the run report as `decision-<seq>.patch`.
+//! Elsewhere the snapshot hashes every file, and the delta lists the files
+//! added, modified, or deleted.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+
+use anyhow::Context;
+use code_git_tooling::CreateGhostCommitOptions;
+use code_git_tooling::create_ghost_commit;
+use serde::Deserialize;
+use serde::Serialize;
+use sha2::Digest;
+use sha2::Sha256;
+
+/// Directories the file-hash snapshot never descends into.
+const SKIPPED_DIRS: &[&str] = &[".git", "target", "node_modules"];
+
+/// Workspaces with more files than this are not hash-snapshotted.
+const MAX_HASHED_FILES: usize = 20_000;
+
+/// Files changed by the CLI turn that followed decision `seq`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TurnChanges {
+    pub seq: u64,
+    pub files: Vec<ChangedFile>,
+    /// Line counts; `None` outside a git repository.
+    pub insertions: Option<u64>,
+    pub deletions: Option<u64>,
+    /// The turn's patch, written when the workspace is a git repository.
+    pub patch_path: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChangedFile {
+    pub path: String,
+    pub change: FileChange,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileChange {
+    Added,
+    Modified,
+    Deleted,
+}
+
+impl FileChange {
+    pub fn marker(self) -> char {
+        match self {
+            Self::Added => 'A',
+            Self::Modified => 'M',
+            Self::Deleted => 'D',
+        }
+    }
+}
+
+impl TurnChanges {
+    /// One-line summary, e.g. `3 files changed (+10 -2)`.
+    pub fn summary(&self) -> String {
+        let files = match self.files.len() {
+            1 => "1 file changed".to_string(),
+            count => format!("{count} files changed"),
+        };
+        match (self.insertions, self.deletions) {
+            (Some(insertions), Some(deletions)) => format!("{files} (+{insertions} -{deletions})"),
+            _ => files,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum WorkspaceSnapshot {
+    Git { commit: String },
+    Files(BTreeMap<String, String>),
+}
+
+impl WorkspaceSnapshot {
+    fn capture(cwd: &Path) -> Option<Self> {
+        match ghost_snapshot(cwd) {
+            Some(commit) => Some(Self::Git { commit }),
+            None => hash_files(cwd).map(Self::Files),
+        }
+    }
+}
+
+/// Commits the working tree, untracked files included, without touching the
+/// index or any ref. `None` outside a git repository.
+pub(crate) fn ghost_snapshot(cwd: &Path) -> Option<String> {
+    create_ghost_commit(&CreateGhostCommitOptions::new(cwd))
+        .ok()
+        .map(|commit| commit.id().to_string())
+}
+
+/// `git diff` between two commits; `None` when git fails.
+pub(crate) fn git_diff(cwd: &Path, args: &[&str], from: &str, to: &str) -> Option<String> {
+    let output = Command::new("git")
+        .args(["diff", "--no-renames", "--no-color"])
+        .args(args)
+        .args([from, to])
+        .current_dir(cwd)
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Snapshots the workspace around CLI turns and records what each turn
+/// changed.
+#[derive(Debug)]
+pub struct TurnChangeTracker {
+    cwd: PathBuf,
+    patch_dir: PathBuf,
+    before: Option<WorkspaceSnapshot>,
+}
+
+impl TurnChangeTracker {
+    /// Patches are written into `patch_dir`, created on first use.
+    pub fn new(cwd: impl Into<PathBuf>, patch_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            cwd: cwd.into(),
+            patch_dir: patch_dir.into(),
+            before: None,
+        }
+    }
+
+    /// Snapshots the workspace before a turn starts.
+    pub fn begin_turn(&mut self) {
+        self.before = WorkspaceSnapshot::capture(&self.cwd);
+    }
+
+    /// Compares the workspace against the snapshot taken by `begin_turn`.
+    /// `None` when nothing changed or no snapshot could be taken.
+    pub fn finish_turn(&mut self, seq: u64) -> Option<TurnChanges> {
+        let before = self.before.take()?;
+        let after = WorkspaceSnapshot::capture(&self.cwd)?;
+        let changes = match (&before, &after) {
+            (WorkspaceSnapshot::Git { commit: from }, WorkspaceSnapshot::Git { commit: to }) => {
+                self.git_changes(seq, from, to)
+            }
+            (WorkspaceSnapshot::Files(from), WorkspaceSnapshot::Files(to)) => {
+                Some(file_hash_changes(seq, from, to))
+            }
+            _ => None,
+        };
+        changes.filter(|changes| !changes.files.is_empty())
+    }
+
+    fn git_changes(&self, seq: u64, from: &str, to: &str) -> Option<TurnChanges> {
+        let git = |args: &[&str]| git_diff(&self.cwd, args, from, to);
+        let mut changes = parse_name_status(seq, &git(&["--name-status"])?);
+        let (insertions, deletions) = parse_numstat(&git(&["--numstat"])?);
+        changes.insertions = Some(insertions);
+        changes.deletions = Some(deletions);
+        if !changes.files.is_empty()
+            && let Some(patch) = git(&["--binary"])
+        {
+            match self.write_patch(seq, &patch) {
+                Ok(path) => changes.patch_path = Some(path),
+                Err(err) => tracing::warn!("failed to store turn patch: {err:#}"),
+            }
+        }
+        Some(changes)
+    }
+
+    fn write_patch(&self, seq: u64, patch: &str) -> anyhow::Result<PathBuf> {
+        fs::create_dir_all(&self.patch_dir)
+            .with_context(|| format!("failed to create {}", self.patch_dir.display()))?;
+        let path = self.patch_dir.join(format!("decision-{seq}.patch"));
+        fs::write(&path, patch).with_context(|| format!("failed to write {}", path.display()))?;
+        Ok(path)
+    }
+}
+
+fn parse_name_status(seq: u64, output: &str) -> TurnChanges {
+    let files = output
+        .lines()
+        .filter_map(|line| {
+            let (status, path) = line.split_once('\t')?;
+            let change = match status.chars().next()? {
+                'A' => FileChange::Added,
+                'D' => FileChange::Deleted,
+                _ => FileChange::Modified,
+            };
+            Some(ChangedFile {
+                path: path.to_string(),
+                change,
+            })
+        })
+        .collect();
+    TurnChanges {
+        seq,
+        files,
+        insertions: None,
+        deletions: None,
+        patch_path: None,
+    }
+}
+
+/// Total inserted and deleted lines; binary files (`-`) count as zero.
+fn parse_numstat(output: &str) -> (u64, u64) {
+    output
+        .lines()
+        .fold((0, 0), |(insertions, deletions), line| {
+            let mut fields = line.split('\t');
+            let mut count = || {
+                fields
+                    .next()
+                    .and_then(|field| field.parse::<u64>().ok())
+                    .unwrap_or(0)
+            };
+            let added = count();
+            let removed = count();
+            (insertions + added, deletions + removed)
+        })
+}
+
+fn file_hash_changes(
+    seq: u64,
+    before: &BTreeMap<String, String>,
+    after: &BTreeMap<String, String>,
+) -> TurnChanges {
+    let mut files = Vec::new();
+    for (path, hash) in after {
+        match before.get(path) {
+            None => files.push(ChangedFile {
+                path: path.clone(),
+                change: FileChange::Added,
+            }),
+            Some(previous) if previous != hash => files.push(ChangedFile {
+                path: path.clone(),
+                change: FileChange::Modified,
+            }),
+            Some(_) => {}
+        }
+    }
+    files.extend(
+        before
+            .keys()
+            .filter(|path| !after.contains_key(*path))
+            .map(|path| ChangedFile {
+                path: path.clone(),
+                change: FileChange::Deleted,
+            }),
+    );
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    TurnChanges {
+        seq,
+        files,
+        insertions: None,
+        deletions: None,
+        patch_path: None,
+    }
+}
+
+/// SHA-256 of every file under `root`, keyed by `/`-separated relative path.
+/// `None` when the tree cannot be read or is too large to hash.
+fn hash_files(root: &Path) -> Option<BTreeMap<String, String>> {
+    let mut hashes = BTreeMap::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in fs::read_dir(&dir).ok()?.flatten() {
+            let path = entry.path();
+            let file_type = entry.file_type().ok()?;
+            if file_type.is_dir() {
+                let name = entry.file_name();
+                if !SKIPPED_DIRS.iter().any(|skipped| name == *skipped) {
+                    pending.push(path);
+                }
+                continue;
+            }
+            if !file_type.is_file() {
+                continue;
+            }
+            if hashes.len() >= MAX_HASHED_FILES {
+                return None;
+            }
+            let Ok(contents) = fs::read(&path) else {
+                continue;
+            };
+            let relative = path.strip_prefix(root).ok()?;
+            let key = relative
+                .components()
+                .map(|part| part.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            let digest = Sha256::digest(&contents);
+            hashes.insert(
+                key,
+                digest.iter().map(|byte| format!("{byte:02x}")).collect(),
+            );
+        }
+    }
+    Some(hashes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn file_hash_snapshots_attribute_changes_to_the_turn() {
+        let workspace = tempfile::tempdir().unwrap();
+        let reports = tempfile::tempdir().unwrap();
+        fs::write(workspace.path().join("keep.txt"), "same").unwrap();
+        fs::write(workspace.path().join("edit.txt"), "old").unwrap();
+        fs::write(workspace.path().join("gone.txt"), "bye").unwrap();
+        fs::create_dir(workspace.path().join("node_modules")).unwrap();
+
+        let mut tracker = TurnChangeTracker::new(workspace.path(), reports.path());
+        tracker.begin_turn();
+        fs::write(workspace.path().join("edit.txt"), "new").unwrap();
+        fs::remove_file(workspace.path().join("gone.txt")).unwrap();
+        fs::create_dir(workspace.path().join("src")).unwrap();
+        fs::write(workspace.path().join("src/lib.rs"), "fn main() {}").unwrap();
+        fs::write(workspace.path().join("node_modules/dep.js"), "ignored").unwrap();
+        let changes = tracker.finish_turn(4).expect("changes recorded");
+
+        assert_eq!(changes.seq, 4);
+        assert_eq!(
+            changes
+                .files
+                .iter()
+                .map(|file| format!("{} {}", file.change.marker(), file.path))
+                .collect::<Vec<_>>(),
+            vec!["M edit.txt", "D gone.txt", "A src/lib.rs"]
+        );
+        assert_eq!(changes.summary(), "3 files changed");
+        assert_eq!(changes.patch_path, None);
+
+        tracker.begin_turn();
+        assert_eq!(tracker.finish_turn(5), None);
+    }
+
+    #[test]
+    fn parses_git_diff_listings() {
+        let changes = parse_name_status(2, "M\tsrc/lib.rs\nA\tsrc/new.rs\nD\told.rs\n");
+        assert_eq!(
+            changes
+                .files
+                .iter()
+                .map(|file| file.change)
+                .collect::<Vec<_>>(),
+            vec![FileChange::Modified, FileChange::Added, FileChange::Deleted]
+        );
+        assert_eq!(
+            parse_numstat("10\t2\tsrc/lib.rs\n-\t-\tlogo.png\n3\t0\tsrc/new.rs\n"),
+            (13, 2)
+        );
+    }
+}
@@ -0,0 +1,188 @@
+//! Checks Auto Drive runs after each CLI turn.
+//!
+//! They run as a task on the coordinator's runtime so the coordinator thread
+//! keeps handling commands (stop, pause, usage reports) while a slow test
+//! command, turn review or workspace fingerprint runs. The outcome comes back
+//! through the command channel as [`AutoCoordinatorCommand::TurnChecked`], one
+//! turn at a time and in order.
+
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Weak;
+use std::sync::mpsc::Sender;
+
+use code_protocol::models::ResponseItem;
+use tokio::runtime::Runtime;
+use tokio::task::AbortHandle;
+
+use crate::AutoCoordinatorCommand;
+use crate::loop_detector::workspace_fingerprint;
+use crate::selective_tests::SelectiveTestRunner;
+use crate::selective_tests::TestCommandResult;
+use crate::stop_conditions::StopConditions;
+use crate::stop_conditions::StopEvaluation;
+use crate::turn_review::TurnReview;
+use crate::turn_review::TurnReviewer;
+
+/// Outcome of the checks run after one CLI turn.
+#[derive(Debug)]
+pub struct TurnChecks {
+    pub(crate) conversation: Vec<ResponseItem>,
+    /// `None` without `[auto_drive.stop_when]`.
+    pub(crate) stop: Option<StopEvaluation>,
+    /// Selective tests run for the files the turn changed; `None` when none
+    /// were selected.
+    pub(crate) tests: Option<Vec<TestCommandResult>>,
+    /// Reviews of the turn requested by the decision that started it.
+    pub(crate) reviews: Vec<TurnReview>,
+    /// Workspace fingerprint after the turn, for the loop detector; `None`
+    /// without one or outside a git repository.
+    pub(crate) fingerprint: Option<u64>,
+}
+
+/// Runs the checks of queued turns one after another.
+pub(crate) struct TurnChecker {
+    /// The coordinator's own command channel. Weak so the coordinator still
+    /// sees the channel close once every handle is dropped.
+    commands: Weak<Sender<AutoCoordinatorCommand>>,
+    stop_conditions: Option<Arc<StopConditions>>,
+    selective_tests: Option<Arc<SelectiveTestRunner>>,
+    reviewer: Option<Arc<TurnReviewer>>,
+    /// Workspace to fingerprint after each turn.
+    fingerprint_cwd: Option<PathBuf>,
+    running: Option<AbortHandle>,
+    waiting: VecDeque<Vec<ResponseItem>>,
+}
+
+impl TurnChecker {
+    pub fn new(
+        commands: Weak<Sender<AutoCoordinatorCommand>>,
+        stop_conditions: Option<StopConditions>,
+        selective_tests: Option<SelectiveTestRunner>,
+        reviewer: Option<Arc<TurnReviewer>>,
+        fingerprint_cwd: Option<PathBuf>,
+    ) -> Self {
+        Self {
+            commands,
+            stop_conditions: stop_conditions.map(Arc::new),
+            selective_tests: selective_tests.map(Arc::new),
+            reviewer,
+            fingerprint_cwd,
+            running: None,
+            waiting: VecDeque::new(),
+        }
+    }
+
+    /// Checks `conversation` once the turns submitted before it are done.
+    pub fn submit(&mut self, runtime: &Runtime, conversation: Vec<ResponseItem>) {
+        self.waiting.push_back(conversation);
+        if self.running.is_none() {
+            self.start_next(runtime);
+        }
+    }
+
+    /// Records that the running checks reported back and starts the next
+    /// queued turn.
+    pub fn finished(&mut self, runtime: &Runtime) {
+        self.running = None;
+        self.start_next(runtime);
+    }
+
+    /// Aborts the running checks and drops the queued turns.
+    pub fn cancel(&mut self) {
+        if let Some(running) = self.running.take() {
+            running.abort();
+        }
+        self.waiting.clear();
+    }
+
+    fn start_next(&mut self, runtime: &Runtime) {
+        let Some(conversation) = self.waiting.pop_front() else {
+            return;
+        };
+        let commands = self.commands.clone();
+        let stop_conditions = self.stop_conditions.clone();
+        let selective_tests = self.selective_tests.clone();
+        let reviewer = self.reviewer.clone();
+        let fingerprint_cwd = self.fingerprint_cwd.clone();
+        let task = runtime.spawn(async move {
+            let stop = match stop_conditions {
+                Some(conditions) => Some(conditions.evaluate().await),
+                None => None,
+            };
+            let tests = match selective_tests {
+                Some(runner) => runner.run_after_turn().await,
+                None => None,
+            };
+            let reviews = match reviewer {
+                Some(reviewer) => reviewer.after_turn().await,
+                None => Vec::new(),
+            };
+            let fingerprint = match fingerprint_cwd {
+                Some(cwd) => tokio::task::spawn_blocking(move || workspace_fingerprint(&cwd))
+                    .await
+                    .ok()
+                    .flatten(),
+                None => None,
+            };
+            if let Some(commands) = commands.upgrade() {
+                let _ = commands.send(AutoCoordinatorCommand::TurnChecked(TurnChecks {
+                    conversation,
+                    stop,
+                    tests,
+                    reviews,
+                    fingerprint,
+                }));
+            }
+        });
+        self.running = Some(task.abort_handle());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    fn message(text: &str) -> Vec<ResponseItem> {
+        vec![ResponseItem::Message {
+            id: None,
+            role: "user".to_string(),
+            content: vec![code_protocol::models::ContentItem::InputText {
+                text: text.to_string(),
+            }],
+        }]
+    }
+
+    #[test]
+    fn reports_turns_in_order_and_stops_when_the_channel_closes() {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let (tx, rx) = mpsc::channel();
+        let tx = Arc::new(tx);
+        let mut checker = TurnChecker::new(Arc::downgrade(&tx), None, None, None, None);
+
+        checker.submit(&runtime, message("first"));
+        checker.submit(&runtime, message("second"));
+        let mut seen = Vec::new();
+        for _ in 0..2 {
+            let Ok(AutoCoordinatorCommand::TurnChecked(checks)) =
+                rx.recv_timeout(Duration::from_secs(5))
+            else {
+                panic!("expected turn checks");
+            };
+            seen.push(checks.conversation);
+            checker.finished(&runtime);
+        }
+        assert_eq!(seen, vec![message("first"), message("second")]);
+
+        drop(tx);
+        checker.submit(&runtime, message("third"));
+        assert!(rx.recv_timeout(Duration::from_secs(5)).is_err());
+    }
+}
@@ -0,0 +1,338 @@
+//! Code reviews requested by coordinator decisions.
+//!
+//! With `auto_drive.turn_reviews` on, a decision may ask for a review of the
+//! CLI turn it starts. The review runs in its own review-mode session on
+//! `review_model`, under a read-only sandbox that never asks for approval, so
+//! the reviewer can read the workspace to check the diff but can never change
+//! it. Its findings join the coordinator history before the next decision.
+//!
+//! - `immediate`: the workspace is snapshotted before the CLI turn starts,
+//!   and the diff the turn produced is reviewed with the other post-turn
+//!   checks, off the coordinator thread.
+//! - `pre_write`: the workspace's uncommitted changes are reviewed before the
+//!   CLI turn writes anything, and the findings ride along with the turn's
+//!   prompt as context.
+//!
+//! `post_turn` reviews are left to the TUI's post-turn review flow.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use anyhow::Context;
+use anyhow::Result;
+use code_core::AuthManager;
+use code_core::ConversationManager;
+use code_core::config::Config;
+use code_core::protocol::AskForApproval;
+use code_core::protocol::EventMsg;
+use code_core::protocol::Op;
+use code_core::protocol::ReviewOutputEvent;
+use code_core::protocol::ReviewRequest;
+use code_core::protocol::SandboxPolicy;
+use code_core::review_format::format_review_findings_block;
+use code_protocol::protocol::SessionSource;
+
+use crate::auto_coordinator::ReviewStrategy;
+use crate::auto_coordinator::ReviewTiming;
+use crate::turn_changes::ghost_snapshot;
+use crate::turn_changes::git_diff;
+
+/// Diffs longer than this are cut before they reach the reviewer.
+const MAX_REVIEW_DIFF_CHARS: usize = 120_000;
+
+#[derive(Debug, Clone)]
+pub(crate) struct TurnReview {
+    pub timing: ReviewTiming,
+    pub output: ReviewOutputEvent,
+}
+
+impl TurnReview {
+    /// One-line summary for the Auto Drive card and exec output.
+    pub fn summary(&self) -> String {
+        let findings = match self.output.findings.len() {
+            1 => "1 finding".to_string(),
+            count => format!("{count} findings"),
+        };
+        let verdict = self.output.overall_correctness.trim();
+        if verdict.is_empty() {
+            format!("review ({}): {findings}", timing_label(self.timing))
+        } else {
+            format!(
+                "review ({}): {findings}, {verdict}",
+                timing_label(self.timing)
+            )
+        }
+    }
+
+    /// The findings as handed to the coordinator and the CLI.
+    pub fn message(&self) -> String {
+        let subject = match self.timing {
+            ReviewTiming::PreWrite => "the uncommitted changes in the workspace",
+            ReviewTiming::Immediate | ReviewTiming::PostTurn => "the last CLI turn's changes",
+        };
+        let mut message = format!("Code review of {subject}");
+        let verdict = self.output.overall_correctness.trim();
+        if !verdict.is_empty() {
+            message.push_str(&format!(": {verdict}"));
+        }
+        let explanation = self.output.overall_explanation.trim();
+        if !explanation.is_empty() {
+            message.push_str(&format!("\n{explanation}"));
+        }
+        if self.output.findings.is_empty() {
+            message.push_str("\nNo findings.");
+        } else {
+            message.push_str(&format_review_findings_block(&self.output.findings, None));
+            message.push_str("\nAddress these findings before building on these changes.");
+        }
+        message
+    }
+}
+
+pub(crate) struct TurnReviewer {
+    cwd: PathBuf,
+    conversations: ConversationManager,
+    /// The session config, read-only and without approvals.
+    review_config: Config,
+    /// Strategy and pre-turn snapshot of an `immediate` review.
+    pending: Mutex<Option<(ReviewStrategy, String)>>,
+    /// A `pre_write` review waiting to join the history after its turn.
+    ready: Mutex<Option<TurnReview>>,
+}
+
+impl TurnReviewer {
+    pub fn from_config(config: &Config, auth_manager: Arc<AuthManager>) -> Option<Self> {
+        if !config.auto_drive.turn_reviews {
+            return None;
+        }
+        let mut review_config = config.clone();
+        review_config.sandbox_policy = SandboxPolicy::ReadOnly;
+        review_config.approval_policy = AskForApproval::Never;
+        review_config.tools_web_search_request = false;
+        Some(Self {
+            cwd: config.cwd.clone(),
+            conversations: ConversationManager::new(auth_manager, SessionSource::Exec),
+            review_config,
+            pending: Mutex::new(None),
+            ready: Mutex::new(None),
+        })
+    }
+
+    pub fn model(&self) -> &str {
+        &self.review_config.review_model
+    }
+
+    /// Runs before the CLI turn of a decision that requested a review.
+    /// Returns the review when it ran now, i.e. for `pre_write`.
+    pub async fn before_turn(&self, strategy: &ReviewStrategy) -> Option<TurnReview> {
+        set(&self.pending, None);
+        set(&self.ready, None);
+        match strategy.timing {
+            ReviewTiming::Immediate => {
+                let snapshot = ghost_snapshot(&self.cwd)?;
+                set(&self.pending, Some((strategy.clone(), snapshot)));
+                None
+            }
+            ReviewTiming::PreWrite => {
+                let snapshot = ghost_snapshot(&self.cwd)?;
+                let diff = git_diff(&self.cwd, &[], "HEAD", &snapshot)?;
+                let review = self.review(strategy, &diff).await?;
+                set(&self.ready, Some(review.clone()));
+                Some(review)
+            }
+            ReviewTiming::PostTurn => None,
+        }
+    }
+
+    /// Runs when the CLI turn ends and returns the reviews that belong in the
+    /// coordinator history.
+    pub async fn after_turn(&self) -> Vec<TurnReview> {
+        let mut reviews: Vec<TurnReview> = take(&self.ready).into_iter().collect();
+        if let Some((strategy, before)) = take(&self.pending) {
+            let cwd = self.cwd.clone();
+            let diff = tokio::task::spawn_blocking(move || {
+                let after = ghost_snapshot(&cwd)?;
+                git_diff(&cwd, &[], &before, &after)
+            })
+            .await
+            .ok()
+            .flatten();
+            if let Some(diff) = diff
+                && let Some(review) = self.review(&strategy, &diff).await
+            {
+                reviews.push(review);
+            }
+        }
+        reviews
+    }
+
+    async fn review(&self, strategy: &ReviewStrategy, diff: &str) -> Option<TurnReview> {
+        if diff.trim().is_empty() {
+            return None;
+        }
+        let request = ReviewRequest {
+            prompt: build_review_request(strategy, diff),
+            user_facing_hint: format!("Auto Drive {} review", timing_label(strategy.timing)),
+            metadata: None,
+        };
+        match self.run_review_session(request).await {
+            Ok(output) => Some(TurnReview {
+                timing: strategy.timing,
+                output,
+            }),
+            Err(err) => {
+                tracing::warn!("auto drive turn review failed: {err:#}");
+                None
+            }
+        }
+    }
+
+    /// Runs `request` in a fresh read-only review session and shuts it down.
+    async fn run_review_session(&self, request: ReviewRequest) -> Result<ReviewOutputEvent> {
+        let new = self
+            .conversations
+            .new_conversation(self.review_config.clone())
+            .await
+            .context("failed to start review session")?;
+        let conversation = new.conversation;
+        let outcome = async {
+            conversation
+                .submit(Op::Review {
+                    review_request: request,
+                })
+                .await?;
+            while let Ok(event) = conversation.next_event().await {
+                match event.msg {
+                    EventMsg::ExitedReviewMode(output) => {
+                        return output.context("review ended without a result");
+                    }
+                    EventMsg::Error(error) => anyhow::bail!(error.message),
+                    EventMsg::ShutdownComplete => break,
+                    _ => {}
+                }
+            }
+            anyhow::bail!("review session ended before the review finished")
+        }
+        .await;
+        let _ = conversation.submit(Op::Shutdown).await;
+        while let Ok(event) = conversation.next_event().await {
+            if matches!(event.msg, EventMsg::ShutdownComplete) {
+                break;
+            }
+        }
+        self.conversations
+            .remove_conversation(&new.conversation_id)
+            .await;
+        outcome
+    }
+}
+
+fn set<T>(slot: &Mutex<Option<T>>, value: Option<T>) {
+    if let Ok(mut slot) = slot.lock() {
+        *slot = value;
+    }
+}
+
+fn take<T>(slot: &Mutex<Option<T>>) -> Option<T> {
+    slot.lock().ok().and_then(|mut slot| slot.take())
+}
+
+pub(crate) fn timing_label(timing: ReviewTiming) -> &'static str {
+    match timing {
+        ReviewTiming::PostTurn => "post_turn",
+        ReviewTiming::PreWrite => "pre_write",
+        ReviewTiming::Immediate => "immediate",
+    }
+}
+
+fn build_review_request(strategy: &ReviewStrategy, diff: &str) -> String {
+    let subject = match strategy.timing {
+        ReviewTiming::PreWrite => {
+            "the uncommitted changes in this workspace. An automated agent is about to make further changes on top of them"
+        }
+        ReviewTiming::Immediate | ReviewTiming::PostTurn => {
+            "the changes an automated agent just made to this workspace"
+        }
+    };
+    let mut request = format!("Review {subject}.");
+    if let Some(hint) = strategy.scope_hint.as_deref().map(str::trim)
+        && !hint.is_empty()
+    {
+        request.push_str(&format!("\nFocus on: {hint}"));
+    }
+    if let Some(custom) = strategy.custom_prompt.as_deref().map(str::trim)
+        && !custom.is_empty()
+    {
+        request.push_str(&format!("\n{custom}"));
+    }
+    request.push_str(&format!("\n\n```diff\n{}\n```", truncate_diff(diff)));
+    request
+}
+
+fn truncate_diff(diff: &str) -> String {
+    if diff.len() <= MAX_REVIEW_DIFF_CHARS {
+        return diff.trim_end().to_string();
+    }
+    let mut end = MAX_REVIEW_DIFF_CHARS;
+    while !diff.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!(
+        "{}\n... diff truncated ({} more bytes)",
+        &diff[..end],
+        diff.len() - end
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parses_review_findings_into_coordinator_messages() {
+        let raw = r#"{
+  "findings": [{
+    "title": "[P1] Retry loop never sleeps",
+    "body": "The backoff is computed but not awaited.",
+    "confidence_score": 0.8,
+    "priority": 1,
+    "code_location": {
+      "absolute_file_path": "/repo/src/retry.rs",
+      "line_range": {"start": 10, "end": 12}
+    }
+  }],
+  "overall_correctness": "patch is incorrect",
+  "overall_explanation": "The retry loop spins.",
+  "overall_confidence_score": 0.7
+}"#;
+        let review = TurnReview {
+            timing: ReviewTiming::Immediate,
+            output: serde_json::from_str(raw).unwrap(),
+        };
+        assert_eq!(
+            review.summary(),
+            "review (immediate): 1 finding, patch is incorrect"
+        );
+        let message = review.message();
+        assert!(message.starts_with(
+            "Code review of the last CLI turn's changes: patch is incorrect\nThe retry loop spins."
+        ));
+        assert!(message.contains("- [P1] Retry loop never sleeps — /repo/src/retry.rs:10-12"));
+    }
+
+    #[test]
+    fn review_request_carries_focus_and_truncated_diff() {
+        let strategy = ReviewStrategy {
+            timing: ReviewTiming::PreWrite,
+            custom_prompt: None,
+            scope_hint: Some("the retry backoff".to_string()),
+        };
+        let diff = "+".repeat(MAX_REVIEW_DIFF_CHARS + 10);
+        let text = build_review_request(&strategy, &diff);
+        assert!(text.starts_with("Review the uncommitted changes in this workspace."));
+        assert!(text.contains("\nFocus on: the retry backoff\n"));
+        assert!(text.ends_with("... diff truncated (10 more bytes)\n```"));
+    }
+}
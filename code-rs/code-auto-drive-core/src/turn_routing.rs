@@ -0,0 +1,149 @@
+//! Complexity-based model routing for CLI turns.
+//!
+//! With `[auto_drive.cli_model_routing]` set, the coordinator tags every CLI
+//! prompt low, medium, or high. Low- and medium-complexity turns run on the
+//! configured cheaper model; high-complexity turns, and levels with no model
+//! configured, stay on the session model. When a decision carries no tag, a
+//! keyword heuristic estimates one.
+
+use code_core::config_types::AutoDriveSettings;
+
+use crate::auto_coordinator::AutoTurnCliAction;
+use crate::auto_coordinator::TurnComplexity;
+
+/// Longest prompt the heuristic still considers a quick mechanical step.
+const LOW_COMPLEXITY_MAX_CHARS: usize = 160;
+
+/// Leading verbs of mechanical turns.
+const LOW_COMPLEXITY_VERBS: &[&str] = &[
+    "run", "rerun", "re-run", "format", "commit", "push", "show", "list", "check", "verify",
+    "lint", "build",
+];
+
+/// Words that mark a turn as design or investigation work.
+const HIGH_COMPLEXITY_WORDS: &[&str] = &[
+    "refactor",
+    "redesign",
+    "architecture",
+    "architect",
+    "migrate",
+    "investigate",
+    "root cause",
+    "debug",
+    "rewrite",
+    "design",
+];
+
+#[derive(Debug)]
+pub(crate) struct CliModelRouter {
+    low: Option<String>,
+    medium: Option<String>,
+}
+
+impl CliModelRouter {
+    /// `None` when no routing model is configured.
+    pub fn from_settings(settings: &AutoDriveSettings) -> Option<Self> {
+        let clean = |model: &Option<String>| {
+            model
+                .as_deref()
+                .map(str::trim)
+                .filter(|model| !model.is_empty())
+                .map(str::to_string)
+        };
+        let router = Self {
+            low: clean(&settings.cli_model_routing.low),
+            medium: clean(&settings.cli_model_routing.medium),
+        };
+        (router.low.is_some() || router.medium.is_some()).then_some(router)
+    }
+
+    pub fn model_for(&self, complexity: TurnComplexity) -> Option<&str> {
+        match complexity {
+            TurnComplexity::Low => self.low.as_deref(),
+            TurnComplexity::Medium => self.medium.as_deref(),
+            TurnComplexity::High => None,
+        }
+    }
+
+    /// Tags the action with its complexity and the model to run it on.
+    pub fn route(&self, action: &mut AutoTurnCliAction) {
+        let complexity = action
+            .complexity
+            .unwrap_or_else(|| estimate_complexity(&action.prompt, action.workstreams.len()));
+        action.complexity = Some(complexity);
+        action.model = self.model_for(complexity).map(str::to_string);
+    }
+}
+
+/// Heuristic complexity for a CLI prompt the coordinator did not tag.
+fn estimate_complexity(prompt: &str, workstreams: usize) -> TurnComplexity {
+    let lower = prompt.trim().to_lowercase();
+    if workstreams > 0
+        || HIGH_COMPLEXITY_WORDS
+            .iter()
+            .any(|word| lower.contains(word))
+    {
+        return TurnComplexity::High;
+    }
+    let first_word = lower
+        .split(|c: char| !c.is_alphanumeric() && c != '-')
+        .find(|word| !word.is_empty())
+        .unwrap_or_default();
+    if lower.chars().count() <= LOW_COMPLEXITY_MAX_CHARS
+        && LOW_COMPLEXITY_VERBS.contains(&first_word)
+    {
+        return TurnComplexity::Low;
+    }
+    TurnComplexity::Medium
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use code_core::config_types::CliModelRouting;
+    use pretty_assertions::assert_eq;
+
+    fn action(prompt: &str, complexity: Option<TurnComplexity>) -> AutoTurnCliAction {
+        AutoTurnCliAction {
+            prompt: prompt.to_string(),
+            context: None,
+            suppress_ui_context: false,
+            attachments: Vec::new(),
+            workstreams: Vec::new(),
+            complexity,
+            model: None,
+        }
+    }
+
+    #[test]
+    fn routes_tagged_and_estimated_turns_to_the_configured_models() {
+        assert!(CliModelRouter::from_settings(&AutoDriveSettings::default()).is_none());
+        let settings = AutoDriveSettings {
+            cli_model_routing: CliModelRouting {
+                low: Some("gpt-5.1-codex-mini".to_string()),
+                medium: Some(" ".to_string()),
+            },
+            ..AutoDriveSettings::default()
+        };
+        let router = CliModelRouter::from_settings(&settings).expect("routing enabled");
+
+        let mut tagged = action("Refactor the parser", Some(TurnComplexity::Low));
+        router.route(&mut tagged);
+        assert_eq!(tagged.model.as_deref(), Some("gpt-5.1-codex-mini"));
+
+        let mut estimated = action("Run cargo test -p parser and report failures", None);
+        router.route(&mut estimated);
+        assert_eq!(estimated.complexity, Some(TurnComplexity::Low));
+        assert_eq!(estimated.model.as_deref(), Some("gpt-5.1-codex-mini"));
+
+        let mut medium = action("Add a --json flag to the stats command", None);
+        router.route(&mut medium);
+        assert_eq!(medium.complexity, Some(TurnComplexity::Medium));
+        assert_eq!(medium.model, None);
+
+        let mut high = action("Run the migration and investigate the flaky test", None);
+        router.route(&mut high);
+        assert_eq!(high.complexity, Some(TurnComplexity::High));
+        assert_eq!(high.model, None);
+    }
+}
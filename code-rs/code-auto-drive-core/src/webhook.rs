@@ -0,0 +1,250 @@
+//! Status webhooks for Auto Drive runs.
+//!
+//! With `auto_drive.webhook_url` set, every decision, budget alert, and
+//! diagnostic alert is POSTed to the endpoint as a small JSON document, so
+//! long-running sessions can feed chat or paging tools without anyone
+//! tailing stdout. Decisions that end the run carry `"terminal": true`.
+//!
+//! When `auto_drive.webhook_secret` is set, each request carries
+//! `X-Auto-Drive-Signature: sha256=<hex>`, the HMAC-SHA256 of the raw body
+//! under that secret. Delivery is best effort: requests go out in order from
+//! a background thread, and failures are logged and never retried. Only a
+//! terminal decision waits for its delivery, so the notification is not lost
+//! when the process exits right after the run ends.
+
+use std::sync::mpsc;
+use std::time::Duration;
+
+use chrono::Utc;
+use code_core::config_types::AutoDriveSettings;
+use ring::hmac;
+use serde_json::Value;
+use serde_json::json;
+
+use crate::auto_coordinator::AutoCoordinatorEvent;
+use crate::auto_coordinator::AutoCoordinatorStatus;
+
+const SIGNATURE_HEADER: &str = "X-Auto-Drive-Signature";
+const EVENT_HEADER: &str = "X-Auto-Drive-Event";
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+struct Delivery {
+    kind: &'static str,
+    body: Vec<u8>,
+    signature: Option<String>,
+    delivered: Option<mpsc::Sender<()>>,
+}
+
+pub(crate) struct WebhookNotifier {
+    secret: Option<String>,
+    tx: mpsc::Sender<Delivery>,
+}
+
+impl WebhookNotifier {
+    /// `None` when no webhook is configured or the delivery thread cannot
+    /// start.
+    pub fn from_settings(settings: &AutoDriveSettings) -> Option<Self> {
+        let url = settings
+            .webhook_url
+            .as_deref()
+            .map(str::trim)
+            .filter(|url| !url.is_empty())?
+            .to_string();
+        let secret = settings
+            .webhook_secret
+            .clone()
+            .filter(|secret| !secret.is_empty());
+        let (tx, rx) = mpsc::channel();
+        let spawned = std::thread::Builder::new()
+            .name("code-auto-webhook".to_string())
+            .spawn(move || deliver_all(&url, rx));
+        if let Err(err) = spawned {
+            tracing::warn!("auto drive webhook disabled: {err}");
+            return None;
+        }
+        Some(Self { secret, tx })
+    }
+
+    /// Queues the event for delivery if it is one the webhook reports.
+    pub fn notify(&self, event: &AutoCoordinatorEvent) {
+        let Some((kind, payload)) = webhook_payload(event) else {
+            return;
+        };
+        let terminal = payload["terminal"].as_bool().unwrap_or(false);
+        let body = payload.to_string().into_bytes();
+        let signature = self
+            .secret
+            .as_deref()
+            .map(|secret| format!("sha256={}", hmac_sha256_hex(secret.as_bytes(), &body)));
+        let (delivered, wait) = if terminal {
+            let (tx, rx) = mpsc::channel();
+            (Some(tx), Some(rx))
+        } else {
+            (None, None)
+        };
+        let delivery = Delivery {
+            kind,
+            body,
+            signature,
+            delivered,
+        };
+        if self.tx.send(delivery).is_ok()
+            && let Some(wait) = wait
+        {
+            let _ = wait.recv_timeout(REQUEST_TIMEOUT + Duration::from_secs(1));
+        }
+    }
+}
+
+fn deliver_all(url: &str, rx: mpsc::Receiver<Delivery>) {
+    let runtime = match tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+    {
+        Ok(runtime) => runtime,
+        Err(err) => {
+            tracing::warn!("auto drive webhook disabled: {err}");
+            return;
+        }
+    };
+    let client = match reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(err) => {
+            tracing::warn!("auto drive webhook disabled: {err}");
+            return;
+        }
+    };
+    while let Ok(delivery) = rx.recv() {
+        let mut request = client
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(EVENT_HEADER, delivery.kind)
+            .body(delivery.body);
+        if let Some(signature) = delivery.signature {
+            request = request.header(SIGNATURE_HEADER, signature);
+        }
+        match runtime.block_on(request.send()) {
+            Ok(response) if response.status().is_success() => {}
+            Ok(response) => {
+                tracing::warn!(
+                    "auto drive webhook {} rejected: HTTP {}",
+                    delivery.kind,
+                    response.status()
+                );
+            }
+            Err(err) => {
+                tracing::warn!("auto drive webhook {} failed: {err}", delivery.kind);
+            }
+        }
+        if let Some(delivered) = delivery.delivered {
+            let _ = delivered.send(());
+        }
+    }
+}
+
+/// Event name and JSON body for the events the webhook reports.
+fn webhook_payload(event: &AutoCoordinatorEvent) -> Option<(&'static str, Value)> {
+    let timestamp = Utc::now().to_rfc3339();
+    match event {
+        AutoCoordinatorEvent::Decision {
+            seq,
+            status,
+            status_title,
+            status_sent_to_user,
+            goal,
+            cli,
+            agents,
+            budget_snapshot,
+            ..
+        } => {
+            let status = match status {
+                AutoCoordinatorStatus::Continue => "continue",
+                AutoCoordinatorStatus::Success => "success",
+                AutoCoordinatorStatus::Failed => "failed",
+            };
+            Some((
+                "decision",
+                json!({
+                    "event": "decision",
+                    "timestamp": timestamp,
+                    "seq": seq,
+                    "status": status,
+                    "terminal": status != "continue",
+                    "title": status_title,
+                    "summary": status_sent_to_user,
+                    "goal": goal,
+                    "cli_prompt": cli.as_ref().map(|cli| cli.prompt.as_str()),
+                    "agents": agents.len(),
+                    "budget": budget_snapshot,
+                }),
+            ))
+        }
+        AutoCoordinatorEvent::BudgetAlert {
+            alert_type,
+            message,
+        } => Some((
+            "budget_alert",
+            json!({
+                "event": "budget_alert",
+                "timestamp": timestamp,
+                "alert_type": alert_type,
+                "message": message,
+            }),
+        )),
+        AutoCoordinatorEvent::DiagnosticAlert {
+            alert_type,
+            message,
+        } => Some((
+            "diagnostic_alert",
+            json!({
+                "event": "diagnostic_alert",
+                "timestamp": timestamp,
+                "alert_type": alert_type,
+                "message": message,
+            }),
+        )),
+        _ => None,
+    }
+}
+
+/// HMAC-SHA256 of `message` under `key`, hex encoded.
+fn hmac_sha256_hex(key: &[u8], message: &[u8]) -> String {
+    hmac::sign(&hmac::Key::new(hmac::HMAC_SHA256, key), message)
+        .as_ref()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auto_coordinator::BudgetAlertType;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn signs_reported_events_with_hmac_sha256() {
+        // RFC 4231 test case 2.
+        assert_eq!(
+            hmac_sha256_hex(b"Jefe", b"what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+
+        let (kind, payload) = webhook_payload(&AutoCoordinatorEvent::BudgetAlert {
+            alert_type: BudgetAlertType::TurnLimitReached,
+            message: "turn limit reached".to_string(),
+        })
+        .expect("budget alerts are reported");
+        assert_eq!(kind, "budget_alert");
+        assert_eq!(payload["alert_type"], "TurnLimitReached");
+        assert_eq!(payload["message"], "turn limit reached");
+
+        assert!(
+            webhook_payload(&AutoCoordinatorEvent::Action {
+                message: "running tests".to_string(),
+            })
+            .is_none()
+        );
+    }
+}
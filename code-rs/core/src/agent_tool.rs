@@ -444,6 +444,39 @@ impl AgentManager {
         }
     }
 
+    /// Cancels the agent once `timeout` elapses if it is still pending or
+    /// running. The progress recorded so far becomes its result.
+    pub fn set_agent_timeout(&self, agent_id: &str, timeout: TokioDuration) {
+        let agent_id = agent_id.to_string();
+        tokio::spawn(async move {
+            tokio::time::sleep(timeout).await;
+            AGENT_MANAGER
+                .write()
+                .await
+                .time_out_agent(&agent_id, timeout)
+                .await;
+        });
+    }
+
+    async fn time_out_agent(&mut self, agent_id: &str, timeout: TokioDuration) {
+        let Some(agent) = self.agents.get_mut(agent_id) else {
+            return;
+        };
+        if !matches!(agent.status, AgentStatus::Pending | AgentStatus::Running) {
+            return;
+        }
+        if let Some(handle) = self.handles.remove(agent_id) {
+            handle.abort();
+        }
+        agent.status = AgentStatus::Cancelled;
+        agent.error = Some(format!("timed out after {}s", timeout.as_secs()));
+        if !agent.progress.is_empty() {
+            agent.result = Some(agent.progress.join("\n"));
+        }
+        agent.completed_at = Some(Utc::now());
+        self.send_agent_status_update().await;
+    }
+
     pub async fn cancel_batch(&mut self, batch_id: &str) -> usize {
         let agent_ids: Vec<String> = self
             .agents
@@ -1661,6 +1694,14 @@ pub fn create_agent_tool(allowed_models: &[String]) -> OpenAiTool {
             ),
         },
     );
+    create_properties.insert(
+        "timeout_seconds".to_string(),
+        JsonSchema::Number {
+            description: Some(
+                "Optional seconds before the agent is cancelled; its partial output is kept as the result".to_string(),
+            ),
+        },
+    );
     properties.insert(
         "create".to_string(),
         JsonSchema::Object {
@@ -1830,6 +1871,9 @@ pub struct RunAgentParams {
     #[serde(default)]
     pub read_only: Option<bool>,
     pub name: Option<String>,
+    /// Seconds before the agent is cancelled, keeping its partial output.
+    #[serde(default)]
+    pub timeout_seconds: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1845,6 +1889,9 @@ pub struct AgentCreateOptions {
     #[serde(default)]
     pub read_only: Option<bool>,
     pub name: Option<String>,
+    /// Seconds before the agent is cancelled, keeping its partial output.
+    #[serde(default)]
+    pub timeout_seconds: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
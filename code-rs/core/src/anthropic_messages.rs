@@ -0,0 +1,886 @@
+//! Client for Anthropic's Messages API (`wire_api = "anthropic_messages"`).
+//!
+//! The request side folds the base instructions and developer messages into
+//! the top-level `system` prompt, turns function calls and their outputs into
+//! `tool_use` / `tool_result` blocks, and merges consecutive items of the same
+//! role, since the API expects user and assistant turns to alternate. The
+//! response side maps the streamed content blocks onto [`ResponseEvent`]s the
+//! way the Responses API reports them: text deltas as they arrive, then one
+//! `OutputItemDone` per finished block, then `Completed` with token usage.
+//! Providers with a `vertex` table send the same request to the `anthropic`
+//! publisher model on Vertex AI.
+
+use std::collections::BTreeMap;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use bytes::Bytes;
+use code_otel::otel_event_manager::OtelEventManager;
+use code_protocol::models::ContentItem;
+use code_protocol::models::ResponseItem;
+use eventsource_stream::Eventsource;
+use futures::Stream;
+use futures::StreamExt;
+use futures::TryStreamExt;
+use reqwest::StatusCode;
+use serde_json::Value;
+use serde_json::json;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use tracing::debug;
+use tracing::trace;
+
+use crate::ModelProviderInfo;
+use crate::auth::AuthManager;
+use crate::client_common::Prompt;
+use crate::client_common::ResponseEvent;
+use crate::client_common::ResponseStream;
+use crate::debug_logger::DebugLogger;
+use crate::error::CodexErr;
+use crate::error::Result;
+use crate::gemini::QuotaExhausted;
+use crate::model_family::ModelFamily;
+use crate::model_provider_info::VERTEX_ANTHROPIC_VERSION;
+use crate::openai_model_info::get_model_info;
+use crate::openai_tools::create_tools_json_for_chat_completions_api;
+use crate::protocol::TokenUsage;
+use crate::quota_ledger;
+use crate::request_middleware::MiddlewareStack;
+use crate::request_retry::Attempt;
+use crate::request_retry::Rejected;
+use crate::request_retry::Rejection;
+use crate::request_retry::is_retryable_status;
+use crate::request_retry::send_with_retries;
+use crate::request_tap::RequestTap;
+use crate::response_anomaly::ResponseAnomaly;
+use crate::sse_buffer::SSE_CHANNEL_CAPACITY;
+
+/// `max_tokens` is required by the API; used when the model is unknown.
+const DEFAULT_MAX_OUTPUT_TOKENS: u64 = 8_192;
+
+/// Stream error types worth retrying; any other error fails the turn.
+const TRANSIENT_ERROR_TYPES: [&str; 3] = ["overloaded_error", "api_error", "rate_limit_error"];
+
+pub(crate) async fn stream_anthropic_messages(
+    prompt: &Prompt,
+    model_family: &ModelFamily,
+    model_slug: &str,
+    client: &reqwest::Client,
+    provider: &ModelProviderInfo,
+    debug_logger: &Arc<Mutex<DebugLogger>>,
+    auth_manager: Option<Arc<AuthManager>>,
+    otel_event_manager: Option<OtelEventManager>,
+    middleware: &MiddlewareStack,
+    request_tap: Option<&RequestTap>,
+    log_tag: Option<&str>,
+) -> Result<ResponseStream> {
+    if prompt.output_schema.is_some() {
+        return Err(CodexErr::UnsupportedOperation(
+            "output_schema is not supported for the Anthropic Messages API".to_string(),
+        ));
+    }
+
+    let mut payload = build_messages_payload(prompt, model_family, model_slug)?;
+    if provider.vertex.is_some() {
+        use_vertex_payload(&mut payload);
+    }
+    let endpoint = provider.get_anthropic_stream_url(model_slug);
+    let extra_headers = middleware.prepare(&endpoint, &mut payload);
+    debug!(
+        "POST to {}: {}",
+        endpoint,
+        serde_json::to_string_pretty(&payload).unwrap_or_default()
+    );
+    if let Some(tap) = request_tap {
+        tap.observe(&endpoint, &extra_headers, &payload)?;
+    }
+
+    let estimate = quota_ledger::estimate_tokens(model_slug, prompt, model_family).await;
+    let delivered = send_with_retries(
+        provider,
+        debug_logger,
+        otel_event_manager.as_ref(),
+        log_tag,
+        estimate,
+        || {
+            let auth = auth_manager.as_ref().and_then(|m| m.auth());
+            let (payload, extra_headers) = (&payload, &extra_headers);
+            async move {
+                let request = provider
+                    .create_anthropic_request_builder(client, &auth, model_slug)
+                    .await?
+                    .header(reqwest::header::ACCEPT, "text/event-stream")
+                    .json(payload)
+                    .headers(extra_headers.clone());
+                Ok(Attempt { request, auth })
+            }
+        },
+        |rejection: Rejection| async move {
+            let status = rejection.response.status();
+            // Includes 529, Anthropic's "overloaded" status.
+            if !is_retryable_status(status) {
+                return rejection.unexpected_status(debug_logger).await;
+            }
+            let delay = rejection.retry_delay();
+            // Vertex AI reports exhausted project quotas in Google's error
+            // format.
+            if rejection.is_last
+                && provider.vertex.is_some()
+                && status == StatusCode::TOO_MANY_REQUESTS
+                && let Some(quota) =
+                    QuotaExhausted::from_body(&rejection.response.text().await.unwrap_or_default())
+            {
+                return Rejected::Fatal(quota.into_error(
+                    "vertex",
+                    delay,
+                    Some(rejection.request_id),
+                ));
+            }
+            Rejected::Retry(delay)
+        },
+    )
+    .await?;
+
+    let (tx_event, rx_event) = mpsc::channel::<Result<ResponseEvent>>(SSE_CHANNEL_CAPACITY);
+    let stream = delivered.response.bytes_stream().map_err(CodexErr::Reqwest);
+    tokio::spawn(process_messages_sse(
+        stream,
+        tx_event,
+        provider.stream_idle_timeout(),
+        Arc::clone(debug_logger),
+        delivered.request_id,
+        otel_event_manager,
+    ));
+    Ok(ResponseStream {
+        rx_event,
+        served_by: None,
+        retries: delivered.retries,
+    })
+}
+
+/// Builds the streaming Messages request for `prompt`.
+fn build_messages_payload(
+    prompt: &Prompt,
+    model_family: &ModelFamily,
+    model_slug: &str,
+) -> Result<Value> {
+    let mut system = prompt.get_full_instructions(model_family).into_owned();
+    let messages = translate_input(&prompt.get_formatted_input(), &mut system);
+    let tools = translate_tools(create_tools_json_for_chat_completions_api(&prompt.tools)?);
+    let max_tokens = get_model_info(model_family)
+        .map(|info| info.max_output_tokens)
+        .unwrap_or(DEFAULT_MAX_OUTPUT_TOKENS);
+
+    let mut payload = json!({
+        "model": model_slug,
+        "max_tokens": max_tokens,
+        "messages": messages,
+        "stream": true,
+    });
+    if let Some(obj) = payload.as_object_mut() {
+        if !system.trim().is_empty() {
+            obj.insert("system".to_string(), Value::String(system));
+        }
+        if !tools.is_empty() {
+            obj.insert("tools".to_string(), Value::Array(tools));
+        }
+        // The Messages API has no `seed`.
+        if let Some(temperature) = prompt.sampling.temperature {
+            obj.insert("temperature".to_string(), json!(temperature));
+        }
+        if let Some(top_p) = prompt.sampling.top_p {
+            obj.insert("top_p".to_string(), json!(top_p));
+        }
+    }
+    Ok(payload)
+}
+
+/// Vertex AI takes the model from the URL and the API version from the body.
+fn use_vertex_payload(payload: &mut Value) {
+    if let Some(obj) = payload.as_object_mut() {
+        obj.remove("model");
+        obj.insert(
+            "anthropic_version".to_string(),
+            Value::String(VERTEX_ANTHROPIC_VERSION.to_string()),
+        );
+    }
+}
+
+/// Converts conversation items to Messages API turns. Developer and system
+/// messages are appended to `system`.
+fn translate_input(input: &[ResponseItem], system: &mut String) -> Vec<Value> {
+    let mut messages = Vec::<Value>::new();
+    let mut tool_use_ids = HashSet::<String>::new();
+
+    for item in input {
+        match item {
+            ResponseItem::Message { role, content, .. } => {
+                if role == "developer" || role == "system" {
+                    let text = content_text(content);
+                    if !text.trim().is_empty() {
+                        if !system.is_empty() {
+                            system.push_str("\n\n");
+                        }
+                        system.push_str(&text);
+                    }
+                    continue;
+                }
+                let role = if role == "assistant" {
+                    "assistant"
+                } else {
+                    "user"
+                };
+                for content in content {
+                    match content {
+                        ContentItem::InputText { text } | ContentItem::OutputText { text } => {
+                            if !text.is_empty() {
+                                push_block(
+                                    &mut messages,
+                                    role,
+                                    json!({ "type": "text", "text": text }),
+                                );
+                            }
+                        }
+                        ContentItem::InputImage { image_url } => {
+                            push_block(&mut messages, role, image_block(image_url));
+                        }
+                    }
+                }
+            }
+            ResponseItem::FunctionCall {
+                name,
+                arguments,
+                call_id,
+                ..
+            } => {
+                let input = serde_json::from_str::<Value>(arguments)
+                    .ok()
+                    .filter(Value::is_object)
+                    .unwrap_or_else(|| json!({}));
+                tool_use_ids.insert(call_id.clone());
+                push_block(
+                    &mut messages,
+                    "assistant",
+                    json!({ "type": "tool_use", "id": call_id, "name": name, "input": input }),
+                );
+            }
+            ResponseItem::CustomToolCall {
+                call_id,
+                name,
+                input,
+                ..
+            } => {
+                tool_use_ids.insert(call_id.clone());
+                push_block(
+                    &mut messages,
+                    "assistant",
+                    json!({
+                        "type": "tool_use",
+                        "id": call_id,
+                        "name": name,
+                        "input": { "input": input },
+                    }),
+                );
+            }
+            ResponseItem::FunctionCallOutput { call_id, output } => {
+                // The API rejects results whose call it has not seen.
+                if tool_use_ids.contains(call_id) {
+                    push_block(
+                        &mut messages,
+                        "user",
+                        json!({
+                            "type": "tool_result",
+                            "tool_use_id": call_id,
+                            "content": output.content,
+                            "is_error": output.success == Some(false),
+                        }),
+                    );
+                }
+            }
+            ResponseItem::CustomToolCallOutput { call_id, output } => {
+                if tool_use_ids.contains(call_id) {
+                    push_block(
+                        &mut messages,
+                        "user",
+                        json!({ "type": "tool_result", "tool_use_id": call_id, "content": output }),
+                    );
+                }
+            }
+            // Local shell calls are an OpenAI built-in tool; Claude never
+            // issues them, and the rest only mean something to the
+            // Responses API.
+            ResponseItem::LocalShellCall { .. }
+            | ResponseItem::Reasoning { .. }
+            | ResponseItem::WebSearchCall { .. }
+            | ResponseItem::CompactionSummary { .. }
+            | ResponseItem::Other => {}
+        }
+    }
+    messages
+}
+
+fn content_text(content: &[ContentItem]) -> String {
+    content
+        .iter()
+        .filter_map(|content| match content {
+            ContentItem::InputText { text } | ContentItem::OutputText { text } => {
+                Some(text.as_str())
+            }
+            ContentItem::InputImage { .. } => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Appends `block` to the last message when it has the same role, so turns
+/// alternate as the API requires.
+fn push_block(messages: &mut Vec<Value>, role: &str, block: Value) {
+    if let Some(last) = messages.last_mut()
+        && last.get("role").and_then(Value::as_str) == Some(role)
+        && let Some(content) = last.get_mut("content").and_then(Value::as_array_mut)
+    {
+        content.push(block);
+        return;
+    }
+    messages.push(json!({ "role": role, "content": [block] }));
+}
+
+/// Data URLs become base64 image sources; anything else is passed by URL.
+fn image_block(image_url: &str) -> Value {
+    let source = image_url
+        .strip_prefix("data:")
+        .and_then(|rest| rest.split_once(";base64,"))
+        .map(|(media_type, data)| {
+            json!({ "type": "base64", "media_type": media_type, "data": data })
+        })
+        .unwrap_or_else(|| json!({ "type": "url", "url": image_url }));
+    json!({ "type": "image", "source": source })
+}
+
+/// Rewrites Chat Completions function tools as Messages API tools.
+fn translate_tools(tools: Vec<Value>) -> Vec<Value> {
+    tools
+        .into_iter()
+        .filter_map(|tool| {
+            let function = tool.get("function")?;
+            Some(json!({
+                "name": function.get("name")?,
+                "description": function
+                    .get("description")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default(),
+                "input_schema": function
+                    .get("parameters")
+                    .cloned()
+                    .unwrap_or_else(|| json!({ "type": "object", "properties": {} })),
+            }))
+        })
+        .collect()
+}
+
+async fn process_messages_sse<S>(
+    stream: S,
+    tx_event: mpsc::Sender<Result<ResponseEvent>>,
+    idle_timeout: Duration,
+    debug_logger: Arc<Mutex<DebugLogger>>,
+    request_id: String,
+    otel_event_manager: Option<OtelEventManager>,
+) where
+    S: Stream<Item = Result<Bytes>> + Unpin,
+{
+    let mut stream = stream.eventsource();
+    let mut state = MessagesStreamState::default();
+
+    loop {
+        let next_event = if let Some(manager) = otel_event_manager.as_ref() {
+            manager
+                .log_sse_event(|| timeout(idle_timeout, stream.next()))
+                .await
+        } else {
+            timeout(idle_timeout, stream.next()).await
+        };
+
+        let sse = match next_event {
+            Ok(Some(Ok(ev))) => ev,
+            Ok(Some(Err(e))) => {
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(
+                        format!("[transport] {e}"),
+                        None,
+                        Some(request_id.clone()),
+                    )))
+                    .await;
+                return;
+            }
+            Ok(None) => {
+                let detail = "stream closed before message_stop";
+                ResponseAnomaly::Truncated.record(detail, otel_event_manager.as_ref());
+                let _ = tx_event
+                    .send(Err(
+                        ResponseAnomaly::Truncated.into_error(detail, &request_id)
+                    ))
+                    .await;
+                if let Ok(logger) = debug_logger.lock() {
+                    let _ = logger.end_request_log(&request_id);
+                }
+                return;
+            }
+            Err(_) => {
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(
+                        "[idle] timeout waiting for SSE".into(),
+                        None,
+                        Some(request_id.clone()),
+                    )))
+                    .await;
+                return;
+            }
+        };
+
+        let data: Value = match serde_json::from_str(&sse.data) {
+            Ok(data) => data,
+            Err(e) => {
+                debug!("anthropic SSE parse error: {e} | event: {}", sse.event);
+                continue;
+            }
+        };
+        trace!(
+            "anthropic_messages received SSE event {}: {data:?}",
+            sse.event
+        );
+        if let Ok(logger) = debug_logger.lock() {
+            let _ = logger.append_response_event(&request_id, "sse_event", &data);
+        }
+
+        let kind = data
+            .get("type")
+            .and_then(Value::as_str)
+            .unwrap_or(sse.event.as_str());
+        let events = match state.on_event(kind, &data) {
+            Ok(events) => events,
+            Err(err) => {
+                let err = match err {
+                    CodexErr::Stream(message, retry_after, None) => {
+                        CodexErr::Stream(message, retry_after, Some(request_id.clone()))
+                    }
+                    other => other,
+                };
+                let _ = tx_event.send(Err(err)).await;
+                if let Ok(logger) = debug_logger.lock() {
+                    let _ = logger.end_request_log(&request_id);
+                }
+                return;
+            }
+        };
+        for event in events {
+            if tx_event.send(Ok(event)).await.is_err() {
+                return;
+            }
+        }
+        if state.completed {
+            if let Ok(logger) = debug_logger.lock() {
+                let _ = logger.end_request_log(&request_id);
+            }
+            return;
+        }
+    }
+}
+
+enum ContentBlock {
+    Text(String),
+    ToolUse {
+        id: String,
+        name: String,
+        partial_json: String,
+    },
+}
+
+/// Tracks the content blocks of one streamed message.
+#[derive(Default)]
+struct MessagesStreamState {
+    response_id: String,
+    usage: TokenUsage,
+    blocks: BTreeMap<u64, ContentBlock>,
+    completed: bool,
+}
+
+impl MessagesStreamState {
+    fn on_event(&mut self, kind: &str, data: &Value) -> Result<Vec<ResponseEvent>> {
+        let index = data
+            .get("index")
+            .and_then(Value::as_u64)
+            .unwrap_or_default();
+        match kind {
+            "message_start" => {
+                let message = data.get("message").unwrap_or(&Value::Null);
+                if let Some(id) = message.get("id").and_then(Value::as_str) {
+                    self.response_id = id.to_string();
+                }
+                if let Some(usage) = message.get("usage") {
+                    self.record_usage(usage);
+                }
+                Ok(vec![ResponseEvent::Created])
+            }
+            "content_block_start" => {
+                let block = data.get("content_block").unwrap_or(&Value::Null);
+                let block = match block.get("type").and_then(Value::as_str) {
+                    Some("text") => ContentBlock::Text(
+                        block
+                            .get("text")
+                            .and_then(Value::as_str)
+                            .unwrap_or_default()
+                            .to_string(),
+                    ),
+                    Some("tool_use") => ContentBlock::ToolUse {
+                        id: string_field(block, "id"),
+                        name: string_field(block, "name"),
+                        partial_json: String::new(),
+                    },
+                    // Thinking and server tool blocks are not requested.
+                    _ => return Ok(Vec::new()),
+                };
+                self.blocks.insert(index, block);
+                Ok(Vec::new())
+            }
+            "content_block_delta" => {
+                let delta = data.get("delta").unwrap_or(&Value::Null);
+                match (
+                    self.blocks.get_mut(&index),
+                    delta.get("type").and_then(Value::as_str),
+                ) {
+                    (Some(ContentBlock::Text(text)), Some("text_delta")) => {
+                        let chunk = delta
+                            .get("text")
+                            .and_then(Value::as_str)
+                            .unwrap_or_default();
+                        if chunk.is_empty() {
+                            return Ok(Vec::new());
+                        }
+                        text.push_str(chunk);
+                        Ok(vec![ResponseEvent::OutputTextDelta {
+                            delta: chunk.to_string(),
+                            item_id: None,
+                            sequence_number: None,
+                            output_index: None,
+                        }])
+                    }
+                    (
+                        Some(ContentBlock::ToolUse { partial_json, .. }),
+                        Some("input_json_delta"),
+                    ) => {
+                        partial_json.push_str(
+                            delta
+                                .get("partial_json")
+                                .and_then(Value::as_str)
+                                .unwrap_or_default(),
+                        );
+                        Ok(Vec::new())
+                    }
+                    _ => Ok(Vec::new()),
+                }
+            }
+            "content_block_stop" => {
+                let item = match self.blocks.remove(&index) {
+                    Some(ContentBlock::Text(text)) if !text.is_empty() => ResponseItem::Message {
+                        id: None,
+                        role: "assistant".to_string(),
+                        content: vec![ContentItem::OutputText { text }],
+                    },
+                    Some(ContentBlock::ToolUse {
+                        id,
+                        name,
+                        partial_json,
+                    }) => ResponseItem::FunctionCall {
+                        id: None,
+                        name,
+                        arguments: if partial_json.trim().is_empty() {
+                            "{}".to_string()
+                        } else {
+                            partial_json
+                        },
+                        call_id: id,
+                    },
+                    _ => return Ok(Vec::new()),
+                };
+                Ok(vec![ResponseEvent::OutputItemDone {
+                    item,
+                    sequence_number: None,
+                    output_index: None,
+                }])
+            }
+            "message_delta" => {
+                if let Some(usage) = data.get("usage") {
+                    self.record_usage(usage);
+                }
+                Ok(Vec::new())
+            }
+            "message_stop" => {
+                self.completed = true;
+                Ok(vec![ResponseEvent::Completed {
+                    response_id: std::mem::take(&mut self.response_id),
+                    token_usage: Some(self.usage.clone()),
+                }])
+            }
+            "error" => {
+                let error = data.get("error").unwrap_or(&Value::Null);
+                let error_type = error.get("type").and_then(Value::as_str).unwrap_or("error");
+                let message = error
+                    .get("message")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default();
+                if TRANSIENT_ERROR_TYPES.contains(&error_type) {
+                    Err(CodexErr::Stream(
+                        format!("[anthropic] {error_type}: {message}"),
+                        None,
+                        None,
+                    ))
+                } else {
+                    Err(CodexErr::ServerError(format!(
+                        "Anthropic {error_type}: {message}"
+                    )))
+                }
+            }
+            // `ping` and event types added later.
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    /// `message_start` reports the input side and `message_delta` the
+    /// running output count; cache reads and writes are part of the input.
+    fn record_usage(&mut self, usage: &Value) {
+        let field = |name: &str| usage.get(name).and_then(Value::as_u64);
+        if let Some(input) = field("input_tokens") {
+            let cache_read = field("cache_read_input_tokens").unwrap_or_default();
+            let cache_write = field("cache_creation_input_tokens").unwrap_or_default();
+            self.usage.input_tokens = input + cache_read + cache_write;
+            self.usage.cached_input_tokens = cache_read;
+        }
+        if let Some(output) = field("output_tokens") {
+            self.usage.output_tokens = output;
+        }
+        self.usage.total_tokens = self.usage.input_tokens + self.usage.output_tokens;
+    }
+}
+
+fn string_field(value: &Value, name: &str) -> String {
+    value
+        .get(name)
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use code_protocol::models::FunctionCallOutputPayload;
+    use pretty_assertions::assert_eq;
+
+    fn message(role: &str, text: &str) -> ResponseItem {
+        ResponseItem::Message {
+            id: None,
+            role: role.to_string(),
+            content: vec![ContentItem::InputText {
+                text: text.to_string(),
+            }],
+        }
+    }
+
+    #[test]
+    fn translates_history_into_alternating_turns() {
+        let input = vec![
+            message("developer", "Stay in the repo."),
+            message("user", "Run the tests"),
+            ResponseItem::FunctionCall {
+                id: None,
+                name: "shell".to_string(),
+                arguments: r#"{"command":["cargo","test"]}"#.to_string(),
+                call_id: "toolu_1".to_string(),
+            },
+            ResponseItem::FunctionCallOutput {
+                call_id: "toolu_1".to_string(),
+                output: FunctionCallOutputPayload {
+                    content: "test result: ok".to_string(),
+                    success: Some(true),
+                },
+            },
+            ResponseItem::FunctionCallOutput {
+                call_id: "call_from_another_provider".to_string(),
+                output: FunctionCallOutputPayload {
+                    content: "dropped".to_string(),
+                    success: None,
+                },
+            },
+            ResponseItem::Message {
+                id: None,
+                role: "user".to_string(),
+                content: vec![ContentItem::InputImage {
+                    image_url: "data:image/png;base64,iVBORw0KGgo=".to_string(),
+                }],
+            },
+            message("assistant", "All tests pass."),
+        ];
+        let mut system = "Base instructions".to_string();
+        let messages = translate_input(&input, &mut system);
+
+        assert_eq!(system, "Base instructions\n\nStay in the repo.");
+        assert_eq!(
+            Value::Array(messages),
+            json!([
+                {"role": "user", "content": [{"type": "text", "text": "Run the tests"}]},
+                {"role": "assistant", "content": [{
+                    "type": "tool_use",
+                    "id": "toolu_1",
+                    "name": "shell",
+                    "input": {"command": ["cargo", "test"]},
+                }]},
+                {"role": "user", "content": [
+                    {
+                        "type": "tool_result",
+                        "tool_use_id": "toolu_1",
+                        "content": "test result: ok",
+                        "is_error": false,
+                    },
+                    {"type": "image", "source": {
+                        "type": "base64",
+                        "media_type": "image/png",
+                        "data": "iVBORw0KGgo=",
+                    }},
+                ]},
+                {"role": "assistant", "content": [{"type": "text", "text": "All tests pass."}]},
+            ])
+        );
+    }
+
+    #[test]
+    fn translates_chat_tools() {
+        let tools = translate_tools(vec![json!({
+            "type": "function",
+            "function": {
+                "name": "shell",
+                "description": "Runs a command",
+                "strict": false,
+                "parameters": {"type": "object", "properties": {"command": {"type": "array"}}},
+            },
+        })]);
+        assert_eq!(
+            tools,
+            vec![json!({
+                "name": "shell",
+                "description": "Runs a command",
+                "input_schema": {"type": "object", "properties": {"command": {"type": "array"}}},
+            })]
+        );
+    }
+
+    #[test]
+    fn maps_streamed_blocks_to_response_events() {
+        let mut state = MessagesStreamState::default();
+        let stream = [
+            json!({"type": "message_start", "message": {"id": "msg_1", "usage": {
+                "input_tokens": 100, "cache_read_input_tokens": 900, "output_tokens": 1,
+            }}}),
+            json!({"type": "content_block_start", "index": 0,
+                "content_block": {"type": "text", "text": ""}}),
+            json!({"type": "content_block_delta", "index": 0,
+                "delta": {"type": "text_delta", "text": "Running "}}),
+            json!({"type": "content_block_delta", "index": 0,
+                "delta": {"type": "text_delta", "text": "tests."}}),
+            json!({"type": "content_block_stop", "index": 0}),
+            json!({"type": "content_block_start", "index": 1, "content_block": {
+                "type": "tool_use", "id": "toolu_1", "name": "shell", "input": {},
+            }}),
+            json!({"type": "content_block_delta", "index": 1,
+                "delta": {"type": "input_json_delta", "partial_json": "{\"command\":"}}),
+            json!({"type": "content_block_delta", "index": 1,
+                "delta": {"type": "input_json_delta", "partial_json": "[\"ls\"]}"}}),
+            json!({"type": "content_block_stop", "index": 1}),
+            json!({"type": "ping"}),
+            json!({"type": "message_delta", "delta": {"stop_reason": "tool_use"},
+                "usage": {"output_tokens": 42}}),
+            json!({"type": "message_stop"}),
+        ];
+        let mut events = Vec::new();
+        for data in &stream {
+            let kind = data["type"].as_str().unwrap();
+            events.extend(state.on_event(kind, data).unwrap());
+        }
+
+        let mut deltas = String::new();
+        let mut items = Vec::new();
+        let mut usage = None;
+        for event in events {
+            match event {
+                ResponseEvent::OutputTextDelta { delta, .. } => deltas.push_str(&delta),
+                ResponseEvent::OutputItemDone { item, .. } => items.push(item),
+                ResponseEvent::Completed {
+                    response_id,
+                    token_usage,
+                } => {
+                    assert_eq!(response_id, "msg_1");
+                    usage = token_usage;
+                }
+                _ => {}
+            }
+        }
+        assert_eq!(deltas, "Running tests.");
+        assert_eq!(
+            items,
+            vec![
+                ResponseItem::Message {
+                    id: None,
+                    role: "assistant".to_string(),
+                    content: vec![ContentItem::OutputText {
+                        text: "Running tests.".to_string(),
+                    }],
+                },
+                ResponseItem::FunctionCall {
+                    id: None,
+                    name: "shell".to_string(),
+                    arguments: "{\"command\":[\"ls\"]}".to_string(),
+                    call_id: "toolu_1".to_string(),
+                },
+            ]
+        );
+        assert_eq!(
+            usage,
+            Some(TokenUsage {
+                input_tokens: 1000,
+                cached_input_tokens: 900,
+                output_tokens: 42,
+                reasoning_output_tokens: 0,
+                total_tokens: 1042,
+            })
+        );
+        assert!(state.completed);
+    }
+
+    #[test]
+    fn overloaded_errors_are_retryable() {
+        let mut state = MessagesStreamState::default();
+        let overloaded = json!({"type": "error",
+            "error": {"type": "overloaded_error", "message": "Overloaded"}});
+        assert!(matches!(
+            state.on_event("error", &overloaded),
+            Err(CodexErr::Stream(..))
+        ));
+        let invalid = json!({"type": "error",
+            "error": {"type": "invalid_request_error", "message": "bad"}});
+        assert!(matches!(
+            state.on_event("error", &invalid),
+            Err(CodexErr::ServerError(_))
+        ));
+    }
+
+    #[test]
+    fn vertex_payloads_name_the_version_instead_of_the_model() {
+        let mut payload = json!({"model": "claude-sonnet-4-5", "max_tokens": 8192, "stream": true});
+        use_vertex_payload(&mut payload);
+        assert_eq!(
+            payload,
+            json!({"anthropic_version": "vertex-2023-10-16", "max_tokens": 8192, "stream": true})
+        );
+    }
+}
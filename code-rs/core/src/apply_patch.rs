@@ -1,6 +1,8 @@
 use crate::acp::AcpFileSystem;
 use crate::codex::Session;
+use crate::file_locks;
 use crate::patch_harness::run_patch_harness;
+use crate::prefetch::ContextPrefetcher;
 use crate::protocol::FileChange;
 use crate::protocol::ReviewDecision;
 use crate::safety::SafetyCheck;
@@ -11,6 +13,7 @@ use code_apply_patch::AffectedPaths;
 use code_apply_patch::ApplyPatchAction;
 use code_apply_patch::ApplyPatchFileChange;
 use code_apply_patch::FileSystem;
+use code_apply_patch::MaybeApplyPatchVerified;
 use code_apply_patch::StdFileSystem;
 use code_apply_patch::print_summary;
 use code_protocol::models::FunctionCallOutputPayload;
@@ -167,6 +170,83 @@ pub(crate) async fn apply_patch(
             });
         }
     };
+
+    // Serialize with other writers in this workspace before touching disk.
+    let locks = match file_locks::lock_paths(
+        &paths_to_lock(&action),
+        &file_locks::owner_label(&sess.session_uuid()),
+    )
+    .await
+    {
+        Ok(locks) => locks,
+        Err(err) => {
+            return ApplyPatchResult::Reply(ResponseInputItem::FunctionCallOutput {
+                call_id: call_id.to_owned(),
+                output: FunctionCallOutputPayload {
+                    content: format!(
+                        "patch not applied: {err}. Another agent is writing the same file; retry after it finishes."
+                    ),
+                    success: Some(false),
+                },
+            });
+        }
+    };
+    let mut notes: Vec<String> = locks
+        .contention()
+        .iter()
+        .map(|contention| {
+            format!(
+                "waited {:.1}s for {} held by {}",
+                contention.waited.as_secs_f64(),
+                contention.path.display(),
+                contention.holder
+            )
+        })
+        .collect();
+    // The patch was verified against the files as they were before approval
+    // and locking; re-verify so a concurrent write is not silently
+    // overwritten.
+    let action = if sess.client_tools().is_some() {
+        action
+    } else {
+        match reverify_patch(&action) {
+            Reverified::Unchanged => action,
+            Reverified::Rebased { action, changed } => {
+                for path in changed {
+                    let writer = locks
+                        .previous_owner(&path)
+                        .unwrap_or("a write outside apply_patch");
+                    notes.push(format!(
+                        "{} changed on disk since the patch was prepared (last written by {writer}); the patch was re-applied to the latest contents",
+                        path.display()
+                    ));
+                }
+                action
+            }
+            Reverified::Conflict { error } => {
+                let writers: Vec<String> = paths_to_lock(&action)
+                    .iter()
+                    .filter_map(|path| locks.previous_owner(path))
+                    .map(str::to_string)
+                    .collect();
+                let by = if writers.is_empty() {
+                    String::new()
+                } else {
+                    format!(" by {}", writers.join(", "))
+                };
+                return ApplyPatchResult::Reply(ResponseInputItem::FunctionCallOutput {
+                    call_id: call_id.to_owned(),
+                    output: FunctionCallOutputPayload {
+                        content: format!(
+                            "patch not applied: the files were changed concurrently{by} and the patch no longer applies ({error}). Re-read the files and retry."
+                        ),
+                        success: Some(false),
+                    },
+                });
+            }
+        }
+    };
+
     let mut stdout = Vec::new();
     let mut stderr = Vec::new();
     let result = if let Some(client_tools) = sess.client_tools() {
@@ -181,9 +261,14 @@ pub(crate) async fn apply_patch(
             .await
     };
 
-    let stdout = String::from_utf8_lossy(&stdout).to_string();
+    drop(locks);
+
+    let mut stdout = String::from_utf8_lossy(&stdout).to_string();
     let stderr = String::from_utf8_lossy(&stderr).to_string();
     let success = result.is_ok();
+    for note in notes {
+        stdout.push_str(&format!("Note: {note}\n"));
+    }
 
     ApplyPatchResult::Applied(ApplyPatchRun {
         auto_approved,
@@ -194,8 +279,66 @@ pub(crate) async fn apply_patch(
     })
 }
 
+/// Every path a patch writes, including rename targets.
+fn paths_to_lock(action: &ApplyPatchAction) -> Vec<PathBuf> {
+    action
+        .changes()
+        .iter()
+        .flat_map(|(path, change)| match change {
+            ApplyPatchFileChange::Update {
+                move_path: Some(dest),
+                ..
+            } => vec![path.clone(), dest.clone()],
+            _ => vec![path.clone()],
+        })
+        .collect()
+}
+
+enum Reverified {
+    Unchanged,
+    /// The files changed but the patch still applies; `action` holds the
+    /// changes computed from the current contents.
+    Rebased {
+        action: ApplyPatchAction,
+        changed: Vec<PathBuf>,
+    },
+    Conflict {
+        error: String,
+    },
+}
+
+/// Re-parses the patch against the files as they are now.
+fn reverify_patch(action: &ApplyPatchAction) -> Reverified {
+    let argv = ["apply_patch".to_string(), action.patch.clone()];
+    match code_apply_patch::maybe_parse_apply_patch_verified(&argv, &action.cwd) {
+        MaybeApplyPatchVerified::Body(fresh) => {
+            let mut changed: Vec<PathBuf> = fresh
+                .changes()
+                .iter()
+                .filter(|(path, change)| action.changes().get(*path) != Some(*change))
+                .map(|(path, _)| path.clone())
+                .collect();
+            if changed.is_empty() {
+                return Reverified::Unchanged;
+            }
+            changed.sort();
+            Reverified::Rebased {
+                action: fresh,
+                changed,
+            }
+        }
+        MaybeApplyPatchVerified::CorrectnessError(err) => Reverified::Conflict {
+            error: err.to_string(),
+        },
+        MaybeApplyPatchVerified::ShellParseError(_) | MaybeApplyPatchVerified::NotApplyPatch => {
+            Reverified::Unchanged
+        }
+    }
+}
+
 pub(crate) fn convert_apply_patch_to_protocol(
     action: &ApplyPatchAction,
+    prefetcher: &ContextPrefetcher,
 ) -> HashMap<PathBuf, FileChange> {
     let changes = action.changes();
     let mut result = HashMap::with_capacity(changes.len());
@@ -210,7 +353,7 @@ pub(crate) fn convert_apply_patch_to_protocol(
                 move_path,
                 new_content,
             } => {
-                let original_content = std::fs::read_to_string(path).unwrap_or_default();
+                let original_content = prefetcher.read_to_string(path).unwrap_or_default();
                 FileChange::Update {
                     unified_diff: unified_diff.clone(),
                     move_path: move_path.clone(),
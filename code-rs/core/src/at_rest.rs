@@ -0,0 +1,320 @@
+//! At-rest encryption for artifacts that persist provider traffic.
+//!
+//! When `encrypt_at_rest = true` is set in `config.toml`, every writer that
+//! stores model requests or responses on disk (session rollouts and their
+//! history snapshots, debug request/response logs, SSE captures, the response
+//! cache, usage and latency logs, Auto Drive event and audit logs) seals its
+//! payload with a per-install key before writing. If the key cannot be loaded
+//! those writers fail rather than fall back to plaintext. The key lives at
+//! `$CODE_HOME/at_rest.key` (created on first use with `0600` permissions) and
+//! can be supplied out of band through the `CODE_AT_REST_KEY` environment
+//! variable as base64.
+//!
+//! Whole files are written as `MAGIC || nonce || ciphertext+tag`. Append-only
+//! JSONL files seal each line individually and prefix it with `LINE_PREFIX`
+//! so readers can mix legacy plaintext lines with encrypted ones. Rollouts
+//! and session snapshots are opened whatever `encrypt_at_rest` is set to now
+//! ([`open_line_in`], [`open_in`]), so turning the setting off does not
+//! strand sessions recorded while it was on.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::OnceLock;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use ring::aead::Aad;
+use ring::aead::CHACHA20_POLY1305;
+use ring::aead::LessSafeKey;
+use ring::aead::NONCE_LEN;
+use ring::aead::Nonce;
+use ring::aead::UnboundKey;
+use ring::rand::SecureRandom;
+use ring::rand::SystemRandom;
+
+/// Header written in front of every sealed file.
+pub const MAGIC: &[u8] = b"CODEENC1";
+/// Prefix written in front of every sealed JSONL line.
+pub const LINE_PREFIX: &str = "enc1:";
+/// Environment variable that overrides the on-disk key (base64, 32 bytes).
+pub const KEY_ENV_VAR: &str = "CODE_AT_REST_KEY";
+
+const KEY_FILE_NAME: &str = "at_rest.key";
+const KEY_LEN: usize = 32;
+
+pub struct AtRestCipher {
+    key: LessSafeKey,
+    rng: SystemRandom,
+}
+
+impl std::fmt::Debug for AtRestCipher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AtRestCipher").finish_non_exhaustive()
+    }
+}
+
+impl AtRestCipher {
+    /// Loads the install key from `CODE_AT_REST_KEY` or `code_home`, creating
+    /// a fresh key file when neither exists.
+    pub fn load_or_create(code_home: &Path) -> io::Result<Arc<Self>> {
+        if let Ok(encoded) = std::env::var(KEY_ENV_VAR) {
+            let bytes = STANDARD
+                .decode(encoded.trim())
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+            return Self::from_key_bytes(&bytes).map(Arc::new);
+        }
+
+        let path = key_path(code_home);
+        let bytes = match fs::read_to_string(&path) {
+            Ok(contents) => STANDARD
+                .decode(contents.trim())
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => create_key_file(&path)?,
+            Err(err) => return Err(err),
+        };
+        Self::from_key_bytes(&bytes).map(Arc::new)
+    }
+
+    pub fn from_key_bytes(bytes: &[u8]) -> io::Result<Self> {
+        if bytes.len() != KEY_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("at-rest key must be {KEY_LEN} bytes, got {}", bytes.len()),
+            ));
+        }
+        let unbound = UnboundKey::new(&CHACHA20_POLY1305, bytes)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "invalid at-rest key"))?;
+        Ok(Self {
+            key: LessSafeKey::new(unbound),
+            rng: SystemRandom::new(),
+        })
+    }
+
+    /// Encrypts `plaintext` into the sealed file format.
+    pub fn seal(&self, plaintext: &[u8]) -> io::Result<Vec<u8>> {
+        let mut nonce = [0u8; NONCE_LEN];
+        self.rng
+            .fill(&mut nonce)
+            .map_err(|_| io::Error::other("failed to generate nonce"))?;
+        let mut in_out = plaintext.to_vec();
+        self.key
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::empty(),
+                &mut in_out,
+            )
+            .map_err(|_| io::Error::other("failed to encrypt payload"))?;
+
+        let mut sealed = Vec::with_capacity(MAGIC.len() + NONCE_LEN + in_out.len());
+        sealed.extend_from_slice(MAGIC);
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&in_out);
+        Ok(sealed)
+    }
+
+    /// Decrypts a sealed payload. Data without the `MAGIC` header is returned
+    /// unchanged so legacy plaintext artifacts stay readable.
+    pub fn open(&self, data: &[u8]) -> io::Result<Vec<u8>> {
+        let Some(rest) = data.strip_prefix(MAGIC) else {
+            return Ok(data.to_vec());
+        };
+        if rest.len() < NONCE_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "sealed payload is truncated",
+            ));
+        }
+        let (nonce_bytes, ciphertext) = rest.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce_bytes)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "invalid nonce"))?;
+        let mut in_out = ciphertext.to_vec();
+        let plaintext = self
+            .key
+            .open_in_place(nonce, Aad::empty(), &mut in_out)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "failed to decrypt payload"))?;
+        Ok(plaintext.to_vec())
+    }
+
+    /// Seals a single line for append-only logs.
+    pub fn seal_line(&self, line: &str) -> io::Result<String> {
+        let sealed = self.seal(line.as_bytes())?;
+        Ok(format!("{LINE_PREFIX}{}", STANDARD.encode(sealed)))
+    }
+
+    /// Opens a line produced by [`Self::seal_line`]; plaintext lines pass through.
+    pub fn open_line(&self, line: &str) -> io::Result<String> {
+        let Some(encoded) = line.strip_prefix(LINE_PREFIX) else {
+            return Ok(line.to_string());
+        };
+        let sealed = STANDARD
+            .decode(encoded.trim())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        let plaintext = self.open(&sealed)?;
+        String::from_utf8(plaintext).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+}
+
+/// Returns the install cipher when `enabled`. Fails when encryption is
+/// enabled but the key cannot be loaded, so callers never silently write
+/// plaintext.
+pub fn cipher_for(enabled: bool, code_home: &Path) -> io::Result<Option<Arc<AtRestCipher>>> {
+    if !enabled {
+        return Ok(None);
+    }
+    reader_cipher(code_home).map(Some).map_err(|err| {
+        io::Error::new(
+            err.kind(),
+            format!("encrypt_at_rest is set but the at-rest key could not be loaded: {err}"),
+        )
+    })
+}
+
+/// Opens a JSONL `line` stored under `code_home` if it was sealed with
+/// [`AtRestCipher::seal_line`]. Plaintext lines are returned as is without
+/// loading the key.
+pub fn open_line_in<'a>(code_home: &Path, line: &'a str) -> io::Result<Cow<'a, str>> {
+    if !line.starts_with(LINE_PREFIX) {
+        return Ok(Cow::Borrowed(line));
+    }
+    reader_cipher(code_home)?.open_line(line).map(Cow::Owned)
+}
+
+/// Opens file contents stored under `code_home` if they were sealed with
+/// [`AtRestCipher::seal`]. Plaintext is returned as is without loading the key.
+pub fn open_in(code_home: &Path, data: Vec<u8>) -> io::Result<Vec<u8>> {
+    if !data.starts_with(MAGIC) {
+        return Ok(data);
+    }
+    reader_cipher(code_home)?.open(&data)
+}
+
+/// The install cipher of `code_home`, loaded once per process.
+fn reader_cipher(code_home: &Path) -> io::Result<Arc<AtRestCipher>> {
+    static CIPHERS: OnceLock<Mutex<HashMap<PathBuf, Arc<AtRestCipher>>>> = OnceLock::new();
+    let ciphers = CIPHERS.get_or_init(|| Mutex::new(HashMap::new()));
+    let mut ciphers = ciphers
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    if let Some(cipher) = ciphers.get(code_home) {
+        return Ok(cipher.clone());
+    }
+    let cipher = AtRestCipher::load_or_create(code_home)?;
+    ciphers.insert(code_home.to_path_buf(), cipher.clone());
+    Ok(cipher)
+}
+
+/// Writes `contents` to `path`, sealing it first when a cipher is present.
+pub fn write_maybe_sealed(
+    cipher: Option<&AtRestCipher>,
+    path: &Path,
+    contents: &[u8],
+) -> io::Result<()> {
+    match cipher {
+        Some(cipher) => fs::write(path, cipher.seal(contents)?),
+        None => fs::write(path, contents),
+    }
+}
+
+/// Reads `path`, transparently opening sealed contents when a cipher is present.
+pub fn read_maybe_sealed(cipher: Option<&AtRestCipher>, path: &Path) -> io::Result<Vec<u8>> {
+    let data = fs::read(path)?;
+    match cipher {
+        Some(cipher) => cipher.open(&data),
+        None => Ok(data),
+    }
+}
+
+fn key_path(code_home: &Path) -> PathBuf {
+    code_home.join(KEY_FILE_NAME)
+}
+
+fn create_key_file(path: &Path) -> io::Result<Vec<u8>> {
+    let mut key = vec![0u8; KEY_LEN];
+    SystemRandom::new()
+        .fill(&mut key)
+        .map_err(|_| io::Error::other("failed to generate at-rest key"))?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    io::Write::write_all(&mut file, STANDARD.encode(&key).as_bytes())?;
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    #[test]
+    fn seal_round_trips_and_hides_plaintext() {
+        let cipher = AtRestCipher::from_key_bytes(&[7u8; KEY_LEN]).unwrap();
+        let sealed = cipher.seal(b"{\"secret\":true}").unwrap();
+        assert!(sealed.starts_with(MAGIC));
+        assert!(!sealed.windows(6).any(|window| window == b"secret"));
+        assert_eq!(cipher.open(&sealed).unwrap(), b"{\"secret\":true}".to_vec());
+    }
+
+    #[test]
+    fn open_passes_through_plaintext_and_rejects_tampering() {
+        let cipher = AtRestCipher::from_key_bytes(&[1u8; KEY_LEN]).unwrap();
+        assert_eq!(cipher.open(b"plain").unwrap(), b"plain".to_vec());
+
+        let mut sealed = cipher.seal(b"payload").unwrap();
+        let last = sealed.len() - 1;
+        sealed[last] ^= 0xff;
+        assert!(cipher.open(&sealed).is_err());
+    }
+
+    #[test]
+    fn line_round_trip() {
+        let cipher = AtRestCipher::from_key_bytes(&[3u8; KEY_LEN]).unwrap();
+        let line = cipher.seal_line("{\"a\":1}").unwrap();
+        assert!(line.starts_with(LINE_PREFIX));
+        assert_eq!(cipher.open_line(&line).unwrap(), "{\"a\":1}");
+        assert_eq!(cipher.open_line("{\"b\":2}").unwrap(), "{\"b\":2}");
+    }
+
+    #[test]
+    fn readers_open_sealed_lines_and_pass_plaintext_through() {
+        let home = TempDir::new().unwrap();
+        assert!(cipher_for(false, home.path()).unwrap().is_none());
+        let cipher = cipher_for(true, home.path()).unwrap().unwrap();
+        let line = cipher.seal_line("{\"a\":1}").unwrap();
+        assert_eq!(open_line_in(home.path(), &line).unwrap(), "{\"a\":1}");
+        assert_eq!(open_line_in(home.path(), "{\"b\":2}").unwrap(), "{\"b\":2}");
+        let sealed = cipher.seal(b"snapshot").unwrap();
+        assert_eq!(open_in(home.path(), sealed).unwrap(), b"snapshot".to_vec());
+    }
+
+    #[test]
+    fn cipher_for_fails_when_the_key_is_unreadable() {
+        let home = TempDir::new().unwrap();
+        fs::write(home.path().join(KEY_FILE_NAME), "not base64!").unwrap();
+        assert!(cipher_for(true, home.path()).is_err());
+    }
+
+    #[test]
+    fn key_file_is_created_once_and_reused() {
+        let home = TempDir::new().unwrap();
+        let first = AtRestCipher::load_or_create(home.path()).unwrap();
+        let sealed = first.seal(b"persisted").unwrap();
+        let second = AtRestCipher::load_or_create(home.path()).unwrap();
+        assert_eq!(second.open(&sealed).unwrap(), b"persisted".to_vec());
+    }
+}
@@ -0,0 +1,334 @@
+//! AWS credentials and Signature Version 4 request signing, for providers
+//! with `wire_api = "bedrock_converse"`.
+//!
+//! Credentials are resolved with the AWS SDK's default provider chain:
+//! environment variables, the `AWS_PROFILE` (or `default`) profile in the
+//! shared `~/.aws/credentials` and `~/.aws/config` files (including SSO,
+//! `credential_process` and assumed roles), web identity tokens, and
+//! ECS or EC2 instance metadata.
+
+use std::sync::Mutex;
+use std::sync::PoisonError;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use aws_config::default_provider::credentials::DefaultCredentialsChain;
+use aws_credential_types::Credentials;
+use aws_credential_types::provider::ProvideCredentials;
+use chrono::DateTime;
+use chrono::Utc;
+use ring::digest;
+use ring::hmac;
+use tokio::sync::OnceCell;
+
+use crate::error::CodexErr;
+use crate::error::EnvVarError;
+use crate::error::Result;
+
+const ALGORITHM: &str = "AWS4-HMAC-SHA256";
+
+/// Temporary credentials are refreshed this long before they expire.
+const REFRESH_BEFORE_EXPIRY: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct AwsCredentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub session_token: Option<String>,
+}
+
+impl AwsCredentials {
+    /// Resolves credentials with the default provider chain. Temporary
+    /// credentials are reused until shortly before they expire; long-lived
+    /// keys are resolved again on every request so rotated keys are picked
+    /// up.
+    pub async fn load() -> Result<Self> {
+        static CHAIN: OnceCell<DefaultCredentialsChain> = OnceCell::const_new();
+        static CACHED: Mutex<Option<Credentials>> = Mutex::new(None);
+
+        if let Some(credentials) = CACHED
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .as_ref()
+            .filter(|credentials| still_fresh(credentials.expiry(), SystemTime::now()))
+        {
+            return Ok(Self::from(credentials));
+        }
+        let chain = CHAIN
+            .get_or_init(|| DefaultCredentialsChain::builder().build())
+            .await;
+        let credentials = chain.provide_credentials().await.map_err(|err| {
+            CodexErr::EnvVar(EnvVarError {
+                var: "AWS_ACCESS_KEY_ID".to_string(),
+                instructions: Some(format!(
+                    "No AWS credentials could be resolved ({err}). Export AWS_ACCESS_KEY_ID and \
+                     AWS_SECRET_ACCESS_KEY, or configure a profile, SSO, web identity or an \
+                     instance role."
+                )),
+            })
+        })?;
+        let loaded = Self::from(&credentials);
+        if credentials.expiry().is_some() {
+            *CACHED.lock().unwrap_or_else(PoisonError::into_inner) = Some(credentials);
+        }
+        Ok(loaded)
+    }
+}
+
+impl From<&Credentials> for AwsCredentials {
+    fn from(credentials: &Credentials) -> Self {
+        Self {
+            access_key_id: credentials.access_key_id().to_string(),
+            secret_access_key: credentials.secret_access_key().to_string(),
+            session_token: credentials.session_token().map(str::to_string),
+        }
+    }
+}
+
+/// Whether credentials expiring at `expiry` can still be used at `now`.
+/// Credentials without an expiry are never cached.
+fn still_fresh(expiry: Option<SystemTime>, now: SystemTime) -> bool {
+    expiry
+        .and_then(|expiry| expiry.duration_since(now).ok())
+        .is_some_and(|left| left > REFRESH_BEFORE_EXPIRY)
+}
+
+/// A request to sign. `headers` are signed in addition to `host`,
+/// `x-amz-date`, and `x-amz-security-token`.
+pub(crate) struct SigningRequest<'a> {
+    pub method: &'a str,
+    pub url: &'a reqwest::Url,
+    pub headers: &'a [(&'a str, &'a str)],
+    pub payload: &'a [u8],
+}
+
+/// Returns the headers to add to `request`: `x-amz-date`, the session token
+/// for temporary credentials, and `authorization`.
+pub(crate) fn sign(
+    request: &SigningRequest<'_>,
+    credentials: &AwsCredentials,
+    region: &str,
+    service: &str,
+    now: DateTime<Utc>,
+) -> Vec<(String, String)> {
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = now.format("%Y%m%d").to_string();
+
+    let host = match (request.url.host_str(), request.url.port()) {
+        (Some(host), Some(port)) => format!("{host}:{port}"),
+        (Some(host), None) => host.to_string(),
+        (None, _) => String::new(),
+    };
+    let mut added = vec![("x-amz-date".to_string(), amz_date.clone())];
+    if let Some(token) = &credentials.session_token {
+        added.push(("x-amz-security-token".to_string(), token.clone()));
+    }
+
+    let mut signed_headers: Vec<(String, String)> = request
+        .headers
+        .iter()
+        .map(|(name, value)| (name.to_ascii_lowercase(), value.trim().to_string()))
+        .chain([("host".to_string(), host)])
+        .chain(added.iter().cloned())
+        .collect();
+    signed_headers.sort();
+    let canonical_headers: String = signed_headers
+        .iter()
+        .map(|(name, value)| format!("{name}:{value}\n"))
+        .collect();
+    let signed_header_names = signed_headers
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>()
+        .join(";");
+
+    let canonical_request = [
+        request.method.to_string(),
+        canonical_uri(request.url.path()),
+        canonical_query(request.url),
+        canonical_headers,
+        signed_header_names.clone(),
+        hex(digest::digest(&digest::SHA256, request.payload).as_ref()),
+    ]
+    .join("\n");
+
+    let scope = format!("{date}/{region}/{service}/aws4_request");
+    let string_to_sign = format!(
+        "{ALGORITHM}\n{amz_date}\n{scope}\n{}",
+        hex(digest::digest(&digest::SHA256, canonical_request.as_bytes()).as_ref())
+    );
+
+    let key = [date.as_str(), region, service, "aws4_request"]
+        .into_iter()
+        .fold(
+            format!("AWS4{}", credentials.secret_access_key).into_bytes(),
+            |key, part| hmac_sha256(&key, part.as_bytes()),
+        );
+    let signature = hex(&hmac_sha256(&key, string_to_sign.as_bytes()));
+
+    added.push((
+        "authorization".to_string(),
+        format!(
+            "{ALGORITHM} Credential={}/{scope}, SignedHeaders={signed_header_names}, Signature={signature}",
+            credentials.access_key_id
+        ),
+    ));
+    added
+}
+
+/// Percent-encodes everything but the RFC 3986 unreserved characters.
+pub(crate) fn uri_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b'~') {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    encoded
+}
+
+/// Services other than S3 sign the already-encoded path encoded once more.
+fn canonical_uri(path: &str) -> String {
+    if path.is_empty() {
+        return "/".to_string();
+    }
+    path.split('/')
+        .map(uri_encode)
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn canonical_query(url: &reqwest::Url) -> String {
+    let mut pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(key, value)| (uri_encode(&key), uri_encode(&value)))
+        .collect();
+    pairs.sort();
+    pairs
+        .iter()
+        .map(|(key, value)| format!("{key}={value}"))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    hmac::sign(&hmac::Key::new(hmac::HMAC_SHA256, key), data)
+        .as_ref()
+        .to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use pretty_assertions::assert_eq;
+
+    fn example_credentials(session_token: Option<&str>) -> AwsCredentials {
+        AwsCredentials {
+            access_key_id: "AKIDEXAMPLE".to_string(),
+            secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_string(),
+            session_token: session_token.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn signs_the_documented_example_request() {
+        let url =
+            reqwest::Url::parse("https://iam.amazonaws.com/?Version=2010-05-08&Action=ListUsers")
+                .unwrap();
+        let headers = sign(
+            &SigningRequest {
+                method: "GET",
+                url: &url,
+                headers: &[(
+                    "Content-Type",
+                    "application/x-www-form-urlencoded; charset=utf-8",
+                )],
+                payload: b"",
+            },
+            &example_credentials(None),
+            "us-east-1",
+            "iam",
+            Utc.with_ymd_and_hms(2015, 8, 30, 12, 36, 0).unwrap(),
+        );
+        assert_eq!(
+            headers,
+            vec![
+                ("x-amz-date".to_string(), "20150830T123600Z".to_string()),
+                (
+                    "authorization".to_string(),
+                    "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/iam/aws4_request, \
+                     SignedHeaders=content-type;host;x-amz-date, \
+                     Signature=5d672d79c15b13162d9279b0855cfba6789a8edb4c82c400e06b5924a6f2b5d7"
+                        .to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn temporary_credentials_sign_the_session_token() {
+        let url = reqwest::Url::parse(
+            "https://bedrock-runtime.us-west-2.amazonaws.com/model/anthropic.claude-v2%3A1/converse-stream",
+        )
+        .unwrap();
+        let headers = sign(
+            &SigningRequest {
+                method: "POST",
+                url: &url,
+                headers: &[],
+                payload: b"{}",
+            },
+            &example_credentials(Some("session")),
+            "us-west-2",
+            "bedrock",
+            Utc.with_ymd_and_hms(2025, 1, 2, 3, 4, 5).unwrap(),
+        );
+        assert_eq!(
+            headers[1],
+            ("x-amz-security-token".to_string(), "session".to_string())
+        );
+        assert!(headers[2].1.contains(
+            "/20250102/us-west-2/bedrock/aws4_request, \
+             SignedHeaders=host;x-amz-date;x-amz-security-token, "
+        ));
+        assert_eq!(
+            canonical_uri(url.path()),
+            "/model/anthropic.claude-v2%253A1/converse-stream"
+        );
+    }
+
+    #[test]
+    fn only_credentials_with_time_left_are_reused() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        assert!(still_fresh(Some(now + Duration::from_secs(3600)), now));
+        assert!(!still_fresh(Some(now + Duration::from_secs(60)), now));
+        assert!(!still_fresh(Some(now - Duration::from_secs(60)), now));
+        assert!(!still_fresh(None, now));
+    }
+
+    #[test]
+    fn converts_sdk_credentials() {
+        let credentials = Credentials::new(
+            "AKIDWORK",
+            "work-secret",
+            Some("work-token".to_string()),
+            None,
+            "test",
+        );
+        assert_eq!(
+            AwsCredentials::from(&credentials),
+            AwsCredentials {
+                access_key_id: "AKIDWORK".to_string(),
+                secret_access_key: "work-secret".to_string(),
+                session_token: Some("work-token".to_string()),
+            }
+        );
+    }
+}
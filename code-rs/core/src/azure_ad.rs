@@ -0,0 +1,250 @@
+//! Microsoft Entra ID (Azure AD) bearer tokens, for Azure OpenAI providers
+//! configured with an `azure_ad` table instead of a static API key.
+//!
+//! With `client_secret_env` set, tokens come from the client-credentials
+//! flow against `https://login.microsoftonline.com/<tenant_id>`. Otherwise
+//! the host's managed identity is used: the App Service / Container Apps
+//! endpoint when `IDENTITY_ENDPOINT` and `IDENTITY_HEADER` are set, else the
+//! Azure Instance Metadata Service. Tokens are cached per configuration and
+//! refreshed shortly before they expire.
+
+use std::collections::HashMap;
+use std::io;
+use std::sync::LazyLock;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+
+use serde::Deserialize;
+use serde::Deserializer;
+
+use crate::error::CodexErr;
+use crate::error::EnvVarError;
+use crate::error::Result;
+use crate::model_provider_info::AzureAdConfig;
+
+/// Scope requested when the config does not name one.
+pub(crate) const DEFAULT_SCOPE: &str = "https://cognitiveservices.azure.com/.default";
+const DEFAULT_AUTHORITY_HOST: &str = "https://login.microsoftonline.com";
+const IMDS_TOKEN_URL: &str = "http://169.254.169.254/metadata/identity/oauth2/token";
+const IMDS_API_VERSION: &str = "2018-02-01";
+const APP_SERVICE_API_VERSION: &str = "2019-08-01";
+/// Tokens are refreshed once they are this close to expiring.
+const REFRESH_MARGIN: Duration = Duration::from_secs(5 * 60);
+/// Longest `expires_in` trusted from a token response.
+const MAX_TOKEN_LIFETIME_SECS: u64 = 24 * 60 * 60;
+const TOKEN_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone)]
+struct CachedToken {
+    access_token: String,
+    expires_at: Instant,
+}
+
+static TOKENS: LazyLock<Mutex<HashMap<AzureAdConfig, CachedToken>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+static CLIENT: LazyLock<reqwest::Client> = LazyLock::new(|| {
+    crate::default_client::create_client(crate::default_client::DEFAULT_ORIGINATOR)
+});
+
+/// A bearer token for `config`, from the cache while it is still fresh.
+pub(crate) async fn bearer_token(config: &AzureAdConfig) -> Result<String> {
+    if let Some(token) = cached(config) {
+        return Ok(token);
+    }
+    let response = match &config.client_secret_env {
+        Some(secret_env) => client_credentials(config, secret_env).await?,
+        None => managed_identity(config).await?,
+    };
+    let token = response.access_token.clone();
+    if let Ok(mut tokens) = TOKENS.lock() {
+        tokens.insert(
+            config.clone(),
+            CachedToken {
+                access_token: response.access_token,
+                expires_at: Instant::now()
+                    + Duration::from_secs(response.expires_in.min(MAX_TOKEN_LIFETIME_SECS)),
+            },
+        );
+    }
+    Ok(token)
+}
+
+fn cached(config: &AzureAdConfig) -> Option<String> {
+    let tokens = TOKENS.lock().ok()?;
+    let token = tokens.get(config)?;
+    (token.expires_at > Instant::now() + REFRESH_MARGIN).then(|| token.access_token.clone())
+}
+
+async fn client_credentials(config: &AzureAdConfig, secret_env: &str) -> Result<TokenResponse> {
+    let (Some(tenant_id), Some(client_id)) = (&config.tenant_id, &config.client_id) else {
+        return Err(token_error(
+            "client_secret_env requires both tenant_id and client_id".to_string(),
+        ));
+    };
+    let secret = std::env::var(secret_env)
+        .ok()
+        .filter(|secret| !secret.trim().is_empty())
+        .ok_or_else(|| {
+            CodexErr::EnvVar(EnvVarError {
+                var: secret_env.to_string(),
+                instructions: Some(format!(
+                    "Set {secret_env} to a client secret of the Entra app registration {client_id}."
+                )),
+            })
+        })?;
+    let authority = config
+        .authority_host
+        .as_deref()
+        .unwrap_or(DEFAULT_AUTHORITY_HOST)
+        .trim_end_matches('/');
+    let url = format!("{authority}/{tenant_id}/oauth2/v2.0/token");
+    let request = CLIENT.post(&url).form(&[
+        ("grant_type", "client_credentials"),
+        ("client_id", client_id.as_str()),
+        ("client_secret", secret.as_str()),
+        ("scope", config.scope()),
+    ]);
+    send(request, &url).await
+}
+
+async fn managed_identity(config: &AzureAdConfig) -> Result<TokenResponse> {
+    let resource = config.resource();
+    let mut query = vec![("resource", resource)];
+    if let Some(client_id) = &config.client_id {
+        query.push(("client_id", client_id.as_str()));
+    }
+    let (url, request) = match (
+        std::env::var("IDENTITY_ENDPOINT"),
+        std::env::var("IDENTITY_HEADER"),
+    ) {
+        (Ok(endpoint), Ok(header)) => {
+            query.push(("api-version", APP_SERVICE_API_VERSION));
+            let request = CLIENT
+                .get(&endpoint)
+                .header("X-IDENTITY-HEADER", header)
+                .query(&query);
+            (endpoint, request)
+        }
+        _ => {
+            query.push(("api-version", IMDS_API_VERSION));
+            let request = CLIENT
+                .get(IMDS_TOKEN_URL)
+                .header("Metadata", "true")
+                .query(&query);
+            (IMDS_TOKEN_URL.to_string(), request)
+        }
+    };
+    send(request, &url).await
+}
+
+async fn send(request: reqwest::RequestBuilder, url: &str) -> Result<TokenResponse> {
+    let response = request
+        .timeout(TOKEN_REQUEST_TIMEOUT)
+        .send()
+        .await
+        .map_err(|err| token_error(format!("request to {url} failed: {err}")))?;
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    if !status.is_success() {
+        return Err(token_error(format!(
+            "{url} returned {status}: {}",
+            error_description(&body)
+        )));
+    }
+    serde_json::from_str(&body)
+        .map_err(|err| token_error(format!("unreadable token response from {url}: {err}")))
+}
+
+fn token_error(detail: String) -> CodexErr {
+    CodexErr::Io(io::Error::other(format!(
+        "failed to get an Azure AD token: {detail}"
+    )))
+}
+
+/// The `error_description` of an OAuth error body, or the body itself.
+fn error_description(body: &str) -> String {
+    serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|value| {
+            value
+                .get("error_description")
+                .and_then(serde_json::Value::as_str)
+                .map(str::to_string)
+        })
+        .unwrap_or_else(|| body.trim().to_string())
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    /// Seconds until the token expires. Managed identity endpoints send it as
+    /// a string.
+    #[serde(deserialize_with = "seconds_from_number_or_string")]
+    expires_in: u64,
+}
+
+fn seconds_from_number_or_string<'de, D>(deserializer: D) -> std::result::Result<u64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Seconds {
+        Number(u64),
+        Text(String),
+    }
+    match Seconds::deserialize(deserializer)? {
+        Seconds::Number(seconds) => Ok(seconds),
+        Seconds::Text(text) => text.parse().map_err(serde::de::Error::custom),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parses_expiry_as_number_or_string() {
+        let entra: TokenResponse =
+            serde_json::from_str(r#"{"token_type":"Bearer","expires_in":3599,"access_token":"a"}"#)
+                .unwrap();
+        let imds: TokenResponse =
+            serde_json::from_str(r#"{"access_token":"b","expires_in":"86399","resource":"r"}"#)
+                .unwrap();
+        assert_eq!((entra.access_token.as_str(), entra.expires_in), ("a", 3599));
+        assert_eq!((imds.access_token.as_str(), imds.expires_in), ("b", 86399));
+    }
+
+    #[test]
+    fn uses_the_oauth_error_description() {
+        assert_eq!(
+            error_description(
+                r#"{"error":"invalid_client","error_description":"AADSTS7000215: Invalid client secret provided."}"#
+            ),
+            "AADSTS7000215: Invalid client secret provided."
+        );
+        assert_eq!(
+            error_description(" upstream timeout \n"),
+            "upstream timeout"
+        );
+    }
+
+    #[tokio::test]
+    async fn serves_fresh_tokens_from_the_cache() {
+        let config = AzureAdConfig {
+            tenant_id: Some("cache-test-tenant".to_string()),
+            ..AzureAdConfig::default()
+        };
+        TOKENS.lock().unwrap().insert(
+            config.clone(),
+            CachedToken {
+                access_token: "cached".to_string(),
+                expires_at: Instant::now() + Duration::from_secs(3600),
+            },
+        );
+        assert_eq!(bearer_token(&config).await.unwrap(), "cached");
+    }
+}
@@ -0,0 +1,276 @@
+//! OpenAI Batch API support ([`ModelClient::submit_batch`] and
+//! [`ModelClient::poll_batch`]).
+//!
+//! Requests are uploaded as a JSONL file with `purpose=batch`, a batch is
+//! created against `/v1/responses` with a 24h completion window, and the
+//! output file is downloaded once the batch completes. Batched requests are
+//! billed at half price but run offline: there is no streaming and no tool
+//! execution, so every prompt gets exactly one model response.
+//!
+//! [`ModelClient::submit_batch`]: crate::ModelClient::submit_batch
+//! [`ModelClient::poll_batch`]: crate::ModelClient::poll_batch
+
+use code_protocol::models::ResponseItem;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::client::ResponseCompletedUsage;
+use crate::client_common::Prompt;
+use crate::protocol::TokenUsage;
+
+/// Endpoint every batched request is sent to.
+pub(crate) const BATCH_ENDPOINT: &str = "/v1/responses";
+pub(crate) const COMPLETION_WINDOW: &str = "24h";
+const MULTIPART_BOUNDARY: &str = "code-batch-upload-boundary";
+
+/// One prompt in a batch; `custom_id` matches it to its result.
+#[derive(Debug, Clone)]
+pub struct BatchRequest {
+    pub custom_id: String,
+    pub prompt: Prompt,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchStatus {
+    Validating,
+    InProgress,
+    Finalizing,
+    Completed,
+    Failed,
+    Expired,
+    Cancelling,
+    Cancelled,
+    #[serde(other)]
+    Unknown,
+}
+
+impl BatchStatus {
+    /// The batch will not change any more.
+    pub fn is_terminal(self) -> bool {
+        matches!(
+            self,
+            BatchStatus::Completed
+                | BatchStatus::Failed
+                | BatchStatus::Expired
+                | BatchStatus::Cancelled
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct BatchRequestCounts {
+    #[serde(default)]
+    pub total: u64,
+    #[serde(default)]
+    pub completed: u64,
+    #[serde(default)]
+    pub failed: u64,
+}
+
+/// A batch as reported by the provider.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct BatchJob {
+    pub id: String,
+    pub status: BatchStatus,
+    #[serde(default)]
+    pub output_file_id: Option<String>,
+    #[serde(default)]
+    pub error_file_id: Option<String>,
+    #[serde(default)]
+    pub request_counts: BatchRequestCounts,
+}
+
+/// The model response to one batched request.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchOutput {
+    pub items: Vec<ResponseItem>,
+    pub token_usage: Option<TokenUsage>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchResult {
+    pub custom_id: String,
+    /// The response, or the provider's error message for this request.
+    pub outcome: Result<BatchOutput, String>,
+}
+
+/// State of a batch plus, once it has completed, the per-request results.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchPoll {
+    pub job: BatchJob,
+    pub results: Vec<BatchResult>,
+}
+
+/// One line of the uploaded JSONL file.
+pub(crate) fn request_line(custom_id: &str, body: &Value) -> String {
+    serde_json::json!({
+        "custom_id": custom_id,
+        "method": "POST",
+        "url": BATCH_ENDPOINT,
+        "body": body,
+    })
+    .to_string()
+}
+
+/// `multipart/form-data` body uploading `jsonl` as a batch input file.
+/// Returns the content type (with boundary) and the body.
+pub(crate) fn upload_body(jsonl: &str) -> (String, Vec<u8>) {
+    let body = format!(
+        "--{MULTIPART_BOUNDARY}\r\n\
+         Content-Disposition: form-data; name=\"purpose\"\r\n\r\n\
+         batch\r\n\
+         --{MULTIPART_BOUNDARY}\r\n\
+         Content-Disposition: form-data; name=\"file\"; filename=\"batch.jsonl\"\r\n\
+         Content-Type: application/jsonl\r\n\r\n\
+         {jsonl}\r\n\
+         --{MULTIPART_BOUNDARY}--\r\n"
+    );
+    (
+        format!("multipart/form-data; boundary={MULTIPART_BOUNDARY}"),
+        body.into_bytes(),
+    )
+}
+
+#[derive(Deserialize)]
+struct OutputLine {
+    custom_id: String,
+    #[serde(default)]
+    response: Option<OutputResponse>,
+    #[serde(default)]
+    error: Option<Value>,
+}
+
+#[derive(Deserialize)]
+struct OutputResponse {
+    status_code: u16,
+    #[serde(default)]
+    body: Value,
+}
+
+#[derive(Deserialize)]
+struct ResponseBody {
+    #[serde(default)]
+    output: Vec<ResponseItem>,
+    #[serde(default)]
+    usage: Option<ResponseCompletedUsage>,
+}
+
+/// Parses an output (or error) file. Lines that are not valid JSON are
+/// skipped.
+pub(crate) fn parse_results(jsonl: &str) -> Vec<BatchResult> {
+    jsonl
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| serde_json::from_str::<OutputLine>(line).ok())
+        .map(|line| {
+            let outcome = match (line.response, line.error) {
+                (Some(response), None) if (200..300).contains(&response.status_code) => {
+                    serde_json::from_value::<ResponseBody>(response.body)
+                        .map(|body| BatchOutput {
+                            items: body.output,
+                            token_usage: body.usage.map(Into::into),
+                        })
+                        .map_err(|err| format!("unreadable response body: {err}"))
+                }
+                (Some(response), None) => Err(error_message(&response.body)
+                    .unwrap_or_else(|| format!("HTTP {}", response.status_code))),
+                (_, Some(error)) => Err(error_message(&error).unwrap_or_else(|| error.to_string())),
+                (None, None) => Err("no response recorded".to_string()),
+            };
+            BatchResult {
+                custom_id: line.custom_id,
+                outcome,
+            }
+        })
+        .collect()
+}
+
+fn error_message(value: &Value) -> Option<String> {
+    let error = value.get("error").unwrap_or(value);
+    error
+        .get("message")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use code_protocol::models::ContentItem;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn request_lines_target_the_responses_endpoint() {
+        let line = request_line("doc-1", &serde_json::json!({"model": "gpt-5"}));
+        assert_eq!(
+            serde_json::from_str::<Value>(&line).unwrap(),
+            serde_json::json!({
+                "custom_id": "doc-1",
+                "method": "POST",
+                "url": "/v1/responses",
+                "body": {"model": "gpt-5"},
+            })
+        );
+    }
+
+    #[test]
+    fn parses_successes_and_failures() {
+        let jsonl = [
+            r#"{"id":"r1","custom_id":"a","response":{"status_code":200,"body":{"output":[{"type":"message","role":"assistant","content":[{"type":"output_text","text":"done"}]}],"usage":{"input_tokens":10,"output_tokens":3,"total_tokens":13}}},"error":null}"#,
+            r#"{"id":"r2","custom_id":"b","response":{"status_code":400,"body":{"error":{"message":"bad input"}}},"error":null}"#,
+            r#"{"id":"r3","custom_id":"c","response":null,"error":{"code":"expired","message":"batch expired"}}"#,
+        ]
+        .join("\n");
+        assert_eq!(
+            parse_results(&jsonl),
+            vec![
+                BatchResult {
+                    custom_id: "a".to_string(),
+                    outcome: Ok(BatchOutput {
+                        items: vec![ResponseItem::Message {
+                            id: None,
+                            role: "assistant".to_string(),
+                            content: vec![ContentItem::OutputText {
+                                text: "done".to_string(),
+                            }],
+                        }],
+                        token_usage: Some(TokenUsage {
+                            input_tokens: 10,
+                            cached_input_tokens: 0,
+                            output_tokens: 3,
+                            reasoning_output_tokens: 0,
+                            total_tokens: 13,
+                        }),
+                    }),
+                },
+                BatchResult {
+                    custom_id: "b".to_string(),
+                    outcome: Err("bad input".to_string()),
+                },
+                BatchResult {
+                    custom_id: "c".to_string(),
+                    outcome: Err("batch expired".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn terminal_statuses() {
+        let job: BatchJob = serde_json::from_str(
+            r#"{"id":"batch_1","status":"in_progress","request_counts":{"total":2,"completed":1,"failed":0}}"#,
+        )
+        .unwrap();
+        assert!(!job.status.is_terminal());
+        assert_eq!(
+            job.request_counts,
+            BatchRequestCounts {
+                total: 2,
+                completed: 1,
+                failed: 0,
+            }
+        );
+        assert!(BatchStatus::Expired.is_terminal());
+    }
+}
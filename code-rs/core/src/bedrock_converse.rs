@@ -0,0 +1,1032 @@
+//! Client for the Amazon Bedrock Converse API
+//! (`wire_api = "bedrock_converse"`).
+//!
+//! Requests go to `model/<model>/converse-stream`, signed with SigV4 (see
+//! [`crate::aws_sigv4`]) unless the provider has a Bedrock API key. The
+//! response is AWS event-stream framed rather than SSE. Throttling, whether
+//! as an HTTP 429 that outlasts the request retries or as an in-stream
+//! `throttlingException`, surfaces as a [`CodexErr::Stream`] carrying a
+//! [`RetryAfter`], which Auto Drive treats as a rate limit.
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use bytes::Bytes;
+use chrono::Utc;
+use code_otel::otel_event_manager::OtelEventManager;
+use code_protocol::models::ContentItem;
+use code_protocol::models::ReasoningItemContent;
+use code_protocol::models::ResponseItem;
+use futures::Stream;
+use futures::StreamExt;
+use futures::TryStreamExt;
+use reqwest::StatusCode;
+use serde_json::Value;
+use serde_json::json;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use tracing::debug;
+use tracing::trace;
+
+use crate::ModelProviderInfo;
+use crate::client_common::Prompt;
+use crate::client_common::ResponseEvent;
+use crate::client_common::ResponseStream;
+use crate::debug_logger::DebugLogger;
+use crate::error::CodexErr;
+use crate::error::Result;
+use crate::error::RetryAfter;
+use crate::model_family::ModelFamily;
+use crate::openai_model_info::get_model_info;
+use crate::openai_tools::create_tools_json_for_chat_completions_api;
+use crate::protocol::TokenUsage;
+use crate::quota_ledger;
+use crate::request_middleware::MiddlewareStack;
+use crate::request_retry::Attempt;
+use crate::request_retry::Rejected;
+use crate::request_retry::Rejection;
+use crate::request_retry::is_retryable_status;
+use crate::request_retry::send_with_retries;
+use crate::request_tap::RequestTap;
+use crate::response_anomaly::ResponseAnomaly;
+use crate::sse_buffer::SSE_CHANNEL_CAPACITY;
+use crate::util::backoff;
+
+/// Used for `maxTokens` when the model is unknown.
+const DEFAULT_MAX_OUTPUT_TOKENS: u64 = 8_192;
+
+/// In-stream exceptions worth retrying; any other exception fails the turn.
+const TRANSIENT_EXCEPTIONS: [&str; 3] = [
+    "internalServerException",
+    "modelStreamErrorException",
+    "serviceUnavailableException",
+];
+
+/// Stop reasons meaning a guardrail or content filter withheld the answer.
+const BLOCKED_STOP_REASONS: [&str; 2] = ["guardrail_intervened", "content_filtered"];
+
+pub(crate) async fn stream_bedrock_converse(
+    prompt: &Prompt,
+    model_family: &ModelFamily,
+    model_slug: &str,
+    client: &reqwest::Client,
+    provider: &ModelProviderInfo,
+    debug_logger: &Arc<Mutex<DebugLogger>>,
+    otel_event_manager: Option<OtelEventManager>,
+    middleware: &MiddlewareStack,
+    request_tap: Option<&RequestTap>,
+    log_tag: Option<&str>,
+) -> Result<ResponseStream> {
+    if prompt.output_schema.is_some() {
+        return Err(CodexErr::UnsupportedOperation(
+            "output_schema is not supported for the Bedrock Converse API".to_string(),
+        ));
+    }
+
+    let mut payload = build_converse_payload(prompt, model_family)?;
+    let endpoint = provider.get_bedrock_stream_url(model_slug);
+    let extra_headers = middleware.prepare(&endpoint, &mut payload);
+    let body = serde_json::to_vec(&payload)?;
+    debug!(
+        "POST to {}: {}",
+        endpoint,
+        serde_json::to_string_pretty(&payload).unwrap_or_default()
+    );
+    if let Some(tap) = request_tap {
+        tap.observe(&endpoint, &extra_headers, &payload)?;
+    }
+
+    let estimate = quota_ledger::estimate_tokens(model_slug, prompt, model_family).await;
+    let delivered = send_with_retries(
+        provider,
+        debug_logger,
+        otel_event_manager.as_ref(),
+        log_tag,
+        estimate,
+        || {
+            let (body, extra_headers) = (&body, &extra_headers);
+            async move {
+                // Signed per attempt: the signature covers the request time.
+                let request = provider
+                    .create_bedrock_request_builder(client, model_slug, body.clone())
+                    .await?
+                    .headers(extra_headers.clone());
+                Ok(Attempt {
+                    request,
+                    auth: None,
+                })
+            }
+        },
+        |rejection: Rejection| async move {
+            let status = rejection.response.status();
+            if !is_retryable_status(status) {
+                return rejection.unexpected_status(debug_logger).await;
+            }
+            let delay = rejection.retry_delay();
+            if rejection.is_last && status == StatusCode::TOO_MANY_REQUESTS {
+                let body = rejection.response.text().await.unwrap_or_default();
+                let message = serde_json::from_str::<Value>(&body)
+                    .ok()
+                    .and_then(|v| v.get("message")?.as_str().map(str::to_string))
+                    .unwrap_or(body);
+                return Rejected::Fatal(throttled(&message, delay, Some(rejection.request_id)));
+            }
+            Rejected::Retry(delay)
+        },
+    )
+    .await?;
+
+    let state = ConverseStreamState {
+        response_id: delivered
+            .response
+            .headers()
+            .get("x-amzn-requestid")
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default()
+            .to_string(),
+        ..ConverseStreamState::default()
+    };
+    let (tx_event, rx_event) = mpsc::channel::<Result<ResponseEvent>>(SSE_CHANNEL_CAPACITY);
+    let stream = delivered.response.bytes_stream().map_err(CodexErr::Reqwest);
+    tokio::spawn(process_converse_stream(
+        stream,
+        state,
+        tx_event,
+        provider.stream_idle_timeout(),
+        Arc::clone(debug_logger),
+        delivered.request_id,
+        otel_event_manager,
+    ));
+    Ok(ResponseStream {
+        rx_event,
+        served_by: None,
+        retries: delivered.retries,
+    })
+}
+
+/// A throttling error with the delay before the next attempt, so callers
+/// wait it out as a rate limit.
+fn throttled(message: &str, delay: Duration, request_id: Option<String>) -> CodexErr {
+    CodexErr::Stream(
+        format!("[bedrock] throttled: {message}"),
+        Some(RetryAfter::from_duration(delay, Utc::now())),
+        request_id,
+    )
+}
+
+/// Builds the `converse-stream` request for `prompt`.
+fn build_converse_payload(prompt: &Prompt, model_family: &ModelFamily) -> Result<Value> {
+    let mut system = prompt.get_full_instructions(model_family).into_owned();
+    let messages = translate_input(&prompt.get_formatted_input(), &mut system);
+    let tools = translate_tools(create_tools_json_for_chat_completions_api(&prompt.tools)?);
+    let max_tokens = get_model_info(model_family)
+        .map(|info| info.max_output_tokens)
+        .unwrap_or(DEFAULT_MAX_OUTPUT_TOKENS);
+
+    // Converse has no `seed`.
+    let mut inference_config = json!({ "maxTokens": max_tokens });
+    if let Some(config) = inference_config.as_object_mut() {
+        if let Some(temperature) = prompt.sampling.temperature {
+            config.insert("temperature".to_string(), json!(temperature));
+        }
+        if let Some(top_p) = prompt.sampling.top_p {
+            config.insert("topP".to_string(), json!(top_p));
+        }
+    }
+    let mut payload = json!({
+        "messages": messages,
+        "inferenceConfig": inference_config,
+    });
+    if let Some(obj) = payload.as_object_mut() {
+        if !system.trim().is_empty() {
+            obj.insert("system".to_string(), json!([{ "text": system }]));
+        }
+        if !tools.is_empty() {
+            obj.insert("toolConfig".to_string(), json!({ "tools": tools }));
+        }
+    }
+    Ok(payload)
+}
+
+/// Converts conversation items to Converse messages. Developer and system
+/// messages are appended to `system`.
+fn translate_input(input: &[ResponseItem], system: &mut String) -> Vec<Value> {
+    let mut messages = Vec::<Value>::new();
+    let mut tool_use_ids = HashSet::<String>::new();
+
+    for item in input {
+        match item {
+            ResponseItem::Message { role, content, .. } => {
+                if role == "developer" || role == "system" {
+                    let text = content
+                        .iter()
+                        .filter_map(|content| match content {
+                            ContentItem::InputText { text } | ContentItem::OutputText { text } => {
+                                Some(text.as_str())
+                            }
+                            ContentItem::InputImage { .. } => None,
+                        })
+                        .collect::<Vec<_>>()
+                        .join("\n");
+                    if !text.trim().is_empty() {
+                        if !system.is_empty() {
+                            system.push_str("\n\n");
+                        }
+                        system.push_str(&text);
+                    }
+                    continue;
+                }
+                let role = if role == "assistant" {
+                    "assistant"
+                } else {
+                    "user"
+                };
+                for content in content {
+                    match content {
+                        ContentItem::InputText { text } | ContentItem::OutputText { text } => {
+                            if !text.is_empty() {
+                                push_block(&mut messages, role, json!({ "text": text }));
+                            }
+                        }
+                        ContentItem::InputImage { image_url } => {
+                            if let Some(block) = image_block(image_url) {
+                                push_block(&mut messages, role, block);
+                            }
+                        }
+                    }
+                }
+            }
+            ResponseItem::FunctionCall {
+                name,
+                arguments,
+                call_id,
+                ..
+            } => {
+                let input = serde_json::from_str::<Value>(arguments)
+                    .ok()
+                    .filter(Value::is_object)
+                    .unwrap_or_else(|| json!({}));
+                tool_use_ids.insert(call_id.clone());
+                push_block(
+                    &mut messages,
+                    "assistant",
+                    json!({ "toolUse": { "toolUseId": call_id, "name": name, "input": input } }),
+                );
+            }
+            ResponseItem::CustomToolCall {
+                call_id,
+                name,
+                input,
+                ..
+            } => {
+                tool_use_ids.insert(call_id.clone());
+                push_block(
+                    &mut messages,
+                    "assistant",
+                    json!({ "toolUse": {
+                        "toolUseId": call_id,
+                        "name": name,
+                        "input": { "input": input },
+                    } }),
+                );
+            }
+            ResponseItem::FunctionCallOutput { call_id, output } => {
+                // The API rejects results whose call it has not seen.
+                if tool_use_ids.contains(call_id) {
+                    let status = if output.success == Some(false) {
+                        "error"
+                    } else {
+                        "success"
+                    };
+                    push_block(
+                        &mut messages,
+                        "user",
+                        json!({ "toolResult": {
+                            "toolUseId": call_id,
+                            "content": [{ "text": output.content }],
+                            "status": status,
+                        } }),
+                    );
+                }
+            }
+            ResponseItem::CustomToolCallOutput { call_id, output } => {
+                if tool_use_ids.contains(call_id) {
+                    push_block(
+                        &mut messages,
+                        "user",
+                        json!({ "toolResult": {
+                            "toolUseId": call_id,
+                            "content": [{ "text": output }],
+                        } }),
+                    );
+                }
+            }
+            // Local shell calls are an OpenAI built-in tool; the rest only
+            // mean something to the Responses API.
+            ResponseItem::LocalShellCall { .. }
+            | ResponseItem::Reasoning { .. }
+            | ResponseItem::WebSearchCall { .. }
+            | ResponseItem::CompactionSummary { .. }
+            | ResponseItem::Other => {}
+        }
+    }
+    messages
+}
+
+/// Appends `block` to the last message when it has the same role, so turns
+/// alternate as the API requires.
+fn push_block(messages: &mut Vec<Value>, role: &str, block: Value) {
+    if let Some(last) = messages.last_mut()
+        && last.get("role").and_then(Value::as_str) == Some(role)
+        && let Some(content) = last.get_mut("content").and_then(Value::as_array_mut)
+    {
+        content.push(block);
+        return;
+    }
+    messages.push(json!({ "role": role, "content": [block] }));
+}
+
+/// Converse only takes inline image bytes, so only data URLs are sent.
+fn image_block(image_url: &str) -> Option<Value> {
+    let (media_type, data) = image_url.strip_prefix("data:")?.split_once(";base64,")?;
+    let format = media_type.strip_prefix("image/")?;
+    Some(json!({ "image": { "format": format, "source": { "bytes": data } } }))
+}
+
+/// Rewrites Chat Completions function tools as Converse tool specs.
+fn translate_tools(tools: Vec<Value>) -> Vec<Value> {
+    tools
+        .into_iter()
+        .filter_map(|tool| {
+            let function = tool.get("function")?;
+            Some(json!({ "toolSpec": {
+                "name": function.get("name")?,
+                "description": function
+                    .get("description")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default(),
+                "inputSchema": {
+                    "json": function
+                        .get("parameters")
+                        .cloned()
+                        .unwrap_or_else(|| json!({ "type": "object", "properties": {} })),
+                },
+            } }))
+        })
+        .collect()
+}
+
+async fn process_converse_stream<S>(
+    mut stream: S,
+    mut state: ConverseStreamState,
+    tx_event: mpsc::Sender<Result<ResponseEvent>>,
+    idle_timeout: Duration,
+    debug_logger: Arc<Mutex<DebugLogger>>,
+    request_id: String,
+    otel_event_manager: Option<OtelEventManager>,
+) where
+    S: Stream<Item = Result<Bytes>> + Unpin,
+{
+    let mut decoder = EventStreamDecoder::default();
+
+    loop {
+        let events = match timeout(idle_timeout, stream.next()).await {
+            Ok(Some(Ok(chunk))) => {
+                decoder.push(&chunk);
+                decode_events(&mut decoder, &mut state, &debug_logger, &request_id)
+            }
+            Ok(Some(Err(e))) => Err(CodexErr::Stream(
+                format!("[transport] {e}"),
+                None,
+                Some(request_id.clone()),
+            )),
+            Ok(None) => {
+                let last = if state.stop_reason.is_none() {
+                    let detail = "stream closed before messageStop";
+                    ResponseAnomaly::Truncated.record(detail, otel_event_manager.as_ref());
+                    Err(ResponseAnomaly::Truncated.into_error(detail, &request_id))
+                } else {
+                    Ok(state.completed())
+                };
+                let _ = tx_event.send(last).await;
+                if let Ok(logger) = debug_logger.lock() {
+                    let _ = logger.end_request_log(&request_id);
+                }
+                return;
+            }
+            Err(_) => Err(CodexErr::Stream(
+                "[idle] timeout waiting for event stream".into(),
+                None,
+                Some(request_id.clone()),
+            )),
+        };
+
+        match events {
+            Ok(events) => {
+                for event in events {
+                    if tx_event.send(Ok(event)).await.is_err() {
+                        return;
+                    }
+                }
+            }
+            Err(err) => {
+                let err = match err {
+                    CodexErr::Stream(message, retry_after, None) => {
+                        CodexErr::Stream(message, retry_after, Some(request_id.clone()))
+                    }
+                    other => other,
+                };
+                let _ = tx_event.send(Err(err)).await;
+                if let Ok(logger) = debug_logger.lock() {
+                    let _ = logger.end_request_log(&request_id);
+                }
+                return;
+            }
+        }
+    }
+}
+
+/// Runs every complete frame buffered in `decoder` through `state`.
+fn decode_events(
+    decoder: &mut EventStreamDecoder,
+    state: &mut ConverseStreamState,
+    debug_logger: &Arc<Mutex<DebugLogger>>,
+    request_id: &str,
+) -> Result<Vec<ResponseEvent>> {
+    let mut events = Vec::new();
+    while let Some(message) = decoder.next_message()? {
+        let data: Value = serde_json::from_slice(&message.payload).unwrap_or(Value::Null);
+        let kind = message
+            .headers
+            .get(":event-type")
+            .or_else(|| message.headers.get(":exception-type"))
+            .map(String::as_str)
+            .unwrap_or_default();
+        trace!("bedrock_converse received event {kind}: {data:?}");
+        if let Ok(logger) = debug_logger.lock() {
+            let _ = logger.append_response_event(
+                request_id,
+                "stream_event",
+                &json!({ "type": kind, "data": data }),
+            );
+        }
+        if message.headers.get(":message-type").map(String::as_str) == Some("event") {
+            events.extend(state.on_event(kind, &data)?);
+        } else {
+            return Err(exception_error(kind, &data));
+        }
+    }
+    Ok(events)
+}
+
+fn exception_error(kind: &str, data: &Value) -> CodexErr {
+    let message = data
+        .get("message")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    if kind == "throttlingException" {
+        throttled(message, backoff(1), None)
+    } else if TRANSIENT_EXCEPTIONS.contains(&kind) {
+        CodexErr::Stream(format!("[bedrock] {kind}: {message}"), None, None)
+    } else {
+        CodexErr::ServerError(format!("Bedrock {kind}: {message}"))
+    }
+}
+
+/// One frame of the `application/vnd.amazon.eventstream` encoding.
+struct EventStreamMessage {
+    /// String-valued headers such as `:event-type`; others are skipped.
+    headers: HashMap<String, String>,
+    payload: Vec<u8>,
+}
+
+/// Splits the response body into event-stream frames: a 12-byte prelude
+/// (total length, headers length, prelude CRC), the headers, the payload,
+/// and a trailing CRC of the whole message. A frame whose CRCs do not match
+/// fails the stream rather than being parsed.
+#[derive(Default)]
+struct EventStreamDecoder {
+    buf: Vec<u8>,
+}
+
+impl EventStreamDecoder {
+    fn push(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+    }
+
+    fn next_message(&mut self) -> Result<Option<EventStreamMessage>> {
+        if self.buf.len() < 12 {
+            return Ok(None);
+        }
+        if crc32fast::hash(&self.buf[0..8]) != read_u32(&self.buf[8..12]) {
+            return Err(corrupt_frame("prelude"));
+        }
+        let total_len = read_u32(&self.buf[0..4]) as usize;
+        let headers_len = read_u32(&self.buf[4..8]) as usize;
+        if total_len < 16 || headers_len + 16 > total_len {
+            return Err(malformed_frame());
+        }
+        if self.buf.len() < total_len {
+            return Ok(None);
+        }
+        let frame: Vec<u8> = self.buf.drain(..total_len).collect();
+        if crc32fast::hash(&frame[..total_len - 4]) != read_u32(&frame[total_len - 4..]) {
+            return Err(corrupt_frame("message"));
+        }
+        let headers = parse_headers(&frame[12..12 + headers_len])?;
+        let payload = frame[12 + headers_len..total_len - 4].to_vec();
+        Ok(Some(EventStreamMessage { headers, payload }))
+    }
+}
+
+fn parse_headers(mut bytes: &[u8]) -> Result<HashMap<String, String>> {
+    let mut headers = HashMap::new();
+    while !bytes.is_empty() {
+        let name_len = bytes[0] as usize;
+        let name = bytes.get(1..1 + name_len).ok_or_else(malformed_frame)?;
+        let name = String::from_utf8_lossy(name).into_owned();
+        bytes = &bytes[1 + name_len..];
+        let value_type = *bytes.first().ok_or_else(malformed_frame)?;
+        bytes = &bytes[1..];
+        let value_len = match value_type {
+            // true, false
+            0 | 1 => 0,
+            // byte, short, int, long, timestamp, uuid
+            2 => 1,
+            3 => 2,
+            4 => 4,
+            5 | 8 => 8,
+            9 => 16,
+            // byte array, string: u16 length prefix
+            6 | 7 => {
+                let len = bytes.get(0..2).ok_or_else(malformed_frame)?;
+                bytes = &bytes[2..];
+                u16::from_be_bytes([len[0], len[1]]) as usize
+            }
+            _ => return Err(malformed_frame()),
+        };
+        let value = bytes.get(..value_len).ok_or_else(malformed_frame)?;
+        if value_type == 7 {
+            headers.insert(name, String::from_utf8_lossy(value).into_owned());
+        }
+        bytes = &bytes[value_len..];
+    }
+    Ok(headers)
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+fn malformed_frame() -> CodexErr {
+    CodexErr::Stream(
+        "[bedrock] malformed event stream frame".to_string(),
+        None,
+        None,
+    )
+}
+
+fn corrupt_frame(part: &str) -> CodexErr {
+    CodexErr::Stream(
+        format!("[bedrock] event stream {part} CRC mismatch"),
+        None,
+        None,
+    )
+}
+
+enum ContentBlock {
+    Text(String),
+    Reasoning(String),
+    ToolUse {
+        id: String,
+        name: String,
+        input: String,
+    },
+}
+
+/// Tracks the content blocks of one streamed Converse response.
+#[derive(Default)]
+struct ConverseStreamState {
+    response_id: String,
+    usage: Option<TokenUsage>,
+    blocks: BTreeMap<u64, ContentBlock>,
+    stop_reason: Option<String>,
+}
+
+impl ConverseStreamState {
+    fn on_event(&mut self, kind: &str, data: &Value) -> Result<Vec<ResponseEvent>> {
+        let index = data
+            .get("contentBlockIndex")
+            .and_then(Value::as_u64)
+            .unwrap_or_default();
+        match kind {
+            "messageStart" => Ok(vec![ResponseEvent::Created]),
+            "contentBlockStart" => {
+                // Only tool use blocks announce themselves; text and
+                // reasoning blocks start with their first delta.
+                if let Some(tool_use) = data.pointer("/start/toolUse") {
+                    self.blocks.insert(
+                        index,
+                        ContentBlock::ToolUse {
+                            id: string_field(tool_use, "toolUseId"),
+                            name: string_field(tool_use, "name"),
+                            input: String::new(),
+                        },
+                    );
+                }
+                Ok(Vec::new())
+            }
+            "contentBlockDelta" => {
+                let delta = data.get("delta").unwrap_or(&Value::Null);
+                if let Some(text) = delta.get("text").and_then(Value::as_str) {
+                    if text.is_empty() {
+                        return Ok(Vec::new());
+                    }
+                    if let ContentBlock::Text(buffer) = self
+                        .blocks
+                        .entry(index)
+                        .or_insert_with(|| ContentBlock::Text(String::new()))
+                    {
+                        buffer.push_str(text);
+                    }
+                    return Ok(vec![ResponseEvent::OutputTextDelta {
+                        delta: text.to_string(),
+                        item_id: None,
+                        sequence_number: None,
+                        output_index: None,
+                    }]);
+                }
+                if let Some(text) = delta
+                    .pointer("/reasoningContent/text")
+                    .and_then(Value::as_str)
+                {
+                    if let ContentBlock::Reasoning(buffer) = self
+                        .blocks
+                        .entry(index)
+                        .or_insert_with(|| ContentBlock::Reasoning(String::new()))
+                    {
+                        buffer.push_str(text);
+                    }
+                    return Ok(vec![ResponseEvent::ReasoningContentDelta {
+                        delta: text.to_string(),
+                        item_id: None,
+                        sequence_number: None,
+                        output_index: None,
+                        content_index: None,
+                    }]);
+                }
+                if let Some(partial) = delta.pointer("/toolUse/input").and_then(Value::as_str)
+                    && let Some(ContentBlock::ToolUse { input, .. }) = self.blocks.get_mut(&index)
+                {
+                    input.push_str(partial);
+                }
+                Ok(Vec::new())
+            }
+            "contentBlockStop" => {
+                let item = match self.blocks.remove(&index) {
+                    Some(ContentBlock::Text(text)) if !text.is_empty() => ResponseItem::Message {
+                        id: None,
+                        role: "assistant".to_string(),
+                        content: vec![ContentItem::OutputText { text }],
+                    },
+                    Some(ContentBlock::Reasoning(text)) if !text.is_empty() => {
+                        ResponseItem::Reasoning {
+                            id: String::new(),
+                            summary: Vec::new(),
+                            content: Some(vec![ReasoningItemContent::ReasoningText { text }]),
+                            encrypted_content: None,
+                        }
+                    }
+                    Some(ContentBlock::ToolUse { id, name, input }) => ResponseItem::FunctionCall {
+                        id: None,
+                        name,
+                        arguments: if input.trim().is_empty() {
+                            "{}".to_string()
+                        } else {
+                            input
+                        },
+                        call_id: id,
+                    },
+                    _ => return Ok(Vec::new()),
+                };
+                Ok(vec![ResponseEvent::OutputItemDone {
+                    item,
+                    sequence_number: None,
+                    output_index: None,
+                }])
+            }
+            "messageStop" => {
+                let reason = string_field(data, "stopReason");
+                if BLOCKED_STOP_REASONS.contains(&reason.as_str()) {
+                    return Err(CodexErr::ContentBlocked(format!(
+                        "Bedrock stopped the response ({reason})"
+                    )));
+                }
+                self.stop_reason = Some(reason);
+                Ok(Vec::new())
+            }
+            "metadata" => {
+                if let Some(usage) = data.get("usage") {
+                    self.usage = Some(token_usage(usage));
+                }
+                Ok(Vec::new())
+            }
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    /// `metadata` follows `messageStop`, so completion waits for the end of
+    /// the stream.
+    fn completed(&mut self) -> ResponseEvent {
+        ResponseEvent::Completed {
+            response_id: std::mem::take(&mut self.response_id),
+            token_usage: self.usage.take(),
+        }
+    }
+}
+
+/// Cache reads and writes are reported apart from `inputTokens`; they are
+/// all part of the input.
+fn token_usage(usage: &Value) -> TokenUsage {
+    let field = |name: &str| usage.get(name).and_then(Value::as_u64).unwrap_or_default();
+    let cache_read = field("cacheReadInputTokens");
+    let input_tokens = field("inputTokens") + cache_read + field("cacheWriteInputTokens");
+    let output_tokens = field("outputTokens");
+    TokenUsage {
+        input_tokens,
+        cached_input_tokens: cache_read,
+        output_tokens,
+        reasoning_output_tokens: 0,
+        total_tokens: input_tokens + output_tokens,
+    }
+}
+
+fn string_field(value: &Value, name: &str) -> String {
+    value
+        .get(name)
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use code_protocol::models::FunctionCallOutputPayload;
+    use pretty_assertions::assert_eq;
+
+    fn message(role: &str, text: &str) -> ResponseItem {
+        ResponseItem::Message {
+            id: None,
+            role: role.to_string(),
+            content: vec![ContentItem::InputText {
+                text: text.to_string(),
+            }],
+        }
+    }
+
+    /// Encodes a frame with string headers.
+    fn frame(headers: &[(&str, &str)], payload: &Value) -> Vec<u8> {
+        let mut header_bytes = Vec::new();
+        for (name, value) in headers {
+            header_bytes.push(name.len() as u8);
+            header_bytes.extend_from_slice(name.as_bytes());
+            header_bytes.push(7);
+            header_bytes.extend_from_slice(&(value.len() as u16).to_be_bytes());
+            header_bytes.extend_from_slice(value.as_bytes());
+        }
+        let payload = payload.to_string().into_bytes();
+        let total_len = 16 + header_bytes.len() + payload.len();
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&(total_len as u32).to_be_bytes());
+        bytes.extend_from_slice(&(header_bytes.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(&crc32fast::hash(&bytes).to_be_bytes());
+        bytes.extend_from_slice(&header_bytes);
+        bytes.extend_from_slice(&payload);
+        bytes.extend_from_slice(&crc32fast::hash(&bytes).to_be_bytes());
+        bytes
+    }
+
+    fn event(kind: &str, payload: Value) -> Vec<u8> {
+        frame(
+            &[(":message-type", "event"), (":event-type", kind)],
+            &payload,
+        )
+    }
+
+    #[test]
+    fn translates_history_into_converse_messages() {
+        let input = vec![
+            message("developer", "Stay in the repo."),
+            message("user", "Run the tests"),
+            ResponseItem::FunctionCall {
+                id: None,
+                name: "shell".to_string(),
+                arguments: r#"{"command":["cargo","test"]}"#.to_string(),
+                call_id: "tooluse_1".to_string(),
+            },
+            ResponseItem::FunctionCallOutput {
+                call_id: "tooluse_1".to_string(),
+                output: FunctionCallOutputPayload {
+                    content: "test result: FAILED".to_string(),
+                    success: Some(false),
+                },
+            },
+            ResponseItem::Message {
+                id: None,
+                role: "user".to_string(),
+                content: vec![ContentItem::InputImage {
+                    image_url: "data:image/png;base64,iVBORw0KGgo=".to_string(),
+                }],
+            },
+        ];
+        let mut system = "Base instructions".to_string();
+        let messages = translate_input(&input, &mut system);
+
+        assert_eq!(system, "Base instructions\n\nStay in the repo.");
+        assert_eq!(
+            Value::Array(messages),
+            json!([
+                {"role": "user", "content": [{"text": "Run the tests"}]},
+                {"role": "assistant", "content": [{"toolUse": {
+                    "toolUseId": "tooluse_1",
+                    "name": "shell",
+                    "input": {"command": ["cargo", "test"]},
+                }}]},
+                {"role": "user", "content": [
+                    {"toolResult": {
+                        "toolUseId": "tooluse_1",
+                        "content": [{"text": "test result: FAILED"}],
+                        "status": "error",
+                    }},
+                    {"image": {"format": "png", "source": {"bytes": "iVBORw0KGgo="}}},
+                ]},
+            ])
+        );
+    }
+
+    #[test]
+    fn decodes_frames_split_across_chunks() {
+        let mut bytes = event("messageStart", json!({"role": "assistant"}));
+        bytes.extend(event(
+            "contentBlockDelta",
+            json!({"contentBlockIndex": 0, "delta": {"text": "Hi"}}),
+        ));
+        let mut decoder = EventStreamDecoder::default();
+        decoder.push(&bytes[..bytes.len() - 3]);
+        let first = decoder.next_message().unwrap().unwrap();
+        assert_eq!(first.headers[":event-type"], "messageStart");
+        assert_eq!(
+            serde_json::from_slice::<Value>(&first.payload).unwrap(),
+            json!({"role": "assistant"})
+        );
+        assert!(decoder.next_message().unwrap().is_none());
+        decoder.push(&bytes[bytes.len() - 3..]);
+        let second = decoder.next_message().unwrap().unwrap();
+        assert_eq!(second.headers[":event-type"], "contentBlockDelta");
+        assert!(decoder.next_message().unwrap().is_none());
+    }
+
+    #[test]
+    fn rejects_frames_with_bad_crcs() {
+        let decode = |bytes: Vec<u8>| {
+            let mut decoder = EventStreamDecoder::default();
+            decoder.push(&bytes);
+            match decoder.next_message() {
+                Err(CodexErr::Stream(message, _, _)) => message,
+                Err(other) => panic!("unexpected error: {other:?}"),
+                Ok(_) => panic!("corrupt frame was accepted"),
+            }
+        };
+        let valid = event("messageStart", json!({"role": "assistant"}));
+
+        let mut bad_length = valid.clone();
+        bad_length[3] ^= 1;
+        assert_eq!(
+            decode(bad_length),
+            "[bedrock] event stream prelude CRC mismatch"
+        );
+
+        let mut bad_payload = valid;
+        let last_payload_byte = bad_payload.len() - 5;
+        bad_payload[last_payload_byte] ^= 1;
+        assert_eq!(
+            decode(bad_payload),
+            "[bedrock] event stream message CRC mismatch"
+        );
+    }
+
+    #[test]
+    fn maps_stream_events_to_response_events() {
+        let mut state = ConverseStreamState {
+            response_id: "req-1".to_string(),
+            ..ConverseStreamState::default()
+        };
+        let stream = [
+            ("messageStart", json!({"role": "assistant"})),
+            (
+                "contentBlockDelta",
+                json!({"contentBlockIndex": 0, "delta": {"text": "Running."}}),
+            ),
+            ("contentBlockStop", json!({"contentBlockIndex": 0})),
+            (
+                "contentBlockStart",
+                json!({"contentBlockIndex": 1, "start": {"toolUse": {
+                    "toolUseId": "tooluse_1",
+                    "name": "shell",
+                }}}),
+            ),
+            (
+                "contentBlockDelta",
+                json!({"contentBlockIndex": 1, "delta": {"toolUse": {"input": "{\"command\":"}}}),
+            ),
+            (
+                "contentBlockDelta",
+                json!({"contentBlockIndex": 1, "delta": {"toolUse": {"input": "[\"ls\"]}"}}}),
+            ),
+            ("contentBlockStop", json!({"contentBlockIndex": 1})),
+            ("messageStop", json!({"stopReason": "tool_use"})),
+            (
+                "metadata",
+                json!({"usage": {
+                    "inputTokens": 200,
+                    "cacheReadInputTokens": 800,
+                    "outputTokens": 40,
+                    "totalTokens": 1040,
+                }}),
+            ),
+        ];
+        let mut items = Vec::new();
+        for (kind, data) in stream {
+            for event in state.on_event(kind, &data).unwrap() {
+                if let ResponseEvent::OutputItemDone { item, .. } = event {
+                    items.push(item);
+                }
+            }
+        }
+        assert_eq!(
+            items,
+            vec![
+                ResponseItem::Message {
+                    id: None,
+                    role: "assistant".to_string(),
+                    content: vec![ContentItem::OutputText {
+                        text: "Running.".to_string(),
+                    }],
+                },
+                ResponseItem::FunctionCall {
+                    id: None,
+                    name: "shell".to_string(),
+                    arguments: r#"{"command":["ls"]}"#.to_string(),
+                    call_id: "tooluse_1".to_string(),
+                },
+            ]
+        );
+        let ResponseEvent::Completed {
+            response_id,
+            token_usage,
+        } = state.completed()
+        else {
+            panic!("expected completion");
+        };
+        assert_eq!(response_id, "req-1");
+        assert_eq!(
+            token_usage,
+            Some(TokenUsage {
+                input_tokens: 1000,
+                cached_input_tokens: 800,
+                output_tokens: 40,
+                reasoning_output_tokens: 0,
+                total_tokens: 1040,
+            })
+        );
+    }
+
+    #[test]
+    fn throttling_exceptions_carry_a_retry_delay() {
+        let mut decoder = EventStreamDecoder::default();
+        decoder.push(&frame(
+            &[
+                (":message-type", "exception"),
+                (":exception-type", "throttlingException"),
+            ],
+            &json!({"message": "Too many requests, please wait before trying again."}),
+        ));
+        let err = decode_events(
+            &mut decoder,
+            &mut ConverseStreamState::default(),
+            &Arc::new(Mutex::new(DebugLogger::new(false).unwrap())),
+            "req-1",
+        )
+        .unwrap_err();
+        let CodexErr::Stream(message, retry_after, _) = err else {
+            panic!("expected a stream error, got {err:?}");
+        };
+        assert!(message.starts_with("[bedrock] throttled"));
+        assert!(retry_after.is_some());
+    }
+}
@@ -7,7 +7,6 @@ use eventsource_stream::Eventsource;
 use futures::Stream;
 use futures::StreamExt;
 use futures::TryStreamExt;
-use reqwest::StatusCode;
 use reqwest::header::HeaderMap;
 use serde_json::json;
 use std::pin::Pin;
@@ -26,11 +25,18 @@ use crate::client_common::ResponseStream;
 use crate::debug_logger::DebugLogger;
 use crate::error::CodexErr;
 use crate::error::Result;
-use crate::error::RetryLimitReachedError;
-use crate::error::UnexpectedResponseError;
 use crate::model_family::ModelFamily;
 use crate::openai_tools::create_tools_json_for_chat_completions_api;
-use crate::util::backoff;
+use crate::quota_ledger;
+use crate::request_middleware::MiddlewareStack;
+use crate::request_retry::Attempt;
+use crate::request_retry::Rejected;
+use crate::request_retry::Rejection;
+use crate::request_retry::is_retryable_status;
+use crate::request_retry::send_with_retries;
+use crate::request_tap::RequestTap;
+use crate::response_anomaly::ResponseAnomaly;
+use crate::sse_buffer::SSE_CHANNEL_CAPACITY;
 use code_app_server_protocol::AuthMode;
 use code_protocol::models::ContentItem;
 use code_protocol::models::ReasoningItemContent;
@@ -48,6 +54,8 @@ pub(crate) async fn stream_chat_completions(
     debug_logger: &Arc<Mutex<DebugLogger>>,
     auth_manager: Option<Arc<AuthManager>>,
     otel_event_manager: Option<OtelEventManager>,
+    middleware: &MiddlewareStack,
+    request_tap: Option<&RequestTap>,
     log_tag: Option<&str>,
 ) -> Result<ResponseStream> {
     if prompt.output_schema.is_some() {
@@ -219,6 +227,25 @@ pub(crate) async fn stream_chat_completions(
                 call_id,
                 ..
             } => {
+                // Parallel calls from one turn go out as a single assistant
+                // message, as the API requires before their tool results.
+                if idx > 0
+                    && matches!(input[idx - 1], ResponseItem::FunctionCall { .. })
+                    && let Some(tool_calls) = messages
+                        .last_mut()
+                        .and_then(|msg| msg.get_mut("tool_calls"))
+                        .and_then(|calls| calls.as_array_mut())
+                {
+                    tool_calls.push(json!({
+                        "id": call_id,
+                        "type": "function",
+                        "function": {
+                            "name": name,
+                            "arguments": arguments,
+                        }
+                    }));
+                    continue;
+                }
                 let mut msg = json!({
                     "role": "assistant",
                     "content": null,
@@ -312,6 +339,18 @@ pub(crate) async fn stream_chat_completions(
         "stream": true,
         "tools": tools_json,
     });
+    if let Some(obj) = payload.as_object_mut() {
+        let sampling = prompt.sampling;
+        if let Some(seed) = sampling.seed {
+            obj.insert("seed".to_string(), json!(seed));
+        }
+        if let Some(temperature) = sampling.temperature {
+            obj.insert("temperature".to_string(), json!(temperature));
+        }
+        if let Some(top_p) = sampling.top_p {
+            obj.insert("top_p".to_string(), json!(top_p));
+        }
+    }
 
     if let Some(openrouter_cfg) = provider.openrouter_config()
         && let Some(obj) = payload.as_object_mut()
@@ -342,150 +381,65 @@ pub(crate) async fn stream_chat_completions(
     }
 
     let endpoint = provider.get_full_url(&None);
+    let extra_headers = middleware.prepare(&endpoint, &mut payload);
     debug!(
         "POST to {}: {}",
         endpoint,
         serde_json::to_string_pretty(&payload).unwrap_or_default()
     );
+    if let Some(tap) = request_tap {
+        tap.observe(&endpoint, &extra_headers, &payload)?;
+    }
 
-    let mut attempt = 0;
-    let max_retries = provider.request_max_retries();
-    let mut request_id = String::new();
-    loop {
-        attempt += 1;
-
-        let auth = auth_manager.as_ref().and_then(|m| m.auth());
-        let mut req_builder = provider.create_request_builder(client, &auth).await?;
-
-        if let Some(auth) = auth.as_ref()
-            && auth.mode == AuthMode::ChatGPT
-            && let Some(account_id) = auth.get_account_id()
-        {
-            req_builder = req_builder.header("chatgpt-account-id", account_id);
-        }
-
-        req_builder = req_builder
-            .header(reqwest::header::ACCEPT, "text/event-stream")
-            .json(&payload);
-
-        if request_id.is_empty() {
-            let endpoint_for_log = provider.get_full_url(&auth);
-            let header_snapshot = req_builder
-                .try_clone()
-                .and_then(|builder| builder.build().ok())
-                .map(|req| header_map_to_json(req.headers()));
-
-            if let Ok(logger) = debug_logger.lock() {
-                request_id = logger
-                    .start_request_log(
-                        &endpoint_for_log,
-                        &payload,
-                        header_snapshot.as_ref(),
-                        log_tag,
-                    )
-                    .unwrap_or_default();
-            }
-        }
-
-        let res = req_builder.send().await;
-
-        match res {
-            Ok(resp) if resp.status().is_success() => {
-                // Log successful response initiation
-                if let Ok(logger) = debug_logger.lock() {
-                    let _ = logger.append_response_event(
-                        &request_id,
-                        "stream_initiated",
-                        &serde_json::json!({
-                            "status": "success",
-                            "status_code": resp.status().as_u16()
-                        }),
-                    );
-                }
-                let (tx_event, rx_event) = mpsc::channel::<Result<ResponseEvent>>(1600);
-                let stream = resp.bytes_stream().map_err(CodexErr::Reqwest);
-                let debug_logger_clone = Arc::clone(debug_logger);
-                let request_id_clone = request_id.clone();
-                tokio::spawn(process_chat_sse(
-                    stream,
-                    tx_event,
-                    provider.stream_idle_timeout(),
-                    debug_logger_clone,
-                    request_id_clone,
-                    otel_event_manager.clone(),
-                ));
-                return Ok(ResponseStream { rx_event });
-            }
-            Ok(res) => {
-                let status = res.status();
-                if !(status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()) {
-                    let body = (res.text().await).unwrap_or_default();
-                    if let Ok(logger) = debug_logger.lock() {
-                        let _ = logger.append_response_event(
-                            &request_id,
-                            "error",
-                            &serde_json::json!({
-                                "status": status.as_u16(),
-                                "body": body
-                            }),
-                        );
-                        let _ = logger.end_request_log(&request_id);
-                    }
-                    return Err(CodexErr::UnexpectedStatus(UnexpectedResponseError {
-                        status,
-                        body,
-                        request_id: None,
-                    }));
-                }
-
-                if attempt > max_retries {
-                    return Err(CodexErr::RetryLimit(RetryLimitReachedError {
-                        status,
-                        request_id: None,
-                        retryable: status.is_server_error()
-                            || status == StatusCode::TOO_MANY_REQUESTS,
-                    }));
+    let estimate = quota_ledger::estimate_tokens(model_slug, prompt, model_family).await;
+    let delivered = send_with_retries(
+        provider,
+        debug_logger,
+        otel_event_manager.as_ref(),
+        log_tag,
+        estimate,
+        || {
+            let auth = auth_manager.as_ref().and_then(|m| m.auth());
+            let (payload, extra_headers) = (&payload, &extra_headers);
+            async move {
+                let mut request = provider.create_request_builder(client, &auth).await?;
+                if let Some(auth) = auth.as_ref()
+                    && auth.mode == AuthMode::ChatGPT
+                    && let Some(account_id) = auth.get_account_id()
+                {
+                    request = request.header("chatgpt-account-id", account_id);
                 }
-
-                let retry_after_secs = res
-                    .headers()
-                    .get(reqwest::header::RETRY_AFTER)
-                    .and_then(|v| v.to_str().ok())
-                    .and_then(|s| s.parse::<u64>().ok());
-
-                let delay = retry_after_secs
-                    .map(|s| Duration::from_millis(s * 1_000))
-                    .unwrap_or_else(|| backoff(attempt));
-                tokio::time::sleep(delay).await;
+                let request = request
+                    .header(reqwest::header::ACCEPT, "text/event-stream")
+                    .json(payload)
+                    .headers(extra_headers.clone());
+                Ok(Attempt { request, auth })
             }
-            Err(e) => {
-                let is_connectivity = e.is_connect() || e.is_timeout() || e.is_request();
-                if attempt > max_retries {
-                    if let Ok(logger) = debug_logger.lock() {
-                        let _ = logger.append_response_event(
-                            &request_id,
-                            "network_error",
-                            &serde_json::json!({
-                                "error": e.to_string()
-                            }),
-                        );
-                        let _ = logger.end_request_log(&request_id);
-                    }
-                    if is_connectivity {
-                        let req_id = (!request_id.is_empty()).then(|| request_id.clone());
-                        return Err(CodexErr::Stream(
-                            format!("[transport] network unavailable: {e}"),
-                            None,
-                            req_id,
-                        ));
-                    }
-                    return Err(e.into());
-                }
-                let delay = backoff(attempt);
-                tokio::time::sleep(delay).await;
+        },
+        |rejection: Rejection| async move {
+            if !is_retryable_status(rejection.response.status()) {
+                return rejection.unexpected_status(debug_logger).await;
             }
-        }
-    }
+            Rejected::Retry(rejection.retry_delay())
+        },
+    )
+    .await?;
+
+    let (tx_event, rx_event) = mpsc::channel::<Result<ResponseEvent>>(SSE_CHANNEL_CAPACITY);
+    let stream = delivered.response.bytes_stream().map_err(CodexErr::Reqwest);
+    tokio::spawn(process_chat_sse(
+        stream,
+        tx_event,
+        provider.stream_idle_timeout(),
+        Arc::clone(debug_logger),
+        delivered.request_id,
+        otel_event_manager,
+    ));
+    Ok(ResponseStream {
+        rx_event,
+        served_by: None,
+        retries: delivered.retries,
+    })
 }
 
 /// Lightweight SSE processor for the Chat Completions streaming format. The
@@ -503,20 +457,20 @@ async fn process_chat_sse<S>(
 {
     let mut stream = stream.eventsource();
 
-    // State to accumulate a function call across streaming chunks.
-    // OpenAI may split the `arguments` string over multiple `delta` events
-    // until the chunk whose `finish_reason` is `tool_calls` is emitted. We
-    // keep collecting the pieces here and forward a single
-    // `ResponseItem::FunctionCall` once the call is complete.
+    // State to accumulate function calls across streaming chunks.
+    // OpenAI may split each call's `arguments` string over multiple `delta`
+    // events, and with parallel tool calls interleaves several calls told
+    // apart by their `index`, until the chunk whose `finish_reason` is
+    // `tool_calls` is emitted. Argument fragments are forwarded as they
+    // arrive; each `ResponseItem::FunctionCall` is sent once complete.
     #[derive(Default)]
     struct FunctionCallState {
         name: Option<String>,
         arguments: String,
         call_id: Option<String>,
-        active: bool,
     }
 
-    let mut fn_call_state = FunctionCallState::default();
+    let mut fn_calls: BTreeMap<u64, FunctionCallState> = BTreeMap::new();
     let mut assistant_text = String::new();
     let mut reasoning_text = String::new();
     let mut current_item_id: Option<String> = None;
@@ -555,6 +509,21 @@ async fn process_chat_sse<S>(
                         }),
                     );
                 }
+                if !fn_calls.is_empty() {
+                    // A tool call was still streaming its arguments; forwarding
+                    // it would fail later with a confusing JSON parse error.
+                    let detail = "stream closed while a tool call was still streaming";
+                    ResponseAnomaly::Truncated.record(detail, otel_event_manager.as_ref());
+                    let _ = tx_event
+                        .send(Err(
+                            ResponseAnomaly::Truncated.into_error(detail, &request_id)
+                        ))
+                        .await;
+                    if let Ok(logger) = debug_logger.lock() {
+                        let _ = logger.end_request_log(&request_id);
+                    }
+                    return;
+                }
                 let _ = tx_event
                     .send(Ok(ResponseEvent::Completed {
                         response_id: String::new(),
@@ -695,7 +664,12 @@ async fn process_chat_sse<S>(
             // Forward any reasoning/thinking deltas if present.
             // Some providers stream `reasoning` as a plain string while others
             // nest the text under an object (e.g. `{ "reasoning": { "text": "…" } }`).
-            if let Some(reasoning_val) = choice.get("delta").and_then(|d| d.get("reasoning")) {
+            // DeepSeek, vLLM and similar servers use `reasoning_content` instead.
+            if let Some(reasoning_val) = choice.get("delta").and_then(|d| {
+                ["reasoning", "reasoning_content"]
+                    .into_iter()
+                    .find_map(|key| d.get(key).filter(|v| !v.is_null()))
+            }) {
                 let mut maybe_text = reasoning_val
                     .as_str()
                     .map(str::to_string)
@@ -774,25 +748,41 @@ async fn process_chat_sse<S>(
                 .get("delta")
                 .and_then(|d| d.get("tool_calls"))
                 .and_then(|tc| tc.as_array())
-                && let Some(tool_call) = tool_calls.first()
             {
-                // Mark that we have an active function call in progress.
-                fn_call_state.active = true;
-
-                // Extract call_id if present.
-                if let Some(id) = tool_call.get("id").and_then(|v| v.as_str()) {
-                    fn_call_state.call_id.get_or_insert_with(|| id.to_string());
-                }
+                for (position, tool_call) in tool_calls.iter().enumerate() {
+                    // `index` identifies a call across chunks; providers that
+                    // omit it stream one call at a time.
+                    let index = tool_call
+                        .get("index")
+                        .and_then(|v| v.as_u64())
+                        .unwrap_or(position as u64);
+                    let state = fn_calls.entry(index).or_default();
+
+                    // Extract call_id if present.
+                    if let Some(id) = tool_call.get("id").and_then(|v| v.as_str()) {
+                        state.call_id.get_or_insert_with(|| id.to_string());
+                    }
 
-                // Extract function details if present.
-                if let Some(function) = tool_call.get("function") {
+                    // Extract function details if present.
+                    let Some(function) = tool_call.get("function") else {
+                        continue;
+                    };
                     if let Some(name) = function.get("name").and_then(|n| n.as_str()) {
-                        fn_call_state.name.get_or_insert_with(|| name.to_string());
+                        state.name.get_or_insert_with(|| name.to_string());
                     }
-
                     if let Some(args_fragment) = function.get("arguments").and_then(|a| a.as_str())
+                        && !args_fragment.is_empty()
                     {
-                        fn_call_state.arguments.push_str(args_fragment);
+                        state.arguments.push_str(args_fragment);
+                        let _ = tx_event
+                            .send(Ok(ResponseEvent::ToolCallArgumentsDelta {
+                                delta: args_fragment.to_string(),
+                                call_id: state.call_id.clone(),
+                                name: state.name.clone(),
+                                item_id: current_item_id.clone(),
+                                output_index: u32::try_from(index).ok(),
+                            }))
+                            .await;
                     }
                 }
             }
@@ -800,7 +790,7 @@ async fn process_chat_sse<S>(
             // Emit end-of-turn when finish_reason signals completion.
             if let Some(finish_reason) = choice.get("finish_reason").and_then(|v| v.as_str()) {
                 match finish_reason {
-                    "tool_calls" if fn_call_state.active => {
+                    "tool_calls" if !fn_calls.is_empty() => {
                         // First, flush the terminal raw reasoning so UIs can finalize
                         // the reasoning stream before any exec/tool events begin.
                         if !reasoning_text.is_empty() {
@@ -821,21 +811,23 @@ async fn process_chat_sse<S>(
                                 .await;
                         }
 
-                        // Then emit the FunctionCall response item.
-                        let item = ResponseItem::FunctionCall {
-                            id: current_item_id.clone(),
-                            name: fn_call_state.name.clone().unwrap_or_else(|| "".to_string()),
-                            arguments: fn_call_state.arguments.clone(),
-                            call_id: fn_call_state.call_id.clone().unwrap_or_else(String::new),
-                        };
+                        // Then emit the FunctionCall response items in call order.
+                        for (index, state) in std::mem::take(&mut fn_calls) {
+                            let item = ResponseItem::FunctionCall {
+                                id: current_item_id.clone(),
+                                name: state.name.unwrap_or_default(),
+                                arguments: state.arguments,
+                                call_id: state.call_id.unwrap_or_default(),
+                            };
 
-                        let _ = tx_event
-                            .send(Ok(ResponseEvent::OutputItemDone {
-                                item,
-                                sequence_number: None,
-                                output_index: None,
-                            }))
-                            .await;
+                            let _ = tx_event
+                                .send(Ok(ResponseEvent::OutputItemDone {
+                                    item,
+                                    sequence_number: None,
+                                    output_index: u32::try_from(index).ok(),
+                                }))
+                                .await;
+                        }
                     }
                     "stop" => {
                         // Regular turn without tool-call. Emit the final assistant message
@@ -886,9 +878,6 @@ async fn process_chat_sse<S>(
                     }))
                     .await;
 
-                // Prepare for potential next turn (should not happen in same stream).
-                // fn_call_state = FunctionCallState::default();
-
                 // Mark the request log as complete
                 if let Ok(logger) = debug_logger.lock() {
                     let _ = logger.end_request_log(&request_id);
@@ -952,7 +941,7 @@ where
                 Poll::Ready(Some(Ok(ResponseEvent::OutputItemDone {
                     item,
                     sequence_number: _,
-                    ..
+                    output_index,
                 }))) => {
                     // If this is an incremental assistant message chunk, accumulate but
                     // do NOT emit yet. Forward any other item (e.g. FunctionCall) right
@@ -991,12 +980,15 @@ where
                     return Poll::Ready(Some(Ok(ResponseEvent::OutputItemDone {
                         item,
                         sequence_number: None,
-                        output_index: None,
+                        output_index,
                     })));
                 }
                 Poll::Ready(Some(Ok(ResponseEvent::RateLimits(snapshot)))) => {
                     return Poll::Ready(Some(Ok(ResponseEvent::RateLimits(snapshot))));
                 }
+                Poll::Ready(Some(Ok(event @ ResponseEvent::Latency(_)))) => {
+                    return Poll::Ready(Some(Ok(event)));
+                }
                 Poll::Ready(Some(Ok(ResponseEvent::Completed {
                     response_id,
                     token_usage,
@@ -1122,6 +1114,11 @@ where
                 Poll::Ready(Some(Ok(ResponseEvent::ReasoningSummaryPartAdded))) => {
                     continue;
                 }
+                Poll::Ready(Some(Ok(event @ ResponseEvent::ToolCallArgumentsDelta { .. }))) => {
+                    // Forwarded in both modes: callers only see these if they
+                    // look for them, and the complete call follows anyway.
+                    return Poll::Ready(Some(Ok(event)));
+                }
                 Poll::Ready(Some(Ok(ResponseEvent::WebSearchCallBegin { call_id }))) => {
                     return Poll::Ready(Some(Ok(ResponseEvent::WebSearchCallBegin { call_id })));
                 }
@@ -1181,7 +1178,7 @@ impl<S> AggregatedChatStream<S> {
     }
 }
 
-fn header_map_to_json(headers: &HeaderMap) -> serde_json::Value {
+pub(crate) fn header_map_to_json(headers: &HeaderMap) -> serde_json::Value {
     let mut ordered: BTreeMap<String, Vec<String>> = BTreeMap::new();
     for (name, value) in headers.iter() {
         let entry = ordered.entry(name.as_str().to_string()).or_default();
@@ -1190,3 +1187,123 @@ fn header_map_to_json(headers: &HeaderMap) -> serde_json::Value {
 
     serde_json::to_value(ordered).unwrap_or(serde_json::Value::Null)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tokio_util::io::ReaderStream;
+
+    async fn run_chat_sse(chunks: &[serde_json::Value]) -> Vec<ResponseEvent> {
+        let mut body = String::new();
+        for chunk in chunks {
+            body.push_str(&format!("data: {chunk}\n\n"));
+        }
+        body.push_str("data: [DONE]\n\n");
+
+        let (tx, mut rx) = mpsc::channel::<Result<ResponseEvent>>(SSE_CHANNEL_CAPACITY);
+        let stream = ReaderStream::new(std::io::Cursor::new(body)).map_err(CodexErr::Io);
+        let debug_logger = Arc::new(Mutex::new(DebugLogger::new(false).unwrap()));
+        tokio::spawn(process_chat_sse(
+            stream,
+            tx,
+            Duration::from_secs(5),
+            debug_logger,
+            String::new(),
+            None,
+        ));
+
+        let mut events = Vec::new();
+        while let Some(event) = rx.recv().await {
+            events.push(event.expect("stream error"));
+        }
+        events
+    }
+
+    #[tokio::test]
+    async fn streams_parallel_tool_call_arguments() {
+        let events = run_chat_sse(&[
+            json!({"choices": [{"delta": {"tool_calls": [
+                {"index": 0, "id": "call_a", "function": {"name": "shell", "arguments": "{\"cmd\":"}},
+                {"index": 1, "id": "call_b", "function": {"name": "read", "arguments": ""}}
+            ]}}]}),
+            json!({"choices": [{"delta": {"tool_calls": [
+                {"index": 1, "function": {"arguments": "{\"path\":\"a\"}"}}
+            ]}}]}),
+            json!({"choices": [{"delta": {"tool_calls": [
+                {"index": 0, "function": {"arguments": "\"ls\"}"}}
+            ]}}]}),
+            json!({"choices": [{"delta": {}, "finish_reason": "tool_calls"}]}),
+        ])
+        .await;
+
+        let deltas: Vec<(Option<String>, String)> = events
+            .iter()
+            .filter_map(|event| match event {
+                ResponseEvent::ToolCallArgumentsDelta { call_id, delta, .. } => {
+                    Some((call_id.clone(), delta.clone()))
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            deltas,
+            vec![
+                (Some("call_a".to_string()), "{\"cmd\":".to_string()),
+                (Some("call_b".to_string()), "{\"path\":\"a\"}".to_string()),
+                (Some("call_a".to_string()), "\"ls\"}".to_string()),
+            ]
+        );
+
+        let calls: Vec<(String, String, String)> = events
+            .iter()
+            .filter_map(|event| match event {
+                ResponseEvent::OutputItemDone {
+                    item:
+                        ResponseItem::FunctionCall {
+                            name,
+                            arguments,
+                            call_id,
+                            ..
+                        },
+                    ..
+                } => Some((call_id.clone(), name.clone(), arguments.clone())),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            calls,
+            vec![
+                (
+                    "call_a".to_string(),
+                    "shell".to_string(),
+                    "{\"cmd\":\"ls\"}".to_string()
+                ),
+                (
+                    "call_b".to_string(),
+                    "read".to_string(),
+                    "{\"path\":\"a\"}".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn forwards_reasoning_content_deltas() {
+        let events = run_chat_sse(&[
+            json!({"choices": [{"delta": {"content": null, "reasoning_content": "thinking"}}]}),
+            json!({"choices": [{"delta": {"content": "done", "reasoning_content": null}}]}),
+            json!({"choices": [{"delta": {}, "finish_reason": "stop"}]}),
+        ])
+        .await;
+
+        assert!(events.iter().any(|event| matches!(
+            event,
+            ResponseEvent::ReasoningContentDelta { delta, .. } if delta == "thinking"
+        )));
+        assert!(events.iter().any(|event| matches!(
+            event,
+            ResponseEvent::OutputTextDelta { delta, .. } if delta == "done"
+        )));
+    }
+}
@@ -1,4 +1,5 @@
 use std::collections::BTreeMap;
+use std::collections::HashSet;
 use std::io::BufRead;
 use std::path::Path;
 use std::sync::OnceLock;
@@ -17,6 +18,7 @@ use eventsource_stream::Eventsource;
 use futures::prelude::*;
 use httpdate::parse_http_date;
 use regex_lite::Regex;
+use reqwest::Method;
 use reqwest::StatusCode;
 use reqwest::header::HeaderMap;
 use serde::Deserialize;
@@ -34,12 +36,21 @@ const AUTH_REQUIRED_MESSAGE: &str = "Authentication required. Run `code login` t
 
 use crate::agent_defaults::default_agent_configs;
 use crate::agent_defaults::enabled_agent_model_specs;
+use crate::anthropic_messages::stream_anthropic_messages;
+use crate::auth::CodexAuth;
+use crate::batch;
+use crate::batch::BatchJob;
+use crate::batch::BatchPoll;
+use crate::batch::BatchRequest;
+use crate::batch::BatchStatus;
+use crate::bedrock_converse::stream_bedrock_converse;
 use crate::chat_completions::AggregateStreamExt;
 use crate::chat_completions::stream_chat_completions;
 use crate::client_common::Prompt;
 use crate::client_common::ResponseEvent;
 use crate::client_common::ResponseStream;
 use crate::client_common::ResponsesApiRequest;
+use crate::client_common::ServedBy;
 use crate::client_common::create_reasoning_param_for_request;
 use crate::config::Config;
 use crate::config_types::ReasoningEffort as ReasoningEffortConfig;
@@ -47,14 +58,15 @@ use crate::config_types::ReasoningSummary as ReasoningSummaryConfig;
 use crate::config_types::TextVerbosity as TextVerbosityConfig;
 use crate::config_types::UiLocale;
 use crate::debug_logger::DebugLogger;
-use crate::default_client::create_client;
+use crate::default_client::create_client_for_provider;
 use crate::error::CodexErr;
 use crate::error::Result;
 use crate::error::RetryAfter;
-use crate::error::RetryLimitReachedError;
 use crate::error::UnexpectedResponseError;
 use crate::error::UsageLimitReachedError;
 use crate::flags::CODEX_RS_SSE_FIXTURE;
+use crate::gemini::stream_gemini;
+use crate::mock_provider::stream_mock;
 use crate::model_family::ModelFamily;
 use crate::model_family::find_family_for_model;
 use crate::model_provider_info::ModelProviderInfo;
@@ -63,11 +75,30 @@ use crate::openai_model_info::get_model_info;
 use crate::openai_tools::ConfigShellToolType;
 use crate::openai_tools::ToolsConfig;
 use crate::openai_tools::create_tools_json_for_responses_api;
+use crate::prompt_cache;
 use crate::protocol::RateLimitSnapshotEvent;
 use crate::protocol::SandboxPolicy;
 use crate::protocol::TokenUsage;
+use crate::provider_health;
+use crate::quota_ledger;
 use crate::reasoning::clamp_reasoning_effort_for_model;
+use crate::request_latency;
+use crate::request_limits;
+use crate::request_middleware::MiddlewareStack;
+use crate::request_middleware::RequestMiddleware;
+use crate::request_retry::Attempt;
+use crate::request_retry::Rejected;
+use crate::request_retry::Rejection;
+use crate::request_retry::is_retryable_status;
+use crate::request_retry::send_with_retries;
+use crate::request_retry::unexpected_status;
+use crate::response_anomaly::AnomalyDetector;
+use crate::response_anomaly::ResponseAnomaly;
+use crate::response_cache::ResponseCache;
 use crate::slash_commands::get_enabled_agents;
+use crate::sse_buffer::BoundedEventSender;
+use crate::sse_buffer::SSE_CHANNEL_CAPACITY;
+use crate::sse_buffer::delta_fingerprint;
 use crate::util::backoff;
 use code_otel::otel_event_manager::OtelEventManager;
 use code_otel::otel_event_manager::TurnLatencyPayload;
@@ -82,6 +113,11 @@ const RESPONSES_BETA_HEADER_EXPERIMENTAL: &str = "responses=experimental";
 struct StreamCheckpoint {
     /// Highest sequence_number observed across attempts. Used to drop replayed deltas.
     last_sequence: Option<u64>,
+    /// Id from `response.created`, needed to resume the stream after a disconnect.
+    response_id: Option<String>,
+    /// `output_index` of every item already forwarded, so a resumed stream
+    /// that replays finished items does not deliver them twice.
+    delivered_outputs: HashSet<u32>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -219,6 +255,7 @@ pub struct ModelClient {
     reasoning_summary_disabled: AtomicBool,
     verbosity: TextVerbosityConfig,
     debug_logger: Arc<Mutex<DebugLogger>>,
+    middleware: MiddlewareStack,
 }
 
 impl Clone for ModelClient {
@@ -237,6 +274,7 @@ impl Clone for ModelClient {
             ),
             verbosity: self.verbosity,
             debug_logger: Arc::clone(&self.debug_logger),
+            middleware: self.middleware.clone(),
         }
     }
 }
@@ -255,7 +293,7 @@ impl ModelClient {
     ) -> Self {
         let effective_verbosity = clamp_text_verbosity_for_model(config.model.as_str(), verbosity);
         let clamped_effort = clamp_reasoning_effort_for_model(config.model.as_str(), effort);
-        let client = create_client(&config.responses_originator_header);
+        let client = create_client_for_provider(&config.responses_originator_header, &provider);
 
         Self {
             config,
@@ -269,9 +307,17 @@ impl ModelClient {
             reasoning_summary_disabled: AtomicBool::new(false),
             verbosity: effective_verbosity,
             debug_logger,
+            middleware: MiddlewareStack::registered(),
         }
     }
 
+    /// Adds `middleware` after any registered process-wide, so it runs on
+    /// every request this client (and its clones) sends.
+    pub fn with_middleware(mut self, middleware: Arc<dyn RequestMiddleware>) -> Self {
+        self.middleware.push(middleware);
+        self
+    }
+
     /// Get the reasoning effort configuration
     pub fn get_reasoning_effort(&self) -> ReasoningEffortConfig {
         self.effort
@@ -407,10 +453,153 @@ impl ModelClient {
         &self.config.model_family
     }
 
-    /// Dispatches to either the Responses or Chat implementation depending on
-    /// the provider config.  Public callers always invoke `stream()` – the
-    /// specialised helpers are private to avoid accidental misuse.
+    /// Streams `prompt` from the provider, or from its `fallback_provider`
+    /// while the provider's circuit breaker is open. Public callers always
+    /// invoke `stream()` – the specialised helpers are private to avoid
+    /// accidental misuse.
+    ///
+    /// Failover to the profile's next `model_providers` entry only happens
+    /// while opening the stream. Once a provider has accepted the request, a
+    /// failure mid-stream is returned from the stream as usual; the turn's
+    /// retry loop then calls `stream()` again, which starts over from the
+    /// first provider. Output already streamed is never resumed on another
+    /// provider, since response ids and sequence numbers are per provider.
     pub async fn stream(&self, prompt: &Prompt) -> Result<ResponseStream> {
+        // `[sampling]` from config fills what the prompt leaves unset.
+        let sampling = prompt.sampling.or(self.config.sampling);
+        let with_sampling;
+        let prompt = if sampling == prompt.sampling {
+            prompt
+        } else {
+            with_sampling = Prompt {
+                sampling,
+                ..prompt.clone()
+            };
+            &with_sampling
+        };
+        let mut result = self.stream_with_fallback(prompt).await;
+        // Only the session provider fails over; clients bound to another
+        // provider (agents, review models) keep to it.
+        if self.provider != self.config.model_provider {
+            return result;
+        }
+        for failover in &self.config.model_provider_failover {
+            let err = match result {
+                Err(err) if should_fail_over(&err) => err,
+                other => return other,
+            };
+            warn!("{err}; failing over to provider {}", failover.id);
+            let mut prompt = prompt.clone();
+            if let Some(model) = &failover.model {
+                prompt.model_override = Some(model.clone());
+                prompt.model_family_override = find_family_for_model(model);
+            }
+            result = self
+                .with_provider(&failover.provider)
+                .stream_with_fallback(&prompt)
+                .await;
+        }
+        result
+    }
+
+    /// Streams from this client's provider, or from its `fallback_provider`
+    /// while the provider's circuit is open.
+    async fn stream_with_fallback(&self, prompt: &Prompt) -> Result<ResponseStream> {
+        let endpoint = self.health_endpoint();
+        if let Err(err) = provider_health::global().check(&self.provider.name, &endpoint) {
+            let Some(fallback) = self.fallback_client() else {
+                return Err(err);
+            };
+            let fallback_endpoint = fallback.health_endpoint();
+            if provider_health::global()
+                .check(&fallback.provider.name, &fallback_endpoint)
+                .is_err()
+            {
+                return Err(err);
+            }
+            warn!("{err}; using fallback provider {}", fallback.provider.name);
+            return fallback.stream_tracked(prompt, fallback_endpoint).await;
+        }
+        self.stream_tracked(prompt, endpoint).await
+    }
+
+    /// Endpoint under which this client's requests are tracked in the
+    /// provider health registry.
+    fn health_endpoint(&self) -> String {
+        self.provider
+            .get_full_url(&self.auth_manager.as_ref().and_then(|m| m.auth()))
+    }
+
+    /// A copy of this client bound to the provider's `fallback_provider`.
+    fn fallback_client(&self) -> Option<ModelClient> {
+        let id = self.provider.fallback_provider.as_deref()?;
+        let Some(provider) = self.config.model_providers.get(id) else {
+            warn!("fallback_provider `{id}` is not defined in model_providers");
+            return None;
+        };
+        Some(self.with_provider(provider))
+    }
+
+    /// A copy of this client that sends requests to `provider`.
+    fn with_provider(&self, provider: &ModelProviderInfo) -> ModelClient {
+        let mut client = self.clone();
+        client.client =
+            create_client_for_provider(&self.config.responses_originator_header, provider);
+        client.provider = provider.clone();
+        client
+    }
+
+    /// The `model_providers` key of this client's provider, or its display
+    /// name when it is not in the table.
+    fn provider_id(&self) -> String {
+        if self.provider == self.config.model_provider {
+            return self.config.model_provider_id.clone();
+        }
+        self.config
+            .model_providers
+            .iter()
+            .find(|(_, provider)| **provider == self.provider)
+            .map(|(id, _)| id.clone())
+            .unwrap_or_else(|| self.provider.name.clone())
+    }
+
+    /// Runs [`Self::dispatch_stream`], records the outcome against
+    /// `endpoint`, and times the request.
+    async fn stream_tracked(&self, prompt: &Prompt, endpoint: String) -> Result<ResponseStream> {
+        let started = std::time::Instant::now();
+        match self.dispatch_stream(prompt).await {
+            Ok(stream) => {
+                let served_by = ServedBy {
+                    provider_id: self.provider_id(),
+                    model: prompt
+                        .model_override
+                        .clone()
+                        .unwrap_or_else(|| self.config.model.clone()),
+                };
+                let stream = request_latency::track(
+                    stream,
+                    started,
+                    served_by.clone(),
+                    self.config.turn_latency_events,
+                    self.otel_event_manager.clone(),
+                );
+                let stream = self.middleware.observe(stream);
+                let mut stream =
+                    provider_health::watch(self.provider.name.clone(), endpoint, started, stream);
+                stream.served_by = Some(served_by);
+                Ok(stream)
+            }
+            Err(err) => {
+                provider_health::global().record_error(&self.provider.name, &endpoint, &err);
+                Err(err)
+            }
+        }
+    }
+
+    /// Dispatches to the Responses, Chat, Anthropic Messages, Gemini,
+    /// Bedrock Converse, or mock implementation depending on the provider
+    /// config.
+    async fn dispatch_stream(&self, prompt: &Prompt) -> Result<ResponseStream> {
         let log_tag = prompt.log_tag.as_deref();
         match self.provider.wire_api {
             WireApi::Responses => self.stream_responses(prompt, log_tag).await,
@@ -433,10 +622,14 @@ impl ModelClient {
                     &self.debug_logger,
                     self.auth_manager.clone(),
                     self.otel_event_manager.clone(),
+                    &self.middleware,
+                    self.config.request_tap.as_ref(),
                     log_tag,
                 )
                 .await?;
 
+                let retries = response_stream.retries;
+
                 // Wrap it with the aggregation adapter so callers see *only*
                 // the final assistant message per turn (matching the
                 // behaviour of the Responses API).
@@ -460,7 +653,97 @@ impl ModelClient {
                     }
                 });
 
-                Ok(ResponseStream { rx_event: rx })
+                Ok(ResponseStream {
+                    rx_event: rx,
+                    served_by: None,
+                    retries,
+                })
+            }
+            WireApi::AnthropicMessages => {
+                let effective_family = prompt
+                    .model_family_override
+                    .as_ref()
+                    .unwrap_or(&self.config.model_family);
+                let model_slug = prompt
+                    .model_override
+                    .as_deref()
+                    .unwrap_or(self.config.model.as_str());
+                stream_anthropic_messages(
+                    prompt,
+                    effective_family,
+                    model_slug,
+                    &self.client,
+                    &self.provider,
+                    &self.debug_logger,
+                    self.auth_manager.clone(),
+                    self.otel_event_manager.clone(),
+                    &self.middleware,
+                    self.config.request_tap.as_ref(),
+                    log_tag,
+                )
+                .await
+            }
+            WireApi::Gemini => {
+                let effective_family = prompt
+                    .model_family_override
+                    .as_ref()
+                    .unwrap_or(&self.config.model_family);
+                let model_slug = prompt
+                    .model_override
+                    .as_deref()
+                    .unwrap_or(self.config.model.as_str());
+                stream_gemini(
+                    prompt,
+                    effective_family,
+                    model_slug,
+                    &self.client,
+                    &self.provider,
+                    &self.debug_logger,
+                    self.auth_manager.clone(),
+                    self.otel_event_manager.clone(),
+                    &self.middleware,
+                    self.config.request_tap.as_ref(),
+                    log_tag,
+                )
+                .await
+            }
+            WireApi::BedrockConverse => {
+                let effective_family = prompt
+                    .model_family_override
+                    .as_ref()
+                    .unwrap_or(&self.config.model_family);
+                let model_slug = prompt
+                    .model_override
+                    .as_deref()
+                    .unwrap_or(self.config.model.as_str());
+                stream_bedrock_converse(
+                    prompt,
+                    effective_family,
+                    model_slug,
+                    &self.client,
+                    &self.provider,
+                    &self.debug_logger,
+                    self.otel_event_manager.clone(),
+                    &self.middleware,
+                    self.config.request_tap.as_ref(),
+                    log_tag,
+                )
+                .await
+            }
+            WireApi::Mock => {
+                let model_slug = prompt
+                    .model_override
+                    .as_deref()
+                    .unwrap_or(self.config.model.as_str());
+                stream_mock(
+                    prompt,
+                    model_slug,
+                    &self.provider,
+                    &self.config.code_home,
+                    &self.config.cwd,
+                    self.otel_event_manager.clone(),
+                )
+                .await
             }
         }
     }
@@ -564,18 +847,24 @@ impl ModelClient {
         let session_id = prompt.session_id_override.unwrap_or(self.session_id);
         let session_id_str = session_id.to_string();
 
-        let mut attempt = 0;
-        let max_retries = self.provider.request_max_retries();
-        let mut request_id = String::new();
-
         // Compute endpoint with the latest available auth (may be None at this point).
         let endpoint = self
             .provider
             .get_full_url(&auth_manager.as_ref().and_then(|m| m.auth()));
 
-        loop {
-            attempt += 1;
+        // Chosen by `prompt_cache_key` in config. With store=false this is inert.
+        let prompt_cache_key = prompt_cache::prompt_cache_key(
+            self.config.prompt_cache_key,
+            session_id,
+            &self.config.cwd,
+        );
+        let response_cache = ResponseCache::from_config(&self.config);
+        let estimate =
+            quota_ledger::estimate_tokens(model_slug, prompt, &self.config.model_family).await;
 
+        // Builds the request body and middleware headers. Reasoning summaries
+        // are left out once the provider has rejected them.
+        let build_payload = || -> Result<(Value, HeaderMap)> {
             let reasoning = self.current_reasoning_param(&request_family, effective_effort);
             // Request encrypted COT if we are not storing responses,
             // otherwise reasoning items will be referenced by ID
@@ -599,8 +888,13 @@ impl ModelClient {
                 store: azure_workaround,
                 stream: true,
                 include,
-                // Use a stable per-process cache key (session id). With store=false this is inert.
-                prompt_cache_key: Some(session_id_str.clone()),
+                prompt_cache_key: prompt_cache_key.clone(),
+                seed: prompt
+                    .sampling
+                    .seed
+                    .filter(|_| self.provider.accepts_responses_seed()),
+                temperature: prompt.sampling.temperature,
+                top_p: prompt.sampling.top_p,
             };
 
             let mut payload_json = serde_json::to_value(&payload)?;
@@ -623,370 +917,390 @@ impl ModelClient {
                     obj.entry(key.clone()).or_insert(value.clone());
                 }
             }
+            let extra_headers = self.middleware.prepare(&endpoint, &mut payload_json);
             let payload_body = serde_json::to_string(&payload_json)?;
+            request_limits::check(
+                self.config.request_limits,
+                payload_body.len(),
+                &input_with_instructions,
+            )?;
+            trace!("POST to {endpoint}: {payload_body}");
+
+            if let Some(tap) = self.config.request_tap.as_ref() {
+                tap.observe(&endpoint, &extra_headers, &payload_json)?;
+            }
+            Ok((payload_json, extra_headers))
+        };
 
-            let mut auth_refresh_error: Option<RefreshTokenError> = None;
-
-            // Always fetch the latest auth in case a prior attempt refreshed the token.
-            let auth = auth_manager.as_ref().and_then(|m| m.auth());
-
-            trace!(
-                "POST to {}: {}",
-                self.provider.get_full_url(&auth),
-                payload_body.as_str()
-            );
-
-            let mut req_builder = self
-                .provider
-                .create_request_builder(&self.client, &auth)
-                .await?;
-
-            let has_beta_header = req_builder
-                .try_clone()
-                .and_then(|builder| builder.build().ok())
-                .is_some_and(|req| req.headers().contains_key("OpenAI-Beta"));
+        let (payload_json, extra_headers) = build_payload()?;
+        let mut cache_key = None;
+        if let Some(cache) = response_cache.as_ref() {
+            let key = ResponseCache::key(&endpoint, &payload_json);
+            if let Some(body) = cache.lookup(&key) {
+                debug!("replaying cached response {key}");
+                let (tx_event, rx_event) =
+                    mpsc::channel::<Result<ResponseEvent>>(SSE_CHANNEL_CAPACITY);
+                tokio::spawn(process_sse(
+                    futures::stream::iter([Ok(body)]),
+                    tx_event,
+                    self.provider.stream_idle_timeout(),
+                    false,
+                    Arc::clone(&self.debug_logger),
+                    String::new(),
+                    self.otel_event_manager.clone(),
+                    Arc::new(RwLock::new(StreamCheckpoint::default())),
+                ));
+                return Ok(ResponseStream {
+                    rx_event,
+                    served_by: None,
+                    retries: 0,
+                });
+            }
+            cache_key = Some(key);
+        }
 
-            if !has_beta_header {
-                let beta_value = if self.provider.is_public_openai_responses_endpoint() {
-                    RESPONSES_BETA_HEADER_V1
+        let mut prepared = Arc::new((payload_json, extra_headers));
+        let mut summary_disabled = self.reasoning_summary_disabled.load(Ordering::Relaxed);
+        let delivered = send_with_retries(
+            &self.provider,
+            &self.debug_logger,
+            self.otel_event_manager.as_ref(),
+            log_tag,
+            estimate,
+            || {
+                // Rebuild without reasoning summaries after a rejection restarted
+                // the request.
+                let rebuilt = if summary_disabled
+                    != self.reasoning_summary_disabled.load(Ordering::Relaxed)
+                {
+                    summary_disabled = !summary_disabled;
+                    build_payload().map(|(payload_json, extra_headers)| {
+                        cache_key = response_cache
+                            .as_ref()
+                            .map(|_| ResponseCache::key(&endpoint, &payload_json));
+                        prepared = Arc::new((payload_json, extra_headers));
+                    })
                 } else {
-                    RESPONSES_BETA_HEADER_EXPERIMENTAL
+                    Ok(())
                 };
-                req_builder = req_builder.header("OpenAI-Beta", beta_value);
-            }
-
-            req_builder = req_builder
-                // Send `conversation_id`/`session_id` so the server can hit the prompt-cache.
-                .header("conversation_id", session_id_str.clone())
-                .header("session_id", session_id_str.clone())
-                .header(reqwest::header::ACCEPT, "text/event-stream")
-                .json(&payload_json);
-
-            if let Some(auth) = auth.as_ref()
-                && auth.mode == AuthMode::ChatGPT
-                && let Some(account_id) = auth.get_account_id()
-            {
-                req_builder = req_builder.header("chatgpt-account-id", account_id);
-            }
-
-            if request_id.is_empty() {
-                let endpoint_for_log = self.provider.get_full_url(&auth);
-                let header_snapshot = req_builder
-                    .try_clone()
-                    .and_then(|builder| builder.build().ok())
-                    .map(|req| header_map_to_json(req.headers()));
-
-                if let Ok(logger) = self.debug_logger.lock() {
-                    request_id = logger
-                        .start_request_log(
-                            &endpoint_for_log,
-                            &payload_json,
-                            header_snapshot.as_ref(),
-                            log_tag,
-                        )
-                        .unwrap_or_default();
-                }
-            }
+                let prepared = Arc::clone(&prepared);
+                // Always fetch the latest auth in case a prior attempt refreshed the token.
+                let auth = auth_manager.as_ref().and_then(|m| m.auth());
+                let session_id_str = &session_id_str;
+                async move {
+                    rebuilt?;
+                    let (payload_json, extra_headers) = &*prepared;
+                    let mut req_builder = self
+                        .provider
+                        .create_request_builder(&self.client, &auth)
+                        .await?;
+
+                    let has_beta_header = req_builder
+                        .try_clone()
+                        .and_then(|builder| builder.build().ok())
+                        .is_some_and(|req| req.headers().contains_key("OpenAI-Beta"));
+
+                    if !has_beta_header {
+                        let beta_value = if self.provider.is_public_openai_responses_endpoint() {
+                            RESPONSES_BETA_HEADER_V1
+                        } else {
+                            RESPONSES_BETA_HEADER_EXPERIMENTAL
+                        };
+                        req_builder = req_builder.header("OpenAI-Beta", beta_value);
+                    }
 
-            let res = if let Some(otel) = self.otel_event_manager.as_ref() {
-                otel.log_request(attempt, || req_builder.send()).await
-            } else {
-                req_builder.send().await
-            };
-            if let Ok(resp) = &res {
-                trace!(
-                    "Response status: {}, request-id: {}",
-                    resp.status(),
-                    resp.headers()
-                        .get("x-request-id")
-                        .map(|v| v.to_str().unwrap_or_default())
-                        .unwrap_or_default()
-                );
-            }
+                    req_builder = req_builder
+                        // Send `conversation_id`/`session_id` so the server can hit the prompt-cache.
+                        .header("conversation_id", session_id_str.clone())
+                        .header("session_id", session_id_str.clone())
+                        .header(reqwest::header::ACCEPT, "text/event-stream")
+                        .json(payload_json);
 
-            match res {
-                Ok(resp) if resp.status().is_success() => {
-                    // Log successful response initiation
-                    if let Ok(logger) = self.debug_logger.lock() {
-                        let _ = logger.append_response_event(
-                            &request_id,
-                            "stream_initiated",
-                            &serde_json::json!({
-                                "status": "success",
-                                "status_code": resp.status().as_u16(),
-                                "x_request_id": resp.headers()
-                                    .get("x-request-id")
-                                    .and_then(|v| v.to_str().ok())
-                                    .unwrap_or_default()
-                            }),
-                        );
-                    }
-                    let (tx_event, rx_event) = mpsc::channel::<Result<ResponseEvent>>(1600);
-
-                    if let Some(snapshot) = parse_rate_limit_snapshot(resp.headers()) {
-                        debug!(
-                            "rate limit headers:\n{}",
-                            format_rate_limit_headers(resp.headers())
-                        );
-
-                        if tx_event
-                            .send(Ok(ResponseEvent::RateLimits(snapshot)))
-                            .await
-                            .is_err()
-                        {
-                            debug!("receiver dropped rate limit snapshot event");
-                        }
+                    if let Some(auth) = auth.as_ref()
+                        && auth.mode == AuthMode::ChatGPT
+                        && let Some(account_id) = auth.get_account_id()
+                    {
+                        req_builder = req_builder.header("chatgpt-account-id", account_id);
                     }
+                    Ok(Attempt {
+                        request: req_builder.headers(extra_headers.clone()),
+                        auth,
+                    })
+                }
+            },
+            |rejection: Rejection| {
+                let auth_manager = auth_manager.as_ref();
+                async move { self.classify_rejection(rejection, auth_manager).await }
+            },
+        )
+        .await?;
+
+        let resp = delivered.response;
+        trace!(
+            "Response status: {}, request-id: {}",
+            resp.status(),
+            resp.headers()
+                .get("x-request-id")
+                .map(|v| v.to_str().unwrap_or_default())
+                .unwrap_or_default()
+        );
+        let (tx_event, rx_event) = mpsc::channel::<Result<ResponseEvent>>(SSE_CHANNEL_CAPACITY);
 
-                    // spawn task to process SSE
-                    let stream = resp.bytes_stream().map_err(CodexErr::Reqwest);
-                    let debug_logger = Arc::clone(&self.debug_logger);
-                    let request_id_clone = request_id.clone();
-                    let otel_event_manager = self.otel_event_manager.clone();
-                    tokio::spawn(process_sse(
-                        stream,
-                        tx_event,
-                        self.provider.stream_idle_timeout(),
-                        debug_logger,
-                        request_id_clone,
-                        otel_event_manager,
-                        Arc::new(RwLock::new(StreamCheckpoint::default())),
-                    ));
+        if let Some(snapshot) = parse_rate_limit_snapshot(resp.headers()) {
+            debug!(
+                "rate limit headers:\n{}",
+                format_rate_limit_headers(resp.headers())
+            );
 
-                    return Ok(ResponseStream { rx_event });
-                }
-                Ok(res) => {
-                    let status = res.status();
-                    // Capture x-request-id up-front in case we consume the response body later.
-                    let x_request_id = res
-                        .headers()
-                        .get("x-request-id")
-                        .and_then(|v| v.to_str().ok())
-                        .map(std::string::ToString::to_string);
-                    let now = Utc::now();
-
-                    // Pull out Retry‑After header if present.
-                    let retry_after_hint = res
-                        .headers()
-                        .get(reqwest::header::RETRY_AFTER)
-                        .and_then(|v| v.to_str().ok())
-                        .and_then(|raw| parse_retry_after_header(raw, now));
-
-                    if status == StatusCode::UNAUTHORIZED {
-                        if let Some(manager) = auth_manager.as_ref() {
-                            match manager.refresh_token_classified().await {
-                                Ok(Some(_)) => {}
-                                Ok(None) => {
-                                    auth_refresh_error =
-                                        Some(RefreshTokenError::permanent(AUTH_REQUIRED_MESSAGE));
-                                }
-                                Err(err) => {
-                                    auth_refresh_error = Some(err);
-                                }
-                            }
-                        } else {
-                            auth_refresh_error = Some(RefreshTokenError::permanent(
-                                "Authentication manager unavailable; please log in again.",
-                            ));
-                        }
-                    }
+            if tx_event
+                .send(Ok(ResponseEvent::RateLimits(snapshot)))
+                .await
+                .is_err()
+            {
+                debug!("receiver dropped rate limit snapshot event");
+            }
+        }
 
-                    // Read the response body once for diagnostics across error branches.
-                    let body_text = res.text().await.unwrap_or_default();
-                    let body = serde_json::from_str::<ErrorResponse>(&body_text).ok();
+        // spawn task to process SSE
+        let stream = resp.bytes_stream().map_err(CodexErr::Reqwest);
+        let stream = match (response_cache.as_ref(), cache_key.as_deref()) {
+            (Some(cache), Some(key)) => cache.record(key, stream),
+            _ => stream.boxed(),
+        };
+        let debug_logger = Arc::clone(&self.debug_logger);
+        let otel_event_manager = self.otel_event_manager.clone();
+        if self.provider.supports_stream_resume() {
+            tokio::spawn(process_resumable_sse(
+                stream,
+                tx_event,
+                self.provider.stream_idle_timeout(),
+                self.provider.retry_degenerate_responses,
+                debug_logger,
+                delivered.request_id,
+                otel_event_manager,
+                StreamResumer {
+                    client: self.client.clone(),
+                    provider: self.provider.clone(),
+                    auth_manager,
+                },
+            ));
+        } else {
+            tokio::spawn(process_sse(
+                stream,
+                tx_event,
+                self.provider.stream_idle_timeout(),
+                self.provider.retry_degenerate_responses,
+                debug_logger,
+                delivered.request_id,
+                otel_event_manager,
+                Arc::new(RwLock::new(StreamCheckpoint::default())),
+            ));
+        }
 
-                    if status == StatusCode::BAD_REQUEST
-                        && let Some(ErrorResponse { ref error }) = body
-                        && !self.reasoning_summary_disabled.load(Ordering::Relaxed)
-                        && is_reasoning_summary_rejected(error)
-                    {
-                        self.disable_reasoning_summary();
-
-                        if let Ok(logger) = self.debug_logger.lock() {
-                            let _ = logger.append_response_event(
-                                &request_id,
-                                "reasoning_summary_disabled",
-                                &serde_json::json!({
-                                    "status": status.as_u16(),
-                                    "message": error.message.clone(),
-                                    "code": error.code.clone(),
-                                    "param": error.param.clone(),
-                                }),
-                            );
-                        }
+        Ok(ResponseStream {
+            rx_event,
+            served_by: None,
+            retries: delivered.retries,
+        })
+    }
 
-                        // Retry immediately with reasoning summaries removed.
-                        attempt = 0;
-                        continue;
-                    }
+    /// Decides what a rejected Responses API request turns into: a token
+    /// refresh and retry after a 401, a restart without reasoning summaries
+    /// when the provider rejects them, or one of the quota and usage errors.
+    async fn classify_rejection(
+        &self,
+        rejection: Rejection,
+        auth_manager: Option<&Arc<AuthManager>>,
+    ) -> Rejected {
+        let Rejection {
+            response: res,
+            attempt,
+            is_last,
+            request_id,
+            auth,
+            quota_key,
+        } = rejection;
+        let status = res.status();
+        // Capture x-request-id up-front in case we consume the response body later.
+        let x_request_id = res
+            .headers()
+            .get("x-request-id")
+            .and_then(|v| v.to_str().ok())
+            .map(std::string::ToString::to_string);
+        let now = Utc::now();
 
-                    // The OpenAI Responses endpoint returns structured JSON bodies even for 4xx/5xx
-                    // errors. When we bubble early with only the HTTP status the caller sees an opaque
-                    // "unexpected status 400 Bad Request" which makes debugging nearly impossible.
-                    // Instead, read (and include) the response text so higher layers and users see the
-                    // exact error message (e.g. "Unknown parameter: 'input[0].metadata'"). The body is
-                    // small and this branch only runs on error paths so the extra allocation is
-                    // negligible.
-                    if !(status == StatusCode::TOO_MANY_REQUESTS
-                        || status == StatusCode::UNAUTHORIZED
-                        || status.is_server_error())
-                    {
-                        // Log error response
-                        if let Ok(logger) = self.debug_logger.lock() {
-                            let _ = logger.append_response_event(
-                                &request_id,
-                                "error",
-                                &serde_json::json!({
-                                    "status": status.as_u16(),
-                                    "body": body_text
-                                }),
-                            );
-                            let _ = logger.end_request_log(&request_id);
-                        }
-                        return Err(CodexErr::UnexpectedStatus(UnexpectedResponseError {
-                            status,
-                            body: body_text,
-                            request_id: None,
-                        }));
+        // Pull out Retry‑After header if present.
+        let retry_after_hint = res
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|raw| parse_retry_after_header(raw, now));
+
+        let mut auth_refresh_error: Option<RefreshTokenError> = None;
+        if status == StatusCode::UNAUTHORIZED {
+            if let Some(manager) = auth_manager {
+                match manager.refresh_token_classified().await {
+                    Ok(Some(_)) => {}
+                    Ok(None) => {
+                        auth_refresh_error =
+                            Some(RefreshTokenError::permanent(AUTH_REQUIRED_MESSAGE));
                     }
-
-                    if let Some(ErrorResponse { ref error }) = body
-                        && is_quota_exceeded_http_error(status, error)
-                    {
-                        return Err(CodexErr::QuotaExceeded);
+                    Err(err) => {
+                        auth_refresh_error = Some(err);
                     }
+                }
+            } else {
+                auth_refresh_error = Some(RefreshTokenError::permanent(
+                    "Authentication manager unavailable; please log in again.",
+                ));
+            }
+        }
 
-                    if status == StatusCode::UNAUTHORIZED
-                        && let Some(error) =
-                            map_unauthorized_outcome(auth.is_some(), auth_refresh_error.as_ref())
-                    {
-                        return Err(error);
-                    }
+        // Read the response body once for diagnostics across error branches.
+        let body_text = res.text().await.unwrap_or_default();
+        let body = serde_json::from_str::<ErrorResponse>(&body_text).ok();
 
-                    if status == StatusCode::TOO_MANY_REQUESTS
-                        && let Some(ErrorResponse { ref error }) = body
-                    {
-                        if error.r#type.as_deref() == Some("usage_limit_reached") {
-                            // Prefer the plan_type provided in the error message if present
-                            // because it's more up to date than the one encoded in the auth
-                            // token.
-                            let plan_type = error
-                                .plan_type
-                                .clone()
-                                .or_else(|| auth.and_then(|a| a.get_plan_type()));
-                            let resets_in_seconds = error.resets_in_seconds;
-                            return Err(CodexErr::UsageLimitReached(UsageLimitReachedError {
-                                plan_type,
-                                resets_in_seconds,
-                            }));
-                        } else if error.r#type.as_deref() == Some("usage_not_included") {
-                            return Err(CodexErr::UsageNotIncluded);
-                        }
-                    }
+        if status == StatusCode::BAD_REQUEST
+            && let Some(ErrorResponse { ref error }) = body
+            && !self.reasoning_summary_disabled.load(Ordering::Relaxed)
+            && is_reasoning_summary_rejected(error)
+        {
+            self.disable_reasoning_summary();
+
+            if let Ok(logger) = self.debug_logger.lock() {
+                let _ = logger.append_response_event(
+                    &request_id,
+                    "reasoning_summary_disabled",
+                    &serde_json::json!({
+                        "status": status.as_u16(),
+                        "message": error.message.clone(),
+                        "code": error.code.clone(),
+                        "param": error.param.clone(),
+                    }),
+                );
+            }
 
-                    if attempt > max_retries {
-                        // On final attempt, surface rich diagnostics for server errors.
-                        // On final attempt, surface rich diagnostics for server errors.
-                        if status.is_server_error() {
-                            let (message, body_excerpt) =
-                                match serde_json::from_str::<ErrorResponse>(&body_text) {
-                                    Ok(ErrorResponse { error }) => {
-                                        let msg = error
-                                            .message
-                                            .unwrap_or_else(|| "server error".to_string());
-                                        (msg, None)
-                                    }
-                                    Err(_) => {
-                                        let mut excerpt = body_text;
-                                        const MAX: usize = 600;
-                                        if excerpt.len() > MAX {
-                                            excerpt.truncate(MAX);
-                                        }
-                                        (
-                                            "server error".to_string(),
-                                            if excerpt.is_empty() {
-                                                None
-                                            } else {
-                                                Some(excerpt)
-                                            },
-                                        )
-                                    }
-                                };
-
-                            // Build a single-line, actionable message for the UI and logs.
-                            let mut msg = format!("server error {status}: {message}");
-                            if let Some(id) = &x_request_id {
-                                msg.push_str(&format!(" (request-id: {id})"));
-                            }
-                            if let Some(excerpt) = &body_excerpt {
-                                msg.push_str(&format!(" | body: {excerpt}"));
-                            }
+            // Retry immediately with reasoning summaries removed.
+            return Rejected::Restart;
+        }
 
-                            // Log detailed context to the debug logger and close the request log.
-                            if let Ok(logger) = self.debug_logger.lock() {
-                                let _ = logger.append_response_event(
-                                    &request_id,
-                                    "server_error_on_retry_limit",
-                                    &serde_json::json!({
-                                        "status": status.as_u16(),
-                                        "x_request_id": x_request_id,
-                                        "message": message,
-                                        "body_excerpt": body_excerpt,
-                                    }),
-                                );
-                                let _ = logger.end_request_log(&request_id);
-                            }
+        // The OpenAI Responses endpoint returns structured JSON bodies even for 4xx/5xx
+        // errors. When we bubble early with only the HTTP status the caller sees an opaque
+        // "unexpected status 400 Bad Request" which makes debugging nearly impossible.
+        // Instead, read (and include) the response text so higher layers and users see the
+        // exact error message (e.g. "Unknown parameter: 'input[0].metadata'"). The body is
+        // small and this branch only runs on error paths so the extra allocation is
+        // negligible.
+        if !(is_retryable_status(status) || status == StatusCode::UNAUTHORIZED) {
+            return Rejected::Fatal(unexpected_status(
+                &self.debug_logger,
+                &request_id,
+                status,
+                body_text,
+            ));
+        }
 
-                            return Err(CodexErr::ServerError(msg));
-                        }
+        if let Some(ErrorResponse { ref error }) = body
+            && is_quota_exceeded_http_error(status, error)
+        {
+            return Rejected::Fatal(CodexErr::QuotaExceeded);
+        }
 
-                        return Err(CodexErr::RetryLimit(RetryLimitReachedError {
-                            status,
-                            request_id: None,
-                            retryable: status.is_server_error()
-                                || status == StatusCode::TOO_MANY_REQUESTS,
-                        }));
-                    }
+        if status == StatusCode::UNAUTHORIZED
+            && let Some(error) =
+                map_unauthorized_outcome(auth.is_some(), auth_refresh_error.as_ref())
+        {
+            return Rejected::Fatal(error);
+        }
 
-                    let mut retry_after_delay = retry_after_hint;
-                    if retry_after_delay.is_none()
-                        && let Some(ErrorResponse { ref error }) = body
-                    {
-                        retry_after_delay = try_parse_retry_after(error, now);
-                    }
+        if status == StatusCode::TOO_MANY_REQUESTS
+            && let Some(ErrorResponse { ref error }) = body
+        {
+            if error.r#type.as_deref() == Some("usage_limit_reached") {
+                // Prefer the plan_type provided in the error message if present
+                // because it's more up to date than the one encoded in the auth
+                // token.
+                let plan_type = error
+                    .plan_type
+                    .clone()
+                    .or_else(|| auth.and_then(|a| a.get_plan_type()));
+                let resets_in_seconds = error.resets_in_seconds;
+                if let Some(secs) = resets_in_seconds {
+                    quota_ledger::global().block_for(&quota_key, Duration::from_secs(secs));
+                }
+                return Rejected::Fatal(CodexErr::UsageLimitReached(UsageLimitReachedError {
+                    plan_type,
+                    resets_in_seconds,
+                }));
+            } else if error.r#type.as_deref() == Some("usage_not_included") {
+                return Rejected::Fatal(CodexErr::UsageNotIncluded);
+            }
+        }
 
-                    let delay = retry_after_delay
-                        .as_ref()
-                        .map(|info| info.delay)
-                        .unwrap_or_else(|| backoff(attempt));
-                    tokio::time::sleep(delay).await;
+        // On final attempt, surface rich diagnostics for server errors.
+        if is_last && status.is_server_error() {
+            let (message, body_excerpt) = match serde_json::from_str::<ErrorResponse>(&body_text) {
+                Ok(ErrorResponse { error }) => {
+                    let msg = error.message.unwrap_or_else(|| "server error".to_string());
+                    (msg, None)
                 }
-                Err(e) => {
-                    let is_connectivity = e.is_connect() || e.is_timeout() || e.is_request();
-                    if attempt > max_retries {
-                        // Log network error before surfacing.
-                        if let Ok(logger) = self.debug_logger.lock() {
-                            let _ = logger.log_error(
-                                &endpoint,
-                                &format!("Network error: {e}"),
-                                log_tag,
-                            );
-                        }
-                        if is_connectivity {
-                            let req_id = (!request_id.is_empty()).then(|| request_id.clone());
-                            return Err(CodexErr::Stream(
-                                format!("[transport] network unavailable: {e}"),
-                                None,
-                                req_id,
-                            ));
-                        }
-                        return Err(e.into());
+                Err(_) => {
+                    let mut excerpt = body_text;
+                    const MAX: usize = 600;
+                    if excerpt.len() > MAX {
+                        excerpt.truncate(MAX);
                     }
-                    let delay = backoff(attempt);
-                    tokio::time::sleep(delay).await;
+                    (
+                        "server error".to_string(),
+                        if excerpt.is_empty() {
+                            None
+                        } else {
+                            Some(excerpt)
+                        },
+                    )
                 }
+            };
+
+            // Build a single-line, actionable message for the UI and logs.
+            let mut msg = format!("server error {status}: {message}");
+            if let Some(id) = &x_request_id {
+                msg.push_str(&format!(" (request-id: {id})"));
+            }
+            if let Some(excerpt) = &body_excerpt {
+                msg.push_str(&format!(" | body: {excerpt}"));
+            }
+
+            // Log detailed context to the debug logger and close the request log.
+            if let Ok(logger) = self.debug_logger.lock() {
+                let _ = logger.append_response_event(
+                    &request_id,
+                    "server_error_on_retry_limit",
+                    &serde_json::json!({
+                        "status": status.as_u16(),
+                        "x_request_id": x_request_id,
+                        "message": message,
+                        "body_excerpt": body_excerpt,
+                    }),
+                );
+                let _ = logger.end_request_log(&request_id);
             }
+
+            return Rejected::Fatal(CodexErr::ServerError(msg));
+        }
+
+        let mut retry_after_delay = retry_after_hint;
+        if retry_after_delay.is_none()
+            && let Some(ErrorResponse { ref error }) = body
+        {
+            retry_after_delay = try_parse_retry_after(error, now);
         }
+
+        Rejected::Retry(
+            retry_after_delay
+                .as_ref()
+                .map(|info| info.delay)
+                .unwrap_or_else(|| backoff(attempt)),
+        )
     }
 
     pub fn get_provider(&self) -> ModelProviderInfo {
@@ -1063,12 +1377,17 @@ impl ModelClient {
             input: &prompt.input,
             instructions: instructions.clone(),
         };
-        let payload_json = serde_json::json!({
+        let mut payload_json = serde_json::json!({
             "model": payload.model,
             "input": payload.input,
             "instructions": instructions,
         });
-        request = request.json(&payload);
+        let compact_url = self
+            .provider
+            .get_compact_url(&auth)
+            .unwrap_or_else(|| self.provider.get_full_url(&auth));
+        let extra_headers = self.middleware.prepare(&compact_url, &mut payload_json);
+        request = request.headers(extra_headers).json(&payload_json);
 
         let header_snapshot = request
             .try_clone()
@@ -1120,6 +1439,134 @@ impl ModelClient {
         let CompactHistoryResponse { output } = serde_json::from_str(&body)?;
         Ok(output)
     }
+
+    /// Uploads `requests` and starts a Batch API job for them. Each prompt
+    /// is sent as a single non-streaming Responses request without tools.
+    pub async fn submit_batch(&self, requests: &[BatchRequest]) -> Result<BatchJob> {
+        let jsonl = requests
+            .iter()
+            .map(|request| {
+                batch::request_line(
+                    &request.custom_id,
+                    &self.batch_request_body(&request.prompt),
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let (content_type, upload) = batch::upload_body(&jsonl);
+        let file: BatchFile = self
+            .batch_call(Method::POST, "files", |builder| {
+                builder
+                    .header(reqwest::header::CONTENT_TYPE, content_type)
+                    .body(upload)
+            })
+            .await?;
+        self.batch_call(Method::POST, "batches", |builder| {
+            builder.json(&serde_json::json!({
+                "input_file_id": file.id,
+                "endpoint": batch::BATCH_ENDPOINT,
+                "completion_window": batch::COMPLETION_WINDOW,
+            }))
+        })
+        .await
+    }
+
+    /// Fetches the state of batch `batch_id`; once it has completed the
+    /// per-request results are downloaded as well.
+    pub async fn poll_batch(&self, batch_id: &str) -> Result<BatchPoll> {
+        let job: BatchJob = self
+            .batch_call(Method::GET, &format!("batches/{batch_id}"), |builder| {
+                builder
+            })
+            .await?;
+        let mut results = Vec::new();
+        if job.status == BatchStatus::Completed {
+            for file_id in [&job.output_file_id, &job.error_file_id]
+                .into_iter()
+                .flatten()
+            {
+                let content = self.batch_file_content(file_id).await?;
+                results.extend(batch::parse_results(&content));
+            }
+        }
+        Ok(BatchPoll { job, results })
+    }
+
+    fn batch_request_body(&self, prompt: &Prompt) -> serde_json::Value {
+        let model = prompt
+            .model_override
+            .as_deref()
+            .unwrap_or(self.config.model.as_str());
+        let family =
+            find_family_for_model(model).unwrap_or_else(|| self.config.model_family.clone());
+        let effort = clamp_reasoning_effort_for_model(model, self.effort);
+        let mut body = serde_json::json!({
+            "model": model,
+            "instructions": prompt.get_full_instructions(&family),
+            "input": prompt.get_formatted_input(),
+            "reasoning": self.current_reasoning_param(&family, effort),
+            "store": false,
+        });
+        let sampling = prompt.sampling.or(self.config.sampling);
+        if let Some(obj) = body.as_object_mut() {
+            if let Some(seed) = sampling
+                .seed
+                .filter(|_| self.provider.accepts_responses_seed())
+            {
+                obj.insert("seed".to_string(), seed.into());
+            }
+            if let Some(temperature) = sampling.temperature {
+                obj.insert("temperature".to_string(), temperature.into());
+            }
+            if let Some(top_p) = sampling.top_p {
+                obj.insert("top_p".to_string(), top_p.into());
+            }
+        }
+        body
+    }
+
+    async fn batch_builder(&self, method: Method, path: &str) -> Result<reqwest::RequestBuilder> {
+        let auth = self.auth_manager.as_ref().and_then(|m| m.auth());
+        self.provider
+            .create_batch_request_builder(&self.client, &auth, method, path)
+            .await
+    }
+
+    async fn batch_call<T: serde::de::DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        build: impl FnOnce(reqwest::RequestBuilder) -> reqwest::RequestBuilder,
+    ) -> Result<T> {
+        let request = build(self.batch_builder(method, path).await?);
+        let body = batch_response_text(request.send().await?).await?;
+        Ok(serde_json::from_str(&body)?)
+    }
+
+    async fn batch_file_content(&self, file_id: &str) -> Result<String> {
+        let request = self
+            .batch_builder(Method::GET, &format!("files/{file_id}/content"))
+            .await?;
+        batch_response_text(request.send().await?).await
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct BatchFile {
+    id: String,
+}
+
+async fn batch_response_text(response: reqwest::Response) -> Result<String> {
+    let status = response.status();
+    let body = response.text().await?;
+    if !status.is_success() {
+        return Err(CodexErr::UnexpectedStatus(UnexpectedResponseError {
+            status,
+            body,
+            request_id: None,
+        }));
+    }
+    Ok(body)
 }
 
 fn clamp_text_verbosity_for_model(
@@ -1192,7 +1639,7 @@ struct ResponseCompleted {
 }
 
 #[derive(Debug, Deserialize)]
-struct ResponseCompletedUsage {
+pub(crate) struct ResponseCompletedUsage {
     input_tokens: u64,
     input_tokens_details: Option<ResponseCompletedInputTokensDetails>,
     output_tokens: u64,
@@ -1355,16 +1802,195 @@ fn header_map_to_json(headers: &HeaderMap) -> Value {
     serde_json::to_value(ordered).unwrap_or(Value::Null)
 }
 
+/// Reconnects to a stored response whose stream dropped partway through.
+struct StreamResumer {
+    client: reqwest::Client,
+    provider: ModelProviderInfo,
+    auth_manager: Option<Arc<AuthManager>>,
+}
+
+impl StreamResumer {
+    async fn reconnect(
+        &self,
+        response_id: &str,
+        starting_after: Option<u64>,
+    ) -> Result<futures::stream::BoxStream<'static, Result<Bytes>>> {
+        let auth = self.auth_manager.as_ref().and_then(|m| m.auth());
+        let resp = self
+            .provider
+            .create_resume_request_builder(&self.client, &auth, response_id, starting_after)
+            .await?
+            .header(reqwest::header::ACCEPT, "text/event-stream")
+            .send()
+            .await?;
+        let status = resp.status();
+        if !status.is_success() {
+            return Err(CodexErr::UnexpectedStatus(UnexpectedResponseError {
+                status,
+                body: resp.text().await.unwrap_or_default(),
+                request_id: None,
+            }));
+        }
+        Ok(resp.bytes_stream().map_err(CodexErr::Reqwest).boxed())
+    }
+}
+
+/// Idle timeouts and transport errors raised by `process_sse`, after which
+/// the response may still be running on the provider.
+fn is_stream_disconnect(err: &CodexErr) -> bool {
+    matches!(
+        err,
+        CodexErr::Stream(message, None, _)
+            if message.starts_with("[idle]") || message.starts_with("[transport]")
+    )
+}
+
+/// Errors after which a profile's next provider is tried: exhausted quota,
+/// server failures, and authentication that will not succeed on retry.
+fn should_fail_over(err: &CodexErr) -> bool {
+    match err {
+        CodexErr::QuotaExceeded
+        | CodexErr::UsageLimitReached(_)
+        | CodexErr::UsageNotIncluded
+        | CodexErr::ServerError(_)
+        | CodexErr::ProviderUnavailable(_)
+        | CodexErr::Tls(_)
+        | CodexErr::AuthRefreshPermanent(_)
+        | CodexErr::EnvVar(_) => true,
+        CodexErr::RetryLimit(err) => err.status.is_server_error(),
+        CodexErr::UnexpectedStatus(err) => {
+            err.status.is_server_error()
+                || err.status == StatusCode::UNAUTHORIZED
+                || err.status == StatusCode::FORBIDDEN
+        }
+        _ => false,
+    }
+}
+
+/// Runs `process_sse` and, when the stream drops after `response.created`,
+/// resumes it from the last sequence number instead of failing the turn.
+/// Up to `stream_max_retries` reconnects are made; the shared checkpoint
+/// drops events and items that were already forwarded.
+async fn process_resumable_sse(
+    stream: futures::stream::BoxStream<'static, Result<Bytes>>,
+    tx_event: mpsc::Sender<Result<ResponseEvent>>,
+    idle_timeout: Duration,
+    degenerate_checks: bool,
+    debug_logger: Arc<Mutex<DebugLogger>>,
+    request_id: String,
+    otel_event_manager: Option<OtelEventManager>,
+    resumer: StreamResumer,
+) {
+    let checkpoint = Arc::new(RwLock::new(StreamCheckpoint::default()));
+    let mut stream = stream;
+    let mut resumes = 0;
+    loop {
+        let (inner_tx, mut inner_rx) = mpsc::channel::<Result<ResponseEvent>>(SSE_CHANNEL_CAPACITY);
+        tokio::spawn(process_sse(
+            stream,
+            inner_tx,
+            idle_timeout,
+            degenerate_checks,
+            Arc::clone(&debug_logger),
+            request_id.clone(),
+            otel_event_manager.clone(),
+            Arc::clone(&checkpoint),
+        ));
+
+        let mut dropped = None;
+        while let Some(event) = inner_rx.recv().await {
+            match event {
+                Err(err) if is_stream_disconnect(&err) => {
+                    dropped = Some(err);
+                    break;
+                }
+                event => {
+                    if tx_event.send(event).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+        let Some(err) = dropped else {
+            return;
+        };
+
+        let (response_id, last_sequence) = checkpoint
+            .read()
+            .map(|c| (c.response_id.clone(), c.last_sequence))
+            .unwrap_or_default();
+        let Some(response_id) =
+            response_id.filter(|_| resumes < resumer.provider.stream_max_retries())
+        else {
+            let _ = tx_event.send(Err(err)).await;
+            return;
+        };
+        resumes += 1;
+        tokio::time::sleep(backoff(resumes)).await;
+        match resumer.reconnect(&response_id, last_sequence).await {
+            Ok(resumed) => {
+                debug!("resumed {response_id} after {last_sequence:?}: {err}");
+                if let Ok(logger) = debug_logger.lock() {
+                    let _ = logger.append_response_event(
+                        &request_id,
+                        "stream_resumed",
+                        &serde_json::json!({
+                            "response_id": response_id,
+                            "starting_after": last_sequence,
+                            "reason": err.to_string(),
+                        }),
+                    );
+                }
+                stream = resumed;
+            }
+            Err(resume_err) => {
+                debug!("failed to resume {response_id}: {resume_err}");
+                let _ = tx_event.send(Err(err)).await;
+                return;
+            }
+        }
+    }
+}
+
 async fn process_sse<S>(
     stream: S,
     tx_event: mpsc::Sender<Result<ResponseEvent>>,
     idle_timeout: Duration,
+    degenerate_checks: bool,
     debug_logger: Arc<Mutex<DebugLogger>>,
     request_id: String,
     otel_event_manager: Option<OtelEventManager>,
     checkpoint: Arc<RwLock<StreamCheckpoint>>,
 ) where
     S: Stream<Item = Result<Bytes>> + Unpin,
+{
+    let mut sender = BoundedEventSender::new(tx_event);
+    process_sse_events(
+        stream,
+        &mut sender,
+        idle_timeout,
+        degenerate_checks,
+        &debug_logger,
+        &request_id,
+        otel_event_manager.as_ref(),
+        &checkpoint,
+    )
+    .await;
+    let _ = sender.flush().await;
+    sender.stats().record(otel_event_manager.as_ref());
+}
+
+async fn process_sse_events<S>(
+    stream: S,
+    sender: &mut BoundedEventSender,
+    idle_timeout: Duration,
+    degenerate_checks: bool,
+    debug_logger: &Mutex<DebugLogger>,
+    request_id: &str,
+    otel_event_manager: Option<&OtelEventManager>,
+    checkpoint: &RwLock<StreamCheckpoint>,
+) where
+    S: Stream<Item = Result<Bytes>> + Unpin,
 {
     let mut stream = stream.eventsource();
 
@@ -1383,12 +2009,27 @@ async fn process_sse<S>(
     let mut last_seq_reasoning_summary: HashMap<(String, u32, u32), u64> = HashMap::new();
     let mut last_seq_reasoning_content: HashMap<(String, u32, u32), u64> = HashMap::new();
     // Best-effort duplicate text guard when sequence_number is unavailable.
-    let mut last_text_reasoning_summary: HashMap<(String, u32, u32), String> = HashMap::new();
-    let mut last_text_reasoning_content: HashMap<(String, u32, u32), String> = HashMap::new();
+    // Fingerprints rather than copies so long reasoning streams stay small.
+    let mut last_text_reasoning_summary: HashMap<(String, u32, u32), u64> = HashMap::new();
+    let mut last_text_reasoning_content: HashMap<(String, u32, u32), u64> = HashMap::new();
     let mut global_last_seq: Option<u64> = checkpoint.read().ok().and_then(|c| c.last_sequence);
+    let mut anomalies = AnomalyDetector::new(degenerate_checks);
+    // `call_id` and name of function calls announced by `output_item.added`,
+    // keyed by item id, so argument deltas can name their call.
+    let mut function_calls: HashMap<String, (Option<String>, Option<String>)> = HashMap::new();
+    if checkpoint
+        .read()
+        .is_ok_and(|c| !c.delivered_outputs.is_empty())
+    {
+        // Resuming: output was delivered before the reconnect.
+        anomalies.observe_output();
+    }
 
     loop {
-        let next_event = if let Some(manager) = otel_event_manager.as_ref() {
+        if sender.try_flush().is_err() {
+            return;
+        }
+        let next_event = if let Some(manager) = otel_event_manager {
             manager
                 .log_sse_event(|| timeout(idle_timeout, stream.next()))
                 .await
@@ -1400,9 +2041,12 @@ async fn process_sse<S>(
             Ok(Some(Ok(sse))) => sse,
             Ok(Some(Err(e))) => {
                 debug!("SSE Error: {e:#}");
-                let event =
-                    CodexErr::Stream(format!("[transport] {e}"), None, Some(request_id.clone()));
-                let _ = tx_event.send(Err(event)).await;
+                let event = CodexErr::Stream(
+                    format!("[transport] {e}"),
+                    None,
+                    Some(request_id.to_string()),
+                );
+                let _ = sender.send(Err(event)).await;
                 return;
             }
             Ok(None) => {
@@ -1411,8 +2055,24 @@ async fn process_sse<S>(
                         id: response_id,
                         usage,
                     }) => {
-                        if let (Some(usage), Some(manager)) = (&usage, otel_event_manager.as_ref())
+                        if let Some((anomaly, detail)) =
+                            anomalies.check_completed(usage.as_ref().map(|u| u.output_tokens))
                         {
+                            anomaly.record(&detail, otel_event_manager);
+                            let _ = sender
+                                .send(Err(anomaly.into_error(&detail, request_id)))
+                                .await;
+                            if let Ok(logger) = debug_logger.lock() {
+                                let _ = logger.append_response_event(
+                                    request_id,
+                                    "sse_buffer",
+                                    &sender.stats().to_json(),
+                                );
+                                let _ = logger.end_request_log(request_id);
+                            }
+                            return;
+                        }
+                        if let (Some(usage), Some(manager)) = (&usage, otel_event_manager) {
                             manager.sse_event_completed(
                                 usage.input_tokens,
                                 usage.output_tokens,
@@ -1428,32 +2088,37 @@ async fn process_sse<S>(
                             response_id,
                             token_usage: usage.map(Into::into),
                         };
-                        let _ = tx_event.send(Ok(event)).await;
+                        let _ = sender.send(Ok(event)).await;
                     }
                     None => {
-                        let error = response_error.unwrap_or(CodexErr::Stream(
-                            "stream closed before response.completed".into(),
-                            None,
-                            Some(request_id.clone()),
-                        ));
-                        if let Some(manager) = otel_event_manager.as_ref() {
+                        let error = response_error.unwrap_or_else(|| {
+                            let detail = "stream closed before response.completed";
+                            ResponseAnomaly::Truncated.record(detail, otel_event_manager);
+                            CodexErr::Stream(detail.into(), None, Some(request_id.to_string()))
+                        });
+                        if let Some(manager) = otel_event_manager {
                             manager.sse_event_completed_failed(&error);
                         }
-                        let _ = tx_event.send(Err(error)).await;
+                        let _ = sender.send(Err(error)).await;
                     }
                 }
                 // Mark the request log as complete
                 if let Ok(logger) = debug_logger.lock() {
-                    let _ = logger.end_request_log(&request_id);
+                    let _ = logger.append_response_event(
+                        request_id,
+                        "sse_buffer",
+                        &sender.stats().to_json(),
+                    );
+                    let _ = logger.end_request_log(request_id);
                 }
                 return;
             }
             Err(_) => {
-                let _ = tx_event
+                let _ = sender
                     .send(Err(CodexErr::Stream(
                         "[idle] timeout waiting for SSE".into(),
                         None,
-                        Some(request_id.clone()),
+                        Some(request_id.to_string()),
                     )))
                     .await;
                 return;
@@ -1467,7 +2132,7 @@ async fn process_sse<S>(
         if let Ok(logger) = debug_logger.lock()
             && let Ok(json_value) = serde_json::from_str::<serde_json::Value>(&sse.data)
         {
-            let _ = logger.append_response_event(&request_id, "sse_event", &json_value);
+            let _ = logger.append_response_event(request_id, "sse_event", &json_value);
         }
 
         let event: SseEvent = match serde_json::from_str(&sse.data) {
@@ -1482,7 +2147,7 @@ async fn process_sse<S>(
                 debug!("Failed to parse SSE event: {e}, data: {excerpt}");
                 if let Ok(logger) = debug_logger.lock() {
                     let _ = logger.append_response_event(
-                        &request_id,
+                        request_id,
                         "sse_parse_error",
                         &serde_json::json!({
                             "error": e.to_string(),
@@ -1498,6 +2163,7 @@ async fn process_sse<S>(
             if let Some(last) = global_last_seq
                 && seq <= last
             {
+                sender.note_dropped();
                 continue;
             }
             global_last_seq = Some(seq);
@@ -1527,6 +2193,14 @@ async fn process_sse<S>(
             // drop the duplicated list inside `response.completed`.
             "response.output_item.done" => {
                 let Some(item_val) = event.item else { continue };
+                anomalies.observe_output();
+                if let Some(output_index) = event.output_index
+                    && let Ok(mut guard) = checkpoint.write()
+                    && !guard.delivered_outputs.insert(output_index)
+                {
+                    debug!("dropping replayed output item {output_index}");
+                    continue;
+                }
                 // Special-case: web_search_call completion -> synthesize a completion event
                 if item_val
                     .get("type")
@@ -1544,7 +2218,7 @@ async fn process_sse<S>(
                         .and_then(|v| v.as_str())
                         .map(std::string::ToString::to_string);
                     let ev = ResponseEvent::WebSearchCallCompleted { call_id, query };
-                    if tx_event.send(Ok(ev)).await.is_err() {
+                    if sender.send(Ok(ev)).await.is_err() {
                         return;
                     }
                 }
@@ -1573,8 +2247,24 @@ async fn process_sse<S>(
                     }
                 }
 
+                // The item is final; its duplicate guards are no longer needed.
+                if let Some(id) = current_item_id.as_deref() {
+                    last_seq_reasoning_summary.retain(|(key, _, _), _| key != id);
+                    last_seq_reasoning_content.retain(|(key, _, _), _| key != id);
+                    last_text_reasoning_summary.retain(|(key, _, _), _| key != id);
+                    last_text_reasoning_content.retain(|(key, _, _), _| key != id);
+                }
+
+                if let Some(detail) = anomalies.observe_item(&item) {
+                    ResponseAnomaly::Truncated.record(&detail, otel_event_manager);
+                    let _ = sender
+                        .send(Err(ResponseAnomaly::Truncated.into_error(&detail, request_id)))
+                        .await;
+                    return;
+                }
+
                 let event = ResponseEvent::OutputItemDone { item, sequence_number: event.sequence_number, output_index: event.output_index };
-                if tx_event.send(Ok(event)).await.is_err() {
+                if sender.send(Ok(event)).await.is_err() {
                     return;
                 }
             }
@@ -1585,19 +2275,31 @@ async fn process_sse<S>(
                         current_item_id = Some(id.clone());
                     }
                     tracing::debug!("sse.delta output_text id={:?} len={}", current_item_id, delta.len());
-                    let ev = ResponseEvent::OutputTextDelta {
-                        delta,
-                        item_id: event.item_id.or_else(|| current_item_id.clone()),
-                        sequence_number: event.sequence_number,
-                        output_index: event.output_index,
-                    };
-                    if tx_event.send(Ok(ev)).await.is_err() {
+                    if let Some(detail) = anomalies.observe_text_delta(&delta) {
+                        ResponseAnomaly::Repetition.record(&detail, otel_event_manager);
+                        let _ = sender
+                            .send(Err(ResponseAnomaly::Repetition.into_error(&detail, request_id)))
+                            .await;
+                        return;
+                    }
+                    // Merged with neighbouring deltas while the consumer lags.
+                    if sender
+                        .send_text_delta(
+                            delta,
+                            event.item_id.or_else(|| current_item_id.clone()),
+                            event.sequence_number,
+                            event.output_index,
+                        )
+                        .await
+                        .is_err()
+                    {
                         return;
                     }
                 }
             }
             "response.reasoning_summary_text.delta" => {
                 if let Some(delta) = event.delta {
+                    anomalies.observe_output();
                     if let Some(ref id) = event.item_id {
                         current_item_id = Some(id.clone());
                     }
@@ -1608,15 +2310,20 @@ async fn process_sse<S>(
                         // Drop duplicates/out‑of‑order by sequence_number when available
                         if let Some(sn) = event.sequence_number {
                             let last = last_seq_reasoning_summary.entry((id.clone(), out_idx, sum_idx)).or_insert(0);
-                            if *last >= sn { continue; }
+                            if *last >= sn {
+                                sender.note_dropped();
+                                continue;
+                            }
                             *last = sn;
                         } else {
                             // Best-effort: drop exact duplicate text for same key when seq is missing
                             let key = (id.clone(), out_idx, sum_idx);
-                            if last_text_reasoning_summary.get(&key) == Some(&delta) {
+                            let fingerprint = delta_fingerprint(&delta);
+                            if last_text_reasoning_summary.get(&key) == Some(&fingerprint) {
+                                sender.note_dropped();
                                 continue;
                             }
-                            last_text_reasoning_summary.insert(key, delta.clone());
+                            last_text_reasoning_summary.insert(key, fingerprint);
                         }
                     }
                     tracing::debug!(
@@ -1632,13 +2339,14 @@ async fn process_sse<S>(
                         output_index: event.output_index,
                         summary_index: event.summary_index,
                     };
-                    if tx_event.send(Ok(ev)).await.is_err() {
+                    if sender.send(Ok(ev)).await.is_err() {
                         return;
                     }
                 }
             }
             "response.reasoning_text.delta" => {
                 if let Some(delta) = event.delta {
+                    anomalies.observe_output();
                     if let Some(ref id) = event.item_id {
                         current_item_id = Some(id.clone());
                     }
@@ -1649,15 +2357,20 @@ async fn process_sse<S>(
                         // Drop duplicates/out‑of‑order by sequence_number when available
                         if let Some(sn) = event.sequence_number {
                             let last = last_seq_reasoning_content.entry((id.clone(), out_idx, content_idx)).or_insert(0);
-                            if *last >= sn { continue; }
+                            if *last >= sn {
+                                sender.note_dropped();
+                                continue;
+                            }
                             *last = sn;
                         } else {
                             // Best-effort: drop exact duplicate text for same key when seq is missing
                             let key = (id.clone(), out_idx, content_idx);
-                            if last_text_reasoning_content.get(&key) == Some(&delta) {
+                            let fingerprint = delta_fingerprint(&delta);
+                            if last_text_reasoning_content.get(&key) == Some(&fingerprint) {
+                                sender.note_dropped();
                                 continue;
                             }
-                            last_text_reasoning_content.insert(key, delta.clone());
+                            last_text_reasoning_content.insert(key, fingerprint);
                         }
                     }
                     tracing::debug!(
@@ -1673,14 +2386,19 @@ async fn process_sse<S>(
                         output_index: event.output_index,
                         content_index: event.content_index,
                     };
-                    if tx_event.send(Ok(ev)).await.is_err() {
+                    if sender.send(Ok(ev)).await.is_err() {
                         return;
                     }
                 }
             }
             "response.created" => {
-                if event.response.is_some() {
-                    let _ = tx_event.send(Ok(ResponseEvent::Created {})).await;
+                if let Some(response) = event.response {
+                    if let Some(id) = response.get("id").and_then(|v| v.as_str())
+                        && let Ok(mut guard) = checkpoint.write()
+                    {
+                        guard.response_id = Some(id.to_string());
+                    }
+                    let _ = sender.send(Ok(ResponseEvent::Created {})).await;
                 }
             }
             "response.failed" => {
@@ -1688,7 +2406,7 @@ async fn process_sse<S>(
                     response_error = Some(CodexErr::Stream(
                         "response.failed event received".to_string(),
                         None,
-                        Some(request_id.clone()),
+                        Some(request_id.to_string()),
                     ));
 
                     let error = resp_val.get("error");
@@ -1704,7 +2422,7 @@ async fn process_sse<S>(
                                     response_error = Some(CodexErr::Stream(
                                         message,
                                         retry_after,
-                                        Some(request_id.clone()),
+                                        Some(request_id.to_string()),
                                     ));
                                 }
                             }
@@ -1729,8 +2447,27 @@ async fn process_sse<S>(
                     };
                 };
             }
+            "response.function_call_arguments.delta" => {
+                if let Some(delta) = event.delta {
+                    let (call_id, name) = event
+                        .item_id
+                        .as_ref()
+                        .and_then(|id| function_calls.get(id))
+                        .cloned()
+                        .unwrap_or_default();
+                    let ev = ResponseEvent::ToolCallArgumentsDelta {
+                        delta,
+                        call_id,
+                        name,
+                        item_id: event.item_id,
+                        output_index: event.output_index,
+                    };
+                    if sender.send(Ok(ev)).await.is_err() {
+                        return;
+                    }
+                }
+            }
             "response.content_part.done"
-            | "response.function_call_arguments.delta"
             | "response.custom_tool_call_input.delta"
             | "response.custom_tool_call_input.done" // also emitted as response.output_item.done
             | "response.in_progress"
@@ -1738,6 +2475,14 @@ async fn process_sse<S>(
             | "response.output_text.done" => {
                 if event.kind == "response.output_item.added"
                     && let Some(item) = event.item.as_ref() {
+                        if item.get("type").and_then(|v| v.as_str()) == Some("function_call")
+                            && let Some(id) = item.get("id").and_then(|v| v.as_str())
+                        {
+                            let field = |key: &str| {
+                                item.get(key).and_then(|v| v.as_str()).map(str::to_string)
+                            };
+                            function_calls.insert(id.to_string(), (field("call_id"), field("name")));
+                        }
                         // Detect web_search_call begin and forward a synthetic event upstream.
                         if let Some(ty) = item.get("type").and_then(|v| v.as_str())
                             && ty == "web_search_call" {
@@ -1747,7 +2492,7 @@ async fn process_sse<S>(
                                     .unwrap_or("")
                                     .to_string();
                                 let ev = ResponseEvent::WebSearchCallBegin { call_id };
-                                if tx_event.send(Ok(ev)).await.is_err() {
+                                if sender.send(Ok(ev)).await.is_err() {
                                     return;
                                 }
                             }
@@ -1756,7 +2501,7 @@ async fn process_sse<S>(
             "response.reasoning_summary_part.added" => {
                 // Boundary between reasoning summary sections (e.g., titles).
                 let event = ResponseEvent::ReasoningSummaryPartAdded;
-                if tx_event.send(Ok(event)).await.is_err() {
+                if sender.send(Ok(event)).await.is_err() {
                     return;
                 }
             }
@@ -1766,13 +2511,13 @@ async fn process_sse<S>(
     }
 }
 
-/// used in tests to stream from a text SSE file
-async fn stream_from_fixture(
+/// used in tests and by the `mock` provider to stream from a text SSE file
+pub(crate) async fn stream_from_fixture(
     path: impl AsRef<Path>,
     provider: ModelProviderInfo,
     otel_event_manager: Option<OtelEventManager>,
 ) -> Result<ResponseStream> {
-    let (tx_event, rx_event) = mpsc::channel::<Result<ResponseEvent>>(1600);
+    let (tx_event, rx_event) = mpsc::channel::<Result<ResponseEvent>>(SSE_CHANNEL_CAPACITY);
     let f = std::fs::File::open(path.as_ref())?;
     let lines = std::io::BufReader::new(f).lines();
 
@@ -1791,12 +2536,17 @@ async fn stream_from_fixture(
         stream,
         tx_event,
         provider.stream_idle_timeout(),
+        provider.retry_degenerate_responses,
         debug_logger,
         String::new(), // Empty request_id for test fixture
         otel_event_manager,
         Arc::new(RwLock::new(StreamCheckpoint::default())),
     ));
-    Ok(ResponseStream { rx_event })
+    Ok(ResponseStream {
+        rx_event,
+        served_by: None,
+        retries: 0,
+    })
 }
 
 // Note: legacy helpers for parsing Retry-After headers and rate-limit messages
@@ -1821,6 +2571,24 @@ mod tests {
     // Helpers
     // ────────────────────────────
 
+    #[test]
+    fn failover_covers_quota_server_and_auth_errors() {
+        let status = |status| {
+            CodexErr::UnexpectedStatus(UnexpectedResponseError {
+                status,
+                body: String::new(),
+                request_id: None,
+            })
+        };
+        assert!(should_fail_over(&CodexErr::QuotaExceeded));
+        assert!(should_fail_over(&status(StatusCode::BAD_GATEWAY)));
+        assert!(should_fail_over(&status(StatusCode::UNAUTHORIZED)));
+        assert!(!should_fail_over(&status(StatusCode::BAD_REQUEST)));
+        assert!(!should_fail_over(&CodexErr::ContentBlocked(
+            "policy".to_string()
+        )));
+    }
+
     #[test]
     fn unauthorized_outcome_returns_permanent_error_for_permanent_refresh_failure() {
         let err = RefreshTokenError::permanent("token revoked");
@@ -1866,6 +2634,15 @@ mod tests {
             stream_idle_timeout_ms: None,
             requires_openai_auth: false,
             openrouter: None,
+            proxy: None,
+            ca_bundle_path: None,
+            client_cert_path: None,
+            client_key_path: None,
+            fallback_provider: None,
+            azure_ad: None,
+            vertex: None,
+            mock: None,
+            retry_degenerate_responses: false,
         };
 
         let client = reqwest::Client::builder()
@@ -1913,6 +2690,15 @@ mod tests {
             stream_idle_timeout_ms: None,
             requires_openai_auth: false,
             openrouter: None,
+            proxy: None,
+            ca_bundle_path: None,
+            client_cert_path: None,
+            client_key_path: None,
+            fallback_provider: None,
+            azure_ad: None,
+            vertex: None,
+            mock: None,
+            retry_degenerate_responses: false,
         };
 
         let client = reqwest::Client::builder()
@@ -1962,6 +2748,15 @@ mod tests {
             stream_idle_timeout_ms: None,
             requires_openai_auth: false,
             openrouter: None,
+            proxy: None,
+            ca_bundle_path: None,
+            client_cert_path: None,
+            client_key_path: None,
+            fallback_provider: None,
+            azure_ad: None,
+            vertex: None,
+            mock: None,
+            retry_degenerate_responses: false,
         };
 
         let client = reqwest::Client::builder()
@@ -2005,6 +2800,7 @@ mod tests {
             stream,
             tx,
             provider.stream_idle_timeout(),
+            provider.retry_degenerate_responses,
             debug_logger,
             String::new(),
             None,
@@ -2045,6 +2841,7 @@ mod tests {
             stream,
             tx,
             provider.stream_idle_timeout(),
+            provider.retry_degenerate_responses,
             debug_logger,
             String::new(),
             None,
@@ -2058,10 +2855,95 @@ mod tests {
         out
     }
 
+    /// Frames `events` as an SSE body, naming each event after its `type`.
+    fn sse_body(events: &[serde_json::Value]) -> String {
+        events
+            .iter()
+            .map(|e| format!("event: {}\ndata: {e}\n\n", e["type"].as_str().unwrap()))
+            .collect()
+    }
+
+    /// A `response.output_item.done` event carrying an assistant message.
+    fn output_item_done(seq: u64, output_index: u32, text: &str) -> serde_json::Value {
+        json!({
+            "type": "response.output_item.done",
+            "sequence_number": seq,
+            "output_index": output_index,
+            "item": {
+                "type": "message",
+                "role": "assistant",
+                "content": [{"type": "output_text", "text": text}]
+            }
+        })
+    }
+
     // ────────────────────────────
     // Tests from `implement-test-for-responses-api-sse-parser`
     // ────────────────────────────
 
+    #[tokio::test]
+    async fn forwards_function_call_argument_deltas() {
+        let provider = ModelProviderInfo {
+            name: "test".to_string(),
+            base_url: Some("https://test.com".to_string()),
+            env_key: Some("TEST_API_KEY".to_string()),
+            env_key_instructions: None,
+            wire_api: WireApi::Responses,
+            query_params: None,
+            http_headers: None,
+            env_http_headers: None,
+            request_max_retries: Some(0),
+            stream_max_retries: Some(0),
+            stream_idle_timeout_ms: Some(1000),
+            requires_openai_auth: false,
+            openrouter: None,
+            proxy: None,
+            ca_bundle_path: None,
+            client_cert_path: None,
+            client_key_path: None,
+            fallback_provider: None,
+            azure_ad: None,
+            vertex: None,
+            mock: None,
+            retry_degenerate_responses: false,
+        };
+        let events = run_sse(
+            vec![
+                json!({
+                    "type": "response.output_item.added",
+                    "output_index": 0,
+                    "item": {"type": "function_call", "id": "fc_1", "call_id": "call_1", "name": "shell", "arguments": ""}
+                }),
+                json!({
+                    "type": "response.function_call_arguments.delta",
+                    "item_id": "fc_1",
+                    "output_index": 0,
+                    "delta": "{\"cmd\":"
+                }),
+                json!({"type": "response.completed", "response": {"id": "resp1"}}),
+            ],
+            provider,
+        )
+        .await;
+
+        match &events[0] {
+            ResponseEvent::ToolCallArgumentsDelta {
+                delta,
+                call_id,
+                name,
+                item_id,
+                output_index,
+            } => {
+                assert_eq!(delta, "{\"cmd\":");
+                assert_eq!(call_id.as_deref(), Some("call_1"));
+                assert_eq!(name.as_deref(), Some("shell"));
+                assert_eq!(item_id.as_deref(), Some("fc_1"));
+                assert_eq!(*output_index, Some(0));
+            }
+            other => panic!("expected ToolCallArgumentsDelta, got {other:?}"),
+        }
+    }
+
     #[tokio::test]
     async fn parses_items_and_completed() {
         let item1 = json!({
@@ -2108,6 +2990,15 @@ mod tests {
             stream_idle_timeout_ms: Some(1000),
             requires_openai_auth: false,
             openrouter: None,
+            proxy: None,
+            ca_bundle_path: None,
+            client_cert_path: None,
+            client_key_path: None,
+            fallback_provider: None,
+            azure_ad: None,
+            vertex: None,
+            mock: None,
+            retry_degenerate_responses: false,
         };
 
         let events = collect_events(
@@ -2173,6 +3064,15 @@ mod tests {
             stream_idle_timeout_ms: Some(1000),
             requires_openai_auth: false,
             openrouter: None,
+            proxy: None,
+            ca_bundle_path: None,
+            client_cert_path: None,
+            client_key_path: None,
+            fallback_provider: None,
+            azure_ad: None,
+            vertex: None,
+            mock: None,
+            retry_degenerate_responses: false,
         };
 
         let events = collect_events(&[sse1.as_bytes()], provider).await;
@@ -2189,6 +3089,175 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn resumed_streams_skip_delivered_events_and_items() {
+        let created = json!({
+            "type": "response.created",
+            "sequence_number": 1,
+            "response": {"id": "resp_1"}
+        });
+        let first = sse_body(&[created.clone(), output_item_done(2, 0, "Hello")]);
+        // The resumed stream replays the created event and re-sends the
+        // first item under a new sequence number.
+        let resumed = sse_body(&[
+            created,
+            output_item_done(3, 0, "Hello"),
+            output_item_done(4, 1, "World"),
+            json!({
+                "type": "response.completed",
+                "sequence_number": 5,
+                "response": {"id": "resp_1"}
+            }),
+        ]);
+
+        let checkpoint = Arc::new(RwLock::new(StreamCheckpoint::default()));
+        let mut kinds = Vec::new();
+        for body in [first, resumed] {
+            let (tx, mut rx) = mpsc::channel::<Result<ResponseEvent>>(16);
+            let stream = ReaderStream::new(std::io::Cursor::new(body)).map_err(CodexErr::Io);
+            let debug_logger = Arc::new(Mutex::new(DebugLogger::new(false).unwrap()));
+            tokio::spawn(process_sse(
+                stream,
+                tx,
+                Duration::from_secs(1),
+                false,
+                debug_logger,
+                String::new(),
+                None,
+                Arc::clone(&checkpoint),
+            ));
+            while let Some(ev) = rx.recv().await {
+                kinds.push(match ev {
+                    Ok(ResponseEvent::Created {}) => "created".to_string(),
+                    Ok(ResponseEvent::OutputItemDone {
+                        item: ResponseItem::Message { content, .. },
+                        ..
+                    }) => format!("{content:?}"),
+                    Ok(ResponseEvent::Completed { response_id, .. }) => response_id,
+                    Ok(other) => panic!("unexpected event: {other:?}"),
+                    Err(err) => {
+                        assert!(!is_stream_disconnect(&err));
+                        "truncated".to_string()
+                    }
+                });
+            }
+        }
+
+        assert_eq!(kinds.len(), 5);
+        assert_eq!(kinds[0], "created");
+        assert!(kinds[1].contains("Hello"));
+        assert_eq!(kinds[2], "truncated");
+        assert!(kinds[3].contains("World"));
+        assert_eq!(kinds[4], "resp_1");
+        let checkpoint = checkpoint.read().unwrap();
+        assert_eq!(checkpoint.response_id.as_deref(), Some("resp_1"));
+        assert_eq!(checkpoint.last_sequence, Some(5));
+    }
+
+    #[tokio::test]
+    async fn dropped_streams_reconnect_without_repeating_events() {
+        let created = json!({
+            "type": "response.created",
+            "sequence_number": 1,
+            "response": {"id": "resp_1"}
+        });
+        // The first connection drops after one item; the resumed stream
+        // replays it before continuing.
+        let first: futures::stream::BoxStream<'static, Result<Bytes>> = futures::stream::iter([
+            Ok(Bytes::from(sse_body(&[
+                created.clone(),
+                output_item_done(2, 0, "Hello"),
+            ]))),
+            Err(CodexErr::Io(std::io::Error::other("connection reset"))),
+        ])
+        .boxed();
+        let resumed = sse_body(&[
+            created,
+            output_item_done(2, 0, "Hello"),
+            output_item_done(3, 0, "Hello"),
+            output_item_done(4, 1, "World"),
+            json!({
+                "type": "response.completed",
+                "sequence_number": 5,
+                "response": {"id": "resp_1"}
+            }),
+        ]);
+
+        let server = wiremock::MockServer::start().await;
+        wiremock::Mock::given(wiremock::matchers::method("GET"))
+            .and(wiremock::matchers::path("/openai/responses/resp_1"))
+            .and(wiremock::matchers::query_param("stream", "true"))
+            .and(wiremock::matchers::query_param("starting_after", "2"))
+            .respond_with(
+                wiremock::ResponseTemplate::new(200)
+                    .insert_header("content-type", "text/event-stream")
+                    .set_body_string(resumed),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        let provider = ModelProviderInfo {
+            name: "azure".to_string(),
+            base_url: Some(format!("{}/openai", server.uri())),
+            env_key: None,
+            env_key_instructions: None,
+            wire_api: WireApi::Responses,
+            query_params: None,
+            http_headers: None,
+            env_http_headers: None,
+            request_max_retries: Some(0),
+            stream_max_retries: Some(1),
+            stream_idle_timeout_ms: Some(1000),
+            requires_openai_auth: false,
+            openrouter: None,
+            proxy: None,
+            ca_bundle_path: None,
+            client_cert_path: None,
+            client_key_path: None,
+            fallback_provider: None,
+            azure_ad: None,
+            vertex: None,
+            mock: None,
+            retry_degenerate_responses: false,
+        };
+        assert!(provider.supports_stream_resume());
+
+        let (tx, mut rx) = mpsc::channel::<Result<ResponseEvent>>(16);
+        tokio::spawn(process_resumable_sse(
+            first,
+            tx,
+            Duration::from_secs(1),
+            false,
+            Arc::new(Mutex::new(DebugLogger::new(false).unwrap())),
+            String::new(),
+            None,
+            StreamResumer {
+                client: reqwest::Client::new(),
+                provider,
+                auth_manager: None,
+            },
+        ));
+        let mut kinds = Vec::new();
+        while let Some(ev) = rx.recv().await {
+            kinds.push(match ev {
+                Ok(ResponseEvent::Created {}) => "created".to_string(),
+                Ok(ResponseEvent::OutputItemDone {
+                    item: ResponseItem::Message { content, .. },
+                    ..
+                }) => format!("{content:?}"),
+                Ok(ResponseEvent::Completed { response_id, .. }) => response_id,
+                Ok(other) => panic!("unexpected event: {other:?}"),
+                Err(err) => panic!("stream should have resumed: {err}"),
+            });
+        }
+
+        assert_eq!(kinds.len(), 4, "{kinds:?}");
+        assert_eq!(kinds[0], "created");
+        assert!(kinds[1].contains("Hello"));
+        assert!(kinds[2].contains("World"));
+        assert_eq!(kinds[3], "resp_1");
+    }
+
     #[tokio::test]
     async fn error_when_error_event() {
         let raw_error = r#"{"type":"response.failed","sequence_number":3,"response":{"id":"resp_689bcf18d7f08194bf3440ba62fe05d803fee0cdac429894","object":"response","created_at":1755041560,"status":"failed","background":false,"error":{"code":"rate_limit_exceeded","message":"Rate limit reached for gpt-5.1 in organization org-AAA on tokens per min (TPM): Limit 30000, Used 22999, Requested 12528. Please try again in 11.054s. Visit https://platform.openai.com/account/rate-limits to learn more."}, "usage":null,"user":null,"metadata":{}}}"#;
@@ -2208,6 +3277,15 @@ mod tests {
             stream_idle_timeout_ms: Some(1000),
             requires_openai_auth: false,
             openrouter: None,
+            proxy: None,
+            ca_bundle_path: None,
+            client_cert_path: None,
+            client_key_path: None,
+            fallback_provider: None,
+            azure_ad: None,
+            vertex: None,
+            mock: None,
+            retry_degenerate_responses: false,
         };
 
         let events = collect_events(&[sse1.as_bytes()], provider).await;
@@ -2314,6 +3392,15 @@ mod tests {
                 stream_idle_timeout_ms: Some(1000),
                 requires_openai_auth: false,
                 openrouter: None,
+                proxy: None,
+                ca_bundle_path: None,
+                client_cert_path: None,
+                client_key_path: None,
+                fallback_provider: None,
+                azure_ad: None,
+                vertex: None,
+                mock: None,
+                retry_degenerate_responses: false,
             };
 
             let out = run_sse(evs, provider).await;
@@ -2326,6 +3413,99 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn degenerate_responses_become_retryable_anomalies() {
+        let provider = ModelProviderInfo {
+            name: "test".to_string(),
+            base_url: Some("https://test.com".to_string()),
+            env_key: Some("TEST_API_KEY".to_string()),
+            env_key_instructions: None,
+            wire_api: WireApi::Responses,
+            query_params: None,
+            http_headers: None,
+            env_http_headers: None,
+            request_max_retries: Some(0),
+            stream_max_retries: Some(0),
+            stream_idle_timeout_ms: Some(1000),
+            requires_openai_auth: false,
+            openrouter: None,
+            proxy: None,
+            ca_bundle_path: None,
+            client_cert_path: None,
+            client_key_path: None,
+            fallback_provider: None,
+            azure_ad: None,
+            vertex: None,
+            mock: None,
+            retry_degenerate_responses: true,
+        };
+        let completed = json!({
+            "type": "response.completed",
+            "response": { "id": "resp1" }
+        });
+        let truncated_call = json!({
+            "type": "response.output_item.done",
+            "item": {
+                "type": "function_call",
+                "name": "shell",
+                "arguments": "{\"command\": [\"ls",
+                "call_id": "c1"
+            }
+        });
+
+        for (events, expected) in [
+            (
+                vec![completed.clone()],
+                "[anomaly:empty_completion] response completed without any output",
+            ),
+            (
+                vec![truncated_call, completed],
+                "[anomaly:truncated] arguments for function call `shell` are not valid JSON (15 bytes)",
+            ),
+        ] {
+            let chunks: Vec<String> = events
+                .iter()
+                .map(|e| format!("event: {}\ndata: {e}\n\n", e["type"].as_str().unwrap()))
+                .collect();
+            let chunks: Vec<&[u8]> = chunks.iter().map(String::as_bytes).collect();
+            let out = collect_events(&chunks, provider.clone()).await;
+            match out.as_slice() {
+                [Err(CodexErr::Stream(msg, None, _))] => assert_eq!(msg, expected),
+                other => panic!("unexpected events: {other:?}"),
+            }
+        }
+
+        // A looping response is cut off mid-stream, before it completes.
+        let delta = json!({"type": "response.output_text.delta", "delta": "the the "});
+        let chunks: Vec<String> = std::iter::repeat_n(&delta, 1000)
+            .chain([&completed])
+            .map(|e| format!("event: {}\ndata: {e}\n\n", e["type"].as_str().unwrap()))
+            .collect();
+        let chunks: Vec<&[u8]> = chunks.iter().map(String::as_bytes).collect();
+        let out = collect_events(&chunks, provider.clone()).await;
+        match out.last() {
+            Some(Err(CodexErr::Stream(msg, None, _))) => {
+                assert!(msg.starts_with("[anomaly:repetition] output repeated \"the \""));
+            }
+            other => panic!("unexpected last event: {other:?}"),
+        }
+        assert!(
+            !out.iter()
+                .any(|ev| matches!(ev, Ok(ResponseEvent::Completed { .. })))
+        );
+
+        // Without the opt-in, empty and looping responses pass through.
+        let provider = ModelProviderInfo {
+            retry_degenerate_responses: false,
+            ..provider
+        };
+        let out = collect_events(&chunks, provider).await;
+        assert!(matches!(
+            out.last(),
+            Some(Ok(ResponseEvent::Completed { .. }))
+        ));
+    }
+
     fn fixed_now() -> DateTime<Utc> {
         Utc.with_ymd_and_hms(2025, 11, 7, 12, 0, 0).unwrap()
     }
@@ -2562,6 +3742,15 @@ mod tests {
             stream_idle_timeout_ms: Some(1000),
             requires_openai_auth: false,
             openrouter: None,
+            proxy: None,
+            ca_bundle_path: None,
+            client_cert_path: None,
+            client_key_path: None,
+            fallback_provider: None,
+            azure_ad: None,
+            vertex: None,
+            mock: None,
+            retry_degenerate_responses: false,
         };
 
         let events = collect_events(&[sse1.as_bytes()], provider).await;
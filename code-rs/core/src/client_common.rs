@@ -1,6 +1,7 @@
 use crate::agent_defaults::model_guide_markdown;
 use crate::config_types::ReasoningEffort as ReasoningEffortConfig;
 use crate::config_types::ReasoningSummary as ReasoningSummaryConfig;
+use crate::config_types::SamplingParams;
 use crate::config_types::TextVerbosity as TextVerbosityConfig;
 use crate::config_types::UiLocale;
 use crate::environment_context::EnvironmentContext;
@@ -9,6 +10,7 @@ use crate::model_family::ModelFamily;
 use crate::openai_tools::OpenAiTool;
 use crate::protocol::RateLimitSnapshotEvent;
 use crate::protocol::TokenUsage;
+use crate::protocol::TurnLatencyEvent;
 use code_apply_patch::APPLY_PATCH_TOOL_INSTRUCTIONS;
 use code_protocol::models::ContentItem;
 use code_protocol::models::ResponseItem;
@@ -96,6 +98,10 @@ pub struct Prompt {
 
     /// Optional override for the model guide placeholder in the developer prompt.
     pub model_descriptions: Option<String>,
+
+    /// Sampling settings for this request; unset values fall back to the
+    /// `[sampling]` config.
+    pub sampling: SamplingParams,
 }
 
 impl Default for Prompt {
@@ -118,6 +124,7 @@ impl Default for Prompt {
             log_tag: None,
             session_id_override: None,
             model_descriptions: None,
+            sampling: SamplingParams::default(),
         }
     }
 }
@@ -328,6 +335,15 @@ pub enum ResponseEvent {
         content_index: Option<u32>,
     },
     ReasoningSummaryPartAdded,
+    /// A fragment of a tool call's JSON arguments as the model streams them.
+    /// The complete call still arrives as an `OutputItemDone`.
+    ToolCallArgumentsDelta {
+        delta: String,
+        call_id: Option<String>,
+        name: Option<String>,
+        item_id: Option<String>,
+        output_index: Option<u32>,
+    },
     WebSearchCallBegin {
         call_id: String,
     },
@@ -336,6 +352,9 @@ pub enum ResponseEvent {
         query: Option<String>,
     },
     RateLimits(RateLimitSnapshotEvent),
+    /// Timings for the request behind this stream, sent just before
+    /// `Completed` when `turn_latency_events` is enabled.
+    Latency(TurnLatencyEvent),
 }
 
 #[derive(Debug, Serialize)]
@@ -488,6 +507,12 @@ pub(crate) struct ResponsesApiRequest<'a> {
     pub(crate) include: Vec<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) prompt_cache_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) seed: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) temperature: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) top_p: Option<f64>,
 }
 
 pub(crate) fn create_reasoning_param_for_request(
@@ -509,6 +534,19 @@ pub(crate) fn create_reasoning_param_for_request(
 
 pub struct ResponseStream {
     pub(crate) rx_event: mpsc::Receiver<Result<ResponseEvent>>,
+    /// Provider and model that accepted the request, set by
+    /// `ModelClient::stream`.
+    pub(crate) served_by: Option<ServedBy>,
+    /// Failed attempts that were retried before the provider accepted the
+    /// request.
+    pub(crate) retries: u64,
+}
+
+/// The provider (its `model_providers` key) and model behind a stream.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ServedBy {
+    pub provider_id: String,
+    pub model: String,
 }
 
 impl Stream for ResponseStream {
@@ -673,6 +711,9 @@ mod tests {
             stream: true,
             include: vec![],
             prompt_cache_key: None,
+            seed: None,
+            temperature: None,
+            top_p: None,
             text: Some(Text {
                 verbosity: OpenAiTextVerbosity::Low,
                 format: None,
@@ -711,6 +752,9 @@ mod tests {
             stream: true,
             include: vec![],
             prompt_cache_key: None,
+            seed: None,
+            temperature: None,
+            top_p: None,
             text: Some(Text {
                 verbosity: OpenAiTextVerbosity::Medium,
                 format: Some(TextFormat {
@@ -758,10 +802,44 @@ mod tests {
             stream: true,
             include: vec![],
             prompt_cache_key: None,
+            seed: None,
+            temperature: None,
+            top_p: None,
             text: None,
         };
 
         let v = serde_json::to_value(&req).expect("json");
         assert!(v.get("text").is_none());
+        assert!(v.get("temperature").is_none());
+    }
+
+    #[test]
+    fn prompt_sampling_overrides_config_per_setting() {
+        let prompt = SamplingParams {
+            seed: Some(7),
+            temperature: None,
+            top_p: None,
+        };
+        let config = SamplingParams {
+            seed: Some(1),
+            temperature: Some(0.0),
+            top_p: None,
+        };
+        assert_eq!(
+            prompt.or(config),
+            SamplingParams {
+                seed: Some(7),
+                temperature: Some(0.0),
+                top_p: None,
+            }
+        );
+        assert!(
+            SamplingParams {
+                top_p: Some(1.5),
+                ..SamplingParams::default()
+            }
+            .validate()
+            .is_err()
+        );
     }
 }
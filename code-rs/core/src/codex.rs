@@ -24,6 +24,7 @@ use crate::bridge_client::spawn_bridge_listener;
 use crate::config_types::ClientTools;
 use crate::config_types::ReasoningEffort as ReasoningEffortConfig;
 use crate::config_types::ReasoningSummary as ReasoningSummaryConfig;
+use crate::config_types::SamplingParams;
 use async_channel::Receiver;
 use async_channel::Sender;
 use base64::Engine;
@@ -65,6 +66,7 @@ use chrono::Local;
 use chrono::Utc;
 use code_protocol::mcp_protocol::AuthMode;
 use code_protocol::models::WebSearchAction;
+use code_protocol::protocol::CancelledToolCall;
 use code_protocol::protocol::RolloutItem;
 use code_protocol::protocol::TurnAbortReason;
 use code_protocol::protocol::TurnAbortedEvent;
@@ -76,6 +78,7 @@ use shlex::split as shlex_split;
 use shlex::try_join as shlex_try_join;
 use tokio::sync::oneshot;
 use tokio::task::AbortHandle;
+use tokio_util::sync::CancellationToken;
 use tracing::debug;
 use tracing::error;
 use tracing::info;
@@ -232,6 +235,10 @@ pub(crate) struct TurnContext {
     pub(crate) shell_environment_policy: ShellEnvironmentPolicy,
     pub(crate) is_review_mode: bool,
     pub(crate) text_format_override: Option<TextFormat>,
+    /// Model for this turn's requests when it differs from the session model.
+    pub(crate) model_override: Option<String>,
+    /// Sampling settings for this turn's requests, on top of `[sampling]`.
+    pub(crate) sampling: SamplingParams,
     pub(crate) ui_locale: UiLocale,
 }
 
@@ -837,6 +844,7 @@ use crate::client::ModelClient;
 use crate::client_common::Prompt;
 use crate::client_common::REVIEW_PROMPT;
 use crate::client_common::ResponseEvent;
+use crate::client_common::ServedBy;
 use crate::client_common::TextFormat;
 use crate::config::Config;
 use crate::config::persist_model_selection;
@@ -849,6 +857,7 @@ use crate::dry_run_guard::DryRunAnalysis;
 use crate::dry_run_guard::DryRunDisposition;
 use crate::dry_run_guard::DryRunGuardState;
 use crate::dry_run_guard::analyze_command;
+use crate::embeddings::WorkspaceSearch;
 use crate::environment_context::BrowserSnapshot;
 use crate::environment_context::EnvironmentContext;
 use crate::environment_context::EnvironmentContextDelta;
@@ -878,6 +887,7 @@ use crate::openai_tools::ToolsConfig;
 use crate::openai_tools::get_openai_tools;
 use crate::parse_command::parse_command;
 use crate::plan_tool::handle_update_plan;
+use crate::prefetch::ContextPrefetcher;
 use crate::project_doc::get_user_instructions;
 use crate::project_features::ProjectCommand;
 use crate::project_features::ProjectHook;
@@ -1213,6 +1223,9 @@ struct State {
     background_execs: std::collections::HashMap<String, BackgroundExecState>,
     /// Active foreground exec calls keyed by call_id (ExecCommandBegin/End lifecycle)
     running_execs: HashMap<String, RunningExecMeta>,
+    /// Tool invocations currently executing, in start order. Reported in
+    /// `TurnAborted` when an interrupt cancels them.
+    in_flight_tool_calls: Vec<InFlightToolCall>,
     next_internal_sub_id: u64,
     token_usage_info: Option<TokenUsageInfo>,
     latest_rate_limits: Option<RateLimitSnapshotEvent>,
@@ -1407,6 +1420,9 @@ pub(crate) struct Session {
     /// sessions can be replayed or inspected later.
     rollout: Mutex<Option<RolloutRecorder>>,
     state: Mutex<State>,
+    /// Cancelled by `Op::Interrupt` so in-flight tools stop promptly; replaced
+    /// with a fresh token after each cancellation.
+    tool_cancel: Mutex<CancellationToken>,
     code_linux_sandbox_exe: Option<PathBuf>,
     user_shell: shell::Shell,
     show_raw_agent_reasoning: bool,
@@ -1417,6 +1433,9 @@ pub(crate) struct Session {
     last_system_status: Mutex<Option<String>>,
     /// Track the last screenshot path and hash to detect changes
     last_screenshot_info: Mutex<Option<(PathBuf, Vec<u8>, Vec<u8>)>>, // (path, phash, dhash)
+    /// Provider and model behind the last stream, to record a turn context
+    /// only when failover changes them.
+    last_served_by: Mutex<Option<ServedBy>>,
     confirm_guard: ConfirmGuardRuntime,
     project_hooks: ProjectHooks,
     project_commands: Vec<ProjectCommand>,
@@ -1426,9 +1445,54 @@ pub(crate) struct Session {
     self_handle: Weak<Session>,
     active_review: Mutex<Option<ReviewRequest>>,
     next_turn_text_format: Mutex<Option<TextFormat>>,
+    next_turn_model: Mutex<Option<String>>,
+    next_turn_sampling: Mutex<Option<SamplingParams>>,
     env_ctx_v2: bool,
     retention_config: crate::config_types::RetentionConfig,
     model_descriptions: Option<String>,
+    /// Reads files the model is likely to need while it is still streaming.
+    prefetcher: ContextPrefetcher,
+    /// Backs the `semantic_search` tool; `None` without `[embeddings]`.
+    semantic_search: Option<Arc<WorkspaceSearch>>,
+}
+
+struct InFlightToolCall {
+    sub_id: String,
+    call_id: String,
+    tool: String,
+}
+
+impl State {
+    fn take_in_flight_tool_calls(&mut self, sub_id: &str) -> Vec<CancelledToolCall> {
+        let (cancelled, remaining) = std::mem::take(&mut self.in_flight_tool_calls)
+            .into_iter()
+            .partition::<Vec<_>, _>(|call| call.sub_id == sub_id);
+        self.in_flight_tool_calls = remaining;
+        cancelled
+            .into_iter()
+            .map(|call| CancelledToolCall {
+                call_id: call.call_id,
+                tool: call.tool,
+            })
+            .collect()
+    }
+}
+
+/// Keeps a tool invocation listed as in flight until the handler returns or
+/// its future is dropped.
+struct InFlightToolGuard<'a> {
+    sess: &'a Session,
+    call_id: String,
+}
+
+impl Drop for InFlightToolGuard<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.sess.state.lock() {
+            state
+                .in_flight_tool_calls
+                .retain(|call| call.call_id != self.call_id);
+        }
+    }
 }
 
 struct HookGuard<'a> {
@@ -1858,7 +1922,8 @@ impl Session {
     pub fn set_task(&self, agent: AgentTask) {
         let mut state = self.state.lock().unwrap();
         if let Some(current_task) = state.current_task.take() {
-            current_task.abort(TurnAbortReason::Replaced);
+            let cancelled = state.take_in_flight_tool_calls(&current_task.sub_id);
+            current_task.abort(TurnAbortReason::Replaced, cancelled);
         }
         state.current_task = Some(agent);
     }
@@ -2152,6 +2217,13 @@ impl Session {
             shell_environment_policy: self.shell_environment_policy.clone(),
             is_review_mode: false,
             text_format_override: self.next_turn_text_format.lock().unwrap().take(),
+            model_override: self.next_turn_model.lock().unwrap().take(),
+            sampling: self
+                .next_turn_sampling
+                .lock()
+                .unwrap()
+                .take()
+                .unwrap_or_default(),
             ui_locale: self.ui_locale.clone(),
         })
     }
@@ -2201,7 +2273,7 @@ impl Session {
             &sub_id,
             EventMsg::ApplyPatchApprovalRequest(ApplyPatchApprovalRequestEvent {
                 call_id: call_id.clone(),
-                changes: convert_apply_patch_to_protocol(action),
+                changes: convert_apply_patch_to_protocol(action, &self.prefetcher),
                 reason,
                 grant_root,
             }),
@@ -2240,6 +2312,16 @@ impl Session {
         self.state.lock().unwrap().history.record_items(items);
     }
 
+    /// Starts reading files the user's message mentions so they are warm by
+    /// the time the model asks for them.
+    fn prefetch_from_input(&self, items: &[InputItem]) {
+        for item in items {
+            if let InputItem::Text { text } = item {
+                self.prefetcher.prefetch_from_text(text);
+            }
+        }
+    }
+
     /// Clean up old screenshots and system status messages from conversation history
     /// This is called when a new user message arrives to keep history manageable
     async fn cleanup_old_status_items(&self) {
@@ -2309,6 +2391,17 @@ impl Session {
         }
     }
 
+    /// Remembers the provider and model behind the latest stream. Returns
+    /// true when they differ from the previous stream's.
+    fn note_served_by(&self, served_by: &ServedBy) -> bool {
+        let mut last = self.last_served_by.lock().unwrap();
+        if last.as_ref() == Some(served_by) {
+            return false;
+        }
+        *last = Some(served_by.clone());
+        true
+    }
+
     fn clone_rollout_recorder(&self) -> Option<RolloutRecorder> {
         let guard = self.rollout.lock().unwrap();
         guard.as_ref().cloned()
@@ -2380,6 +2473,7 @@ impl Session {
         seq_hint: Option<u64>,
         output_index: Option<u32>,
         attempt_req: u64,
+        sandbox_denied: bool,
     ) {
         let ExecToolCallOutput {
             stdout,
@@ -2410,6 +2504,7 @@ impl Session {
                 stderr,
                 exit_code: *exit_code,
                 duration: *duration,
+                sandbox_denied,
             })
         };
         let order = crate::protocol::OrderMeta {
@@ -2535,6 +2630,7 @@ impl Session {
                     stderr,
                     exit_code,
                     duration: Duration::ZERO,
+                    sandbox_denied: false,
                 });
                 let event = self.make_event_with_order(
                     sub_id,
@@ -2643,7 +2739,9 @@ impl Session {
         let output_stderr;
         let borrowed: &ExecToolCallOutput = match &result {
             Ok(output) => output,
-            Err(CodexErr::Sandbox(SandboxErr::Timeout { output })) => output,
+            Err(CodexErr::Sandbox(
+                SandboxErr::Timeout { output } | SandboxErr::Denied { output },
+            )) => output,
             Err(e) => {
                 output_stderr = ExecToolCallOutput {
                     exit_code: -1,
@@ -2665,6 +2763,7 @@ impl Session {
             seq_hint.map(|h| h.saturating_add(1)),
             output_index,
             attempt_req,
+            matches!(result, Err(CodexErr::Sandbox(SandboxErr::Denied { .. }))),
         )
         .await;
 
@@ -3602,15 +3701,47 @@ impl Session {
         arguments: Option<serde_json::Value>,
         timeout: Option<Duration>,
     ) -> anyhow::Result<CallToolResult> {
-        self.mcp_connection_manager
-            .call_tool(server, tool, arguments, timeout)
-            .await
+        let cancel = self.tool_cancellation_token();
+        tokio::select! {
+            result = self
+                .mcp_connection_manager
+                .call_tool(server, tool, arguments, timeout) => result,
+            _ = cancel.cancelled() => Err(anyhow::anyhow!("cancelled by user")),
+        }
+    }
+
+    /// Token that fires when the user interrupts the current turn. Tool
+    /// handlers grab it when they start so an interrupt stops them mid-flight.
+    pub(crate) fn tool_cancellation_token(&self) -> CancellationToken {
+        self.tool_cancel.lock().unwrap().clone()
+    }
+
+    /// Registers `call_id` as in flight for the lifetime of the returned guard.
+    fn track_tool_call(&self, sub_id: &str, call_id: &str, tool: &str) -> InFlightToolGuard<'_> {
+        self.state
+            .lock()
+            .unwrap()
+            .in_flight_tool_calls
+            .push(InFlightToolCall {
+                sub_id: sub_id.to_string(),
+                call_id: call_id.to_string(),
+                tool: tool.to_string(),
+            });
+        InFlightToolGuard {
+            sess: self,
+            call_id: call_id.to_string(),
+        }
     }
 
     fn abort(&self) {
         info!("Aborting existing session");
 
         self.mark_all_running_execs_as_cancelled();
+        std::mem::replace(
+            &mut *self.tool_cancel.lock().unwrap(),
+            CancellationToken::new(),
+        )
+        .cancel();
 
         let mut state = self.state.lock().unwrap();
         state.pending_approvals.clear();
@@ -3621,14 +3752,17 @@ impl Session {
         state.turn_scratchpad = None;
         // Take current task while holding the lock, then drop the lock BEFORE calling abort
         let current = state.current_task.take();
+        let cancelled = current
+            .as_ref()
+            .map(|agent| state.take_in_flight_tool_calls(&agent.sub_id))
+            .unwrap_or_default();
         drop(state);
         if let Some(agent) = current {
-            agent.abort(TurnAbortReason::Interrupted);
+            agent.abort(TurnAbortReason::Interrupted, cancelled);
         }
-        // Also terminate any running exec sessions (PTY-based) so child processes do not linger.
-        // Best-effort cleanup for PTY-based exec sessions would go here. The
-        // PTY implementation already kills processes on session drop; in the
-        // common LocalShellCall path we also kill processes immediately via
+        // Running shell commands observe the cancelled token and kill their
+        // process group (SIGTERM, then SIGKILL after a grace period); commands
+        // whose future is dropped instead are reaped the same way by
         // KillOnDrop in exec.rs.
     }
 
@@ -3951,6 +4085,7 @@ impl Drop for ExecDropGuard {
             stderr,
             exit_code,
             duration: Duration::ZERO,
+            sandbox_denied: false,
         });
 
         if let Some(session) = self.session.upgrade() {
@@ -4187,12 +4322,15 @@ impl AgentTask {
         }
     }
 
-    fn abort(self, reason: TurnAbortReason) {
+    fn abort(self, reason: TurnAbortReason, cancelled_tool_calls: Vec<CancelledToolCall>) {
         if !self.handle.is_finished() {
             self.handle.abort();
             let event = self.sess.make_event(
                 &self.sub_id,
-                EventMsg::TurnAborted(TurnAbortedEvent { reason }),
+                EventMsg::TurnAborted(TurnAbortedEvent {
+                    reason,
+                    cancelled_tool_calls,
+                }),
             );
             let sess = self.sess.clone();
             let sub_id = self.sub_id.clone();
@@ -4490,7 +4628,14 @@ async fn submission_loop(
                 };
 
                 // Create debug logger based on config
-                let debug_logger = match crate::debug_logger::DebugLogger::new(config.debug) {
+                let debug_logger = match crate::at_rest::cipher_for(
+                    config.debug && config.encrypt_at_rest,
+                    &config.code_home,
+                )
+                .and_then(|cipher| {
+                    Ok(crate::debug_logger::DebugLogger::new(config.debug)?
+                        .with_at_rest_cipher(cipher))
+                }) {
                     Ok(logger) => std::sync::Arc::new(std::sync::Mutex::new(logger)),
                     Err(e) => {
                         warn!("Failed to create debug logger: {}", e);
@@ -4626,6 +4771,20 @@ async fn submission_loop(
                 tools_config.web_search_allowed_domains =
                     config.tools_web_search_allowed_domains.clone();
 
+                let semantic_search = match &config.embeddings {
+                    Some(_) => match WorkspaceSearch::open(&config).await {
+                        Ok(search) => Some(Arc::new(search)),
+                        Err(err) => {
+                            let message = format!("semantic_search is unavailable: {err:#}");
+                            warn!("{message}");
+                            mcp_connection_errors.push(message);
+                            None
+                        }
+                    },
+                    None => None,
+                };
+                tools_config.semantic_search = semantic_search.is_some();
+
                 let mut agent_models: Vec<String> = if config.agents.is_empty() {
                     default_agent_configs()
                         .into_iter()
@@ -4657,6 +4816,8 @@ async fn submission_loop(
                     approval_policy,
                     sandbox_policy,
                     shell_environment_policy: config.shell_environment_policy.clone(),
+                    prefetcher: ContextPrefetcher::new(cwd.clone()),
+                    semantic_search,
                     cwd,
                     _writable_roots: writable_roots,
                     mcp_connection_manager,
@@ -4667,6 +4828,7 @@ async fn submission_loop(
                     ui_locale: config.ui_locale.clone(),
                     notify,
                     state: Mutex::new(state),
+                    tool_cancel: Mutex::new(CancellationToken::new()),
                     rollout: Mutex::new(rollout_recorder),
                     code_linux_sandbox_exe: config.code_linux_sandbox_exe.clone(),
                     disable_response_storage,
@@ -4675,6 +4837,7 @@ async fn submission_loop(
                     pending_browser_screenshots: Mutex::new(Vec::new()),
                     last_system_status: Mutex::new(None),
                     last_screenshot_info: Mutex::new(None),
+                    last_served_by: Mutex::new(None),
                     confirm_guard: ConfirmGuardRuntime::from_config(&config.confirm_guard),
                     project_hooks: config.project_hooks.clone(),
                     project_commands: config.project_commands.clone(),
@@ -4684,6 +4847,8 @@ async fn submission_loop(
                     self_handle: Weak::new(),
                     active_review: Mutex::new(None),
                     next_turn_text_format: Mutex::new(None),
+                    next_turn_model: Mutex::new(None),
+                    next_turn_sampling: Mutex::new(None),
                     env_ctx_v2: config.env_ctx_v2,
                     retention_config: config.retention.clone(),
                     model_descriptions,
@@ -4772,6 +4937,18 @@ async fn submission_loop(
                     }
                 }
 
+                if let Some(notice) = crate::git_info::take_git_missing_notice() {
+                    let event = sess_arc.make_event(
+                        &sub.id,
+                        EventMsg::BackgroundEvent(BackgroundEventEvent {
+                            message: notice.to_string(),
+                        }),
+                    );
+                    if let Err(e) = tx_event.send(event).await {
+                        warn!("failed to send git notice event: {e}");
+                    }
+                }
+
                 if let Some(sess_arc) = &sess {
                     spawn_bridge_listener(sess_arc.clone());
                     sess_arc
@@ -4819,6 +4996,8 @@ async fn submission_loop(
                 // This prevents token buildup from old screenshots/status messages
                 sess.cleanup_old_status_items().await;
 
+                sess.prefetch_from_input(&items);
+
                 // Abort synchronously here to avoid a race that can kill the
                 // newly spawned agent if the async abort runs after set_task.
                 sess.notify_wait_interrupted(WaitInterruptReason::UserMessage);
@@ -4838,6 +5017,7 @@ async fn submission_loop(
                     }
                 };
 
+                sess.prefetch_from_input(&items);
                 if sess.has_running_task() {
                     let mut response_item = response_input_from_core_items(items.clone());
                     sess.enforce_user_message_limits(&sub.id, &mut response_item);
@@ -4993,6 +5173,19 @@ async fn submission_loop(
                     }
                 });
             }
+            Op::GetProviderHealth => {
+                let event = Event {
+                    id: sub.id.clone(),
+                    event_seq: 0,
+                    msg: EventMsg::ProviderHealth(crate::protocol::ProviderHealthEvent {
+                        endpoints: crate::provider_health::global().snapshot(),
+                    }),
+                    order: None,
+                };
+                if let Err(e) = tx_event.send(event).await {
+                    warn!("failed to send ProviderHealth event: {e}");
+                }
+            }
             // Upstream protocol no longer includes ListMcpTools; skip handling here.
             Op::ListCustomPrompts => {
                 let sess = match sess.as_ref() {
@@ -5073,6 +5266,26 @@ async fn submission_loop(
                 };
                 *sess_arc.next_turn_text_format.lock().unwrap() = Some(format);
             }
+            Op::SetNextTurnModel { model } => {
+                let sess_arc = match sess.as_ref() {
+                    Some(sess) => Arc::clone(sess),
+                    None => {
+                        send_no_session_event(sub.id).await;
+                        continue;
+                    }
+                };
+                *sess_arc.next_turn_model.lock().unwrap() = Some(model);
+            }
+            Op::SetNextTurnSampling { sampling } => {
+                let sess_arc = match sess.as_ref() {
+                    Some(sess) => Arc::clone(sess),
+                    None => {
+                        send_no_session_event(sub.id).await;
+                        continue;
+                    }
+                };
+                *sess_arc.next_turn_sampling.lock().unwrap() = Some(sampling);
+            }
             Op::Shutdown => {
                 info!("Shutting down Codex instance");
 
@@ -5160,7 +5373,13 @@ async fn spawn_review_thread(
     }
     let review_config = Arc::new(review_config);
 
-    let review_debug_logger = match crate::debug_logger::DebugLogger::new(review_config.debug) {
+    let review_debug_logger = match crate::at_rest::cipher_for(
+        review_config.debug && review_config.encrypt_at_rest,
+        &review_config.code_home,
+    )
+    .and_then(|cipher| {
+        Ok(crate::debug_logger::DebugLogger::new(review_config.debug)?.with_at_rest_cipher(cipher))
+    }) {
         Ok(logger) => Arc::new(Mutex::new(logger)),
         Err(err) => {
             warn!("failed to create review debug logger: {err}");
@@ -5203,6 +5422,8 @@ async fn spawn_review_thread(
         shell_environment_policy: parent_turn_context.shell_environment_policy.clone(),
         is_review_mode: true,
         text_format_override: None,
+        model_override: None,
+        sampling: SamplingParams::default(),
         ui_locale: parent_turn_context.ui_locale.clone(),
     });
 
@@ -5806,12 +6027,15 @@ async fn run_turn(
             include_additional_instructions: true,
             prepend_developer_messages: Vec::new(),
             text_format: tc.text_format_override.clone(),
-            model_override: None,
-            model_family_override: None,
+            model_override: tc.model_override.clone(),
+            model_family_override: tc.model_override.as_deref().map(|model| {
+                find_family_for_model(model).unwrap_or_else(|| derive_default_model_family(model))
+            }),
             output_schema: None,
             log_tag: Some("codex/turn".to_string()),
             session_id_override: None,
             model_descriptions: sess.model_descriptions.clone(),
+            sampling: tc.sampling,
         };
 
         // Start a new scratchpad for this HTTP attempt
@@ -5830,6 +6054,16 @@ async fn run_turn(
             }
             Err(CodexErr::Interrupted) => return Err(CodexErr::Interrupted),
             Err(CodexErr::EnvVar(var)) => return Err(CodexErr::EnvVar(var)),
+            Err(
+                e @ (CodexErr::ContentBlocked(_)
+                | CodexErr::ProviderUnavailable(_)
+                | CodexErr::RequestTooLarge(_)
+                | CodexErr::MockFixture(_)
+                | CodexErr::DryRun
+                | CodexErr::Tls(_)),
+            ) => {
+                return Err(e);
+            }
             Err(
                 e @ (CodexErr::UsageLimitReached(_)
                 | CodexErr::UsageNotIncluded
@@ -6283,6 +6517,22 @@ async fn try_run_turn(
             return Err(e);
         }
     };
+    // Retries and later turns usually hit the same provider; only a switch
+    // (failover, or back to the first provider) is worth a rollout entry.
+    if let Some(served_by) = stream.served_by.clone()
+        && sess.note_served_by(&served_by)
+        && let Some(item) = crate::protocol::turn_context_item(
+            &sess.cwd,
+            sess.approval_policy,
+            &sess.sandbox_policy,
+            served_by.model,
+            served_by.provider_id,
+            sess.client.get_reasoning_effort(),
+            sess.client.get_reasoning_summary(),
+        )
+    {
+        sess.persist_rollout_items(&[RolloutItem::TurnContext(item)]).await;
+    }
 
     let mut output = Vec::new();
     loop {
@@ -6318,6 +6568,7 @@ async fn try_run_turn(
                 sequence_number,
                 output_index,
             } => {
+                sess.prefetcher.prefetch_from_item(&item);
                 let response = handle_response_item(
                     sess,
                     turn_diff_tracker,
@@ -6329,6 +6580,10 @@ async fn try_run_turn(
                 )
                 .await?;
 
+                if let Some(response) = &response {
+                    sess.prefetcher.prefetch_from_tool_output(response);
+                }
+
                 // Save into scratchpad so we can seed a retry if the stream drops later.
                 sess.scratchpad_push(&item, &response, sub_id);
 
@@ -6372,6 +6627,9 @@ async fn try_run_turn(
             } => {
                 let (new_info, rate_limits, should_emit);
                 {
+                    if let Some(usage) = token_usage.as_ref() {
+                        crate::prompt_cache::record_usage(usage);
+                    }
                     let mut state = sess.state.lock().unwrap();
                     let info = TokenUsageInfo::new_or_append(
                         &state.token_usage_info,
@@ -6524,6 +6782,12 @@ async fn try_run_turn(
                     sess.tx_event.send(stamped).await.ok();
                 }
             }
+            // Tool calls run once the complete call arrives as OutputItemDone.
+            ResponseEvent::ToolCallArgumentsDelta { .. } => {}
+            ResponseEvent::Latency(latency) => {
+                let msg = EventMsg::TurnLatency(latency);
+                sess.tx_event.send(sess.make_event(sub_id, msg)).await.ok();
+            }
             ResponseEvent::RateLimits(snapshot) => {
                 let mut state = sess.state.lock().unwrap();
                 state.latest_rate_limits = Some(snapshot.clone());
@@ -6690,6 +6954,7 @@ async fn handle_response_item(
                 }
             };
 
+            let _in_flight = sess.track_tool_call(sub_id, &effective_call_id, "local_shell");
             let exec_params = to_exec_params(params, sess);
             Some(
                 handle_container_exec_with_params(
@@ -6830,6 +7095,7 @@ async fn handle_function_call(
     attempt_req: u64,
 ) -> ResponseInputItem {
     let ctx = ToolCallCtx::new(sub_id.clone(), call_id.clone(), seq_hint, output_index);
+    let _in_flight = sess.track_tool_call(&sub_id, &call_id, &name);
     match name.as_str() {
         "container.exec" | "shell" => {
             let params = match parse_container_exec_arguments(arguments, sess, &call_id) {
@@ -6858,6 +7124,7 @@ async fn handle_function_call(
         "web_fetch" => handle_web_fetch(sess, &ctx, arguments).await,
         "wait" => handle_wait(sess, &ctx, arguments).await,
         "kill" => handle_kill(sess, &ctx, arguments).await,
+        "semantic_search" => handle_semantic_search(sess, &ctx, arguments).await,
         _ => {
             match sess.mcp_connection_manager.parse_tool_name(&name) {
                 Some((server, tool_name)) => {
@@ -8038,6 +8305,73 @@ async fn handle_wait(sess: &Session, ctx: &ToolCallCtx, arguments: String) -> Re
     ).await
 }
 
+// Search the embeddings index for text matching a query.
+async fn handle_semantic_search(
+    sess: &Session,
+    ctx: &ToolCallCtx,
+    arguments: String,
+) -> ResponseInputItem {
+    #[derive(serde::Deserialize)]
+    struct Params {
+        query: String,
+        #[serde(default)]
+        limit: Option<usize>,
+    }
+
+    let params_for_event = serde_json::from_str::<serde_json::Value>(&arguments).ok();
+    let call_id = ctx.call_id.clone();
+    execute_custom_tool(
+        sess,
+        ctx,
+        "semantic_search".to_string(),
+        params_for_event,
+        || async move {
+            let failure = |content: String| ResponseInputItem::FunctionCallOutput {
+                call_id: call_id.clone(),
+                output: FunctionCallOutputPayload {
+                    content,
+                    success: Some(false),
+                },
+            };
+            let params: Params = match serde_json::from_str(&arguments) {
+                Ok(params) => params,
+                Err(e) => return failure(format!("Invalid semantic_search arguments: {e}")),
+            };
+            let Some(search) = sess.semantic_search.as_ref() else {
+                return failure(
+                    "semantic_search needs an [embeddings] table in config.toml".to_string(),
+                );
+            };
+            let limit = params.limit.unwrap_or(8).clamp(1, 32);
+            let hits = match search.search(&params.query, limit).await {
+                Ok(hits) => hits,
+                Err(e) => return failure(format!("semantic_search failed: {e:#}")),
+            };
+            let content = if hits.is_empty() {
+                "No indexed files matched.".to_string()
+            } else {
+                hits.iter()
+                    .map(|hit| {
+                        format!(
+                            "{} (score {:.2})\n{}",
+                            hit.document.source, hit.score, hit.document.text
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n\n")
+            };
+            ResponseInputItem::FunctionCallOutput {
+                call_id: call_id.clone(),
+                output: FunctionCallOutputPayload {
+                    content,
+                    success: Some(true),
+                },
+            }
+        },
+    )
+    .await
+}
+
 // Kill a background shell execution by call_id.
 async fn handle_kill(sess: &Session, ctx: &ToolCallCtx, arguments: String) -> ResponseInputItem {
     use serde::Deserialize;
@@ -8165,6 +8499,7 @@ async fn handle_kill(sess: &Session, ctx: &ToolCallCtx, arguments: String) -> Re
                 stderr: output.stderr.text.clone(),
                 exit_code: output.exit_code,
                 duration: output.duration,
+                sandbox_denied: false,
             });
             let event = Event {
                 id: sub_id_for_end.clone(),
@@ -8364,6 +8699,7 @@ pub(crate) async fn handle_agent_tool(
             let files = create_opts.files.take();
             let write = create_opts.write.take();
             let read_only = create_opts.read_only.take();
+            let timeout_seconds = create_opts.timeout_seconds.take();
             let mut normalized_name = normalize_agent_name(create_opts.name.take());
             if normalized_name.is_none() {
                 normalized_name = derive_agent_name_from_task(&task);
@@ -8378,6 +8714,7 @@ pub(crate) async fn handle_agent_tool(
                 write,
                 read_only,
                 name: normalized_name.clone(),
+                timeout_seconds,
             };
 
             let mut create_event = serde_json::Map::new();
@@ -8430,6 +8767,9 @@ pub(crate) async fn handle_agent_tool(
             if let Some(flag) = read_only {
                 create_event.insert("read_only".to_string(), serde_json::Value::Bool(flag));
             }
+            if let Some(secs) = timeout_seconds {
+                create_event.insert("timeout_seconds".to_string(), serde_json::Value::from(secs));
+            }
             if let Some(ref name_str) = normalized_name
                 && !name_str.is_empty()
             {
@@ -8970,6 +9310,12 @@ pub(crate) async fn handle_run_agent(
                 agent_labels.push((agent_ids.last().cloned().unwrap(), label));
             }
 
+            if let Some(secs) = params.timeout_seconds.filter(|secs| *secs > 0) {
+                for agent_id in &agent_ids {
+                    manager.set_agent_timeout(agent_id, Duration::from_secs(secs));
+                }
+            }
+
             // Send agent status update event
             drop(manager); // Release the write lock first
             if !agent_ids.is_empty() {
@@ -10697,7 +11043,7 @@ async fn handle_container_exec_with_params(
                     },
                 };
             }
-            let changes = convert_apply_patch_to_protocol(&action);
+            let changes = convert_apply_patch_to_protocol(&action, &sess.prefetcher);
             turn_diff_tracker.on_patch_begin(&changes);
 
             match apply_patch::apply_patch(
@@ -10953,7 +11299,22 @@ async fn handle_container_exec_with_params(
     let backgrounded_task = backgrounded.clone();
     let suppress_event_flag_task = suppress_event_flag.clone();
     let display_label_task = display_label.clone();
+    let turn_cancel = sess.tool_cancellation_token();
     let task_handle = tokio::spawn(async move {
+        // An interrupt stops the command unless it has already been moved to
+        // the background, in which case it is expected to outlive the turn.
+        let exec_cancel = CancellationToken::new();
+        let cancel_watch = tokio::spawn({
+            let exec_cancel = exec_cancel.clone();
+            let backgrounded = backgrounded_task.clone();
+            async move {
+                turn_cancel.cancelled().await;
+                if !backgrounded.load(std::sync::atomic::Ordering::Relaxed) {
+                    exec_cancel.cancel();
+                }
+            }
+        });
+
         // Build stdout stream with tail capture. We cannot stamp via `Session` here,
         // but deltas will be delivered with neutral ordering which the UI tolerates.
         let stdout_stream = if exec_command_context.apply_patch.is_some() {
@@ -10966,6 +11327,7 @@ async fn handle_container_exec_with_params(
                 session: None,
                 tail_buf: Some(tail_buf_task.clone()),
                 order: Some(order_meta_for_deltas.clone()),
+                cancel: Some(exec_cancel),
             })
         };
 
@@ -10979,7 +11341,9 @@ async fn handle_container_exec_with_params(
             stdout_stream,
         )
         .await;
+        cancel_watch.abort();
 
+        let sandbox_denied = matches!(res, Err(CodexErr::Sandbox(SandboxErr::Denied { .. })));
         // Normalize to ExecToolCallOutput
         let (out, exit_code) = match res {
             Ok(o) => {
@@ -10989,6 +11353,26 @@ async fn handle_container_exec_with_params(
             Err(CodexErr::Sandbox(SandboxErr::Timeout { output })) => {
                 (output.as_ref().clone(), 124)
             }
+            // Keep the real exit status so clients can tell sandbox denials
+            // apart from commands that failed to spawn.
+            Err(CodexErr::Sandbox(SandboxErr::Denied { output })) => {
+                let exit = output.exit_code;
+                (output.as_ref().clone(), exit)
+            }
+            Err(CodexErr::Interrupted) => {
+                let (exit_code, msg) = synthetic_exec_end_payload(true);
+                (
+                    ExecToolCallOutput {
+                        exit_code,
+                        stdout: StreamOutput::new(String::new()),
+                        stderr: StreamOutput::new(msg.clone()),
+                        aggregated_output: StreamOutput::new(msg),
+                        duration: start.elapsed(),
+                        timed_out: false,
+                    },
+                    exit_code,
+                )
+            }
             Err(e) => {
                 let msg = get_error_message_ui(&e);
                 (
@@ -11012,6 +11396,7 @@ async fn handle_container_exec_with_params(
             stderr: out.stderr.text.clone(),
             exit_code,
             duration: out.duration,
+            sandbox_denied,
         });
         let ev = Event {
             id: sub_id_for_events.clone(),
@@ -11299,6 +11684,7 @@ async fn handle_sandbox_error(
                             output_index: None,
                             sequence_number: None,
                         }),
+                        cancel: Some(sess.tool_cancellation_token()),
                     })
                 },
             },
@@ -11704,9 +12090,19 @@ where
     let begin_event = sess.make_event_with_order(&ctx.sub_id, begin_msg, begin_order, ctx.seq_hint);
     sess.send_event(begin_event).await;
 
-    // Execute the tool
+    // Execute the tool; an interrupt abandons it.
     let start = Instant::now();
-    let result = tool_fn().await;
+    let cancel = sess.tool_cancellation_token();
+    let result = tokio::select! {
+        result = tool_fn() => result,
+        _ = cancel.cancelled() => ResponseInputItem::FunctionCallOutput {
+            call_id: ctx.call_id.clone(),
+            output: FunctionCallOutputPayload {
+                content: format!("{tool_name} cancelled by user"),
+                success: Some(false),
+            },
+        },
+    };
     let duration = start.elapsed();
 
     // Extract success/failure from result. Prefer explicit success flag when available.
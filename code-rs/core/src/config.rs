@@ -8,16 +8,20 @@ use crate::config_types::AgentConfig;
 use crate::config_types::AllowedCommand;
 use crate::config_types::AllowedCommandMatchKind;
 use crate::config_types::AutoDriveContinueMode;
+use crate::config_types::AutoDriveGoalChanges;
 use crate::config_types::AutoDriveSettings;
 use crate::config_types::BrowserConfig;
 use crate::config_types::CachedTerminalBackground;
 use crate::config_types::ClientTools;
 use crate::config_types::ConfirmGuardConfig;
+use crate::config_types::CoordinatorPromptMode;
 use crate::config_types::DEFAULT_OTEL_ENVIRONMENT;
+use crate::config_types::EmbeddingsConfig;
 use crate::config_types::GithubConfig;
 use crate::config_types::History;
 use crate::config_types::McpServerConfig;
 use crate::config_types::McpServerTransportConfig;
+use crate::config_types::ModelPrice;
 use crate::config_types::Notice;
 use crate::config_types::Notifications;
 use crate::config_types::OtelConfig;
@@ -25,8 +29,12 @@ use crate::config_types::OtelConfigToml;
 use crate::config_types::OtelExporterKind;
 use crate::config_types::ProjectCommandConfig;
 use crate::config_types::ProjectHookConfig;
+use crate::config_types::PromptCacheKeyStrategy;
 use crate::config_types::ReasoningEffort;
 use crate::config_types::ReasoningSummary;
+use crate::config_types::RequestLimits;
+use crate::config_types::ResponseCacheMode;
+use crate::config_types::SamplingParams;
 use crate::config_types::SandboxWorkspaceWrite;
 use crate::config_types::ShellEnvironmentPolicy;
 use crate::config_types::ShellEnvironmentPolicyToml;
@@ -36,6 +44,7 @@ use crate::config_types::ThemeName;
 use crate::config_types::Tui;
 use crate::config_types::UiLocale;
 use crate::config_types::UriBasedFileOpener;
+use crate::config_types::UsageStats;
 use crate::config_types::ValidationConfig;
 use crate::git_info::resolve_root_git_project_for_trust;
 use crate::model_family::ModelFamily;
@@ -51,6 +60,7 @@ use crate::protocol::ApprovedCommandMatchKind;
 use crate::protocol::AskForApproval;
 use crate::protocol::SandboxPolicy;
 use crate::reasoning::clamp_reasoning_effort_for_model;
+use crate::request_tap::RequestTap;
 use code_app_server_protocol::AuthMode;
 use code_protocol::config_types::SandboxMode;
 use dirs::home_dir;
@@ -161,6 +171,17 @@ fn merge_with_default_agents(agents: Vec<AgentConfig>) -> Vec<AgentConfig> {
     deduped
 }
 
+/// A provider that `ModelClient::stream` fails over to when the active
+/// provider returns a quota, server, or fatal authentication error.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProviderFailover {
+    /// Key in `model_providers`.
+    pub id: String,
+    pub provider: ModelProviderInfo,
+    /// Model to request from this provider instead of the session model.
+    pub model: Option<String>,
+}
+
 /// Application configuration loaded from disk and merged with overrides.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Config {
@@ -202,6 +223,10 @@ pub struct Config {
     /// Info needed to make an API request to the model.
     pub model_provider: ModelProviderInfo,
 
+    /// Providers to fail over to, in order, from the active profile's
+    /// `model_providers` list (the active provider excluded).
+    pub model_provider_failover: Vec<ProviderFailover>,
+
     /// Name of the active profile, if any, that populated this configuration.
     pub active_profile: Option<String>,
 
@@ -232,6 +257,16 @@ pub struct Config {
     /// Defaults to `false`.
     pub show_raw_agent_reasoning: bool,
 
+    /// When `true`, artifacts that persist provider traffic (rollouts, debug
+    /// request, response and SSE logs, Auto Drive event and audit logs) are
+    /// encrypted with the install key from `$CODE_HOME/at_rest.key`. Defaults
+    /// to `false`.
+    pub encrypt_at_rest: bool,
+
+    /// Hook run with the first model request of sessions started from this
+    /// config. Set by front-ends; never read from `config.toml`.
+    pub request_tap: Option<RequestTap>,
+
     /// Disable server-side response storage (sends the full conversation
     /// context with every request). Currently necessary for OpenAI customers
     /// who have opted into Zero Data Retention (ZDR).
@@ -308,6 +343,28 @@ pub struct Config {
     /// Combined provider map (defaults merged with user-defined overrides).
     pub model_providers: HashMap<String, ModelProviderInfo>,
 
+    /// Per-model prices keyed by model slug, used for exec cost reports.
+    pub model_prices: HashMap<String, ModelPrice>,
+
+    /// On-disk caching of Responses API streams under
+    /// `<code_home>/cache/responses`.
+    pub response_cache: ResponseCacheMode,
+
+    /// How the provider-side `prompt_cache_key` is chosen.
+    pub prompt_cache_key: PromptCacheKeyStrategy,
+
+    /// Size checks applied to Responses API requests before sending.
+    pub request_limits: RequestLimits,
+
+    /// `seed` / `temperature` / `top_p` sent with model requests.
+    pub sampling: SamplingParams,
+
+    /// Send a `TurnLatency` event with the timings of every model request.
+    pub turn_latency_events: bool,
+
+    /// Opt-in local statistics about exec runs.
+    pub usage_stats: UsageStats,
+
     /// Maximum number of bytes to include from an AGENTS.md project doc file.
     pub project_doc_max_bytes: usize,
 
@@ -391,6 +448,10 @@ pub struct Config {
     /// Validation harness configuration.
     pub validation: ValidationConfig,
 
+    /// Embedding provider used for semantic retrieval. `None` without an
+    /// `[embeddings]` table, which leaves the `semantic_search` tool off.
+    pub embeddings: Option<EmbeddingsConfig>,
+
     /// Resolved subagent command configurations (including custom ones).
     /// If a command with name `plan|solve|code` exists here, it overrides
     /// the built-in defaults for that slash command.
@@ -1164,6 +1225,29 @@ pub fn set_auto_drive_settings(
             .to_string()
             .to_ascii_lowercase(),
     );
+    if let Some(ref fast_model) = settings.fast_model {
+        doc["auto_drive"]["fast_model"] = toml_edit::value(fast_model.trim());
+    }
+    if let Some(slo) = settings.decision_latency_slo_ms {
+        doc["auto_drive"]["decision_latency_slo_ms"] = toml_edit::value(slo as i64);
+    }
+    doc["auto_drive"]["decision_latency_breach_turns"] =
+        toml_edit::value(settings.decision_latency_breach_turns as i64);
+    if !settings.model_fallbacks.is_empty() {
+        let mut fallbacks = TomlArray::new();
+        for model in &settings.model_fallbacks {
+            fallbacks.push(model.as_str());
+        }
+        doc["auto_drive"]["model_fallbacks"] = toml_edit::value(fallbacks);
+    }
+    if let Some(model) = settings.cli_model_routing.low.as_deref() {
+        doc["auto_drive"]["cli_model_routing"]["low"] = toml_edit::value(model);
+    }
+    if let Some(model) = settings.cli_model_routing.medium.as_deref() {
+        doc["auto_drive"]["cli_model_routing"]["medium"] = toml_edit::value(model);
+    }
+    doc["auto_drive"]["simple_loop_max_turns"] =
+        toml_edit::value(settings.simple_loop_max_turns as i64);
     doc["auto_drive"]["auto_resolve_review_attempts"] =
         toml_edit::value(settings.auto_resolve_review_attempts.get() as i64);
 
@@ -1174,6 +1258,16 @@ pub fn set_auto_drive_settings(
         AutoDriveContinueMode::Manual => "manual",
     };
     doc["auto_drive"]["continue_mode"] = toml_edit::value(mode_str);
+    doc["auto_drive"]["coordinator_prompt_mode"] =
+        toml_edit::value(match settings.coordinator_prompt_mode {
+            CoordinatorPromptMode::Merge => "merge",
+            CoordinatorPromptMode::Replace => "replace",
+        });
+    doc["auto_drive"]["goal_changes"] = toml_edit::value(match settings.goal_changes {
+        AutoDriveGoalChanges::Auto => "auto",
+        AutoDriveGoalChanges::Confirm => "confirm",
+        AutoDriveGoalChanges::Reject => "reject",
+    });
 
     // Enhanced features
     doc["auto_drive"]["checkpoint_enabled"] = toml_edit::value(settings.checkpoint_enabled);
@@ -1184,6 +1278,7 @@ pub fn set_auto_drive_settings(
         toml_edit::value(settings.checkpoint_interval as i64);
     doc["auto_drive"]["diagnostics_enabled"] = toml_edit::value(settings.diagnostics_enabled);
     doc["auto_drive"]["loop_threshold"] = toml_edit::value(settings.loop_threshold as i64);
+    doc["auto_drive"]["compact_threshold"] = toml_edit::value(settings.compact_threshold);
     if let Some(budget) = settings.token_budget {
         doc["auto_drive"]["token_budget"] = toml_edit::value(budget as i64);
     }
@@ -1193,12 +1288,20 @@ pub fn set_auto_drive_settings(
     if let Some(duration) = settings.duration_limit_seconds {
         doc["auto_drive"]["duration_limit_seconds"] = toml_edit::value(duration as i64);
     }
+    if let Some(cost) = settings.cost_budget_usd {
+        doc["auto_drive"]["cost_budget_usd"] = toml_edit::value(cost);
+    }
     doc["auto_drive"]["max_concurrent_agents"] =
         toml_edit::value(settings.max_concurrent_agents as i64);
+    if let Some(ref suffix) = settings.goal_suffix {
+        doc["auto_drive"]["goal_suffix"] = toml_edit::value(suffix.as_str());
+    }
     doc["auto_drive"]["audit_enabled"] = toml_edit::value(settings.audit_enabled);
     if let Some(ref path) = settings.audit_path {
         doc["auto_drive"]["audit_path"] = toml_edit::value(path.display().to_string());
     }
+    doc["auto_drive"]["event_log_enabled"] = toml_edit::value(settings.event_log_enabled);
+    doc["auto_drive"]["event_log_keep"] = toml_edit::value(settings.event_log_keep as i64);
     doc["auto_drive"]["telemetry_enabled"] = toml_edit::value(settings.telemetry_enabled);
     doc["auto_drive"]["high_throughput"]["max_sessions"] =
         toml_edit::value(settings.high_throughput.max_sessions as i64);
@@ -1897,6 +2000,39 @@ pub struct ConfigToml {
     #[serde(default)]
     pub model_providers: HashMap<String, ModelProviderInfo>,
 
+    /// Prices in USD per million tokens, keyed by model slug.
+    #[serde(default)]
+    pub model_prices: HashMap<String, ModelPrice>,
+
+    /// `read` replays cached responses for identical requests; `write` only
+    /// records them.
+    #[serde(default)]
+    pub response_cache: ResponseCacheMode,
+
+    /// `session` (default) keys the provider prompt cache by session id,
+    /// `repo` shares it across sessions in the same repository, `disabled`
+    /// omits the key.
+    #[serde(default)]
+    pub prompt_cache_key: PromptCacheKeyStrategy,
+
+    /// `max_bytes` / `max_items` checked before a Responses API request is
+    /// sent.
+    #[serde(default)]
+    pub request_limits: RequestLimits,
+
+    /// `seed`, `temperature`, and `top_p` for reproducible runs.
+    #[serde(default)]
+    pub sampling: SamplingParams,
+
+    /// Emit `turn_latency` events (TTFB, first token, duration, retries)
+    /// for every model request.
+    #[serde(default)]
+    pub turn_latency_events: bool,
+
+    /// Opt-in local statistics about exec runs.
+    #[serde(default)]
+    pub usage_stats: UsageStats,
+
     /// Maximum number of bytes to include from an AGENTS.md project doc file.
     pub project_doc_max_bytes: Option<usize>,
 
@@ -1942,6 +2078,9 @@ pub struct ConfigToml {
     /// Defaults to `false`.
     pub show_raw_agent_reasoning: Option<bool>,
 
+    /// Encrypt persisted provider traffic at rest. Defaults to `false`.
+    pub encrypt_at_rest: Option<bool>,
+
     pub model_reasoning_effort: Option<ReasoningEffort>,
     pub model_reasoning_summary: Option<ReasoningSummary>,
     pub model_text_verbosity: Option<TextVerbosity>,
@@ -1991,6 +2130,9 @@ pub struct ConfigToml {
     /// Validation harness configuration.
     pub validation: Option<ValidationConfig>,
 
+    /// Embedding provider used for semantic retrieval.
+    pub embeddings: Option<EmbeddingsConfig>,
+
     /// Configuration for subagent commands (built-ins and custom).
     #[serde(default)]
     pub subagents: Option<crate::config_types::SubagentsToml>,
@@ -2300,19 +2442,41 @@ impl Config {
             model_providers.entry(key).or_insert(provider);
         }
 
+        let failover_ids = config_profile.model_providers.clone().unwrap_or_default();
         let model_provider_id = model_provider
             .or(config_profile.model_provider)
+            .or_else(|| failover_ids.first().cloned())
             .or(cfg.model_provider)
             .unwrap_or_else(|| "openai".to_string());
-        let model_provider = model_providers
-            .get(&model_provider_id)
-            .ok_or_else(|| {
+        let lookup_provider = |id: &str| {
+            model_providers.get(id).cloned().ok_or_else(|| {
                 std::io::Error::new(
                     std::io::ErrorKind::NotFound,
-                    format!("Model provider `{model_provider_id}` not found"),
+                    format!("Model provider `{id}` not found"),
                 )
-            })?
-            .clone();
+            })
+        };
+        let model_provider = lookup_provider(&model_provider_id)?;
+        model_provider.load_tls().map_err(|err| {
+            std::io::Error::new(
+                err.kind(),
+                format!("Model provider `{model_provider_id}`: {err}"),
+            )
+        })?;
+        cfg.sampling
+            .validate()
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+        let model_provider_failover = failover_ids
+            .iter()
+            .filter(|id| **id != model_provider_id)
+            .map(|id| {
+                Ok(ProviderFailover {
+                    id: id.clone(),
+                    provider: lookup_provider(id)?,
+                    model: config_profile.provider_models.get(id).cloned(),
+                })
+            })
+            .collect::<std::io::Result<Vec<_>>>()?;
 
         // Capture workspace-write details early to avoid borrow after partial moves
         let cfg_workspace = cfg.sandbox_workspace_write.clone();
@@ -2615,6 +2779,7 @@ impl Config {
             model_auto_compact_token_limit,
             model_provider_id,
             model_provider,
+            model_provider_failover,
             cwd: resolved_cwd,
             approval_policy: effective_approval,
             sandbox_policy,
@@ -2638,6 +2803,13 @@ impl Config {
             experimental_client_tools: cfg.experimental_client_tools.clone(),
             agents,
             model_providers,
+            model_prices: cfg.model_prices,
+            response_cache: cfg.response_cache,
+            prompt_cache_key: cfg.prompt_cache_key,
+            request_limits: cfg.request_limits,
+            sampling: cfg.sampling,
+            turn_latency_events: cfg.turn_latency_events,
+            usage_stats: cfg.usage_stats,
             project_doc_max_bytes: cfg.project_doc_max_bytes.unwrap_or(PROJECT_DOC_MAX_BYTES),
             project_doc_fallback_filenames: cfg
                 .project_doc_fallback_filenames
@@ -2666,6 +2838,8 @@ impl Config {
                 .show_raw_agent_reasoning
                 .or(show_raw_agent_reasoning)
                 .unwrap_or(false),
+            encrypt_at_rest: cfg.encrypt_at_rest.unwrap_or(false),
+            request_tap: None,
             model_reasoning_effort: chat_reasoning_effort,
             model_reasoning_summary: config_profile
                 .model_reasoning_summary
@@ -2698,6 +2872,7 @@ impl Config {
             using_chatgpt_auth,
             github: cfg.github.unwrap_or_default(),
             validation: cfg.validation.unwrap_or_default(),
+            embeddings: cfg.embeddings,
             subagent_commands: cfg.subagents.map(|s| s.commands).unwrap_or_default(),
             experimental_resume: cfg.experimental_resume,
             // Surface TUI notifications preference from config when present.
@@ -3466,6 +3641,15 @@ model_verbosity = "high"
             stream_idle_timeout_ms: Some(300_000),
             requires_openai_auth: false,
             openrouter: None,
+            proxy: None,
+            ca_bundle_path: None,
+            client_cert_path: None,
+            client_key_path: None,
+            fallback_provider: None,
+            azure_ad: None,
+            vertex: None,
+            mock: None,
+            retry_degenerate_responses: false,
         };
         let model_provider_map = {
             let mut model_provider_map = built_in_model_providers();
@@ -3962,6 +4146,44 @@ model_verbosity = "high"
         assert_eq!(legacy.review_model.as_deref(), Some("gpt-5.1-codex"));
     }
 
+    #[test]
+    fn profile_provider_list_sets_the_failover_chain() -> std::io::Result<()> {
+        let fixture = create_test_fixture()?;
+        let mut cfg = fixture.cfg.clone();
+        cfg.profiles.insert(
+            "failover".to_string(),
+            ConfigProfile {
+                model_providers: Some(vec![
+                    "openai-chat-completions".to_string(),
+                    "openai".to_string(),
+                ]),
+                provider_models: HashMap::from([("openai".to_string(), "gpt-5.1".to_string())]),
+                ..Default::default()
+            },
+        );
+
+        let config = Config::load_from_base_config_with_overrides(
+            cfg,
+            ConfigOverrides {
+                config_profile: Some("failover".to_string()),
+                cwd: Some(fixture.cwd()),
+                ..Default::default()
+            },
+            fixture.code_home(),
+        )?;
+
+        assert_eq!(config.model_provider_id, "openai-chat-completions");
+        assert_eq!(
+            config.model_provider_failover,
+            vec![ProviderFailover {
+                id: "openai".to_string(),
+                provider: fixture.openai_provider.clone(),
+                model: Some("gpt-5.1".to_string()),
+            }]
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_compact_prompt_override_prefers_cli_string() -> std::io::Result<()> {
         let fixture = create_test_fixture()?;
@@ -1,4 +1,5 @@
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::path::PathBuf;
 
 use crate::config_types::ReasoningEffort;
@@ -18,6 +19,14 @@ pub struct ConfigProfile {
     /// The key in the `model_providers` map identifying the
     /// [`ModelProviderInfo`] to use.
     pub model_provider: Option<String>,
+    /// Ordered `model_providers` keys to fail over through when a request
+    /// hits a quota, server, or fatal authentication error. The first entry
+    /// is the active provider unless `model_provider` names another.
+    pub model_providers: Option<Vec<String>>,
+    /// Model to request from each provider in `model_providers`, keyed by
+    /// provider id, when it differs from `model`.
+    #[serde(default)]
+    pub provider_models: HashMap<String, String>,
     pub approval_policy: Option<AskForApproval>,
     pub disable_response_storage: Option<bool>,
     pub model_reasoning_effort: Option<ReasoningEffort>,
@@ -487,6 +487,66 @@ pub struct GithubConfig {
     pub actionlint_strict: bool,
 }
 
+/// How the `prompt_cache_key` sent with Responses API requests is chosen
+/// (`prompt_cache_key`).
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum PromptCacheKeyStrategy {
+    /// A fresh key per session (the session id).
+    #[default]
+    Session,
+    /// A key derived from the git repository root, shared by every session
+    /// in the same repository.
+    Repo,
+    /// Do not send a `prompt_cache_key`.
+    Disabled,
+}
+
+/// Whether Responses API streams are cached on disk (`response_cache`).
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ResponseCacheMode {
+    /// No caching.
+    #[default]
+    Off,
+    /// Replay cached responses for identical requests; record misses.
+    Read,
+    /// Record every response, never replaying one.
+    Write,
+}
+
+/// Backend used to compute embeddings for semantic retrieval.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum EmbeddingProviderKind {
+    /// OpenAI `/v1/embeddings` (or any compatible endpoint).
+    #[default]
+    Openai,
+    /// A local Ollama server (`/api/embed`).
+    Ollama,
+}
+
+/// Settings for the `[embeddings]` table.
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct EmbeddingsConfig {
+    /// Which backend computes embeddings.
+    #[serde(default)]
+    pub provider: EmbeddingProviderKind,
+
+    /// Embedding model; defaults to the provider's recommended model.
+    #[serde(default)]
+    pub model: Option<String>,
+
+    /// Override for the provider's base URL.
+    #[serde(default)]
+    pub base_url: Option<String>,
+
+    /// Environment variable holding the API key (OpenAI only). Defaults to
+    /// `OPENAI_API_KEY`.
+    #[serde(default)]
+    pub env_key: Option<String>,
+}
+
 #[derive(Deserialize, Debug, Clone, PartialEq, Default)]
 pub struct ValidationConfig {
     /// Legacy master toggle for the validation harness (kept for config compatibility).
@@ -614,6 +674,75 @@ pub struct History {
     pub max_bytes: Option<usize>,
 }
 
+/// `[request_limits]`: size checks applied to Responses API requests before
+/// they are sent, so an oversized conversation fails with a breakdown instead
+/// of an opaque provider 400.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RequestLimits {
+    /// Largest serialized request body, in bytes.
+    #[serde(default)]
+    pub max_bytes: Option<u64>,
+    /// Most conversation items (messages, tool calls, outputs) per request.
+    #[serde(default)]
+    pub max_items: Option<u64>,
+}
+
+/// `[sampling]`: sampling settings sent with every model request, for
+/// reproducible runs. Unset values leave the provider default; providers
+/// that do not accept a setting never receive it.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Default)]
+pub struct SamplingParams {
+    /// Seed for deterministic sampling (Chat Completions, Gemini, and
+    /// third-party Responses API servers).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    /// Between 0 and 2; lower is more deterministic.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+    /// Nucleus sampling cutoff between 0 and 1.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f64>,
+}
+
+impl SamplingParams {
+    /// `self`, with settings it leaves unset taken from `fallback`.
+    pub fn or(self, fallback: SamplingParams) -> SamplingParams {
+        SamplingParams {
+            seed: self.seed.or(fallback.seed),
+            temperature: self.temperature.or(fallback.temperature),
+            top_p: self.top_p.or(fallback.top_p),
+        }
+    }
+
+    /// Why these settings are out of range, if they are.
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(temperature) = self.temperature
+            && !(0.0..=2.0).contains(&temperature)
+        {
+            return Err(format!(
+                "sampling.temperature must be between 0 and 2, got {temperature}"
+            ));
+        }
+        if let Some(top_p) = self.top_p
+            && !(0.0..=1.0).contains(&top_p)
+        {
+            return Err(format!(
+                "sampling.top_p must be between 0 and 1, got {top_p}"
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// `[usage_stats]`: anonymized statistics about `code exec` runs, aggregated
+/// in `~/.code/usage_stats.jsonl` and never sent anywhere.
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct UsageStats {
+    /// Record one line per exec run. Off unless set to true.
+    #[serde(default)]
+    pub enabled: bool,
+}
+
 #[derive(Deserialize, Debug, Copy, Clone, PartialEq, Default)]
 #[serde(rename_all = "kebab-case")]
 pub enum HistoryPersistence {
@@ -770,6 +899,24 @@ impl<'de> Deserialize<'de> for AutoResolveAttemptLimit {
     }
 }
 
+/// Price of a model in USD per million tokens, used to estimate run cost.
+///
+/// ```toml
+/// [model_prices.gpt-5]
+/// input = 1.25
+/// cached_input = 0.125
+/// output = 10.0
+/// ```
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct ModelPrice {
+    pub input: f64,
+    /// Price of cached input tokens; defaults to `input` when unset.
+    #[serde(default)]
+    pub cached_input: Option<f64>,
+    /// Price of output tokens, reasoning tokens included.
+    pub output: f64,
+}
+
 /// Auto Drive behavioral defaults persisted via `config.toml`.
 #[derive(Deserialize, Debug, Clone, PartialEq)]
 pub struct AutoDriveSettings {
@@ -803,12 +950,51 @@ pub struct AutoDriveSettings {
     #[serde(default = "default_auto_drive_reasoning_effort")]
     pub model_reasoning_effort: ReasoningEffort,
 
+    /// How `$CODE_HOME/prompt_coordinator.md` and project `AUTO_COORDINATOR.md`
+    /// files combine with the built-in coordinator prompt.
+    #[serde(default)]
+    pub coordinator_prompt_mode: CoordinatorPromptMode,
+
+    /// Faster coordinator model for routine `continue` decisions once
+    /// decision latency breaches `decision_latency_slo_ms`. Finish and
+    /// failure judgments stay with `model`.
+    #[serde(default)]
+    pub fast_model: Option<String>,
+
+    /// Target p95 coordinator decision latency in milliseconds. None disables
+    /// latency tracking.
+    #[serde(default)]
+    pub decision_latency_slo_ms: Option<u64>,
+
+    /// Consecutive decisions whose p95 latency must exceed the SLO before
+    /// switching to `fast_model`.
+    #[serde(default = "default_decision_latency_breach_turns")]
+    pub decision_latency_breach_turns: u32,
+
+    /// Coordinator models to switch to, in order, when the active one is
+    /// rejected for good (unknown model, quota, auth) or keeps returning
+    /// decisions that fail validation.
+    #[serde(default)]
+    pub model_fallbacks: Vec<String>,
+
+    /// Cheaper models for low- and medium-complexity CLI turns.
+    #[serde(default)]
+    pub cli_model_routing: CliModelRouting,
+
+    /// CLI turns Auto Drive keeps running with a templated continue prompt
+    /// once every coordinator model, `model_fallbacks` included, is
+    /// unavailable (quota or permanent auth failure). 0 ends the run instead.
+    #[serde(default = "default_simple_loop_max_turns")]
+    pub simple_loop_max_turns: u32,
+
     #[serde(default)]
     pub auto_resolve_review_attempts: AutoResolveAttemptLimit,
 
     /// Number of parallel instances of the same model to use for concurrent
     /// role execution (coordinator, executor, tester, reviewer). Range: 1-10.
     /// When > 1, multiple API calls are made in parallel with different role prompts.
+    /// `code exec` also uses it as the size of the CLI worker pool that runs
+    /// independent workstreams.
     #[serde(default = "default_parallel_instances")]
     pub parallel_instances: u8,
 
@@ -847,10 +1033,84 @@ pub struct AutoDriveSettings {
     #[serde(default)]
     pub duration_limit_seconds: Option<u64>,
 
+    /// Maximum estimated spend in US dollars, priced with `model_prices`.
+    /// None means unlimited.
+    #[serde(default)]
+    pub cost_budget_usd: Option<f64>,
+
     /// Maximum concurrent agents for parallel execution.
     #[serde(default = "default_max_concurrent_agents")]
     pub max_concurrent_agents: usize,
 
+    /// Default timeout in seconds for each coordinator-dispatched agent.
+    /// Decisions may override it per agent. None means agents run until
+    /// completion.
+    #[serde(default)]
+    pub agent_timeout_seconds: Option<u64>,
+
+    /// Policy text appended to every `code exec --auto` goal. Unset uses the
+    /// built-in test-first instructions; an empty string appends nothing.
+    #[serde(default)]
+    pub goal_suffix: Option<String>,
+
+    /// Let coordinator decisions request a code review of their CLI turn.
+    /// Reviews run on `review_model` without tools, and their findings are
+    /// added to the coordinator history before the next decision.
+    #[serde(default)]
+    pub turn_reviews: bool,
+
+    /// When set, `code exec --auto` queues the run for this time (`HH:MM`,
+    /// `daily HH:MM`, or an RFC 3339 timestamp) instead of starting it, and
+    /// `code exec scheduler run` starts it. `--schedule now` overrides it.
+    #[serde(default)]
+    pub schedule: Option<String>,
+
+    /// Share of the coordinator model's context window (or its auto-compact
+    /// limit) the history may fill before it is compacted. Range: 0.1-1.0.
+    #[serde(default = "default_compact_threshold")]
+    pub compact_threshold: f64,
+
+    /// Coordinator roles that take alternating turns driving the run, each
+    /// with its own instructions and model. Needs at least two roles; the
+    /// first one makes the first decision.
+    #[serde(default)]
+    pub roles: Vec<AutoDriveRole>,
+
+    /// What happens when the coordinator changes the goal mid-run.
+    #[serde(default)]
+    pub goal_changes: AutoDriveGoalChanges,
+
+    /// Hold the first decision that writes files with an intervention
+    /// request until the operator approves it. Set by
+    /// `code exec --auto-confirm-first-write`; not read from `config.toml`
+    /// because only exec can answer the checkpoint.
+    #[serde(skip)]
+    pub confirm_first_write: bool,
+
+    /// Let the coordinator split a turn into independent workstreams that
+    /// run concurrently in separate CLI sessions, up to `parallel_instances`
+    /// at a time. Set by `code exec`, which owns the worker pool; not read
+    /// from `config.toml`.
+    #[serde(skip)]
+    pub cli_workers: bool,
+
+    /// Record every coordinator request, its raw output, and how it parsed
+    /// under `debug_logs/auto_coordinator`. Set by
+    /// `code exec --debug-coordinator`; not read from `config.toml`.
+    #[serde(skip)]
+    pub debug_coordinator: bool,
+
+    /// Write every coordinator response to `NNNN.json` in this directory.
+    /// Set by `code exec --record-decisions`; not read from `config.toml`.
+    #[serde(skip)]
+    pub record_decisions: Option<PathBuf>,
+
+    /// Answer coordinator requests with the responses recorded in this
+    /// directory, in order, instead of calling the model. Set by
+    /// `code exec --replay-decisions`; not read from `config.toml`.
+    #[serde(skip)]
+    pub replay_decisions: Option<PathBuf>,
+
     /// Enable audit logging.
     #[serde(default)]
     pub audit_enabled: bool,
@@ -859,6 +1119,16 @@ pub struct AutoDriveSettings {
     #[serde(default)]
     pub audit_path: Option<PathBuf>,
 
+    /// Record coordinator and conversation events of `code exec --auto` runs
+    /// under `auto_drive/events` for `code exec auto replay`.
+    #[serde(default)]
+    pub event_log_enabled: bool,
+
+    /// Number of most recent event logs kept; older ones are deleted when a
+    /// new run starts recording.
+    #[serde(default = "default_event_log_keep")]
+    pub event_log_keep: usize,
+
     /// Enable telemetry collection.
     #[serde(default)]
     pub telemetry_enabled: bool,
@@ -866,6 +1136,42 @@ pub struct AutoDriveSettings {
     /// High throughput multi-agent settings.
     #[serde(default)]
     pub high_throughput: HighThroughputSettings,
+
+    /// Objective termination criteria checked after every turn.
+    #[serde(default)]
+    pub stop_when: AutoDriveStopWhen,
+
+    /// Tests run after CLI turns that change files.
+    #[serde(default)]
+    pub selective_tests: SelectiveTestSettings,
+
+    /// How the coordinator retries failed model requests.
+    #[serde(default)]
+    pub retry: AutoDriveRetrySettings,
+
+    /// Endpoint that receives a JSON POST for every decision, budget alert,
+    /// and diagnostic alert.
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+
+    /// Key for the `X-Auto-Drive-Signature` HMAC-SHA256 header on webhook
+    /// requests. Unset sends them unsigned.
+    #[serde(default)]
+    pub webhook_secret: Option<String>,
+}
+
+/// A coordinator role under `[[auto_drive.roles]]`.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct AutoDriveRole {
+    pub name: String,
+    /// Coordinator model for this role's decisions. Defaults to
+    /// `auto_drive.model`.
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Instructions the role follows on its turns, e.g. "Plan the change and
+    /// review what the implementer did; do not write code yourself."
+    #[serde(default)]
+    pub prompt: Option<String>,
 }
 
 impl Default for AutoDriveSettings {
@@ -880,6 +1186,13 @@ impl Default for AutoDriveSettings {
             continue_mode: AutoDriveContinueMode::TenSeconds,
             model: default_auto_drive_model(),
             model_reasoning_effort: default_auto_drive_reasoning_effort(),
+            coordinator_prompt_mode: CoordinatorPromptMode::default(),
+            fast_model: None,
+            decision_latency_slo_ms: None,
+            decision_latency_breach_turns: default_decision_latency_breach_turns(),
+            model_fallbacks: Vec::new(),
+            cli_model_routing: CliModelRouting::default(),
+            simple_loop_max_turns: default_simple_loop_max_turns(),
             auto_resolve_review_attempts: AutoResolveAttemptLimit::default(),
             parallel_instances: default_parallel_instances(),
             // Enhanced features defaults
@@ -891,15 +1204,154 @@ impl Default for AutoDriveSettings {
             token_budget: None,
             turn_limit: None,
             duration_limit_seconds: None,
+            cost_budget_usd: None,
             max_concurrent_agents: default_max_concurrent_agents(),
+            agent_timeout_seconds: None,
+            goal_suffix: None,
+            schedule: None,
+            compact_threshold: default_compact_threshold(),
+            roles: Vec::new(),
+            goal_changes: AutoDriveGoalChanges::default(),
+            confirm_first_write: false,
+            cli_workers: false,
+            debug_coordinator: false,
+            record_decisions: None,
+            replay_decisions: None,
+            turn_reviews: false,
             audit_enabled: false,
             audit_path: None,
+            event_log_enabled: false,
+            event_log_keep: default_event_log_keep(),
             telemetry_enabled: false,
             high_throughput: HighThroughputSettings::default(),
+            stop_when: AutoDriveStopWhen::default(),
+            selective_tests: SelectiveTestSettings::default(),
+            retry: AutoDriveRetrySettings::default(),
+            webhook_url: None,
+            webhook_secret: None,
+        }
+    }
+}
+
+/// `[auto_drive.stop_when]`: when every configured condition holds after a
+/// turn, Auto Drive finishes successfully even if the coordinator has not
+/// declared success yet.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct AutoDriveStopWhen {
+    /// The project's test command exits 0. The command comes from
+    /// `test_command`, or is detected from the project (`cargo test`,
+    /// `npm test`, `go test ./...`, `python -m pytest`).
+    #[serde(default)]
+    pub tests_passing: bool,
+
+    /// Overrides the detected command used by `tests_passing`.
+    #[serde(default)]
+    pub test_command: Option<String>,
+
+    /// A path, relative to the working directory, exists.
+    #[serde(default)]
+    pub file_exists: Option<String>,
+
+    /// A shell command exits 0.
+    #[serde(default)]
+    pub command_succeeds: Option<String>,
+
+    /// Seconds each condition command may run before it counts as failing.
+    /// Defaults to 600.
+    #[serde(default)]
+    pub timeout_seconds: Option<u64>,
+}
+
+impl AutoDriveStopWhen {
+    pub fn is_empty(&self) -> bool {
+        !self.tests_passing && self.file_exists.is_none() && self.command_succeeds.is_none()
+    }
+}
+
+/// `[auto_drive.retry]`: backoff for failed coordinator requests. The
+/// defaults keep retrying transient errors and waiting out rate limits for up
+/// to a week; CI runs can lower the window or cap attempts to fail fast.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct AutoDriveRetrySettings {
+    /// Stop retrying once this many seconds have passed since the first
+    /// attempt, or when a rate limit resets after that.
+    #[serde(default = "default_retry_max_elapsed_seconds")]
+    pub max_elapsed_seconds: u64,
+
+    /// Stop after this many attempts. Unlimited when unset.
+    #[serde(default)]
+    pub max_attempts: Option<u32>,
+
+    /// First backoff delay; each retry doubles it.
+    #[serde(default = "default_retry_base_delay_ms")]
+    pub base_delay_ms: u64,
+
+    /// Longest backoff delay.
+    #[serde(default = "default_retry_max_delay_seconds")]
+    pub max_delay_seconds: u64,
+
+    /// Randomize delays so parallel runs do not retry in lockstep.
+    #[serde(default = "default_true")]
+    pub jitter: bool,
+
+    /// Seconds added to the reset time a rate-limited provider announces.
+    #[serde(default = "default_retry_rate_limit_buffer_seconds")]
+    pub rate_limit_buffer_seconds: u64,
+}
+
+impl Default for AutoDriveRetrySettings {
+    fn default() -> Self {
+        Self {
+            max_elapsed_seconds: default_retry_max_elapsed_seconds(),
+            max_attempts: None,
+            base_delay_ms: default_retry_base_delay_ms(),
+            max_delay_seconds: default_retry_max_delay_seconds(),
+            jitter: true,
+            rate_limit_buffer_seconds: default_retry_rate_limit_buffer_seconds(),
         }
     }
 }
 
+const fn default_retry_max_elapsed_seconds() -> u64 {
+    7 * 24 * 60 * 60
+}
+
+const fn default_retry_base_delay_ms() -> u64 {
+    1_000
+}
+
+const fn default_retry_max_delay_seconds() -> u64 {
+    15 * 60
+}
+
+const fn default_retry_rate_limit_buffer_seconds() -> u64 {
+    5
+}
+
+/// `[auto_drive.selective_tests]`: after a CLI turn changes files, Auto Drive
+/// runs the commands of every rule whose glob matches a changed path and
+/// hands the results to the coordinator's next decision.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct SelectiveTestSettings {
+    /// Checked in order; each command runs at most once per turn.
+    #[serde(default)]
+    pub rules: Vec<SelectiveTestRule>,
+
+    /// Seconds each command may run. Defaults to 600.
+    #[serde(default)]
+    pub timeout_seconds: Option<u64>,
+}
+
+/// One `[[auto_drive.selective_tests.rules]]` entry.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SelectiveTestRule {
+    /// Pattern matched against paths relative to the working directory; `*`
+    /// also matches `/`.
+    pub glob: String,
+    /// Shell command run in the session sandbox.
+    pub command: String,
+}
+
 fn default_auto_drive_model() -> String {
     // Keep aligned with the coordinator's preferred model fallback.
     String::from("gpt-5.1")
@@ -920,16 +1372,50 @@ const fn default_checkpoint_interval() -> u32 {
     5
 }
 
+const fn default_compact_threshold() -> f64 {
+    0.8
+}
+
 /// Default loop detection threshold.
 const fn default_loop_threshold() -> u32 {
     3
 }
 
+const fn default_decision_latency_breach_turns() -> u32 {
+    3
+}
+
+const fn default_simple_loop_max_turns() -> u32 {
+    20
+}
+
 /// Default maximum concurrent agents.
 const fn default_max_concurrent_agents() -> usize {
     8
 }
 
+const fn default_event_log_keep() -> usize {
+    20
+}
+
+/// Models Auto Drive runs CLI turns on by complexity, set under
+/// `[auto_drive.cli_model_routing]`. The coordinator tags each turn low,
+/// medium, or high; unset levels and high-complexity turns stay on the
+/// session model.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct CliModelRouting {
+    #[serde(default)]
+    pub low: Option<String>,
+    #[serde(default)]
+    pub medium: Option<String>,
+}
+
+impl CliModelRouting {
+    pub fn is_enabled(&self) -> bool {
+        self.low.is_some() || self.medium.is_some()
+    }
+}
+
 /// High throughput pool/session defaults.
 #[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
 pub struct HighThroughputSettings {
@@ -977,6 +1463,32 @@ const fn default_backpressure_multiplier() -> i32 {
     10
 }
 
+/// How custom coordinator prompt files combine with the built-in prompt.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum CoordinatorPromptMode {
+    /// Append the custom prompt to the built-in one.
+    #[default]
+    Merge,
+    /// Use the custom prompt instead of the built-in one.
+    Replace,
+}
+
+/// `auto_drive.goal_changes`: how a goal amended by the coordinator mid-run
+/// is handled.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum AutoDriveGoalChanges {
+    /// Adopt the amended goal right away.
+    #[default]
+    Auto,
+    /// Hold the decision with an intervention request until the operator
+    /// approves the amended goal.
+    Confirm,
+    /// Keep the original goal and ignore amendments.
+    Reject,
+}
+
 #[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
 #[serde(rename_all = "kebab-case")]
 pub enum AutoDriveContinueMode {
@@ -0,0 +1,687 @@
+//! Import conversations from other tools as resumable sessions.
+//!
+//! Supported inputs are the `conversations.json` file from an OpenAI (ChatGPT)
+//! data export and plain Markdown chat logs. Each imported conversation is
+//! written as a regular rollout file under `sessions/` and registered in the
+//! session catalog, so `code resume` and Auto Drive can continue from it like
+//! any locally recorded session.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use anyhow::Result;
+use anyhow::bail;
+use code_protocol::ConversationId;
+use code_protocol::models::ContentItem;
+use code_protocol::models::ResponseItem;
+use code_protocol::protocol::RolloutItem;
+use code_protocol::protocol::RolloutLine;
+use code_protocol::protocol::SessionMeta;
+use code_protocol::protocol::SessionMetaLine;
+use code_protocol::protocol::SessionSource;
+use serde::Deserialize;
+use serde_json::Value;
+use time::OffsetDateTime;
+use time::format_description::FormatItem;
+use time::macros::format_description;
+
+use crate::at_rest::AtRestCipher;
+use crate::default_client::DEFAULT_ORIGINATOR;
+use crate::git_info::collect_git_info;
+use crate::rollout::SESSIONS_SUBDIR;
+use crate::rollout::catalog::update_catalog_entry;
+
+const ROLLOUT_TIMESTAMP_FORMAT: &[FormatItem] =
+    format_description!("[year]-[month]-[day]T[hour]:[minute]:[second].[subsecond digits:3]Z");
+const ROLLOUT_FILENAME_FORMAT: &[FormatItem] =
+    format_description!("[year]-[month]-[day]T[hour]-[minute]-[second]");
+
+/// Source format of an import file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    /// `conversations.json` from an OpenAI data export.
+    Openai,
+    /// A Markdown log with one heading or `Role:` label per message.
+    Markdown,
+}
+
+impl std::str::FromStr for ImportFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "openai" | "chatgpt" => Ok(ImportFormat::Openai),
+            "markdown" | "md" => Ok(ImportFormat::Markdown),
+            other => Err(format!(
+                "unknown import format `{other}` (expected `openai` or `markdown`)"
+            )),
+        }
+    }
+}
+
+impl ImportFormat {
+    /// Picks a format from the file extension, falling back to sniffing the
+    /// contents for JSON.
+    pub fn detect(path: &Path, contents: &str) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => ImportFormat::Openai,
+            Some("md" | "markdown" | "txt") => ImportFormat::Markdown,
+            _ if serde_json::from_str::<Value>(contents).is_ok() => ImportFormat::Openai,
+            _ => ImportFormat::Markdown,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportedRole {
+    User,
+    Assistant,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedMessage {
+    pub role: ImportedRole,
+    pub text: String,
+    pub created_at: Option<OffsetDateTime>,
+}
+
+/// A conversation parsed from an external transcript, before it is written.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedConversation {
+    pub title: Option<String>,
+    pub created_at: Option<OffsetDateTime>,
+    pub messages: Vec<ImportedMessage>,
+}
+
+/// A conversation written to disk and registered in the catalog.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedSession {
+    pub session_id: ConversationId,
+    pub rollout_path: PathBuf,
+    pub title: Option<String>,
+    pub message_count: usize,
+}
+
+/// A conversation that could not be imported.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SkippedConversation {
+    /// Position in the import file, counting from 1.
+    pub index: usize,
+    pub title: Option<String>,
+    pub reason: String,
+}
+
+/// Sessions written by [`import_file`] and the conversations it skipped.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ImportReport {
+    pub sessions: Vec<ImportedSession>,
+    pub skipped: Vec<SkippedConversation>,
+}
+
+/// Parses `path` and writes one session per conversation it contains.
+/// Sessions are recorded with `cwd` as their working directory. A
+/// conversation that cannot be parsed or written is skipped and reported;
+/// the import fails only when nothing could be imported.
+pub async fn import_file(
+    code_home: &Path,
+    cipher: Option<&AtRestCipher>,
+    cwd: &Path,
+    path: &Path,
+    format: Option<ImportFormat>,
+) -> Result<ImportReport> {
+    let contents = tokio::fs::read_to_string(path)
+        .await
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    let format = format.unwrap_or_else(|| ImportFormat::detect(path, &contents));
+    let parsed = match format {
+        ImportFormat::Openai => parse_openai_export(&contents)?,
+        ImportFormat::Markdown => vec![Ok(parse_markdown(&contents)?)],
+    };
+
+    let mut report = ImportReport::default();
+    for (index, conversation) in (1..).zip(parsed) {
+        let conversation = match conversation {
+            Ok(conversation) => conversation,
+            Err(skipped) => {
+                report.skipped.push(skipped);
+                continue;
+            }
+        };
+        match write_session(code_home, cipher, cwd, &conversation).await {
+            Ok(session) => report.sessions.push(session),
+            Err(err) => report.skipped.push(SkippedConversation {
+                index,
+                title: conversation.title,
+                reason: format!("{err:#}"),
+            }),
+        }
+    }
+    if report.sessions.is_empty() {
+        let reasons: Vec<String> = report
+            .skipped
+            .iter()
+            .map(|skipped| format!("#{}: {}", skipped.index, skipped.reason))
+            .collect();
+        bail!("no conversation could be imported ({})", reasons.join("; "));
+    }
+    Ok(report)
+}
+
+/// Writes `conversation` as a rollout file and adds it to the catalog.
+/// Lines are sealed with `cipher` if given.
+pub async fn write_session(
+    code_home: &Path,
+    cipher: Option<&AtRestCipher>,
+    cwd: &Path,
+    conversation: &ImportedConversation,
+) -> Result<ImportedSession> {
+    let created_at = conversation
+        .created_at
+        .unwrap_or_else(OffsetDateTime::now_utc);
+    let session_id = ConversationId::new();
+
+    let dir = code_home
+        .join(SESSIONS_SUBDIR)
+        .join(created_at.year().to_string())
+        .join(format!("{:02}", u8::from(created_at.month())))
+        .join(format!("{:02}", created_at.day()));
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .with_context(|| format!("failed to create {}", dir.display()))?;
+    let rollout_path = dir.join(format!(
+        "rollout-{}-{session_id}.jsonl",
+        created_at.format(ROLLOUT_FILENAME_FORMAT)?
+    ));
+
+    let meta_timestamp = created_at.format(ROLLOUT_TIMESTAMP_FORMAT)?;
+    let mut lines = vec![RolloutLine {
+        timestamp: meta_timestamp.clone(),
+        item: RolloutItem::SessionMeta(SessionMetaLine {
+            meta: SessionMeta {
+                id: session_id,
+                timestamp: meta_timestamp.clone(),
+                cwd: cwd.to_path_buf(),
+                originator: DEFAULT_ORIGINATOR.to_string(),
+                cli_version: env!("CARGO_PKG_VERSION").to_string(),
+                instructions: None,
+                source: SessionSource::Cli,
+            },
+            git: collect_git_info(cwd).await,
+        }),
+    }];
+    let mut last_timestamp = meta_timestamp;
+    for message in &conversation.messages {
+        if let Some(at) = message.created_at {
+            last_timestamp = at.format(ROLLOUT_TIMESTAMP_FORMAT)?;
+        }
+        let (role, content) = match message.role {
+            ImportedRole::User => (
+                "user",
+                ContentItem::InputText {
+                    text: message.text.clone(),
+                },
+            ),
+            ImportedRole::Assistant => (
+                "assistant",
+                ContentItem::OutputText {
+                    text: message.text.clone(),
+                },
+            ),
+        };
+        lines.push(RolloutLine {
+            timestamp: last_timestamp.clone(),
+            item: RolloutItem::ResponseItem(ResponseItem::Message {
+                id: None,
+                role: role.to_string(),
+                content: vec![content],
+            }),
+        });
+    }
+
+    let mut jsonl = String::new();
+    for line in &lines {
+        let line = serde_json::to_string(line)?;
+        match cipher {
+            Some(cipher) => jsonl.push_str(&cipher.seal_line(&line)?),
+            None => jsonl.push_str(&line),
+        }
+        jsonl.push('\n');
+    }
+    tokio::fs::write(&rollout_path, jsonl)
+        .await
+        .with_context(|| format!("failed to write {}", rollout_path.display()))?;
+    update_catalog_entry(code_home, &rollout_path, session_id.into(), &last_timestamp)
+        .await
+        .context("failed to register imported session in the catalog")?;
+
+    Ok(ImportedSession {
+        session_id,
+        rollout_path,
+        title: conversation.title.clone(),
+        message_count: conversation.messages.len(),
+    })
+}
+
+#[derive(Deserialize)]
+struct ExportConversation {
+    create_time: Option<f64>,
+    #[serde(default)]
+    mapping: HashMap<String, ExportNode>,
+    current_node: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ExportNode {
+    message: Option<ExportMessage>,
+    parent: Option<String>,
+    #[serde(default)]
+    children: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct ExportMessage {
+    author: ExportAuthor,
+    content: Option<ExportContent>,
+    create_time: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct ExportAuthor {
+    role: String,
+}
+
+#[derive(Deserialize)]
+struct ExportContent {
+    #[serde(default)]
+    parts: Vec<Value>,
+}
+
+/// Parses an OpenAI export: either the full `conversations.json` array or a
+/// single conversation object. Returns one entry per conversation, in
+/// order; conversations that are malformed or have no user or assistant text
+/// come back as [`SkippedConversation`]s.
+pub fn parse_openai_export(
+    contents: &str,
+) -> Result<Vec<std::result::Result<ImportedConversation, SkippedConversation>>> {
+    let value: Value = serde_json::from_str(contents).context("export is not valid JSON")?;
+    let raw = match value {
+        Value::Array(items) => items,
+        other => vec![other],
+    };
+    if raw.is_empty() {
+        bail!("export contains no conversations");
+    }
+    Ok((1..)
+        .zip(raw)
+        .map(|(index, item)| parse_export_conversation(index, item))
+        .collect())
+}
+
+fn parse_export_conversation(
+    index: usize,
+    item: Value,
+) -> std::result::Result<ImportedConversation, SkippedConversation> {
+    let title = item
+        .get("title")
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|title| !title.is_empty())
+        .map(str::to_string);
+    let skipped = |reason: String| SkippedConversation {
+        index,
+        title: title.clone(),
+        reason,
+    };
+    let export: ExportConversation = serde_json::from_value(item)
+        .map_err(|err| skipped(format!("unrecognized OpenAI export conversation: {err}")))?;
+    let messages = export_branch(&export)
+        .into_iter()
+        .filter_map(|message| {
+            let role = match message.author.role.as_str() {
+                "user" => ImportedRole::User,
+                "assistant" => ImportedRole::Assistant,
+                _ => return None,
+            };
+            let text = message
+                .content
+                .as_ref()?
+                .parts
+                .iter()
+                .filter_map(Value::as_str)
+                .collect::<Vec<_>>()
+                .join("\n");
+            let text = text.trim();
+            (!text.is_empty()).then(|| ImportedMessage {
+                role,
+                text: text.to_string(),
+                created_at: message.create_time.and_then(unix_seconds),
+            })
+        })
+        .collect::<Vec<_>>();
+    if messages.is_empty() {
+        return Err(skipped("no user or assistant messages".to_string()));
+    }
+    Ok(ImportedConversation {
+        title,
+        created_at: export.create_time.and_then(unix_seconds),
+        messages,
+    })
+}
+
+/// Messages on the conversation's active branch, oldest first. Exports store
+/// every edit as a tree; `current_node` marks the branch the user last saw.
+fn export_branch(export: &ExportConversation) -> Vec<&ExportMessage> {
+    let leaf = export.current_node.clone().or_else(|| {
+        let mut node_id = export
+            .mapping
+            .iter()
+            .find(|(_, node)| node.parent.is_none())
+            .map(|(id, _)| id.clone())?;
+        for _ in 0..export.mapping.len() {
+            match export.mapping.get(&node_id)?.children.last() {
+                Some(child) => node_id = child.clone(),
+                None => break,
+            }
+        }
+        Some(node_id)
+    });
+
+    let mut branch = Vec::new();
+    let mut next = leaf;
+    // Bounded by the node count so a malformed cycle cannot loop forever.
+    for _ in 0..export.mapping.len() {
+        let Some(node) = next.as_ref().and_then(|id| export.mapping.get(id)) else {
+            break;
+        };
+        if let Some(message) = node.message.as_ref() {
+            branch.push(message);
+        }
+        next = node.parent.clone();
+    }
+    branch.reverse();
+    branch
+}
+
+/// Export timestamps are fractional Unix seconds.
+fn unix_seconds(seconds: f64) -> Option<OffsetDateTime> {
+    let whole = seconds.trunc();
+    let nanos = ((seconds - whole) * 1e9).round() as i64;
+    OffsetDateTime::from_unix_timestamp(whole as i64)
+        .ok()
+        .map(|at| at + time::Duration::nanoseconds(nanos))
+}
+
+/// Parses a Markdown chat log. A message starts at a heading naming the
+/// speaker (`## User`, `### Assistant`), or at a bold (`**You:** ...`) or
+/// plain (`ChatGPT: ...`) label that begins a block: the first line, a line
+/// after a blank line, a closing code fence, or another labelled line. A
+/// label in the middle of a paragraph is part of the message. A leading
+/// `# Title` heading that is not a speaker becomes the conversation title.
+pub fn parse_markdown(contents: &str) -> Result<ImportedConversation> {
+    let mut title = None;
+    let mut messages: Vec<ImportedMessage> = Vec::new();
+    let mut current: Option<(ImportedRole, Vec<&str>)> = None;
+    let mut in_fence = false;
+    let mut at_block_start = true;
+
+    let mut finish = |current: Option<(ImportedRole, Vec<&str>)>| {
+        if let Some((role, lines)) = current {
+            let text = lines.join("\n").trim().to_string();
+            if !text.is_empty() {
+                messages.push(ImportedMessage {
+                    role,
+                    text,
+                    created_at: None,
+                });
+            }
+        }
+    };
+
+    for line in contents.lines() {
+        let is_fence = line.trim_start().starts_with("```");
+        if is_fence {
+            in_fence = !in_fence;
+        }
+        if !in_fence
+            && let Some((role, rest)) = speaker_label(line)
+            && (at_block_start || line.trim_start().starts_with('#'))
+        {
+            finish(current.take());
+            current = Some((role, if rest.is_empty() { vec![] } else { vec![rest] }));
+            at_block_start = true;
+            continue;
+        }
+        at_block_start = line.trim().is_empty() || (is_fence && !in_fence);
+        match current.as_mut() {
+            Some((_, lines)) => lines.push(line),
+            None => {
+                if title.is_none()
+                    && let Some(heading) = line.strip_prefix("# ")
+                {
+                    title = Some(heading.trim().to_string());
+                }
+            }
+        }
+    }
+    finish(current.take());
+
+    if messages.is_empty() {
+        bail!("no `User`/`Assistant` messages found in the Markdown log");
+    }
+    Ok(ImportedConversation {
+        title,
+        created_at: None,
+        messages,
+    })
+}
+
+/// Returns the speaker and any text that follows the label on the same line.
+fn speaker_label(line: &str) -> Option<(ImportedRole, &str)> {
+    let trimmed = line.trim();
+    if let Some(heading) = trimmed.strip_prefix('#') {
+        let name = heading.trim_start_matches('#').trim().trim_end_matches(':');
+        return speaker_role(name.trim_matches('*')).map(|role| (role, ""));
+    }
+    if let Some(bold) = trimmed.strip_prefix("**") {
+        let (label, rest) = bold.split_once("**")?;
+        let label = label.trim_end_matches(':');
+        let rest = rest.strip_prefix(':').unwrap_or(rest);
+        return speaker_role(label).map(|role| (role, rest.trim()));
+    }
+    let (label, rest) = trimmed.split_once(':')?;
+    speaker_role(label).map(|role| (role, rest.trim()))
+}
+
+fn speaker_role(name: &str) -> Option<ImportedRole> {
+    match name.trim().to_ascii_lowercase().as_str() {
+        "user" | "you" | "human" | "me" => Some(ImportedRole::User),
+        "assistant" | "chatgpt" | "gpt" | "ai" | "claude" | "model" | "bot" => {
+            Some(ImportedRole::Assistant)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session_catalog::SessionCatalog;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    fn text(role: ImportedRole, text: &str) -> (ImportedRole, String) {
+        (role, text.to_string())
+    }
+
+    fn texts(conversation: &ImportedConversation) -> Vec<(ImportedRole, String)> {
+        conversation
+            .messages
+            .iter()
+            .map(|message| (message.role, message.text.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn openai_export_follows_current_branch() {
+        let export = json!([{
+            "title": "Refactor plan",
+            "create_time": 1_700_000_000.5,
+            "current_node": "a2",
+            "mapping": {
+                "root": {"message": null, "parent": null, "children": ["sys"]},
+                "sys": {
+                    "message": {"author": {"role": "system"}, "content": {"parts": [""]}},
+                    "parent": "root",
+                    "children": ["u1"]
+                },
+                "u1": {
+                    "message": {
+                        "author": {"role": "user"},
+                        "content": {"content_type": "text", "parts": ["Split the parser"]},
+                        "create_time": 1_700_000_001.0
+                    },
+                    "parent": "sys",
+                    "children": ["a1", "a2"]
+                },
+                "a1": {
+                    "message": {"author": {"role": "assistant"}, "content": {"parts": ["old draft"]}},
+                    "parent": "u1",
+                    "children": []
+                },
+                "a2": {
+                    "message": {"author": {"role": "assistant"}, "content": {"parts": ["Start with the lexer."]}},
+                    "parent": "u1",
+                    "children": []
+                }
+            }
+        }]);
+
+        let conversations: Vec<ImportedConversation> = parse_openai_export(&export.to_string())
+            .unwrap()
+            .into_iter()
+            .map(std::result::Result::unwrap)
+            .collect();
+        assert_eq!(conversations.len(), 1);
+        assert_eq!(conversations[0].title.as_deref(), Some("Refactor plan"));
+        assert_eq!(
+            texts(&conversations[0]),
+            vec![
+                text(ImportedRole::User, "Split the parser"),
+                text(ImportedRole::Assistant, "Start with the lexer."),
+            ]
+        );
+        assert_eq!(
+            conversations[0].messages[0].created_at,
+            OffsetDateTime::from_unix_timestamp(1_700_000_001).ok()
+        );
+    }
+
+    #[test]
+    fn markdown_log_splits_on_speaker_labels() {
+        let log = "# Debugging session\n\n## User\nWhy does `cargo test` hang?\nMe: I already tried --nocapture.\n\n## Assistant\nCheck for a deadlock:\n```\nUser: not a label inside code\n```\n**You:** Found it, thanks.\nChatGPT: Glad it helped.\n";
+
+        let conversation = parse_markdown(log).unwrap();
+        assert_eq!(conversation.title.as_deref(), Some("Debugging session"));
+        assert_eq!(
+            texts(&conversation),
+            vec![
+                text(
+                    ImportedRole::User,
+                    "Why does `cargo test` hang?\nMe: I already tried --nocapture."
+                ),
+                text(
+                    ImportedRole::Assistant,
+                    "Check for a deadlock:\n```\nUser: not a label inside code\n```"
+                ),
+                text(ImportedRole::User, "Found it, thanks."),
+                text(ImportedRole::Assistant, "Glad it helped."),
+            ]
+        );
+        assert!(parse_markdown("just some notes").is_err());
+    }
+
+    #[test]
+    fn labels_mid_paragraph_stay_in_the_message() {
+        let log = "User: Summarize the meeting notes.\nAssistant: Here is the summary.\nThe notes say:\nAI: the new model ships Friday.\nUser: reviewed it.\n\nUser: Thanks!";
+
+        let conversation = parse_markdown(log).unwrap();
+        assert_eq!(
+            texts(&conversation),
+            vec![
+                text(ImportedRole::User, "Summarize the meeting notes."),
+                text(
+                    ImportedRole::Assistant,
+                    "Here is the summary.\nThe notes say:\nAI: the new model ships Friday.\nUser: reviewed it."
+                ),
+                text(ImportedRole::User, "Thanks!"),
+            ]
+        );
+    }
+
+    #[test]
+    fn malformed_export_conversations_are_skipped() {
+        let export = json!([
+            {"title": "Broken", "mapping": "not a map"},
+            {"title": "Empty", "mapping": {}},
+            {
+                "title": "Fine",
+                "current_node": "u1",
+                "mapping": {
+                    "u1": {
+                        "message": {"author": {"role": "user"}, "content": {"parts": ["hi"]}},
+                        "parent": null,
+                        "children": []
+                    }
+                }
+            }
+        ]);
+
+        let parsed = parse_openai_export(&export.to_string()).unwrap();
+        let skipped: Vec<(usize, Option<String>)> = parsed
+            .iter()
+            .filter_map(|entry| entry.as_ref().err())
+            .map(|skipped| (skipped.index, skipped.title.clone()))
+            .collect();
+        assert_eq!(
+            skipped,
+            vec![
+                (1, Some("Broken".to_string())),
+                (2, Some("Empty".to_string())),
+            ]
+        );
+        let imported: Vec<&ImportedConversation> = parsed
+            .iter()
+            .filter_map(|entry| entry.as_ref().ok())
+            .collect();
+        assert_eq!(imported.len(), 1);
+        assert_eq!(imported[0].title.as_deref(), Some("Fine"));
+    }
+
+    #[tokio::test]
+    async fn imported_session_is_registered_in_catalog() {
+        let code_home = tempfile::tempdir().unwrap();
+        let cwd = tempfile::tempdir().unwrap();
+        let log_path = cwd.path().join("chat.md");
+        std::fs::write(&log_path, "User: hello\nAssistant: hi there\n").unwrap();
+
+        let sessions = import_file(code_home.path(), None, cwd.path(), &log_path, None)
+            .await
+            .unwrap()
+            .sessions;
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].message_count, 2);
+        assert!(sessions[0].rollout_path.starts_with(code_home.path()));
+
+        let entry = SessionCatalog::new(code_home.path().to_path_buf())
+            .find_by_id(&sessions[0].session_id.to_string())
+            .await
+            .unwrap()
+            .expect("imported session in catalog");
+        assert_eq!(entry.session_source, SessionSource::Cli);
+        assert_eq!(entry.user_message_count, 1);
+        assert_eq!(entry.last_user_snippet.as_deref(), Some("hello"));
+        assert_eq!(entry.cwd_real, cwd.path().to_path_buf());
+    }
+}
@@ -160,7 +160,7 @@ impl ConversationManager {
         path: PathBuf,
     ) -> CodexResult<NewConversation> {
         // Compute the prefix up to the cut point.
-        let history = RolloutRecorder::get_rollout_history(&path).await?;
+        let history = RolloutRecorder::get_rollout_history(&config.code_home, &path).await?;
         let history = truncate_after_dropping_last_messages(history, num_messages_to_drop);
 
         // If there is no prior history to seed, just start a fresh conversation.
@@ -1,3 +1,4 @@
+use crate::at_rest::AtRestCipher;
 use chrono::Local;
 use code_otel::otel_event_manager::TurnLatencyPayload;
 use serde_json::Value;
@@ -5,7 +6,9 @@ use std::collections::HashMap;
 use std::fs::OpenOptions;
 use std::fs::{self};
 use std::io::Write;
+use std::path::Path;
 use std::path::PathBuf;
+use std::sync::Arc;
 use std::sync::Mutex;
 use uuid::Uuid;
 
@@ -25,6 +28,8 @@ pub struct DebugLogger {
     session_usage_file: Mutex<PathBuf>,
     turn_latency_dir: PathBuf,
     turn_latency_file: Mutex<Option<PathBuf>>,
+    // Seals every artifact before it hits disk when `encrypt_at_rest` is on.
+    cipher: Option<Arc<AtRestCipher>>,
 }
 
 impl DebugLogger {
@@ -38,6 +43,7 @@ impl DebugLogger {
                 session_usage_file: Mutex::new(PathBuf::new()),
                 turn_latency_dir: PathBuf::new(),
                 turn_latency_file: Mutex::new(None),
+                cipher: None,
             });
         }
 
@@ -61,9 +67,24 @@ impl DebugLogger {
             session_usage_file: Mutex::new(PathBuf::new()),
             turn_latency_dir,
             turn_latency_file: Mutex::new(None),
+            cipher: None,
         })
     }
 
+    /// Encrypt every log artifact written by this logger with `cipher`.
+    pub fn with_at_rest_cipher(mut self, cipher: Option<Arc<AtRestCipher>>) -> Self {
+        self.cipher = cipher;
+        self
+    }
+
+    fn write_artifact(
+        &self,
+        path: impl AsRef<Path>,
+        contents: impl AsRef<[u8]>,
+    ) -> Result<(), std::io::Error> {
+        crate::at_rest::write_maybe_sealed(self.cipher.as_deref(), path.as_ref(), contents.as_ref())
+    }
+
     fn ensure_log_dir(&self, tag: Option<&str>) -> Result<PathBuf, std::io::Error> {
         let Some(tag) = tag else {
             return Ok(self.log_dir.clone());
@@ -135,7 +156,7 @@ impl DebugLogger {
 
         // Write pretty-printed JSON to request file
         let formatted_request = serde_json::to_string_pretty(&request_entry)?;
-        fs::write(&request_file_path, formatted_request)?;
+        self.write_artifact(&request_file_path, formatted_request)?;
 
         // Prepare response file path
         let response_filename = format!(
@@ -210,7 +231,7 @@ impl DebugLogger {
 
             // Write pretty-printed JSON to response file
             let formatted_response = serde_json::to_string_pretty(&response_data)?;
-            fs::write(&stream_info.response_file, formatted_response)?;
+            self.write_artifact(&stream_info.response_file, formatted_response)?;
         }
 
         Ok(())
@@ -230,8 +251,8 @@ impl DebugLogger {
         };
 
         let mut entries: Vec<Value> = if path.exists() {
-            let contents = fs::read_to_string(&path)?;
-            serde_json::from_str(&contents).unwrap_or_default()
+            let contents = crate::at_rest::read_maybe_sealed(self.cipher.as_deref(), &path)?;
+            serde_json::from_slice(&contents).unwrap_or_default()
         } else {
             Vec::new()
         };
@@ -239,7 +260,7 @@ impl DebugLogger {
         entries.push(usage);
 
         let formatted = serde_json::to_string_pretty(&entries)?;
-        fs::write(path, formatted)?;
+        self.write_artifact(path, formatted)?;
         Ok(())
     }
 
@@ -262,7 +283,7 @@ impl DebugLogger {
         }
 
         if !path.exists() {
-            fs::write(&path, "[]")?;
+            self.write_artifact(&path, "[]")?;
         }
 
         let mut guard = self.session_usage_file.lock().expect("usage lock poisoned");
@@ -327,8 +348,12 @@ impl DebugLogger {
             }),
         };
 
+        let mut line = serde_json::to_string(&entry)?;
+        if let Some(cipher) = self.cipher.as_deref() {
+            line = cipher.seal_line(&line)?;
+        }
         let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
-        writeln!(file, "{}", serde_json::to_string(&entry)?)?;
+        writeln!(file, "{line}")?;
         Ok(())
     }
 
@@ -375,7 +400,7 @@ impl DebugLogger {
         });
 
         let formatted = serde_json::to_string_pretty(&log_entry)?;
-        fs::write(file_path, formatted)?;
+        self.write_artifact(file_path, formatted)?;
 
         Ok(())
     }
@@ -404,7 +429,7 @@ impl DebugLogger {
         });
 
         let formatted = serde_json::to_string_pretty(&log_entry)?;
-        fs::write(file_path, formatted)?;
+        self.write_artifact(file_path, formatted)?;
 
         Ok(())
     }
@@ -432,7 +457,7 @@ impl DebugLogger {
             chunk
         );
 
-        fs::write(file_path, log_entry)?;
+        self.write_artifact(file_path, log_entry)?;
 
         Ok(())
     }
@@ -460,11 +485,29 @@ impl DebugLogger {
             error
         );
 
-        fs::write(file_path, log_entry)?;
+        self.write_artifact(file_path, log_entry)?;
 
         Ok(())
     }
 
+    /// Write `payload` as pretty JSON to `name` under the `tag` directory and
+    /// return the file's path; `None` when logging is disabled.
+    pub fn log_artifact(
+        &self,
+        tag: &str,
+        name: &str,
+        payload: &Value,
+    ) -> Result<Option<PathBuf>, std::io::Error> {
+        if !self.enabled {
+            return Ok(None);
+        }
+
+        let file_path = self.ensure_log_dir(Some(tag))?.join(name);
+        let formatted = serde_json::to_string_pretty(payload)?;
+        self.write_artifact(&file_path, formatted)?;
+        Ok(Some(file_path))
+    }
+
     pub fn is_enabled(&self) -> bool {
         self.enabled
     }
@@ -494,7 +537,7 @@ impl DebugLogger {
         });
 
         let formatted = serde_json::to_string_pretty(&log_entry)?;
-        fs::write(file_path, formatted)?;
+        self.write_artifact(file_path, formatted)?;
 
         Ok(())
     }
@@ -1,3 +1,7 @@
+use crate::http_client::ProviderTls;
+use crate::http_client::apply_proxy;
+use crate::http_client::resolve_proxy;
+use crate::model_provider_info::ModelProviderInfo;
 use reqwest::header::HeaderValue;
 use std::sync::LazyLock;
 use std::sync::Mutex;
@@ -69,7 +73,24 @@ fn sanitize_user_agent(candidate: String, fallback: &str) -> String {
 }
 
 /// Create a reqwest client with default `originator` and `User-Agent` headers set.
+/// Proxies come from the environment (see [`crate::http_client::ProxyConfig`]).
 pub fn create_client(originator: &str) -> reqwest::Client {
+    build_client(originator, None)
+}
+
+/// Like [`create_client`], but applies `provider`'s connection settings: its
+/// `proxy` replaces the environment's proxies, and `ca_bundle_path` and
+/// `client_cert_path`/`client_key_path` add trusted roots and a client
+/// certificate. Unusable TLS files are logged and skipped here; startup
+/// validates them for the active provider.
+pub fn create_client_for_provider(
+    originator: &str,
+    provider: &ModelProviderInfo,
+) -> reqwest::Client {
+    build_client(originator, Some(provider))
+}
+
+fn build_client(originator: &str, provider: Option<&ModelProviderInfo>) -> reqwest::Client {
     use reqwest::header::HeaderMap;
     use reqwest::header::HeaderValue;
 
@@ -79,12 +100,23 @@ pub fn create_client(originator: &str) -> reqwest::Client {
     headers.insert("originator", originator_value);
     let ua = get_code_user_agent(Some(originator));
 
-    match reqwest::Client::builder()
+    let builder = reqwest::Client::builder()
         // Set UA via dedicated helper to avoid header validation pitfalls
         .user_agent(ua)
-        .default_headers(headers)
-        .build()
-    {
+        .default_headers(headers);
+    let builder = apply_proxy(
+        builder,
+        &resolve_proxy(provider.and_then(|provider| provider.proxy.as_deref())),
+    );
+    let tls = provider.map_or_else(|| Ok(ProviderTls::default()), ModelProviderInfo::load_tls);
+    let builder = match tls {
+        Ok(tls) => tls.apply(builder),
+        Err(err) => {
+            tracing::error!("ignoring unusable TLS settings: {err}");
+            builder
+        }
+    };
+    match builder.build() {
         Ok(client) => client,
         Err(_) => reqwest::Client::new(),
     }
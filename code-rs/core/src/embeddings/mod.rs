@@ -0,0 +1,211 @@
+//! Semantic retrieval: pluggable embedding providers and a per-repository
+//! vector store.
+//!
+//! An [`EmbeddingProvider`] (OpenAI or a local Ollama server, chosen by the
+//! `[embeddings]` config table) turns text into vectors. The [`VectorStore`]
+//! keeps those vectors on disk under `<code_home>/embeddings/`, one file per
+//! repository. [`SemanticIndex`] ties the two together so callers can index
+//! code chunks or past sessions once and then look them up by meaning.
+//! [`WorkspaceSearch`] keeps an index of the session's workspace behind the
+//! model's `semantic_search` tool.
+
+mod provider;
+mod store;
+mod workspace;
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use tokio::sync::Mutex;
+
+pub use provider::EmbeddingProvider;
+pub use provider::OllamaEmbeddings;
+pub use provider::OpenAiEmbeddings;
+pub use provider::provider_from_config;
+pub use store::Document;
+pub use store::ScoredDocument;
+pub use store::VectorStore;
+pub use store::store_path;
+use workspace::workspace_documents;
+
+use crate::config::Config;
+use crate::git_info::get_git_repo_root;
+
+/// Inputs sent to the provider per request when indexing.
+const EMBED_BATCH_SIZE: usize = 64;
+
+/// A vector store paired with the provider that fills and queries it.
+pub struct SemanticIndex {
+    provider: Box<dyn EmbeddingProvider>,
+    store: VectorStore,
+}
+
+impl SemanticIndex {
+    pub fn new(provider: Box<dyn EmbeddingProvider>, store: VectorStore) -> Self {
+        Self { provider, store }
+    }
+
+    pub fn store(&self) -> &VectorStore {
+        &self.store
+    }
+
+    /// Embeds the documents that are new or changed since they were last
+    /// indexed, then persists the store. Returns how many were embedded.
+    pub async fn index(&mut self, documents: Vec<Document>) -> Result<usize> {
+        let model = self.provider.model_id();
+        let stale: Vec<Document> = documents
+            .into_iter()
+            .filter(|document| self.store.needs_embedding(&model, document))
+            .collect();
+        if stale.is_empty() {
+            return Ok(0);
+        }
+        for batch in stale.chunks(EMBED_BATCH_SIZE) {
+            let texts: Vec<String> = batch.iter().map(|document| document.text.clone()).collect();
+            let vectors = self.provider.embed(&texts).await?;
+            anyhow::ensure!(
+                vectors.len() == batch.len(),
+                "embedding provider returned {} vectors for {} inputs",
+                vectors.len(),
+                batch.len()
+            );
+            for (document, vector) in batch.iter().cloned().zip(vectors) {
+                self.store.upsert(&model, document, vector);
+            }
+        }
+        self.store.save()?;
+        Ok(stale.len())
+    }
+
+    /// Makes the store hold exactly `documents`: drops the ones no longer
+    /// present, then embeds the new or changed ones like [`Self::index`].
+    pub async fn sync(&mut self, documents: Vec<Document>) -> Result<usize> {
+        let current: HashSet<&str> = documents.iter().map(|document| document.id.as_str()).collect();
+        let removed: Vec<String> = self
+            .store
+            .ids()
+            .filter(|id| !current.contains(id))
+            .map(str::to_string)
+            .collect();
+        for id in &removed {
+            self.store.remove(id);
+        }
+        let embedded = self.index(documents).await?;
+        if embedded == 0 && !removed.is_empty() {
+            self.store.save()?;
+        }
+        Ok(embedded)
+    }
+
+    /// Returns up to `limit` indexed documents closest in meaning to `query`.
+    pub async fn search(&self, query: &str, limit: usize) -> Result<Vec<ScoredDocument>> {
+        let mut vectors = self.provider.embed(&[query.to_string()]).await?;
+        let Some(vector) = vectors.pop() else {
+            anyhow::bail!("embedding provider returned no vector for the query");
+        };
+        Ok(self.store.search(&self.provider.model_id(), &vector, limit))
+    }
+}
+
+/// Semantic search over the workspace of one session.
+pub struct WorkspaceSearch {
+    root: PathBuf,
+    index: Mutex<SemanticIndex>,
+}
+
+impl WorkspaceSearch {
+    /// Opens the index for the repository containing the session's working
+    /// directory (or the directory itself outside a Git repository) using the
+    /// configured provider.
+    pub async fn open(config: &Config) -> Result<Self> {
+        let provider = provider_from_config(config)?;
+        let root = get_git_repo_root(&config.cwd).unwrap_or_else(|| config.cwd.clone());
+        let path = store_path(&config.code_home, &root);
+        let store = tokio::task::spawn_blocking(move || VectorStore::open(path)).await??;
+        Ok(Self {
+            root,
+            index: Mutex::new(SemanticIndex::new(provider, store)),
+        })
+    }
+
+    /// Brings the index up to date with the workspace files, then returns up
+    /// to `limit` chunks closest in meaning to `query`. Only chunks that are
+    /// new or changed since the last search are embedded.
+    pub async fn search(&self, query: &str, limit: usize) -> Result<Vec<ScoredDocument>> {
+        let root = self.root.clone();
+        let documents = tokio::task::spawn_blocking(move || workspace_documents(&root)).await?;
+        let mut index = self.index.lock().await;
+        index.sync(documents).await?;
+        index.search(query, limit).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use pretty_assertions::assert_eq;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering;
+    use tempfile::tempdir;
+
+    /// Embeds text by counting a few keywords, and counts embedded inputs.
+    struct KeywordProvider {
+        embedded: std::sync::Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl EmbeddingProvider for KeywordProvider {
+        fn model_id(&self) -> String {
+            "test/keywords".to_string()
+        }
+
+        async fn embed(&self, inputs: &[String]) -> Result<Vec<Vec<f32>>> {
+            self.embedded.fetch_add(inputs.len(), Ordering::SeqCst);
+            Ok(inputs
+                .iter()
+                .map(|text| {
+                    ["parse", "render", "network"]
+                        .iter()
+                        .map(|keyword| text.matches(keyword).count() as f32)
+                        .collect()
+                })
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn indexes_only_changed_documents_and_searches_by_meaning() {
+        let dir = tempdir().unwrap();
+        let embedded = std::sync::Arc::new(AtomicUsize::new(0));
+        let provider = Box::new(KeywordProvider {
+            embedded: embedded.clone(),
+        });
+        let store = VectorStore::open(dir.path().join("store.jsonl")).unwrap();
+        let mut index = SemanticIndex::new(provider, store);
+        let documents = vec![
+            Document {
+                id: "a".to_string(),
+                source: "src/parser.rs".to_string(),
+                text: "parse tokens, parse expressions".to_string(),
+            },
+            Document {
+                id: "b".to_string(),
+                source: "src/render.rs".to_string(),
+                text: "render widgets".to_string(),
+            },
+        ];
+
+        assert_eq!(index.index(documents.clone()).await.unwrap(), 2);
+        assert_eq!(index.index(documents.clone()).await.unwrap(), 0);
+        assert_eq!(embedded.load(Ordering::SeqCst), 2);
+
+        let hits = index.search("how do we render?", 1).await.unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].document.source, "src/render.rs");
+
+        assert_eq!(index.sync(vec![documents[1].clone()]).await.unwrap(), 0);
+        assert_eq!(index.store().ids().collect::<Vec<_>>(), vec!["b"]);
+    }
+}
@@ -0,0 +1,211 @@
+use anyhow::Context;
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::auth::OPENAI_API_KEY_ENV_VAR;
+use crate::config::Config;
+use crate::config_types::EmbeddingProviderKind;
+use crate::default_client::create_client;
+
+const OPENAI_DEFAULT_BASE_URL: &str = "https://api.openai.com/v1";
+const OPENAI_DEFAULT_MODEL: &str = "text-embedding-3-small";
+const OLLAMA_DEFAULT_BASE_URL: &str = "http://localhost:11434";
+const OLLAMA_DEFAULT_MODEL: &str = "nomic-embed-text";
+
+/// Turns text into embedding vectors.
+#[async_trait]
+pub trait EmbeddingProvider: Send + Sync {
+    /// Identifies the provider and model. Vectors produced under different
+    /// ids live in different spaces and are never compared.
+    fn model_id(&self) -> String;
+
+    /// Embeds `inputs`, returning one vector per input in the same order.
+    async fn embed(&self, inputs: &[String]) -> Result<Vec<Vec<f32>>>;
+}
+
+/// Builds the provider selected by the `[embeddings]` config table.
+pub fn provider_from_config(config: &Config) -> Result<Box<dyn EmbeddingProvider>> {
+    let settings = config
+        .embeddings
+        .as_ref()
+        .context("add an [embeddings] table to config.toml to use semantic retrieval")?;
+    let client = create_client(&config.responses_originator_header);
+    let base_url = settings.base_url.as_deref().map(str::to_string);
+    match settings.provider {
+        EmbeddingProviderKind::Openai => {
+            let env_key = settings
+                .env_key
+                .as_deref()
+                .unwrap_or(OPENAI_API_KEY_ENV_VAR);
+            let api_key = std::env::var(env_key)
+                .ok()
+                .filter(|key| !key.trim().is_empty())
+                .with_context(|| format!("set {env_key} to use OpenAI embeddings"))?;
+            Ok(Box::new(OpenAiEmbeddings::new(
+                client,
+                base_url.unwrap_or_else(|| OPENAI_DEFAULT_BASE_URL.to_string()),
+                api_key,
+                settings
+                    .model
+                    .clone()
+                    .unwrap_or_else(|| OPENAI_DEFAULT_MODEL.to_string()),
+            )))
+        }
+        EmbeddingProviderKind::Ollama => Ok(Box::new(OllamaEmbeddings::new(
+            client,
+            base_url.unwrap_or_else(|| OLLAMA_DEFAULT_BASE_URL.to_string()),
+            settings
+                .model
+                .clone()
+                .unwrap_or_else(|| OLLAMA_DEFAULT_MODEL.to_string()),
+        ))),
+    }
+}
+
+/// OpenAI `/v1/embeddings`, or any endpoint compatible with it.
+pub struct OpenAiEmbeddings {
+    client: reqwest::Client,
+    base_url: String,
+    api_key: String,
+    model: String,
+}
+
+impl OpenAiEmbeddings {
+    pub fn new(client: reqwest::Client, base_url: String, api_key: String, model: String) -> Self {
+        Self {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key,
+            model,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct OpenAiEmbeddingResponse {
+    data: Vec<OpenAiEmbedding>,
+}
+
+#[derive(Deserialize)]
+struct OpenAiEmbedding {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+#[async_trait]
+impl EmbeddingProvider for OpenAiEmbeddings {
+    fn model_id(&self) -> String {
+        format!("openai/{}", self.model)
+    }
+
+    async fn embed(&self, inputs: &[String]) -> Result<Vec<Vec<f32>>> {
+        if inputs.is_empty() {
+            return Ok(Vec::new());
+        }
+        let response: OpenAiEmbeddingResponse = self
+            .client
+            .post(format!("{}/embeddings", self.base_url))
+            .bearer_auth(&self.api_key)
+            .json(&json!({ "model": self.model, "input": inputs }))
+            .send()
+            .await?
+            .error_for_status()
+            .context("embedding request failed")?
+            .json()
+            .await
+            .context("failed to parse embedding response")?;
+        let mut data = response.data;
+        // The API does not promise to return entries in input order.
+        data.sort_by_key(|entry| entry.index);
+        Ok(data.into_iter().map(|entry| entry.embedding).collect())
+    }
+}
+
+/// A local Ollama server's `/api/embed` endpoint.
+pub struct OllamaEmbeddings {
+    client: reqwest::Client,
+    base_url: String,
+    model: String,
+}
+
+impl OllamaEmbeddings {
+    pub fn new(client: reqwest::Client, base_url: String, model: String) -> Self {
+        Self {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            model,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct OllamaEmbedResponse {
+    embeddings: Vec<Vec<f32>>,
+}
+
+#[async_trait]
+impl EmbeddingProvider for OllamaEmbeddings {
+    fn model_id(&self) -> String {
+        format!("ollama/{}", self.model)
+    }
+
+    async fn embed(&self, inputs: &[String]) -> Result<Vec<Vec<f32>>> {
+        if inputs.is_empty() {
+            return Ok(Vec::new());
+        }
+        let response: OllamaEmbedResponse = self
+            .client
+            .post(format!("{}/api/embed", self.base_url))
+            .json(&json!({ "model": self.model, "input": inputs }))
+            .send()
+            .await?
+            .error_for_status()
+            .context("embedding request failed")?
+            .json()
+            .await
+            .context("failed to parse embedding response")?;
+        Ok(response.embeddings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use wiremock::Mock;
+    use wiremock::MockServer;
+    use wiremock::ResponseTemplate;
+    use wiremock::matchers::method;
+    use wiremock::matchers::path;
+
+    #[tokio::test]
+    async fn openai_embeddings_are_returned_in_input_order() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/embeddings"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": [
+                    { "index": 1, "embedding": [0.0, 1.0] },
+                    { "index": 0, "embedding": [1.0, 0.0] },
+                ]
+            })))
+            .mount(&server)
+            .await;
+
+        let provider = OpenAiEmbeddings::new(
+            reqwest::Client::new(),
+            format!("{}/v1/", server.uri()),
+            "sk-test".to_string(),
+            "text-embedding-3-small".to_string(),
+        );
+        let vectors = provider
+            .embed(&["first".to_string(), "second".to_string()])
+            .await
+            .unwrap();
+
+        assert_eq!(vectors, vec![vec![1.0, 0.0], vec![0.0, 1.0]]);
+        assert_eq!(provider.model_id(), "openai/text-embedding-3-small");
+    }
+}
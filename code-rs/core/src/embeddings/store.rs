@@ -0,0 +1,223 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::BufWriter;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use anyhow::Result;
+use serde::Deserialize;
+use serde::Serialize;
+use sha1::Digest;
+use sha1::Sha1;
+
+/// A piece of text to index, such as a file chunk or a past session summary.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Document {
+    /// Stable identifier; indexing a document with the same id replaces it.
+    pub id: String,
+    /// Where the text came from (a path, a session id, ...).
+    pub source: String,
+    pub text: String,
+}
+
+/// A search hit with its cosine similarity to the query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScoredDocument {
+    pub document: Document,
+    pub score: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredVector {
+    model: String,
+    content_hash: String,
+    document: Document,
+    embedding: Vec<f32>,
+}
+
+/// Location of the vector store for `repo_root` under `code_home`.
+pub fn store_path(code_home: &Path, repo_root: &Path) -> PathBuf {
+    let mut hasher = Sha1::new();
+    hasher.update(repo_root.to_string_lossy().as_bytes());
+    let key = format!("{:x}", hasher.finalize());
+    code_home.join("embeddings").join(format!("{key}.jsonl"))
+}
+
+/// Embedded documents for one repository, persisted as JSONL.
+#[derive(Debug)]
+pub struct VectorStore {
+    path: PathBuf,
+    entries: BTreeMap<String, StoredVector>,
+}
+
+impl VectorStore {
+    /// Loads the store at `path`; a missing file yields an empty store.
+    pub fn open(path: PathBuf) -> Result<Self> {
+        let mut entries = BTreeMap::new();
+        match File::open(&path) {
+            Ok(file) => {
+                for line in BufReader::new(file).lines() {
+                    let line = line?;
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    match serde_json::from_str::<StoredVector>(&line) {
+                        Ok(entry) => {
+                            entries.insert(entry.document.id.clone(), entry);
+                        }
+                        Err(err) => tracing::warn!("skipping malformed vector store line: {err}"),
+                    }
+                }
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("failed to open vector store {}", path.display()));
+            }
+        }
+        Ok(Self { path, entries })
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Whether `document` is missing, changed, or was embedded by another model.
+    pub fn needs_embedding(&self, model: &str, document: &Document) -> bool {
+        self.entries.get(&document.id).is_none_or(|entry| {
+            entry.model != model || entry.content_hash != content_hash(&document.text)
+        })
+    }
+
+    pub fn upsert(&mut self, model: &str, document: Document, embedding: Vec<f32>) {
+        self.entries.insert(
+            document.id.clone(),
+            StoredVector {
+                model: model.to_string(),
+                content_hash: content_hash(&document.text),
+                document,
+                embedding,
+            },
+        );
+    }
+
+    /// Ids of the stored documents, in order.
+    pub fn ids(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(String::as_str)
+    }
+
+    pub fn remove(&mut self, id: &str) -> bool {
+        self.entries.remove(id).is_some()
+    }
+
+    /// Returns up to `limit` documents embedded by `model`, most similar first.
+    pub fn search(&self, model: &str, query: &[f32], limit: usize) -> Vec<ScoredDocument> {
+        let mut hits: Vec<ScoredDocument> = self
+            .entries
+            .values()
+            .filter(|entry| entry.model == model)
+            .filter_map(|entry| {
+                cosine_similarity(query, &entry.embedding).map(|score| ScoredDocument {
+                    document: entry.document.clone(),
+                    score,
+                })
+            })
+            .collect();
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+        hits.truncate(limit);
+        hits
+    }
+
+    /// Writes the store to disk, replacing the previous file atomically.
+    pub fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create {}", parent.display()))?;
+        }
+        let tmp_path = self.path.with_extension("jsonl.tmp");
+        let mut writer = BufWriter::new(File::create(&tmp_path)?);
+        for entry in self.entries.values() {
+            serde_json::to_writer(&mut writer, entry)?;
+            writer.write_all(b"\n")?;
+        }
+        writer.flush()?;
+        drop(writer);
+        std::fs::rename(&tmp_path, &self.path)
+            .with_context(|| format!("failed to write vector store {}", self.path.display()))
+    }
+}
+
+fn content_hash(text: &str) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(text.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+fn cosine_similarity(a: &[f32], b: &[f32]) -> Option<f32> {
+    if a.len() != b.len() || a.is_empty() {
+        return None;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return None;
+    }
+    Some(dot / (norm_a * norm_b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::tempdir;
+
+    fn doc(id: &str, text: &str) -> Document {
+        Document {
+            id: id.to_string(),
+            source: format!("src/{id}.rs"),
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn search_ranks_by_similarity_and_survives_reload() {
+        let dir = tempdir().unwrap();
+        let path = store_path(dir.path(), Path::new("/repo"));
+        let mut store = VectorStore::open(path.clone()).unwrap();
+        assert!(store.is_empty());
+
+        store.upsert("m", doc("parser", "fn parse()"), vec![1.0, 0.0]);
+        store.upsert("m", doc("render", "fn render()"), vec![0.6, 0.8]);
+        store.upsert("other", doc("legacy", "fn legacy()"), vec![1.0, 0.0]);
+        store.save().unwrap();
+
+        let store = VectorStore::open(path).unwrap();
+        let ids: Vec<String> = store
+            .search("m", &[0.0, 1.0], 10)
+            .into_iter()
+            .map(|hit| hit.document.id)
+            .collect();
+        assert_eq!(ids, vec!["render".to_string(), "parser".to_string()]);
+    }
+
+    #[test]
+    fn changed_text_or_model_needs_reembedding() {
+        let dir = tempdir().unwrap();
+        let mut store = VectorStore::open(dir.path().join("store.jsonl")).unwrap();
+        store.upsert("m", doc("a", "one"), vec![1.0]);
+
+        assert!(!store.needs_embedding("m", &doc("a", "one")));
+        assert!(store.needs_embedding("m", &doc("a", "two")));
+        assert!(store.needs_embedding("other", &doc("a", "one")));
+        assert!(store.needs_embedding("m", &doc("b", "one")));
+    }
+}
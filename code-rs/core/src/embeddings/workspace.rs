@@ -0,0 +1,83 @@
+use std::path::Path;
+
+use ignore::WalkBuilder;
+
+use super::Document;
+
+/// Lines per indexed chunk.
+const CHUNK_LINES: usize = 60;
+/// Larger files (lockfiles, bundles, generated data) are not indexed.
+const MAX_FILE_BYTES: u64 = 256 * 1024;
+
+/// Splits the text files under `root` into chunks of [`CHUNK_LINES`] lines,
+/// identified by path and first line. Hidden files, files Git ignores, and
+/// binary or non-UTF-8 files are skipped.
+pub(crate) fn workspace_documents(root: &Path) -> Vec<Document> {
+    let mut documents = Vec::new();
+    for entry in WalkBuilder::new(root).build().flatten() {
+        if !entry
+            .file_type()
+            .is_some_and(|file_type| file_type.is_file())
+        {
+            continue;
+        }
+        if !entry
+            .metadata()
+            .is_ok_and(|metadata| metadata.len() <= MAX_FILE_BYTES)
+        {
+            continue;
+        }
+        let Ok(text) = std::fs::read_to_string(entry.path()) else {
+            continue;
+        };
+        if text.contains('\0') {
+            continue;
+        }
+        let path = entry.path().strip_prefix(root).unwrap_or(entry.path());
+        let path = path.to_string_lossy();
+        let lines: Vec<&str> = text.lines().collect();
+        for (index, chunk) in lines.chunks(CHUNK_LINES).enumerate() {
+            let text = chunk.join("\n");
+            if text.trim().is_empty() {
+                continue;
+            }
+            let first = index * CHUNK_LINES + 1;
+            let last = first + chunk.len() - 1;
+            documents.push(Document {
+                id: format!("{path}:{first}"),
+                source: format!("{path}:{first}-{last}"),
+                text,
+            });
+        }
+    }
+    documents
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::tempdir;
+
+    #[test]
+    fn chunks_text_files_and_skips_ignored_and_binary_ones() {
+        let dir = tempdir().unwrap();
+        std::fs::create_dir(dir.path().join(".git")).unwrap();
+        std::fs::write(dir.path().join(".gitignore"), "target/\n").unwrap();
+        std::fs::create_dir(dir.path().join("target")).unwrap();
+        std::fs::write(dir.path().join("target/out.rs"), "fn built() {}\n").unwrap();
+        std::fs::write(dir.path().join("logo.png"), b"\x89PNG\0\0").unwrap();
+        let source: String = (1..=70).map(|line| format!("line {line}\n")).collect();
+        std::fs::write(dir.path().join("lib.rs"), source).unwrap();
+
+        let mut sources: Vec<String> = workspace_documents(dir.path())
+            .into_iter()
+            .map(|document| document.source)
+            .collect();
+        sources.sort();
+        assert_eq!(
+            sources,
+            vec!["lib.rs:1-60".to_string(), "lib.rs:61-70".to_string()]
+        );
+    }
+}
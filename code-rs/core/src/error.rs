@@ -1,4 +1,5 @@
 use crate::exec::ExecToolCallOutput;
+use crate::http_client::tls_failure_detail;
 use chrono::DateTime;
 use chrono::Duration as ChronoDuration;
 use chrono::Utc;
@@ -127,6 +128,36 @@ pub enum CodexErr {
     #[error("{0}")]
     ServerError(String),
 
+    /// The provider's safety filters blocked the prompt or the response.
+    /// Retrying the same request gets the same answer.
+    #[error("response blocked by the model provider: {0}")]
+    ContentBlocked(String),
+
+    /// The provider's circuit breaker is open after repeated failures and no
+    /// fallback provider is available.
+    #[error("{0}")]
+    ProviderUnavailable(ProviderUnavailableError),
+
+    /// The TLS handshake with the provider failed. Retrying cannot fix an
+    /// untrusted server certificate or a rejected client certificate.
+    #[error("{0}")]
+    Tls(TlsError),
+
+    /// The request exceeded a configured `[request_limits]` check and was
+    /// not sent.
+    #[error("{0}")]
+    RequestTooLarge(RequestTooLargeError),
+
+    /// The `mock` provider has no usable fixture for the request. Retrying
+    /// reads the same fixtures directory.
+    #[error("mock provider: {0}")]
+    MockFixture(String),
+
+    /// A dry-run [`RequestTap`](crate::request_tap::RequestTap) stopped the
+    /// request before it was authorized or sent.
+    #[error("dry run: request not sent")]
+    DryRun,
+
     /// Retry limit exceeded.
     #[error("{0}")]
     RetryLimit(RetryLimitReachedError),
@@ -218,6 +249,106 @@ impl std::fmt::Display for RetryLimitReachedError {
     }
 }
 
+#[derive(Debug)]
+pub struct ProviderUnavailableError {
+    pub provider: String,
+    pub consecutive_failures: u32,
+    /// Time until the circuit lets a request through again.
+    pub retry_in: Duration,
+    pub last_error: Option<String>,
+}
+
+impl std::fmt::Display for ProviderUnavailableError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} is unavailable after {} consecutive failed requests{}; retrying in {}s",
+            self.provider,
+            self.consecutive_failures,
+            self.last_error
+                .as_ref()
+                .map(|error| format!(" (last error: {error})"))
+                .unwrap_or_default(),
+            self.retry_in.as_secs_f64().ceil() as u64
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct TlsError {
+    pub provider: String,
+    /// Innermost error reported by the TLS stack.
+    pub detail: String,
+}
+
+impl TlsError {
+    /// Classifies a failed request; `None` unless it failed during the TLS
+    /// handshake.
+    pub(crate) fn from_request_error(provider: &str, err: &reqwest::Error) -> Option<Self> {
+        if !err.is_connect() {
+            return None;
+        }
+        tls_failure_detail(err).map(|detail| TlsError {
+            provider: provider.to_string(),
+            detail,
+        })
+    }
+}
+
+impl std::fmt::Display for TlsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "TLS handshake with {} failed: {}. If a proxy re-signs HTTPS traffic, set \
+             `ca_bundle_path` on the provider to its CA bundle; if the server requires a \
+             client certificate, set `client_cert_path` and `client_key_path`.",
+            self.provider, self.detail
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct RequestTooLargeError {
+    /// Which limit was exceeded, e.g. `request_limits.max_bytes`.
+    pub limit: &'static str,
+    pub max: u64,
+    pub actual: u64,
+    /// Largest conversation items, biggest first.
+    pub largest_items: Vec<RequestItemSize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestItemSize {
+    /// Position in the request input.
+    pub index: usize,
+    /// Item type, with the tool name for calls.
+    pub kind: String,
+    pub bytes: u64,
+}
+
+impl std::fmt::Display for RequestTooLargeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "request not sent: {} exceeds {} ({})",
+            self.actual, self.limit, self.max
+        )?;
+        if !self.largest_items.is_empty() {
+            let items = self
+                .largest_items
+                .iter()
+                .map(|item| format!("#{} {} ({} bytes)", item.index, item.kind, item.bytes))
+                .collect::<Vec<_>>()
+                .join(", ");
+            write!(f, ". Largest items: {items}")?;
+        }
+        write!(
+            f,
+            ". Run /compact to shrink the conversation, or raise the limit."
+        )
+    }
+}
+
 #[derive(Debug)]
 pub struct UsageLimitReachedError {
     pub plan_type: Option<String>,
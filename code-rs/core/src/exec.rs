@@ -11,10 +11,25 @@ use std::time::Duration;
 use std::time::Instant;
 
 use async_channel::Sender;
+#[cfg(unix)]
+use nix::sys::signal::Signal;
+#[cfg(unix)]
+use nix::sys::signal::killpg;
+#[cfg(unix)]
+use nix::sys::wait::Id;
+#[cfg(unix)]
+use nix::sys::wait::WaitPidFlag;
+#[cfg(unix)]
+use nix::sys::wait::WaitStatus;
+#[cfg(unix)]
+use nix::sys::wait::waitid;
+#[cfg(unix)]
+use nix::unistd::Pid;
 use tokio::io::AsyncRead;
 use tokio::io::AsyncReadExt;
 use tokio::io::BufReader;
 use tokio::process::Child;
+use tokio_util::sync::CancellationToken;
 
 use crate::codex::Session;
 use crate::error::CodexErr;
@@ -46,6 +61,13 @@ const TIMEOUT_CODE: i32 = 64;
 const EXIT_CODE_SIGNAL_BASE: i32 = 128; // conventional shell: 128 + signal
 const EXEC_TIMEOUT_EXIT_CODE: i32 = 124; // conventional timeout exit code
 
+/// How long a cancelled command's process group gets to exit after SIGTERM
+/// before it is sent SIGKILL.
+const CANCEL_GRACE_PERIOD: Duration = Duration::from_secs(2);
+/// How often [`terminate_process_group`] checks whether the child has exited.
+#[cfg(unix)]
+const LEADER_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
 // I/O buffer sizing
 const READ_CHUNK_SIZE: usize = 8192; // bytes per read
 const AGGREGATE_BUFFER_INITIAL_CAPACITY: usize = 8 * 1024; // 8 KiB
@@ -122,6 +144,9 @@ pub struct StdoutStream {
     /// Optional ordering metadata so UIs can associate deltas with the correct
     /// provider attempt/output index even when `session` is not available.
     pub(crate) order: Option<OrderMeta>,
+    /// When cancelled, the command's process group is terminated and the
+    /// exec fails with [`CodexErr::Interrupted`].
+    pub(crate) cancel: Option<CancellationToken>,
 }
 
 pub async fn process_exec_tool_call(
@@ -222,7 +247,7 @@ pub async fn process_exec_tool_call(
                 }));
             }
 
-            if exit_code != 0 && is_likely_sandbox_denied(sandbox_type, exit_code) {
+            if is_likely_sandbox_denied(sandbox_type, &exec_output) {
                 return Err(CodexErr::Sandbox(SandboxErr::Denied {
                     output: Box::new(exec_output),
                 }));
@@ -237,21 +262,36 @@ pub async fn process_exec_tool_call(
     }
 }
 
-/// We don't have a fully deterministic way to tell if our command failed
-/// because of the sandbox - a command in the user's zshrc file might hit an
-/// error, but the command itself might fail or succeed for other reasons.
-/// For now, we conservatively check for 'command not found' (exit code 127),
-/// and can add additional cases as necessary.
-fn is_likely_sandbox_denied(sandbox_type: SandboxType, exit_code: i32) -> bool {
-    if sandbox_type == SandboxType::None {
+/// Whether a failed sandboxed command was most likely blocked by the sandbox
+/// rather than failing on its own. A bare exit status proves nothing (any
+/// script may exit 126), so this looks for the errors the sandboxes produce.
+fn is_likely_sandbox_denied(sandbox_type: SandboxType, output: &ExecToolCallOutput) -> bool {
+    if sandbox_type == SandboxType::None || output.exit_code == 0 {
         return false;
     }
 
-    match exit_code {
-        126 => true,          // found but not executable (likely permission denial)
-        1 | 2 | 127 => false, // common non-sandbox failures
-        _ => false,
+    const SANDBOX_DENIED_KEYWORDS: [&str; 4] = [
+        "operation not permitted",
+        "permission denied",
+        "read-only file system",
+        "sandbox-exec",
+    ];
+    let mentions_denial = [&output.stderr.text, &output.aggregated_output.text]
+        .into_iter()
+        .any(|text| {
+            let text = text.to_ascii_lowercase();
+            SANDBOX_DENIED_KEYWORDS
+                .iter()
+                .any(|keyword| text.contains(keyword))
+        });
+    if mentions_denial {
+        return true;
     }
+
+    // seccomp kills blocked syscalls with SIGSYS.
+    const SIGSYS_CODE: i32 = 31;
+    sandbox_type == SandboxType::LinuxSeccomp
+        && output.exit_code == EXIT_CODE_SIGNAL_BASE + SIGSYS_CODE
 }
 
 #[derive(Debug, Clone)]
@@ -354,6 +394,16 @@ async fn consume_truncated_output(
     })?;
 
     let (agg_tx, agg_rx) = async_channel::unbounded::<Vec<u8>>();
+    let cancel = stdout_stream
+        .as_ref()
+        .and_then(|stream| stream.cancel.clone());
+    let cancelled = async {
+        match &cancel {
+            Some(token) => token.cancelled().await,
+            None => std::future::pending().await,
+        }
+    };
+    tokio::pin!(cancelled);
 
     let stdout_handle = tokio::spawn(read_capped(
         BufReader::new(stdout_reader),
@@ -380,11 +430,8 @@ async fn consume_truncated_output(
                         Err(_) => {
                             // timeout
                             #[cfg(unix)]
-                            {
-                                if let Some(pid) = killer.as_mut().id() {
-                                    // Best-effort kill entire process group
-                                    unsafe { libc::kill(-(pid as i32), libc::SIGKILL); }
-                                }
+                            if let Some(group) = ProcessGroup::of(killer.as_mut()) {
+                                group.signal(Signal::SIGKILL);
                             }
                             killer.as_mut().start_kill()?;
                             // Debatable whether `child.wait().await` should be called here.
@@ -396,6 +443,9 @@ async fn consume_truncated_output(
                     killer.as_mut().start_kill()?;
                     (synthetic_exit_status(EXIT_CODE_SIGNAL_BASE + SIGKILL_CODE), false)
                 }
+                _ = &mut cancelled => {
+                    return Err(cancel_child(killer, stdout_handle, stderr_handle).await);
+                }
             }
         }
         None => {
@@ -409,6 +459,9 @@ async fn consume_truncated_output(
                     killer.as_mut().start_kill()?;
                     (synthetic_exit_status(EXIT_CODE_SIGNAL_BASE + SIGKILL_CODE), false)
                 }
+                _ = &mut cancelled => {
+                    return Err(cancel_child(killer, stdout_handle, stderr_handle).await);
+                }
             }
         }
     };
@@ -469,6 +522,76 @@ async fn consume_truncated_output(
     })
 }
 
+/// Terminates a cancelled command and abandons its output readers, which may
+/// otherwise wait forever on pipes held open by orphaned grandchildren.
+async fn cancel_child(
+    mut killer: KillOnDrop,
+    stdout_handle: tokio::task::JoinHandle<io::Result<StreamOutput<Vec<u8>>>>,
+    stderr_handle: tokio::task::JoinHandle<io::Result<StreamOutput<Vec<u8>>>>,
+) -> CodexErr {
+    if let Some(child) = killer.child.take() {
+        terminate_process_group(child).await;
+    }
+    stdout_handle.abort();
+    stderr_handle.abort();
+    CodexErr::Interrupted
+}
+
+/// Sends SIGTERM to the child's process group, waits up to
+/// [`CANCEL_GRACE_PERIOD`] for the child to exit, then SIGKILLs whatever is
+/// left of the group before reaping the child.
+async fn terminate_process_group(mut child: Child) {
+    #[cfg(unix)]
+    if let Some(group) = ProcessGroup::of(&child) {
+        group.signal(Signal::SIGTERM);
+        let deadline = tokio::time::Instant::now() + CANCEL_GRACE_PERIOD;
+        while group.leader_running() && tokio::time::Instant::now() < deadline {
+            tokio::time::sleep(LEADER_POLL_INTERVAL).await;
+        }
+        group.signal(Signal::SIGKILL);
+    }
+    let _ = child.start_kill();
+    let _ = child.wait().await;
+}
+
+/// The process group a spawned child leads; `spawn_child_async` gives every
+/// child a new group whose id is the child's pid.
+///
+/// The group id can be reused once the leader has been reaped and the group
+/// is empty, so a `ProcessGroup` borrows the child: it only exists while the
+/// leader is unreaped, and `Child::wait` cannot run until it is dropped.
+#[cfg(unix)]
+struct ProcessGroup<'a> {
+    pgid: Pid,
+    _leader: std::marker::PhantomData<&'a ()>,
+}
+
+#[cfg(unix)]
+impl<'a> ProcessGroup<'a> {
+    /// The group led by `child`, or `None` once the child has been reaped.
+    fn of(child: &'a Child) -> Option<Self> {
+        let pid = i32::try_from(child.id()?).ok()?;
+        Some(Self {
+            pgid: Pid::from_raw(pid),
+            _leader: std::marker::PhantomData,
+        })
+    }
+
+    /// Best effort: the group may already be gone.
+    fn signal(&self, signal: Signal) {
+        let _ = killpg(self.pgid, signal);
+    }
+
+    /// Whether the leader has yet to exit. Leaves an exited leader unreaped.
+    fn leader_running(&self) -> bool {
+        let flags = WaitPidFlag::WEXITED | WaitPidFlag::WNOHANG | WaitPidFlag::WNOWAIT;
+        matches!(
+            waitid(Id::Pid(self.pgid), flags),
+            Ok(WaitStatus::StillAlive)
+        )
+    }
+}
+
 async fn read_capped<R: AsyncRead + Unpin + Send + 'static>(
     mut reader: R,
     stream: Option<StdoutStream>,
@@ -558,6 +681,10 @@ fn synthetic_exit_status(code: i32) -> ExitStatus {
 /// future is dropped before the child has exited. This prevents orphaned
 /// processes when a running turn is interrupted (e.g., user presses Esc or
 /// Ctrl+C) and the task executing the command is aborted.
+///
+/// The child's process group is only signalled while the child is unreaped
+/// (see [`ProcessGroup`]); once it has been waited on, the group id may
+/// belong to someone else.
 struct KillOnDrop {
     child: Option<Child>,
 }
@@ -576,8 +703,22 @@ impl KillOnDrop {
 
 impl Drop for KillOnDrop {
     fn drop(&mut self) {
-        if let Some(child) = self.child.as_mut() {
-            let _ = child.start_kill();
+        let Some(mut child) = self.child.take() else {
+            return;
+        };
+        // Give the whole process group the same grace period as an explicit
+        // cancellation; without a runtime, kill it right away.
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn(terminate_process_group(child));
+            }
+            Err(_) => {
+                #[cfg(unix)]
+                if let Some(group) = ProcessGroup::of(&child) {
+                    group.signal(Signal::SIGKILL);
+                }
+                let _ = child.start_kill();
+            }
         }
     }
 }
@@ -587,3 +728,101 @@ fn synthetic_exit_status(code: i32) -> ExitStatus {
     #[expect(clippy::unwrap_used)]
     std::process::ExitStatus::from_raw(code.try_into().unwrap())
 }
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use nix::errno::Errno;
+    use std::process::Stdio;
+
+    fn output(exit_code: i32, stderr: &str) -> ExecToolCallOutput {
+        ExecToolCallOutput {
+            exit_code,
+            stdout: StreamOutput::new(String::new()),
+            stderr: StreamOutput::new(stderr.to_string()),
+            aggregated_output: StreamOutput::new(stderr.to_string()),
+            duration: Duration::ZERO,
+            timed_out: false,
+        }
+    }
+
+    #[test]
+    fn exit_status_alone_is_not_a_sandbox_denial() {
+        assert!(!is_likely_sandbox_denied(
+            SandboxType::LinuxSeccomp,
+            &output(126, "")
+        ));
+        assert!(is_likely_sandbox_denied(
+            SandboxType::LinuxSeccomp,
+            &output(1, "touch: cannot touch 'x': Permission denied")
+        ));
+        assert!(is_likely_sandbox_denied(
+            SandboxType::LinuxSeccomp,
+            &output(EXIT_CODE_SIGNAL_BASE + 31, "")
+        ));
+        assert!(!is_likely_sandbox_denied(
+            SandboxType::None,
+            &output(1, "Operation not permitted")
+        ));
+    }
+
+    #[tokio::test]
+    async fn cancelled_exec_kills_process_group() {
+        let child = tokio::process::Command::new("sh")
+            .args(["-c", "sleep 30 & sleep 30"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .process_group(0)
+            .spawn()
+            .unwrap();
+        let pgid = Pid::from_raw(i32::try_from(child.id().unwrap()).unwrap());
+        let (tx_event, _rx_event) = async_channel::unbounded();
+        let cancel = CancellationToken::new();
+        let stream = StdoutStream {
+            sub_id: "sub".to_string(),
+            call_id: "call".to_string(),
+            tx_event,
+            session: None,
+            tail_buf: None,
+            order: None,
+            cancel: Some(cancel.clone()),
+        };
+
+        let started = Instant::now();
+        let exec = tokio::spawn(consume_truncated_output(child, None, Some(stream)));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        cancel.cancel();
+
+        let result = exec.await.unwrap();
+        assert!(matches!(result, Err(CodexErr::Interrupted)));
+        assert!(started.elapsed() < Duration::from_secs(10));
+
+        // The background `sleep` is gone too once its zombie is reaped by
+        // whoever inherited it.
+        while killpg(pgid, None).is_ok() && started.elapsed() < Duration::from_secs(10) {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(killpg(pgid, None), Err(Errno::ESRCH));
+    }
+
+    #[tokio::test]
+    async fn process_group_outlives_the_leader_until_it_is_reaped() {
+        let mut child = tokio::process::Command::new("sh")
+            .args(["-c", "exit 0"])
+            .process_group(0)
+            .spawn()
+            .unwrap();
+
+        let group = ProcessGroup::of(&child).unwrap();
+        let started = Instant::now();
+        while group.leader_running() {
+            assert!(started.elapsed() < Duration::from_secs(10));
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        // Checking for exit must not reap the leader.
+        assert!(child.id().is_some());
+
+        child.wait().await.unwrap();
+        assert!(ProcessGroup::of(&child).is_none());
+    }
+}
@@ -0,0 +1,240 @@
+//! Per-file advisory locks for agents that write to a shared workspace.
+//!
+//! Write agents normally get their own worktree, but sessions and agents that
+//! share a checkout (several `code exec` runs, parallel Auto Drive roles, an
+//! agent launched without a worktree) can patch the same file at the same
+//! time. `apply_patch` takes an exclusive lock on every path it touches before
+//! writing, so writes to one path are serialized across processes. Each lock
+//! file records the session that last held it, which lets a writer that had to
+//! wait, or whose patch no longer applies, report who it collided with.
+//!
+//! Lock files live in the system temp directory, named by a hash of the
+//! absolute path, so the workspace itself is never touched.
+
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::Instant;
+
+use fs2::FileExt;
+use sha1::Digest;
+use sha1::Sha1;
+
+/// Longest a writer waits for another writer to release a path.
+const LOCK_TIMEOUT: Duration = Duration::from_secs(30);
+const RETRY_SLEEP: Duration = Duration::from_millis(50);
+
+/// Another writer held a path this writer needed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Contention {
+    pub path: PathBuf,
+    pub holder: String,
+    pub waited: Duration,
+}
+
+struct HeldLock {
+    path: PathBuf,
+    file: File,
+    /// Owner recorded by the previous holder, if it was someone else.
+    previous_owner: Option<String>,
+}
+
+/// Exclusive locks on a set of paths; released on drop.
+pub(crate) struct FileLocks {
+    held: Vec<HeldLock>,
+    contention: Vec<Contention>,
+}
+
+impl FileLocks {
+    /// Paths this writer had to wait for.
+    pub fn contention(&self) -> &[Contention] {
+        &self.contention
+    }
+
+    /// The other session that last wrote `path`, if any.
+    pub fn previous_owner(&self, path: &Path) -> Option<&str> {
+        self.held
+            .iter()
+            .find(|lock| lock.path == path)
+            .and_then(|lock| lock.previous_owner.as_deref())
+    }
+}
+
+impl Drop for FileLocks {
+    fn drop(&mut self) {
+        for lock in &self.held {
+            let _ = FileExt::unlock(&lock.file);
+        }
+    }
+}
+
+/// Label other writers see for this session.
+pub(crate) fn owner_label(session_id: &impl std::fmt::Display) -> String {
+    format!("session {session_id} (pid {})", std::process::id())
+}
+
+/// Locks every path in `paths` for `owner`, waiting up to [`LOCK_TIMEOUT`]
+/// for each. Paths are locked in sorted order so two writers with
+/// overlapping sets cannot deadlock.
+pub(crate) async fn lock_paths(paths: &[PathBuf], owner: &str) -> std::io::Result<FileLocks> {
+    lock_paths_in(&lock_dir(), paths, owner, LOCK_TIMEOUT).await
+}
+
+async fn lock_paths_in(
+    dir: &Path,
+    paths: &[PathBuf],
+    owner: &str,
+    timeout: Duration,
+) -> std::io::Result<FileLocks> {
+    std::fs::create_dir_all(dir)?;
+    let mut paths = paths.to_vec();
+    paths.sort();
+    paths.dedup();
+
+    let mut locks = FileLocks {
+        held: Vec::with_capacity(paths.len()),
+        contention: Vec::new(),
+    };
+    for path in paths {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(dir.join(lock_file_name(&path)))?;
+        let started = Instant::now();
+        let mut blocked = false;
+        let mut holder: Option<String> = None;
+        loop {
+            match file.try_lock_exclusive() {
+                Ok(()) => break,
+                Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => {
+                    if !blocked {
+                        blocked = true;
+                        holder = read_owner(&mut file);
+                    }
+                    if started.elapsed() >= timeout {
+                        return Err(std::io::Error::new(
+                            std::io::ErrorKind::WouldBlock,
+                            format!(
+                                "{} is locked by {}",
+                                path.display(),
+                                holder.as_deref().unwrap_or("another writer")
+                            ),
+                        ));
+                    }
+                    tokio::time::sleep(RETRY_SLEEP).await;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        if blocked {
+            locks.contention.push(Contention {
+                path: path.clone(),
+                holder: holder.unwrap_or_else(|| "another writer".to_string()),
+                waited: started.elapsed(),
+            });
+        }
+        let previous_owner = read_owner(&mut file).filter(|previous| previous != owner);
+        file.set_len(0)?;
+        file.seek(SeekFrom::Start(0))?;
+        file.write_all(owner.as_bytes())?;
+        locks.held.push(HeldLock {
+            path,
+            file,
+            previous_owner,
+        });
+    }
+    Ok(locks)
+}
+
+fn lock_dir() -> PathBuf {
+    std::env::temp_dir().join("code-file-locks")
+}
+
+fn lock_file_name(path: &Path) -> String {
+    let absolute = dunce::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let mut hasher = Sha1::new();
+    hasher.update(absolute.to_string_lossy().as_bytes());
+    format!("{:x}.lock", hasher.finalize())
+}
+
+fn read_owner(file: &mut File) -> Option<String> {
+    let mut owner = String::new();
+    file.seek(SeekFrom::Start(0)).ok()?;
+    file.read_to_string(&mut owner).ok()?;
+    let owner = owner.trim();
+    (!owner.is_empty()).then(|| owner.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[tokio::test]
+    async fn serializes_writers_and_reports_the_holder() {
+        let dir = tempfile::tempdir().unwrap();
+        let locks_dir = dir.path().join("locks");
+        let shared = dir.path().join("src/lib.rs");
+        let other = dir.path().join("src/main.rs");
+
+        let first = lock_paths_in(
+            &locks_dir,
+            &[shared.clone()],
+            "session a",
+            Duration::from_secs(5),
+        )
+        .await
+        .unwrap();
+        assert!(first.contention().is_empty());
+
+        // A disjoint path is not blocked.
+        let unrelated = lock_paths_in(
+            &locks_dir,
+            &[other.clone()],
+            "session b",
+            Duration::from_millis(10),
+        )
+        .await
+        .unwrap();
+        drop(unrelated);
+
+        let err = lock_paths_in(
+            &locks_dir,
+            &[other.clone(), shared.clone()],
+            "session b",
+            Duration::from_millis(100),
+        )
+        .await
+        .err()
+        .unwrap();
+        assert_eq!(
+            err.to_string(),
+            format!("{} is locked by session a", shared.display())
+        );
+
+        let waiter = tokio::spawn({
+            let locks_dir = locks_dir.clone();
+            let shared = shared.clone();
+            async move {
+                lock_paths_in(&locks_dir, &[shared], "session b", Duration::from_secs(5)).await
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        drop(first);
+
+        let second = waiter.await.unwrap().unwrap();
+        let contention = second.contention();
+        assert_eq!(contention.len(), 1);
+        assert_eq!(contention[0].path, shared);
+        assert_eq!(contention[0].holder, "session a");
+        assert_eq!(second.previous_owner(&shared), Some("session a"));
+    }
+}
@@ -0,0 +1,928 @@
+//! Client for Google's Gemini API (`wire_api = "gemini"`).
+//!
+//! Requests go to `models/<model>:streamGenerateContent?alt=sse`, or the
+//! same method of the `google` publisher model on Vertex AI. The base
+//! instructions and developer messages become the `systemInstruction`,
+//! function tools become `functionDeclarations`, and function calls and their
+//! outputs become `functionCall` / `functionResponse` parts. Gemini has no
+//! end-of-stream marker, so the stream is complete once a chunk carries a
+//! `finishReason`; responses stopped by the safety filters surface as
+//! [`CodexErr::ContentBlocked`].
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use bytes::Bytes;
+use chrono::Utc;
+use code_otel::otel_event_manager::OtelEventManager;
+use code_protocol::models::ContentItem;
+use code_protocol::models::ReasoningItemContent;
+use code_protocol::models::ResponseItem;
+use eventsource_stream::Eventsource;
+use futures::Stream;
+use futures::StreamExt;
+use futures::TryStreamExt;
+use reqwest::StatusCode;
+use serde_json::Value;
+use serde_json::json;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use tracing::debug;
+use tracing::trace;
+use uuid::Uuid;
+
+use crate::ModelProviderInfo;
+use crate::auth::AuthManager;
+use crate::client_common::Prompt;
+use crate::client_common::ResponseEvent;
+use crate::client_common::ResponseStream;
+use crate::debug_logger::DebugLogger;
+use crate::error::CodexErr;
+use crate::error::Result;
+use crate::error::RetryAfter;
+use crate::model_family::ModelFamily;
+use crate::openai_model_info::get_model_info;
+use crate::openai_tools::create_tools_json_for_chat_completions_api;
+use crate::protocol::TokenUsage;
+use crate::quota_ledger;
+use crate::request_middleware::MiddlewareStack;
+use crate::request_retry::Attempt;
+use crate::request_retry::Rejected;
+use crate::request_retry::Rejection;
+use crate::request_retry::is_retryable_status;
+use crate::request_retry::retry_after;
+use crate::request_retry::send_with_retries;
+use crate::request_tap::RequestTap;
+use crate::response_anomaly::ResponseAnomaly;
+use crate::sse_buffer::SSE_CHANNEL_CAPACITY;
+use crate::util::backoff;
+
+/// Used for `maxOutputTokens` when the model is unknown.
+const DEFAULT_MAX_OUTPUT_TOKENS: u64 = 8_192;
+
+/// Finish reasons meaning the safety filters stopped the response.
+const BLOCKED_FINISH_REASONS: [&str; 6] = [
+    "SAFETY",
+    "RECITATION",
+    "BLOCKLIST",
+    "PROHIBITED_CONTENT",
+    "SPII",
+    "IMAGE_SAFETY",
+];
+
+/// JSON Schema keywords the function declaration schema rejects.
+const UNSUPPORTED_SCHEMA_KEYS: [&str; 3] = ["additionalProperties", "$schema", "strict"];
+
+pub(crate) async fn stream_gemini(
+    prompt: &Prompt,
+    model_family: &ModelFamily,
+    model_slug: &str,
+    client: &reqwest::Client,
+    provider: &ModelProviderInfo,
+    debug_logger: &Arc<Mutex<DebugLogger>>,
+    auth_manager: Option<Arc<AuthManager>>,
+    otel_event_manager: Option<OtelEventManager>,
+    middleware: &MiddlewareStack,
+    request_tap: Option<&RequestTap>,
+    log_tag: Option<&str>,
+) -> Result<ResponseStream> {
+    if prompt.output_schema.is_some() {
+        return Err(CodexErr::UnsupportedOperation(
+            "output_schema is not supported for the Gemini API".to_string(),
+        ));
+    }
+
+    let mut payload = build_generate_content_payload(prompt, model_family)?;
+    let endpoint = provider.get_gemini_stream_url(model_slug);
+    let extra_headers = middleware.prepare(&endpoint, &mut payload);
+    debug!(
+        "POST to {}: {}",
+        endpoint,
+        serde_json::to_string_pretty(&payload).unwrap_or_default()
+    );
+    if let Some(tap) = request_tap {
+        tap.observe(&endpoint, &extra_headers, &payload)?;
+    }
+
+    let estimate = quota_ledger::estimate_tokens(model_slug, prompt, model_family).await;
+    let delivered = send_with_retries(
+        provider,
+        debug_logger,
+        otel_event_manager.as_ref(),
+        log_tag,
+        estimate,
+        || {
+            let auth = auth_manager.as_ref().and_then(|m| m.auth());
+            let (payload, extra_headers) = (&payload, &extra_headers);
+            async move {
+                let request = provider
+                    .create_gemini_request_builder(client, &auth, model_slug)
+                    .await?
+                    .header(reqwest::header::ACCEPT, "text/event-stream")
+                    .json(payload)
+                    .headers(extra_headers.clone());
+                Ok(Attempt { request, auth })
+            }
+        },
+        |rejection: Rejection| async move {
+            let status = rejection.response.status();
+            if !is_retryable_status(status) {
+                return rejection.unexpected_status(debug_logger).await;
+            }
+            let retry_after_delay = retry_after(rejection.response.headers());
+            if status != StatusCode::TOO_MANY_REQUESTS {
+                return Rejected::Retry(rejection.retry_delay());
+            }
+            let Rejection {
+                response,
+                attempt,
+                is_last,
+                request_id,
+                ..
+            } = rejection;
+            let quota = QuotaExhausted::from_body(&response.text().await.unwrap_or_default());
+            let delay = retry_after_delay
+                .or_else(|| quota.as_ref().and_then(|quota| quota.retry_delay))
+                .unwrap_or_else(|| backoff(attempt));
+            if is_last && let Some(quota) = quota {
+                let label = if provider.vertex.is_some() {
+                    "vertex"
+                } else {
+                    "gemini"
+                };
+                return Rejected::Fatal(quota.into_error(label, delay, Some(request_id)));
+            }
+            Rejected::Retry(delay)
+        },
+    )
+    .await?;
+
+    let (tx_event, rx_event) = mpsc::channel::<Result<ResponseEvent>>(SSE_CHANNEL_CAPACITY);
+    let stream = delivered.response.bytes_stream().map_err(CodexErr::Reqwest);
+    tokio::spawn(process_gemini_sse(
+        stream,
+        tx_event,
+        provider.stream_idle_timeout(),
+        Arc::clone(debug_logger),
+        delivered.request_id,
+        otel_event_manager,
+    ));
+    Ok(ResponseStream {
+        rx_event,
+        served_by: None,
+        retries: delivered.retries,
+    })
+}
+
+/// A Google API `RESOURCE_EXHAUSTED` error, sent with a 429 once a quota of
+/// the project is used up.
+#[derive(Debug, PartialEq)]
+pub(crate) struct QuotaExhausted {
+    message: String,
+    /// The `google.rpc.RetryInfo` delay, when the error carries one.
+    retry_delay: Option<Duration>,
+}
+
+impl QuotaExhausted {
+    /// Parses an error body. Vertex AI wraps streaming errors in an array.
+    pub(crate) fn from_body(body: &str) -> Option<Self> {
+        let value = serde_json::from_str::<Value>(body).ok()?;
+        let error = match &value {
+            Value::Array(errors) => errors.first()?.get("error")?,
+            _ => value.get("error")?,
+        };
+        if error.get("status").and_then(Value::as_str) != Some("RESOURCE_EXHAUSTED") {
+            return None;
+        }
+        let retry_delay = error
+            .get("details")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter(|detail| {
+                detail
+                    .get("@type")
+                    .and_then(Value::as_str)
+                    .is_some_and(|kind| kind.ends_with("google.rpc.RetryInfo"))
+            })
+            .find_map(|detail| detail.get("retryDelay")?.as_str())
+            .and_then(|delay| delay.strip_suffix('s')?.parse::<f64>().ok())
+            .filter(|secs| secs.is_finite() && *secs >= 0.0)
+            .map(Duration::from_secs_f64);
+        Some(Self {
+            message: error
+                .get("message")
+                .and_then(Value::as_str)
+                .unwrap_or("RESOURCE_EXHAUSTED")
+                .to_string(),
+            retry_delay,
+        })
+    }
+
+    /// A stream error carrying the retry delay, so the turn is reported as
+    /// throttled rather than failed.
+    pub(crate) fn into_error(
+        self,
+        label: &str,
+        delay: Duration,
+        request_id: Option<String>,
+    ) -> CodexErr {
+        CodexErr::Stream(
+            format!("[{label}] quota exceeded: {}", self.message),
+            Some(RetryAfter::from_duration(delay, Utc::now())),
+            request_id,
+        )
+    }
+}
+
+/// Builds the `generateContent` request for `prompt`.
+fn build_generate_content_payload(prompt: &Prompt, model_family: &ModelFamily) -> Result<Value> {
+    let mut system = prompt.get_full_instructions(model_family).into_owned();
+    let contents = translate_input(&prompt.get_formatted_input(), &mut system);
+    let declarations = translate_tools(create_tools_json_for_chat_completions_api(&prompt.tools)?);
+    let max_output_tokens = get_model_info(model_family)
+        .map(|info| info.max_output_tokens)
+        .unwrap_or(DEFAULT_MAX_OUTPUT_TOKENS);
+
+    let mut generation_config = json!({ "maxOutputTokens": max_output_tokens });
+    if let Some(config) = generation_config.as_object_mut() {
+        let sampling = prompt.sampling;
+        if let Some(seed) = sampling.seed {
+            config.insert("seed".to_string(), json!(seed));
+        }
+        if let Some(temperature) = sampling.temperature {
+            config.insert("temperature".to_string(), json!(temperature));
+        }
+        if let Some(top_p) = sampling.top_p {
+            config.insert("topP".to_string(), json!(top_p));
+        }
+    }
+    let mut payload = json!({
+        "contents": contents,
+        "generationConfig": generation_config,
+    });
+    if let Some(obj) = payload.as_object_mut() {
+        if !system.trim().is_empty() {
+            obj.insert(
+                "systemInstruction".to_string(),
+                json!({ "parts": [{ "text": system }] }),
+            );
+        }
+        if !declarations.is_empty() {
+            obj.insert(
+                "tools".to_string(),
+                json!([{ "functionDeclarations": declarations }]),
+            );
+        }
+    }
+    Ok(payload)
+}
+
+/// Converts conversation items to Gemini `contents`. Developer and system
+/// messages are appended to `system`.
+fn translate_input(input: &[ResponseItem], system: &mut String) -> Vec<Value> {
+    let mut contents = Vec::<Value>::new();
+    // `functionResponse` parts name their function rather than the call.
+    let mut call_names = HashMap::<String, String>::new();
+
+    for item in input {
+        match item {
+            ResponseItem::Message { role, content, .. } => {
+                if role == "developer" || role == "system" {
+                    let text = content
+                        .iter()
+                        .filter_map(|content| match content {
+                            ContentItem::InputText { text } | ContentItem::OutputText { text } => {
+                                Some(text.as_str())
+                            }
+                            ContentItem::InputImage { .. } => None,
+                        })
+                        .collect::<Vec<_>>()
+                        .join("\n");
+                    if !text.trim().is_empty() {
+                        if !system.is_empty() {
+                            system.push_str("\n\n");
+                        }
+                        system.push_str(&text);
+                    }
+                    continue;
+                }
+                let role = if role == "assistant" { "model" } else { "user" };
+                for content in content {
+                    match content {
+                        ContentItem::InputText { text } | ContentItem::OutputText { text } => {
+                            if !text.is_empty() {
+                                push_part(&mut contents, role, json!({ "text": text }));
+                            }
+                        }
+                        ContentItem::InputImage { image_url } => {
+                            push_part(&mut contents, role, image_part(image_url));
+                        }
+                    }
+                }
+            }
+            ResponseItem::FunctionCall {
+                name,
+                arguments,
+                call_id,
+                ..
+            } => {
+                let args = serde_json::from_str::<Value>(arguments)
+                    .ok()
+                    .filter(Value::is_object)
+                    .unwrap_or_else(|| json!({}));
+                call_names.insert(call_id.clone(), name.clone());
+                push_part(
+                    &mut contents,
+                    "model",
+                    json!({ "functionCall": { "name": name, "args": args } }),
+                );
+            }
+            ResponseItem::CustomToolCall {
+                call_id,
+                name,
+                input,
+                ..
+            } => {
+                call_names.insert(call_id.clone(), name.clone());
+                push_part(
+                    &mut contents,
+                    "model",
+                    json!({ "functionCall": { "name": name, "args": { "input": input } } }),
+                );
+            }
+            ResponseItem::FunctionCallOutput { call_id, output } => {
+                if let Some(name) = call_names.get(call_id) {
+                    push_part(
+                        &mut contents,
+                        "user",
+                        json!({ "functionResponse": {
+                            "name": name,
+                            "response": { "content": output.content },
+                        } }),
+                    );
+                }
+            }
+            ResponseItem::CustomToolCallOutput { call_id, output } => {
+                if let Some(name) = call_names.get(call_id) {
+                    push_part(
+                        &mut contents,
+                        "user",
+                        json!({ "functionResponse": {
+                            "name": name,
+                            "response": { "content": output },
+                        } }),
+                    );
+                }
+            }
+            // Local shell calls are an OpenAI built-in tool; the rest only
+            // mean something to the Responses API.
+            ResponseItem::LocalShellCall { .. }
+            | ResponseItem::Reasoning { .. }
+            | ResponseItem::WebSearchCall { .. }
+            | ResponseItem::CompactionSummary { .. }
+            | ResponseItem::Other => {}
+        }
+    }
+    contents
+}
+
+/// Appends `part` to the last turn when it has the same role.
+fn push_part(contents: &mut Vec<Value>, role: &str, part: Value) {
+    if let Some(last) = contents.last_mut()
+        && last.get("role").and_then(Value::as_str) == Some(role)
+        && let Some(parts) = last.get_mut("parts").and_then(Value::as_array_mut)
+    {
+        parts.push(part);
+        return;
+    }
+    contents.push(json!({ "role": role, "parts": [part] }));
+}
+
+/// Data URLs are sent inline; anything else by URI.
+fn image_part(image_url: &str) -> Value {
+    image_url
+        .strip_prefix("data:")
+        .and_then(|rest| rest.split_once(";base64,"))
+        .map(|(mime_type, data)| json!({ "inlineData": { "mimeType": mime_type, "data": data } }))
+        .unwrap_or_else(|| json!({ "fileData": { "fileUri": image_url } }))
+}
+
+/// Rewrites Chat Completions function tools as Gemini function declarations.
+fn translate_tools(tools: Vec<Value>) -> Vec<Value> {
+    tools
+        .into_iter()
+        .filter_map(|tool| {
+            let function = tool.get("function")?;
+            let mut declaration = json!({
+                "name": function.get("name")?,
+                "description": function
+                    .get("description")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default(),
+            });
+            if let Some(mut parameters) = function.get("parameters").cloned() {
+                strip_unsupported_schema_keys(&mut parameters);
+                declaration["parameters"] = parameters;
+            }
+            Some(declaration)
+        })
+        .collect()
+}
+
+fn strip_unsupported_schema_keys(schema: &mut Value) {
+    match schema {
+        Value::Object(map) => {
+            for key in UNSUPPORTED_SCHEMA_KEYS {
+                map.remove(key);
+            }
+            map.values_mut().for_each(strip_unsupported_schema_keys);
+        }
+        Value::Array(items) => items.iter_mut().for_each(strip_unsupported_schema_keys),
+        _ => {}
+    }
+}
+
+async fn process_gemini_sse<S>(
+    stream: S,
+    tx_event: mpsc::Sender<Result<ResponseEvent>>,
+    idle_timeout: Duration,
+    debug_logger: Arc<Mutex<DebugLogger>>,
+    request_id: String,
+    otel_event_manager: Option<OtelEventManager>,
+) where
+    S: Stream<Item = Result<Bytes>> + Unpin,
+{
+    let mut stream = stream.eventsource();
+    let mut state = GenerateContentState::default();
+
+    loop {
+        let next_event = if let Some(manager) = otel_event_manager.as_ref() {
+            manager
+                .log_sse_event(|| timeout(idle_timeout, stream.next()))
+                .await
+        } else {
+            timeout(idle_timeout, stream.next()).await
+        };
+
+        let sse = match next_event {
+            Ok(Some(Ok(ev))) => ev,
+            Ok(Some(Err(e))) => {
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(
+                        format!("[transport] {e}"),
+                        None,
+                        Some(request_id.clone()),
+                    )))
+                    .await;
+                return;
+            }
+            Ok(None) => {
+                if state.finish_reason.is_none() {
+                    let detail = "stream closed before a finishReason";
+                    ResponseAnomaly::Truncated.record(detail, otel_event_manager.as_ref());
+                    let _ = tx_event
+                        .send(Err(
+                            ResponseAnomaly::Truncated.into_error(detail, &request_id)
+                        ))
+                        .await;
+                } else {
+                    for event in state.finish() {
+                        if tx_event.send(Ok(event)).await.is_err() {
+                            break;
+                        }
+                    }
+                }
+                if let Ok(logger) = debug_logger.lock() {
+                    let _ = logger.end_request_log(&request_id);
+                }
+                return;
+            }
+            Err(_) => {
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(
+                        "[idle] timeout waiting for SSE".into(),
+                        None,
+                        Some(request_id.clone()),
+                    )))
+                    .await;
+                return;
+            }
+        };
+
+        let chunk: Value = match serde_json::from_str(&sse.data) {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                debug!("gemini SSE parse error: {e}");
+                continue;
+            }
+        };
+        trace!("gemini received SSE chunk: {chunk:?}");
+        if let Ok(logger) = debug_logger.lock() {
+            let _ = logger.append_response_event(&request_id, "sse_event", &chunk);
+        }
+
+        match state.on_chunk(&chunk) {
+            Ok(events) => {
+                for event in events {
+                    if tx_event.send(Ok(event)).await.is_err() {
+                        return;
+                    }
+                }
+            }
+            Err(err) => {
+                let err = match err {
+                    CodexErr::Stream(message, retry_after, None) => {
+                        CodexErr::Stream(message, retry_after, Some(request_id.clone()))
+                    }
+                    other => other,
+                };
+                let _ = tx_event.send(Err(err)).await;
+                if let Ok(logger) = debug_logger.lock() {
+                    let _ = logger.end_request_log(&request_id);
+                }
+                return;
+            }
+        }
+    }
+}
+
+/// Accumulates one streamed `generateContent` response.
+#[derive(Default)]
+struct GenerateContentState {
+    response_id: String,
+    text: String,
+    reasoning: String,
+    usage: Option<TokenUsage>,
+    finish_reason: Option<String>,
+}
+
+impl GenerateContentState {
+    fn on_chunk(&mut self, chunk: &Value) -> Result<Vec<ResponseEvent>> {
+        if let Some(reason) = chunk
+            .pointer("/promptFeedback/blockReason")
+            .and_then(Value::as_str)
+        {
+            return Err(CodexErr::ContentBlocked(format!(
+                "Gemini blocked the prompt ({reason})"
+            )));
+        }
+        if let Some(id) = chunk.get("responseId").and_then(Value::as_str) {
+            self.response_id = id.to_string();
+        }
+        if let Some(usage) = chunk.get("usageMetadata") {
+            self.usage = Some(token_usage(usage));
+        }
+
+        let mut events = Vec::new();
+        let Some(candidate) = chunk.pointer("/candidates/0") else {
+            return Ok(events);
+        };
+        let parts = candidate
+            .pointer("/content/parts")
+            .and_then(Value::as_array)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        for part in parts {
+            if let Some(text) = part.get("text").and_then(Value::as_str) {
+                if text.is_empty() {
+                    continue;
+                }
+                if part.get("thought").and_then(Value::as_bool) == Some(true) {
+                    self.reasoning.push_str(text);
+                    events.push(ResponseEvent::ReasoningContentDelta {
+                        delta: text.to_string(),
+                        item_id: None,
+                        sequence_number: None,
+                        output_index: None,
+                        content_index: None,
+                    });
+                } else {
+                    self.text.push_str(text);
+                    events.push(ResponseEvent::OutputTextDelta {
+                        delta: text.to_string(),
+                        item_id: None,
+                        sequence_number: None,
+                        output_index: None,
+                    });
+                }
+            } else if let Some(call) = part.get("functionCall") {
+                // Text streamed before the call is a finished message.
+                events.extend(self.take_items());
+                let call_id = call
+                    .get("id")
+                    .and_then(Value::as_str)
+                    .map(str::to_string)
+                    .unwrap_or_else(|| format!("call_{}", Uuid::new_v4().simple()));
+                let args = call.get("args").cloned().unwrap_or_else(|| json!({}));
+                events.push(ResponseEvent::OutputItemDone {
+                    item: ResponseItem::FunctionCall {
+                        id: None,
+                        name: call
+                            .get("name")
+                            .and_then(Value::as_str)
+                            .unwrap_or_default()
+                            .to_string(),
+                        arguments: args.to_string(),
+                        call_id,
+                    },
+                    sequence_number: None,
+                    output_index: None,
+                });
+            }
+        }
+
+        if let Some(reason) = candidate.get("finishReason").and_then(Value::as_str) {
+            if BLOCKED_FINISH_REASONS.contains(&reason) {
+                return Err(CodexErr::ContentBlocked(format!(
+                    "Gemini stopped the response ({reason})"
+                )));
+            }
+            if reason == "MALFORMED_FUNCTION_CALL" {
+                return Err(CodexErr::Stream(
+                    "[gemini] malformed function call".to_string(),
+                    None,
+                    None,
+                ));
+            }
+            self.finish_reason = Some(reason.to_string());
+        }
+        Ok(events)
+    }
+
+    /// The buffered reasoning and message, then `Completed`.
+    fn finish(&mut self) -> Vec<ResponseEvent> {
+        let mut events = self.take_items();
+        events.push(ResponseEvent::Completed {
+            response_id: std::mem::take(&mut self.response_id),
+            token_usage: self.usage.take(),
+        });
+        events
+    }
+
+    fn take_items(&mut self) -> Vec<ResponseEvent> {
+        let mut items = Vec::new();
+        if !self.reasoning.is_empty() {
+            items.push(ResponseItem::Reasoning {
+                id: String::new(),
+                summary: Vec::new(),
+                content: Some(vec![ReasoningItemContent::ReasoningText {
+                    text: std::mem::take(&mut self.reasoning),
+                }]),
+                encrypted_content: None,
+            });
+        }
+        if !self.text.is_empty() {
+            items.push(ResponseItem::Message {
+                id: None,
+                role: "assistant".to_string(),
+                content: vec![ContentItem::OutputText {
+                    text: std::mem::take(&mut self.text),
+                }],
+            });
+        }
+        items
+            .into_iter()
+            .map(|item| ResponseEvent::OutputItemDone {
+                item,
+                sequence_number: None,
+                output_index: None,
+            })
+            .collect()
+    }
+}
+
+/// Thinking tokens are billed as output, so they count towards
+/// `output_tokens` as well as `reasoning_output_tokens`.
+fn token_usage(usage: &Value) -> TokenUsage {
+    let field = |name: &str| usage.get(name).and_then(Value::as_u64).unwrap_or_default();
+    let input_tokens = field("promptTokenCount");
+    let reasoning_output_tokens = field("thoughtsTokenCount");
+    let output_tokens = field("candidatesTokenCount") + reasoning_output_tokens;
+    TokenUsage {
+        input_tokens,
+        cached_input_tokens: field("cachedContentTokenCount"),
+        output_tokens,
+        reasoning_output_tokens,
+        total_tokens: usage
+            .get("totalTokenCount")
+            .and_then(Value::as_u64)
+            .unwrap_or(input_tokens + output_tokens),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use code_protocol::models::FunctionCallOutputPayload;
+    use pretty_assertions::assert_eq;
+
+    fn message(role: &str, text: &str) -> ResponseItem {
+        ResponseItem::Message {
+            id: None,
+            role: role.to_string(),
+            content: vec![ContentItem::InputText {
+                text: text.to_string(),
+            }],
+        }
+    }
+
+    #[test]
+    fn translates_history_into_contents() {
+        let input = vec![
+            message("developer", "Stay in the repo."),
+            message("user", "List the files"),
+            ResponseItem::FunctionCall {
+                id: None,
+                name: "shell".to_string(),
+                arguments: r#"{"command":["ls"]}"#.to_string(),
+                call_id: "call_1".to_string(),
+            },
+            ResponseItem::FunctionCallOutput {
+                call_id: "call_1".to_string(),
+                output: FunctionCallOutputPayload {
+                    content: "Cargo.toml".to_string(),
+                    success: Some(true),
+                },
+            },
+            message("assistant", "One file."),
+        ];
+        let mut system = "Base instructions".to_string();
+        let contents = translate_input(&input, &mut system);
+
+        assert_eq!(system, "Base instructions\n\nStay in the repo.");
+        assert_eq!(
+            Value::Array(contents),
+            json!([
+                {"role": "user", "parts": [{"text": "List the files"}]},
+                {"role": "model", "parts": [
+                    {"functionCall": {"name": "shell", "args": {"command": ["ls"]}}},
+                ]},
+                {"role": "user", "parts": [
+                    {"functionResponse": {"name": "shell", "response": {"content": "Cargo.toml"}}},
+                ]},
+                {"role": "model", "parts": [{"text": "One file."}]},
+            ])
+        );
+    }
+
+    #[test]
+    fn sends_sampling_in_generation_config() {
+        let prompt = Prompt {
+            sampling: crate::config_types::SamplingParams {
+                seed: Some(42),
+                temperature: Some(0.0),
+                top_p: None,
+            },
+            ..Prompt::default()
+        };
+        let family = crate::model_family::derive_default_model_family("gemini-2.5-pro");
+        let payload = build_generate_content_payload(&prompt, &family).unwrap();
+        assert_eq!(payload["generationConfig"]["seed"], json!(42));
+        assert_eq!(payload["generationConfig"]["temperature"], json!(0.0));
+        assert!(payload["generationConfig"].get("topP").is_none());
+    }
+
+    #[test]
+    fn strips_unsupported_schema_keywords_from_tools() {
+        let declarations = translate_tools(vec![json!({
+            "type": "function",
+            "function": {
+                "name": "shell",
+                "description": "Runs a command",
+                "strict": false,
+                "parameters": {
+                    "type": "object",
+                    "properties": {"env": {"type": "object", "additionalProperties": true}},
+                    "additionalProperties": false,
+                },
+            },
+        })]);
+        assert_eq!(
+            declarations,
+            vec![json!({
+                "name": "shell",
+                "description": "Runs a command",
+                "parameters": {"type": "object", "properties": {"env": {"type": "object"}}},
+            })]
+        );
+    }
+
+    #[test]
+    fn maps_streamed_chunks_to_response_events() {
+        let mut state = GenerateContentState::default();
+        let mut events = state
+            .on_chunk(&json!({
+                "responseId": "resp_1",
+                "candidates": [{"content": {"role": "model", "parts": [{"text": "Listing "}]}}],
+            }))
+            .unwrap();
+        events.extend(
+            state
+                .on_chunk(&json!({
+                    "candidates": [{
+                        "content": {"role": "model", "parts": [
+                            {"text": "files."},
+                            {"functionCall": {"name": "shell", "args": {"command": ["ls"]}}},
+                        ]},
+                        "finishReason": "STOP",
+                    }],
+                    "usageMetadata": {
+                        "promptTokenCount": 1000,
+                        "cachedContentTokenCount": 800,
+                        "candidatesTokenCount": 30,
+                        "thoughtsTokenCount": 12,
+                        "totalTokenCount": 1042,
+                    },
+                }))
+                .unwrap(),
+        );
+        events.extend(state.finish());
+
+        let mut deltas = String::new();
+        let mut items = Vec::new();
+        let mut usage = None;
+        for event in events {
+            match event {
+                ResponseEvent::OutputTextDelta { delta, .. } => deltas.push_str(&delta),
+                ResponseEvent::OutputItemDone { item, .. } => items.push(item),
+                ResponseEvent::Completed {
+                    response_id,
+                    token_usage,
+                } => {
+                    assert_eq!(response_id, "resp_1");
+                    usage = token_usage;
+                }
+                _ => {}
+            }
+        }
+        assert_eq!(deltas, "Listing files.");
+        assert_eq!(items.len(), 2);
+        assert_eq!(
+            items[0],
+            ResponseItem::Message {
+                id: None,
+                role: "assistant".to_string(),
+                content: vec![ContentItem::OutputText {
+                    text: "Listing files.".to_string(),
+                }],
+            }
+        );
+        let ResponseItem::FunctionCall {
+            name,
+            arguments,
+            call_id,
+            ..
+        } = &items[1]
+        else {
+            panic!("expected a function call");
+        };
+        assert_eq!(name, "shell");
+        assert_eq!(arguments, r#"{"command":["ls"]}"#);
+        assert!(call_id.starts_with("call_"));
+        assert_eq!(
+            usage,
+            Some(TokenUsage {
+                input_tokens: 1000,
+                cached_input_tokens: 800,
+                output_tokens: 42,
+                reasoning_output_tokens: 12,
+                total_tokens: 1042,
+            })
+        );
+    }
+
+    #[test]
+    fn safety_blocks_become_content_blocked_errors() {
+        let mut state = GenerateContentState::default();
+        let blocked_prompt = json!({"promptFeedback": {"blockReason": "SAFETY"}});
+        assert!(matches!(
+            state.on_chunk(&blocked_prompt),
+            Err(CodexErr::ContentBlocked(reason)) if reason.contains("SAFETY")
+        ));
+        let blocked_response = json!({"candidates": [{"finishReason": "RECITATION"}]});
+        assert!(matches!(
+            state.on_chunk(&blocked_response),
+            Err(CodexErr::ContentBlocked(reason)) if reason.contains("RECITATION")
+        ));
+    }
+
+    #[test]
+    fn resource_exhausted_errors_carry_the_retry_delay() {
+        let body = r#"[{"error": {"code": 429, "message": "Quota exceeded for aiplatform.googleapis.com/generate_content_requests_per_minute_per_project_per_base_model.", "status": "RESOURCE_EXHAUSTED", "details": [{"@type": "type.googleapis.com/google.rpc.RetryInfo", "retryDelay": "38s"}]}}]"#;
+        let quota = QuotaExhausted::from_body(body).unwrap();
+        assert_eq!(quota.retry_delay, Some(Duration::from_secs(38)));
+        match quota.into_error("vertex", Duration::from_secs(38), None) {
+            CodexErr::Stream(message, Some(_), None) => {
+                assert!(message.starts_with("[vertex] quota exceeded: Quota exceeded for"));
+            }
+            other => panic!("unexpected error: {other:?}"),
+        }
+
+        let unavailable = r#"{"error": {"code": 429, "message": "busy", "status": "UNAVAILABLE"}}"#;
+        assert_eq!(QuotaExhausted::from_body(unavailable), None);
+        assert_eq!(QuotaExhausted::from_body("rate limited"), None);
+    }
+}
@@ -1,6 +1,10 @@
 use std::collections::HashSet;
 use std::path::Path;
 use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::OnceLock;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
 
 use code_app_server_protocol::GitSha;
 use code_protocol::protocol::GitInfo;
@@ -11,6 +15,41 @@ use tokio::process::Command;
 use tokio::time::Duration as TokioDuration;
 use tokio::time::timeout;
 
+mod dotgit;
+
+static GIT_AVAILABLE: OnceLock<bool> = OnceLock::new();
+static GIT_MISSING_NOTICE_TAKEN: AtomicBool = AtomicBool::new(false);
+
+const GIT_MISSING_NOTICE: &str = "git was not found on PATH. Branch and commit details are read \
+from .git without it; diffs, history, worktrees, and branch management are unavailable until git \
+is installed.";
+
+/// Whether a working `git` binary is on `PATH`. Probed once per process.
+pub fn git_available() -> bool {
+    *GIT_AVAILABLE.get_or_init(|| {
+        let available = std::process::Command::new("git")
+            .arg("--version")
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success());
+        if !available {
+            tracing::warn!("git binary not found; using .git fallbacks");
+        }
+        available
+    })
+}
+
+/// Returns the user-facing notice about a missing `git` the first time it is
+/// called in a process where git is unavailable, and `None` otherwise.
+pub fn take_git_missing_notice() -> Option<&'static str> {
+    if git_available() || GIT_MISSING_NOTICE_TAKEN.swap(true, Ordering::Relaxed) {
+        return None;
+    }
+    Some(GIT_MISSING_NOTICE)
+}
+
 /// Return `true` if the project folder specified by the `Config` is inside a
 /// Git repository.
 ///
@@ -55,6 +94,10 @@ pub struct GitDiffToRemote {
 /// Uses timeouts to prevent freezing on large repositories.
 /// All git commands (except the initial repo check) run in parallel for better performance.
 pub async fn collect_git_info(cwd: &Path) -> Option<GitInfo> {
+    if !git_available() {
+        return collect_git_info_from_dotgit(cwd);
+    }
+
     // Check if we're in a git repository first
     let is_git_repo = run_git_command_with_timeout(&["rev-parse", "--git-dir"], cwd)
         .await?
@@ -108,6 +151,19 @@ pub async fn collect_git_info(cwd: &Path) -> Option<GitInfo> {
     Some(git_info)
 }
 
+/// [`collect_git_info`] without the `git` binary.
+fn collect_git_info_from_dotgit(cwd: &Path) -> Option<GitInfo> {
+    let checkout = dotgit::discover(cwd)?;
+    Some(GitInfo {
+        commit_hash: checkout.head_commit(),
+        branch: match checkout.head() {
+            Some(dotgit::Head::Branch(branch)) => Some(branch),
+            _ => None,
+        },
+        repository_url: checkout.remote_url("origin"),
+    })
+}
+
 /// A minimal commit summary entry used for pickers (subject + timestamp + sha).
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct CommitLogEntry {
@@ -507,6 +563,12 @@ async fn diff_against_sha(cwd: &Path, sha: &GitSha) -> Option<String> {
 pub fn resolve_root_git_project_for_trust(cwd: &Path) -> Option<PathBuf> {
     let base = if cwd.is_dir() { cwd } else { cwd.parent()? };
 
+    if !git_available() {
+        let common_dir = dotgit::discover(base)?.common_dir().to_path_buf();
+        let common_dir = std::fs::canonicalize(&common_dir).unwrap_or(common_dir);
+        return common_dir.parent().map(Path::to_path_buf);
+    }
+
     // TODO: we should make this async, but it's primarily used deep in
     // callstacks of sync code, and should almost always be fast
     let git_dir_out = std::process::Command::new("git")
@@ -536,6 +598,12 @@ pub fn resolve_root_git_project_for_trust(cwd: &Path) -> Option<PathBuf> {
 /// Returns a list of local git branches.
 /// Includes the default branch at the beginning of the list, if it exists.
 pub async fn local_git_branches(cwd: &Path) -> Vec<String> {
+    if !git_available() {
+        return dotgit::discover(cwd)
+            .map(|checkout| checkout.local_branches())
+            .unwrap_or_default();
+    }
+
     let mut branches: Vec<String> = if let Some(out) =
         run_git_command_with_timeout(&["branch", "--format=%(refname:short)"], cwd).await
         && out.status.success()
@@ -563,6 +631,13 @@ pub async fn local_git_branches(cwd: &Path) -> Vec<String> {
 
 /// Returns the current checked out branch name.
 pub async fn current_branch_name(cwd: &Path) -> Option<String> {
+    if !git_available() {
+        return match dotgit::discover(cwd)?.head()? {
+            dotgit::Head::Branch(branch) => Some(branch),
+            dotgit::Head::Detached(_) => None,
+        };
+    }
+
     let out = run_git_command_with_timeout(&["branch", "--show-current"], cwd).await?;
     if !out.status.success() {
         return None;
@@ -581,8 +656,22 @@ mod tests {
     use std::path::PathBuf;
     use tempfile::TempDir;
 
+    /// Runs `git` in `dir` without user or system config and returns its
+    /// trimmed stdout, panicking when it fails.
+    pub(super) fn git(dir: &Path, args: &[&str]) -> String {
+        let output = std::process::Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .env("GIT_CONFIG_GLOBAL", "/dev/null")
+            .env("GIT_CONFIG_NOSYSTEM", "1")
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "git {args:?} failed: {output:?}");
+        String::from_utf8(output.stdout).unwrap().trim().to_string()
+    }
+
     // Helper function to create a test git repository
-    async fn create_test_git_repo(temp_dir: &TempDir) -> PathBuf {
+    pub(super) async fn create_test_git_repo(temp_dir: &TempDir) -> PathBuf {
         let repo_path = temp_dir.path().join("repo");
         fs::create_dir(&repo_path).expect("Failed to create repo dir");
         let envs = vec![
@@ -808,6 +897,22 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn dotgit_fallback_matches_git_cli() {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let (repo_path, branch) = create_test_git_repo_with_remote(&temp_dir).await;
+
+        let from_git = collect_git_info(&repo_path)
+            .await
+            .expect("Should collect git info from repo");
+        let from_dotgit =
+            collect_git_info_from_dotgit(&repo_path).expect("Should read .git directly");
+
+        assert_eq!(from_dotgit.commit_hash, from_git.commit_hash);
+        assert_eq!(from_dotgit.branch, Some(branch));
+        assert_eq!(from_dotgit.repository_url, from_git.repository_url);
+    }
+
     #[tokio::test]
     async fn test_collect_git_info_detached_head() {
         let temp_dir = TempDir::new().expect("Failed to create temp dir");
@@ -0,0 +1,128 @@
+//! Reads repository metadata with gix.
+//!
+//! Used when the `git` binary is not installed (minimal containers), so the
+//! repo root, current branch, HEAD commit, local branches, and origin URL
+//! are still known. gix handles loose and packed refs, linked worktrees
+//! (`.git` files and `commondir`), and the full config format; a repository
+//! it cannot open, such as one using the reftable backend, reads as no
+//! repository.
+
+use std::path::Path;
+
+/// An open checkout, main or linked worktree.
+pub(super) struct Checkout(gix::Repository);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum Head {
+    Branch(String),
+    Detached(String),
+}
+
+/// Walks up from `start` to the nearest checkout.
+pub(super) fn discover(start: &Path) -> Option<Checkout> {
+    match gix::discover(start) {
+        Ok(repo) => Some(Checkout(repo)),
+        Err(err) => {
+            tracing::debug!("no readable repository at {}: {err}", start.display());
+            None
+        }
+    }
+}
+
+impl Checkout {
+    /// Shared directory holding refs and config; the checkout's own `.git`
+    /// outside linked worktrees.
+    pub fn common_dir(&self) -> &Path {
+        self.0.common_dir()
+    }
+
+    pub fn head(&self) -> Option<Head> {
+        let head = self.0.head().ok()?;
+        match head.referent_name() {
+            Some(name) => Some(Head::Branch(name.shorten().to_string())),
+            None => head.id().map(|id| Head::Detached(id.to_string())),
+        }
+    }
+
+    /// Commit HEAD points at; `None` on an unborn branch.
+    pub fn head_commit(&self) -> Option<String> {
+        self.0.head_id().ok().map(|id| id.to_string())
+    }
+
+    /// Local branch names, loose and packed, sorted.
+    pub fn local_branches(&self) -> Vec<String> {
+        let Ok(references) = self.0.references() else {
+            return Vec::new();
+        };
+        let Ok(branches) = references.local_branches() else {
+            return Vec::new();
+        };
+        let mut names: Vec<String> = branches
+            .filter_map(Result::ok)
+            .map(|reference| reference.name().shorten().to_string())
+            .collect();
+        names.sort_unstable();
+        names
+    }
+
+    pub fn remote_url(&self, remote: &str) -> Option<String> {
+        let key = format!("remote.{remote}.url");
+        self.0
+            .config_snapshot()
+            .string(key.as_str())
+            .map(|url| url.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git_info::tests::create_test_git_repo;
+    use crate::git_info::tests::git;
+    use pretty_assertions::assert_eq;
+
+    #[tokio::test]
+    async fn reads_packed_refs_and_linked_worktrees() {
+        let temp = tempfile::tempdir().unwrap();
+        let main = create_test_git_repo(&temp).await;
+        git(&main, &["branch", "-M", "main"]);
+        git(&main, &["branch", "feature/x"]);
+        git(
+            &main,
+            &["remote", "add", "origin", "https://example.com/repo.git"],
+        );
+        git(&main, &["pack-refs", "--all"]);
+        let head = git(&main, &["rev-parse", "HEAD"]);
+        let work_tree = temp.path().join("wt");
+        git(
+            &main,
+            &[
+                "worktree",
+                "add",
+                "-q",
+                "--detach",
+                &work_tree.to_string_lossy(),
+            ],
+        );
+        std::fs::create_dir_all(work_tree.join("src")).unwrap();
+
+        let checkout = discover(&work_tree.join("src")).unwrap();
+        assert_eq!(
+            std::fs::canonicalize(checkout.common_dir()).unwrap(),
+            std::fs::canonicalize(main.join(".git")).unwrap()
+        );
+        assert_eq!(checkout.head(), Some(Head::Detached(head.clone())));
+        assert_eq!(checkout.head_commit(), Some(head));
+        assert_eq!(
+            checkout.local_branches(),
+            vec!["feature/x".to_string(), "main".to_string()]
+        );
+        assert_eq!(
+            checkout.remote_url("origin"),
+            Some("https://example.com/repo.git".to_string())
+        );
+
+        let main_checkout = discover(&main).unwrap();
+        assert_eq!(main_checkout.head(), Some(Head::Branch("main".to_string())));
+    }
+}
@@ -0,0 +1,134 @@
+//! GitHub integration shared by the TUI and `code exec`: token discovery,
+//! remote URL parsing and a minimal REST client.
+
+use std::process::Command;
+
+use anyhow::Context;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use crate::default_client::DEFAULT_ORIGINATOR;
+use crate::default_client::create_client;
+
+pub const API_BASE: &str = "https://api.github.com";
+
+/// Source of a GitHub API token.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TokenSource {
+    /// `GITHUB_TOKEN` or `GH_TOKEN` environment variable.
+    Env,
+    /// Fetched via `gh auth token` from the GitHub CLI.
+    GhCli,
+}
+
+/// Obtain a GitHub token, preferring environment variables and falling back to `gh`.
+/// Returns the token string and its source if available.
+pub fn get_github_token() -> Option<(String, TokenSource)> {
+    if let Ok(t) = std::env::var("GITHUB_TOKEN") {
+        if !t.is_empty() {
+            return Some((t, TokenSource::Env));
+        }
+    }
+    if let Ok(t) = std::env::var("GH_TOKEN") {
+        if !t.is_empty() {
+            return Some((t, TokenSource::Env));
+        }
+    }
+    // Fallback: use GitHub CLI if installed and logged in.
+    if let Ok(out) = Command::new("gh").args(["auth", "token"]).output() {
+        if out.status.success() {
+            let token = String::from_utf8_lossy(&out.stdout).trim().to_string();
+            if !token.is_empty() {
+                return Some((token, TokenSource::GhCli));
+            }
+        }
+    }
+    None
+}
+
+/// `owner` and `repo` from a GitHub remote URL.
+pub fn parse_owner_repo(url: &str) -> Option<(String, String)> {
+    // git@github.com:owner/repo.git or https://github.com/owner/repo(.git)
+    let rest = match url.strip_prefix("git@github.com:") {
+        Some(rest) => rest,
+        None => &url[url.find("github.com/")? + "github.com/".len()..],
+    };
+    let (owner, repo) = rest.trim_end_matches(".git").split_once('/')?;
+    Some((owner.to_string(), repo.to_string()))
+}
+
+/// Authenticated client for the GitHub REST API.
+pub struct GitHubClient {
+    client: reqwest::Client,
+    token: String,
+}
+
+impl GitHubClient {
+    /// Build a client with the token from [`get_github_token`].
+    pub fn from_env() -> anyhow::Result<Self> {
+        let (token, _) = get_github_token().context(
+            "no GitHub token found; set GITHUB_TOKEN or GH_TOKEN, or log in with `gh auth login`",
+        )?;
+        Ok(Self {
+            client: create_client(DEFAULT_ORIGINATOR),
+            token,
+        })
+    }
+
+    /// `GET {API_BASE}{path}` and decode the JSON response.
+    pub async fn get<T: DeserializeOwned>(&self, path: &str) -> anyhow::Result<T> {
+        let url = format!("{API_BASE}{path}");
+        let request = self.client.get(&url);
+        self.send(request, "GET", &url).await
+    }
+
+    /// `POST {API_BASE}{path}` with a JSON body and decode the JSON response.
+    pub async fn post<B: Serialize, T: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &B,
+    ) -> anyhow::Result<T> {
+        let url = format!("{API_BASE}{path}");
+        let request = self.client.post(&url).json(body);
+        self.send(request, "POST", &url).await
+    }
+
+    async fn send<T: DeserializeOwned>(
+        &self,
+        request: reqwest::RequestBuilder,
+        method: &str,
+        url: &str,
+    ) -> anyhow::Result<T> {
+        let response = request
+            .bearer_auth(&self.token)
+            .header("Accept", "application/vnd.github+json")
+            .send()
+            .await
+            .with_context(|| format!("{method} {url} failed"))?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            anyhow::bail!("{method} {url} returned {status}: {}", body.trim());
+        }
+        Ok(response.json().await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parses_ssh_and_https_remotes() {
+        assert_eq!(
+            parse_owner_repo("git@github.com:acme/widgets.git"),
+            Some(("acme".to_string(), "widgets".to_string()))
+        );
+        assert_eq!(
+            parse_owner_repo("https://github.com/acme/widgets"),
+            Some(("acme".to_string(), "widgets".to_string()))
+        );
+        assert_eq!(parse_owner_repo("https://gitlab.com/acme/widgets"), None);
+    }
+}
@@ -6,10 +6,17 @@
 #![deny(clippy::print_stdout, clippy::print_stderr)]
 
 pub mod account_usage;
+mod anthropic_messages;
 mod apply_patch;
+pub mod at_rest;
 pub mod auth;
 pub mod auth_accounts;
+mod aws_sigv4;
+mod azure_ad;
+mod gcp_adc;
 pub mod bash;
+pub mod batch;
+mod bedrock_converse;
 mod bridge_client;
 mod chat_completions;
 mod client;
@@ -27,10 +34,20 @@ pub mod config_profile;
 pub mod config_types;
 pub mod context_timeline;
 mod conversation_history;
+pub mod conversation_import;
 pub mod custom_prompts;
 pub mod debug_logger;
+pub mod embeddings;
 mod environment_context;
 mod reasoning;
+mod request_latency;
+mod request_limits;
+pub mod request_middleware;
+mod request_retry;
+pub mod request_tap;
+mod response_anomaly;
+mod response_cache;
+mod sse_buffer;
 pub mod retention;
 pub mod telemetry;
 pub use environment_context::BrowserSnapshot;
@@ -48,8 +65,11 @@ pub mod error;
 pub mod exec;
 mod exec_command;
 pub mod exec_env;
+mod file_locks;
 mod flags;
+mod gemini;
 pub mod git_info;
+pub mod github;
 pub mod git_worktree;
 pub mod history;
 pub mod housekeeping;
@@ -59,16 +79,21 @@ pub mod landlock;
 pub mod mcp_connection_manager;
 mod mcp_tool_call;
 mod message_history;
+mod mock_provider;
 mod model_provider_info;
 pub mod parse_command;
 pub mod slash_commands;
 mod truncate;
 mod unified_exec;
 mod user_instructions;
+pub use model_provider_info::AzureAdConfig;
 pub use model_provider_info::BUILT_IN_OSS_MODEL_PROVIDER_ID;
+pub use model_provider_info::MockConfig;
+pub use model_provider_info::MockRoute;
 pub use model_provider_info::ModelProviderInfo;
 pub use model_provider_info::OpenRouterConfig;
 pub use model_provider_info::OpenRouterProviderConfig;
+pub use model_provider_info::VertexConfig;
 pub use model_provider_info::WireApi;
 pub use model_provider_info::built_in_model_providers;
 pub use model_provider_info::create_oss_provider_with_base_url;
@@ -93,16 +118,22 @@ mod openai_tools;
 pub mod otel_init;
 mod patch_harness;
 pub mod plan_tool;
+mod prefetch;
 pub mod project_doc;
 pub mod project_features;
+pub mod prompt_cache;
+mod provider_health;
+mod quota_ledger;
 mod rollout;
 pub(crate) mod safety;
 pub mod seatbelt;
 pub mod session_catalog;
+pub mod session_snapshot;
 pub mod shell;
 pub mod spawn;
 pub mod terminal;
 mod text_encoding;
+pub mod tokenizer;
 mod tool_apply_patch;
 pub mod turn_diff_tracker;
 mod workflow_validation;
@@ -118,6 +149,7 @@ pub use rollout::list::ConversationsPage;
 pub use rollout::list::Cursor;
 pub use session_catalog::SessionCatalog;
 pub use session_catalog::SessionQuery;
+pub use session_catalog::SessionSearchHit;
 pub use session_catalog::entry_to_rollout_path;
 mod function_tool;
 mod user_notification;
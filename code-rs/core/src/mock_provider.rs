@@ -0,0 +1,390 @@
+//! Canned model responses for `wire_api = "mock"`, so the TUI, `code exec`
+//! and Auto Drive can be developed and demoed without any network access.
+//!
+//! Each request is answered from a file in the provider's fixtures
+//! directory. The first `routes` entry matching the latest user message
+//! picks the file; otherwise it is named after the prompt hash (the first 12
+//! hex digits of the SHA-1 of that message): `<hash>.<step>.<ext>`, then
+//! `<hash>.<ext>` for the first request of a turn, then `default.<ext>`. The
+//! step counts the model responses since the user message, so a turn that
+//! runs tools can be scripted one request at a time.
+//!
+//! `.sse` fixtures are replayed as a Responses API event stream, in the same
+//! format as `CODEX_RS_SSE_FIXTURE`. `.json` fixtures hold the response items
+//! to return, and any other file is returned as one assistant message.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use code_otel::otel_event_manager::OtelEventManager;
+use code_protocol::models::ContentItem;
+use code_protocol::models::ResponseItem;
+use code_protocol::protocol::InputMessageKind;
+use regex_lite::Regex;
+use sha1::Digest;
+use sha1::Sha1;
+use tokio::sync::mpsc;
+use tracing::debug;
+
+use crate::client::stream_from_fixture;
+use crate::client_common::Prompt;
+use crate::client_common::ResponseEvent;
+use crate::client_common::ResponseStream;
+use crate::error::CodexErr;
+use crate::error::Result;
+use crate::model_provider_info::MockConfig;
+use crate::model_provider_info::ModelProviderInfo;
+use crate::protocol::TokenUsage;
+use crate::sse_buffer::SSE_CHANNEL_CAPACITY;
+use crate::tokenizer::Tokenizer;
+
+/// Fixture extensions tried for hash and default lookups, in order.
+const FIXTURE_EXTENSIONS: [&str; 4] = ["sse", "json", "md", "txt"];
+/// Hex digits of the SHA-1 kept in prompt hashes.
+const PROMPT_HASH_LEN: usize = 12;
+
+/// Serves `prompt` from the fixture it routes to.
+pub(crate) async fn stream_mock(
+    prompt: &Prompt,
+    model_slug: &str,
+    provider: &ModelProviderInfo,
+    code_home: &Path,
+    cwd: &Path,
+    otel_event_manager: Option<OtelEventManager>,
+) -> Result<ResponseStream> {
+    let config = provider.mock.clone().unwrap_or_default();
+    let fixtures_dir = match &config.fixtures_dir {
+        Some(dir) => cwd.join(dir),
+        None => code_home.join("fixtures"),
+    };
+    let input = prompt.get_formatted_input();
+    let request = MockRequest::from_input(&input);
+    let path = resolve_fixture(&config, &fixtures_dir, &request)?;
+    debug!(
+        prompt_hash = %request.prompt_hash,
+        step = request.step,
+        fixture = %path.display(),
+        "Serving mock fixture"
+    );
+
+    let output = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("sse") => {
+            return stream_from_fixture(&path, provider.clone(), otel_event_manager).await;
+        }
+        Some("json") => {
+            let contents = read_fixture(&path)?;
+            serde_json::from_str::<Vec<ResponseItem>>(&contents).map_err(|err| {
+                CodexErr::MockFixture(format!(
+                    "{} is not a JSON array of response items: {err}",
+                    path.display()
+                ))
+            })?
+        }
+        _ => vec![ResponseItem::Message {
+            id: None,
+            role: "assistant".to_string(),
+            content: vec![ContentItem::OutputText {
+                text: read_fixture(&path)?,
+            }],
+        }],
+    };
+
+    let tokenizer = Tokenizer::for_model(model_slug);
+    let input_tokens = tokenizer.count_items(&input) as u64;
+    let output_tokens = tokenizer.count_items(&output) as u64;
+    let token_usage = TokenUsage {
+        input_tokens,
+        cached_input_tokens: 0,
+        output_tokens,
+        reasoning_output_tokens: 0,
+        total_tokens: input_tokens + output_tokens,
+    };
+    let response_id = format!("mock_{}_{}", request.prompt_hash, request.step);
+
+    let (tx_event, rx_event) = mpsc::channel::<Result<ResponseEvent>>(SSE_CHANNEL_CAPACITY);
+    tokio::spawn(async move {
+        let mut events = vec![ResponseEvent::Created];
+        for (index, item) in output.into_iter().enumerate() {
+            let output_index = Some(index as u32);
+            if let ResponseItem::Message { content, .. } = &item {
+                for chunk in content {
+                    if let ContentItem::OutputText { text } = chunk {
+                        events.push(ResponseEvent::OutputTextDelta {
+                            delta: text.clone(),
+                            item_id: None,
+                            sequence_number: None,
+                            output_index,
+                        });
+                    }
+                }
+            }
+            events.push(ResponseEvent::OutputItemDone {
+                item,
+                sequence_number: None,
+                output_index,
+            });
+        }
+        events.push(ResponseEvent::Completed {
+            response_id,
+            token_usage: Some(token_usage),
+        });
+        for event in events {
+            if tx_event.send(Ok(event)).await.is_err() {
+                return;
+            }
+        }
+    });
+    Ok(ResponseStream {
+        rx_event,
+        served_by: None,
+        retries: 0,
+    })
+}
+
+/// What a request is routed by.
+#[derive(Debug, PartialEq)]
+struct MockRequest {
+    /// Text of the latest user message.
+    text: String,
+    prompt_hash: String,
+    /// 1 for the first request of a turn, plus one per model response since.
+    step: u32,
+}
+
+impl MockRequest {
+    fn from_input(input: &[ResponseItem]) -> Self {
+        let latest_user_message = input.iter().rposition(|item| {
+            user_text(item).is_some_and(|text| {
+                matches!(
+                    InputMessageKind::from(("user", text.as_str())),
+                    InputMessageKind::Plain
+                )
+            })
+        });
+        let (text, since) = match latest_user_message {
+            Some(index) => (
+                user_text(&input[index]).unwrap_or_default(),
+                &input[index + 1..],
+            ),
+            None => (String::new(), input),
+        };
+
+        // Each run of model output items is one earlier response.
+        let mut step = 1;
+        let mut in_response = false;
+        for item in since {
+            let model_output = matches!(
+                item,
+                ResponseItem::Message { role, .. } if role == "assistant"
+            ) || matches!(
+                item,
+                ResponseItem::Reasoning { .. }
+                    | ResponseItem::FunctionCall { .. }
+                    | ResponseItem::CustomToolCall { .. }
+                    | ResponseItem::LocalShellCall { .. }
+                    | ResponseItem::WebSearchCall { .. }
+            );
+            if model_output && !in_response {
+                step += 1;
+            }
+            in_response = model_output;
+        }
+
+        Self {
+            prompt_hash: prompt_hash(&text),
+            text,
+            step,
+        }
+    }
+}
+
+/// The hash fixtures are named after.
+fn prompt_hash(text: &str) -> String {
+    let mut sha = Sha1::new();
+    sha.update(text.trim());
+    let mut hash = format!("{:x}", sha.finalize());
+    hash.truncate(PROMPT_HASH_LEN);
+    hash
+}
+
+fn user_text(item: &ResponseItem) -> Option<String> {
+    let ResponseItem::Message { role, content, .. } = item else {
+        return None;
+    };
+    if role != "user" {
+        return None;
+    }
+    let text = content
+        .iter()
+        .filter_map(|chunk| match chunk {
+            ContentItem::InputText { text } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    Some(text)
+}
+
+fn resolve_fixture(
+    config: &MockConfig,
+    fixtures_dir: &Path,
+    request: &MockRequest,
+) -> Result<PathBuf> {
+    for route in &config.routes {
+        if route.step.is_some_and(|step| step != request.step) {
+            continue;
+        }
+        let hash_matches = route
+            .prompt_hash
+            .as_deref()
+            .is_some_and(|hash| hash.eq_ignore_ascii_case(&request.prompt_hash));
+        let pattern_matches = match route.pattern.as_deref() {
+            Some(pattern) => Regex::new(pattern)
+                .map_err(|err| {
+                    CodexErr::MockFixture(format!("invalid route pattern `{pattern}`: {err}"))
+                })?
+                .is_match(&request.text),
+            None => false,
+        };
+        if hash_matches || pattern_matches {
+            return Ok(fixtures_dir.join(&route.fixture));
+        }
+    }
+
+    let hash = &request.prompt_hash;
+    let mut stems = vec![format!("{hash}.{}", request.step)];
+    if request.step == 1 {
+        stems.push(hash.clone());
+    }
+    stems.push("default".to_string());
+    let suggested = if request.step == 1 { hash } else { &stems[0] };
+    stems
+        .iter()
+        .flat_map(|stem| {
+            FIXTURE_EXTENSIONS
+                .iter()
+                .map(move |ext| fixtures_dir.join(format!("{stem}.{ext}")))
+        })
+        .find(|path| path.is_file())
+        .ok_or_else(|| {
+            CodexErr::MockFixture(format!(
+                "no fixture for prompt hash {hash} (step {}); add {} or a matching route",
+                request.step,
+                fixtures_dir.join(format!("{suggested}.sse")).display()
+            ))
+        })
+}
+
+fn read_fixture(path: &Path) -> Result<String> {
+    std::fs::read_to_string(path)
+        .map_err(|err| CodexErr::MockFixture(format!("cannot read {}: {err}", path.display())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model_provider_info::MockRoute;
+    use code_protocol::models::FunctionCallOutputPayload;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn user(text: &str) -> ResponseItem {
+        ResponseItem::Message {
+            id: None,
+            role: "user".to_string(),
+            content: vec![ContentItem::InputText {
+                text: text.to_string(),
+            }],
+        }
+    }
+
+    fn tool_round(call_id: &str) -> [ResponseItem; 2] {
+        [
+            ResponseItem::FunctionCall {
+                id: None,
+                name: "shell".to_string(),
+                arguments: r#"{"command":["ls"]}"#.to_string(),
+                call_id: call_id.to_string(),
+            },
+            ResponseItem::FunctionCallOutput {
+                call_id: call_id.to_string(),
+                output: FunctionCallOutputPayload {
+                    content: "README.md".to_string(),
+                    success: Some(true),
+                },
+            },
+        ]
+    }
+
+    #[test]
+    fn routes_by_the_latest_user_message_and_step() {
+        let mut input = vec![
+            user("<environment_context>\n  <cwd>/repo</cwd>\n</environment_context>"),
+            user("List the files"),
+        ];
+        let first = MockRequest::from_input(&input);
+        assert_eq!(first.text, "List the files");
+        assert_eq!(first.prompt_hash, prompt_hash("  List the files\n"));
+        assert_eq!(first.step, 1);
+
+        input.extend(tool_round("call_1"));
+        input.extend(tool_round("call_2"));
+        let third = MockRequest::from_input(&input);
+        assert_eq!(
+            (third.prompt_hash.as_str(), third.step),
+            (first.prompt_hash.as_str(), 3)
+        );
+    }
+
+    #[test]
+    fn resolves_routes_then_hash_files_then_the_default() {
+        let dir = TempDir::new().unwrap();
+        let request = MockRequest::from_input(&[user("Fix the failing test")]);
+        let hash = request.prompt_hash.clone();
+        std::fs::write(dir.path().join("default.md"), "Done.").unwrap();
+        let config = MockConfig::default();
+        assert_eq!(
+            resolve_fixture(&config, dir.path(), &request).unwrap(),
+            dir.path().join("default.md")
+        );
+
+        std::fs::write(dir.path().join(format!("{hash}.json")), "[]").unwrap();
+        assert_eq!(
+            resolve_fixture(&config, dir.path(), &request).unwrap(),
+            dir.path().join(format!("{hash}.json"))
+        );
+
+        let config = MockConfig {
+            fixtures_dir: None,
+            routes: vec![
+                MockRoute {
+                    pattern: Some("(?i)failing test".to_string()),
+                    step: Some(2),
+                    fixture: PathBuf::from("never.sse"),
+                    ..MockRoute::default()
+                },
+                MockRoute {
+                    pattern: Some("(?i)failing test".to_string()),
+                    fixture: PathBuf::from("tests/fix.sse"),
+                    ..MockRoute::default()
+                },
+            ],
+        };
+        assert_eq!(
+            resolve_fixture(&config, dir.path(), &request).unwrap(),
+            dir.path().join("tests/fix.sse")
+        );
+    }
+
+    #[test]
+    fn missing_fixtures_name_the_file_to_add() {
+        let dir = TempDir::new().unwrap();
+        let request = MockRequest::from_input(&[user("hello")]);
+        let err = resolve_fixture(&MockConfig::default(), dir.path(), &request).unwrap_err();
+        assert!(matches!(err, CodexErr::MockFixture(_)));
+        assert!(
+            err.to_string()
+                .contains(&format!("{}.sse", request.prompt_hash))
+        );
+    }
+}
@@ -7,8 +7,15 @@
 //!      key. These override or extend the defaults at runtime.
 
 use crate::CodexAuth;
+use crate::aws_sigv4;
+use crate::aws_sigv4::AwsCredentials;
+use crate::aws_sigv4::SigningRequest;
+use crate::azure_ad;
 use crate::error::CodexErr;
 use crate::error::EnvVarError;
+use crate::gcp_adc;
+use crate::http_client::ProviderTls;
+use chrono::Utc;
 use code_app_server_protocol::AuthMode;
 use serde::Deserialize;
 use serde::Serialize;
@@ -16,6 +23,7 @@ use serde_json::Value;
 use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::env::VarError;
+use std::path::PathBuf;
 use std::time::Duration;
 const DEFAULT_STREAM_IDLE_TIMEOUT_MS: u64 = 300_000;
 const DEFAULT_STREAM_MAX_RETRIES: u64 = 5;
@@ -24,6 +32,17 @@ const DEFAULT_REQUEST_MAX_RETRIES: u64 = 4;
 const MAX_STREAM_MAX_RETRIES: u64 = 100;
 /// Hard cap for user-configured `request_max_retries`.
 const MAX_REQUEST_MAX_RETRIES: u64 = 100;
+const ANTHROPIC_DEFAULT_BASE_URL: &str = "https://api.anthropic.com/v1";
+const ANTHROPIC_VERSION_HEADER: &str = "anthropic-version";
+const ANTHROPIC_API_VERSION: &str = "2023-06-01";
+const GEMINI_DEFAULT_BASE_URL: &str = "https://generativelanguage.googleapis.com/v1beta";
+const BEDROCK_DEFAULT_REGION: &str = "us-east-1";
+const VERTEX_DEFAULT_REGION: &str = "us-central1";
+/// Endpoint name `mock` providers are tracked under; nothing is requested.
+const MOCK_BASE_URL: &str = "mock://fixtures";
+/// Sent in the body of Anthropic requests to Vertex AI, which does not take
+/// the `anthropic-version` header.
+pub(crate) const VERTEX_ANTHROPIC_VERSION: &str = "vertex-2023-10-16";
 
 /// Wire protocol that the provider speaks. Most third-party services only
 /// implement the classic OpenAI Chat Completions JSON schema, whereas OpenAI
@@ -40,6 +59,22 @@ pub enum WireApi {
     /// Regular Chat Completions compatible with `/v1/chat/completions`.
     #[default]
     Chat,
+
+    /// Anthropic's Messages API at `/v1/messages`, used for Claude models.
+    #[serde(rename = "anthropic_messages")]
+    AnthropicMessages,
+
+    /// Google's Gemini API, streamed from
+    /// `/models/<model>:streamGenerateContent`.
+    Gemini,
+
+    /// The Amazon Bedrock Converse API at `/model/<model>/converse-stream`,
+    /// with SigV4-signed requests.
+    #[serde(rename = "bedrock_converse")]
+    BedrockConverse,
+
+    /// Canned responses read from a fixtures directory, for working offline.
+    Mock,
 }
 
 /// Serializable representation of a provider definition.
@@ -90,6 +125,153 @@ pub struct ModelProviderInfo {
     /// Optional OpenRouter-specific configuration for routing preferences and metadata.
     #[serde(default)]
     pub openrouter: Option<OpenRouterConfig>,
+
+    /// Proxy URL for requests to this provider, overriding `HTTPS_PROXY`,
+    /// `HTTP_PROXY`, and `NO_PROXY`. An empty string connects directly.
+    #[serde(
+        default,
+        alias = "https_proxy",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub proxy: Option<String>,
+
+    /// PEM bundle of extra root certificates trusted for this provider, such
+    /// as the CA of a TLS-intercepting proxy.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_bundle_path: Option<PathBuf>,
+
+    /// PEM client certificate presented for mutual TLS. Requires
+    /// `client_key_path`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_cert_path: Option<PathBuf>,
+
+    /// PKCS#8 PEM private key for `client_cert_path`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_key_path: Option<PathBuf>,
+
+    /// Id of another entry in `model_providers` that serves requests while
+    /// this provider's circuit breaker is open.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback_provider: Option<String>,
+
+    /// Authenticate with Microsoft Entra ID (Azure AD) bearer tokens instead
+    /// of an API key.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub azure_ad: Option<AzureAdConfig>,
+
+    /// Serve the provider from Vertex AI, authenticated with Google
+    /// Application Default Credentials. Applies to the `gemini` and
+    /// `anthropic_messages` wire APIs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vertex: Option<VertexConfig>,
+
+    /// Fixtures directory and routing rules of a `mock` provider.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mock: Option<MockConfig>,
+
+    /// Also retry responses that complete without any output or get stuck
+    /// repeating the same text. Truncated responses are always retried.
+    #[serde(default)]
+    pub retry_degenerate_responses: bool,
+}
+
+/// `azure_ad` settings of a provider. With `client_secret_env` the
+/// client-credentials flow is used; without it, the host's managed identity.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq, Hash)]
+pub struct AzureAdConfig {
+    /// Directory (tenant) id. Required for the client-credentials flow.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant_id: Option<String>,
+
+    /// Application (client) id of the app registration, or of a
+    /// user-assigned managed identity.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+
+    /// Environment variable holding the app registration's client secret.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_secret_env: Option<String>,
+
+    /// Token scope. Defaults to `https://cognitiveservices.azure.com/.default`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+
+    /// Entra authority for sovereign clouds, e.g.
+    /// `https://login.microsoftonline.us`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub authority_host: Option<String>,
+}
+
+impl AzureAdConfig {
+    pub(crate) fn scope(&self) -> &str {
+        self.scope.as_deref().unwrap_or(azure_ad::DEFAULT_SCOPE)
+    }
+
+    /// Managed identity endpoints take a resource rather than a scope.
+    pub(crate) fn resource(&self) -> &str {
+        let scope = self.scope();
+        scope.strip_suffix("/.default").unwrap_or(scope)
+    }
+}
+
+/// `vertex` settings of a provider.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct VertexConfig {
+    /// Google Cloud project id that is billed for the requests.
+    pub project: String,
+
+    /// Vertex AI location, e.g. `us-east5` or `global`. Defaults to
+    /// `us-central1`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+}
+
+impl VertexConfig {
+    pub(crate) fn region(&self) -> &str {
+        self.region.as_deref().unwrap_or(VERTEX_DEFAULT_REGION)
+    }
+
+    /// The regional endpoint, or the global one for the `global` location.
+    pub(crate) fn base_url(&self) -> String {
+        match self.region() {
+            "global" => "https://aiplatform.googleapis.com/v1".to_string(),
+            region => format!("https://{region}-aiplatform.googleapis.com/v1"),
+        }
+    }
+}
+
+/// `mock` settings of a provider.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct MockConfig {
+    /// Directory holding the fixtures. Relative paths are resolved against
+    /// the session's working directory. Defaults to `<code_home>/fixtures`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fixtures_dir: Option<PathBuf>,
+
+    /// Rules checked in order before fixtures named after the prompt hash.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub routes: Vec<MockRoute>,
+}
+
+/// A `mock` routing rule. It matches when the step matches (if set) and
+/// either `prompt_hash` equals the hash of the latest user message or
+/// `pattern` matches its text.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct MockRoute {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt_hash: Option<String>,
+
+    /// Regular expression searched for in the latest user message.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+
+    /// Request number within the turn, starting at 1 and counting the
+    /// requests that follow tool calls.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub step: Option<u32>,
+
+    /// Fixture file, relative to the fixtures directory.
+    pub fixture: PathBuf,
 }
 
 /// OpenRouter-specific configuration, allowing users to control routing and pricing metadata.
@@ -187,19 +369,161 @@ impl ModelProviderInfo {
         client: &'a reqwest::Client,
         auth: &Option<CodexAuth>,
     ) -> crate::error::Result<reqwest::RequestBuilder> {
-        let effective_auth = self.effective_auth(auth)?;
+        let effective_auth = self.effective_auth(auth).await?;
 
         let url = self.get_full_url(&effective_auth);
 
-        let mut builder = client.post(&url);
+        self.authorize(client.post(&url), effective_auth.as_ref())
+            .await
+    }
 
-        if let Some(auth) = effective_auth.as_ref() {
-            builder = builder.bearer_auth(auth.get_token().await?);
+    /// Request builder that resumes streaming the stored response
+    /// `response_id` after event `starting_after`.
+    pub(crate) async fn create_resume_request_builder(
+        &self,
+        client: &reqwest::Client,
+        auth: &Option<CodexAuth>,
+        response_id: &str,
+        starting_after: Option<u64>,
+    ) -> crate::error::Result<reqwest::RequestBuilder> {
+        let effective_auth = self.effective_auth(auth).await?;
+        let url = self.get_resume_stream_url(&effective_auth, response_id, starting_after);
+        self.authorize(client.get(url), effective_auth.as_ref())
+            .await
+    }
+
+    /// Request builder for a streamed Messages request for `model` on an
+    /// Anthropic provider. On Vertex AI the URL names the model.
+    pub(crate) async fn create_anthropic_request_builder(
+        &self,
+        client: &reqwest::Client,
+        auth: &Option<CodexAuth>,
+        model: &str,
+    ) -> crate::error::Result<reqwest::RequestBuilder> {
+        let effective_auth = self.effective_auth(auth).await?;
+        let url = self.get_anthropic_stream_url(model);
+        self.authorize(client.post(url), effective_auth.as_ref())
+            .await
+    }
+
+    /// Request builder for the streaming `generateContent` endpoint of
+    /// `model` on a Gemini provider, whose URLs name the model.
+    pub(crate) async fn create_gemini_request_builder(
+        &self,
+        client: &reqwest::Client,
+        auth: &Option<CodexAuth>,
+        model: &str,
+    ) -> crate::error::Result<reqwest::RequestBuilder> {
+        let effective_auth = self.effective_auth(auth).await?;
+        let url = self.get_gemini_stream_url(model);
+        self.authorize(client.post(url), effective_auth.as_ref())
+            .await
+    }
+
+    /// Request builder for the Converse stream of `model` on a Bedrock
+    /// provider. Without an `env_key` (a Bedrock API key) the request is
+    /// signed with the AWS credentials, which covers `body`, so the body is
+    /// set here.
+    pub(crate) async fn create_bedrock_request_builder(
+        &self,
+        client: &reqwest::Client,
+        model: &str,
+        body: Vec<u8>,
+    ) -> crate::error::Result<reqwest::RequestBuilder> {
+        let url = self.get_bedrock_stream_url(model);
+        let mut builder = client
+            .post(&url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(
+                reqwest::header::ACCEPT,
+                "application/vnd.amazon.eventstream",
+            );
+        if let Some(key) = self.api_key()? {
+            builder = builder.bearer_auth(key);
+        } else {
+            let parsed = reqwest::Url::parse(&url).map_err(|err| {
+                CodexErr::UnsupportedOperation(format!("invalid Bedrock URL {url}: {err}"))
+            })?;
+            let headers = aws_sigv4::sign(
+                &SigningRequest {
+                    method: "POST",
+                    url: &parsed,
+                    headers: &[("content-type", "application/json")],
+                    payload: &body,
+                },
+                &AwsCredentials::load().await?,
+                &self.bedrock_region(),
+                "bedrock",
+                Utc::now(),
+            );
+            for (name, value) in headers {
+                builder = builder.header(name, value);
+            }
+        }
+        Ok(self.apply_http_headers(builder.body(body)))
+    }
+
+    /// Adds the credentials, in the header the wire API expects, and the
+    /// provider's extra headers.
+    async fn authorize(
+        &self,
+        mut builder: reqwest::RequestBuilder,
+        auth: Option<&CodexAuth>,
+    ) -> crate::error::Result<reqwest::RequestBuilder> {
+        if let Some(auth) = auth {
+            let token = auth.get_token().await?;
+            builder = match self.wire_api {
+                _ if self.vertex.is_some() => builder.bearer_auth(token),
+                WireApi::AnthropicMessages => builder.header("x-api-key", token),
+                WireApi::Gemini => builder.header("x-goog-api-key", token),
+                WireApi::Responses | WireApi::Chat | WireApi::BedrockConverse | WireApi::Mock => {
+                    builder.bearer_auth(token)
+                }
+            };
+        }
+        if self.wire_api == WireApi::AnthropicMessages
+            && self.vertex.is_none()
+            && !self.http_headers.as_ref().is_some_and(|headers| {
+                headers
+                    .keys()
+                    .any(|key| key.eq_ignore_ascii_case(ANTHROPIC_VERSION_HEADER))
+            })
+        {
+            builder = builder.header(ANTHROPIC_VERSION_HEADER, ANTHROPIC_API_VERSION);
         }
 
         Ok(self.apply_http_headers(builder))
     }
 
+    /// Request builder for a Batch API call to `path`. Batches are only
+    /// offered on Responses API providers authenticated with an API key.
+    pub(crate) async fn create_batch_request_builder(
+        &self,
+        client: &reqwest::Client,
+        auth: &Option<CodexAuth>,
+        method: reqwest::Method,
+        path: &str,
+    ) -> crate::error::Result<reqwest::RequestBuilder> {
+        if self.wire_api != WireApi::Responses {
+            return Err(CodexErr::UnsupportedOperation(
+                "The Batch API requires a Responses API provider".to_string(),
+            ));
+        }
+        let effective_auth = self.effective_auth(auth).await?;
+        if effective_auth
+            .as_ref()
+            .is_some_and(|auth| auth.mode == AuthMode::ChatGPT)
+        {
+            return Err(CodexErr::UnsupportedOperation(
+                "The Batch API requires an API key; ChatGPT sign-in does not support it"
+                    .to_string(),
+            ));
+        }
+        let url = self.get_batch_api_url(&effective_auth, path);
+        self.authorize(client.request(method, url), effective_auth.as_ref())
+            .await
+    }
+
     pub async fn create_compact_request_builder<'a>(
         &'a self,
         client: &'a reqwest::Client,
@@ -210,7 +534,7 @@ impl ModelProviderInfo {
                 "Compaction endpoint requires Responses API providers".to_string(),
             ));
         }
-        let effective_auth = self.effective_auth(auth)?;
+        let effective_auth = self.effective_auth(auth).await?;
         let url = self.get_compact_url(&effective_auth).ok_or_else(|| {
             CodexErr::UnsupportedOperation(
                 "Compaction endpoint requires Responses API providers".to_string(),
@@ -225,7 +549,21 @@ impl ModelProviderInfo {
         Ok(self.apply_http_headers(builder))
     }
 
-    fn effective_auth(&self, auth: &Option<CodexAuth>) -> crate::error::Result<Option<CodexAuth>> {
+    /// The credentials requests are sent with: an Azure AD token when
+    /// `azure_ad` is configured, a Google access token when `vertex` is,
+    /// else the `env_key` API key, else `auth`.
+    async fn effective_auth(
+        &self,
+        auth: &Option<CodexAuth>,
+    ) -> crate::error::Result<Option<CodexAuth>> {
+        if let Some(azure_ad) = &self.azure_ad {
+            let token = azure_ad::bearer_token(azure_ad).await?;
+            return Ok(Some(CodexAuth::from_api_key(&token)));
+        }
+        if self.vertex.is_some() {
+            let token = gcp_adc::access_token().await?;
+            return Ok(Some(CodexAuth::from_api_key(&token)));
+        }
         match self.api_key() {
             Ok(Some(key)) => Ok(Some(CodexAuth::from_api_key(&key))),
             Ok(None) => Ok(auth.clone()),
@@ -258,27 +596,149 @@ impl ModelProviderInfo {
     }
 
     pub(crate) fn get_full_url(&self, auth: &Option<CodexAuth>) -> String {
-        let default_base_url = if matches!(
+        let default_base_url = if self.wire_api == WireApi::AnthropicMessages {
+            ANTHROPIC_DEFAULT_BASE_URL.to_string()
+        } else if self.wire_api == WireApi::Gemini {
+            GEMINI_DEFAULT_BASE_URL.to_string()
+        } else if self.wire_api == WireApi::BedrockConverse {
+            bedrock_base_url(&self.bedrock_region())
+        } else if self.wire_api == WireApi::Mock {
+            MOCK_BASE_URL.to_string()
+        } else if matches!(
             auth,
             Some(CodexAuth {
                 mode: AuthMode::ChatGPT,
                 ..
             })
         ) {
-            "https://chatgpt.com/backend-api/codex"
+            "https://chatgpt.com/backend-api/codex".to_string()
         } else {
-            "https://api.openai.com/v1"
+            "https://api.openai.com/v1".to_string()
         };
         let query_string = self.get_query_string();
-        let base_url = self
-            .base_url
-            .clone()
-            .unwrap_or(default_base_url.to_string());
+        let base_url = self.base_url.clone().unwrap_or(default_base_url);
 
         match self.wire_api {
             WireApi::Responses => format!("{base_url}/responses{query_string}"),
             WireApi::Chat => format!("{base_url}/chat/completions{query_string}"),
+            WireApi::AnthropicMessages => format!("{base_url}/messages{query_string}"),
+            WireApi::Gemini => format!("{base_url}/models{query_string}"),
+            WireApi::BedrockConverse => format!("{base_url}/model{query_string}"),
+            WireApi::Mock => base_url,
+        }
+    }
+
+    pub(crate) fn get_resume_stream_url(
+        &self,
+        auth: &Option<CodexAuth>,
+        response_id: &str,
+        starting_after: Option<u64>,
+    ) -> String {
+        let full_url = self.get_full_url(auth);
+        let (endpoint, query) = full_url.split_once('?').unwrap_or((&full_url, ""));
+        let mut url = format!("{endpoint}/{response_id}?stream=true");
+        if let Some(sequence) = starting_after {
+            url.push_str(&format!("&starting_after={sequence}"));
+        }
+        if !query.is_empty() {
+            url.push('&');
+            url.push_str(query);
+        }
+        url
+    }
+
+    /// URL of `path` (`files`, `batches/<id>`, ...) next to the Responses
+    /// endpoint, for the Batch API.
+    pub(crate) fn get_batch_api_url(&self, auth: &Option<CodexAuth>, path: &str) -> String {
+        let full_url = self.get_full_url(auth);
+        let (endpoint, query) = full_url.split_once('?').unwrap_or((&full_url, ""));
+        let base_url = endpoint.strip_suffix("/responses").unwrap_or(endpoint);
+        let mut url = format!("{base_url}/{path}");
+        if !query.is_empty() {
+            url.push('?');
+            url.push_str(query);
+        }
+        url
+    }
+
+    pub(crate) fn get_anthropic_stream_url(&self, model: &str) -> String {
+        match &self.vertex {
+            Some(vertex) => {
+                self.get_vertex_model_url(vertex, "anthropic", model, "streamRawPredict")
+            }
+            None => self.get_full_url(&None),
+        }
+    }
+
+    pub(crate) fn get_gemini_stream_url(&self, model: &str) -> String {
+        if let Some(vertex) = &self.vertex {
+            return self.get_vertex_model_url(vertex, "google", model, "streamGenerateContent");
+        }
+        let base_url = self
+            .base_url
+            .clone()
+            .unwrap_or(GEMINI_DEFAULT_BASE_URL.to_string());
+        let extra_params = self.get_query_string().replacen('?', "&", 1);
+        format!("{base_url}/models/{model}:streamGenerateContent?alt=sse{extra_params}")
+    }
+
+    /// URL of `method` on a `publisher` model in the provider's Vertex AI
+    /// project. `base_url`, when set, replaces the regional endpoint.
+    fn get_vertex_model_url(
+        &self,
+        vertex: &VertexConfig,
+        publisher: &str,
+        model: &str,
+        method: &str,
+    ) -> String {
+        let base_url = self.base_url.clone().unwrap_or_else(|| vertex.base_url());
+        let project = &vertex.project;
+        let region = vertex.region();
+        let mut url = format!(
+            "{base_url}/projects/{project}/locations/{region}/publishers/{publisher}/models/{model}:{method}"
+        );
+        if method == "streamGenerateContent" {
+            url.push_str("?alt=sse");
+            url.push_str(&self.get_query_string().replacen('?', "&", 1));
+        } else {
+            url.push_str(&self.get_query_string());
         }
+        url
+    }
+
+    pub(crate) fn get_bedrock_stream_url(&self, model: &str) -> String {
+        let base_url = self
+            .base_url
+            .clone()
+            .unwrap_or_else(|| bedrock_base_url(&self.bedrock_region()));
+        let model = aws_sigv4::uri_encode(model);
+        let query_string = self.get_query_string();
+        format!("{base_url}/model/{model}/converse-stream{query_string}")
+    }
+
+    /// The region in a `bedrock-runtime.<region>.amazonaws.com` base URL,
+    /// else `AWS_REGION`, `AWS_DEFAULT_REGION`, or `us-east-1`.
+    pub(crate) fn bedrock_region(&self) -> String {
+        self.base_url
+            .as_deref()
+            .and_then(|base_url| reqwest::Url::parse(base_url).ok())
+            .and_then(|url| {
+                let host = url.host_str()?;
+                let rest = host
+                    .strip_prefix("bedrock-runtime.")
+                    .or_else(|| host.strip_prefix("bedrock-runtime-fips."))?;
+                rest.split('.').next().map(str::to_string)
+            })
+            .or_else(|| {
+                ["AWS_REGION", "AWS_DEFAULT_REGION"]
+                    .into_iter()
+                    .find_map(|var| {
+                        std::env::var(var)
+                            .ok()
+                            .filter(|value| !value.trim().is_empty())
+                    })
+            })
+            .unwrap_or_else(|| BEDROCK_DEFAULT_REGION.to_string())
     }
 
     pub(crate) fn get_compact_url(&self, auth: &Option<CodexAuth>) -> Option<String> {
@@ -308,6 +768,22 @@ impl ModelProviderInfo {
             .unwrap_or(false)
     }
 
+    /// Whether a dropped stream can be resumed with `GET /responses/{id}`.
+    /// Only stored responses can be retrieved, and Azure is the one endpoint
+    /// that gets `store: true`.
+    pub(crate) fn supports_stream_resume(&self) -> bool {
+        self.is_azure_responses_endpoint()
+    }
+
+    /// Whether a Responses API request may carry `seed`. OpenAI, Azure, and
+    /// the ChatGPT backend reject it; other Responses servers accept it.
+    pub(crate) fn accepts_responses_seed(&self) -> bool {
+        self.wire_api == WireApi::Responses
+            && !self.is_public_openai_responses_endpoint()
+            && !self.is_azure_responses_endpoint()
+            && !self.is_backend_responses_endpoint()
+    }
+
     pub(crate) fn is_backend_responses_endpoint(&self) -> bool {
         if self.wire_api != WireApi::Responses {
             return false;
@@ -410,6 +886,15 @@ impl ModelProviderInfo {
             .unwrap_or(Duration::from_millis(DEFAULT_STREAM_IDLE_TIMEOUT_MS))
     }
 
+    /// Reads this provider's `ca_bundle_path` and client certificate files.
+    pub(crate) fn load_tls(&self) -> std::io::Result<ProviderTls> {
+        ProviderTls::load(
+            self.ca_bundle_path.as_deref(),
+            self.client_cert_path.as_deref(),
+            self.client_key_path.as_deref(),
+        )
+    }
+
     pub fn base_url_for_probe(&self) -> String {
         self.base_url
             .clone()
@@ -427,6 +912,10 @@ fn wire_api_override_from_env(env_key: &str) -> Option<WireApi> {
         Ok(value) => match value.trim().to_ascii_lowercase().as_str() {
             "chat" => Some(WireApi::Chat),
             "responses" => Some(WireApi::Responses),
+            "anthropic_messages" => Some(WireApi::AnthropicMessages),
+            "gemini" => Some(WireApi::Gemini),
+            "bedrock_converse" => Some(WireApi::BedrockConverse),
+            "mock" => Some(WireApi::Mock),
             other if !other.is_empty() => {
                 tracing::warn!(
                     "Ignoring unknown {env_key} value '{other}'; falling back to default wire API"
@@ -486,6 +975,15 @@ pub fn built_in_model_providers() -> HashMap<String, ModelProviderInfo> {
                 stream_idle_timeout_ms: None,
                 requires_openai_auth: true,
                 openrouter: None,
+                proxy: None,
+                ca_bundle_path: None,
+                client_cert_path: None,
+                client_key_path: None,
+                fallback_provider: None,
+                azure_ad: None,
+                vertex: None,
+                mock: None,
+                retry_degenerate_responses: false,
             },
         ),
         (BUILT_IN_OSS_MODEL_PROVIDER_ID, create_oss_provider()),
@@ -531,9 +1029,22 @@ pub fn create_oss_provider_with_base_url(base_url: &str) -> ModelProviderInfo {
         stream_idle_timeout_ms: None,
         requires_openai_auth: false,
         openrouter: None,
+        proxy: None,
+        ca_bundle_path: None,
+        client_cert_path: None,
+        client_key_path: None,
+        fallback_provider: None,
+        azure_ad: None,
+        vertex: None,
+        mock: None,
+        retry_degenerate_responses: false,
     }
 }
 
+fn bedrock_base_url(region: &str) -> String {
+    format!("https://bedrock-runtime.{region}.amazonaws.com")
+}
+
 fn matches_azure_responses_base_url(base_url: &str) -> bool {
     let base = base_url.to_ascii_lowercase();
     const AZURE_MARKERS: [&str; 5] = [
@@ -571,6 +1082,15 @@ base_url = "http://localhost:11434/v1"
             stream_idle_timeout_ms: None,
             requires_openai_auth: false,
             openrouter: None,
+            proxy: None,
+            ca_bundle_path: None,
+            client_cert_path: None,
+            client_key_path: None,
+            fallback_provider: None,
+            azure_ad: None,
+            vertex: None,
+            mock: None,
+            retry_degenerate_responses: false,
         };
 
         let provider: ModelProviderInfo = toml::from_str(azure_provider_toml).unwrap();
@@ -601,6 +1121,15 @@ query_params = { api-version = "2025-04-01-preview" }
             stream_idle_timeout_ms: None,
             requires_openai_auth: false,
             openrouter: None,
+            proxy: None,
+            ca_bundle_path: None,
+            client_cert_path: None,
+            client_key_path: None,
+            fallback_provider: None,
+            azure_ad: None,
+            vertex: None,
+            mock: None,
+            retry_degenerate_responses: false,
         };
 
         let provider: ModelProviderInfo = toml::from_str(azure_provider_toml).unwrap();
@@ -634,12 +1163,158 @@ env_http_headers = { "X-Example-Env-Header" = "EXAMPLE_ENV_VAR" }
             stream_idle_timeout_ms: None,
             requires_openai_auth: false,
             openrouter: None,
+            proxy: None,
+            ca_bundle_path: None,
+            client_cert_path: None,
+            client_key_path: None,
+            fallback_provider: None,
+            azure_ad: None,
+            vertex: None,
+            mock: None,
+            retry_degenerate_responses: false,
         };
 
         let provider: ModelProviderInfo = toml::from_str(azure_provider_toml).unwrap();
         assert_eq!(expected_provider, provider);
     }
 
+    #[test]
+    fn anthropic_messages_provider_defaults_to_the_anthropic_endpoint() {
+        let anthropic_provider_toml = r#"
+name = "Anthropic"
+env_key = "ANTHROPIC_API_KEY"
+wire_api = "anthropic_messages"
+        "#;
+        let provider: ModelProviderInfo = toml::from_str(anthropic_provider_toml).unwrap();
+        assert_eq!(provider.wire_api, WireApi::AnthropicMessages);
+        assert_eq!(
+            provider.get_full_url(&None),
+            "https://api.anthropic.com/v1/messages"
+        );
+
+        let proxied = ModelProviderInfo {
+            base_url: Some("https://proxy.example.com/anthropic/v1".into()),
+            ..provider
+        };
+        assert_eq!(
+            proxied.get_full_url(&None),
+            "https://proxy.example.com/anthropic/v1/messages"
+        );
+        assert_eq!(proxied.get_compact_url(&None), None);
+    }
+
+    #[test]
+    fn gemini_provider_streams_from_the_model_url() {
+        let gemini_provider_toml = r#"
+name = "Gemini"
+env_key = "GEMINI_API_KEY"
+wire_api = "gemini"
+        "#;
+        let provider: ModelProviderInfo = toml::from_str(gemini_provider_toml).unwrap();
+        assert_eq!(provider.wire_api, WireApi::Gemini);
+        assert_eq!(
+            provider.get_gemini_stream_url("gemini-2.5-pro"),
+            "https://generativelanguage.googleapis.com/v1beta/models/gemini-2.5-pro:streamGenerateContent?alt=sse"
+        );
+
+        let with_params = ModelProviderInfo {
+            base_url: Some("https://proxy.example.com/v1beta".into()),
+            query_params: Some(maplit::hashmap! {
+                "project".to_string() => "demo".to_string(),
+            }),
+            ..provider
+        };
+        assert_eq!(
+            with_params.get_gemini_stream_url("gemini-2.5-flash"),
+            "https://proxy.example.com/v1beta/models/gemini-2.5-flash:streamGenerateContent?alt=sse&project=demo"
+        );
+    }
+
+    #[test]
+    fn vertex_providers_use_regional_model_urls() {
+        let vertex_provider_toml = r#"
+name = "Vertex AI"
+wire_api = "gemini"
+vertex = { project = "my-project" }
+        "#;
+        let provider: ModelProviderInfo = toml::from_str(vertex_provider_toml).unwrap();
+        assert_eq!(
+            provider.get_gemini_stream_url("gemini-2.5-pro"),
+            "https://us-central1-aiplatform.googleapis.com/v1/projects/my-project/locations/us-central1/publishers/google/models/gemini-2.5-pro:streamGenerateContent?alt=sse"
+        );
+
+        let claude = ModelProviderInfo {
+            wire_api: WireApi::AnthropicMessages,
+            vertex: Some(VertexConfig {
+                project: "my-project".to_string(),
+                region: Some("us-east5".to_string()),
+            }),
+            ..provider.clone()
+        };
+        assert_eq!(
+            claude.get_anthropic_stream_url("claude-sonnet-4-5@20250929"),
+            "https://us-east5-aiplatform.googleapis.com/v1/projects/my-project/locations/us-east5/publishers/anthropic/models/claude-sonnet-4-5@20250929:streamRawPredict"
+        );
+
+        let global = ModelProviderInfo {
+            vertex: Some(VertexConfig {
+                project: "my-project".to_string(),
+                region: Some("global".to_string()),
+            }),
+            ..provider
+        };
+        assert_eq!(
+            global.get_gemini_stream_url("gemini-2.5-flash"),
+            "https://aiplatform.googleapis.com/v1/projects/my-project/locations/global/publishers/google/models/gemini-2.5-flash:streamGenerateContent?alt=sse"
+        );
+    }
+
+    #[test]
+    fn bedrock_provider_takes_the_region_from_its_base_url() {
+        let bedrock_provider_toml = r#"
+name = "Bedrock"
+base_url = "https://bedrock-runtime.eu-west-1.amazonaws.com"
+wire_api = "bedrock_converse"
+        "#;
+        let provider: ModelProviderInfo = toml::from_str(bedrock_provider_toml).unwrap();
+        assert_eq!(provider.wire_api, WireApi::BedrockConverse);
+        assert_eq!(provider.bedrock_region(), "eu-west-1");
+        assert_eq!(
+            provider.get_bedrock_stream_url("anthropic.claude-3-5-sonnet-20240620-v1:0"),
+            "https://bedrock-runtime.eu-west-1.amazonaws.com/model/anthropic.claude-3-5-sonnet-20240620-v1%3A0/converse-stream"
+        );
+    }
+
+    #[test]
+    fn azure_resume_urls_keep_the_api_version() {
+        let azure_provider_toml = r#"
+name = "Azure"
+base_url = "https://xxxxx.openai.azure.com/openai"
+query_params = { api-version = "2025-04-01-preview" }
+        "#;
+        let provider: ModelProviderInfo = toml::from_str(azure_provider_toml).unwrap();
+        assert!(provider.supports_stream_resume());
+        assert_eq!(
+            provider.get_resume_stream_url(&None, "resp_1", Some(42)),
+            "https://xxxxx.openai.azure.com/openai/responses/resp_1?stream=true&starting_after=42&api-version=2025-04-01-preview"
+        );
+    }
+
+    #[test]
+    fn batch_api_urls_sit_next_to_the_responses_endpoint() {
+        let azure_provider_toml = r#"
+name = "Azure"
+base_url = "https://xxxxx.openai.azure.com/openai"
+wire_api = "responses"
+query_params = { api-version = "2025-04-01-preview" }
+        "#;
+        let provider: ModelProviderInfo = toml::from_str(azure_provider_toml).unwrap();
+        assert_eq!(
+            provider.get_batch_api_url(&None, "batches/batch_1"),
+            "https://xxxxx.openai.azure.com/openai/batches/batch_1?api-version=2025-04-01-preview"
+        );
+    }
+
     #[test]
     fn detects_azure_responses_base_urls() {
         fn provider_for(base_url: &str) -> ModelProviderInfo {
@@ -657,6 +1332,15 @@ env_http_headers = { "X-Example-Env-Header" = "EXAMPLE_ENV_VAR" }
                 stream_idle_timeout_ms: None,
                 requires_openai_auth: false,
                 openrouter: None,
+                proxy: None,
+                ca_bundle_path: None,
+                client_cert_path: None,
+                client_key_path: None,
+                fallback_provider: None,
+                azure_ad: None,
+                vertex: None,
+                mock: None,
+                retry_degenerate_responses: false,
             }
         }
 
@@ -690,6 +1374,15 @@ env_http_headers = { "X-Example-Env-Header" = "EXAMPLE_ENV_VAR" }
             stream_idle_timeout_ms: None,
             requires_openai_auth: false,
             openrouter: None,
+            proxy: None,
+            ca_bundle_path: None,
+            client_cert_path: None,
+            client_key_path: None,
+            fallback_provider: None,
+            azure_ad: None,
+            vertex: None,
+            mock: None,
+            retry_degenerate_responses: false,
         };
         assert!(named_provider.is_azure_responses_endpoint());
 
@@ -91,6 +91,8 @@ pub struct ToolsConfig {
     pub include_view_image_tool: bool,
     pub web_search_allowed_domains: Option<Vec<String>>,
     pub agent_model_allowed_values: Vec<String>,
+    /// Offer `semantic_search`; set when an `[embeddings]` provider is configured.
+    pub semantic_search: bool,
 }
 
 #[allow(dead_code)]
@@ -156,6 +158,7 @@ impl ToolsConfig {
             include_view_image_tool,
             web_search_allowed_domains: None,
             agent_model_allowed_values: Vec::new(),
+            semantic_search: false,
         }
     }
 
@@ -711,6 +714,10 @@ pub fn get_openai_tools(
     tools.push(create_wait_tool());
     tools.push(create_kill_tool());
 
+    if config.semantic_search {
+        tools.push(create_semantic_search_tool());
+    }
+
     if config.web_search_request {
         let tool = match &config.web_search_allowed_domains {
             Some(domains) if !domains.is_empty() => OpenAiTool::WebSearch(WebSearchTool {
@@ -798,6 +805,38 @@ pub fn create_kill_tool() -> OpenAiTool {
     })
 }
 
+pub fn create_semantic_search_tool() -> OpenAiTool {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "query".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "What the code you are looking for does, in natural language.".to_string(),
+            ),
+            allowed_values: None,
+        },
+    );
+    properties.insert(
+        "limit".to_string(),
+        JsonSchema::Number {
+            description: Some(
+                "Maximum number of chunks to return (default 8, max 32).".to_string(),
+            ),
+        },
+    );
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: "semantic_search".to_string(),
+        description: "Search the workspace files by meaning rather than by exact text. Returns the closest chunks of up to 60 lines with their path and line range. Use it to find where a concept is implemented when you do not know the identifiers to grep for.".to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["query".to_string()]),
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
 fn create_browser_tool(browser_enabled: bool) -> OpenAiTool {
     let mut actions = vec!["open", "status", "fetch"];
     if browser_enabled {
@@ -1071,6 +1110,37 @@ mod tests {
         );
     }
 
+    #[test]
+    fn semantic_search_is_offered_when_enabled() {
+        let model_family = find_family_for_model("codex-mini-latest")
+            .expect("codex-mini-latest should be a valid model family");
+        let mut config = ToolsConfig::new(
+            &model_family,
+            AskForApproval::Never,
+            SandboxPolicy::ReadOnly,
+            false,
+            false,
+            false,
+            /*use_experimental_streamable_shell_tool*/ false,
+            false,
+        );
+        apply_default_agent_models(&mut config);
+        config.semantic_search = true;
+        let tools = get_openai_tools(&config, Some(HashMap::new()), false, false);
+
+        assert_eq_tool_names(
+            &tools,
+            &[
+                "local_shell",
+                "browser",
+                "agent",
+                "wait",
+                "kill",
+                "semantic_search",
+            ],
+        );
+    }
+
     #[test]
     fn test_get_openai_tools_with_active_agents() {
         let model_family = find_family_for_model("codex-mini-latest")
@@ -0,0 +1,354 @@
+//! Speculative prefetch of files the model is likely to read next.
+//!
+//! Paths mentioned in the user's input, in streamed assistant items, and in
+//! tool output are read on a blocking task while the model keeps streaming.
+//! The read warms the OS page cache for the shell commands that usually
+//! follow, and the contents are kept in a small bounded cache that in-process
+//! readers consult before touching the filesystem again. Cached entries are
+//! only served while the file's [`FileStamp`] is unchanged, and files changed
+//! shortly before they are read are not cached at all, since a same-size
+//! write within the filesystem's timestamp resolution would leave the stamp
+//! as it was.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::collections::VecDeque;
+use std::fs::Metadata;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use code_protocol::models::ContentItem;
+use code_protocol::models::FunctionCallOutputPayload;
+use code_protocol::models::ResponseInputItem;
+use code_protocol::models::ResponseItem;
+
+/// Files larger than this are left to the page cache alone.
+const MAX_FILE_BYTES: u64 = 512 * 1024;
+/// Total bytes kept in memory; the oldest entries are evicted first.
+const MAX_CACHE_BYTES: usize = 16 * 1024 * 1024;
+/// New paths read per prefetch request.
+const MAX_PATHS_PER_REQUEST: usize = 16;
+/// Files changed this recently are not cached. Wider than the coarsest
+/// common timestamp resolution (2 s on FAT).
+const RACY_WINDOW: Duration = Duration::from_secs(3);
+
+/// Metadata that must be unchanged for a cached file to be served.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileStamp {
+    len: u64,
+    modified: Option<SystemTime>,
+    /// A file replaced by a rename gets a new inode.
+    #[cfg(unix)]
+    inode: u64,
+    /// Moves on every write, even when a tool restores the mtime.
+    #[cfg(unix)]
+    changed: Option<SystemTime>,
+}
+
+impl FileStamp {
+    fn of(metadata: &Metadata) -> Self {
+        #[cfg(unix)]
+        use std::os::unix::fs::MetadataExt;
+
+        Self {
+            len: metadata.len(),
+            modified: metadata.modified().ok(),
+            #[cfg(unix)]
+            inode: metadata.ino(),
+            #[cfg(unix)]
+            changed: u64::try_from(metadata.ctime()).ok().and_then(|secs| {
+                SystemTime::UNIX_EPOCH.checked_add(Duration::new(
+                    secs,
+                    u32::try_from(metadata.ctime_nsec()).unwrap_or(0),
+                ))
+            }),
+        }
+    }
+
+    /// Whether the file may have changed within [`RACY_WINDOW`] before
+    /// `now`, or its timestamps are unavailable.
+    fn is_racy(&self, now: SystemTime) -> bool {
+        #[cfg(unix)]
+        let latest = self.modified.max(self.changed);
+        #[cfg(not(unix))]
+        let latest = self.modified;
+        latest.is_none_or(|latest| now.duration_since(latest).unwrap_or_default() < RACY_WINDOW)
+    }
+}
+
+struct CachedFile {
+    stamp: FileStamp,
+    contents: Arc<Vec<u8>>,
+}
+
+#[derive(Default)]
+struct PrefetchCache {
+    entries: HashMap<PathBuf, CachedFile>,
+    order: VecDeque<PathBuf>,
+    /// Paths already read or being read, so repeated mentions are free.
+    requested: HashSet<PathBuf>,
+    total_bytes: usize,
+}
+
+impl PrefetchCache {
+    fn insert(&mut self, path: PathBuf, file: CachedFile) {
+        self.total_bytes += file.contents.len();
+        if let Some(old) = self.entries.insert(path.clone(), file) {
+            self.total_bytes -= old.contents.len();
+            self.order.retain(|existing| existing != &path);
+        }
+        self.order.push_back(path);
+        while self.total_bytes > MAX_CACHE_BYTES
+            && let Some(oldest) = self.order.pop_front()
+        {
+            if let Some(evicted) = self.entries.remove(&oldest) {
+                self.total_bytes -= evicted.contents.len();
+            }
+            self.requested.remove(&oldest);
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct ContextPrefetcher {
+    cwd: PathBuf,
+    cache: Arc<Mutex<PrefetchCache>>,
+}
+
+impl ContextPrefetcher {
+    pub fn new(cwd: PathBuf) -> Self {
+        Self {
+            cwd,
+            cache: Arc::new(Mutex::new(PrefetchCache::default())),
+        }
+    }
+
+    /// Starts reading files referenced by a streamed model item.
+    pub fn prefetch_from_item(&self, item: &ResponseItem) {
+        match item {
+            ResponseItem::Message { content, .. } => {
+                for content in content {
+                    if let ContentItem::InputText { text } | ContentItem::OutputText { text } =
+                        content
+                    {
+                        self.prefetch_from_text(text);
+                    }
+                }
+            }
+            ResponseItem::FunctionCall { arguments, .. } => self.prefetch_from_text(arguments),
+            ResponseItem::CustomToolCall { input, .. } => self.prefetch_from_text(input),
+            _ => {}
+        }
+    }
+
+    /// Starts reading files referenced by a tool's output, e.g. search hits.
+    pub fn prefetch_from_tool_output(&self, output: &ResponseInputItem) {
+        match output {
+            ResponseInputItem::FunctionCallOutput {
+                output: FunctionCallOutputPayload { content, .. },
+                ..
+            } => self.prefetch_from_text(content),
+            ResponseInputItem::CustomToolCallOutput { output, .. } => {
+                self.prefetch_from_text(output)
+            }
+            _ => {}
+        }
+    }
+
+    /// Starts reading every not-yet-requested file `text` refers to. Returns
+    /// immediately; does nothing outside a Tokio runtime.
+    pub fn prefetch_from_text(&self, text: &str) {
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let paths: Vec<PathBuf> = {
+            let Ok(mut cache) = self.cache.lock() else {
+                return;
+            };
+            referenced_paths(text, &self.cwd)
+                .into_iter()
+                .filter(|path| cache.requested.insert(path.clone()))
+                .take(MAX_PATHS_PER_REQUEST)
+                .collect()
+        };
+        if paths.is_empty() {
+            return;
+        }
+        let cache = Arc::clone(&self.cache);
+        runtime.spawn_blocking(move || {
+            for path in paths {
+                if let Some(file) = read_candidate(&path, SystemTime::now())
+                    && let Ok(mut cache) = cache.lock()
+                {
+                    cache.insert(path, file);
+                }
+            }
+        });
+    }
+
+    /// Reads `path` as UTF-8, from the cache when the file is unchanged.
+    pub fn read_to_string(&self, path: &Path) -> std::io::Result<String> {
+        if let Some(contents) = self.cached(path)
+            && let Ok(text) = String::from_utf8(contents.to_vec())
+        {
+            return Ok(text);
+        }
+        std::fs::read_to_string(path)
+    }
+
+    fn cached(&self, path: &Path) -> Option<Arc<Vec<u8>>> {
+        let (contents, stamp) = {
+            let cache = self.cache.lock().ok()?;
+            let file = cache.entries.get(path)?;
+            (Arc::clone(&file.contents), file.stamp)
+        };
+        let metadata = std::fs::metadata(path).ok()?;
+        (FileStamp::of(&metadata) == stamp).then_some(contents)
+    }
+}
+
+/// Reads `path` for the cache unless it is too large or changed too close to
+/// `now` for its stamp to tell later writes apart.
+fn read_candidate(path: &Path, now: SystemTime) -> Option<CachedFile> {
+    let metadata = std::fs::metadata(path).ok()?;
+    if !metadata.is_file() || metadata.len() > MAX_FILE_BYTES {
+        return None;
+    }
+    let stamp = FileStamp::of(&metadata);
+    if stamp.is_racy(now) {
+        return None;
+    }
+    let contents = std::fs::read(path).ok()?;
+    (contents.len() as u64 == stamp.len).then(|| CachedFile {
+        stamp,
+        contents: Arc::new(contents),
+    })
+}
+
+/// Path-like tokens in `text` resolved against `cwd`, in order of first
+/// mention. Only relative paths without `..` and absolute paths inside `cwd`
+/// are kept; `path:line:col` suffixes are dropped.
+fn referenced_paths(text: &str, cwd: &Path) -> Vec<PathBuf> {
+    let mut seen = HashSet::new();
+    text.split(|c: char| c.is_whitespace() || "`\"'()[]{}<>,;|\\=".contains(c))
+        .filter_map(|token| {
+            let token = token.trim_end_matches(['.', ':', '!', '?']);
+            let token = token.split(':').next().unwrap_or_default();
+            if !looks_like_path(token) {
+                return None;
+            }
+            let path = Path::new(token);
+            let resolved = if path.is_absolute() {
+                path.starts_with(cwd).then(|| path.to_path_buf())?
+            } else {
+                cwd.join(path)
+            };
+            let escapes = resolved
+                .components()
+                .any(|component| component == Component::ParentDir);
+            (!escapes).then_some(resolved)
+        })
+        .filter(|path| seen.insert(path.clone()))
+        .collect()
+}
+
+/// A token with a directory separator or a short alphanumeric extension.
+fn looks_like_path(token: &str) -> bool {
+    if token.is_empty() || token.starts_with('-') {
+        return false;
+    }
+    let name = token.rsplit('/').next().unwrap_or(token);
+    let has_extension = name.rsplit_once('.').is_some_and(|(stem, ext)| {
+        !stem.is_empty()
+            && (1..=8).contains(&ext.len())
+            && ext.chars().all(|c| c.is_ascii_alphanumeric())
+            && ext.chars().any(|c| c.is_ascii_alphabetic())
+    });
+    has_extension || (token.contains('/') && !name.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn extracts_paths_from_prose_and_tool_output() {
+        let cwd = Path::new("/repo");
+        let text = "Look at `src/lib.rs:42:7` and core/mod.rs, then run \
+                    {\"command\":[\"bash\",\"-lc\",\"sed -n 1,80p docs/guide.md\"]}. \
+                    Ignore ../secret.txt, https://example.com/a.html, v1.2 and /etc/passwd. \
+                    src/lib.rs again.";
+        assert_eq!(
+            referenced_paths(text, cwd),
+            vec![
+                PathBuf::from("/repo/src/lib.rs"),
+                PathBuf::from("/repo/core/mod.rs"),
+                PathBuf::from("/repo/docs/guide.md"),
+            ]
+        );
+    }
+
+    /// Late enough that files written by the test are past [`RACY_WINDOW`].
+    fn later() -> SystemTime {
+        SystemTime::now() + Duration::from_secs(60)
+    }
+
+    #[tokio::test]
+    async fn serves_cached_contents_until_the_file_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.md");
+        std::fs::write(&path, "first").unwrap();
+        let prefetcher = ContextPrefetcher::new(dir.path().to_path_buf());
+
+        let file = read_candidate(&path, later()).unwrap();
+        prefetcher.cache.lock().unwrap().insert(path.clone(), file);
+        assert_eq!(
+            prefetcher.cached(&path).as_deref(),
+            Some(&b"first".to_vec())
+        );
+
+        std::fs::write(&path, "second!").unwrap();
+        assert_eq!(prefetcher.cached(&path), None);
+        assert_eq!(prefetcher.read_to_string(&path).unwrap(), "second!");
+    }
+
+    #[test]
+    fn recently_changed_files_are_not_cached() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.md");
+        std::fs::write(&path, "first").unwrap();
+
+        assert!(read_candidate(&path, SystemTime::now()).is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn same_size_replacement_with_the_old_mtime_is_a_miss() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.md");
+        std::fs::write(&path, "first").unwrap();
+        let prefetcher = ContextPrefetcher::new(dir.path().to_path_buf());
+        let file = read_candidate(&path, later()).unwrap();
+        let modified = file.stamp.modified.unwrap();
+        prefetcher.cache.lock().unwrap().insert(path.clone(), file);
+
+        let replacement = dir.path().join("notes.md.tmp");
+        std::fs::write(&replacement, "fir5t").unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(&replacement)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+        std::fs::rename(&replacement, &path).unwrap();
+
+        assert_eq!(prefetcher.cached(&path), None);
+        assert_eq!(prefetcher.read_to_string(&path).unwrap(), "fir5t");
+    }
+}
@@ -1,7 +1,9 @@
 //! Project-level documentation discovery.
 //!
 //! Project-level documentation can be stored in files named `AGENTS.md` and
-//! Auto Drive can use specialised guidance stored in `AUTO_AGENTS.md`.
+//! Auto Drive can use specialised guidance stored in `AUTO_AGENTS.md`; the
+//! Auto Drive coordinator prompt can be extended or replaced by
+//! `AUTO_COORDINATOR.md`.
 //! We include the concatenation of all files found along the path from the
 //! repository root to the current working directory as follows:
 //!
@@ -24,6 +26,9 @@ const AGENT_FILENAMES: &[&str] = &["AGENTS.md"];
 /// Filenames recognised for Auto Drive instructions.
 const AUTO_AGENT_FILENAMES: &[&str] = &["AUTO_AGENTS.md"];
 
+/// Filenames recognised for project-level Auto Drive coordinator prompts.
+const AUTO_COORDINATOR_FILENAMES: &[&str] = &["AUTO_COORDINATOR.md"];
+
 /// When both `Config::instructions` and the project doc are present, they will
 /// be concatenated with the following separator.
 const PROJECT_DOC_SEPARATOR: &str = "\n\n--- project-doc ---\n\n";
@@ -199,6 +204,12 @@ pub fn discover_auto_drive_doc_paths(config: &Config) -> std::io::Result<Vec<Pat
     discover_project_doc_paths_with_candidates(config, AUTO_AGENT_FILENAMES)
 }
 
+/// `AUTO_COORDINATOR.md` files from the repository root down to the current
+/// working directory.
+pub fn discover_coordinator_prompt_paths(config: &Config) -> std::io::Result<Vec<PathBuf>> {
+    discover_project_doc_paths_with_candidates(config, AUTO_COORDINATOR_FILENAMES)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,102 @@
+//! Provider-side prompt caching: the `prompt_cache_key` sent with Responses
+//! API requests and a process-wide tally of how much input was served from
+//! the cache.
+//!
+//! Each session reports its own cache hit rate through the cumulative
+//! `TokenUsageInfo`; [`global_usage`] adds up every session in the process
+//! so frontends can show a running rate across sessions as well.
+
+use std::path::Path;
+use std::sync::Mutex;
+
+use ring::digest;
+use uuid::Uuid;
+
+use crate::config_types::PromptCacheKeyStrategy;
+use crate::git_info::resolve_root_git_project_for_trust;
+use crate::protocol::TokenUsage;
+
+static GLOBAL_USAGE: Mutex<TokenUsage> = Mutex::new(TokenUsage {
+    input_tokens: 0,
+    cached_input_tokens: 0,
+    output_tokens: 0,
+    reasoning_output_tokens: 0,
+    total_tokens: 0,
+});
+
+/// The key to send for a request, or `None` when keys are disabled. `repo`
+/// falls back to the session id outside a git repository.
+pub(crate) fn prompt_cache_key(
+    strategy: PromptCacheKeyStrategy,
+    session_id: Uuid,
+    cwd: &Path,
+) -> Option<String> {
+    match strategy {
+        PromptCacheKeyStrategy::Session => Some(session_id.to_string()),
+        PromptCacheKeyStrategy::Repo => Some(
+            resolve_root_git_project_for_trust(cwd)
+                .map(|root| repo_key(&root))
+                .unwrap_or_else(|| session_id.to_string()),
+        ),
+        PromptCacheKeyStrategy::Disabled => None,
+    }
+}
+
+/// Stable key for a repository root that does not reveal its path.
+fn repo_key(root: &Path) -> String {
+    let hash = digest::digest(&digest::SHA256, root.to_string_lossy().as_bytes());
+    let hex: String = hash.as_ref()[..16]
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    format!("repo-{hex}")
+}
+
+/// Adds one response's usage to the process-wide tally.
+pub(crate) fn record_usage(usage: &TokenUsage) {
+    if let Ok(mut total) = GLOBAL_USAGE.lock() {
+        total.add_assign(usage);
+    }
+}
+
+/// Usage of every session in this process so far.
+pub fn global_usage() -> TokenUsage {
+    GLOBAL_USAGE
+        .lock()
+        .map(|total| total.clone())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn strategies_choose_the_expected_key() {
+        let session_id = Uuid::new_v4();
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(
+            prompt_cache_key(PromptCacheKeyStrategy::Session, session_id, dir.path()),
+            Some(session_id.to_string())
+        );
+        assert_eq!(
+            prompt_cache_key(PromptCacheKeyStrategy::Disabled, session_id, dir.path()),
+            None
+        );
+        // Outside a repository the repo strategy keeps the session id.
+        assert_eq!(
+            prompt_cache_key(PromptCacheKeyStrategy::Repo, session_id, dir.path()),
+            Some(session_id.to_string())
+        );
+    }
+
+    #[test]
+    fn repo_key_is_stable_and_opaque() {
+        let root = Path::new("/work/project");
+        assert_eq!(repo_key(root), repo_key(root));
+        assert_ne!(repo_key(root), repo_key(Path::new("/work/other")));
+        assert!(!repo_key(root).contains("project"));
+        assert_eq!(repo_key(root).len(), "repo-".len() + 32);
+    }
+}
@@ -3,6 +3,7 @@
 //! Uses a SQ (Submission Queue) / EQ (Event Queue) pattern to asynchronously communicate
 //! between user and agent.
 
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::fmt;
 use std::path::Path;
@@ -21,6 +22,7 @@ use uuid::Uuid;
 use crate::client_common::TextFormat;
 use crate::config_types::ReasoningEffort as ReasoningEffortConfig;
 use crate::config_types::ReasoningSummary as ReasoningSummaryConfig;
+use crate::config_types::SamplingParams;
 use crate::config_types::TextVerbosity as TextVerbosityConfig;
 use crate::message_history::HistoryEntry;
 use crate::model_provider_info::ModelProviderInfo;
@@ -29,6 +31,7 @@ use crate::plan_tool::UpdatePlanArgs;
 
 // Re-export review types from the shared protocol crate so callers can use
 // `code_core::protocol::ReviewFinding` and friends.
+pub use code_protocol::protocol::CancelledToolCall;
 pub use code_protocol::protocol::ConversationPathResponseEvent;
 pub use code_protocol::protocol::ENVIRONMENT_CONTEXT_OPEN_TAG;
 pub use code_protocol::protocol::ExitedReviewModeEvent;
@@ -143,6 +146,13 @@ pub enum Op {
     /// Set a one-off text format to apply on the next turn.
     SetNextTextFormat { format: TextFormat },
 
+    /// Run the next turn on `model` instead of the session model.
+    SetNextTurnModel { model: String },
+
+    /// Use these sampling settings for the next turn's requests; unset
+    /// values fall back to `[sampling]`.
+    SetNextTurnSampling { sampling: SamplingParams },
+
     /// Approve a command execution
     ExecApproval {
         /// The id of the submission we are approving
@@ -205,6 +215,10 @@ pub enum Op {
     /// Reply is delivered via `EventMsg::ListCustomPromptsResponse`.
     ListCustomPrompts,
 
+    /// Request the health of every model provider endpoint used in this
+    /// process. Reply is delivered via `EventMsg::ProviderHealth`.
+    GetProviderHealth,
+
     /// Request the agent to summarize the current conversation context.
     /// The agent will use its existing context (either conversation history or previous response id)
     /// to generate a summary which will be returned as an AgentMessage event.
@@ -568,6 +582,28 @@ pub fn recorded_event_from_protocol(
     })
 }
 
+/// Rollout record of the settings a turn ran under and the provider and
+/// model that served it.
+pub(crate) fn turn_context_item(
+    cwd: &Path,
+    approval_policy: AskForApproval,
+    sandbox_policy: &SandboxPolicy,
+    model: String,
+    model_provider: String,
+    effort: ReasoningEffortConfig,
+    summary: ReasoningSummaryConfig,
+) -> Option<code_protocol::protocol::TurnContextItem> {
+    Some(code_protocol::protocol::TurnContextItem {
+        cwd: cwd.to_path_buf(),
+        approval_policy: convert_value(&approval_policy)?,
+        sandbox_policy: convert_value(sandbox_policy)?,
+        model,
+        effort: convert_value(&effort),
+        summary: convert_value(&summary)?,
+        model_provider: Some(model_provider),
+    })
+}
+
 fn convert_value<T, U>(value: &T) -> Option<U>
 where
     T: Serialize,
@@ -758,6 +794,13 @@ pub enum EventMsg {
     /// List of custom prompts available to the agent.
     ListCustomPromptsResponse(ListCustomPromptsResponseEvent),
 
+    /// Response to `GetProviderHealth`.
+    ProviderHealth(ProviderHealthEvent),
+
+    /// Timings for one model request; sent after it completes when
+    /// `turn_latency_events` is enabled.
+    TurnLatency(TurnLatencyEvent),
+
     PlanUpdate(UpdatePlanArgs),
 
     /// Browser screenshot has been captured and is ready for display
@@ -829,6 +872,14 @@ impl TokenUsage {
         self.non_cached_input() + self.output_tokens
     }
 
+    /// Share of input tokens served from the provider's prompt cache, or
+    /// `None` before any input was sent.
+    pub fn cache_hit_rate(&self) -> Option<f64> {
+        (self.input_tokens > 0).then(|| {
+            self.cached_input_tokens.min(self.input_tokens) as f64 / self.input_tokens as f64
+        })
+    }
+
     /// For estimating what % of the model's context window is used, we need to account
     /// for reasoning output tokens from prior turns being dropped from the context window.
     /// We approximate this here by subtracting reasoning output tokens from the total.
@@ -1108,6 +1159,10 @@ pub struct ExecCommandEndEvent {
     pub exit_code: i32,
     /// The duration of the command execution.
     pub duration: Duration,
+    /// Core concluded the sandbox blocked the command, as opposed to the
+    /// command failing on its own.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub sandbox_denied: bool,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -1343,6 +1398,84 @@ pub struct EnvironmentContextDeltaEvent {
     pub base_fingerprint: Option<String>,
 }
 
+/// Payload for `ProviderHealth`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ProviderHealthEvent {
+    pub endpoints: Vec<ProviderEndpointHealth>,
+}
+
+/// Payload for `TurnLatency`.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct TurnLatencyEvent {
+    /// `model_providers` key of the provider that served the request.
+    pub provider: String,
+    pub model: String,
+    /// Time until the response headers arrived, including any retries.
+    pub ttfb_ms: u64,
+    /// Time until the first streamed output: text, reasoning, or a tool
+    /// call.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_token_ms: Option<u64>,
+    /// Time until the response completed.
+    pub duration_ms: u64,
+    /// Failed attempts that were retried before the request was accepted.
+    pub retries: u64,
+}
+
+impl std::fmt::Display for TurnLatencyEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}/{}: first byte {} ms",
+            self.provider, self.model, self.ttfb_ms
+        )?;
+        if let Some(first_token_ms) = self.first_token_ms {
+            write!(f, ", first token {first_token_ms} ms")?;
+        }
+        write!(f, ", done in {} ms", self.duration_ms)?;
+        match self.retries {
+            0 => Ok(()),
+            1 => write!(f, ", 1 retry"),
+            retries => write!(f, ", {retries} retries"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ProviderEndpointHealth {
+    /// Provider display name.
+    pub provider: String,
+    pub endpoint: String,
+    pub circuit: CircuitState,
+    pub consecutive_failures: u32,
+    pub requests: u64,
+    pub failures: u64,
+    /// Failure counts keyed by error class (`timeout`, `connection`,
+    /// `server`, `stream`).
+    pub errors_by_class: BTreeMap<String, u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    /// Moving average of the time to the first streamed event.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+    /// Time left before an open circuit lets requests through again.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_in_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Display)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum CircuitState {
+    /// Requests flow normally.
+    Closed,
+    /// Requests fail fast (or go to the fallback provider).
+    Open,
+    /// The cooldown has passed; the next request decides whether the
+    /// circuit closes or opens again.
+    HalfOpen,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct BrowserSnapshotEvent {
     /// JSON serialization of the browser snapshot metadata.
@@ -0,0 +1,359 @@
+//! Process-wide health of model provider endpoints, with a circuit breaker.
+//!
+//! Every `ModelClient` records its request outcomes here, keyed by endpoint
+//! URL, so conversations and agents that share a provider also share what
+//! is known about it. Only provider faults count: timeouts, connection
+//! errors, 5xx responses, and streams that break off. Rate limits are the
+//! quota ledger's business, and client errors or content blocks say nothing
+//! about the provider.
+//!
+//! After [`FAILURE_THRESHOLD`] consecutive faulty requests the endpoint's
+//! circuit opens for [`BASE_COOLDOWN`], doubling each time it reopens up to
+//! [`MAX_COOLDOWN`]. While it is open, requests fail fast with
+//! [`CodexErr::ProviderUnavailable`] (or go to the provider's
+//! `fallback_provider`) instead of spending the whole retry budget. Once the
+//! cooldown passes the circuit is half-open: the next success closes it and
+//! the next failure opens it again.
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::OnceLock;
+use std::time::Duration;
+use std::time::Instant;
+
+use tokio::sync::mpsc;
+
+use crate::client_common::ResponseEvent;
+use crate::client_common::ResponseStream;
+use crate::error::CodexErr;
+use crate::error::ProviderUnavailableError;
+use crate::error::Result;
+use crate::protocol::CircuitState;
+use crate::protocol::ProviderEndpointHealth;
+use crate::sse_buffer::SSE_CHANNEL_CAPACITY;
+
+/// Consecutive faulty requests that open the circuit.
+const FAILURE_THRESHOLD: u32 = 3;
+/// How long the circuit stays open the first time it trips.
+const BASE_COOLDOWN: Duration = Duration::from_secs(30);
+const MAX_COOLDOWN: Duration = Duration::from_secs(300);
+/// Weight of the newest sample in the latency moving average.
+const LATENCY_WEIGHT: f64 = 0.2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum ErrorClass {
+    Timeout,
+    Connection,
+    Server,
+    Stream,
+}
+
+impl ErrorClass {
+    fn as_str(self) -> &'static str {
+        match self {
+            ErrorClass::Timeout => "timeout",
+            ErrorClass::Connection => "connection",
+            ErrorClass::Server => "server",
+            ErrorClass::Stream => "stream",
+        }
+    }
+}
+
+/// The class of `err` when it is the provider's fault.
+fn classify(err: &CodexErr) -> Option<ErrorClass> {
+    match err {
+        CodexErr::Stream(message, None, _) if message.starts_with("[idle]") => {
+            Some(ErrorClass::Timeout)
+        }
+        CodexErr::Stream(message, None, _) if message.starts_with("[transport]") => {
+            Some(ErrorClass::Connection)
+        }
+        // A retry hint means the provider is throttling, not failing.
+        CodexErr::Stream(_, Some(_), _) => None,
+        CodexErr::Stream(..) => Some(ErrorClass::Stream),
+        CodexErr::Reqwest(err) if err.is_timeout() => Some(ErrorClass::Timeout),
+        CodexErr::Reqwest(_) => Some(ErrorClass::Connection),
+        CodexErr::ServerError(_) => Some(ErrorClass::Server),
+        CodexErr::RetryLimit(err) if err.status.is_server_error() => Some(ErrorClass::Server),
+        CodexErr::UnexpectedStatus(err) if err.status.is_server_error() => Some(ErrorClass::Server),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Default)]
+struct EndpointHealth {
+    provider: String,
+    consecutive_failures: u32,
+    requests: u64,
+    failures: u64,
+    errors_by_class: BTreeMap<ErrorClass, u64>,
+    last_error: Option<String>,
+    latency_ms: Option<f64>,
+    /// Set while the circuit is open or half-open.
+    open_until: Option<Instant>,
+    /// Times the circuit has opened since it last closed.
+    trips: u32,
+}
+
+impl EndpointHealth {
+    fn state(&self, now: Instant) -> CircuitState {
+        match self.open_until {
+            None => CircuitState::Closed,
+            Some(until) if until > now => CircuitState::Open,
+            Some(_) => CircuitState::HalfOpen,
+        }
+    }
+
+    fn record_success(&mut self, latency: Duration) {
+        self.requests += 1;
+        self.consecutive_failures = 0;
+        self.open_until = None;
+        self.trips = 0;
+        let sample = latency.as_secs_f64() * 1_000.0;
+        self.latency_ms = Some(self.latency_ms.map_or(sample, |average| {
+            average + LATENCY_WEIGHT * (sample - average)
+        }));
+    }
+
+    fn record_failure(&mut self, class: ErrorClass, message: String, now: Instant) {
+        self.requests += 1;
+        self.failures += 1;
+        self.consecutive_failures += 1;
+        *self.errors_by_class.entry(class).or_default() += 1;
+        self.last_error = Some(message);
+
+        let trips = match self.state(now) {
+            CircuitState::Closed => self.consecutive_failures >= FAILURE_THRESHOLD,
+            CircuitState::HalfOpen => true,
+            // Requests that were already in flight when the circuit opened.
+            CircuitState::Open => false,
+        };
+        if trips {
+            let cooldown = BASE_COOLDOWN
+                .saturating_mul(1 << self.trips.min(4))
+                .min(MAX_COOLDOWN);
+            self.trips += 1;
+            self.open_until = Some(now + cooldown);
+        }
+    }
+
+    fn snapshot(&self, endpoint: &str, now: Instant) -> ProviderEndpointHealth {
+        ProviderEndpointHealth {
+            provider: self.provider.clone(),
+            endpoint: endpoint.to_string(),
+            circuit: self.state(now),
+            consecutive_failures: self.consecutive_failures,
+            requests: self.requests,
+            failures: self.failures,
+            errors_by_class: self
+                .errors_by_class
+                .iter()
+                .map(|(class, count)| (class.as_str().to_string(), *count))
+                .collect(),
+            last_error: self.last_error.clone(),
+            latency_ms: self.latency_ms.map(|ms| ms.round() as u64),
+            retry_in_ms: self
+                .open_until
+                .filter(|until| *until > now)
+                .map(|until| (until - now).as_millis() as u64),
+        }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct ProviderHealth {
+    endpoints: Mutex<HashMap<String, EndpointHealth>>,
+}
+
+/// The registry shared by every model client in this process.
+pub(crate) fn global() -> &'static ProviderHealth {
+    static HEALTH: OnceLock<ProviderHealth> = OnceLock::new();
+    HEALTH.get_or_init(ProviderHealth::default)
+}
+
+impl ProviderHealth {
+    /// Fails with [`CodexErr::ProviderUnavailable`] while the circuit for
+    /// `endpoint` is open.
+    pub fn check(&self, provider: &str, endpoint: &str) -> Result<()> {
+        let now = Instant::now();
+        let endpoints = self.lock();
+        let Some(health) = endpoints.get(endpoint) else {
+            return Ok(());
+        };
+        match health.open_until {
+            Some(until) if until > now => {
+                Err(CodexErr::ProviderUnavailable(ProviderUnavailableError {
+                    provider: provider.to_string(),
+                    consecutive_failures: health.consecutive_failures,
+                    retry_in: until - now,
+                    last_error: health.last_error.clone(),
+                }))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn record_success(&self, provider: &str, endpoint: &str, latency: Duration) {
+        self.entry(provider, endpoint, |health| health.record_success(latency));
+    }
+
+    /// Counts `err` against `endpoint` when it is a provider fault.
+    pub fn record_error(&self, provider: &str, endpoint: &str, err: &CodexErr) {
+        let Some(class) = classify(err) else {
+            return;
+        };
+        let now = Instant::now();
+        self.entry(provider, endpoint, |health| {
+            health.record_failure(class, err.to_string(), now);
+        });
+    }
+
+    /// Every endpoint seen so far, ordered by endpoint.
+    pub fn snapshot(&self) -> Vec<ProviderEndpointHealth> {
+        let now = Instant::now();
+        let endpoints = self.lock();
+        let mut snapshot: Vec<_> = endpoints
+            .iter()
+            .map(|(endpoint, health)| health.snapshot(endpoint, now))
+            .collect();
+        snapshot.sort_by(|a, b| a.endpoint.cmp(&b.endpoint));
+        snapshot
+    }
+
+    fn entry(&self, provider: &str, endpoint: &str, update: impl FnOnce(&mut EndpointHealth)) {
+        let mut endpoints = self.lock();
+        let health = endpoints.entry(endpoint.to_string()).or_default();
+        health.provider = provider.to_string();
+        update(health);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, EndpointHealth>> {
+        self.endpoints
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+/// Passes `stream` through, recording a success with the time from
+/// `started` to the first event once the response completes, or the first
+/// error it carries.
+pub(crate) fn watch(
+    provider: String,
+    endpoint: String,
+    started: Instant,
+    mut stream: ResponseStream,
+) -> ResponseStream {
+    let retries = stream.retries;
+    let (tx_event, rx_event) = mpsc::channel::<Result<ResponseEvent>>(SSE_CHANNEL_CAPACITY);
+    tokio::spawn(async move {
+        let mut first_event = None;
+        let mut recorded = false;
+        while let Some(event) = stream.rx_event.recv().await {
+            let latency = *first_event.get_or_insert_with(|| started.elapsed());
+            if !recorded {
+                match &event {
+                    Ok(ResponseEvent::Completed { .. }) => {
+                        global().record_success(&provider, &endpoint, latency);
+                        recorded = true;
+                    }
+                    Err(err) => {
+                        global().record_error(&provider, &endpoint, err);
+                        recorded = true;
+                    }
+                    Ok(_) => {}
+                }
+            }
+            if tx_event.send(event).await.is_err() {
+                return;
+            }
+        }
+    });
+    ResponseStream {
+        rx_event,
+        served_by: None,
+        retries,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn server_error() -> (ErrorClass, String) {
+        (ErrorClass::Server, "502 Bad Gateway".to_string())
+    }
+
+    #[test]
+    fn opens_after_consecutive_failures_and_closes_on_success() {
+        let mut health = EndpointHealth::default();
+        let now = Instant::now();
+        for _ in 0..FAILURE_THRESHOLD - 1 {
+            let (class, message) = server_error();
+            health.record_failure(class, message, now);
+        }
+        assert_eq!(health.state(now), CircuitState::Closed);
+
+        let (class, message) = server_error();
+        health.record_failure(class, message, now);
+        assert_eq!(health.state(now), CircuitState::Open);
+        let after_cooldown = now + BASE_COOLDOWN;
+        assert_eq!(health.state(after_cooldown), CircuitState::HalfOpen);
+
+        // A failed probe reopens the circuit for twice as long.
+        let (class, message) = server_error();
+        health.record_failure(class, message, after_cooldown);
+        assert_eq!(health.open_until, Some(after_cooldown + BASE_COOLDOWN * 2));
+
+        health.record_success(Duration::from_millis(400));
+        let snapshot = health.snapshot("https://api.example.com/v1/responses", now);
+        assert_eq!(snapshot.circuit, CircuitState::Closed);
+        assert_eq!(snapshot.consecutive_failures, 0);
+        assert_eq!(snapshot.requests, 5);
+        assert_eq!(snapshot.failures, 4);
+        assert_eq!(
+            snapshot.errors_by_class,
+            BTreeMap::from([("server".to_string(), 4)])
+        );
+        assert_eq!(snapshot.latency_ms, Some(400));
+        assert_eq!(snapshot.retry_in_ms, None);
+    }
+
+    #[test]
+    fn only_provider_faults_count() {
+        let idle = CodexErr::Stream("[idle] timeout waiting for SSE".to_string(), None, None);
+        let throttled = CodexErr::Stream(
+            "rate limited".to_string(),
+            Some(crate::error::RetryAfter::from_duration(
+                Duration::from_secs(5),
+                chrono::Utc::now(),
+            )),
+            None,
+        );
+        assert_eq!(classify(&idle), Some(ErrorClass::Timeout));
+        assert_eq!(classify(&throttled), None);
+        assert_eq!(classify(&CodexErr::QuotaExceeded), None);
+        assert_eq!(
+            classify(&CodexErr::ContentBlocked("SAFETY".to_string())),
+            None
+        );
+
+        let registry = ProviderHealth::default();
+        let endpoint = "https://api.example.com/v1/responses";
+        for _ in 0..FAILURE_THRESHOLD {
+            registry.record_error("Example", endpoint, &throttled);
+        }
+        assert!(registry.check("Example", endpoint).is_ok());
+        for _ in 0..FAILURE_THRESHOLD {
+            registry.record_error("Example", endpoint, &idle);
+        }
+        match registry.check("Example", endpoint) {
+            Err(CodexErr::ProviderUnavailable(err)) => {
+                assert_eq!(err.consecutive_failures, FAILURE_THRESHOLD);
+                assert!(err.retry_in <= BASE_COOLDOWN);
+            }
+            other => panic!("expected an open circuit, got {other:?}"),
+        }
+    }
+}
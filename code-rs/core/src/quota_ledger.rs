@@ -0,0 +1,422 @@
+//! Process-wide admission control for model requests that share an account.
+//!
+//! Agents, batch exec runs, and the Auto Drive coordinator all stream through
+//! the same account. Each request first reserves its estimated token count in
+//! the ledger. The ledger learns the remaining token allowance and reset time
+//! from rate-limit response headers, and 429s or usage-limit errors block the
+//! account until the advertised reset. A request that would overrun the
+//! remaining allowance waits for in-flight requests to settle or for the
+//! window to reset. If the wait would be longer than [`MAX_ADMISSION_WAIT`],
+//! the request is rejected with [`CodexErr::UsageLimitReached`] instead of
+//! being sent only to come back as a 429.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::OnceLock;
+use std::time::Duration;
+use std::time::Instant;
+
+use reqwest::header::HeaderMap;
+use tokio::sync::Notify;
+
+use crate::client_common::Prompt;
+use crate::error::CodexErr;
+use crate::error::Result;
+use crate::error::UsageLimitReachedError;
+use crate::model_family::ModelFamily;
+use crate::tokenizer::Tokenizer;
+
+/// Longest a request waits for admission before it is rejected.
+const MAX_ADMISSION_WAIT: Duration = Duration::from_secs(60);
+/// How often a waiting request re-checks while others are in flight.
+const RECHECK_INTERVAL: Duration = Duration::from_secs(1);
+/// Longest block or reset a provider header can impose on an account.
+const MAX_HOLD: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Debug, Default)]
+struct AccountQuota {
+    /// Tokens the provider reported as remaining in the current window.
+    remaining_tokens: Option<u64>,
+    /// When `remaining_tokens` stops applying.
+    resets_at: Option<Instant>,
+    /// Set by a 429 or an exhausted usage window.
+    blocked_until: Option<Instant>,
+    /// Tokens reserved by requests whose response headers are still pending.
+    reserved: u64,
+}
+
+enum Admission {
+    Admit,
+    /// Retry after the given delay (or sooner if a reservation is released).
+    Wait(Duration),
+}
+
+impl AccountQuota {
+    fn admission(&mut self, estimate: u64, now: Instant) -> Admission {
+        if let Some(until) = self.blocked_until {
+            if until > now {
+                return Admission::Wait(until - now);
+            }
+            self.blocked_until = None;
+        }
+        if self.resets_at.is_some_and(|reset| reset <= now) {
+            self.remaining_tokens = None;
+            self.resets_at = None;
+        }
+        let Some(remaining) = self.remaining_tokens else {
+            return Admission::Admit;
+        };
+        if self.reserved.saturating_add(estimate) <= remaining {
+            return Admission::Admit;
+        }
+        let until_reset = self.resets_at.map(|reset| reset - now);
+        if self.reserved > 0 {
+            // In-flight requests may report a fresher (larger) allowance.
+            return Admission::Wait(
+                until_reset.map_or(RECHECK_INTERVAL, |wait| wait.min(RECHECK_INTERVAL)),
+            );
+        }
+        match until_reset {
+            Some(wait) => Admission::Wait(wait),
+            // Nothing to wait on; let the provider decide.
+            None => Admission::Admit,
+        }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct QuotaLedger {
+    accounts: Mutex<HashMap<String, AccountQuota>>,
+    released: Notify,
+}
+
+/// The ledger shared by every conversation in this process.
+pub(crate) fn global() -> &'static QuotaLedger {
+    static LEDGER: OnceLock<QuotaLedger> = OnceLock::new();
+    LEDGER.get_or_init(QuotaLedger::default)
+}
+
+/// Ledger key for requests billed to the same provider account.
+pub(crate) fn account_key(provider_name: &str, account_id: Option<&str>) -> String {
+    format!("{provider_name}:{}", account_id.unwrap_or_default())
+}
+
+/// Estimated tokens for the instructions and input `prompt` sends to
+/// `model_slug`, counted with the model's tokenizer. Encoding a long
+/// conversation takes a while, so it runs on the blocking pool; callers count
+/// once per request rather than once per retry.
+pub(crate) async fn estimate_tokens(
+    model_slug: &str,
+    prompt: &Prompt,
+    model_family: &ModelFamily,
+) -> u64 {
+    let tokenizer = Tokenizer::for_model(model_slug);
+    let instructions = prompt.get_full_instructions(model_family).into_owned();
+    let input = prompt.get_formatted_input();
+    let count = move || tokenizer.count(&instructions) + tokenizer.count_items(&input);
+    let tokens = if tokenizer == Tokenizer::Heuristic {
+        count()
+    } else {
+        tokio::task::spawn_blocking(count).await.unwrap_or_default()
+    };
+    tokens as u64
+}
+
+impl QuotaLedger {
+    /// Reserves `estimate` tokens for `key`, waiting while the ledger predicts
+    /// the request would exceed the account's limit.
+    pub async fn admit(&self, key: &str, estimate: u64) -> Result<Reservation<'_>> {
+        let deadline = Instant::now() + MAX_ADMISSION_WAIT;
+        loop {
+            let now = Instant::now();
+            let wait = {
+                let mut accounts = self.lock();
+                let account = accounts.entry(key.to_string()).or_default();
+                match account.admission(estimate, now) {
+                    Admission::Admit => {
+                        account.reserved += estimate;
+                        return Ok(Reservation {
+                            ledger: self,
+                            key: key.to_string(),
+                            tokens: estimate,
+                            released: false,
+                        });
+                    }
+                    Admission::Wait(wait) => wait,
+                }
+            };
+            if now + wait > deadline {
+                let resets_in_seconds = wait.as_secs() + u64::from(wait.subsec_nanos() > 0);
+                return Err(CodexErr::UsageLimitReached(UsageLimitReachedError {
+                    plan_type: None,
+                    resets_in_seconds: Some(resets_in_seconds),
+                }));
+            }
+            let _ = tokio::time::timeout(wait, self.released.notified()).await;
+        }
+    }
+
+    /// Holds new requests for `key` until `delay` has passed.
+    pub fn block_for(&self, key: &str, delay: Duration) {
+        let until = hold_until(Instant::now(), delay);
+        let mut accounts = self.lock();
+        let account = accounts.entry(key.to_string()).or_default();
+        if account.blocked_until.is_none_or(|current| current < until) {
+            account.blocked_until = Some(until);
+        }
+    }
+
+    /// Updates `key` from rate-limit response headers.
+    fn observe_headers(&self, key: &str, headers: &HeaderMap) {
+        let now = Instant::now();
+        let remaining = header_str(headers, "x-ratelimit-remaining-tokens")
+            .and_then(|value| value.parse::<u64>().ok());
+        let reset = header_str(headers, "x-ratelimit-reset-tokens").and_then(parse_reset_duration);
+        let exhausted_window = [
+            (
+                "x-codex-primary-used-percent",
+                "x-codex-primary-reset-after-seconds",
+            ),
+            (
+                "x-codex-secondary-used-percent",
+                "x-codex-secondary-reset-after-seconds",
+            ),
+        ]
+        .into_iter()
+        .filter(|(used, _)| {
+            header_str(headers, used)
+                .and_then(|value| value.parse::<f64>().ok())
+                .is_some_and(|percent| percent >= 100.0)
+        })
+        .filter_map(|(_, reset)| header_str(headers, reset)?.parse::<u64>().ok())
+        .max();
+
+        if remaining.is_none() && exhausted_window.is_none() {
+            return;
+        }
+        let mut accounts = self.lock();
+        let account = accounts.entry(key.to_string()).or_default();
+        if let Some(remaining) = remaining {
+            account.remaining_tokens = Some(remaining);
+            account.resets_at = reset.map(|reset| hold_until(now, reset));
+        }
+        if let Some(seconds) = exhausted_window {
+            let until = hold_until(now, Duration::from_secs(seconds));
+            if account.blocked_until.is_none_or(|current| current < until) {
+                account.blocked_until = Some(until);
+            }
+        }
+    }
+
+    fn release(&self, key: &str, tokens: u64) {
+        if let Some(account) = self.lock().get_mut(key) {
+            account.reserved = account.reserved.saturating_sub(tokens);
+        }
+        self.released.notify_waiters();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, AccountQuota>> {
+        self.accounts
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+/// Tokens held for one request attempt. Released once the response headers
+/// arrive (see [`Reservation::settle`]) or when dropped.
+pub(crate) struct Reservation<'a> {
+    ledger: &'a QuotaLedger,
+    key: String,
+    tokens: u64,
+    released: bool,
+}
+
+impl Reservation<'_> {
+    /// Records the response's rate-limit headers and releases the reservation.
+    pub fn settle(mut self, headers: &HeaderMap) {
+        self.ledger.observe_headers(&self.key, headers);
+        self.ledger.release(&self.key, self.tokens);
+        self.released = true;
+    }
+}
+
+impl Drop for Reservation<'_> {
+    fn drop(&mut self) {
+        if !self.released {
+            self.ledger.release(&self.key, self.tokens);
+        }
+    }
+}
+
+/// `now + delay`, with `delay` capped at [`MAX_HOLD`] so a huge header value
+/// neither overflows the clock nor blocks the account for good.
+fn hold_until(now: Instant, delay: Duration) -> Instant {
+    now.checked_add(delay.min(MAX_HOLD)).unwrap_or(now)
+}
+
+fn header_str<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name)?.to_str().ok()
+}
+
+/// Parses OpenAI reset durations such as `1s`, `6m0s`, `20ms`, or `1h2m3.5s`.
+fn parse_reset_duration(value: &str) -> Option<Duration> {
+    let mut total = Duration::ZERO;
+    let mut rest = value.trim();
+    if rest.is_empty() {
+        return None;
+    }
+    while !rest.is_empty() {
+        let split = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(rest.len());
+        let (number, tail) = rest.split_at(split);
+        let number: f64 = number.parse().ok()?;
+        let unit_len = tail
+            .find(|c: char| c.is_ascii_digit() || c == '.')
+            .unwrap_or(tail.len());
+        let (unit, tail) = tail.split_at(unit_len);
+        let seconds = match unit {
+            "h" => number * 3_600.0,
+            "m" => number * 60.0,
+            "s" | "" => number,
+            "ms" => number / 1_000.0,
+            _ => return None,
+        };
+        total = total.checked_add(Duration::try_from_secs_f64(seconds).ok()?)?;
+        rest = tail;
+    }
+    Some(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model_family::find_family_for_model;
+    use code_protocol::models::ContentItem;
+    use code_protocol::models::ResponseItem;
+    use pretty_assertions::assert_eq;
+    use reqwest::header::HeaderValue;
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn parses_reset_durations() {
+        assert_eq!(parse_reset_duration("1s"), Some(Duration::from_secs(1)));
+        assert_eq!(parse_reset_duration("6m0s"), Some(Duration::from_secs(360)));
+        assert_eq!(
+            parse_reset_duration("20ms"),
+            Some(Duration::from_millis(20))
+        );
+        assert_eq!(
+            parse_reset_duration("1h2m3.5s"),
+            Some(Duration::from_millis(3_723_500))
+        );
+        assert_eq!(parse_reset_duration("soon"), None);
+        assert_eq!(
+            parse_reset_duration("18446744073709551615s18446744073709551615s"),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn huge_resets_and_blocks_are_capped() {
+        let ledger = QuotaLedger::default();
+        ledger.block_for("openai:acct", Duration::MAX);
+        ledger.admit("codex:", 1).await.unwrap().settle(&headers(&[
+            ("x-codex-primary-used-percent", "100.0"),
+            (
+                "x-codex-primary-reset-after-seconds",
+                "18446744073709551615",
+            ),
+        ]));
+
+        let err = ledger.admit("openai:acct", 1).await.err().unwrap();
+        assert!(matches!(
+            err,
+            CodexErr::UsageLimitReached(UsageLimitReachedError {
+                resets_in_seconds: Some(seconds),
+                ..
+            }) if seconds <= MAX_HOLD.as_secs()
+        ));
+        assert!(ledger.admit("codex:", 1).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn estimates_instructions_and_input() {
+        let family = find_family_for_model("gpt-5").expect("known model slug");
+        let mut prompt = Prompt::default();
+        let empty = estimate_tokens("gpt-5", &prompt, &family).await;
+        prompt.input.push(ResponseItem::Message {
+            id: None,
+            role: "user".to_string(),
+            content: vec![ContentItem::InputText {
+                text: "hello world".to_string(),
+            }],
+        });
+        assert_eq!(estimate_tokens("gpt-5", &prompt, &family).await, empty + 2);
+    }
+
+    #[tokio::test]
+    async fn waits_for_in_flight_requests_before_overrunning_the_allowance() {
+        let ledger = QuotaLedger::default();
+        let first = ledger.admit("openai:", 100).await.unwrap();
+        first.settle(&headers(&[
+            ("x-ratelimit-remaining-tokens", "1000"),
+            ("x-ratelimit-reset-tokens", "30s"),
+        ]));
+
+        let in_flight = ledger.admit("openai:", 800).await.unwrap();
+        let waiting = ledger.admit("openai:", 400);
+        tokio::pin!(waiting);
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), &mut waiting)
+                .await
+                .is_err()
+        );
+
+        in_flight.settle(&headers(&[
+            ("x-ratelimit-remaining-tokens", "900"),
+            ("x-ratelimit-reset-tokens", "30s"),
+        ]));
+        let admitted = tokio::time::timeout(Duration::from_millis(500), waiting)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(admitted.tokens, 400);
+
+        // Other accounts are unaffected.
+        assert!(ledger.admit("other:", 10_000).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn rejects_when_the_account_is_blocked_past_the_wait_limit() {
+        let ledger = QuotaLedger::default();
+        ledger.block_for("openai:acct", Duration::from_secs(600));
+
+        let err = ledger.admit("openai:acct", 1).await.err().unwrap();
+        assert!(matches!(
+            err,
+            CodexErr::UsageLimitReached(UsageLimitReachedError {
+                resets_in_seconds: Some(600),
+                ..
+            })
+        ));
+
+        let exhausted = QuotaLedger::default();
+        exhausted
+            .admit("codex:", 1)
+            .await
+            .unwrap()
+            .settle(&headers(&[
+                ("x-codex-primary-used-percent", "100.0"),
+                ("x-codex-primary-reset-after-seconds", "3600"),
+            ]));
+        assert!(exhausted.admit("codex:", 1).await.is_err());
+    }
+}
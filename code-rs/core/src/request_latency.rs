@@ -0,0 +1,176 @@
+//! Per-request latency: time to the response headers (TTFB), to the first
+//! streamed output, and to completion, plus how many attempts were retried.
+//!
+//! Every request is reported to the OTEL event manager as
+//! `codex.request_latency`. With `turn_latency_events` enabled the same
+//! numbers reach clients as [`EventMsg::TurnLatency`] so regressions between
+//! models and providers can be measured without an OTEL collector.
+//!
+//! [`EventMsg::TurnLatency`]: crate::protocol::EventMsg::TurnLatency
+
+use std::time::Duration;
+use std::time::Instant;
+
+use code_otel::otel_event_manager::OtelEventManager;
+use tokio::sync::mpsc;
+
+use crate::client_common::ResponseEvent;
+use crate::client_common::ResponseStream;
+use crate::client_common::ServedBy;
+use crate::error::Result;
+use crate::protocol::TurnLatencyEvent;
+use crate::sse_buffer::SSE_CHANNEL_CAPACITY;
+
+/// Passes `stream` through, timing it from `started` (when the request was
+/// first sent). `stream` must have just been returned, so its response
+/// headers arrived now. On `Completed`, reports the timings to `otel` and,
+/// when `emit_event` is set, sends them as [`ResponseEvent::Latency`] first.
+pub(crate) fn track(
+    mut stream: ResponseStream,
+    started: Instant,
+    served_by: ServedBy,
+    emit_event: bool,
+    otel_event_manager: Option<OtelEventManager>,
+) -> ResponseStream {
+    let ttfb = started.elapsed();
+    let retries = stream.retries;
+    let (tx_event, rx_event) = mpsc::channel::<Result<ResponseEvent>>(SSE_CHANNEL_CAPACITY);
+    tokio::spawn(async move {
+        let mut first_token = None;
+        while let Some(event) = stream.rx_event.recv().await {
+            if first_token.is_none()
+                && let Ok(event) = &event
+                && is_output(event)
+            {
+                first_token = Some(started.elapsed());
+            }
+            if let Ok(ResponseEvent::Completed { .. }) = &event {
+                let duration = started.elapsed();
+                let latency = TurnLatencyEvent {
+                    provider: served_by.provider_id.clone(),
+                    model: served_by.model.clone(),
+                    ttfb_ms: millis(ttfb),
+                    first_token_ms: first_token.map(millis),
+                    duration_ms: millis(duration),
+                    retries,
+                };
+                if let Some(otel) = otel_event_manager.as_ref() {
+                    otel.request_latency(
+                        &latency.provider,
+                        &latency.model,
+                        ttfb,
+                        first_token,
+                        duration,
+                        retries,
+                    );
+                }
+                if emit_event
+                    && tx_event
+                        .send(Ok(ResponseEvent::Latency(latency)))
+                        .await
+                        .is_err()
+                {
+                    return;
+                }
+            }
+            if tx_event.send(event).await.is_err() {
+                return;
+            }
+        }
+    });
+    ResponseStream {
+        rx_event,
+        served_by: None,
+        retries,
+    }
+}
+
+/// Events that carry model output, as opposed to bookkeeping.
+fn is_output(event: &ResponseEvent) -> bool {
+    matches!(
+        event,
+        ResponseEvent::OutputTextDelta { .. }
+            | ResponseEvent::ReasoningSummaryDelta { .. }
+            | ResponseEvent::ReasoningContentDelta { .. }
+            | ResponseEvent::ToolCallArgumentsDelta { .. }
+            | ResponseEvent::OutputItemDone { .. }
+    )
+}
+
+fn millis(duration: Duration) -> u64 {
+    u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    async fn run(events: Vec<ResponseEvent>, emit_event: bool) -> Vec<ResponseEvent> {
+        let (tx, rx_event) = mpsc::channel(events.len().max(1));
+        for event in events {
+            tx.send(Ok(event)).await.unwrap();
+        }
+        drop(tx);
+        let stream = ResponseStream {
+            rx_event,
+            served_by: None,
+            retries: 2,
+        };
+        let served_by = ServedBy {
+            provider_id: "openai".to_string(),
+            model: "gpt-5".to_string(),
+        };
+        let mut tracked = track(stream, Instant::now(), served_by, emit_event, None);
+        assert_eq!(tracked.retries, 2);
+        let mut seen = Vec::new();
+        while let Some(event) = tracked.rx_event.recv().await {
+            seen.push(event.unwrap());
+        }
+        seen
+    }
+
+    fn completed() -> ResponseEvent {
+        ResponseEvent::Completed {
+            response_id: "resp_1".to_string(),
+            token_usage: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn reports_latency_before_completed() {
+        let seen = run(
+            vec![
+                ResponseEvent::Created,
+                ResponseEvent::OutputTextDelta {
+                    delta: "hi".to_string(),
+                    item_id: None,
+                    sequence_number: None,
+                    output_index: None,
+                },
+                completed(),
+            ],
+            true,
+        )
+        .await;
+        assert_eq!(seen.len(), 4);
+        let ResponseEvent::Latency(latency) = &seen[2] else {
+            panic!("expected latency before Completed, got {:?}", seen[2]);
+        };
+        assert_eq!(latency.provider, "openai");
+        assert_eq!(latency.model, "gpt-5");
+        assert_eq!(latency.retries, 2);
+        assert!(latency.first_token_ms.is_some());
+        assert!(latency.ttfb_ms <= latency.duration_ms);
+        assert!(matches!(seen[3], ResponseEvent::Completed { .. }));
+    }
+
+    #[tokio::test]
+    async fn forwards_unchanged_when_events_are_off() {
+        let seen = run(vec![ResponseEvent::Created, completed()], false).await;
+        assert!(matches!(
+            seen.as_slice(),
+            [ResponseEvent::Created, ResponseEvent::Completed { .. }]
+        ));
+    }
+}
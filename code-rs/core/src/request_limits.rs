@@ -0,0 +1,157 @@
+//! Pre-send size checks for Responses API requests (`[request_limits]`).
+//!
+//! Providers reject oversized payloads with a bare 400 that says little about
+//! which part of the conversation is to blame. When a limit is configured the
+//! request is measured before it is sent and, if it is over, fails with
+//! [`CodexErr::RequestTooLarge`] naming the largest conversation items.
+
+use code_protocol::models::ResponseItem;
+
+use crate::config_types::RequestLimits;
+use crate::error::CodexErr;
+use crate::error::RequestItemSize;
+use crate::error::RequestTooLargeError;
+use crate::error::Result;
+
+/// Items listed in the error.
+const LARGEST_ITEMS_SHOWN: usize = 5;
+
+/// Checks a request whose serialized body is `body_bytes` long and whose
+/// input is `items`.
+pub(crate) fn check(
+    limits: RequestLimits,
+    body_bytes: usize,
+    items: &[ResponseItem],
+) -> Result<()> {
+    let over = if let Some(max) = limits.max_bytes
+        && body_bytes as u64 > max
+    {
+        Some(("request_limits.max_bytes", max, body_bytes as u64))
+    } else if let Some(max) = limits.max_items
+        && items.len() as u64 > max
+    {
+        Some(("request_limits.max_items", max, items.len() as u64))
+    } else {
+        None
+    };
+    let Some((limit, max, actual)) = over else {
+        return Ok(());
+    };
+    Err(CodexErr::RequestTooLarge(RequestTooLargeError {
+        limit,
+        max,
+        actual,
+        largest_items: largest_items(items),
+    }))
+}
+
+fn largest_items(items: &[ResponseItem]) -> Vec<RequestItemSize> {
+    let mut sizes: Vec<RequestItemSize> = items
+        .iter()
+        .enumerate()
+        .map(|(index, item)| RequestItemSize {
+            index,
+            kind: item_kind(item),
+            bytes: serde_json::to_vec(item).map_or(0, |bytes| bytes.len() as u64),
+        })
+        .collect();
+    sizes.sort_by(|a, b| b.bytes.cmp(&a.bytes).then(a.index.cmp(&b.index)));
+    sizes.truncate(LARGEST_ITEMS_SHOWN);
+    sizes
+}
+
+fn item_kind(item: &ResponseItem) -> String {
+    match item {
+        ResponseItem::Message { role, .. } => format!("{role} message"),
+        ResponseItem::FunctionCall { name, .. } => format!("function_call {name}"),
+        ResponseItem::CustomToolCall { name, .. } => format!("custom_tool_call {name}"),
+        other => serde_json::to_value(other)
+            .ok()
+            .and_then(|value| value.get("type")?.as_str().map(str::to_string))
+            .unwrap_or_else(|| "item".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use code_protocol::models::ContentItem;
+    use code_protocol::models::FunctionCallOutputPayload;
+    use pretty_assertions::assert_eq;
+
+    fn user(text: &str) -> ResponseItem {
+        ResponseItem::Message {
+            id: None,
+            role: "user".to_string(),
+            content: vec![ContentItem::InputText {
+                text: text.to_string(),
+            }],
+        }
+    }
+
+    fn output(call_id: &str, content: &str) -> ResponseItem {
+        ResponseItem::FunctionCallOutput {
+            call_id: call_id.to_string(),
+            output: FunctionCallOutputPayload {
+                content: content.to_string(),
+                success: None,
+            },
+        }
+    }
+
+    #[test]
+    fn unset_limits_never_fail() {
+        let items = vec![user(&"x".repeat(10_000))];
+        assert!(check(RequestLimits::default(), 10_000_000, &items).is_ok());
+    }
+
+    #[test]
+    fn reports_largest_items_when_over_byte_limit() {
+        let items = vec![
+            user("hi"),
+            output("c1", &"a".repeat(500)),
+            user("again"),
+            output("c2", &"b".repeat(2_000)),
+        ];
+        let limits = RequestLimits {
+            max_bytes: Some(1_000),
+            max_items: None,
+        };
+        let Err(CodexErr::RequestTooLarge(err)) = check(limits, 2_800, &items) else {
+            panic!("expected RequestTooLarge");
+        };
+        assert_eq!(
+            (err.limit, err.max, err.actual),
+            ("request_limits.max_bytes", 1_000, 2_800)
+        );
+        assert_eq!(
+            err.largest_items
+                .iter()
+                .map(|item| (item.index, item.kind.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                (3, "function_call_output"),
+                (1, "function_call_output"),
+                (2, "user message"),
+                (0, "user message"),
+            ]
+        );
+        assert!(err.to_string().contains("Run /compact"));
+    }
+
+    #[test]
+    fn enforces_item_count() {
+        let items = vec![user("a"), user("b"), user("c")];
+        let limits = RequestLimits {
+            max_bytes: None,
+            max_items: Some(2),
+        };
+        let Err(CodexErr::RequestTooLarge(err)) = check(limits, 100, &items) else {
+            panic!("expected RequestTooLarge");
+        };
+        assert_eq!(
+            (err.limit, err.max, err.actual),
+            ("request_limits.max_items", 2, 3)
+        );
+    }
+}
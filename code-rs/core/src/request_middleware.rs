@@ -0,0 +1,194 @@
+//! Request middleware for [`ModelClient`].
+//!
+//! Middleware sees every model request body right before it is sent and may
+//! edit it (for example to strip metadata that must not leave the network)
+//! or add headers. It also sees every event streamed back, which is enough to
+//! mirror traffic to an audit log. Register middleware on a single client
+//! with [`ModelClient::with_middleware`], or process-wide with [`register`]
+//! so that clients created internally by sessions pick it up too.
+//!
+//! [`ModelClient`]: crate::ModelClient
+//! [`ModelClient::with_middleware`]: crate::ModelClient::with_middleware
+
+use std::fmt;
+use std::sync::Arc;
+use std::sync::LazyLock;
+use std::sync::RwLock;
+
+use reqwest::header::HeaderMap;
+use serde_json::Value;
+use tokio::sync::mpsc;
+
+use crate::client_common::ResponseEvent;
+use crate::client_common::ResponseStream;
+use crate::error::Result;
+use crate::sse_buffer::SSE_CHANNEL_CAPACITY;
+
+/// Hooks run around every model request. Both methods default to doing
+/// nothing. Middleware is shared between concurrent requests, so any state
+/// it keeps needs interior mutability.
+pub trait RequestMiddleware: Send + Sync {
+    /// Called with the JSON body before it is sent to `endpoint`. Edits to
+    /// `body` are sent as-is; entries added to `headers` are set on the
+    /// request, replacing headers of the same name.
+    fn on_request(&self, _endpoint: &str, _body: &mut Value, _headers: &mut HeaderMap) {}
+
+    /// Called with each event streamed back, before the session sees it.
+    fn on_event(&self, _event: &ResponseEvent) {}
+}
+
+static REGISTERED: LazyLock<RwLock<Vec<Arc<dyn RequestMiddleware>>>> =
+    LazyLock::new(|| RwLock::new(Vec::new()));
+
+/// Registers `middleware` for every [`ModelClient`] created after this call.
+///
+/// [`ModelClient`]: crate::ModelClient
+pub fn register(middleware: Arc<dyn RequestMiddleware>) {
+    if let Ok(mut registered) = REGISTERED.write() {
+        registered.push(middleware);
+    }
+}
+
+/// The middleware a client runs, in registration order.
+#[derive(Clone, Default)]
+pub(crate) struct MiddlewareStack(Vec<Arc<dyn RequestMiddleware>>);
+
+impl fmt::Debug for MiddlewareStack {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MiddlewareStack")
+            .field("len", &self.0.len())
+            .finish()
+    }
+}
+
+impl MiddlewareStack {
+    /// Middleware registered process-wide with [`register`].
+    pub(crate) fn registered() -> Self {
+        Self(
+            REGISTERED
+                .read()
+                .map(|registered| registered.clone())
+                .unwrap_or_default(),
+        )
+    }
+
+    pub(crate) fn push(&mut self, middleware: Arc<dyn RequestMiddleware>) {
+        self.0.push(middleware);
+    }
+
+    /// Runs each middleware's [`RequestMiddleware::on_request`] over `body`
+    /// in order and returns the headers they added.
+    pub(crate) fn prepare(&self, endpoint: &str, body: &mut Value) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for middleware in &self.0 {
+            middleware.on_request(endpoint, body, &mut headers);
+        }
+        headers
+    }
+
+    /// Passes `stream` through, showing each successful event to the
+    /// middleware. Returns `stream` untouched when there is none.
+    pub(crate) fn observe(&self, mut stream: ResponseStream) -> ResponseStream {
+        if self.0.is_empty() {
+            return stream;
+        }
+        let middleware = self.0.clone();
+        let (tx_event, rx_event) = mpsc::channel::<Result<ResponseEvent>>(SSE_CHANNEL_CAPACITY);
+        tokio::spawn(async move {
+            while let Some(event) = stream.rx_event.recv().await {
+                if let Ok(event) = &event {
+                    for middleware in &middleware {
+                        middleware.on_event(event);
+                    }
+                }
+                if tx_event.send(event).await.is_err() {
+                    return;
+                }
+            }
+        });
+        ResponseStream {
+            rx_event,
+            served_by: stream.served_by,
+            retries: stream.retries,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use reqwest::header::HeaderValue;
+    use std::sync::Mutex;
+
+    struct StripMetadata;
+
+    impl RequestMiddleware for StripMetadata {
+        fn on_request(&self, _endpoint: &str, body: &mut Value, headers: &mut HeaderMap) {
+            if let Some(body) = body.as_object_mut() {
+                body.remove("metadata");
+            }
+            headers.insert("x-scrubbed", HeaderValue::from_static("1"));
+        }
+    }
+
+    #[derive(Default)]
+    struct Recorder {
+        seen: Mutex<Vec<String>>,
+    }
+
+    impl RequestMiddleware for Recorder {
+        fn on_request(&self, endpoint: &str, body: &mut Value, _headers: &mut HeaderMap) {
+            self.seen.lock().unwrap().push(format!("{endpoint} {body}"));
+        }
+
+        fn on_event(&self, event: &ResponseEvent) {
+            self.seen.lock().unwrap().push(format!("{event:?}"));
+        }
+    }
+
+    #[test]
+    fn middleware_runs_in_order() {
+        let recorder = Arc::new(Recorder::default());
+        let mut middleware = MiddlewareStack::default();
+        middleware.push(Arc::new(StripMetadata));
+        middleware.push(recorder.clone());
+        let mut body = serde_json::json!({"model": "gpt-5", "metadata": {"user": "alice"}});
+
+        let headers = middleware.prepare("https://api.example.com/v1/responses", &mut body);
+
+        assert_eq!(body, serde_json::json!({"model": "gpt-5"}));
+        assert_eq!(
+            headers.get("x-scrubbed"),
+            Some(&HeaderValue::from_static("1"))
+        );
+        assert_eq!(
+            *recorder.seen.lock().unwrap(),
+            vec![r#"https://api.example.com/v1/responses {"model":"gpt-5"}"#.to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn observe_shows_events_and_forwards_them() {
+        let recorder = Arc::new(Recorder::default());
+        let mut middleware = MiddlewareStack::default();
+        middleware.push(recorder.clone());
+        let (tx, rx_event) = mpsc::channel(1);
+        tx.send(Ok(ResponseEvent::Created)).await.unwrap();
+        drop(tx);
+        let stream = ResponseStream {
+            rx_event,
+            served_by: None,
+            retries: 0,
+        };
+
+        let mut observed = middleware.observe(stream);
+
+        assert!(matches!(
+            observed.rx_event.recv().await,
+            Some(Ok(ResponseEvent::Created))
+        ));
+        assert!(observed.rx_event.recv().await.is_none());
+        assert_eq!(*recorder.seen.lock().unwrap(), vec!["Created".to_string()]);
+    }
+}
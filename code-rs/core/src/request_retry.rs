@@ -0,0 +1,393 @@
+//! The request retry loop shared by every wire API.
+//!
+//! Each attempt reserves the request's estimated tokens in the quota ledger,
+//! sends the request the caller built for it and settles the reservation
+//! against the response headers. Network errors are retried with backoff.
+//! Non-success responses go back to the caller, which decides whether the
+//! turn fails or the request is retried after a delay; a retried 429 also
+//! holds the rest of the account back for that delay. Once
+//! `request_max_retries` is used up the last rejection surfaces as
+//! [`CodexErr::RetryLimit`].
+
+use std::future::Future;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use code_otel::otel_event_manager::OtelEventManager;
+use reqwest::StatusCode;
+use reqwest::header::HeaderMap;
+use serde_json::json;
+
+use crate::ModelProviderInfo;
+use crate::auth::CodexAuth;
+use crate::chat_completions::header_map_to_json;
+use crate::debug_logger::DebugLogger;
+use crate::error::CodexErr;
+use crate::error::Result;
+use crate::error::RetryLimitReachedError;
+use crate::error::TlsError;
+use crate::error::UnexpectedResponseError;
+use crate::quota_ledger;
+use crate::util::backoff;
+
+/// One attempt's request, built with the auth it was signed with.
+pub(crate) struct Attempt {
+    pub request: reqwest::RequestBuilder,
+    pub auth: Option<CodexAuth>,
+}
+
+/// A non-success response, handed to the caller to classify.
+pub(crate) struct Rejection {
+    pub response: reqwest::Response,
+    /// 1-based, counted from the last restart.
+    pub attempt: u64,
+    /// No retries are left after this attempt.
+    pub is_last: bool,
+    /// Debug log id of the request; empty unless debug logging is on.
+    pub request_id: String,
+    pub auth: Option<CodexAuth>,
+    pub quota_key: String,
+}
+
+/// What to do about a [`Rejection`].
+pub(crate) enum Rejected {
+    Fatal(CodexErr),
+    /// Retry after the delay, or fail with [`CodexErr::RetryLimit`] when no
+    /// retries are left.
+    Retry(Duration),
+    /// Start over from the first attempt without waiting, after the caller
+    /// changed what it builds.
+    Restart,
+}
+
+/// A successful response and the bookkeeping that goes with it.
+pub(crate) struct Delivered {
+    pub response: reqwest::Response,
+    pub request_id: String,
+    pub retries: u64,
+}
+
+impl Rejection {
+    /// Fails with the response body, closing the request log.
+    pub(crate) async fn unexpected_status(self, debug_logger: &Mutex<DebugLogger>) -> Rejected {
+        let status = self.response.status();
+        let body = self.response.text().await.unwrap_or_default();
+        Rejected::Fatal(unexpected_status(
+            debug_logger,
+            &self.request_id,
+            status,
+            body,
+        ))
+    }
+
+    /// The `Retry-After` delay, or exponential backoff without one.
+    pub(crate) fn retry_delay(&self) -> Duration {
+        retry_after(self.response.headers()).unwrap_or_else(|| backoff(self.attempt))
+    }
+}
+
+/// 429s and server errors are worth retrying; anything else fails the turn.
+pub(crate) fn is_retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// Reads a `Retry-After` header given in seconds.
+pub(crate) fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    headers
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|s| s.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+}
+
+/// Logs a rejected response's body and closes the request log.
+pub(crate) fn unexpected_status(
+    debug_logger: &Mutex<DebugLogger>,
+    request_id: &str,
+    status: StatusCode,
+    body: String,
+) -> CodexErr {
+    if let Ok(logger) = debug_logger.lock() {
+        let _ = logger.append_response_event(
+            request_id,
+            "error",
+            &json!({ "status": status.as_u16(), "body": body }),
+        );
+        let _ = logger.end_request_log(request_id);
+    }
+    CodexErr::UnexpectedStatus(UnexpectedResponseError {
+        status,
+        body,
+        request_id: None,
+    })
+}
+
+/// Sends the request `build` makes until it succeeds, `reject` gives up on
+/// it or the provider's retries run out. `estimate` is the request's token
+/// estimate for the quota ledger.
+pub(crate) async fn send_with_retries<B, BF, R, RF>(
+    provider: &ModelProviderInfo,
+    debug_logger: &Arc<Mutex<DebugLogger>>,
+    otel_event_manager: Option<&OtelEventManager>,
+    log_tag: Option<&str>,
+    estimate: u64,
+    mut build: B,
+    mut reject: R,
+) -> Result<Delivered>
+where
+    B: FnMut() -> BF,
+    BF: Future<Output = Result<Attempt>>,
+    R: FnMut(Rejection) -> RF,
+    RF: Future<Output = Rejected>,
+{
+    let max_retries = provider.request_max_retries();
+    let mut request_id = String::new();
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+
+        let Attempt { request, auth } = build().await?;
+        if request_id.is_empty() {
+            request_id = start_request_log(debug_logger, &request, log_tag);
+        }
+
+        let quota_key = quota_ledger::account_key(
+            &provider.name,
+            auth.as_ref().and_then(CodexAuth::get_account_id).as_deref(),
+        );
+        let reservation = quota_ledger::global().admit(&quota_key, estimate).await?;
+
+        let res = match otel_event_manager {
+            Some(otel) => otel.log_request(attempt, || request.send()).await,
+            None => request.send().await,
+        };
+        match &res {
+            Ok(resp) => reservation.settle(resp.headers()),
+            Err(_) => drop(reservation),
+        }
+
+        let is_last = attempt > max_retries;
+        match res {
+            Ok(response) if response.status().is_success() => {
+                if let Ok(logger) = debug_logger.lock() {
+                    let _ = logger.append_response_event(
+                        &request_id,
+                        "stream_initiated",
+                        &json!({
+                            "status": "success",
+                            "status_code": response.status().as_u16(),
+                            "x_request_id": response
+                                .headers()
+                                .get("x-request-id")
+                                .and_then(|v| v.to_str().ok())
+                                .unwrap_or_default(),
+                        }),
+                    );
+                }
+                return Ok(Delivered {
+                    response,
+                    request_id,
+                    retries: attempt - 1,
+                });
+            }
+            Ok(response) => {
+                let status = response.status();
+                let rejection = Rejection {
+                    response,
+                    attempt,
+                    is_last,
+                    request_id: request_id.clone(),
+                    auth,
+                    quota_key: quota_key.clone(),
+                };
+                match reject(rejection).await {
+                    Rejected::Fatal(err) => return Err(err),
+                    Rejected::Restart => attempt = 0,
+                    Rejected::Retry(_) if is_last => {
+                        return Err(CodexErr::RetryLimit(RetryLimitReachedError {
+                            status,
+                            request_id: None,
+                            retryable: is_retryable_status(status),
+                        }));
+                    }
+                    Rejected::Retry(delay) => {
+                        if status == StatusCode::TOO_MANY_REQUESTS {
+                            // Hold other conversations on this account back too.
+                            quota_ledger::global().block_for(&quota_key, delay);
+                        }
+                        tokio::time::sleep(delay).await;
+                    }
+                }
+            }
+            Err(e) => {
+                if let Some(tls) = TlsError::from_request_error(&provider.name, &e) {
+                    return Err(CodexErr::Tls(tls));
+                }
+                if is_last {
+                    if let Ok(logger) = debug_logger.lock() {
+                        let _ = logger.append_response_event(
+                            &request_id,
+                            "network_error",
+                            &json!({ "error": e.to_string() }),
+                        );
+                        let _ = logger.end_request_log(&request_id);
+                    }
+                    if e.is_connect() || e.is_timeout() || e.is_request() {
+                        let req_id = (!request_id.is_empty()).then(|| request_id.clone());
+                        return Err(CodexErr::Stream(
+                            format!("[transport] network unavailable: {e}"),
+                            None,
+                            req_id,
+                        ));
+                    }
+                    return Err(e.into());
+                }
+                tokio::time::sleep(backoff(attempt)).await;
+            }
+        }
+    }
+}
+
+/// Opens the debug log for `request`, returning its id (empty when debug
+/// logging is off).
+fn start_request_log(
+    debug_logger: &Mutex<DebugLogger>,
+    request: &reqwest::RequestBuilder,
+    log_tag: Option<&str>,
+) -> String {
+    let Ok(logger) = debug_logger.lock() else {
+        return String::new();
+    };
+    if !logger.is_enabled() {
+        return String::new();
+    }
+    let Some(request) = request.try_clone().and_then(|builder| builder.build().ok()) else {
+        return String::new();
+    };
+    let payload: serde_json::Value = request
+        .body()
+        .and_then(reqwest::Body::as_bytes)
+        .and_then(|body| serde_json::from_slice(body).ok())
+        .unwrap_or_default();
+    logger
+        .start_request_log(
+            request.url().as_str(),
+            &payload,
+            Some(&header_map_to_json(request.headers())),
+            log_tag,
+        )
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model_provider_info::create_oss_provider_with_base_url;
+    use pretty_assertions::assert_eq;
+    use wiremock::Mock;
+    use wiremock::MockServer;
+    use wiremock::ResponseTemplate;
+    use wiremock::matchers::method;
+
+    fn provider(server: &MockServer, retries: u64) -> ModelProviderInfo {
+        ModelProviderInfo {
+            request_max_retries: Some(retries),
+            ..create_oss_provider_with_base_url(&server.uri())
+        }
+    }
+
+    async fn send(provider: &ModelProviderInfo) -> Result<Delivered> {
+        let client = reqwest::Client::new();
+        let debug_logger = Arc::new(Mutex::new(DebugLogger::new(false).expect("logger")));
+        send_with_retries(
+            provider,
+            &debug_logger,
+            None,
+            None,
+            0,
+            || {
+                let request = client.post(provider.get_full_url(&None));
+                async move {
+                    Ok(Attempt {
+                        request,
+                        auth: None,
+                    })
+                }
+            },
+            |rejection: Rejection| async move {
+                if is_retryable_status(rejection.response.status()) {
+                    Rejected::Retry(Duration::ZERO)
+                } else {
+                    Rejected::Fatal(CodexErr::UnsupportedOperation(
+                        rejection.response.status().to_string(),
+                    ))
+                }
+            },
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn retries_until_the_request_succeeds() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let delivered = send(&provider(&server, 2)).await.expect("delivered");
+        assert_eq!(delivered.response.status(), StatusCode::OK);
+        assert_eq!(delivered.retries, 2);
+    }
+
+    #[tokio::test]
+    async fn running_out_of_retries_is_a_retry_limit_error() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        match send(&provider(&server, 1)).await {
+            Err(CodexErr::RetryLimit(err)) => {
+                assert_eq!(err.status, StatusCode::SERVICE_UNAVAILABLE);
+            }
+            other => panic!("unexpected outcome: {:?}", other.map(|d| d.retries)),
+        }
+    }
+
+    #[tokio::test]
+    async fn fatal_rejections_are_not_retried() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(400))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        assert!(matches!(
+            send(&provider(&server, 3)).await,
+            Err(CodexErr::UnsupportedOperation(_))
+        ));
+    }
+
+    #[test]
+    fn retry_after_reads_seconds() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+        headers.insert(reqwest::header::RETRY_AFTER, "7".parse().expect("header"));
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(7)));
+        headers.insert(
+            reqwest::header::RETRY_AFTER,
+            "soon".parse().expect("header"),
+        );
+        assert_eq!(retry_after(&headers), None);
+    }
+}
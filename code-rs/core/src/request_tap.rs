@@ -0,0 +1,198 @@
+//! Hook for observing the first serialized model request of a session.
+//!
+//! Front-ends such as `code exec --print-prompt` set
+//! [`Config::request_tap`](crate::config::Config::request_tap) before
+//! starting a session. The tap receives the endpoint, the headers added by
+//! request middleware and the exact JSON body (instructions, input items,
+//! tools, text format) before the request is authorized, and fires at most
+//! once for the sessions sharing the config. Credentials in the URL and
+//! headers are redacted before the hook sees them. A dry-run tap then fails
+//! the request with [`CodexErr::DryRun`], so no credentials are loaded and
+//! nothing is sent.
+
+use std::fmt;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use reqwest::header::HeaderMap;
+use serde_json::Value;
+
+use crate::error::CodexErr;
+use crate::error::Result;
+use crate::protocol::ErrorEvent;
+use crate::protocol::EventMsg;
+
+const REDACTED: &str = "<redacted>";
+
+/// The first model request as it is about to be sent.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObservedRequest {
+    pub endpoint: String,
+    /// Header names and values in send order, with credentials redacted.
+    pub headers: Vec<(String, String)>,
+    pub body: Value,
+}
+
+type RequestHook = Box<dyn FnOnce(&ObservedRequest) + Send>;
+
+/// Runs a hook with the first request of the sessions whose config holds it.
+/// Clones share the hook.
+#[derive(Clone)]
+pub struct RequestTap {
+    hook: Arc<Mutex<Option<RequestHook>>>,
+    dry_run: bool,
+}
+
+impl RequestTap {
+    pub fn new(hook: impl FnOnce(&ObservedRequest) + Send + 'static) -> Self {
+        Self {
+            hook: Arc::new(Mutex::new(Some(Box::new(hook)))),
+            dry_run: false,
+        }
+    }
+
+    /// Fails every request with [`CodexErr::DryRun`] after the hook ran.
+    pub fn dry_run(mut self) -> Self {
+        self.dry_run = true;
+        self
+    }
+
+    /// Runs the hook if it has not fired yet.
+    pub(crate) fn observe(
+        &self,
+        endpoint: &str,
+        headers: &HeaderMap,
+        payload: &Value,
+    ) -> Result<()> {
+        let hook = self.hook.lock().ok().and_then(|mut guard| guard.take());
+        if let Some(hook) = hook {
+            hook(&ObservedRequest {
+                endpoint: redact_query(endpoint),
+                headers: headers
+                    .iter()
+                    .map(|(name, value)| {
+                        let value = if is_sensitive(name.as_str()) {
+                            REDACTED.to_string()
+                        } else {
+                            value.to_str().unwrap_or_default().to_string()
+                        };
+                        (name.as_str().to_string(), value)
+                    })
+                    .collect(),
+                body: payload.clone(),
+            });
+        }
+        if self.dry_run {
+            return Err(CodexErr::DryRun);
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for RequestTap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RequestTap")
+            .field("dry_run", &self.dry_run)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for RequestTap {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.hook, &other.hook) && self.dry_run == other.dry_run
+    }
+}
+
+/// Whether `msg` is the error a dry-run tap ends the turn with.
+pub fn is_dry_run_stop(msg: &EventMsg) -> bool {
+    let EventMsg::Error(ErrorEvent { message }) = msg else {
+        return false;
+    };
+    *message == CodexErr::DryRun.to_string()
+}
+
+fn is_sensitive(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    ["auth", "key", "token", "secret", "cookie", "password"]
+        .iter()
+        .any(|marker| name.contains(marker))
+}
+
+/// Redacts the values of credential-like query parameters.
+fn redact_query(endpoint: &str) -> String {
+    let Some((base, query)) = endpoint.split_once('?') else {
+        return endpoint.to_string();
+    };
+    let query = query
+        .split('&')
+        .map(|pair| match pair.split_once('=') {
+            Some((key, _)) if is_sensitive(key) => format!("{key}={REDACTED}"),
+            _ => pair.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("&");
+    format!("{base}?{query}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use reqwest::header::HeaderValue;
+    use serde_json::json;
+
+    #[test]
+    fn hook_fires_once_with_redacted_request() {
+        let seen: Arc<Mutex<Vec<ObservedRequest>>> = Arc::new(Mutex::new(Vec::new()));
+        let sink = seen.clone();
+        let tap = RequestTap::new(move |request| {
+            sink.lock().unwrap().push(request.clone());
+        });
+
+        let mut headers = HeaderMap::new();
+        headers.insert("authorization", HeaderValue::from_static("Bearer sk-123"));
+        headers.insert("x-api-key", HeaderValue::from_static("sk-456"));
+        headers.insert("session_id", HeaderValue::from_static("s1"));
+        tap.observe(
+            "https://example.test/v1/responses?api-version=2025-04-01&api-key=sk-789",
+            &headers,
+            &json!({"model": "m"}),
+        )
+        .unwrap();
+        tap.clone()
+            .observe(
+                "https://example.test/v1/responses",
+                &HeaderMap::new(),
+                &json!({"model": "other"}),
+            )
+            .unwrap();
+
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![ObservedRequest {
+                endpoint:
+                    "https://example.test/v1/responses?api-version=2025-04-01&api-key=<redacted>"
+                        .to_string(),
+                headers: vec![
+                    ("authorization".to_string(), "<redacted>".to_string()),
+                    ("x-api-key".to_string(), "<redacted>".to_string()),
+                    ("session_id".to_string(), "s1".to_string()),
+                ],
+                body: json!({"model": "m"}),
+            }]
+        );
+    }
+
+    #[test]
+    fn dry_run_tap_fails_every_request() {
+        let tap = RequestTap::new(|_| {}).dry_run();
+        for _ in 0..2 {
+            let err = tap
+                .observe("https://example.test", &HeaderMap::new(), &json!({}))
+                .unwrap_err();
+            assert!(is_dry_run_stop(&EventMsg::Error(ErrorEvent {
+                message: err.to_string(),
+            })));
+        }
+    }
+}
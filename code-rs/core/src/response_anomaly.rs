@@ -0,0 +1,265 @@
+//! Detection of degenerate provider responses in the SSE path.
+//!
+//! Some providers occasionally end a stream early, loop on the same few
+//! tokens, or report success without producing anything. Left alone these
+//! surface downstream as confusing JSON parse errors or empty turns, so the
+//! stream processors classify them here and fail the attempt with a
+//! retryable [`CodexErr::Stream`] instead.
+//!
+//! Truncation is always checked. Empty completions and repetition are only
+//! checked for providers with `retry_degenerate_responses`, since legitimate
+//! output can look like either.
+
+use code_otel::otel_event_manager::OtelEventManager;
+use code_protocol::models::ResponseItem;
+use tracing::warn;
+
+use crate::error::CodexErr;
+
+/// Output text kept for the repetition check; older text is dropped.
+const REPETITION_WINDOW_CHARS: usize = 4096;
+/// New output text between two repetition checks while streaming.
+const REPETITION_CHECK_INTERVAL_CHARS: usize = 256;
+/// Longest repeated unit considered (in chars).
+const MAX_REPEAT_PERIOD: usize = 200;
+/// A trailing run must cover at least this many chars to count as a loop.
+/// Tables, separators and fixtures can legitimately repeat for a while.
+const MIN_REPEAT_RUN_CHARS: usize = 3072;
+/// ... and repeat its unit at least this many times.
+const MIN_REPEAT_CYCLES: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ResponseAnomaly {
+    /// The stream ended, or an item closed, before the response was whole.
+    Truncated,
+    /// The output degenerated into the same short sequence over and over.
+    Repetition,
+    /// The provider completed the response without producing any output.
+    EmptyCompletion,
+}
+
+impl ResponseAnomaly {
+    /// Stable identifier used in telemetry and error messages.
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            ResponseAnomaly::Truncated => "truncated",
+            ResponseAnomaly::Repetition => "repetition",
+            ResponseAnomaly::EmptyCompletion => "empty_completion",
+        }
+    }
+
+    /// Logs the anomaly and forwards it to telemetry.
+    pub(crate) fn record(self, detail: &str, otel_event_manager: Option<&OtelEventManager>) {
+        warn!("provider response anomaly ({}): {detail}", self.as_str());
+        if let Some(manager) = otel_event_manager {
+            manager.sse_event_anomaly(self.as_str(), detail);
+        }
+    }
+
+    /// Retryable stream error carrying the anomaly type.
+    pub(crate) fn into_error(self, detail: &str, request_id: &str) -> CodexErr {
+        CodexErr::Stream(
+            format!("[anomaly:{}] {detail}", self.as_str()),
+            None,
+            Some(request_id.to_string()),
+        )
+    }
+}
+
+/// Accumulates what a single response stream produced.
+#[derive(Debug, Default)]
+pub(crate) struct AnomalyDetector {
+    /// Whether empty completions and repetition count as anomalies.
+    degenerate_checks: bool,
+    saw_output: bool,
+    recent_text: String,
+    /// Chars added to `recent_text` since the last repetition check.
+    unchecked_chars: usize,
+}
+
+impl AnomalyDetector {
+    pub(crate) fn new(degenerate_checks: bool) -> Self {
+        Self {
+            degenerate_checks,
+            ..Self::default()
+        }
+    }
+
+    /// Records assistant text as it streams, returning a description once
+    /// the output has degenerated into repetition.
+    pub(crate) fn observe_text_delta(&mut self, delta: &str) -> Option<String> {
+        if delta.is_empty() {
+            return None;
+        }
+        self.saw_output = true;
+        if !self.degenerate_checks {
+            return None;
+        }
+        self.recent_text.push_str(delta);
+        let excess = self
+            .recent_text
+            .chars()
+            .count()
+            .saturating_sub(REPETITION_WINDOW_CHARS);
+        if excess > 0 {
+            let cut = self
+                .recent_text
+                .char_indices()
+                .nth(excess)
+                .map_or(self.recent_text.len(), |(idx, _)| idx);
+            self.recent_text.drain(..cut);
+        }
+        self.unchecked_chars += delta.chars().count();
+        if self.unchecked_chars < REPETITION_CHECK_INTERVAL_CHARS {
+            return None;
+        }
+        self.unchecked_chars = 0;
+        self.repetition()
+    }
+
+    /// Notes output that is not assistant text (reasoning, unparsed items).
+    pub(crate) fn observe_output(&mut self) {
+        self.saw_output = true;
+    }
+
+    /// Records a finished output item, returning a description when the item
+    /// itself is malformed.
+    pub(crate) fn observe_item(&mut self, item: &ResponseItem) -> Option<String> {
+        self.saw_output = true;
+        match item {
+            ResponseItem::FunctionCall {
+                name, arguments, ..
+            } if !arguments.trim().is_empty()
+                && serde_json::from_str::<serde_json::Value>(arguments).is_err() =>
+            {
+                Some(format!(
+                    "arguments for function call `{name}` are not valid JSON ({} bytes)",
+                    arguments.len()
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    /// Checks the response as a whole once the provider reports completion.
+    /// `output_tokens` is the provider-reported count, when present.
+    pub(crate) fn check_completed(
+        &self,
+        output_tokens: Option<u64>,
+    ) -> Option<(ResponseAnomaly, String)> {
+        if !self.degenerate_checks {
+            return None;
+        }
+        if !self.saw_output && output_tokens.unwrap_or(0) == 0 {
+            return Some((
+                ResponseAnomaly::EmptyCompletion,
+                "response completed without any output".to_string(),
+            ));
+        }
+        self.repetition()
+            .map(|detail| (ResponseAnomaly::Repetition, detail))
+    }
+
+    fn repetition(&self) -> Option<String> {
+        repeated_tail(&self.recent_text)
+            .map(|(unit, cycles)| format!("output repeated {unit:?} {cycles} times"))
+    }
+}
+
+/// Finds a short unit that the end of `text` repeats over and over, returning
+/// the unit and how many times it occurs. Units without letters (table rules,
+/// separators, runs of numbers) are structure, not a stuck model.
+fn repeated_tail(text: &str) -> Option<(String, usize)> {
+    let chars: Vec<char> = text.chars().collect();
+    if chars.len() < MIN_REPEAT_RUN_CHARS {
+        return None;
+    }
+    for period in 1..=MAX_REPEAT_PERIOD.min(chars.len() / 2) {
+        let run = (period..chars.len())
+            .rev()
+            .take_while(|&idx| chars[idx] == chars[idx - period])
+            .count()
+            + period;
+        let cycles = run / period;
+        if run >= MIN_REPEAT_RUN_CHARS && cycles >= MIN_REPEAT_CYCLES {
+            let unit = &chars[chars.len() - period..];
+            if !unit.iter().any(|c| c.is_alphabetic()) {
+                return None;
+            }
+            return Some((unit.iter().collect(), cycles));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn empty_completion_unless_provider_reports_output_tokens() {
+        let detector = AnomalyDetector::new(true);
+        assert_eq!(
+            detector.check_completed(Some(0)).map(|(kind, _)| kind),
+            Some(ResponseAnomaly::EmptyCompletion)
+        );
+        assert_eq!(detector.check_completed(Some(12)), None);
+        assert_eq!(AnomalyDetector::default().check_completed(Some(0)), None);
+    }
+
+    #[test]
+    fn detects_runaway_repetition_while_streaming() {
+        let mut detector = AnomalyDetector::new(true);
+        assert_eq!(detector.observe_text_delta("Here is the plan.\n"), None);
+        let detail = (0..1000)
+            .position(|_| detector.observe_text_delta("the the ").is_some())
+            .expect("repetition detected");
+        // Caught within one check interval of crossing the threshold.
+        let threshold = MIN_REPEAT_RUN_CHARS / 8;
+        assert!((threshold..threshold + 32).contains(&detail), "{detail}");
+
+        let mut detector = AnomalyDetector::default();
+        for _ in 0..1000 {
+            assert_eq!(detector.observe_text_delta("the the "), None);
+        }
+        assert_eq!(detector.check_completed(None), None);
+    }
+
+    #[test]
+    fn tolerates_ordinary_and_structured_text() {
+        let mut detector = AnomalyDetector::new(true);
+        for line in 0..500 {
+            assert_eq!(
+                detector.observe_text_delta(&format!("line {line}: ok\n")),
+                None
+            );
+        }
+        let mut detector = AnomalyDetector::new(true);
+        detector.observe_text_delta("| name | value |\n");
+        for _ in 0..600 {
+            assert_eq!(detector.observe_text_delta("|------|-------|\n"), None);
+        }
+        for _ in 0..2000 {
+            assert_eq!(detector.observe_text_delta("0, "), None);
+        }
+        assert_eq!(detector.check_completed(None), None);
+    }
+
+    #[test]
+    fn flags_function_call_with_truncated_arguments() {
+        let mut detector = AnomalyDetector::default();
+        let call = |arguments: &str| ResponseItem::FunctionCall {
+            id: None,
+            name: "shell".to_string(),
+            arguments: arguments.to_string(),
+            call_id: "c1".to_string(),
+        };
+        assert_eq!(detector.observe_item(&call(r#"{"command": ["ls"]}"#)), None);
+        assert_eq!(detector.observe_item(&call("")), None);
+        assert_eq!(
+            detector.observe_item(&call(r#"{"command": ["l"#)),
+            Some("arguments for function call `shell` are not valid JSON (15 bytes)".to_string())
+        );
+    }
+}
@@ -0,0 +1,323 @@
+//! On-disk cache of Responses API streams (`response_cache`, `code exec
+//! --cache`).
+//!
+//! A completed response body is stored as raw SSE in
+//! `<code_home>/cache/responses/<key>.sse`. The key is the SHA-256 of the
+//! endpoint and the request payload, minus `prompt_cache_key`, which holds
+//! the per-process session id. In `read` mode a cached request replays the
+//! stored stream instead of calling the provider and a miss is recorded; in
+//! `write` mode every response is recorded and nothing is replayed. Streams
+//! that fail or end before `response.completed` are not stored.
+//!
+//! Bodies are sealed with the at-rest cipher when `encrypt_at_rest` is set.
+//! The directory is kept under [`MAX_CACHE_BYTES`] by deleting the least
+//! recently used responses; a replay counts as a use.
+
+use std::io;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use bytes::Bytes;
+use futures::StreamExt;
+use futures::stream::BoxStream;
+use futures::stream::Stream;
+use ring::digest;
+use serde_json::Value;
+
+use crate::at_rest;
+use crate::at_rest::AtRestCipher;
+use crate::config::Config;
+use crate::config_types::ResponseCacheMode;
+use crate::error::Result;
+
+const COMPLETED_MARKER: &[u8] = b"response.completed";
+
+/// Total size of the recorded responses kept on disk.
+pub(crate) const MAX_CACHE_BYTES: u64 = 512 * 1024 * 1024;
+
+pub(crate) struct ResponseCache {
+    code_home: PathBuf,
+    dir: PathBuf,
+    mode: ResponseCacheMode,
+    cipher: Option<Arc<AtRestCipher>>,
+    max_bytes: u64,
+}
+
+impl ResponseCache {
+    /// `None` when caching is off, or when `encrypt_at_rest` is set but the
+    /// key cannot be loaded.
+    pub fn from_config(config: &Config) -> Option<Self> {
+        if config.response_cache == ResponseCacheMode::Off {
+            return None;
+        }
+        match at_rest::cipher_for(config.encrypt_at_rest, &config.code_home) {
+            Ok(cipher) => Self::new(&config.code_home, config.response_cache, cipher),
+            Err(err) => {
+                tracing::warn!("response cache disabled: {err}");
+                None
+            }
+        }
+    }
+
+    /// `None` when caching is off.
+    pub fn new(
+        code_home: &Path,
+        mode: ResponseCacheMode,
+        cipher: Option<Arc<AtRestCipher>>,
+    ) -> Option<Self> {
+        (mode != ResponseCacheMode::Off).then(|| Self {
+            code_home: code_home.to_path_buf(),
+            dir: code_home.join("cache").join("responses"),
+            mode,
+            cipher,
+            max_bytes: MAX_CACHE_BYTES,
+        })
+    }
+
+    pub fn key(endpoint: &str, payload: &Value) -> String {
+        let mut payload = payload.clone();
+        if let Some(obj) = payload.as_object_mut() {
+            obj.remove("prompt_cache_key");
+        }
+        let mut ctx = digest::Context::new(&digest::SHA256);
+        ctx.update(endpoint.as_bytes());
+        ctx.update(b"\n");
+        ctx.update(payload.to_string().as_bytes());
+        ctx.finish()
+            .as_ref()
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    }
+
+    /// The recorded body for `key`, in `read` mode only.
+    pub fn lookup(&self, key: &str) -> Option<Bytes> {
+        if self.mode != ResponseCacheMode::Read {
+            return None;
+        }
+        let path = self.path(key);
+        let data = std::fs::read(&path).ok()?;
+        let body = match at_rest::open_in(&self.code_home, data) {
+            Ok(body) => body,
+            Err(err) => {
+                tracing::warn!("failed to open cached response {}: {err}", path.display());
+                return None;
+            }
+        };
+        // Mark the entry as recently used for eviction.
+        if let Ok(file) = std::fs::File::options().write(true).open(&path) {
+            let _ = file.set_modified(SystemTime::now());
+        }
+        Some(Bytes::from(body))
+    }
+
+    /// Passes `stream` through, saving the body under `key` once the stream
+    /// ends after `response.completed`.
+    pub fn record<S>(&self, key: &str, stream: S) -> BoxStream<'static, Result<Bytes>>
+    where
+        S: Stream<Item = Result<Bytes>> + Send + 'static,
+    {
+        let path = self.path(key);
+        let cipher = self.cipher.clone();
+        let max_bytes = self.max_bytes;
+        let body = Arc::new(Mutex::new(Some(Vec::new())));
+        let sink = Arc::clone(&body);
+        let recorded = stream.inspect(move |chunk| {
+            let mut body = sink
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner);
+            match chunk {
+                Ok(bytes) => {
+                    if let Some(body) = body.as_mut() {
+                        body.extend_from_slice(bytes);
+                    }
+                }
+                // A failed stream is never stored.
+                Err(_) => *body = None,
+            }
+        });
+        let finish = futures::stream::once(async move {
+            let body = body
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner)
+                .take();
+            if let Some(body) = body
+                && body
+                    .windows(COMPLETED_MARKER.len())
+                    .any(|window| window == COMPLETED_MARKER)
+            {
+                if let Err(err) = write_atomically(&path, cipher.as_deref(), &body) {
+                    tracing::warn!("failed to cache response at {}: {err}", path.display());
+                } else if let Some(dir) = path.parent()
+                    && let Err(err) = evict(dir, max_bytes)
+                {
+                    tracing::warn!("failed to trim response cache {}: {err}", dir.display());
+                }
+            }
+            None::<Result<Bytes>>
+        })
+        .filter_map(std::future::ready);
+        recorded.chain(finish).boxed()
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{key}.sse"))
+    }
+}
+
+/// Writes `body` (sealed with `cipher` if given) through a uniquely named
+/// temporary file, so concurrent recordings of the same key do not clobber
+/// each other's partial writes.
+fn write_atomically(path: &Path, cipher: Option<&AtRestCipher>, body: &[u8]) -> io::Result<()> {
+    let dir = path
+        .parent()
+        .ok_or_else(|| io::Error::other("cache path has no parent directory"))?;
+    std::fs::create_dir_all(dir)?;
+    let sealed;
+    let contents = match cipher {
+        Some(cipher) => {
+            sealed = cipher.seal(body)?;
+            sealed.as_slice()
+        }
+        None => body,
+    };
+    let mut tmp = tempfile::NamedTempFile::new_in(dir)?;
+    tmp.write_all(contents)?;
+    tmp.persist(path).map_err(|err| err.error)?;
+    Ok(())
+}
+
+/// Deletes the least recently used recordings in `dir` until the rest fit
+/// in `max_bytes`. Recordings another process removed meanwhile are skipped.
+fn evict(dir: &Path, max_bytes: u64) -> io::Result<()> {
+    let mut entries = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        if path.extension().is_none_or(|ext| ext != "sse") {
+            continue;
+        }
+        let metadata = match entry.metadata() {
+            Ok(metadata) => metadata,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err),
+        };
+        let used = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+        entries.push((used, metadata.len(), path));
+    }
+    entries.sort_by(|a, b| b.0.cmp(&a.0));
+    let mut kept = 0u64;
+    for (_, len, path) in entries {
+        kept = kept.saturating_add(len);
+        if kept > max_bytes
+            && let Err(err) = std::fs::remove_file(&path)
+            && err.kind() != io::ErrorKind::NotFound
+        {
+            return Err(err);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::CodexErr;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    const BODY: &str = "event: response.completed\ndata: {\"type\":\"response.completed\",\"response\":{\"id\":\"resp_1\"}}\n\n";
+
+    #[test]
+    fn key_ignores_the_session_cache_key() {
+        let payload = json!({"model": "gpt-5", "input": [], "prompt_cache_key": "session-a"});
+        let rerun = json!({"model": "gpt-5", "input": [], "prompt_cache_key": "session-b"});
+        let other = json!({"model": "gpt-5", "input": [{"role": "user"}]});
+        let endpoint = "https://api.openai.com/v1/responses";
+        assert_eq!(
+            ResponseCache::key(endpoint, &payload),
+            ResponseCache::key(endpoint, &rerun)
+        );
+        assert_ne!(
+            ResponseCache::key(endpoint, &payload),
+            ResponseCache::key(endpoint, &other)
+        );
+    }
+
+    #[tokio::test]
+    async fn replays_completed_streams_in_read_mode() {
+        let home = tempfile::tempdir().unwrap();
+        let writer = ResponseCache::new(home.path(), ResponseCacheMode::Write, None).unwrap();
+        let chunks = vec![Ok(Bytes::from(&BODY[..10])), Ok(Bytes::from(&BODY[10..]))];
+        let passed: Vec<Bytes> = writer
+            .record("complete", futures::stream::iter(chunks))
+            .map(|chunk| chunk.unwrap())
+            .collect()
+            .await;
+        assert_eq!(passed.concat(), BODY.as_bytes());
+        assert_eq!(writer.lookup("complete"), None);
+
+        let failed = vec![
+            Ok(Bytes::from(BODY)),
+            Err(CodexErr::Stream("reset".to_string(), None, None)),
+        ];
+        let _ = writer
+            .record("failed", futures::stream::iter(failed))
+            .collect::<Vec<_>>()
+            .await;
+
+        let reader = ResponseCache::new(home.path(), ResponseCacheMode::Read, None).unwrap();
+        assert_eq!(reader.lookup("complete"), Some(Bytes::from(BODY)));
+        assert_eq!(reader.lookup("failed"), None);
+        assert!(ResponseCache::new(home.path(), ResponseCacheMode::Off, None).is_none());
+    }
+
+    async fn record(cache: &ResponseCache, key: &str) {
+        let _ = cache
+            .record(key, futures::stream::iter([Ok(Bytes::from(BODY))]))
+            .collect::<Vec<_>>()
+            .await;
+    }
+
+    #[tokio::test]
+    async fn sealed_recordings_replay_as_plaintext() {
+        let home = tempfile::tempdir().unwrap();
+        let cipher = at_rest::cipher_for(true, home.path()).unwrap();
+        let writer = ResponseCache::new(home.path(), ResponseCacheMode::Write, cipher).unwrap();
+        record(&writer, "sealed").await;
+
+        let stored = std::fs::read(writer.path("sealed")).unwrap();
+        assert!(stored.starts_with(at_rest::MAGIC));
+        let reader = ResponseCache::new(home.path(), ResponseCacheMode::Read, None).unwrap();
+        assert_eq!(reader.lookup("sealed"), Some(Bytes::from(BODY)));
+    }
+
+    #[tokio::test]
+    async fn least_recently_used_recordings_are_evicted() {
+        let home = tempfile::tempdir().unwrap();
+        let mut cache = ResponseCache::new(home.path(), ResponseCacheMode::Read, None).unwrap();
+        cache.max_bytes = 2 * BODY.len() as u64;
+        let age = |key: &str, secs: u64| {
+            std::fs::File::options()
+                .write(true)
+                .open(cache.path(key))
+                .unwrap()
+                .set_modified(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(secs))
+                .unwrap();
+        };
+        record(&cache, "old").await;
+        age("old", 1);
+        record(&cache, "replayed").await;
+        age("replayed", 2);
+        assert!(cache.lookup("replayed").is_some());
+
+        record(&cache, "new").await;
+        assert_eq!(cache.lookup("old"), None);
+        assert!(cache.lookup("replayed").is_some());
+        assert!(cache.lookup("new").is_some());
+    }
+}
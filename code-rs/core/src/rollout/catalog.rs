@@ -22,7 +22,8 @@ use uuid::Uuid;
 
 use super::SESSIONS_SUBDIR;
 
-const INDEX_SUBDIR: &str = "sessions/index";
+/// Shared with the SQLite session index.
+pub(super) const INDEX_SUBDIR: &str = "sessions/index";
 const CATALOG_FILENAME: &str = "catalog.jsonl";
 
 /// Canonical entry in the session catalog index.
@@ -92,6 +93,10 @@ pub struct SessionIndexEntry {
     /// Whether session is marked as deleted
     #[serde(default)]
     pub deleted: bool,
+
+    /// User-assigned labels, kept by the SQLite session index
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
 }
 
 impl SessionIndexEntry {
@@ -407,10 +412,16 @@ async fn scan_rollout_files(sessions_root: &Path) -> io::Result<HashMap<Uuid, Se
 }
 
 /// Parse a rollout file and extract catalog entry information.
-async fn parse_rollout_file(path: &Path, sessions_root: &Path) -> Option<SessionIndexEntry> {
+pub(crate) async fn parse_rollout_file(
+    path: &Path,
+    sessions_root: &Path,
+) -> Option<SessionIndexEntry> {
     use tokio::io::AsyncBufReadExt;
     use tokio::io::BufReader;
 
+    // Rollout paths are stored relative to code_home, the sessions root's parent.
+    let code_home = sessions_root.parent()?;
+
     // Read the file
     let file = match tokio::fs::File::open(path).await {
         Ok(f) => f,
@@ -439,6 +450,9 @@ async fn parse_rollout_file(path: &Path, sessions_root: &Path) -> Option<Session
             continue;
         }
 
+        let Ok(line) = crate::at_rest::open_line_in(code_home, &line) else {
+            continue;
+        };
         let rollout_line: RolloutLine = match serde_json::from_str(&line) {
             Ok(rl) => rl,
             Err(_) => continue,
@@ -497,8 +511,6 @@ async fn parse_rollout_file(path: &Path, sessions_root: &Path) -> Option<Session
     let cwd_real = cwd_real?;
     let session_source = session_source?;
 
-    // Make rollout_path relative to sessions_root's parent (code_home)
-    let code_home = sessions_root.parent()?;
     let rollout_path = path.strip_prefix(code_home).ok()?.to_path_buf();
 
     // Check for snapshot file
@@ -536,10 +548,11 @@ async fn parse_rollout_file(path: &Path, sessions_root: &Path) -> Option<Session
         sync_version: 0,
         archived: false,
         deleted: false,
+        tags: Vec::new(),
     })
 }
 
-fn should_replace(existing: &SessionIndexEntry, candidate: &SessionIndexEntry) -> bool {
+pub(crate) fn should_replace(existing: &SessionIndexEntry, candidate: &SessionIndexEntry) -> bool {
     if existing.last_event_at != candidate.last_event_at {
         return existing.last_event_at < candidate.last_event_at;
     }
@@ -604,7 +617,7 @@ fn truncate_snippet(text: &str) -> String {
     text.chars().take(100).collect()
 }
 
-fn is_system_status_snippet(text: &str) -> bool {
+pub(crate) fn is_system_status_snippet(text: &str) -> bool {
     text.starts_with("== System Status ==")
 }
 
@@ -634,6 +647,7 @@ mod tests {
             sync_version: 0,
             archived: false,
             deleted: false,
+            tags: Vec::new(),
         };
 
         let entry2 = SessionIndexEntry {
@@ -669,6 +683,7 @@ mod tests {
             sync_version: 0,
             archived: false,
             deleted: false,
+            tags: Vec::new(),
         };
 
         // Create and save catalog
@@ -709,6 +724,7 @@ mod tests {
             sync_version: 0,
             archived: false,
             deleted: false,
+            tags: Vec::new(),
         };
 
         let entry2 = SessionIndexEntry {
@@ -759,6 +775,7 @@ mod tests {
             sync_version: 0,
             archived: false,
             deleted: false,
+            tags: Vec::new(),
         };
 
         let mut catalog = SessionCatalog::load(code_home)?;
@@ -811,6 +828,7 @@ mod tests {
             sync_version: 0,
             archived: false,
             deleted: false,
+            tags: Vec::new(),
         };
 
         let mut catalog = SessionCatalog::load(code_home)?;
@@ -854,6 +872,7 @@ mod tests {
             sync_version: 0,
             archived: false,
             deleted: false,
+            tags: Vec::new(),
         };
 
         let entry2 = SessionIndexEntry {
@@ -916,6 +935,7 @@ mod tests {
             sync_version: 0,
             archived: false,
             deleted: false,
+            tags: Vec::new(),
         };
 
         let mut catalog = SessionCatalog::load(code_home)?;
@@ -993,6 +1013,7 @@ mod tests {
             sync_version: 0,
             archived: false,
             deleted: false,
+            tags: Vec::new(),
         };
 
         let mut catalog = SessionCatalog::load(code_home)?;
@@ -129,7 +129,8 @@ pub(crate) async fn get_conversations(
     let anchor = cursor.cloned();
 
     let result =
-        traverse_directories_for_paths(root.clone(), page_size, anchor, allowed_sources).await?;
+        traverse_directories_for_paths(code_home, root.clone(), page_size, anchor, allowed_sources)
+            .await?;
     Ok(result)
 }
 
@@ -145,6 +146,7 @@ pub(crate) async fn get_conversation(path: &Path) -> io::Result<String> {
 /// Directory layout: `~/.codex/sessions/YYYY/MM/DD/rollout-YYYY-MM-DDThh-mm-ss-<uuid>.jsonl`
 /// Returned newest (latest) first.
 async fn traverse_directories_for_paths(
+    code_home: &Path,
     root: PathBuf,
     page_size: usize,
     anchor: Option<Cursor>,
@@ -208,9 +210,10 @@ async fn traverse_directories_for_paths(
                             continue;
                         }
                     }
-                    let summary = read_head_and_tail(&path, HEAD_RECORD_LIMIT, TAIL_RECORD_LIMIT)
-                        .await
-                        .unwrap_or_default();
+                    let summary =
+                        read_head_and_tail(code_home, &path, HEAD_RECORD_LIMIT, TAIL_RECORD_LIMIT)
+                            .await
+                            .unwrap_or_default();
                     if !allowed_sources.is_empty()
                         && !summary
                             .source
@@ -348,7 +351,10 @@ fn parse_timestamp_uuid_from_filename(name: &str) -> Option<(OffsetDateTime, Uui
     Some((ts, uuid))
 }
 
+/// Reads the first and last records of a rollout, opening lines sealed with
+/// the at-rest key of `code_home`.
 async fn read_head_and_tail(
+    code_home: &Path,
     path: &Path,
     head_limit: usize,
     tail_limit: usize,
@@ -367,8 +373,11 @@ async fn read_head_and_tail(
         if trimmed.is_empty() {
             continue;
         }
+        let Ok(trimmed) = crate::at_rest::open_line_in(code_home, trimmed) else {
+            continue;
+        };
 
-        let parsed: Result<RolloutLine, _> = serde_json::from_str(trimmed);
+        let parsed: Result<RolloutLine, _> = serde_json::from_str(&trimmed);
         let Ok(rollout_line) = parsed else { continue };
 
         match &rollout_line.item {
@@ -410,9 +419,12 @@ async fn read_head_and_tail(
         if trimmed.is_empty() {
             continue;
         }
-        let parsed: Result<RolloutLine, _> = serde_json::from_str(trimmed);
+        let Ok(trimmed) = crate::at_rest::open_line_in(code_home, trimmed) else {
+            continue;
+        };
+        let parsed: Result<RolloutLine, _> = serde_json::from_str(&trimmed);
         if parsed.is_ok() {
-            tail_events.push(serde_json::from_str(trimmed).unwrap_or_default());
+            tail_events.push(serde_json::from_str(&trimmed).unwrap_or_default());
         }
     }
     tail_events.reverse();
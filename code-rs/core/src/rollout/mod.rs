@@ -12,6 +12,7 @@ pub mod catalog;
 pub mod list;
 pub(crate) mod policy;
 pub mod recorder;
+pub(crate) mod session_index;
 
 pub use code_protocol::protocol::SessionMeta;
 #[allow(unused_imports)]
@@ -5,6 +5,7 @@ use std::fs::{self};
 use std::io::Error as IoError;
 use std::path::Path;
 use std::path::PathBuf;
+use std::sync::Arc;
 
 use code_protocol::ConversationId;
 use code_protocol::models::ContentItem;
@@ -30,6 +31,8 @@ use super::list::Cursor;
 use super::list::get_conversations;
 use super::policy::should_persist_response_item;
 use super::policy::should_persist_rollout_item;
+use crate::at_rest;
+use crate::at_rest::AtRestCipher;
 use crate::config::Config;
 use crate::default_client::DEFAULT_ORIGINATOR;
 use crate::git_info::collect_git_info;
@@ -129,6 +132,7 @@ impl RolloutRecorder {
     /// cannot be created or the rollout file cannot be opened we return the
     /// error so the caller can decide whether to disable persistence.
     pub async fn new(config: &Config, params: RolloutRecorderParams) -> std::io::Result<Self> {
+        let cipher = at_rest::cipher_for(config.encrypt_at_rest, &config.code_home)?;
         let (file, rollout_path, meta) = match params {
             RolloutRecorderParams::Create {
                 conversation_id,
@@ -195,6 +199,7 @@ impl RolloutRecorder {
         // driver instead of blocking the runtime.
         tokio::task::spawn(rollout_writer(
             file,
+            cipher,
             rx,
             meta,
             cwd,
@@ -288,7 +293,7 @@ impl RolloutRecorder {
             },
         )
         .await?;
-        let history = Self::get_rollout_history(path).await?;
+        let history = Self::get_rollout_history(&config.code_home, path).await?;
         let (session_id, items) = match history {
             InitialHistory::Resumed(resumed) => {
                 (uuid::Uuid::from(resumed.conversation_id), resumed.history)
@@ -296,8 +301,11 @@ impl RolloutRecorder {
             _ => (uuid::Uuid::new_v4(), Vec::new()),
         };
         let snapshot_path = path.with_extension("snapshot.json");
-        let history_snapshot = match tokio::fs::read_to_string(&snapshot_path).await {
-            Ok(json) => match serde_json::from_str::<crate::history::HistorySnapshot>(&json) {
+        let history_snapshot = match tokio::fs::read(&snapshot_path)
+            .await
+            .and_then(|data| at_rest::open_in(&config.code_home, data))
+        {
+            Ok(json) => match serde_json::from_slice::<crate::history::HistorySnapshot>(&json) {
                 Ok(snapshot) => Some(snapshot),
                 Err(e) => {
                     warn!(
@@ -327,7 +335,12 @@ impl RolloutRecorder {
         Ok((recorder, saved))
     }
 
-    pub(crate) async fn get_rollout_history(path: &Path) -> std::io::Result<InitialHistory> {
+    /// Reads the rollout at `path`, opening lines sealed with the at-rest key
+    /// of `code_home`.
+    pub(crate) async fn get_rollout_history(
+        code_home: &Path,
+        path: &Path,
+    ) -> std::io::Result<InitialHistory> {
         info!("Resuming rollout from {path:?}");
         let text = tokio::fs::read_to_string(path).await?;
         if text.trim().is_empty() {
@@ -340,7 +353,8 @@ impl RolloutRecorder {
             if line.trim().is_empty() {
                 continue;
             }
-            let v: Value = match serde_json::from_str(line) {
+            let line = at_rest::open_line_in(code_home, line)?;
+            let v: Value = match serde_json::from_str(&line) {
                 Ok(v) => v,
                 Err(e) => {
                     warn!("failed to parse line as JSON: {line:?}, error: {e}");
@@ -489,13 +503,14 @@ fn create_log_file(
 
 async fn rollout_writer(
     file: tokio::fs::File,
+    cipher: Option<Arc<AtRestCipher>>,
     mut rx: mpsc::Receiver<RolloutCmd>,
     mut meta: Option<SessionMeta>,
     cwd: std::path::PathBuf,
     snapshot_path: PathBuf,
     mut catalog_state: Option<CatalogUpdateState>,
 ) -> std::io::Result<()> {
-    let mut writer = JsonlWriter { file };
+    let mut writer = JsonlWriter { file, cipher };
 
     // If we have a meta, collect git info asynchronously and write meta first
     if let Some(session_meta) = meta.take() {
@@ -551,7 +566,9 @@ async fn rollout_writer(
                 }
             }
             RolloutCmd::SetSnapshot(snapshot) => {
-                if let Err(err) = write_snapshot(&snapshot_path, &snapshot).await {
+                if let Err(err) =
+                    write_snapshot(&snapshot_path, &snapshot, writer.cipher.as_deref()).await
+                {
                     warn!("failed to persist history snapshot: {err}");
                 }
             }
@@ -564,14 +581,23 @@ async fn rollout_writer(
     Ok(())
 }
 
-async fn write_snapshot(path: &Path, snapshot: &serde_json::Value) -> std::io::Result<()> {
-    let json = serde_json::to_vec(snapshot)
+async fn write_snapshot(
+    path: &Path,
+    snapshot: &serde_json::Value,
+    cipher: Option<&AtRestCipher>,
+) -> std::io::Result<()> {
+    let mut json = serde_json::to_vec(snapshot)
         .map_err(|e| IoError::other(format!("failed to serialize history snapshot: {e}")))?;
+    if let Some(cipher) = cipher {
+        json = cipher.seal(&json)?;
+    }
     tokio::fs::write(path, json).await
 }
 
 struct JsonlWriter {
     file: tokio::fs::File,
+    /// Seals each line with `encrypt_at_rest`.
+    cipher: Option<Arc<AtRestCipher>>,
 }
 
 impl JsonlWriter {
@@ -595,6 +621,9 @@ impl JsonlWriter {
     }
     async fn write_line(&mut self, item: &impl serde::Serialize) -> std::io::Result<()> {
         let mut json = serde_json::to_string(item)?;
+        if let Some(cipher) = self.cipher.as_deref() {
+            json = cipher.seal_line(&json)?;
+        }
         json.push('\n');
         self.file.write_all(json.as_bytes()).await?;
         self.file.flush().await?;
@@ -0,0 +1,876 @@
+//! SQLite index of session rollouts, stored at
+//! `<code_home>/sessions/index/sessions.sqlite`.
+//!
+//! The index remembers the size and modification time of every rollout file
+//! it has read, so reconciling only parses files that are new or changed
+//! since the last query. It is created (or rebuilt after a schema change) on
+//! first use. Tags live in their own table and survive rebuilds.
+//!
+//! User and assistant messages are kept in an FTS5 table for full-text
+//! search across sessions.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fs::Metadata;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::UNIX_EPOCH;
+
+use anyhow::Context;
+use anyhow::Result;
+use code_protocol::models::ContentItem;
+use code_protocol::models::ResponseItem;
+use code_protocol::protocol::InputMessageKind;
+use code_protocol::protocol::RolloutItem;
+use code_protocol::protocol::RolloutLine;
+use code_protocol::protocol::SessionSource;
+use rusqlite::Connection;
+use rusqlite::OptionalExtension;
+use rusqlite::Transaction;
+use rusqlite::params;
+use rusqlite::params_from_iter;
+use rusqlite::types::Value as SqlValue;
+use tokio::io::AsyncBufReadExt;
+use tokio::io::BufReader;
+use tokio::task;
+use uuid::Uuid;
+
+use super::SESSIONS_SUBDIR;
+use super::catalog::INDEX_SUBDIR;
+use super::catalog::SessionIndexEntry;
+use super::catalog::is_system_status_snippet;
+use super::catalog::parse_rollout_file;
+use super::catalog::should_replace;
+use crate::session_catalog::SessionQuery;
+use crate::session_catalog::SessionSearchHit;
+
+const DATABASE_FILENAME: &str = "sessions.sqlite";
+/// Bump when the `rollout_files`, `sessions`, or `messages` tables change;
+/// they are then dropped and rebuilt from the rollouts.
+const SCHEMA_VERSION: i64 = 2;
+/// Length of search snippets, in tokens.
+const SNIPPET_TOKENS: i64 = 24;
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS rollout_files (
+    path TEXT PRIMARY KEY,
+    size INTEGER NOT NULL,
+    mtime_ns INTEGER NOT NULL,
+    session_id TEXT
+);
+CREATE INDEX IF NOT EXISTS rollout_files_by_session ON rollout_files (session_id);
+CREATE TABLE IF NOT EXISTS sessions (
+    session_id TEXT PRIMARY KEY,
+    rollout_path TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    last_event_at TEXT NOT NULL,
+    cwd_real TEXT NOT NULL,
+    git_project_root TEXT,
+    session_source TEXT NOT NULL,
+    message_count INTEGER NOT NULL,
+    user_message_count INTEGER NOT NULL,
+    archived INTEGER NOT NULL,
+    deleted INTEGER NOT NULL,
+    entry TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS sessions_by_recency
+    ON sessions (last_event_at DESC, created_at DESC, session_id DESC);
+CREATE INDEX IF NOT EXISTS sessions_by_cwd ON sessions (cwd_real);
+CREATE INDEX IF NOT EXISTS sessions_by_git_root ON sessions (git_project_root);
+CREATE TABLE IF NOT EXISTS session_tags (
+    session_id TEXT NOT NULL,
+    tag TEXT NOT NULL,
+    PRIMARY KEY (session_id, tag)
+);
+CREATE VIRTUAL TABLE IF NOT EXISTS messages USING fts5 (
+    text,
+    path UNINDEXED,
+    session_id UNINDEXED,
+    role UNINDEXED,
+    timestamp UNINDEXED,
+    tokenize = 'porter unicode61'
+);
+";
+
+const ORDER_BY: &str = "ORDER BY last_event_at DESC, created_at DESC, session_id DESC";
+
+/// Size and modification time of a rollout file, used to skip files that
+/// have not changed since they were indexed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileStamp {
+    size: i64,
+    mtime_ns: i64,
+}
+
+/// A rollout file found on disk.
+#[derive(Debug)]
+struct RolloutFile {
+    /// Relative to `code_home`, as stored in the index.
+    relative: String,
+    absolute: PathBuf,
+    stamp: FileStamp,
+}
+
+/// A rollout file with what was read from it.
+#[derive(Debug)]
+struct ParsedFile {
+    file: RolloutFile,
+    entry: Option<SessionIndexEntry>,
+    messages: Vec<IndexedMessage>,
+}
+
+/// A user or assistant message, as indexed for search.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct IndexedMessage {
+    role: &'static str,
+    timestamp: String,
+    text: String,
+}
+
+/// What reconciling changed.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct ReconcileStats {
+    pub(crate) parsed: usize,
+    pub(crate) removed: usize,
+}
+
+pub(crate) struct SessionIndex {
+    conn: Connection,
+}
+
+impl SessionIndex {
+    /// Opens the index under `code_home`, creating it when missing. A file
+    /// that cannot be opened as the index (for example a corrupt one) is
+    /// replaced with an empty index.
+    pub(crate) fn open(code_home: &Path) -> Result<Self> {
+        let path = database_path(code_home);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create {}", parent.display()))?;
+        }
+        match Self::open_at(&path) {
+            Ok(index) => Ok(index),
+            Err(err) if path.exists() => {
+                tracing::warn!("rebuilding session index {}: {err:#}", path.display());
+                std::fs::remove_file(&path)
+                    .with_context(|| format!("failed to remove {}", path.display()))?;
+                Self::open_at(&path)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    fn open_at(path: &Path) -> Result<Self> {
+        let conn = Connection::open(path)
+            .with_context(|| format!("failed to open session index {}", path.display()))?;
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version != SCHEMA_VERSION {
+            conn.execute_batch(
+                "DROP TABLE IF EXISTS rollout_files;
+                 DROP TABLE IF EXISTS sessions;
+                 DROP TABLE IF EXISTS messages;",
+            )?;
+        }
+        conn.execute_batch(SCHEMA)?;
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+        Ok(Self { conn })
+    }
+
+    /// Sessions matching `query`, newest first.
+    pub(crate) fn query(&self, query: &SessionQuery) -> Result<Vec<SessionIndexEntry>> {
+        let mut sql = String::from("SELECT entry FROM sessions s WHERE 1 = 1");
+        let mut args = session_filters(query, &mut sql)?;
+        sql.push(' ');
+        sql.push_str(ORDER_BY);
+        if let Some(limit) = query.limit {
+            sql.push_str(" LIMIT ?");
+            args.push(SqlValue::Integer(limit as i64));
+        }
+        self.entries(&sql, params_from_iter(args))
+    }
+
+    /// Messages matching `text` in sessions matching `query`, best match
+    /// first and at most one hit per session. Every word of `text` must
+    /// appear in the message; a trailing `*` matches a prefix.
+    pub(crate) fn search(&self, text: &str, query: &SessionQuery) -> Result<Vec<SessionSearchHit>> {
+        let Some(expression) = match_expression(text) else {
+            return Ok(Vec::new());
+        };
+        let mut sql = format!(
+            "SELECT s.entry, messages.role, messages.timestamp,
+                    snippet(messages, 0, '[', ']', '…', {SNIPPET_TOKENS}), messages.rank
+             FROM messages
+             JOIN sessions s
+               ON s.session_id = messages.session_id AND s.rollout_path = messages.path
+             WHERE messages MATCH ?"
+        );
+        let mut args = vec![SqlValue::Text(expression)];
+        args.extend(session_filters(query, &mut sql)?);
+        sql.push_str(" ORDER BY messages.rank");
+
+        let mut statement = self.conn.prepare(&sql)?;
+        let mut rows = statement.query(params_from_iter(args))?;
+        let mut seen = HashSet::new();
+        let mut hits = Vec::new();
+        while let Some(row) = rows.next()? {
+            if query.limit.is_some_and(|limit| hits.len() >= limit) {
+                break;
+            }
+            let mut entry: SessionIndexEntry = serde_json::from_str(&row.get::<_, String>(0)?)?;
+            if !seen.insert(entry.session_id) {
+                continue;
+            }
+            entry.tags = self.tags(&entry.session_id)?;
+            let snippet: String = row.get(3)?;
+            hits.push(SessionSearchHit {
+                session_id: entry.session_id,
+                role: row.get(1)?,
+                timestamp: row.get(2)?,
+                snippet: snippet.split_whitespace().collect::<Vec<_>>().join(" "),
+                score: -row.get::<_, f64>(4)?,
+                entry,
+            });
+        }
+        Ok(hits)
+    }
+
+    /// The newest session whose id starts with `id_prefix`, ignoring case.
+    pub(crate) fn find_by_id(&self, id_prefix: &str) -> Result<Option<SessionIndexEntry>> {
+        let pattern = format!(
+            "{}%",
+            id_prefix
+                .to_ascii_lowercase()
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_")
+        );
+        let sql = format!(
+            "SELECT entry FROM sessions WHERE session_id LIKE ? ESCAPE '\\' {ORDER_BY} LIMIT 1"
+        );
+        Ok(self.entries(&sql, params![pattern])?.pop())
+    }
+
+    /// Replaces the tags of `session_id`.
+    pub(crate) fn set_tags(&mut self, session_id: Uuid, tags: &[String]) -> Result<()> {
+        let session_id = session_id.to_string();
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "DELETE FROM session_tags WHERE session_id = ?",
+            params![session_id],
+        )?;
+        for tag in tags {
+            tx.execute(
+                "INSERT OR IGNORE INTO session_tags (session_id, tag) VALUES (?, ?)",
+                params![session_id, tag],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn entries(&self, sql: &str, args: impl rusqlite::Params) -> Result<Vec<SessionIndexEntry>> {
+        let mut statement = self.conn.prepare(sql)?;
+        let rows = statement.query_map(args, |row| row.get::<_, String>(0))?;
+        let mut entries = Vec::new();
+        for row in rows {
+            let mut entry: SessionIndexEntry = serde_json::from_str(&row?)?;
+            entry.tags = self.tags(&entry.session_id)?;
+            entries.push(entry);
+        }
+        Ok(entries)
+    }
+
+    fn tags(&self, session_id: &Uuid) -> Result<Vec<String>> {
+        let mut statement = self
+            .conn
+            .prepare_cached("SELECT tag FROM session_tags WHERE session_id = ? ORDER BY tag")?;
+        let tags = statement
+            .query_map(params![session_id.to_string()], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        Ok(tags)
+    }
+
+    fn stamps(&self) -> Result<HashMap<String, FileStamp>> {
+        let mut statement = self
+            .conn
+            .prepare("SELECT path, size, mtime_ns FROM rollout_files")?;
+        let stamps = statement
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    FileStamp {
+                        size: row.get(1)?,
+                        mtime_ns: row.get(2)?,
+                    },
+                ))
+            })?
+            .collect::<rusqlite::Result<HashMap<_, _>>>()?;
+        Ok(stamps)
+    }
+
+    /// Records parsed files and forgets removed ones. Returns the other
+    /// rollout files of sessions whose indexed file went away, which must be
+    /// parsed to pick the session's new entry.
+    fn apply(&mut self, parsed: Vec<ParsedFile>, removed: &[String]) -> Result<Vec<String>> {
+        let tx = self.conn.transaction()?;
+        let mut orphaned = HashSet::new();
+        for path in removed {
+            tx.execute("DELETE FROM rollout_files WHERE path = ?", params![path])?;
+            tx.execute("DELETE FROM messages WHERE path = ?", params![path])?;
+            orphaned.extend(remove_sessions_at(&tx, path)?);
+        }
+        for ParsedFile {
+            file,
+            entry,
+            messages,
+        } in parsed
+        {
+            tx.execute(
+                "INSERT OR REPLACE INTO rollout_files (path, size, mtime_ns, session_id)
+                 VALUES (?, ?, ?, ?)",
+                params![
+                    file.relative,
+                    file.stamp.size,
+                    file.stamp.mtime_ns,
+                    entry.as_ref().map(|entry| entry.session_id.to_string()),
+                ],
+            )?;
+            orphaned.extend(remove_sessions_at(&tx, &file.relative)?);
+            tx.execute(
+                "DELETE FROM messages WHERE path = ?",
+                params![file.relative],
+            )?;
+            if let Some(entry) = entry {
+                let session_id = entry.session_id.to_string();
+                for message in messages {
+                    tx.execute(
+                        "INSERT INTO messages (text, path, session_id, role, timestamp)
+                         VALUES (?, ?, ?, ?, ?)",
+                        params![
+                            message.text,
+                            file.relative,
+                            session_id,
+                            message.role,
+                            message.timestamp,
+                        ],
+                    )?;
+                }
+                orphaned.remove(&session_id);
+                upsert_session(&tx, entry)?;
+            }
+        }
+        let mut reparse = Vec::new();
+        for session_id in orphaned {
+            let mut statement =
+                tx.prepare_cached("SELECT path FROM rollout_files WHERE session_id = ?")?;
+            let paths = statement
+                .query_map(params![session_id], |row| row.get::<_, String>(0))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            reparse.extend(paths);
+        }
+        tx.commit()?;
+        Ok(reparse)
+    }
+}
+
+/// Brings the index under `code_home` up to date with the rollout files.
+pub(crate) async fn reconcile(code_home: &Path) -> Result<ReconcileStats> {
+    let home = code_home.to_path_buf();
+    let known = task::spawn_blocking(move || SessionIndex::open(&home)?.stamps())
+        .await
+        .context("session index task panicked")??;
+
+    let sessions_root = code_home.join(SESSIONS_SUBDIR);
+    let files = scan_rollout_files(code_home, &sessions_root).await?;
+    let seen: HashSet<&str> = files.iter().map(|file| file.relative.as_str()).collect();
+    let removed: Vec<String> = known
+        .keys()
+        .filter(|path| !seen.contains(path.as_str()))
+        .cloned()
+        .collect();
+    let changed: Vec<RolloutFile> = files
+        .into_iter()
+        .filter(|file| known.get(&file.relative) != Some(&file.stamp))
+        .collect();
+
+    let mut stats = ReconcileStats {
+        parsed: changed.len(),
+        removed: removed.len(),
+    };
+    if changed.is_empty() && removed.is_empty() {
+        return Ok(stats);
+    }
+
+    let parsed = parse_files(changed, code_home, &sessions_root).await;
+    let reparse = apply(code_home, parsed, removed).await?;
+    if !reparse.is_empty() {
+        let mut files = Vec::with_capacity(reparse.len());
+        for relative in reparse {
+            let path = code_home.join(relative);
+            if let Ok(metadata) = tokio::fs::metadata(&path).await
+                && let Some(file) = rollout_file(code_home, path, &metadata)
+            {
+                files.push(file);
+            }
+        }
+        stats.parsed += files.len();
+        let parsed = parse_files(files, code_home, &sessions_root).await;
+        apply(code_home, parsed, Vec::new()).await?;
+    }
+    Ok(stats)
+}
+
+async fn apply(
+    code_home: &Path,
+    parsed: Vec<ParsedFile>,
+    removed: Vec<String>,
+) -> Result<Vec<String>> {
+    let home = code_home.to_path_buf();
+    task::spawn_blocking(move || SessionIndex::open(&home)?.apply(parsed, &removed))
+        .await
+        .context("session index task panicked")?
+}
+
+async fn parse_files(
+    files: Vec<RolloutFile>,
+    code_home: &Path,
+    sessions_root: &Path,
+) -> Vec<ParsedFile> {
+    let mut parsed = Vec::with_capacity(files.len());
+    for file in files {
+        let entry = parse_rollout_file(&file.absolute, sessions_root).await;
+        let messages = match entry {
+            Some(_) => read_messages(code_home, &file.absolute).await,
+            None => Vec::new(),
+        };
+        parsed.push(ParsedFile {
+            file,
+            entry,
+            messages,
+        });
+    }
+    parsed
+}
+
+/// User and assistant messages of a rollout, skipping the environment and
+/// instruction messages injected at the start of a session.
+async fn read_messages(code_home: &Path, path: &Path) -> Vec<IndexedMessage> {
+    let file = match tokio::fs::File::open(path).await {
+        Ok(file) => file,
+        Err(err) => {
+            tracing::warn!("failed to open rollout file {}: {err}", path.display());
+            return Vec::new();
+        }
+    };
+    let mut lines = BufReader::new(file).lines();
+    let mut messages = Vec::new();
+    while let Some(line) = lines.next_line().await.ok().flatten() {
+        let Ok(line) = crate::at_rest::open_line_in(code_home, &line) else {
+            continue;
+        };
+        let Ok(RolloutLine {
+            timestamp,
+            item: RolloutItem::ResponseItem(ResponseItem::Message { role, content, .. }),
+        }) = serde_json::from_str::<RolloutLine>(&line)
+        else {
+            continue;
+        };
+        let role = match role.as_str() {
+            "user" => "user",
+            "assistant" => "assistant",
+            _ => continue,
+        };
+        let text = content
+            .iter()
+            .filter_map(|item| match item {
+                ContentItem::InputText { text } | ContentItem::OutputText { text } => {
+                    Some(text.as_str())
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        if text.trim().is_empty()
+            || (role == "user"
+                && (is_system_status_snippet(&text)
+                    || !matches!(
+                        InputMessageKind::from(("user", text.as_str())),
+                        InputMessageKind::Plain
+                    )))
+        {
+            continue;
+        }
+        messages.push(IndexedMessage {
+            role,
+            timestamp,
+            text,
+        });
+    }
+    messages
+}
+
+/// FTS5 expression requiring every word of `text`. Words are quoted so
+/// punctuation in them is not read as query syntax.
+fn match_expression(text: &str) -> Option<String> {
+    let terms: Vec<String> = text
+        .split_whitespace()
+        .filter_map(|word| {
+            let (word, prefix) = match word.strip_suffix('*') {
+                Some(stem) => (stem, "*"),
+                None => (word, ""),
+            };
+            (!word.is_empty()).then(|| format!("\"{}\"{prefix}", word.replace('"', "\"\"")))
+        })
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+/// Appends the `WHERE` conditions for `query` to `sql`, which selects from
+/// `sessions` aliased as `s`, and returns their arguments.
+fn session_filters(query: &SessionQuery, sql: &mut String) -> Result<Vec<SqlValue>> {
+    let mut args = Vec::new();
+    if !query.include_archived {
+        sql.push_str(" AND s.archived = 0");
+    }
+    if !query.include_deleted {
+        sql.push_str(" AND s.deleted = 0");
+    }
+    if let Some(cwd) = &query.cwd {
+        sql.push_str(" AND s.cwd_real = ?");
+        args.push(SqlValue::Text(path_text(cwd)));
+    }
+    if let Some(git_root) = &query.git_root {
+        sql.push_str(" AND s.git_project_root = ?");
+        args.push(SqlValue::Text(path_text(git_root)));
+    }
+    if !query.sources.is_empty() {
+        let placeholders = vec!["?"; query.sources.len()].join(", ");
+        sql.push_str(&format!(" AND s.session_source IN ({placeholders})"));
+        for source in &query.sources {
+            args.push(SqlValue::Text(source_text(source)?));
+        }
+    }
+    if query.min_user_messages > 0 {
+        sql.push_str(" AND s.user_message_count >= ?");
+        args.push(SqlValue::Integer(query.min_user_messages as i64));
+    }
+    if let Some(tag) = &query.tag {
+        sql.push_str(" AND s.session_id IN (SELECT session_id FROM session_tags WHERE tag = ?)");
+        args.push(SqlValue::Text(tag.clone()));
+    }
+    Ok(args)
+}
+
+/// Indexes `entry` unless the session already has a newer one from another
+/// rollout file.
+fn upsert_session(tx: &Transaction<'_>, entry: SessionIndexEntry) -> Result<()> {
+    let session_id = entry.session_id.to_string();
+    let existing: Option<String> = tx
+        .query_row(
+            "SELECT entry FROM sessions WHERE session_id = ?",
+            params![session_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+    if let Some(existing) = existing {
+        let existing: SessionIndexEntry = serde_json::from_str(&existing)?;
+        if !should_replace(&existing, &entry) {
+            return Ok(());
+        }
+    }
+    tx.execute(
+        "INSERT OR REPLACE INTO sessions (
+            session_id, rollout_path, created_at, last_event_at, cwd_real, git_project_root,
+            session_source, message_count, user_message_count, archived, deleted, entry
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        params![
+            session_id,
+            path_text(&entry.rollout_path),
+            entry.created_at,
+            entry.last_event_at,
+            path_text(&entry.cwd_real),
+            entry.git_project_root.as_deref().map(path_text),
+            source_text(&entry.session_source)?,
+            entry.message_count as i64,
+            entry.user_message_count as i64,
+            entry.archived,
+            entry.deleted,
+            serde_json::to_string(&entry)?,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Drops the sessions indexed from `path` and returns their ids.
+fn remove_sessions_at(tx: &Transaction<'_>, path: &str) -> Result<Vec<String>> {
+    let mut statement =
+        tx.prepare_cached("SELECT session_id FROM sessions WHERE rollout_path = ?")?;
+    let session_ids = statement
+        .query_map(params![path], |row| row.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    tx.execute("DELETE FROM sessions WHERE rollout_path = ?", params![path])?;
+    Ok(session_ids)
+}
+
+/// Every `rollout-*.jsonl` file under `sessions_root`.
+async fn scan_rollout_files(code_home: &Path, sessions_root: &Path) -> Result<Vec<RolloutFile>> {
+    let mut files = Vec::new();
+    if !tokio::fs::try_exists(sessions_root).await.unwrap_or(false) {
+        return Ok(files);
+    }
+    let mut queue = vec![sessions_root.to_path_buf()];
+    while let Some(dir) = queue.pop() {
+        let mut read_dir = tokio::fs::read_dir(&dir)
+            .await
+            .with_context(|| format!("failed to read {}", dir.display()))?;
+        while let Some(entry) = read_dir.next_entry().await? {
+            let path = entry.path();
+            let metadata = entry.metadata().await?;
+            if metadata.is_dir() {
+                queue.push(path);
+            } else if metadata.is_file()
+                && let Some(name) = path.file_name().and_then(|name| name.to_str())
+                && name.starts_with("rollout-")
+                && name.ends_with(".jsonl")
+                && let Some(file) = rollout_file(code_home, path, &metadata)
+            {
+                files.push(file);
+            }
+        }
+    }
+    Ok(files)
+}
+
+fn rollout_file(code_home: &Path, path: PathBuf, metadata: &Metadata) -> Option<RolloutFile> {
+    let mtime_ns = metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |since| since.as_nanos() as i64);
+    Some(RolloutFile {
+        relative: path_text(path.strip_prefix(code_home).ok()?),
+        absolute: path,
+        stamp: FileStamp {
+            size: metadata.len() as i64,
+            mtime_ns,
+        },
+    })
+}
+
+fn database_path(code_home: &Path) -> PathBuf {
+    code_home.join(INDEX_SUBDIR).join(DATABASE_FILENAME)
+}
+
+fn path_text(path: &Path) -> String {
+    path.to_string_lossy().into_owned()
+}
+
+fn source_text(source: &SessionSource) -> Result<String> {
+    Ok(serde_json::to_string(source)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn entry(session_id: Uuid, rollout_path: &str, last_event_at: &str) -> SessionIndexEntry {
+        SessionIndexEntry {
+            session_id,
+            rollout_path: PathBuf::from(rollout_path),
+            snapshot_path: None,
+            created_at: "2025-01-01T10:00:00.000Z".to_string(),
+            last_event_at: last_event_at.to_string(),
+            cwd_real: PathBuf::from("/repo"),
+            cwd_display: "/repo".to_string(),
+            git_project_root: Some(PathBuf::from("/repo")),
+            git_branch: None,
+            model_provider: None,
+            session_source: SessionSource::Cli,
+            message_count: 4,
+            user_message_count: 1,
+            last_user_snippet: None,
+            sync_origin_device: None,
+            sync_version: 0,
+            archived: false,
+            deleted: false,
+            tags: Vec::new(),
+        }
+    }
+
+    fn parsed(entry: SessionIndexEntry, messages: &[(&'static str, &str)]) -> ParsedFile {
+        let relative = path_text(&entry.rollout_path);
+        ParsedFile {
+            file: RolloutFile {
+                absolute: PathBuf::from(&relative),
+                relative,
+                stamp: FileStamp {
+                    size: 1,
+                    mtime_ns: 1,
+                },
+            },
+            entry: Some(entry),
+            messages: messages
+                .iter()
+                .map(|(role, text)| IndexedMessage {
+                    role,
+                    timestamp: "2025-01-01T10:01:00.000Z".to_string(),
+                    text: text.to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn keeps_the_newest_file_of_a_session_and_falls_back_when_it_goes() {
+        let temp = TempDir::new().unwrap();
+        let mut index = SessionIndex::open(temp.path()).unwrap();
+        let id = Uuid::new_v4();
+        let older = "sessions/a/rollout-1.jsonl";
+        let newer = "sessions/b/rollout-2.jsonl";
+
+        let reparse = index
+            .apply(
+                vec![
+                    parsed(entry(id, newer, "2025-01-02T00:00:00Z"), &[]),
+                    parsed(entry(id, older, "2025-01-01T00:00:00Z"), &[]),
+                ],
+                &[],
+            )
+            .unwrap();
+        assert!(reparse.is_empty());
+        let found = index
+            .find_by_id(&id.to_string()[..8].to_uppercase())
+            .unwrap();
+        assert_eq!(
+            found.map(|entry| entry.rollout_path),
+            Some(PathBuf::from(newer))
+        );
+
+        let reparse = index.apply(Vec::new(), &[newer.to_string()]).unwrap();
+        assert_eq!(reparse, vec![older.to_string()]);
+        assert_eq!(index.find_by_id(&id.to_string()).unwrap(), None);
+    }
+
+    #[test]
+    fn filters_by_query_and_tag() {
+        let temp = TempDir::new().unwrap();
+        let mut index = SessionIndex::open(temp.path()).unwrap();
+        let first = Uuid::new_v4();
+        let second = Uuid::new_v4();
+        let mut exec = entry(second, "sessions/rollout-2.jsonl", "2025-01-03T00:00:00Z");
+        exec.session_source = SessionSource::Exec;
+        index
+            .apply(
+                vec![
+                    parsed(
+                        entry(first, "sessions/rollout-1.jsonl", "2025-01-02T00:00:00Z"),
+                        &[],
+                    ),
+                    parsed(exec, &[]),
+                ],
+                &[],
+            )
+            .unwrap();
+        index.set_tags(first, &["retry".to_string()]).unwrap();
+
+        let ids = |query: &SessionQuery| -> Vec<Uuid> {
+            index
+                .query(query)
+                .unwrap()
+                .into_iter()
+                .map(|entry| entry.session_id)
+                .collect()
+        };
+        assert_eq!(ids(&SessionQuery::default()), vec![second, first]);
+        assert_eq!(
+            ids(&SessionQuery {
+                sources: vec![SessionSource::Cli],
+                cwd: Some(PathBuf::from("/repo")),
+                ..SessionQuery::default()
+            }),
+            vec![first]
+        );
+        assert_eq!(
+            ids(&SessionQuery {
+                tag: Some("retry".to_string()),
+                ..SessionQuery::default()
+            }),
+            vec![first]
+        );
+        assert_eq!(
+            index.query(&SessionQuery::default()).unwrap()[1].tags,
+            vec!["retry".to_string()]
+        );
+    }
+
+    #[test]
+    fn search_ranks_one_hit_per_session() {
+        let temp = TempDir::new().unwrap();
+        let mut index = SessionIndex::open(temp.path()).unwrap();
+        let retry = Uuid::new_v4();
+        let other = Uuid::new_v4();
+        index
+            .apply(
+                vec![
+                    parsed(
+                        entry(retry, "sessions/rollout-1.jsonl", "2025-01-02T00:00:00Z"),
+                        &[
+                            ("user", "Please refactor the retry logic in the client"),
+                            ("assistant", "I refactored the retry loop and its logic."),
+                        ],
+                    ),
+                    parsed(
+                        entry(other, "sessions/rollout-2.jsonl", "2025-01-03T00:00:00Z"),
+                        &[("user", "Add a logo to the README")],
+                    ),
+                ],
+                &[],
+            )
+            .unwrap();
+
+        let hits = index
+            .search("refactoring retry", &SessionQuery::default())
+            .unwrap();
+        assert_eq!(
+            hits.iter().map(|hit| hit.session_id).collect::<Vec<_>>(),
+            vec![retry]
+        );
+        assert!(hits[0].snippet.contains("[retry]"), "{}", hits[0].snippet);
+        assert_eq!(hits[0].timestamp, "2025-01-01T10:01:00.000Z");
+
+        let hits = index.search("lo*", &SessionQuery::default()).unwrap();
+        assert_eq!(hits.len(), 2);
+        assert!(
+            index
+                .search("  ", &SessionQuery::default())
+                .unwrap()
+                .is_empty()
+        );
+        assert!(
+            index
+                .search(
+                    "README",
+                    &SessionQuery {
+                        cwd: Some(PathBuf::from("/elsewhere")),
+                        ..SessionQuery::default()
+                    }
+                )
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn quotes_search_words() {
+        assert_eq!(
+            match_expression(r#"retry-logic say "hi" fo*"#),
+            Some(r#""retry-logic" "say" """hi""" "fo"*"#.to_string())
+        );
+        assert_eq!(match_expression(" * "), None);
+    }
+}
@@ -710,3 +710,57 @@ async fn test_source_filter_excludes_non_matching_sessions() {
         path.ends_with("rollout-2025-08-01T10-00-00-00000000-0000-0000-0000-00000000004d.jsonl")
     }));
 }
+
+#[tokio::test]
+async fn sealed_rollout_resumes_after_encryption_is_turned_off() {
+    let temp = TempDir::new().unwrap();
+    let code_home = temp.path();
+    let mut overrides = ConfigOverrides::default();
+    overrides.cwd = Some(code_home.to_path_buf());
+    let mut config = Config::load_from_base_config_with_overrides(
+        ConfigToml::default(),
+        overrides,
+        code_home.to_path_buf(),
+    )
+    .unwrap();
+    config.encrypt_at_rest = true;
+
+    let recorder = crate::rollout::RolloutRecorder::new(
+        &config,
+        crate::rollout::recorder::RolloutRecorderParams::new(
+            ConversationId::new(),
+            None,
+            SessionSource::Cli,
+        ),
+    )
+    .await
+    .unwrap();
+    let message = ResponseItem::Message {
+        id: None,
+        role: "assistant".to_string(),
+        content: vec![ContentItem::OutputText {
+            text: "the secret plan".to_string(),
+        }],
+    };
+    recorder
+        .record_items(&[RolloutItem::ResponseItem(message.clone())])
+        .await
+        .unwrap();
+    recorder.shutdown().await.unwrap();
+    let rollout_path = recorder.rollout_path.clone();
+    assert!(
+        !fs::read_to_string(&rollout_path)
+            .unwrap()
+            .contains("the secret plan")
+    );
+
+    config.encrypt_at_rest = false;
+    let (recorder, saved) = crate::rollout::RolloutRecorder::resume(&config, &rollout_path)
+        .await
+        .unwrap();
+    recorder.shutdown().await.unwrap();
+    assert_eq!(
+        reconstruct_history_like_rollout(&saved.items),
+        vec![message]
+    );
+}
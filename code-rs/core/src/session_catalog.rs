@@ -1,4 +1,4 @@
-//! Async-friendly wrapper around the rollout session catalog.
+//! Async-friendly wrapper around the SQLite session index.
 
 use std::collections::HashMap;
 use std::path::Path;
@@ -12,9 +12,11 @@ use code_protocol::protocol::SessionSource;
 use once_cell::sync::OnceCell;
 use tokio::sync::Mutex as AsyncMutex;
 use tokio::task;
+use uuid::Uuid;
 
 use crate::rollout::catalog::SessionIndexEntry;
-use crate::rollout::catalog::{self as rollout_catalog};
+use crate::rollout::session_index::SessionIndex;
+use crate::rollout::session_index::{self};
 
 /// Query parameters for catalog lookups.
 #[derive(Debug, Clone, Default)]
@@ -31,90 +33,51 @@ pub struct SessionQuery {
     pub include_archived: bool,
     /// Include deleted sessions.
     pub include_deleted: bool,
+    /// Only sessions carrying this tag.
+    pub tag: Option<String>,
     /// Maximum number of rows to return.
     pub limit: Option<usize>,
 }
 
+/// A message matching a full-text search, with the session it belongs to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionSearchHit {
+    pub session_id: Uuid,
+    /// Timestamp of the matching message.
+    pub timestamp: String,
+    /// `user` or `assistant`.
+    pub role: String,
+    /// Excerpt of the message with matched words wrapped in `[` and `]`.
+    pub snippet: String,
+    /// Relevance; higher is better.
+    pub score: f64,
+    pub entry: SessionIndexEntry,
+}
+
 /// Public catalog facade used by TUI/CLI/Exec entrypoints.
 pub struct SessionCatalog {
     code_home: PathBuf,
-    cache: Arc<AsyncMutex<Option<rollout_catalog::SessionCatalog>>>,
+    /// Serializes index updates from catalogs sharing a code home.
+    lock: Arc<AsyncMutex<()>>,
 }
 
 impl SessionCatalog {
     /// Create a catalog facade for the provided code home directory.
     pub fn new(code_home: PathBuf) -> Self {
-        let cache = catalog_cache_handle(&code_home);
-        Self { code_home, cache }
+        let lock = index_lock_handle(&code_home);
+        Self { code_home, lock }
     }
 
     /// Query the catalog with the provided filters, returning ordered entries.
     pub async fn query(&self, query: &SessionQuery) -> Result<Vec<SessionIndexEntry>> {
-        let catalog = self.load_inner().await?;
-        let mut rows = Vec::new();
-
-        let candidates: Vec<&SessionIndexEntry> = if let Some(cwd) = &query.cwd {
-            catalog.by_cwd(cwd)
-        } else if let Some(git_root) = &query.git_root {
-            catalog.by_git_root(git_root)
-        } else {
-            catalog.all_ordered()
-        };
-
-        for entry in candidates {
-            if !query.include_archived && entry.archived {
-                continue;
-            }
-            if !query.include_deleted && entry.deleted {
-                continue;
-            }
-            if let Some(cwd) = &query.cwd
-                && &entry.cwd_real != cwd
-            {
-                continue;
-            }
-            if let Some(git_root) = &query.git_root
-                && entry.git_project_root.as_ref() != Some(git_root)
-            {
-                continue;
-            }
-            if !query.sources.is_empty() && !query.sources.contains(&entry.session_source) {
-                continue;
-            }
-            if entry.user_message_count < query.min_user_messages {
-                continue;
-            }
-
-            rows.push(entry.clone());
-
-            if let Some(limit) = query.limit
-                && rows.len() >= limit
-            {
-                break;
-            }
-        }
-
-        Ok(rows)
+        let query = query.clone();
+        self.read(move |index| index.query(&query)).await
     }
 
     /// Find a session by UUID (prefix matches allowed, case-insensitive).
     pub async fn find_by_id(&self, id_prefix: &str) -> Result<Option<SessionIndexEntry>> {
-        let catalog = self.load_inner().await?;
-        let needle = id_prefix.to_ascii_lowercase();
-
-        let entry = catalog
-            .all_ordered()
-            .into_iter()
-            .find(|entry| {
-                entry
-                    .session_id
-                    .to_string()
-                    .to_ascii_lowercase()
-                    .starts_with(&needle)
-            })
-            .cloned();
-
-        Ok(entry)
+        let id_prefix = id_prefix.to_string();
+        self.read(move |index| index.find_by_id(&id_prefix)).await
     }
 
     /// Return the newest session matching the query.
@@ -125,38 +88,45 @@ impl SessionCatalog {
         Ok(rows.pop())
     }
 
+    /// Full-text search over user and assistant messages of the sessions
+    /// matching `query`, best match first with one hit per session.
+    pub async fn search(&self, text: &str, query: &SessionQuery) -> Result<Vec<SessionSearchHit>> {
+        let text = text.to_string();
+        let query = query.clone();
+        self.read(move |index| index.search(&text, &query)).await
+    }
+
+    /// Replace the tags of a session.
+    pub async fn set_tags(&self, session_id: Uuid, tags: Vec<String>) -> Result<()> {
+        let _guard = self.lock.lock().await;
+        let code_home = self.code_home.clone();
+        task::spawn_blocking(move || SessionIndex::open(&code_home)?.set_tags(session_id, &tags))
+            .await
+            .context("session index task panicked")?
+            .context("failed to update session tags")
+    }
+
     /// Convert a catalog entry to an absolute rollout path.
     pub fn entry_rollout_path(&self, entry: &SessionIndexEntry) -> PathBuf {
         entry_to_rollout_path(&self.code_home, entry)
     }
 
-    async fn load_inner(&self) -> Result<rollout_catalog::SessionCatalog> {
-        {
-            let mut guard = self.cache.lock().await;
-            if let Some(existing) = guard.as_mut() {
-                existing
-                    .reconcile(&self.code_home)
-                    .await
-                    .context("failed to reconcile session catalog")?;
-                return Ok(existing.clone());
-            }
-        }
-
+    /// Bring the index up to date with the rollout files, then run `read`
+    /// against it.
+    async fn read<T, F>(&self, read: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&SessionIndex) -> Result<T> + Send + 'static,
+    {
+        let _guard = self.lock.lock().await;
+        session_index::reconcile(&self.code_home)
+            .await
+            .context("failed to reconcile session index")?;
         let code_home = self.code_home.clone();
-        let mut catalog =
-            task::spawn_blocking(move || rollout_catalog::SessionCatalog::load(&code_home))
-                .await
-                .context("catalog task panicked")?
-                .context("failed to load session catalog")?;
-
-        catalog
-            .reconcile(&self.code_home)
+        task::spawn_blocking(move || read(&SessionIndex::open(&code_home)?))
             .await
-            .context("failed to reconcile session catalog")?;
-
-        let mut guard = self.cache.lock().await;
-        *guard = Some(catalog.clone());
-        Ok(catalog)
+            .context("session index task panicked")?
+            .context("failed to read session index")
     }
 }
 
@@ -165,14 +135,14 @@ pub fn entry_to_rollout_path(code_home: &Path, entry: &SessionIndexEntry) -> Pat
     code_home.join(&entry.rollout_path)
 }
 
-type SharedCatalog = Arc<AsyncMutex<Option<rollout_catalog::SessionCatalog>>>;
+type SharedLock = Arc<AsyncMutex<()>>;
 
-fn catalog_cache_handle(code_home: &Path) -> SharedCatalog {
-    static CACHE: OnceCell<Mutex<HashMap<PathBuf, SharedCatalog>>> = OnceCell::new();
-    let cache = CACHE.get_or_init(|| Mutex::new(HashMap::new()));
-    let mut guard = cache.lock().expect("session catalog cache poisoned");
+fn index_lock_handle(code_home: &Path) -> SharedLock {
+    static LOCKS: OnceCell<Mutex<HashMap<PathBuf, SharedLock>>> = OnceCell::new();
+    let locks = LOCKS.get_or_init(|| Mutex::new(HashMap::new()));
+    let mut guard = locks.lock().expect("session index locks poisoned");
     guard
         .entry(code_home.to_path_buf())
-        .or_insert_with(|| Arc::new(AsyncMutex::new(None)))
+        .or_insert_with(|| Arc::new(AsyncMutex::new(())))
         .clone()
 }
@@ -0,0 +1,400 @@
+//! Whole-session snapshots for checkpointed `exec` runs.
+//!
+//! A rollout file is enough to resume a conversation on the machine that
+//! wrote it, but a preemptible CI runner needs to carry a session to a fresh
+//! process, often on another host with an empty `CODE_HOME`. A
+//! [`SessionSnapshot`] bundles what that process needs into one archive file:
+//!
+//! - the session's rollout lines, verbatim, and its history snapshot;
+//! - the effective settings that shaped the turn (model, provider, reasoning
+//!   effort, approval and sandbox policy, cwd);
+//! - approval requests that were still waiting for an answer;
+//! - caller-owned state such as budgets and tool bookkeeping, stored as named
+//!   JSON sections so core does not need to know their types.
+//!
+//! [`SessionSnapshot::restore`] writes the rollout back under the new
+//! `CODE_HOME` and resumes it through [`ConversationManager`], keeping the
+//! original conversation id. Live process state (running commands, background
+//! agents, the in-flight model request) cannot be carried over; the restored
+//! session starts idle and pending approvals must be asked again.
+//!
+//! When `encrypt_at_rest` is enabled the archive is sealed with the install
+//! key, so the restoring process needs the same key (`CODE_AT_REST_KEY`).
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use chrono::DateTime;
+use chrono::Utc;
+use serde::Deserialize;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::AuthManager;
+use crate::ConversationManager;
+use crate::NewConversation;
+use crate::SESSIONS_SUBDIR;
+use crate::at_rest;
+use crate::at_rest::AtRestCipher;
+use crate::config::Config;
+use crate::config_types::ReasoningEffort;
+use crate::error::CodexErr;
+use crate::error::Result as CodexResult;
+use crate::model_family::derive_default_model_family;
+use crate::model_family::find_family_for_model;
+use crate::protocol::AskForApproval;
+use crate::protocol::SandboxPolicy;
+
+/// Bumped whenever the archive layout changes incompatibly.
+pub const SNAPSHOT_FORMAT_VERSION: u32 = 1;
+
+/// Subdirectory of `sessions/` that receives restored rollouts.
+const RESTORED_SUBDIR: &str = "restored";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionSnapshot {
+    pub format_version: u32,
+    pub session_id: String,
+    pub created_at: DateTime<Utc>,
+    pub config: SnapshotConfig,
+    /// File name of the original rollout, reused when restoring.
+    pub rollout_file_name: String,
+    /// Rollout lines exactly as they were written.
+    pub rollout: Vec<String>,
+    /// Contents of the rollout's `.snapshot.json` sibling, if any.
+    #[serde(default)]
+    pub history_snapshot: Option<Value>,
+    #[serde(default)]
+    pub pending_approvals: Vec<PendingApproval>,
+    /// Caller-owned state keyed by owner, e.g. `"auto_drive.budget"`.
+    #[serde(default)]
+    pub sections: BTreeMap<String, Value>,
+}
+
+/// Settings that shaped the session when it was captured.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotConfig {
+    pub model: String,
+    pub model_provider_id: String,
+    pub model_reasoning_effort: ReasoningEffort,
+    pub approval_policy: AskForApproval,
+    pub sandbox_policy: SandboxPolicy,
+    pub cwd: PathBuf,
+}
+
+/// An approval request that had not been answered at capture time.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingApproval {
+    pub call_id: String,
+    /// Command awaiting approval, for exec requests.
+    #[serde(default)]
+    pub command: Option<Vec<String>>,
+    /// Files the patch would change, for patch requests.
+    #[serde(default)]
+    pub changes: Vec<PathBuf>,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+impl SessionSnapshot {
+    /// Captures the session recorded at `rollout_path` with the settings in
+    /// `config`.
+    pub fn capture(config: &Config, rollout_path: &Path) -> io::Result<Self> {
+        let text = std::fs::read_to_string(rollout_path)?;
+        let rollout = text
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| at_rest::open_line_in(&config.code_home, line).map(Cow::into_owned))
+            .collect::<io::Result<Vec<String>>>()?;
+        let session_id = rollout
+            .iter()
+            .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+            .find(|line| line.get("type").and_then(Value::as_str) == Some("session_meta"))
+            .and_then(|line| line.pointer("/payload/id"))
+            .and_then(|id| id.as_str().map(str::to_string))
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("rollout {} has no session metadata", rollout_path.display()),
+                )
+            })?;
+        let rollout_file_name = rollout_path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| format!("rollout-{session_id}.jsonl"));
+
+        let history_snapshot = match std::fs::read(rollout_path.with_extension("snapshot.json")) {
+            Ok(json) => {
+                let json = at_rest::open_in(&config.code_home, json)?;
+                Some(serde_json::from_slice(&json).map_err(io::Error::other)?)
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+            Err(err) => return Err(err),
+        };
+
+        Ok(Self {
+            format_version: SNAPSHOT_FORMAT_VERSION,
+            session_id,
+            created_at: Utc::now(),
+            config: SnapshotConfig {
+                model: config.model.clone(),
+                model_provider_id: config.model_provider_id.clone(),
+                model_reasoning_effort: config.model_reasoning_effort,
+                approval_policy: config.approval_policy,
+                sandbox_policy: config.sandbox_policy.clone(),
+                cwd: config.cwd.clone(),
+            },
+            rollout_file_name,
+            rollout,
+            history_snapshot,
+            pending_approvals: Vec::new(),
+            sections: BTreeMap::new(),
+        })
+    }
+
+    pub fn add_pending_approval(&mut self, approval: PendingApproval) {
+        self.pending_approvals.push(approval);
+    }
+
+    /// Stores caller-owned state under `key`, replacing any previous value.
+    pub fn set_section<T: Serialize>(&mut self, key: &str, value: &T) -> io::Result<()> {
+        let value = serde_json::to_value(value).map_err(io::Error::other)?;
+        self.sections.insert(key.to_string(), value);
+        Ok(())
+    }
+
+    /// Reads the section stored under `key`, if present.
+    pub fn section<T: DeserializeOwned>(&self, key: &str) -> io::Result<Option<T>> {
+        self.sections
+            .get(key)
+            .map(|value| serde_json::from_value(value.clone()).map_err(io::Error::other))
+            .transpose()
+    }
+
+    /// Writes the archive to `path`, sealed when `config.encrypt_at_rest` is on.
+    pub fn write(&self, config: &Config, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_vec(self).map_err(io::Error::other)?;
+        let cipher = at_rest::cipher_for(config.encrypt_at_rest, &config.code_home)?;
+        at_rest::write_maybe_sealed(cipher.as_deref(), path, &json)
+    }
+
+    /// Loads an archive written by [`SessionSnapshot::write`].
+    pub fn load(config: &Config, path: &Path) -> io::Result<Self> {
+        let data = at_rest::open_in(&config.code_home, std::fs::read(path)?)?;
+        let snapshot: Self = serde_json::from_slice(&data).map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("malformed session snapshot {}: {err}", path.display()),
+            )
+        })?;
+        if snapshot.format_version > SNAPSHOT_FORMAT_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "session snapshot {} has format {}, newer than supported {SNAPSHOT_FORMAT_VERSION}",
+                    path.display(),
+                    snapshot.format_version
+                ),
+            ));
+        }
+        Ok(snapshot)
+    }
+
+    /// Applies the captured settings to `config`. The provider is only
+    /// switched when the restoring process knows it.
+    pub fn apply_to_config(&self, config: &mut Config) {
+        let settings = &self.config;
+        if config.model != settings.model {
+            config.model = settings.model.clone();
+            config.model_family = find_family_for_model(&config.model)
+                .unwrap_or_else(|| derive_default_model_family(&config.model));
+        }
+        if let Some(provider) = config.model_providers.get(&settings.model_provider_id) {
+            config.model_provider = provider.clone();
+            config.model_provider_id = settings.model_provider_id.clone();
+        }
+        config.model_reasoning_effort = settings.model_reasoning_effort;
+        config.approval_policy = settings.approval_policy;
+        config.sandbox_policy = settings.sandbox_policy.clone();
+        config.cwd = settings.cwd.clone();
+    }
+
+    /// Writes the rollout under `code_home/sessions/restored` and returns its
+    /// path. Lines and the history snapshot are sealed with `cipher` if given.
+    pub fn materialize_rollout(
+        &self,
+        code_home: &Path,
+        cipher: Option<&AtRestCipher>,
+    ) -> io::Result<PathBuf> {
+        // Only the file name is trusted from the archive, and the fallback
+        // name only from a well-formed session id.
+        let file_name = match Path::new(&self.rollout_file_name).file_name() {
+            Some(name) => name.to_string_lossy().into_owned(),
+            None => {
+                let session_id = Uuid::parse_str(&self.session_id).map_err(|_| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!(
+                            "session snapshot has an invalid session id {:?}",
+                            self.session_id
+                        ),
+                    )
+                })?;
+                format!("rollout-{session_id}.jsonl")
+            }
+        };
+        let dir = code_home.join(SESSIONS_SUBDIR).join(RESTORED_SUBDIR);
+        std::fs::create_dir_all(&dir)?;
+        let path = dir.join(file_name);
+        let mut text = String::new();
+        for line in &self.rollout {
+            match cipher {
+                Some(cipher) => text.push_str(&cipher.seal_line(line)?),
+                None => text.push_str(line),
+            }
+            text.push('\n');
+        }
+        std::fs::write(&path, text)?;
+        if let Some(history) = self.history_snapshot.as_ref() {
+            let json = serde_json::to_vec(history).map_err(io::Error::other)?;
+            at_rest::write_maybe_sealed(cipher, &path.with_extension("snapshot.json"), &json)?;
+        }
+        Ok(path)
+    }
+
+    /// Recreates the conversation in this process from the snapshot.
+    pub async fn restore(
+        &self,
+        manager: &ConversationManager,
+        mut config: Config,
+        auth_manager: Arc<AuthManager>,
+    ) -> CodexResult<NewConversation> {
+        self.apply_to_config(&mut config);
+        let cipher = at_rest::cipher_for(config.encrypt_at_rest, &config.code_home)?;
+        let rollout_path = self
+            .materialize_rollout(&config.code_home, cipher.as_deref())
+            .map_err(CodexErr::Io)?;
+        manager
+            .resume_conversation_from_rollout(config, rollout_path, auth_manager)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ConfigOverrides;
+    use crate::config::ConfigToml;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn round_trips_rollout_sections_and_settings() {
+        let source_home = tempfile::tempdir().unwrap();
+        let target_home = tempfile::tempdir().unwrap();
+        let mut config = Config::load_from_base_config_with_overrides(
+            ConfigToml::default(),
+            ConfigOverrides::default(),
+            source_home.path().to_path_buf(),
+        )
+        .unwrap();
+        config.approval_policy = AskForApproval::Never;
+
+        let rollout_path = source_home
+            .path()
+            .join("rollout-2025-01-01T00-00-00-abc.jsonl");
+        let lines = [
+            json!({"timestamp": "t0", "type": "session_meta", "payload": {"id": "abc"}}),
+            json!({"timestamp": "t1", "type": "response_item", "payload": {"type": "message"}}),
+        ];
+        let text: String = lines.iter().map(|line| format!("{line}\n")).collect();
+        std::fs::write(&rollout_path, text).unwrap();
+        std::fs::write(rollout_path.with_extension("snapshot.json"), "{\"v\":1}").unwrap();
+
+        let mut snapshot = SessionSnapshot::capture(&config, &rollout_path).unwrap();
+        snapshot.add_pending_approval(PendingApproval {
+            call_id: "call-1".to_string(),
+            command: Some(vec!["cargo".to_string(), "publish".to_string()]),
+            changes: Vec::new(),
+            reason: None,
+        });
+        snapshot
+            .set_section("auto_drive.budget", &json!({"tokens_used": 42}))
+            .unwrap();
+        let archive = source_home.path().join("checkpoint/session.json");
+        snapshot.write(&config, &archive).unwrap();
+
+        let loaded = SessionSnapshot::load(&config, &archive).unwrap();
+        assert_eq!(loaded.session_id, "abc");
+        assert_eq!(loaded.pending_approvals, snapshot.pending_approvals);
+        assert_eq!(
+            loaded.section::<Value>("auto_drive.budget").unwrap(),
+            Some(json!({"tokens_used": 42}))
+        );
+
+        let mut restored_config = config.clone();
+        restored_config.approval_policy = AskForApproval::OnRequest;
+        loaded.apply_to_config(&mut restored_config);
+        assert_eq!(restored_config.approval_policy, AskForApproval::Never);
+
+        let restored = loaded
+            .materialize_rollout(target_home.path(), None)
+            .unwrap();
+        assert_eq!(
+            restored,
+            target_home
+                .path()
+                .join("sessions/restored/rollout-2025-01-01T00-00-00-abc.jsonl")
+        );
+        assert_eq!(
+            std::fs::read_to_string(&restored).unwrap(),
+            std::fs::read_to_string(&rollout_path).unwrap()
+        );
+        assert_eq!(
+            std::fs::read_to_string(restored.with_extension("snapshot.json")).unwrap(),
+            "{\"v\":1}"
+        );
+    }
+
+    #[test]
+    fn fallback_rollout_name_needs_a_session_uuid() {
+        let home = tempfile::tempdir().unwrap();
+        let mut snapshot = SessionSnapshot {
+            format_version: SNAPSHOT_FORMAT_VERSION,
+            session_id: "../../escaped".to_string(),
+            created_at: Utc::now(),
+            config: SnapshotConfig {
+                model: "gpt-5".to_string(),
+                model_provider_id: "openai".to_string(),
+                model_reasoning_effort: ReasoningEffort::default(),
+                approval_policy: AskForApproval::Never,
+                sandbox_policy: SandboxPolicy::ReadOnly,
+                cwd: PathBuf::from("/repo"),
+            },
+            rollout_file_name: "..".to_string(),
+            rollout: Vec::new(),
+            history_snapshot: None,
+            pending_approvals: Vec::new(),
+            sections: BTreeMap::new(),
+        };
+
+        let err = snapshot.materialize_rollout(home.path(), None).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        snapshot.session_id = "67e55044-10b1-426f-9247-bb680e5fe0c8".to_string();
+        assert_eq!(
+            snapshot.materialize_rollout(home.path(), None).unwrap(),
+            home.path()
+                .join("sessions/restored/rollout-67e55044-10b1-426f-9247-bb680e5fe0c8.jsonl")
+        );
+    }
+}
@@ -0,0 +1,310 @@
+//! Bounded buffering between the SSE parsers and the turn loop.
+//!
+//! Long reasoning or text outputs arrive as thousands of tiny deltas. Queuing
+//! each one as its own event lets a slow consumer pile up a large backlog, so
+//! the stream processors send through [`BoundedEventSender`]: the channel is
+//! kept small, consecutive `OutputTextDelta`s for the same item are merged
+//! while the channel is full, and the merged text is flushed as soon as there
+//! is room (or once it reaches [`MAX_COALESCED_DELTA_BYTES`]).
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::Hash;
+use std::hash::Hasher;
+
+use code_otel::otel_event_manager::OtelEventManager;
+use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::TrySendError;
+
+use crate::client_common::ResponseEvent;
+use crate::error::Result;
+
+/// Capacity of the channel between a stream processor and its consumer.
+pub(crate) const SSE_CHANNEL_CAPACITY: usize = 256;
+/// Merged text held back while the channel is full; beyond this the sender
+/// waits for the consumer instead of growing the buffer.
+const MAX_COALESCED_DELTA_BYTES: usize = 16 * 1024;
+
+/// Counters describing how a single stream was buffered.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct SseBufferStats {
+    /// Events handed to the consumer.
+    pub(crate) sent: u64,
+    /// Text deltas folded into an earlier pending delta instead of queued.
+    pub(crate) coalesced: u64,
+    /// Duplicate or out-of-order deltas dropped by the sequence guards.
+    pub(crate) dropped: u64,
+    /// Times the sender had to wait for the consumer to make room.
+    pub(crate) backpressure_waits: u64,
+    /// Deepest the channel got, in events.
+    pub(crate) peak_queued: usize,
+}
+
+impl SseBufferStats {
+    pub(crate) fn to_json(self) -> serde_json::Value {
+        serde_json::json!({
+            "sent": self.sent,
+            "coalesced": self.coalesced,
+            "dropped": self.dropped,
+            "backpressure_waits": self.backpressure_waits,
+            "peak_queued": self.peak_queued,
+        })
+    }
+
+    /// Forwards the counters to telemetry when anything noteworthy happened.
+    pub(crate) fn record(self, otel_event_manager: Option<&OtelEventManager>) {
+        if self.coalesced == 0 && self.dropped == 0 && self.backpressure_waits == 0 {
+            return;
+        }
+        tracing::debug!(
+            "sse buffer: sent={} coalesced={} dropped={} waits={} peak={}",
+            self.sent,
+            self.coalesced,
+            self.dropped,
+            self.backpressure_waits,
+            self.peak_queued
+        );
+        if let Some(manager) = otel_event_manager {
+            manager.sse_buffer_stats(
+                self.sent,
+                self.coalesced,
+                self.dropped,
+                self.backpressure_waits,
+                self.peak_queued as u64,
+            );
+        }
+    }
+}
+
+#[derive(Debug)]
+struct PendingDelta {
+    delta: String,
+    item_id: Option<String>,
+    sequence_number: Option<u64>,
+    output_index: Option<u32>,
+}
+
+impl PendingDelta {
+    fn into_event(self) -> ResponseEvent {
+        ResponseEvent::OutputTextDelta {
+            delta: self.delta,
+            item_id: self.item_id,
+            sequence_number: self.sequence_number,
+            output_index: self.output_index,
+        }
+    }
+}
+
+/// Receiver went away; the stream processor should stop.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct ReceiverClosed;
+
+/// Wraps the event channel with delta coalescing and buffer metrics.
+pub(crate) struct BoundedEventSender {
+    tx: mpsc::Sender<Result<ResponseEvent>>,
+    pending: Option<PendingDelta>,
+    stats: SseBufferStats,
+}
+
+impl BoundedEventSender {
+    pub(crate) fn new(tx: mpsc::Sender<Result<ResponseEvent>>) -> Self {
+        Self {
+            tx,
+            pending: None,
+            stats: SseBufferStats::default(),
+        }
+    }
+
+    pub(crate) fn stats(&self) -> SseBufferStats {
+        self.stats
+    }
+
+    pub(crate) fn note_dropped(&mut self) {
+        self.stats.dropped += 1;
+    }
+
+    /// Queues an assistant text delta, merging it into the pending one when
+    /// the consumer has fallen behind.
+    pub(crate) async fn send_text_delta(
+        &mut self,
+        delta: String,
+        item_id: Option<String>,
+        sequence_number: Option<u64>,
+        output_index: Option<u32>,
+    ) -> std::result::Result<(), ReceiverClosed> {
+        match self.pending.as_mut() {
+            Some(pending) if pending.item_id == item_id && pending.output_index == output_index => {
+                pending.delta.push_str(&delta);
+                pending.sequence_number = sequence_number.or(pending.sequence_number);
+                self.stats.coalesced += 1;
+            }
+            _ => {
+                self.flush().await?;
+                self.pending = Some(PendingDelta {
+                    delta,
+                    item_id,
+                    sequence_number,
+                    output_index,
+                });
+            }
+        }
+
+        if !self.try_send_pending()?
+            && self
+                .pending
+                .as_ref()
+                .is_some_and(|p| p.delta.len() >= MAX_COALESCED_DELTA_BYTES)
+        {
+            self.flush().await?;
+        }
+        Ok(())
+    }
+
+    /// Flushes any pending delta, then sends `event`, waiting for room.
+    pub(crate) async fn send(
+        &mut self,
+        event: Result<ResponseEvent>,
+    ) -> std::result::Result<(), ReceiverClosed> {
+        self.flush().await?;
+        self.send_inner(event).await
+    }
+
+    /// Sends the pending delta only if the channel has room right now, so
+    /// merged text is not held back while the stream is idle.
+    pub(crate) fn try_flush(&mut self) -> std::result::Result<(), ReceiverClosed> {
+        self.try_send_pending().map(|_| ())
+    }
+
+    /// Sends the pending delta, if any, waiting for room.
+    pub(crate) async fn flush(&mut self) -> std::result::Result<(), ReceiverClosed> {
+        match self.pending.take() {
+            Some(pending) => self.send_inner(Ok(pending.into_event())).await,
+            None => Ok(()),
+        }
+    }
+
+    async fn send_inner(
+        &mut self,
+        event: Result<ResponseEvent>,
+    ) -> std::result::Result<(), ReceiverClosed> {
+        if self.tx.capacity() == 0 {
+            self.stats.backpressure_waits += 1;
+        }
+        self.tx.send(event).await.map_err(|_| ReceiverClosed)?;
+        self.note_sent();
+        Ok(())
+    }
+
+    /// Queues the pending delta without waiting. Returns `false`, leaving the
+    /// delta pending, when the channel is full.
+    fn try_send_pending(&mut self) -> std::result::Result<bool, ReceiverClosed> {
+        let Some(pending) = self.pending.take() else {
+            return Ok(true);
+        };
+        match self.tx.try_send(Ok(pending.into_event())) {
+            Ok(()) => {
+                self.note_sent();
+                Ok(true)
+            }
+            Err(TrySendError::Closed(_)) => Err(ReceiverClosed),
+            Err(TrySendError::Full(Ok(ResponseEvent::OutputTextDelta {
+                delta,
+                item_id,
+                sequence_number,
+                output_index,
+            }))) => {
+                self.pending = Some(PendingDelta {
+                    delta,
+                    item_id,
+                    sequence_number,
+                    output_index,
+                });
+                Ok(false)
+            }
+            Err(TrySendError::Full(_)) => Ok(false),
+        }
+    }
+
+    fn note_sent(&mut self) {
+        self.stats.sent += 1;
+        let queued = self.tx.max_capacity() - self.tx.capacity();
+        self.stats.peak_queued = self.stats.peak_queued.max(queued);
+    }
+}
+
+/// Fingerprint of a delta for the duplicate guard, so the guard keeps a few
+/// bytes per key instead of a copy of the last delta.
+pub(crate) fn delta_fingerprint(delta: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    delta.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn text(event: Option<Result<ResponseEvent>>) -> String {
+        match event {
+            Some(Ok(ResponseEvent::OutputTextDelta { delta, .. })) => delta,
+            other => panic!("expected a text delta, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn coalesces_deltas_while_channel_is_full() {
+        let (tx, mut rx) = mpsc::channel(1);
+        let mut sender = BoundedEventSender::new(tx);
+        for piece in ["a", "b", "c", "d"] {
+            sender
+                .send_text_delta(piece.to_string(), Some("m1".into()), None, Some(0))
+                .await
+                .unwrap();
+        }
+        assert_eq!(text(rx.recv().await), "a");
+        sender.flush().await.unwrap();
+        assert_eq!(text(rx.recv().await), "bcd");
+        assert_eq!(
+            sender.stats(),
+            SseBufferStats {
+                sent: 2,
+                coalesced: 2,
+                dropped: 0,
+                backpressure_waits: 0,
+                peak_queued: 1,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn other_events_flush_pending_text_first() {
+        let (tx, mut rx) = mpsc::channel(4);
+        let mut sender = BoundedEventSender::new(tx);
+        sender
+            .send_text_delta("hi".to_string(), Some("m1".into()), None, Some(0))
+            .await
+            .unwrap();
+        sender
+            .send(Ok(ResponseEvent::ReasoningSummaryPartAdded))
+            .await
+            .unwrap();
+        assert_eq!(text(rx.recv().await), "hi");
+        assert!(matches!(
+            rx.recv().await,
+            Some(Ok(ResponseEvent::ReasoningSummaryPartAdded))
+        ));
+    }
+
+    #[tokio::test]
+    async fn reports_closed_receiver() {
+        let (tx, rx) = mpsc::channel(1);
+        drop(rx);
+        let mut sender = BoundedEventSender::new(tx);
+        assert_eq!(
+            sender
+                .send_text_delta("x".to_string(), None, None, None)
+                .await,
+            Err(ReceiverClosed)
+        );
+    }
+}
@@ -165,7 +165,10 @@ impl Session {
 
         let event = Event {
             id: sub_id.clone(),
-            msg: EventMsg::TurnAborted(TurnAbortedEvent { reason }),
+            msg: EventMsg::TurnAborted(TurnAbortedEvent {
+                reason,
+                cancelled_tool_calls: Vec::new(),
+            }),
         };
         self.send_event(event).await;
 
@@ -0,0 +1,190 @@
+//! Local token counts for context-window and quota decisions.
+//!
+//! OpenAI model families are counted with their tiktoken encoding:
+//! `o200k_base` for GPT-4o and later (including the o-series, GPT-5 and
+//! Codex models) and `cl100k_base` for GPT-4 and GPT-3.5. Other providers do
+//! not publish their tokenizers, so their models fall back to the usual
+//! estimate of four bytes per token.
+
+use code_protocol::models::ContentItem;
+use code_protocol::models::ReasoningItemContent;
+use code_protocol::models::ReasoningItemReasoningSummary;
+use code_protocol::models::ResponseItem;
+use tiktoken_rs::CoreBPE;
+
+/// Bytes per token assumed when no tokenizer is known for the model.
+const BYTES_PER_TOKEN: usize = 4;
+/// Image URLs (usually base64 data URLs) cost far fewer tokens than their
+/// length suggests; only this share of their bytes is counted.
+const IMAGE_URL_BYTES_DIVISOR: usize = 10;
+
+/// Model slug prefixes and the encoding their models use. Checked in order,
+/// so more specific prefixes come first.
+const MODEL_PREFIXES: &[(&str, Tokenizer)] = &[
+    ("gpt-5", Tokenizer::O200kBase),
+    ("gpt-4.1", Tokenizer::O200kBase),
+    ("gpt-4.5", Tokenizer::O200kBase),
+    ("gpt-4o", Tokenizer::O200kBase),
+    ("chatgpt-4o", Tokenizer::O200kBase),
+    ("gpt-oss", Tokenizer::O200kBase),
+    ("codex-", Tokenizer::O200kBase),
+    ("o1", Tokenizer::O200kBase),
+    ("o3", Tokenizer::O200kBase),
+    ("o4", Tokenizer::O200kBase),
+    ("gpt-4", Tokenizer::Cl100kBase),
+    ("gpt-3.5", Tokenizer::Cl100kBase),
+];
+
+/// How tokens are counted for a model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tokenizer {
+    O200kBase,
+    Cl100kBase,
+    /// Bytes divided by [`BYTES_PER_TOKEN`], rounded up.
+    Heuristic,
+}
+
+impl Tokenizer {
+    /// The tokenizer for `model_slug`. Provider prefixes such as `openai/`
+    /// are ignored; unknown models get [`Tokenizer::Heuristic`].
+    pub fn for_model(model_slug: &str) -> Self {
+        let slug = model_slug.rsplit('/').next().unwrap_or(model_slug);
+        let slug = slug.to_ascii_lowercase();
+        MODEL_PREFIXES
+            .iter()
+            .find(|(prefix, _)| slug.starts_with(prefix))
+            .map_or(Tokenizer::Heuristic, |(_, tokenizer)| *tokenizer)
+    }
+
+    /// Tokens in `text`.
+    pub fn count(self, text: &str) -> usize {
+        match self.bpe() {
+            Some(bpe) => bpe.encode_ordinary(text).len(),
+            None => text.len().div_ceil(BYTES_PER_TOKEN),
+        }
+    }
+
+    /// Tokens in the text an item sends to the model. Items without model
+    /// visible text (shell calls, web searches, compaction summaries) count
+    /// as zero.
+    pub fn count_item(self, item: &ResponseItem) -> usize {
+        let bpe = self.bpe();
+        let mut tokens = 0;
+        let mut heuristic_bytes = 0;
+        let mut image_bytes = 0;
+        let mut add = |text: &str| match bpe {
+            Some(bpe) => tokens += bpe.encode_ordinary(text).len(),
+            None => heuristic_bytes += text.len(),
+        };
+        match item {
+            ResponseItem::Message { content, .. } => {
+                for chunk in content {
+                    match chunk {
+                        ContentItem::InputText { text } | ContentItem::OutputText { text } => {
+                            add(text)
+                        }
+                        ContentItem::InputImage { image_url } => {
+                            image_bytes += image_url.len() / IMAGE_URL_BYTES_DIVISOR;
+                        }
+                    }
+                }
+            }
+            ResponseItem::FunctionCall {
+                name, arguments, ..
+            } => {
+                add(name);
+                add(arguments);
+            }
+            ResponseItem::FunctionCallOutput { output, .. } => add(&output.content),
+            ResponseItem::CustomToolCall { name, input, .. } => {
+                add(name);
+                add(input);
+            }
+            ResponseItem::CustomToolCallOutput { output, .. } => add(output),
+            ResponseItem::Reasoning {
+                summary, content, ..
+            } => {
+                for ReasoningItemReasoningSummary::SummaryText { text } in summary {
+                    add(text);
+                }
+                for segment in content.iter().flatten() {
+                    match segment {
+                        ReasoningItemContent::ReasoningText { text }
+                        | ReasoningItemContent::Text { text } => add(text),
+                    }
+                }
+            }
+            _ => {}
+        }
+        tokens + (heuristic_bytes + image_bytes).div_ceil(BYTES_PER_TOKEN)
+    }
+
+    /// Tokens in all of `items`.
+    pub fn count_items(self, items: &[ResponseItem]) -> usize {
+        items.iter().map(|item| self.count_item(item)).sum()
+    }
+
+    fn bpe(self) -> Option<&'static CoreBPE> {
+        match self {
+            Tokenizer::O200kBase => Some(tiktoken_rs::o200k_base_singleton()),
+            Tokenizer::Cl100kBase => Some(tiktoken_rs::cl100k_base_singleton()),
+            Tokenizer::Heuristic => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use code_protocol::models::FunctionCallOutputPayload;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn picks_the_encoding_by_model_family() {
+        assert_eq!(Tokenizer::for_model("gpt-5-codex"), Tokenizer::O200kBase);
+        assert_eq!(
+            Tokenizer::for_model("openai/gpt-4o-mini"),
+            Tokenizer::O200kBase
+        );
+        assert_eq!(Tokenizer::for_model("o3"), Tokenizer::O200kBase);
+        assert_eq!(Tokenizer::for_model("gpt-4-turbo"), Tokenizer::Cl100kBase);
+        assert_eq!(
+            Tokenizer::for_model("claude-sonnet-4-5"),
+            Tokenizer::Heuristic
+        );
+    }
+
+    #[test]
+    fn counts_text_with_the_model_encoding() {
+        assert_eq!(Tokenizer::O200kBase.count("hello world"), 2);
+        assert_eq!(Tokenizer::Cl100kBase.count("hello world"), 2);
+        assert_eq!(Tokenizer::Heuristic.count("hello world"), 3);
+    }
+
+    #[test]
+    fn heuristic_counts_item_bytes() {
+        let items = vec![
+            ResponseItem::Message {
+                id: None,
+                role: "user".to_string(),
+                content: vec![
+                    ContentItem::InputText {
+                        text: "a".repeat(10),
+                    },
+                    ContentItem::InputImage {
+                        image_url: "b".repeat(100),
+                    },
+                ],
+            },
+            ResponseItem::FunctionCallOutput {
+                call_id: "call_1".to_string(),
+                output: FunctionCallOutputPayload {
+                    content: "c".repeat(8),
+                    success: Some(true),
+                },
+            },
+        ];
+        // (10 + 100 / 10) / 4 rounded up, plus 8 / 4.
+        assert_eq!(Tokenizer::Heuristic.count_items(&items), 7);
+    }
+}
@@ -330,3 +330,93 @@ async fn reconcile_prefers_last_event_over_mtime() {
 
     assert_eq!(latest.session_id, newer_id);
 }
+
+#[tokio::test]
+async fn tags_filter_queries_and_survive_rescans() {
+    let temp = TempDir::new().unwrap();
+    let cwd = PathBuf::from("/workspace/project");
+    let tagged_id = Uuid::parse_str("ffffffff-ffff-4fff-8fff-ffffffffffff").unwrap();
+    let rollout_path = write_rollout_transcript(
+        temp.path(),
+        tagged_id,
+        "2025-11-15T12:00:00Z",
+        "2025-11-15T12:05:00Z",
+        &cwd,
+        SessionSource::Cli,
+        "tagged",
+    );
+    write_rollout_transcript(
+        temp.path(),
+        Uuid::parse_str("99999999-9999-4999-8999-999999999999").unwrap(),
+        "2025-11-16T12:00:00Z",
+        "2025-11-16T12:05:00Z",
+        &cwd,
+        SessionSource::Cli,
+        "untagged",
+    );
+
+    let catalog = SessionCatalog::new(temp.path().to_path_buf());
+    catalog
+        .set_tags(tagged_id, vec!["release".to_string()])
+        .await
+        .unwrap();
+    set_file_mtime(
+        &rollout_path,
+        FileTime::from_system_time(SystemTime::now() + Duration::from_secs(60)),
+    )
+    .unwrap();
+
+    let results = catalog
+        .query(&SessionQuery {
+            tag: Some("release".to_string()),
+            ..SessionQuery::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].session_id, tagged_id);
+    assert_eq!(results[0].tags, vec!["release".to_string()]);
+}
+
+#[tokio::test]
+async fn search_finds_sessions_by_message_text() {
+    let temp = TempDir::new().unwrap();
+    let cwd = PathBuf::from("/workspace/project");
+    let target_id = Uuid::parse_str("abababab-abab-4bab-8bab-abababababab").unwrap();
+    write_rollout_transcript(
+        temp.path(),
+        target_id,
+        "2025-11-15T12:00:00Z",
+        "2025-11-15T12:05:00Z",
+        &cwd,
+        SessionSource::Cli,
+        "refactor the retry logic in the client",
+    );
+    let rollout_path = write_rollout_transcript(
+        temp.path(),
+        Uuid::parse_str("cdcdcdcd-cdcd-4dcd-8dcd-cdcdcdcdcdcd").unwrap(),
+        "2025-11-16T12:00:00Z",
+        "2025-11-16T12:05:00Z",
+        &cwd,
+        SessionSource::Cli,
+        "update the changelog",
+    );
+
+    let catalog = SessionCatalog::new(temp.path().to_path_buf());
+    let hits = catalog
+        .search("retry logic", &SessionQuery::default())
+        .await
+        .unwrap();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].session_id, target_id);
+    assert_eq!(hits[0].role, "user");
+    assert_eq!(hits[0].timestamp, "2025-11-15T12:05:00Z");
+    assert!(hits[0].snippet.contains("[retry] [logic]"));
+
+    fs::remove_file(rollout_path).unwrap();
+    let hits = catalog
+        .search("changelog", &SessionQuery::default())
+        .await
+        .unwrap();
+    assert!(hits.is_empty());
+}
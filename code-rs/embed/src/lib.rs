@@ -0,0 +1,534 @@
+//! Supported API for embedding the agent in another program.
+//!
+//! [`ConfigBuilder`] loads a [`SessionConfig`], [`Engine`] starts, resumes,
+//! and forks sessions, and [`Session`] submits input and yields a typed
+//! [`SessionEvent`] stream. Depend on this crate rather than `code-core`:
+//! everything `code-core` exports serves the crates in this workspace and may
+//! change in any release.
+//!
+//! Stability: items in this crate follow semver. Enums and event payloads
+//! are `#[non_exhaustive]`, so minor releases may add variants, fields, and
+//! builder methods; removing or renaming an item or changing a signature
+//! waits for a major release. [`SessionEvent::Other`] carries the internal
+//! [`EventMsg`] and is exempt. `tests/embedding_api.rs` drives this surface
+//! end to end; a change that needs that test edited is a breaking change.
+//!
+//! ```ignore
+//! let config = ConfigBuilder::new().cwd(repo).model("gpt-5.1").build()?;
+//! let engine = Engine::new(&config);
+//! let session = engine.start(config).await?;
+//! let output = session.run("Summarize the README").await?;
+//! println!("{}", output.last_agent_message.unwrap_or_default());
+//! session.shutdown().await?;
+//! ```
+
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use code_core::AuthManager;
+use code_core::CodexConversation;
+use code_core::ConversationManager;
+use code_core::NewConversation;
+use code_core::config::Config;
+use code_core::config::ConfigOverrides;
+use code_core::config::find_code_home;
+use code_core::config::load_config_as_toml_with_cli_overrides;
+use code_core::error::CodexErr;
+use code_core::find_conversation_path_by_id_str;
+use code_core::protocol::Event;
+use code_core::protocol::InputItem;
+use code_core::protocol::Op;
+use code_protocol::ConversationId;
+use code_protocol::mcp_protocol::AuthMode;
+use code_protocol::protocol::SessionSource;
+use futures::Stream;
+use toml::Value as TomlValue;
+
+pub use code_core::protocol::AskForApproval;
+pub use code_core::protocol::EventMsg;
+pub use code_core::protocol::ReviewDecision;
+pub use code_core::protocol::TokenUsage;
+pub use code_protocol::config_types::SandboxMode;
+
+/// Errors returned by the embedding API.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum EmbedError {
+    #[error("failed to load config: {0}")]
+    Config(#[source] std::io::Error),
+    #[error("failed to read recorded sessions: {0}")]
+    Sessions(#[source] std::io::Error),
+    #[error("no session found with id {0}")]
+    SessionNotFound(String),
+    #[error(transparent)]
+    Session(#[from] CodexErr),
+}
+
+pub type Result<T> = std::result::Result<T, EmbedError>;
+
+/// An endpoint speaking the OpenAI Responses API, used instead of the
+/// provider `config.toml` selects. It is added to `model_providers` like a
+/// `-c` override, so `id` must be a plain key other than the built-in
+/// `openai` and `oss`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Provider {
+    id: String,
+    base_url: String,
+    env_key: Option<String>,
+    max_retries: Option<u64>,
+}
+
+impl Provider {
+    /// `base_url` is the API root, for example `https://api.openai.com/v1`.
+    pub fn new(id: impl Into<String>, base_url: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            base_url: base_url.into(),
+            env_key: None,
+            max_retries: None,
+        }
+    }
+
+    /// Environment variable holding the API key. Without one the stored
+    /// credentials or `OPENAI_API_KEY` are used.
+    pub fn env_key(mut self, var: impl Into<String>) -> Self {
+        self.env_key = Some(var.into());
+        self
+    }
+
+    /// Retries for failed requests and dropped streams.
+    pub fn max_retries(mut self, retries: u64) -> Self {
+        self.max_retries = Some(retries);
+        self
+    }
+
+    fn into_overrides(self) -> Vec<(String, TomlValue)> {
+        let table = format!("model_providers.{}", self.id);
+        let mut overrides = vec![
+            (format!("{table}.name"), TomlValue::from(self.id.clone())),
+            (format!("{table}.base_url"), self.base_url.into()),
+            (format!("{table}.wire_api"), "responses".into()),
+        ];
+        if let Some(env_key) = self.env_key {
+            overrides.push((format!("{table}.env_key"), env_key.into()));
+        }
+        if let Some(retries) = self.max_retries {
+            let retries = TomlValue::Integer(i64::try_from(retries).unwrap_or(i64::MAX));
+            overrides.push((format!("{table}.request_max_retries"), retries.clone()));
+            overrides.push((format!("{table}.stream_max_retries"), retries));
+        }
+        overrides.push(("model_provider".to_string(), self.id.into()));
+        overrides
+    }
+}
+
+/// Settings for the sessions an [`Engine`] runs, built by [`ConfigBuilder`].
+#[derive(Debug, Clone)]
+pub struct SessionConfig(Config);
+
+impl SessionConfig {
+    pub fn model(&self) -> &str {
+        &self.0.model
+    }
+
+    /// Name of the provider requests go to.
+    pub fn model_provider_id(&self) -> &str {
+        &self.0.model_provider_id
+    }
+
+    pub fn cwd(&self) -> &Path {
+        &self.0.cwd
+    }
+
+    pub fn code_home(&self) -> &Path {
+        &self.0.code_home
+    }
+}
+
+/// Builds a [`SessionConfig`] the way the CLI does: `config.toml` under the
+/// code home, then `-c`-style overrides, then the typed settings set here.
+#[derive(Debug, Clone, Default)]
+pub struct ConfigBuilder {
+    code_home: Option<PathBuf>,
+    cwd: Option<PathBuf>,
+    model: Option<String>,
+    model_provider: Option<Provider>,
+    approval_policy: Option<AskForApproval>,
+    sandbox_mode: Option<SandboxMode>,
+    base_instructions: Option<String>,
+    overrides: Vec<(String, TomlValue)>,
+}
+
+impl ConfigBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Directory holding `config.toml`, credentials, and sessions. Defaults to
+    /// `$CODE_HOME`, then `~/.code`.
+    pub fn code_home(mut self, code_home: impl Into<PathBuf>) -> Self {
+        self.code_home = Some(code_home.into());
+        self
+    }
+
+    /// Working directory for tools. Defaults to the process directory.
+    pub fn cwd(mut self, cwd: impl Into<PathBuf>) -> Self {
+        self.cwd = Some(cwd.into());
+        self
+    }
+
+    pub fn model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    /// Sends requests to this provider instead of the configured one.
+    pub fn model_provider(mut self, provider: Provider) -> Self {
+        self.model_provider = Some(provider);
+        self
+    }
+
+    pub fn approval_policy(mut self, policy: AskForApproval) -> Self {
+        self.approval_policy = Some(policy);
+        self
+    }
+
+    pub fn sandbox_mode(mut self, mode: SandboxMode) -> Self {
+        self.sandbox_mode = Some(mode);
+        self
+    }
+
+    /// Replaces the built-in system instructions.
+    pub fn base_instructions(mut self, instructions: impl Into<String>) -> Self {
+        self.base_instructions = Some(instructions.into());
+        self
+    }
+
+    /// Sets a `config.toml` key by dotted path, like `-c key=value` on the
+    /// command line.
+    pub fn set(mut self, key: impl Into<String>, value: impl Into<TomlValue>) -> Self {
+        self.overrides.push((key.into(), value.into()));
+        self
+    }
+
+    pub fn build(self) -> Result<SessionConfig> {
+        let code_home = match self.code_home {
+            Some(code_home) => code_home,
+            None => find_code_home().map_err(EmbedError::Config)?,
+        };
+        let mut overrides = self.overrides;
+        if let Some(provider) = self.model_provider {
+            overrides.extend(provider.into_overrides());
+        }
+        let toml = load_config_as_toml_with_cli_overrides(&code_home, overrides)
+            .map_err(EmbedError::Config)?;
+        let overrides = ConfigOverrides {
+            model: self.model,
+            cwd: self.cwd,
+            approval_policy: self.approval_policy,
+            sandbox_mode: self.sandbox_mode,
+            base_instructions: self.base_instructions,
+            ..ConfigOverrides::default()
+        };
+        let config = Config::load_from_base_config_with_overrides(toml, overrides, code_home)
+            .map_err(EmbedError::Config)?;
+        Ok(SessionConfig(config))
+    }
+}
+
+/// Starts, resumes, and forks sessions. Sessions are recorded under the
+/// code home like `code exec` runs, so the CLI can resume them too.
+pub struct Engine {
+    manager: ConversationManager,
+    auth_manager: Arc<AuthManager>,
+}
+
+impl Engine {
+    /// Uses the credentials stored in the config's code home, or
+    /// `OPENAI_API_KEY`.
+    pub fn new(config: &SessionConfig) -> Self {
+        let auth_manager = AuthManager::shared_with_mode_and_originator(
+            config.0.code_home.clone(),
+            AuthMode::ApiKey,
+            config.0.responses_originator_header.clone(),
+        );
+        Self {
+            manager: ConversationManager::new(auth_manager.clone(), SessionSource::Exec),
+            auth_manager,
+        }
+    }
+
+    pub async fn start(&self, config: SessionConfig) -> Result<Session> {
+        let new = self.manager.new_conversation(config.0).await?;
+        Ok(Session::from_new(new))
+    }
+
+    /// Continues a recorded session with its full history.
+    pub async fn resume(&self, config: SessionConfig, session_id: &str) -> Result<Session> {
+        let path = self.rollout_path(&config, session_id).await?;
+        let new = self
+            .manager
+            .resume_conversation_from_rollout(config.0, path, self.auth_manager.clone())
+            .await?;
+        Ok(Session::from_new(new))
+    }
+
+    /// Starts a new session from a recorded one's history, minus its last
+    /// `drop_last_messages` user messages and everything after them.
+    pub async fn fork(
+        &self,
+        config: SessionConfig,
+        session_id: &str,
+        drop_last_messages: usize,
+    ) -> Result<Session> {
+        let path = self.rollout_path(&config, session_id).await?;
+        let new = self
+            .manager
+            .fork_conversation(drop_last_messages, config.0, path)
+            .await?;
+        Ok(Session::from_new(new))
+    }
+
+    async fn rollout_path(&self, config: &SessionConfig, session_id: &str) -> Result<PathBuf> {
+        find_conversation_path_by_id_str(config.code_home(), session_id)
+            .await
+            .map_err(EmbedError::Sessions)?
+            .ok_or_else(|| EmbedError::SessionNotFound(session_id.to_string()))
+    }
+}
+
+/// One running session.
+pub struct Session {
+    id: ConversationId,
+    model: String,
+    conversation: Arc<CodexConversation>,
+}
+
+impl Session {
+    fn from_new(new: NewConversation) -> Self {
+        Self {
+            id: new.conversation_id,
+            model: new.session_configured.model,
+            conversation: new.conversation,
+        }
+    }
+
+    /// Id to pass to [`Engine::resume`] and [`Engine::fork`].
+    pub fn id(&self) -> String {
+        self.id.to_string()
+    }
+
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// Starts a turn and returns its id; the turn's events carry it.
+    pub async fn submit(&self, text: impl Into<String>) -> Result<String> {
+        self.submit_op(Op::UserInput {
+            items: vec![InputItem::Text { text: text.into() }],
+        })
+        .await
+    }
+
+    /// Answers [`SessionEvent::ExecApprovalRequest`].
+    pub async fn approve_exec(&self, request_id: &str, decision: ReviewDecision) -> Result<()> {
+        self.submit_op(Op::ExecApproval {
+            id: request_id.to_string(),
+            decision,
+        })
+        .await
+        .map(drop)
+    }
+
+    /// Answers [`SessionEvent::PatchApprovalRequest`].
+    pub async fn approve_patch(&self, request_id: &str, decision: ReviewDecision) -> Result<()> {
+        self.submit_op(Op::PatchApproval {
+            id: request_id.to_string(),
+            decision,
+        })
+        .await
+        .map(drop)
+    }
+
+    /// Stops the running turn; it still ends with [`SessionEvent::TurnComplete`]
+    /// or [`SessionEvent::Error`].
+    pub async fn interrupt(&self) -> Result<()> {
+        self.submit_op(Op::Interrupt).await.map(drop)
+    }
+
+    /// Waits for the next event. Fails once the session has shut down.
+    pub async fn next_event(&self) -> Result<SessionEvent> {
+        let event = self.conversation.next_event().await?;
+        Ok(SessionEvent::from_event(event))
+    }
+
+    /// Events until the session shuts down.
+    pub fn events(&self) -> impl Stream<Item = SessionEvent> + '_ {
+        futures::stream::unfold(self, |session| async move {
+            let event = session.next_event().await.ok()?;
+            Some((event, session))
+        })
+    }
+
+    /// Submits `text` and waits for the turn to finish. Approval requests are
+    /// not answered here; use [`Session::submit`] and [`Session::events`]
+    /// when the approval policy can ask.
+    pub async fn run(&self, text: impl Into<String>) -> Result<TurnOutput> {
+        let turn_id = self.submit(text).await?;
+        let mut output = TurnOutput::default();
+        loop {
+            match self.next_event().await? {
+                SessionEvent::TokenUsage { turn_id: id, total } if id == turn_id => {
+                    output.usage = total;
+                }
+                SessionEvent::Error {
+                    turn_id: id,
+                    message,
+                } if id == turn_id => {
+                    output.errors.push(message);
+                }
+                SessionEvent::TurnComplete {
+                    turn_id: id,
+                    last_agent_message,
+                } if id == turn_id => {
+                    output.last_agent_message = last_agent_message;
+                    return Ok(output);
+                }
+                SessionEvent::ShutdownComplete => return Ok(output),
+                _ => {}
+            }
+        }
+    }
+
+    /// Ends the session and waits until it has flushed its recording.
+    pub async fn shutdown(self) -> Result<()> {
+        self.submit_op(Op::Shutdown).await?;
+        while let Ok(event) = self.conversation.next_event().await {
+            if matches!(event.msg, EventMsg::ShutdownComplete) {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    async fn submit_op(&self, op: Op) -> Result<String> {
+        Ok(self.conversation.submit(op).await?)
+    }
+}
+
+/// Result of [`Session::run`].
+#[derive(Debug, Clone, Default, PartialEq)]
+#[non_exhaustive]
+pub struct TurnOutput {
+    pub last_agent_message: Option<String>,
+    /// Session totals as of the end of the turn.
+    pub usage: TokenUsage,
+    /// Errors reported during the turn.
+    pub errors: Vec<String>,
+}
+
+/// Session events. `turn_id` is the id [`Session::submit`] returned for the
+/// turn the event belongs to.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum SessionEvent {
+    TurnStarted {
+        turn_id: String,
+    },
+    AgentMessageDelta {
+        turn_id: String,
+        delta: String,
+    },
+    AgentMessage {
+        turn_id: String,
+        text: String,
+    },
+    /// Answer with [`Session::approve_exec`].
+    ExecApprovalRequest {
+        turn_id: String,
+        request_id: String,
+        command: Vec<String>,
+        cwd: PathBuf,
+        reason: Option<String>,
+    },
+    /// Answer with [`Session::approve_patch`].
+    PatchApprovalRequest {
+        turn_id: String,
+        request_id: String,
+        files: Vec<PathBuf>,
+        reason: Option<String>,
+    },
+    TokenUsage {
+        turn_id: String,
+        total: TokenUsage,
+    },
+    Error {
+        turn_id: String,
+        message: String,
+    },
+    TurnComplete {
+        turn_id: String,
+        last_agent_message: Option<String>,
+    },
+    ShutdownComplete,
+    /// Any other event. Not covered by the stability guarantee.
+    Other {
+        turn_id: String,
+        msg: EventMsg,
+    },
+}
+
+impl SessionEvent {
+    fn from_event(event: Event) -> Self {
+        let turn_id = event.id;
+        match event.msg {
+            EventMsg::TaskStarted => Self::TurnStarted { turn_id },
+            EventMsg::AgentMessageDelta(delta) => Self::AgentMessageDelta {
+                turn_id,
+                delta: delta.delta,
+            },
+            EventMsg::AgentMessage(message) => Self::AgentMessage {
+                turn_id,
+                text: message.message,
+            },
+            EventMsg::ExecApprovalRequest(request) => Self::ExecApprovalRequest {
+                turn_id,
+                request_id: request.call_id,
+                command: request.command,
+                cwd: request.cwd,
+                reason: request.reason,
+            },
+            EventMsg::ApplyPatchApprovalRequest(request) => {
+                let mut files: Vec<PathBuf> = request.changes.into_keys().collect();
+                files.sort();
+                Self::PatchApprovalRequest {
+                    turn_id,
+                    request_id: request.call_id,
+                    files,
+                    reason: request.reason,
+                }
+            }
+            EventMsg::TokenCount(count) => match count.info {
+                Some(info) => Self::TokenUsage {
+                    turn_id,
+                    total: info.total_token_usage,
+                },
+                None => Self::Other {
+                    turn_id,
+                    msg: EventMsg::TokenCount(count),
+                },
+            },
+            EventMsg::Error(error) => Self::Error {
+                turn_id,
+                message: error.message,
+            },
+            EventMsg::TaskComplete(complete) => Self::TurnComplete {
+                turn_id,
+                last_agent_message: complete.last_agent_message,
+            },
+            EventMsg::ShutdownComplete => Self::ShutdownComplete,
+            msg => Self::Other { turn_id, msg },
+        }
+    }
+}
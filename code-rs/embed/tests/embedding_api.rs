@@ -0,0 +1,160 @@
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+//! End-to-end coverage of the supported embedding API in `code_embed`.
+//! Changing this test to keep it passing means the change is breaking.
+
+use code_core::spawn::CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR;
+use code_embed::AskForApproval;
+use code_embed::ConfigBuilder;
+use code_embed::EmbedError;
+use code_embed::Engine;
+use code_embed::Provider;
+use code_embed::SandboxMode;
+use code_embed::SessionEvent;
+use futures::StreamExt;
+use pretty_assertions::assert_eq;
+use serde_json::Value;
+use serde_json::json;
+use tempfile::TempDir;
+use wiremock::Mock;
+use wiremock::MockServer;
+use wiremock::ResponseTemplate;
+use wiremock::matchers::method;
+use wiremock::matchers::path_regex;
+
+fn sse_reply(id: &str, text: &str) -> String {
+    let message = json!({
+        "type": "response.output_item.done",
+        "item": {
+            "type": "message",
+            "role": "assistant",
+            "id": format!("msg-{id}"),
+            "content": [{ "type": "output_text", "text": text }],
+        },
+    });
+    let completed = json!({
+        "type": "response.completed",
+        "response": {
+            "id": id,
+            "usage": {
+                "input_tokens": 12,
+                "input_tokens_details": null,
+                "output_tokens": 5,
+                "output_tokens_details": null,
+                "total_tokens": 17,
+            },
+            "output": [],
+        },
+    });
+    format!(
+        "event: response.output_item.done\ndata: {message}\n\nevent: response.completed\ndata: {completed}\n\n"
+    )
+}
+
+async fn mount_reply(server: &MockServer, id: &str, text: &str) {
+    Mock::given(method("POST"))
+        .and(path_regex(".*/responses$"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("content-type", "text/event-stream")
+                .set_body_string(sse_reply(id, text)),
+        )
+        .up_to_n_times(1)
+        .mount(server)
+        .await;
+}
+
+fn user_texts(body: &Value) -> Vec<String> {
+    body["input"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|item| item["role"] == "user")
+        .flat_map(|item| item["content"].as_array().cloned().unwrap_or_default())
+        .filter_map(|part| part["text"].as_str().map(str::to_string))
+        .collect()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn start_resume_and_fork_through_the_embedding_api() {
+    if std::env::var(CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR).is_ok() {
+        println!("Skipping test because network access is disabled inside the sandbox.");
+        return;
+    }
+
+    let server = MockServer::start().await;
+    mount_reply(&server, "resp-1", "Hello from the mock").await;
+    mount_reply(&server, "resp-2", "Second reply").await;
+
+    let code_home = TempDir::new().unwrap();
+    let cwd = TempDir::new().unwrap();
+    let provider = Provider::new("embedded", format!("{}/v1", server.uri())).max_retries(0);
+    let config = ConfigBuilder::new()
+        .code_home(code_home.path())
+        .cwd(cwd.path())
+        .model("gpt-5")
+        .model_provider(provider)
+        .approval_policy(AskForApproval::Never)
+        .sandbox_mode(SandboxMode::ReadOnly)
+        .set("hide_agent_reasoning", true)
+        .build()
+        .expect("config");
+    assert_eq!(config.model_provider_id(), "embedded");
+    assert_eq!(config.model(), "gpt-5");
+    let engine = Engine::new(&config);
+
+    // Start a session and run one turn.
+    let session = engine.start(config.clone()).await.expect("start");
+    assert_eq!(session.model(), "gpt-5");
+    let output = session.run("hello").await.expect("first turn");
+    assert_eq!(
+        output.last_agent_message.as_deref(),
+        Some("Hello from the mock")
+    );
+    assert!(output.errors.is_empty(), "{:?}", output.errors);
+    let session_id = session.id();
+    session.shutdown().await.expect("shutdown");
+
+    // Resume it: the next request carries the first turn's history, and the
+    // typed stream reports the turn.
+    let resumed = engine
+        .resume(config.clone(), &session_id)
+        .await
+        .expect("resume");
+    let turn_id = resumed.submit("again").await.expect("submit");
+    let mut messages = Vec::new();
+    {
+        let mut events = Box::pin(resumed.events());
+        while let Some(event) = events.next().await {
+            match event {
+                SessionEvent::AgentMessage { turn_id: id, text } if id == turn_id => {
+                    messages.push(text);
+                }
+                SessionEvent::TurnComplete { turn_id: id, .. } if id == turn_id => break,
+                _ => {}
+            }
+        }
+    }
+    assert_eq!(messages, vec!["Second reply".to_string()]);
+    resumed.shutdown().await.expect("shutdown");
+
+    let requests = server.received_requests().await.expect("recorded requests");
+    assert_eq!(requests.len(), 2);
+    let second: Value = requests[1].body_json().unwrap();
+    let texts = user_texts(&second);
+    assert!(texts.iter().any(|text| text == "hello"), "{texts:?}");
+    assert!(texts.iter().any(|text| text == "again"), "{texts:?}");
+
+    // Fork before the second turn: a new session with a fresh id.
+    let forked = engine
+        .fork(config.clone(), &session_id, 1)
+        .await
+        .expect("fork");
+    assert_ne!(forked.id(), session_id);
+    forked.shutdown().await.expect("shutdown");
+
+    let missing = engine
+        .resume(config, "00000000-0000-0000-0000-000000000000")
+        .await;
+    assert!(matches!(missing, Err(EmbedError::SessionNotFound(_))));
+}
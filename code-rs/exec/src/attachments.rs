@@ -0,0 +1,337 @@
+//! Inputs attached alongside the prompt (`--image`, `--file`).
+//!
+//! Either flag accepts `-` to read the attachment from stdin, so CI artifacts
+//! can be piped in without writing temp files. Every `--image` is validated
+//! (exists, supported format, size limit) and read concurrently before the
+//! session starts, so a bad path fails the run up front with every problem
+//! listed instead of being dropped mid-turn. Files are inlined as an image
+//! data URL or a tagged text block.
+
+use std::io::Read;
+use std::path::Path;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use base64::Engine;
+use code_core::protocol::InputItem;
+
+use crate::event_processor::EventProcessor;
+
+/// Path value that means "read this attachment from stdin".
+pub(crate) const STDIN_PATH: &str = "-";
+
+/// Largest non-image file inlined into the prompt.
+const MAX_INLINE_FILE_BYTES: usize = 1024 * 1024;
+
+/// Largest image accepted by `--image`, matching the Responses API limit.
+const MAX_IMAGE_BYTES: u64 = 20 * 1024 * 1024;
+
+/// Outcome of validating and reading one `--image`, reported to the event
+/// processor before the session starts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ImageStatus {
+    /// Path as given on the command line (`-` for stdin).
+    pub path: String,
+    pub mime: Option<&'static str>,
+    pub bytes: u64,
+    /// Actionable reason the image was rejected; `None` when it loaded.
+    pub error: Option<String>,
+}
+
+impl ImageStatus {
+    pub fn is_ok(&self) -> bool {
+        self.error.is_none()
+    }
+
+    pub fn describe(&self) -> String {
+        match (&self.error, self.mime) {
+            (Some(error), _) => format!("{}: {error}", self.path),
+            (None, Some(mime)) => format!("{} ({mime}, {} bytes)", self.path, self.bytes),
+            (None, None) => self.path.clone(),
+        }
+    }
+}
+
+/// Fails when more than one input wants stdin. `prompt_from_stdin` is true
+/// when the prompt itself will be read from stdin.
+pub(crate) fn check_stdin_usage(
+    images: &[PathBuf],
+    files: &[PathBuf],
+    prompt_from_stdin: bool,
+) -> anyhow::Result<()> {
+    let attachments_from_stdin = images
+        .iter()
+        .chain(files)
+        .filter(|path| is_stdin(path))
+        .count();
+    if attachments_from_stdin > 1 {
+        anyhow::bail!("only one --image or --file may read from stdin (`-`)");
+    }
+    if attachments_from_stdin == 1 && prompt_from_stdin {
+        anyhow::bail!("pass the prompt as an argument when an attachment is read from stdin");
+    }
+    Ok(())
+}
+
+/// Turns `--image` and `--file` arguments into input items, images first.
+/// Images are loaded concurrently and each one's status is reported; the
+/// call fails once every image has been checked if any of them was rejected.
+pub(crate) async fn load_attachments(
+    images: Vec<PathBuf>,
+    files: Vec<PathBuf>,
+    event_processor: &mut dyn EventProcessor,
+) -> anyhow::Result<Vec<InputItem>> {
+    let total = images.len();
+    let mut items = Vec::new();
+    let mut failed = 0;
+    for (status, item) in load_images(images).await {
+        event_processor.report_image(&status);
+        match item {
+            Some(item) => items.push(item),
+            None => failed += 1,
+        }
+    }
+    if failed > 0 {
+        anyhow::bail!("{failed} of {total} --image attachments could not be used");
+    }
+    for path in files {
+        let (name, bytes) = if is_stdin(&path) {
+            ("stdin".to_string(), read_stdin()?)
+        } else {
+            let bytes = std::fs::read(&path)
+                .with_context(|| format!("failed to read --file {}", path.display()))?;
+            (path.display().to_string(), bytes)
+        };
+        items.push(file_item(&name, &path, bytes)?);
+    }
+    Ok(items)
+}
+
+/// Loads every image on its own blocking task, returning results in
+/// argument order.
+async fn load_images(images: Vec<PathBuf>) -> Vec<(ImageStatus, Option<InputItem>)> {
+    let tasks: Vec<_> = images
+        .into_iter()
+        .map(|path| {
+            let name = if is_stdin(&path) {
+                "stdin".to_string()
+            } else {
+                path.display().to_string()
+            };
+            (name, tokio::task::spawn_blocking(move || load_image(&path)))
+        })
+        .collect();
+    let mut results = Vec::with_capacity(tasks.len());
+    for (path, task) in tasks {
+        let result = task
+            .await
+            .unwrap_or_else(|err| Err(format!("failed to load: {err}")));
+        results.push(match result {
+            Ok((mime, bytes)) => (
+                ImageStatus {
+                    path,
+                    mime: Some(mime),
+                    bytes: bytes.len() as u64,
+                    error: None,
+                },
+                Some(image_item(mime, &bytes)),
+            ),
+            Err(error) => (
+                ImageStatus {
+                    path,
+                    mime: None,
+                    bytes: 0,
+                    error: Some(error),
+                },
+                None,
+            ),
+        });
+    }
+    results
+}
+
+/// Validates one image and returns its sniffed MIME type and contents, or an
+/// error message that says how to fix it.
+fn load_image(path: &Path) -> Result<(&'static str, Vec<u8>), String> {
+    let bytes = if is_stdin(path) {
+        read_stdin().map_err(|err| format!("{err:#}"))?
+    } else {
+        let metadata = std::fs::metadata(path).map_err(|err| match err.kind() {
+            std::io::ErrorKind::NotFound => {
+                "file not found; relative paths are resolved from the current directory".to_string()
+            }
+            _ => format!("cannot read file: {err}"),
+        })?;
+        if metadata.is_dir() {
+            return Err("is a directory; pass an image file".to_string());
+        }
+        check_image_size(metadata.len())?;
+        std::fs::read(path).map_err(|err| format!("cannot read file: {err}"))?
+    };
+    check_image_size(bytes.len() as u64)?;
+    let mime = sniff_mime(&bytes)
+        .ok_or_else(|| "unsupported format; convert it to PNG, JPEG, GIF, or WebP".to_string())?;
+    Ok((mime, bytes))
+}
+
+fn check_image_size(len: u64) -> Result<(), String> {
+    if len > MAX_IMAGE_BYTES {
+        return Err(format!(
+            "image is {len} bytes; images are limited to {MAX_IMAGE_BYTES} bytes, resize or compress it"
+        ));
+    }
+    Ok(())
+}
+
+fn is_stdin(path: &Path) -> bool {
+    path.as_os_str() == STDIN_PATH
+}
+
+fn read_stdin() -> anyhow::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    std::io::stdin()
+        .read_to_end(&mut bytes)
+        .context("failed to read attachment from stdin")?;
+    Ok(bytes)
+}
+
+fn file_item(name: &str, path: &Path, bytes: Vec<u8>) -> anyhow::Result<InputItem> {
+    let mime = detect_mime(path, &bytes);
+    if mime.starts_with("image/") {
+        return Ok(image_item(&mime, &bytes));
+    }
+    if bytes.len() > MAX_INLINE_FILE_BYTES {
+        anyhow::bail!(
+            "--file {name} is {} bytes; non-image attachments are limited to {MAX_INLINE_FILE_BYTES}",
+            bytes.len()
+        );
+    }
+    let text = match String::from_utf8(bytes) {
+        Ok(contents) => format!("<file name=\"{name}\" mime=\"{mime}\">\n{contents}\n</file>"),
+        Err(err) => {
+            let encoded = base64::engine::general_purpose::STANDARD.encode(err.as_bytes());
+            format!(
+                "<file name=\"{name}\" mime=\"{mime}\" encoding=\"base64\">\n{encoded}\n</file>"
+            )
+        }
+    };
+    Ok(InputItem::Text { text })
+}
+
+fn image_item(mime: &str, bytes: &[u8]) -> InputItem {
+    let encoded = base64::engine::general_purpose::STANDARD.encode(bytes);
+    InputItem::Image {
+        image_url: format!("data:{mime};base64,{encoded}"),
+    }
+}
+
+/// Content sniffing wins over the extension; stdin has no extension at all.
+fn detect_mime(path: &Path, bytes: &[u8]) -> String {
+    if let Some(mime) = sniff_mime(bytes) {
+        return mime.to_string();
+    }
+    if !is_stdin(path)
+        && let Some(mime) = mime_guess::from_path(path).first()
+    {
+        return mime.essence_str().to_string();
+    }
+    if std::str::from_utf8(bytes).is_ok() {
+        "text/plain".to_string()
+    } else {
+        "application/octet-stream".to_string()
+    }
+}
+
+fn sniff_mime(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("image/jpeg")
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        Some("image/gif")
+    } else if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn rejects_competing_stdin_readers() {
+        let stdin = vec![PathBuf::from(STDIN_PATH)];
+        assert!(check_stdin_usage(&stdin, &stdin, false).is_err());
+        assert!(check_stdin_usage(&stdin, &[], true).is_err());
+        assert!(check_stdin_usage(&stdin, &[], false).is_ok());
+        assert!(check_stdin_usage(&[], &[], true).is_ok());
+    }
+
+    #[tokio::test]
+    async fn images_are_validated_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let png = dir.path().join("shot.png");
+        std::fs::write(&png, b"\x89PNG\r\n\x1a\nrest").unwrap();
+        let text = dir.path().join("notes.png");
+        std::fs::write(&text, b"not an image").unwrap();
+        let missing = dir.path().join("missing.png");
+
+        let results = load_images(vec![png.clone(), text, missing, dir.path().to_path_buf()]).await;
+        let errors: Vec<Option<String>> = results
+            .iter()
+            .map(|(status, _)| status.error.clone())
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                None,
+                Some("unsupported format; convert it to PNG, JPEG, GIF, or WebP".to_string()),
+                Some(
+                    "file not found; relative paths are resolved from the current directory"
+                        .to_string()
+                ),
+                Some("is a directory; pass an image file".to_string()),
+            ]
+        );
+        assert_eq!(
+            results[0].0.describe(),
+            format!("{} (image/png, 12 bytes)", png.display())
+        );
+        assert!(matches!(&results[0].1, Some(InputItem::Image { .. })));
+        assert!(check_image_size(MAX_IMAGE_BYTES + 1).is_err());
+    }
+
+    #[test]
+    fn files_become_text_or_image_items_by_content() {
+        let text = file_item("notes.txt", Path::new("notes.txt"), b"hi".to_vec()).unwrap();
+        let InputItem::Text { text } = text else {
+            panic!("expected text item");
+        };
+        assert_eq!(
+            text,
+            "<file name=\"notes.txt\" mime=\"text/plain\">\nhi\n</file>"
+        );
+
+        let png = file_item(
+            "stdin",
+            Path::new(STDIN_PATH),
+            b"\x89PNG\r\n\x1a\n".to_vec(),
+        )
+        .unwrap();
+        let InputItem::Image { image_url } = png else {
+            panic!("expected image item");
+        };
+        assert!(image_url.starts_with("data:image/png;base64,"));
+
+        let binary = file_item("blob", Path::new(STDIN_PATH), vec![0, 159, 146, 150]).unwrap();
+        let InputItem::Text { text } = binary else {
+            panic!("expected text item");
+        };
+        assert!(text.starts_with(
+            "<file name=\"blob\" mime=\"application/octet-stream\" encoding=\"base64\">"
+        ));
+    }
+}
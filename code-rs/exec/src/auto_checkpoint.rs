@@ -0,0 +1,226 @@
+//! Auto Drive checkpoints for `--checkpoint-every` and
+//! `code exec resume --from-checkpoint`.
+//!
+//! The checkpoint holds the coordinator's `AutoDriveHistory`, the goal, and
+//! the number of CLI turns run so far. It is rewritten after every Nth CLI
+//! turn and marked completed when the run succeeds, so only interrupted or
+//! failed runs are offered for resume.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use code_auto_drive_core::AutoCoordinatorEvent;
+use code_auto_drive_core::AutoDriveHistory;
+use code_auto_drive_core::AutoRunPhase;
+use code_auto_drive_core::checkpoint::AutoDriveCheckpoint;
+use code_auto_drive_core::checkpoint::CheckpointManager;
+use code_auto_drive_core::checkpoint::CheckpointPhase;
+use code_auto_drive_core::checkpoint::TokenUsage as CheckpointTokenUsage;
+use code_core::protocol::TokenUsage;
+use code_protocol::models::ResponseItem;
+
+pub(crate) struct AutoCheckpointer {
+    manager: CheckpointManager,
+    checkpoint: AutoDriveCheckpoint,
+    /// Save after every `every`th CLI turn; 0 disables checkpoints.
+    every: usize,
+    /// Tokens spent before this process resumed the run.
+    prior_usage: CheckpointTokenUsage,
+    restored: bool,
+}
+
+impl AutoCheckpointer {
+    pub fn start(dir: PathBuf, every: usize, goal: &str, session_id: &str) -> anyhow::Result<Self> {
+        let mut manager = CheckpointManager::new(dir);
+        let checkpoint = manager.create(goal, session_id)?;
+        Ok(Self {
+            manager,
+            checkpoint,
+            every,
+            prior_usage: CheckpointTokenUsage::default(),
+            restored: false,
+        })
+    }
+
+    pub fn resume(dir: PathBuf, every: usize, checkpoint: AutoDriveCheckpoint) -> Self {
+        Self {
+            manager: CheckpointManager::new(dir),
+            prior_usage: checkpoint.token_usage.clone(),
+            checkpoint,
+            every,
+            restored: true,
+        }
+    }
+
+    pub fn session_id(&self) -> &str {
+        &self.checkpoint.session_id
+    }
+
+    pub fn turns_completed(&self) -> usize {
+        self.checkpoint.turns_completed
+    }
+
+    /// Coordinator history to continue from; empty for a new run.
+    pub fn history(&self) -> Vec<ResponseItem> {
+        self.checkpoint.history.clone()
+    }
+
+    /// The event announcing a resumed run.
+    pub fn restored_event(&self) -> Option<AutoCoordinatorEvent> {
+        self.restored
+            .then(|| AutoCoordinatorEvent::CheckpointRestored {
+                session_id: self.checkpoint.session_id.clone(),
+                turns: self.checkpoint.turns_completed,
+            })
+    }
+
+    /// Saves the checkpoint when `turns` is due. Returns the event to report.
+    pub fn after_turn(
+        &mut self,
+        history: &AutoDriveHistory,
+        turns: usize,
+        usage: &TokenUsage,
+    ) -> Option<AutoCoordinatorEvent> {
+        if self.every == 0 || turns % self.every != 0 {
+            return None;
+        }
+        match self.save(history, turns, usage, &AutoRunPhase::Active) {
+            Ok(()) => Some(AutoCoordinatorEvent::CheckpointSaved {
+                session_id: self.checkpoint.session_id.clone(),
+                turns,
+            }),
+            Err(err) => {
+                eprintln!("[auto] failed to save checkpoint: {err:#}");
+                None
+            }
+        }
+    }
+
+    /// Records the final state; a successful run is marked completed so it is
+    /// no longer offered for resume.
+    pub fn finish(
+        &mut self,
+        history: &AutoDriveHistory,
+        turns: usize,
+        usage: &TokenUsage,
+        success: bool,
+    ) {
+        if self.every == 0 && !self.restored {
+            return;
+        }
+        let result = self
+            .save(history, turns, usage, &AutoRunPhase::Active)
+            .and_then(|()| {
+                if !success {
+                    return Ok(());
+                }
+                self.checkpoint.phase = CheckpointPhase::Completed;
+                self.manager.save(&self.checkpoint)
+            });
+        if let Err(err) = result {
+            eprintln!("[auto] failed to save checkpoint: {err:#}");
+        }
+    }
+
+    fn save(
+        &mut self,
+        history: &AutoDriveHistory,
+        turns: usize,
+        usage: &TokenUsage,
+        phase: &AutoRunPhase,
+    ) -> anyhow::Result<()> {
+        let usage = CheckpointTokenUsage {
+            input_tokens: self.prior_usage.input_tokens + usage.input_tokens,
+            output_tokens: self.prior_usage.output_tokens + usage.output_tokens,
+            total_tokens: self.prior_usage.total_tokens + usage.total_tokens,
+        };
+        self.manager.update(
+            &mut self.checkpoint,
+            history.raw_snapshot(),
+            turns,
+            usage,
+            phase,
+        )
+    }
+}
+
+/// Loads the checkpoint for `session_id`, or the most recent unfinished one.
+pub(crate) fn load(dir: &Path, session_id: Option<&str>) -> anyhow::Result<AutoDriveCheckpoint> {
+    let manager = CheckpointManager::new(dir.to_path_buf());
+    let checkpoint = match session_id {
+        Some(id) => manager.restore(id)?,
+        None => manager.restore_latest()?,
+    };
+    let Some(checkpoint) = checkpoint else {
+        anyhow::bail!(
+            "no Auto Drive checkpoint found in {}; run with --auto --checkpoint-every N first",
+            dir.display()
+        );
+    };
+    if checkpoint.phase == CheckpointPhase::Completed {
+        anyhow::bail!(
+            "Auto Drive session {} already completed; nothing to resume",
+            checkpoint.session_id
+        );
+    }
+    Ok(checkpoint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use code_protocol::models::ContentItem;
+    use pretty_assertions::assert_eq;
+
+    fn message(text: &str) -> ResponseItem {
+        ResponseItem::Message {
+            id: None,
+            role: "user".to_string(),
+            content: vec![ContentItem::InputText {
+                text: text.to_string(),
+            }],
+        }
+    }
+
+    #[test]
+    fn resumes_history_from_the_last_due_turn() {
+        let dir = tempfile::tempdir().unwrap();
+        let usage = TokenUsage {
+            total_tokens: 100,
+            ..Default::default()
+        };
+        let mut checkpoints =
+            AutoCheckpointer::start(dir.path().to_path_buf(), 2, "ship it", "session-1").unwrap();
+        let mut history = AutoDriveHistory::new();
+
+        history.append_raw(&[message("turn 1")]);
+        assert!(checkpoints.after_turn(&history, 1, &usage).is_none());
+        history.append_raw(&[message("turn 2")]);
+        assert!(checkpoints.after_turn(&history, 2, &usage).is_some());
+        history.append_raw(&[message("turn 3")]);
+        assert!(checkpoints.after_turn(&history, 3, &usage).is_none());
+
+        let checkpoint = load(dir.path(), None).unwrap();
+        assert_eq!(checkpoint.goal, "ship it");
+        assert_eq!(checkpoint.turns_completed, 2);
+        assert_eq!(checkpoint.history.len(), 2);
+
+        let mut resumed = AutoCheckpointer::resume(dir.path().to_path_buf(), 2, checkpoint);
+        assert!(matches!(
+            resumed.restored_event(),
+            Some(AutoCoordinatorEvent::CheckpointRestored { turns: 2, .. })
+        ));
+        let mut history = AutoDriveHistory::new();
+        history.replace_all(resumed.history());
+        resumed.finish(&history, 2, &usage, true);
+
+        let err = load(dir.path(), Some("session-1")).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Auto Drive session session-1 already completed; nothing to resume"
+        );
+        let manager = CheckpointManager::new(dir.path().to_path_buf());
+        let saved = manager.restore("session-1").unwrap().unwrap();
+        assert_eq!(saved.token_usage.total_tokens, 200);
+    }
+}
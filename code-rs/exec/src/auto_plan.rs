@@ -0,0 +1,175 @@
+//! `--auto-plan-only`: let the coordinator plan a run without executing it.
+//!
+//! The coordinator makes its first N decisions as usual, but every CLI turn
+//! is answered with a stub reply instead of reaching the CLI session, and
+//! workstreams and agents are never launched. The recorded decisions are
+//! printed as a plan so the fan-out can be reviewed before granting write
+//! access.
+
+use code_auto_drive_core::AutoCoordinatorStatus;
+use code_auto_drive_core::AutoTurnAgentsAction;
+use code_auto_drive_core::AutoTurnAgentsTiming;
+use code_auto_drive_core::AutoTurnCliAction;
+
+/// Stands in for the CLI's reply to every planned turn.
+pub(crate) const SIMULATED_CLI_REPLY: &str = "Acknowledged, not executed: this is a plan-only run, so no command ran and no file changed. Plan the next step as if this one had succeeded.";
+
+#[derive(Debug)]
+pub(crate) struct AutoPlan {
+    max_decisions: usize,
+    steps: Vec<PlanStep>,
+}
+
+#[derive(Debug)]
+struct PlanStep {
+    status: AutoCoordinatorStatus,
+    title: Option<String>,
+    cli_prompt: Option<String>,
+    workstreams: Vec<String>,
+    agents: Vec<PlannedAgent>,
+}
+
+#[derive(Debug)]
+struct PlannedAgent {
+    prompt: String,
+    write: bool,
+    timing: Option<AutoTurnAgentsTiming>,
+}
+
+impl AutoPlan {
+    pub fn new(max_decisions: usize) -> Self {
+        Self {
+            max_decisions: max_decisions.max(1),
+            steps: Vec::new(),
+        }
+    }
+
+    pub fn record(
+        &mut self,
+        status: AutoCoordinatorStatus,
+        title: Option<&str>,
+        cli: Option<&AutoTurnCliAction>,
+        agents: &[AutoTurnAgentsAction],
+        agents_timing: Option<AutoTurnAgentsTiming>,
+    ) {
+        self.steps.push(PlanStep {
+            status,
+            title: non_empty(title),
+            cli_prompt: cli.and_then(|action| non_empty(Some(&action.prompt))),
+            workstreams: cli
+                .map(|action| {
+                    action
+                        .workstreams
+                        .iter()
+                        .map(|workstream| {
+                            format!("{}: {}", workstream.id, one_line(&workstream.prompt))
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+            agents: agents
+                .iter()
+                .map(|agent| PlannedAgent {
+                    prompt: one_line(&agent.prompt),
+                    write: agent.write,
+                    timing: agents_timing,
+                })
+                .collect(),
+        });
+    }
+
+    /// True once the requested number of decisions has been planned.
+    pub fn is_complete(&self) -> bool {
+        self.steps.len() >= self.max_decisions
+    }
+
+    pub fn render(&self) -> String {
+        let mut lines = vec![format!(
+            "[auto] plan ({} decisions, nothing executed):",
+            self.steps.len()
+        )];
+        for (index, step) in self.steps.iter().enumerate() {
+            let title = step.title.as_deref().unwrap_or("(untitled)");
+            lines.push(format!("{}. {title} [{:?}]", index + 1, step.status));
+            if let Some(prompt) = step.cli_prompt.as_deref() {
+                lines.push(format!("   cli: {}", one_line(prompt)));
+            }
+            for workstream in &step.workstreams {
+                lines.push(format!("   workstream {workstream}"));
+            }
+            for agent in &step.agents {
+                let access = if agent.write { "write" } else { "read-only" };
+                let timing = match agent.timing {
+                    Some(AutoTurnAgentsTiming::Blocking) => ", blocking",
+                    Some(AutoTurnAgentsTiming::Parallel) => ", parallel",
+                    None => "",
+                };
+                lines.push(format!("   agent ({access}{timing}): {}", agent.prompt));
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+fn non_empty(text: Option<&str>) -> Option<String> {
+    text.map(str::trim)
+        .filter(|text| !text.is_empty())
+        .map(str::to_string)
+}
+
+fn one_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use code_auto_drive_core::AutoTurnWorkstream;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn renders_decisions_with_their_fan_out() {
+        let cli = AutoTurnCliAction {
+            prompt: "Add the tokenizer module\nand its tests".to_string(),
+            context: None,
+            suppress_ui_context: false,
+            attachments: Vec::new(),
+            workstreams: vec![AutoTurnWorkstream {
+                id: "docs".to_string(),
+                prompt: "Document the tokenizer".to_string(),
+                context: None,
+            }],
+            complexity: None,
+            model: None,
+        };
+        let agents = vec![AutoTurnAgentsAction {
+            prompt: "Review the grammar".to_string(),
+            context: None,
+            write: false,
+            write_requested: None,
+            models: None,
+            timeout_seconds: None,
+        }];
+        let mut plan = AutoPlan::new(2);
+        plan.record(
+            AutoCoordinatorStatus::Continue,
+            Some("Build the tokenizer"),
+            Some(&cli),
+            &agents,
+            Some(AutoTurnAgentsTiming::Parallel),
+        );
+        assert!(!plan.is_complete());
+        plan.record(AutoCoordinatorStatus::Success, Some(" "), None, &[], None);
+        assert!(plan.is_complete());
+
+        assert_eq!(
+            plan.render(),
+            "[auto] plan (2 decisions, nothing executed):\n\
+             1. Build the tokenizer [Continue]\n   \
+             cli: Add the tokenizer module and its tests\n   \
+             workstream docs: Document the tokenizer\n   \
+             agent (read-only, parallel): Review the grammar\n\
+             2. (untitled) [Success]"
+        );
+    }
+}
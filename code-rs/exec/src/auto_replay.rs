@@ -0,0 +1,111 @@
+//! Recording and replay of exec Auto Drive runs (`code exec auto replay`).
+
+use std::ops::ControlFlow;
+use std::sync::Arc;
+
+use code_auto_drive_core::event_log::AutoEventLogEntry;
+use code_auto_drive_core::event_log::AutoEventLogWriter;
+use code_auto_drive_core::event_log::event_log_path;
+use code_auto_drive_core::event_log::read_event_log;
+use code_core::at_rest;
+use code_core::config::Config;
+use code_core::protocol::Event;
+
+use crate::attachments::ImageStatus;
+use crate::cli::ReplayArgs;
+use crate::cost_report::CostReport;
+use crate::event_processor::CodexStatus;
+use crate::event_processor::EventProcessor;
+use crate::exit_code::FailureClass;
+use crate::interrupt::Interruption;
+use crate::print_auto_event;
+use crate::replay::replay_paced;
+use crate::run_environment::RunEnvironment;
+use crate::run_guard::RunLimit;
+
+/// Forwards events to the wrapped processor and records each one in the
+/// session's Auto Drive event log.
+pub(crate) struct RecordingEventProcessor {
+    inner: Box<dyn EventProcessor>,
+    log: Arc<AutoEventLogWriter>,
+}
+
+impl RecordingEventProcessor {
+    pub fn new(inner: Box<dyn EventProcessor>, log: Arc<AutoEventLogWriter>) -> Self {
+        Self { inner, log }
+    }
+}
+
+impl EventProcessor for RecordingEventProcessor {
+    fn print_config_summary(&mut self, config: &Config, prompt: &str) {
+        self.inner.print_config_summary(config, prompt);
+    }
+
+    fn process_event(&mut self, event: Event) -> CodexStatus {
+        self.log.record_conversation(&event);
+        self.inner.process_event(event)
+    }
+
+    fn report_run_limit(&mut self, limit: RunLimit) {
+        self.inner.report_run_limit(limit);
+    }
+
+    fn report_interruption(&mut self, interruption: Interruption) {
+        self.inner.report_interruption(interruption);
+    }
+
+    fn report_environment(&mut self, environment: &RunEnvironment) {
+        self.inner.report_environment(environment);
+    }
+
+    fn report_image(&mut self, status: &ImageStatus) {
+        self.inner.report_image(status);
+    }
+
+    fn report_failure(&mut self, failure: FailureClass) {
+        self.inner.report_failure(failure);
+    }
+
+    fn report_cost(&mut self, report: &CostReport) {
+        self.inner.report_cost(report);
+    }
+}
+
+/// Re-emits a recorded run through `event_processor` and the `[auto]`
+/// renderer. `speed` scales the recorded gaps between events; `0` replays
+/// without pauses.
+pub(crate) async fn run_replay(
+    args: ReplayArgs,
+    config: &Config,
+    mut event_processor: Box<dyn EventProcessor>,
+) -> anyhow::Result<()> {
+    let path = event_log_path(&config.code_home, &args.session_id);
+    let cipher = at_rest::cipher_for(config.encrypt_at_rest, &config.code_home)?;
+    let records = read_event_log(&path, cipher.as_deref())?;
+    if records.is_empty() {
+        anyhow::bail!("auto drive event log {} has no events", path.display());
+    }
+    eprintln!(
+        "[replay] {} event(s) from {} at {}x",
+        records.len(),
+        path.display(),
+        args.speed
+    );
+
+    let records = records
+        .into_iter()
+        .map(|record| (record.offset_ms, record.entry));
+    replay_paced(records, args.speed, |entry| {
+        match entry {
+            AutoEventLogEntry::Coordinator(event) => print_auto_event(&event),
+            AutoEventLogEntry::Conversation(event) => {
+                if matches!(event_processor.process_event(event), CodexStatus::Shutdown) {
+                    return ControlFlow::Break(());
+                }
+            }
+        }
+        ControlFlow::Continue(())
+    })
+    .await;
+    Ok(())
+}
@@ -0,0 +1,241 @@
+//! `--schedule` and `code exec scheduler`: queue Auto Drive runs and start
+//! them when they come due.
+//!
+//! A queued run keeps the arguments `code exec` was started with, minus
+//! `--schedule`, and the scheduler re-launches this binary with them plus
+//! `--schedule now`. Budgets, checkpoints, reports, and every other flag
+//! therefore behave exactly as if the run had been started by hand.
+
+use std::process::Stdio;
+use std::time::Duration;
+
+use chrono::DateTime;
+use chrono::Local;
+use chrono::Utc;
+use code_auto_drive_core::run_scheduler::RunQueue;
+use code_auto_drive_core::run_scheduler::SCHEDULE_NOW;
+use code_auto_drive_core::run_scheduler::ScheduledRun;
+use code_auto_drive_core::run_scheduler::ScheduledRunResult;
+use code_auto_drive_core::run_scheduler::default_schedule_dir;
+use code_core::config::Config;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use crate::cli::SchedulerArgs;
+use crate::cli::SchedulerCommand;
+use crate::cli::SchedulerRunArgs;
+
+const SCHEDULE_FLAG: &str = "--schedule";
+
+/// The schedule the run should wait for: `--schedule`, else
+/// `auto_drive.schedule`. `None` runs immediately.
+pub(crate) fn effective_schedule(cli: Option<String>, config: &Config) -> Option<String> {
+    cli.or_else(|| config.auto_drive.schedule.clone())
+        .map(|when| when.trim().to_string())
+        .filter(|when| !when.is_empty() && !when.eq_ignore_ascii_case(SCHEDULE_NOW))
+}
+
+/// Queues the current invocation to run at `when`. `stdin_prompt` is the
+/// goal when it was piped in rather than passed as an argument.
+pub(crate) fn queue_run(
+    config: &Config,
+    goal: &str,
+    when: &str,
+    stdin_prompt: Option<String>,
+) -> anyhow::Result<()> {
+    let run = ScheduledRun::new(
+        goal,
+        when,
+        std::env::current_dir()?,
+        scheduled_args(std::env::args().skip(1))?,
+        stdin_prompt,
+    )?;
+    RunQueue::new(default_schedule_dir(&config.code_home)).add(&run)?;
+    out_println!(
+        "[auto] scheduled run {} for {} ({}); `code exec scheduler run` starts it",
+        run.id,
+        local_time(run.next_run_at),
+        run.schedule
+    );
+    Ok(())
+}
+
+pub(crate) async fn run_scheduler(args: SchedulerArgs, config: &Config) -> anyhow::Result<()> {
+    let queue = RunQueue::new(default_schedule_dir(&config.code_home));
+    match args.command {
+        SchedulerCommand::Run(args) => run_due_jobs(&queue, args).await,
+        SchedulerCommand::List => {
+            let runs = queue.list()?;
+            if runs.is_empty() {
+                out_println!("No scheduled runs.");
+            }
+            for run in runs {
+                let goal = run.goal.trim().lines().next().unwrap_or_default();
+                out_println!(
+                    "{}  {}  {}  {goal}",
+                    run.id,
+                    local_time(run.next_run_at),
+                    run.schedule
+                );
+            }
+            Ok(())
+        }
+        SchedulerCommand::Cancel(args) => {
+            if !queue.cancel(&args.id)? {
+                anyhow::bail!("no scheduled run {}", args.id);
+            }
+            out_println!("Cancelled scheduled run {}.", args.id);
+            Ok(())
+        }
+    }
+}
+
+async fn run_due_jobs(queue: &RunQueue, args: SchedulerRunArgs) -> anyhow::Result<()> {
+    let poll_interval = Duration::from_secs(args.poll_interval.max(1));
+    if !args.once {
+        eprintln!(
+            "[scheduler] waiting for due runs (checking every {}s)",
+            poll_interval.as_secs()
+        );
+    }
+    loop {
+        let now = Utc::now();
+        for run in queue.list()?.into_iter().filter(|run| run.is_due(now)) {
+            if !queue.claim(&run)? {
+                continue;
+            }
+            let result = start_run(&run).await;
+            let exit = match result.exit_code {
+                Some(code) => format!("exit {code}"),
+                None => "killed".to_string(),
+            };
+            eprintln!("[scheduler] run {} finished ({exit})", run.id);
+            queue.complete(run, result)?;
+        }
+        if args.once {
+            return Ok(());
+        }
+        tokio::time::sleep(poll_interval).await;
+    }
+}
+
+async fn start_run(run: &ScheduledRun) -> ScheduledRunResult {
+    let started_at = Utc::now();
+    let goal = run.goal.trim().lines().next().unwrap_or_default();
+    eprintln!("[scheduler] starting run {}: {goal}", run.id);
+    let exit_code = match launch(run).await {
+        Ok(code) => code,
+        Err(err) => {
+            eprintln!("[scheduler] run {} could not start: {err:#}", run.id);
+            Some(1)
+        }
+    };
+    ScheduledRunResult {
+        started_at,
+        finished_at: Utc::now(),
+        exit_code,
+    }
+}
+
+async fn launch(run: &ScheduledRun) -> anyhow::Result<Option<i32>> {
+    let mut child = Command::new(std::env::current_exe()?)
+        .args(&run.args)
+        .args([SCHEDULE_FLAG, SCHEDULE_NOW])
+        .current_dir(&run.launch_dir)
+        .stdin(if run.stdin.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .spawn()?;
+    if let Some(prompt) = run.stdin.as_deref()
+        && let Some(mut stdin) = child.stdin.take()
+    {
+        stdin.write_all(prompt.as_bytes()).await?;
+    }
+    Ok(child.wait().await?.code())
+}
+
+/// `args` without `--schedule` and its value. Refuses `-c` overrides: the
+/// queue is stored in plain text and their values may hold secrets.
+fn scheduled_args(args: impl IntoIterator<Item = String>) -> anyhow::Result<Vec<String>> {
+    let mut kept = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            kept.push(arg);
+            kept.extend(args);
+            break;
+        }
+        if arg == "--config" || arg.starts_with("--config=") || arg.starts_with("-c") {
+            anyhow::bail!(
+                "--schedule does not save `-c`/`--config` overrides because they may hold \
+                 secrets; move them to config.toml or a profile (`--profile`)"
+            );
+        }
+        if arg == SCHEDULE_FLAG {
+            args.next();
+        } else if !arg.starts_with(&format!("{SCHEDULE_FLAG}=")) {
+            kept.push(arg);
+        }
+    }
+    Ok(kept)
+}
+
+fn local_time(at: DateTime<Utc>) -> String {
+    at.with_timezone(&Local)
+        .format("%Y-%m-%d %H:%M %Z")
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn queued_arguments_drop_the_schedule() {
+        let args = [
+            "exec",
+            "--auto",
+            "--schedule",
+            "daily 02:00",
+            "--max-tokens",
+            "50000",
+            "--schedule=03:00",
+            "Fix the flaky test",
+        ]
+        .map(str::to_string);
+        assert_eq!(
+            scheduled_args(args).unwrap(),
+            vec![
+                "exec",
+                "--auto",
+                "--max-tokens",
+                "50000",
+                "Fix the flaky test"
+            ]
+        );
+    }
+
+    #[test]
+    fn config_overrides_are_not_queued() {
+        for override_args in [
+            ["-c", "model_providers.corp.http_headers.token=\"secret\""],
+            ["--config", "model=gpt-5"],
+            ["--config=model=gpt-5", "Fix it"],
+            ["-cmodel=gpt-5", "Fix it"],
+        ] {
+            let args = ["exec", "--auto"]
+                .into_iter()
+                .chain(override_args)
+                .map(str::to_string);
+            assert!(scheduled_args(args).is_err(), "{override_args:?}");
+        }
+        let after_separator = ["exec", "--", "-c is a flag"].map(str::to_string);
+        assert_eq!(
+            scheduled_args(after_separator).unwrap(),
+            vec!["exec", "--", "-c is a flag"]
+        );
+    }
+}
@@ -0,0 +1,410 @@
+//! `code exec batch`: run many prompts from a single manifest file.
+//!
+//! A manifest lists prompts together with optional per-entry overrides for
+//! the working directory, model, sandbox mode and last-message output file.
+//! All entries share one [`ConversationManager`] (and therefore one
+//! [`AuthManager`]) so the process pays startup cost only once.
+//!
+//! Manifests may be written as JSON, TOML or YAML; the format is picked from
+//! the file extension (`.toml` selects TOML, `.yaml`/`.yml` YAML, anything
+//! else is parsed as JSON).
+//!
+//! ```toml
+//! parallel = 2
+//!
+//! [[prompts]]
+//! id = "docs"
+//! prompt = "Update the README"
+//! cwd = "../docs"
+//! output = "out/docs.md"
+//!
+//! [[prompts]]
+//! prompt = "Fix clippy warnings"
+//! model = "gpt-5.1-codex"
+//! sandbox = "workspace-write"
+//! ```
+
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::Context;
+use code_core::AuthManager;
+use code_core::ConversationManager;
+use code_core::NewConversation;
+use code_core::config::Config;
+use code_core::config::ConfigOverrides;
+use code_core::git_info::get_git_repo_root;
+use code_core::protocol::AskForApproval;
+use code_core::protocol::EventMsg;
+use code_core::protocol::InputItem;
+use code_core::protocol::Op;
+use code_core::protocol::TaskCompleteEvent;
+use code_protocol::config_types::SandboxMode;
+use code_protocol::protocol::SessionSource;
+use serde::Deserialize;
+use tokio::sync::Semaphore;
+
+use crate::cli::BatchArgs;
+use crate::event_processor::LastMessageWrite;
+use crate::event_processor::handle_last_message;
+
+/// Top-level manifest document.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct BatchManifest {
+    /// Default concurrency when `--parallel` is not given on the command line.
+    #[serde(default)]
+    pub parallel: Option<usize>,
+    pub prompts: Vec<BatchEntry>,
+}
+
+/// A single prompt to run. Relative paths resolve against the manifest's
+/// directory.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct BatchEntry {
+    /// Label used in progress output. Defaults to the 1-based entry index.
+    #[serde(default)]
+    pub id: Option<String>,
+    pub prompt: String,
+    #[serde(default)]
+    pub cwd: Option<PathBuf>,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub sandbox: Option<SandboxMode>,
+    /// File that receives the final agent message for this entry.
+    #[serde(default)]
+    pub output: Option<PathBuf>,
+}
+
+/// Options shared by every entry, taken from the parent `code exec` flags.
+pub(crate) struct BatchDefaults {
+    pub model: Option<String>,
+    pub model_provider: Option<String>,
+    pub config_profile: Option<String>,
+    pub sandbox_mode: Option<SandboxMode>,
+    pub cwd: Option<PathBuf>,
+    pub code_linux_sandbox_exe: Option<PathBuf>,
+    pub include_plan_tool: bool,
+    pub skip_git_repo_check: bool,
+    pub cli_kv_overrides: Vec<(String, toml::Value)>,
+}
+
+#[derive(Debug)]
+struct BatchOutcome {
+    label: String,
+    result: anyhow::Result<()>,
+}
+
+pub(crate) fn load_manifest(path: &Path) -> anyhow::Result<BatchManifest> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read batch manifest {}", path.display()))?;
+    let manifest = parse_manifest(path, &contents)?;
+    if manifest.prompts.is_empty() {
+        anyhow::bail!("batch manifest {} contains no prompts", path.display());
+    }
+    if let Some(index) = manifest
+        .prompts
+        .iter()
+        .position(|entry| entry.prompt.trim().is_empty())
+    {
+        anyhow::bail!(
+            "batch manifest {} entry #{} has an empty prompt",
+            path.display(),
+            index + 1
+        );
+    }
+    Ok(manifest)
+}
+
+fn parse_manifest(path: &Path, contents: &str) -> anyhow::Result<BatchManifest> {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase);
+    match extension.as_deref() {
+        Some("toml") => toml::from_str(contents)
+            .with_context(|| format!("batch manifest {} is not valid TOML", path.display())),
+        Some("yaml" | "yml") => serde_yaml::from_str(contents)
+            .with_context(|| format!("batch manifest {} is not valid YAML", path.display())),
+        _ => serde_json::from_str(contents)
+            .with_context(|| format!("batch manifest {} is not valid JSON", path.display())),
+    }
+}
+
+fn resolve_relative(base: &Path, path: &Path) -> PathBuf {
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        base.join(path)
+    }
+}
+
+pub(crate) async fn run_batch(args: BatchArgs, defaults: BatchDefaults) -> anyhow::Result<()> {
+    let manifest = load_manifest(&args.manifest)?;
+    let manifest_dir = args
+        .manifest
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    let parallel = args.parallel.or(manifest.parallel).unwrap_or(1).max(1);
+
+    // Resolve every entry's config up front so configuration mistakes are
+    // reported before any model traffic starts.
+    let mut jobs: Vec<(String, Config, String, Option<PathBuf>)> = Vec::new();
+    for (index, entry) in manifest.prompts.into_iter().enumerate() {
+        let label = entry
+            .id
+            .clone()
+            .unwrap_or_else(|| format!("#{}", index + 1));
+        let cwd = entry
+            .cwd
+            .as_deref()
+            .map(|cwd| resolve_relative(&manifest_dir, cwd))
+            .or_else(|| defaults.cwd.clone());
+        let overrides = ConfigOverrides {
+            model: entry.model.clone().or_else(|| defaults.model.clone()),
+            config_profile: defaults.config_profile.clone(),
+            approval_policy: Some(AskForApproval::Never),
+            sandbox_mode: entry.sandbox.or(defaults.sandbox_mode),
+            cwd: cwd.map(|p| p.canonicalize().unwrap_or(p)),
+            model_provider: defaults.model_provider.clone(),
+            code_linux_sandbox_exe: defaults.code_linux_sandbox_exe.clone(),
+            include_plan_tool: Some(defaults.include_plan_tool),
+            ..Default::default()
+        };
+        let config = Config::load_with_cli_overrides(defaults.cli_kv_overrides.clone(), overrides)
+            .with_context(|| format!("failed to load config for batch entry {label}"))?;
+        if !defaults.skip_git_repo_check && get_git_repo_root(&config.cwd).is_none() {
+            anyhow::bail!(
+                "batch entry {label}: {} is not inside a trusted directory and --skip-git-repo-check was not specified",
+                config.cwd.display()
+            );
+        }
+        let output = entry
+            .output
+            .as_deref()
+            .map(|path| resolve_relative(&manifest_dir, path));
+        jobs.push((label, config, entry.prompt, output));
+    }
+
+    let Some((_, first_config, _, _)) = jobs.first() else {
+        return Ok(());
+    };
+    let auth_manager = AuthManager::shared_with_mode_and_originator(
+        first_config.code_home.clone(),
+        code_protocol::mcp_protocol::AuthMode::ApiKey,
+        first_config.responses_originator_header.clone(),
+    );
+    let conversation_manager =
+        Arc::new(ConversationManager::new(auth_manager, SessionSource::Exec));
+
+    let total = jobs.len();
+    eprintln!("[batch] running {total} prompt(s) with parallelism {parallel}");
+
+    let semaphore = Arc::new(Semaphore::new(parallel));
+    let mut handles = Vec::with_capacity(total);
+    for (label, config, prompt, output) in jobs {
+        let semaphore = semaphore.clone();
+        let conversation_manager = conversation_manager.clone();
+        handles.push(tokio::spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            eprintln!("[batch] {label}: started");
+            let result = run_entry(&conversation_manager, config, prompt, output.as_deref()).await;
+            match &result {
+                Ok(()) => eprintln!("[batch] {label}: ok"),
+                Err(err) => eprintln!("[batch] {label}: failed: {err:#}"),
+            }
+            BatchOutcome { label, result }
+        }));
+    }
+
+    let mut failed: Vec<String> = Vec::new();
+    for handle in handles {
+        match handle.await {
+            Ok(BatchOutcome { label, result }) => {
+                if result.is_err() {
+                    failed.push(label);
+                }
+            }
+            Err(err) => failed.push(format!("<panicked: {err}>")),
+        }
+    }
+
+    eprintln!(
+        "[batch] {} succeeded, {} failed",
+        total - failed.len(),
+        failed.len()
+    );
+    if !failed.is_empty() {
+        anyhow::bail!("batch entries failed: {}", failed.join(", "));
+    }
+    Ok(())
+}
+
+async fn run_entry(
+    conversation_manager: &ConversationManager,
+    config: Config,
+    prompt: String,
+    output: Option<&Path>,
+) -> anyhow::Result<()> {
+    let NewConversation {
+        conversation_id,
+        conversation,
+        ..
+    } = conversation_manager.new_conversation(config).await?;
+
+    let submit_id = conversation
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text { text: prompt }],
+        })
+        .await?;
+
+    let mut last_agent_message: Option<String> = None;
+    let mut error_message: Option<String> = None;
+    loop {
+        let event = conversation.next_event().await?;
+        match event.msg {
+            EventMsg::Error(err) => {
+                error_message = Some(err.message);
+            }
+            EventMsg::TaskComplete(TaskCompleteEvent {
+                last_agent_message: message,
+            }) if event.id == submit_id => {
+                last_agent_message = message;
+                break;
+            }
+            EventMsg::ShutdownComplete => break,
+            _ => {}
+        }
+    }
+
+    let _ = conversation.submit(Op::Shutdown).await;
+    while let Ok(event) = conversation.next_event().await {
+        if matches!(event.msg, EventMsg::ShutdownComplete) {
+            break;
+        }
+    }
+    conversation_manager
+        .remove_conversation(&conversation_id)
+        .await;
+
+    if let Some(path) = output {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create {}", parent.display()))?;
+        }
+        if let LastMessageWrite::Failed { error } =
+            handle_last_message(last_agent_message.as_deref(), path).await
+        {
+            anyhow::bail!("failed to write {}: {error}", path.display());
+        }
+    }
+
+    match error_message {
+        Some(message) => Err(anyhow::anyhow!(message)),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parses_json_manifest() {
+        let manifest = parse_manifest(
+            Path::new("batch.json"),
+            r#"{
+                "parallel": 3,
+                "prompts": [
+                    { "prompt": "one" },
+                    { "id": "two", "prompt": "two", "cwd": "sub", "model": "m", "sandbox": "read-only", "output": "out.md" }
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            manifest,
+            BatchManifest {
+                parallel: Some(3),
+                prompts: vec![
+                    BatchEntry {
+                        id: None,
+                        prompt: "one".to_string(),
+                        cwd: None,
+                        model: None,
+                        sandbox: None,
+                        output: None,
+                    },
+                    BatchEntry {
+                        id: Some("two".to_string()),
+                        prompt: "two".to_string(),
+                        cwd: Some(PathBuf::from("sub")),
+                        model: Some("m".to_string()),
+                        sandbox: Some(SandboxMode::ReadOnly),
+                        output: Some(PathBuf::from("out.md")),
+                    },
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn parses_toml_manifest() {
+        let manifest = parse_manifest(
+            Path::new("batch.toml"),
+            "[[prompts]]\nprompt = \"hello\"\nsandbox = \"workspace-write\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            manifest,
+            BatchManifest {
+                parallel: None,
+                prompts: vec![BatchEntry {
+                    id: None,
+                    prompt: "hello".to_string(),
+                    cwd: None,
+                    model: None,
+                    sandbox: Some(SandboxMode::WorkspaceWrite),
+                    output: None,
+                }],
+            }
+        );
+    }
+
+    #[test]
+    fn parses_yaml_manifest() {
+        let manifest = parse_manifest(
+            Path::new("batch.yml"),
+            "parallel: 2\nprompts:\n  - id: docs\n    prompt: Update the README\n    output: out/docs.md\n",
+        )
+        .unwrap();
+        assert_eq!(
+            manifest,
+            BatchManifest {
+                parallel: Some(2),
+                prompts: vec![BatchEntry {
+                    id: Some("docs".to_string()),
+                    prompt: "Update the README".to_string(),
+                    cwd: None,
+                    model: None,
+                    sandbox: None,
+                    output: Some(PathBuf::from("out/docs.md")),
+                }],
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_empty_prompt() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("batch.json");
+        std::fs::write(&path, r#"{"prompts":[{"prompt":"  "}]}"#).unwrap();
+        let err = load_manifest(&path).unwrap_err();
+        assert!(err.to_string().contains("entry #1 has an empty prompt"));
+    }
+}
@@ -3,6 +3,9 @@ use clap::ValueEnum;
 use code_common::CliConfigOverrides;
 use std::path::PathBuf;
 
+use crate::event_sink::EventSinkAddr;
+use crate::prompt_template::TemplateVar;
+
 #[derive(Parser, Debug)]
 #[command(version)]
 pub struct Cli {
@@ -14,7 +17,144 @@ pub struct Cli {
     #[arg(long = "auto", default_value_t = false)]
     pub auto_drive: bool,
 
-    /// Optional image(s) to attach to the initial prompt.
+    /// Run Auto Drive on each goal of a YAML backlog in order, carrying a
+    /// summary of finished goals into the next one. Goals may set their own
+    /// `max_tokens`, `max_cost`, and `max_duration`.
+    #[arg(
+        long = "auto-backlog",
+        value_name = "FILE",
+        conflicts_with_all = ["prompt", "template"]
+    )]
+    pub auto_backlog: Option<PathBuf>,
+
+    /// With Auto Drive, run planning and read-only turns freely but pause
+    /// before the first turn that writes files and ask for approval (see
+    /// `--on-intervention`).
+    #[arg(long = "auto-confirm-first-write", default_value_t = false)]
+    pub auto_confirm_first_write: bool,
+
+    /// With Auto Drive, write every coordinator request (developer messages,
+    /// schema, conversation slice), its raw output, and how it parsed to a
+    /// per-run directory under `debug_logs/auto_coordinator`.
+    #[arg(long = "debug-coordinator", default_value_t = false)]
+    pub debug_coordinator: bool,
+
+    /// With Auto Drive, save every coordinator response to DIR (one
+    /// `NNNN.json` per request) so the run's decisions can be replayed.
+    #[arg(long = "record-decisions", value_name = "DIR")]
+    pub record_decisions: Option<PathBuf>,
+
+    /// With Auto Drive, answer coordinator requests with the responses
+    /// recorded by `--record-decisions DIR`, in order, instead of calling the
+    /// model. CLI turns still run for real.
+    #[arg(
+        long = "replay-decisions",
+        value_name = "DIR",
+        conflicts_with = "record_decisions"
+    )]
+    pub replay_decisions: Option<PathBuf>,
+
+    /// Run Auto Drive for its first N decisions (default 5) without
+    /// executing anything: CLI turns get a stub reply, workstreams and agents
+    /// are not launched, and the resulting plan is printed.
+    #[arg(
+        long = "auto-plan-only",
+        value_name = "N",
+        num_args = 0..=1,
+        default_missing_value = "5",
+        conflicts_with_all = ["auto_backlog", "auto_resume", "generate_commit_message"]
+    )]
+    pub auto_plan_only: Option<usize>,
+
+    /// With Auto Drive, what to do when the coordinator asks for
+    /// intervention. Defaults to `pause` when stdin is a terminal and `fail`
+    /// otherwise.
+    #[arg(long = "on-intervention", value_enum, value_name = "POLICY")]
+    pub on_intervention: Option<InterventionPolicy>,
+
+    /// With Auto Drive, whether coordinator agents may write files. `ask`
+    /// prompts before each decision that launches write-enabled agents;
+    /// rejected agents run read-only.
+    #[arg(
+        long = "approve-writes",
+        value_enum,
+        value_name = "POLICY",
+        default_value_t = WriteApprovalPolicy::Always
+    )]
+    pub approve_writes: WriteApprovalPolicy,
+
+    /// With Auto Drive, queue the run for WHEN instead of starting it:
+    /// `HH:MM` (next time the local clock shows it), `daily HH:MM`, or an
+    /// RFC 3339 timestamp. `code exec scheduler run` starts queued runs.
+    /// `now` runs immediately. Overrides `auto_drive.schedule`.
+    #[arg(long = "schedule", value_name = "WHEN")]
+    pub schedule: Option<String>,
+
+    /// With Auto Drive, save a checkpoint after every N CLI turns so an
+    /// interrupted run can continue with `resume --from-checkpoint`. `0`
+    /// disables checkpoints.
+    #[arg(long = "checkpoint-every", value_name = "N", default_value_t = 1)]
+    pub checkpoint_every: usize,
+
+    /// Directory for Auto Drive checkpoints. Defaults to
+    /// `$CODE_HOME/auto_drive/checkpoints`.
+    #[arg(long = "checkpoint-dir", value_name = "DIR")]
+    pub checkpoint_dir: Option<PathBuf>,
+
+    /// Continue the interrupted `--auto` run SESSION_ID from its last
+    /// checkpoint, with the Auto Drive history and goal restored. Same as
+    /// `resume --from-checkpoint SESSION_ID`.
+    #[arg(
+        long = "auto-resume",
+        value_name = "SESSION_ID",
+        conflicts_with_all = ["prompt", "template", "auto_backlog"]
+    )]
+    pub auto_resume: Option<String>,
+
+    /// With Auto Drive, stop once the coordinator and the CLI agent have used
+    /// this many tokens together. Overrides `auto_drive.token_budget`.
+    #[arg(long = "max-tokens", value_name = "N")]
+    pub max_tokens: Option<u64>,
+
+    /// With Auto Drive, stop once the estimated spend reaches this many US
+    /// dollars. Requires a `model_prices` entry for the model. Overrides
+    /// `auto_drive.cost_budget_usd`.
+    #[arg(long = "max-cost", value_name = "USD")]
+    pub max_cost: Option<f64>,
+
+    /// With Auto Drive, stop after the turn that is running when this many
+    /// seconds have passed. Overrides `auto_drive.duration_limit_seconds`.
+    #[arg(long = "max-duration", value_name = "SECONDS")]
+    pub max_duration: Option<u64>,
+
+    /// With Auto Drive, compact the coordinator history once it is projected
+    /// to fill this share of the model's context window, from 0.1 to 1.0.
+    /// Overrides `auto_drive.compact_threshold` (default 0.8).
+    #[arg(long = "compact-threshold", value_name = "RATIO")]
+    pub compact_threshold: Option<f64>,
+
+    /// With Auto Drive, print a conventional-commit message and a pull
+    /// request body summarizing the goal, decisions, changed files, and tests
+    /// once the run ends.
+    #[arg(long = "generate-commit-message", default_value_t = false)]
+    pub generate_commit_message: bool,
+
+    /// With Auto Drive, do not append the test-first instructions (or
+    /// `auto_drive.goal_suffix`) to the goal.
+    #[arg(
+        long = "no-test-suffix",
+        default_value_t = false,
+        conflicts_with = "goal_suffix_file"
+    )]
+    pub no_test_suffix: bool,
+
+    /// With Auto Drive, append the contents of FILE to the goal instead of
+    /// the test-first instructions. Overrides `auto_drive.goal_suffix`.
+    #[arg(long = "goal-suffix-file", value_name = "FILE")]
+    pub goal_suffix_file: Option<PathBuf>,
+
+    /// Optional image(s) to attach to the initial prompt. Use `-` to read
+    /// image bytes from stdin.
     #[arg(
         long = "image",
         short = 'i',
@@ -23,6 +163,12 @@ pub struct Cli {
     )]
     pub images: Vec<PathBuf>,
 
+    /// File to attach to the initial prompt; repeat for more. Text is
+    /// inlined, images are sent as images, other binaries are base64-encoded.
+    /// Use `-` for stdin.
+    #[arg(long = "file", value_name = "FILE")]
+    pub files: Vec<PathBuf>,
+
     /// Model the agent should use.
     #[arg(long, short = 'm')]
     pub model: Option<String>,
@@ -57,6 +203,17 @@ pub struct Cli {
     #[clap(long = "cd", short = 'C', value_name = "DIR")]
     pub cwd: Option<PathBuf>,
 
+    /// Run in a temporary git worktree (or a copy, outside git) of the working
+    /// directory. On success the changes are applied back to the original
+    /// checkout; on failure they are saved as a patch. The copy is deleted
+    /// afterwards.
+    #[arg(
+        long = "ephemeral-worktree",
+        default_value_t = false,
+        conflicts_with = "handoff_to_tui"
+    )]
+    pub ephemeral_worktree: bool,
+
     /// Enable debug logging of all LLM requests and responses to files.
     #[clap(long = "debug", short = 'd', default_value_t = false)]
     pub debug: bool,
@@ -65,10 +222,36 @@ pub struct Cli {
     #[arg(long = "skip-git-repo-check", default_value_t = false)]
     pub skip_git_repo_check: bool,
 
+    /// Interrupt and shut down the run after this many seconds of wall-clock
+    /// time.
+    #[arg(long = "timeout", value_name = "SECONDS")]
+    pub timeout: Option<i64>,
+
+    /// Interrupt and shut down the run once it tries to start more than this
+    /// many turns.
+    #[arg(long = "max-turns", value_name = "N")]
+    pub max_turns: Option<i32>,
+
     /// Path to a JSON Schema file describing the model's final response shape.
     #[arg(long = "output-schema", value_name = "FILE")]
     pub output_schema: Option<PathBuf>,
 
+    /// How many follow-up turns to request when the final message does not
+    /// satisfy `--output-schema` before exiting with an error.
+    #[arg(
+        long = "output-schema-retries",
+        value_name = "N",
+        default_value_t = 1,
+        requires = "output_schema"
+    )]
+    pub output_schema_retries: i32,
+
+    /// Cache Responses API streams under `~/.code/cache/responses`, keyed by
+    /// request. `read` replays identical requests from the cache and records
+    /// the rest; `write` only records. Overrides `response_cache`.
+    #[arg(long = "cache", value_name = "MODE")]
+    pub cache: Option<CacheMode>,
+
     #[clap(skip)]
     pub config_overrides: CliConfigOverrides,
 
@@ -76,10 +259,67 @@ pub struct Cli {
     #[arg(long = "color", value_enum, default_value_t = Color::Auto)]
     pub color: Color,
 
-    /// Print events to stdout as JSONL.
+    /// Prefix human output lines with wall-clock time (default) or time
+    /// elapsed since the run started. `elapsed` also prints each turn's
+    /// duration when the turn completes.
+    #[arg(long = "timestamps", value_enum, default_value_t = Timestamps::Wall)]
+    pub timestamps: Timestamps,
+
+    /// Condensed human output: a single live status line while the run is in
+    /// progress, then the final agent message and a summary table.
+    #[arg(
+        long = "progress",
+        visible_alias = "quiet",
+        default_value_t = false,
+        conflicts_with = "json"
+    )]
+    pub progress: bool,
+
+    /// Print the fully rendered first model request (instructions, input,
+    /// tools, text format) to stdout as `pretty` (default) or `raw` JSON.
+    #[arg(
+        long = "print-prompt",
+        value_name = "FORMAT",
+        value_enum,
+        num_args = 0..=1,
+        default_missing_value = "pretty"
+    )]
+    pub print_prompt: Option<PrintPromptFormat>,
+
+    /// Print the fully resolved first request (endpoint, redacted headers and
+    /// body) and exit instead of sending it to the model. Implies
+    /// `--print-prompt`.
+    #[arg(
+        long = "dry-run",
+        default_value_t = false,
+        conflicts_with_all = ["auto_drive", "auto_backlog", "provider_batch"]
+    )]
+    pub dry_run: bool,
+
+    /// Print events to stdout as JSONL. Shorthand for `--output-format json`.
     #[arg(long = "json", default_value_t = false)]
     pub json: bool,
 
+    /// Format used for stdout: human-readable text, JSONL events, or a JUnit
+    /// XML report written once the session ends.
+    #[arg(long = "output-format", value_enum, default_value_t = OutputFormat::Human)]
+    pub output_format: OutputFormat,
+
+    /// Also mirror every event as NDJSON to `unix:///path.sock` or
+    /// `tcp://host:port`.
+    #[arg(long = "event-sink", value_name = "URL")]
+    pub event_sink: Option<EventSinkAddr>,
+
+    /// Also write a Markdown transcript of the run (prompt, messages, tool
+    /// calls with folded output, token usage) to this file.
+    #[arg(long = "transcript-file", value_name = "FILE")]
+    pub transcript_file: Option<PathBuf>,
+
+    /// Also write everything printed to stdout to this file, byte for byte
+    /// (including ANSI styling when `--color always` is set).
+    #[arg(long = "tee", value_name = "FILE")]
+    pub tee: Option<PathBuf>,
+
     /// Whether to include the plan tool in the conversation.
     #[arg(long = "include-plan-tool", default_value_t = false)]
     pub include_plan_tool: bool,
@@ -88,6 +328,46 @@ pub struct Cli {
     #[arg(long = "output-last-message")]
     pub last_message_file: Option<PathBuf>,
 
+    /// When the run fails or ends with a question, write a resume marker and
+    /// print (default) or launch the command that continues this session in
+    /// the TUI.
+    #[arg(
+        long = "handoff-to-tui",
+        value_name = "MODE",
+        value_enum,
+        num_args = 0..=1,
+        default_missing_value = "print"
+    )]
+    pub handoff_to_tui: Option<HandoffMode>,
+
+    /// Keep the session open and re-run the prompt whenever files matching
+    /// GLOB change (gitignored paths are skipped). Repeat the flag to watch
+    /// several globs. Runs until Ctrl-C.
+    #[arg(long = "watch", value_name = "GLOB", conflicts_with = "auto_drive")]
+    pub watch: Vec<String>,
+
+    /// Send the prompts in this manifest (the `code exec batch` format)
+    /// through the provider's Batch API at half price, wait for the batch to
+    /// finish, and print each response. Batched prompts get one model reply
+    /// each, without tool calls. Requires a Responses API provider and an API
+    /// key.
+    #[arg(
+        long = "batch",
+        value_name = "MANIFEST",
+        conflicts_with_all = ["prompt", "template", "auto_drive", "auto_backlog"]
+    )]
+    pub provider_batch: Option<PathBuf>,
+
+    /// Render the prompt from this Jinja template file instead of PROMPT.
+    /// `{{ name }}` takes the value of `--var name=...`; `{{ env.NAME }}`
+    /// reads an environment variable. Only for a new session or `resume`.
+    #[arg(long = "template", value_name = "FILE", conflicts_with = "prompt")]
+    pub template: Option<PathBuf>,
+
+    /// Template variable as `key=value`; repeat for more variables.
+    #[arg(long = "var", value_name = "KEY=VALUE", requires = "template")]
+    pub vars: Vec<TemplateVar>,
+
     /// Initial instructions for the agent. If not provided as an argument (or
     /// if `-` is used), instructions are read from stdin.
     #[arg(value_name = "PROMPT")]
@@ -98,6 +378,181 @@ pub struct Cli {
 pub enum Command {
     /// Resume a previous session by id or pick the most recent with --last.
     Resume(ResumeArgs),
+
+    /// Run every prompt listed in a JSON, TOML or YAML manifest file.
+    Batch(BatchArgs),
+
+    /// Auto Drive utilities.
+    Auto(AutoArgs),
+
+    /// Auto Drive audit log utilities.
+    AutoAudit(AutoAuditArgs),
+
+    /// Start, list, or cancel Auto Drive runs queued with `--schedule`.
+    Scheduler(SchedulerArgs),
+
+    /// Re-render a recorded session through the selected output format
+    /// without contacting the model.
+    Replay(RolloutReplayArgs),
+
+    /// Draft a reply (and optional patch) to a pull request review thread
+    /// and post it back to GitHub.
+    ReviewReply(ReviewReplyArgs),
+
+    /// Print or export the anonymized usage statistics recorded when
+    /// `[usage_stats] enabled = true`.
+    Stats(StatsArgs),
+
+    /// Print a shell completion script for `code-exec`.
+    Completions(CompletionsArgs),
+
+    /// Print the `code-exec(1)` man page, or write pages for every
+    /// subcommand into a directory.
+    Man(ManArgs),
+}
+
+#[derive(Parser, Debug)]
+pub struct StatsArgs {
+    /// Print the aggregate as JSON.
+    #[arg(long = "json", default_value_t = false)]
+    pub json: bool,
+
+    /// Write the aggregate as JSON to FILE instead of printing it.
+    #[arg(long = "output", value_name = "FILE", conflicts_with = "json")]
+    pub output: Option<PathBuf>,
+
+    /// Delete the recorded runs.
+    #[arg(long = "reset", default_value_t = false, conflicts_with_all = ["json", "output"])]
+    pub reset: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct CompletionsArgs {
+    /// Shell to generate completions for.
+    #[arg(value_enum)]
+    pub shell: clap_complete::Shell,
+}
+
+#[derive(Parser, Debug)]
+pub struct ManArgs {
+    /// Write `code-exec.1` and one page per subcommand into this directory
+    /// instead of printing the main page to stdout.
+    #[arg(long = "output-dir", value_name = "DIR")]
+    pub output_dir: Option<PathBuf>,
+}
+
+#[derive(Parser, Debug)]
+pub struct ReviewReplyArgs {
+    /// Pull request number.
+    #[arg(long = "pr", value_name = "NUMBER")]
+    pub pr: u64,
+
+    /// Id of a review comment in the thread; the thread's first comment is
+    /// found automatically.
+    #[arg(long = "thread", value_name = "COMMENT_ID")]
+    pub thread: u64,
+
+    /// Repository as OWNER/NAME. Defaults to the `origin` remote of the
+    /// working directory.
+    #[arg(long = "repo", value_name = "OWNER/NAME")]
+    pub repo: Option<String>,
+
+    /// Post the reply to the thread. Without this flag the reply is only
+    /// printed.
+    #[arg(long = "post", default_value_t = false)]
+    pub post: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct RolloutReplayArgs {
+    /// Rollout JSONL path, or a session id (prefix) from the session catalog.
+    #[arg(value_name = "ROLLOUT")]
+    pub rollout: String,
+
+    /// Playback speed relative to the recording; `0` replays without pauses.
+    #[arg(long = "speed", default_value_t = 1.0, value_parser = crate::replay::parse_speed)]
+    pub speed: f64,
+}
+
+#[derive(Parser, Debug)]
+pub struct AutoArgs {
+    #[command(subcommand)]
+    pub command: AutoCommand,
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum AutoCommand {
+    /// Re-emit a recorded Auto Drive run through the event processors
+    /// without contacting the model.
+    Replay(ReplayArgs),
+}
+
+#[derive(Parser, Debug)]
+pub struct ReplayArgs {
+    /// Session id of the recorded `code exec --auto` run.
+    #[arg(value_name = "SESSION_ID")]
+    pub session_id: String,
+
+    /// Playback speed relative to the recording; `0` replays without pauses.
+    #[arg(long = "speed", default_value_t = 1.0, value_parser = crate::replay::parse_speed)]
+    pub speed: f64,
+}
+
+#[derive(Parser, Debug)]
+pub struct AutoAuditArgs {
+    #[command(subcommand)]
+    pub command: AutoAuditCommand,
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum AutoAuditCommand {
+    /// Check the hash chain of an audit log and exit non-zero if an entry
+    /// was edited, reordered, or removed.
+    Verify(AuditVerifyArgs),
+}
+
+#[derive(Parser, Debug)]
+pub struct AuditVerifyArgs {
+    /// Audit log, e.g. `$CODE_HOME/auto_drive/audit/<session>.jsonl`.
+    #[arg(value_name = "FILE")]
+    pub file: PathBuf,
+}
+
+#[derive(Parser, Debug)]
+pub struct SchedulerArgs {
+    #[command(subcommand)]
+    pub command: SchedulerCommand,
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum SchedulerCommand {
+    /// Start queued runs as they come due, one at a time.
+    Run(SchedulerRunArgs),
+
+    /// List queued runs, soonest first.
+    List,
+
+    /// Remove a queued run.
+    Cancel(SchedulerCancelArgs),
+}
+
+#[derive(Parser, Debug)]
+pub struct SchedulerRunArgs {
+    /// Start the runs that are already due, then exit instead of waiting
+    /// for the next one. Suits cron or systemd timers.
+    #[arg(long = "once", default_value_t = false)]
+    pub once: bool,
+
+    /// Seconds between checks for due runs.
+    #[arg(long = "poll-interval", value_name = "SECONDS", default_value_t = 30)]
+    pub poll_interval: u64,
+}
+
+#[derive(Parser, Debug)]
+pub struct SchedulerCancelArgs {
+    /// Id printed when the run was queued, or shown by `scheduler list`.
+    #[arg(value_name = "ID")]
+    pub id: String,
 }
 
 #[derive(Parser, Debug)]
@@ -111,11 +566,39 @@ pub struct ResumeArgs {
     #[arg(long = "last", default_value_t = false, conflicts_with = "session_id")]
     pub last: bool,
 
+    /// Copy the session into a new session id and continue there, leaving the
+    /// original rollout untouched.
+    #[arg(long = "fork", default_value_t = false)]
+    pub fork: bool,
+
+    /// Continue an interrupted `--auto` run from its last checkpoint, with
+    /// the Auto Drive history and goal restored. Without SESSION_ID the most
+    /// recent unfinished run is used.
+    #[arg(
+        long = "from-checkpoint",
+        default_value_t = false,
+        conflicts_with_all = ["fork", "prompt"]
+    )]
+    pub from_checkpoint: bool,
+
     /// Prompt to send after resuming the session. If `-` is used, read from stdin.
     #[arg(value_name = "PROMPT")]
     pub prompt: Option<String>,
 }
 
+#[derive(Parser, Debug)]
+pub struct BatchArgs {
+    /// Manifest describing the prompts to run (`.json`, `.toml`, `.yaml` or
+    /// `.yml`).
+    #[arg(value_name = "MANIFEST")]
+    pub manifest: PathBuf,
+
+    /// Maximum number of prompts to run concurrently. Defaults to the
+    /// manifest's `parallel` value, or 1 (sequential).
+    #[arg(long = "parallel", value_name = "N")]
+    pub parallel: Option<usize>,
+}
+
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
 #[value(rename_all = "kebab-case")]
 pub enum Color {
@@ -124,3 +607,74 @@ pub enum Color {
     #[default]
     Auto,
 }
+
+/// `--cache` modes; see `response_cache` in the config docs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[value(rename_all = "kebab-case")]
+pub enum CacheMode {
+    Off,
+    Read,
+    Write,
+}
+
+/// What `--handoff-to-tui` does once the run ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[value(rename_all = "kebab-case")]
+pub enum HandoffMode {
+    /// Print the resume command.
+    Print,
+    /// Launch the TUI resuming this session when attached to a terminal.
+    Launch,
+}
+
+/// How exec answers an Auto Drive intervention request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[value(rename_all = "kebab-case")]
+pub enum InterventionPolicy {
+    /// Read a reply from stdin: `y` continues, `n` or an empty line stops,
+    /// anything else is sent to the coordinator as guidance.
+    Pause,
+    /// Stop the run and exit with a failure.
+    Fail,
+    /// Continue as if the operator had approved.
+    Continue,
+}
+
+/// Whether exec lets Auto Drive agents write files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[value(rename_all = "kebab-case")]
+pub enum WriteApprovalPolicy {
+    /// Run write-enabled agents as the coordinator requested.
+    Always,
+    /// Run every agent read-only.
+    Never,
+    /// Read `y` from stdin to approve; anything else runs them read-only.
+    Ask,
+}
+
+/// Prefix for event lines in human output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+#[value(rename_all = "kebab-case")]
+pub enum Timestamps {
+    /// Wall-clock UTC time.
+    #[default]
+    Wall,
+    /// Time since the run started, plus each turn's duration when it ends.
+    Elapsed,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+#[value(rename_all = "kebab-case")]
+pub enum OutputFormat {
+    #[default]
+    Human,
+    Json,
+    Junit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[value(rename_all = "kebab-case")]
+pub enum PrintPromptFormat {
+    Pretty,
+    Raw,
+}
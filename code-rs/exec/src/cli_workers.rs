@@ -0,0 +1,232 @@
+//! Worker pool for Auto Drive workstreams.
+//!
+//! With `parallel_instances` above 1 the coordinator may split a turn into
+//! independent workstreams. Each one runs in a fresh CLI conversation, at most
+//! `parallel_instances` at a time, and the results feed the merge turn of the
+//! main conversation. Worker events are not forwarded to the output mode;
+//! exec prints one line when a workstream starts and one when it ends.
+
+use std::sync::Arc;
+
+use code_auto_drive_core::AutoTurnWorkstream;
+use code_core::ConversationManager;
+use code_core::config::Config;
+use code_core::protocol::EventMsg;
+use code_core::protocol::InputItem;
+use code_core::protocol::Op;
+use code_core::protocol::TaskCompleteEvent;
+use code_core::protocol::TokenUsage;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// Longest worker reply quoted back into the merge prompt.
+const MAX_RESULT_CHARS: usize = 4_000;
+
+/// What one workstream produced.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct WorkstreamResult {
+    pub id: String,
+    pub prompt: String,
+    /// The worker's final message, or why the workstream failed.
+    pub outcome: Result<String, String>,
+    pub usage: TokenUsage,
+}
+
+/// Runs workstreams in separate conversations, `parallel_instances` at a time.
+pub(crate) struct CliWorkerPool {
+    manager: Arc<ConversationManager>,
+    config: Config,
+    permits: Arc<Semaphore>,
+}
+
+impl CliWorkerPool {
+    /// `None` when `parallel_instances` leaves no room for a second session.
+    pub fn new(manager: Arc<ConversationManager>, config: &Config) -> Option<Self> {
+        let size = config.auto_drive.parallel_instances.clamp(1, 5);
+        (size > 1).then(|| Self {
+            manager,
+            config: config.clone(),
+            permits: Arc::new(Semaphore::new(usize::from(size))),
+        })
+    }
+
+    /// Runs every workstream to completion and returns the results in the
+    /// order the coordinator listed them.
+    pub async fn run(&self, workstreams: &[AutoTurnWorkstream]) -> Vec<WorkstreamResult> {
+        let mut tasks = JoinSet::new();
+        for (index, workstream) in workstreams.iter().cloned().enumerate() {
+            let manager = self.manager.clone();
+            let config = self.config.clone();
+            let permits = self.permits.clone();
+            tasks.spawn(async move {
+                let _permit = permits.acquire_owned().await;
+                out_println!(
+                    "[auto] workstream {} started: {}",
+                    workstream.id,
+                    workstream.prompt
+                );
+                let result = run_workstream(&manager, config, workstream).await;
+                match &result.outcome {
+                    Ok(_) => out_println!("[auto] workstream {} finished", result.id),
+                    Err(reason) => out_println!("[auto] workstream {} failed: {reason}", result.id),
+                }
+                (index, result)
+            });
+        }
+
+        let mut results: Vec<Option<WorkstreamResult>> = vec![None; workstreams.len()];
+        while let Some(joined) = tasks.join_next().await {
+            match joined {
+                Ok((index, result)) => results[index] = Some(result),
+                Err(err) => tracing::warn!("workstream task failed: {err}"),
+            }
+        }
+        results
+            .into_iter()
+            .zip(workstreams)
+            .map(|(result, workstream)| {
+                result.unwrap_or_else(|| WorkstreamResult {
+                    id: workstream.id.clone(),
+                    prompt: workstream.prompt.clone(),
+                    outcome: Err("worker task aborted".to_string()),
+                    usage: TokenUsage::default(),
+                })
+            })
+            .collect()
+    }
+}
+
+async fn run_workstream(
+    manager: &ConversationManager,
+    config: Config,
+    workstream: AutoTurnWorkstream,
+) -> WorkstreamResult {
+    let AutoTurnWorkstream {
+        id,
+        prompt,
+        context,
+    } = workstream;
+    let mut usage = TokenUsage::default();
+    let outcome = match manager.new_conversation(config).await {
+        Ok(new) => {
+            let conversation = new.conversation;
+            let text = match context.as_deref().map(str::trim) {
+                Some(context) if !context.is_empty() => format!("{context}\n\n{prompt}"),
+                _ => prompt.clone(),
+            };
+            let mut outcome = Err("worker session ended before the turn completed".to_string());
+            match conversation
+                .submit(Op::UserInput {
+                    items: vec![InputItem::Text { text }],
+                })
+                .await
+            {
+                Ok(submit_id) => {
+                    while let Ok(event) = conversation.next_event().await {
+                        match event.msg {
+                            EventMsg::TokenCount(count) => {
+                                if let Some(info) = count.info {
+                                    usage = info.total_token_usage;
+                                }
+                            }
+                            EventMsg::Error(error) => {
+                                outcome = Err(error.message);
+                            }
+                            EventMsg::TaskComplete(TaskCompleteEvent { last_agent_message })
+                                if event.id == submit_id =>
+                            {
+                                if let Some(message) = last_agent_message {
+                                    outcome = Ok(message);
+                                }
+                                break;
+                            }
+                            EventMsg::ShutdownComplete => break,
+                            _ => {}
+                        }
+                    }
+                }
+                Err(err) => outcome = Err(err.to_string()),
+            }
+            let _ = conversation.submit(Op::Shutdown).await;
+            while let Ok(event) = conversation.next_event().await {
+                if matches!(event.msg, EventMsg::ShutdownComplete) {
+                    break;
+                }
+            }
+            manager.remove_conversation(&new.conversation_id).await;
+            outcome
+        }
+        Err(err) => Err(format!("failed to start worker session: {err}")),
+    };
+    WorkstreamResult {
+        id,
+        prompt,
+        outcome,
+        usage,
+    }
+}
+
+/// Section appended to the merge prompt so the main session sees what each
+/// workstream did.
+pub(crate) fn workstream_results_section(results: &[WorkstreamResult]) -> String {
+    let mut lines = vec![
+        "<workstreams>".to_string(),
+        "These workstreams ran in parallel in separate sessions. Merge their work and verify it."
+            .to_string(),
+    ];
+    for result in results {
+        lines.push(String::new());
+        lines.push(format!("{}: {}", result.id, result.prompt));
+        match &result.outcome {
+            Ok(message) => {
+                let message = message.trim();
+                let shown: String = message.chars().take(MAX_RESULT_CHARS).collect();
+                lines.push(format!("status: finished\nresult: {shown}"));
+                if shown.len() < message.len() {
+                    lines.push("(result truncated)".to_string());
+                }
+            }
+            Err(reason) => lines.push(format!("status: failed ({reason})")),
+        }
+    }
+    lines.push("</workstreams>".to_string());
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn results_section_lists_each_workstream_in_order() {
+        let results = vec![
+            WorkstreamResult {
+                id: "ws-1".to_string(),
+                prompt: "Port the parser".to_string(),
+                outcome: Ok("Parser ported; tests pass.\n".to_string()),
+                usage: TokenUsage::default(),
+            },
+            WorkstreamResult {
+                id: "ws-2".to_string(),
+                prompt: "Port the lexer".to_string(),
+                outcome: Err("stream disconnected".to_string()),
+                usage: TokenUsage::default(),
+            },
+        ];
+
+        assert_eq!(
+            workstream_results_section(&results),
+            "<workstreams>\n\
+             These workstreams ran in parallel in separate sessions. Merge their work and verify it.\n\
+             \n\
+             ws-1: Port the parser\n\
+             status: finished\n\
+             result: Parser ported; tests pass.\n\
+             \n\
+             ws-2: Port the lexer\n\
+             status: failed (stream disconnected)\n\
+             </workstreams>"
+        );
+    }
+}
@@ -0,0 +1,73 @@
+//! Shell completions (`code exec completions`) and man pages (`code exec man`)
+//! generated from the clap definition, so packaged scripts never drift from
+//! the actual flags.
+
+use std::path::PathBuf;
+
+use anyhow::Context;
+use clap::Args;
+use clap::CommandFactory;
+use clap_complete::Shell;
+use code_common::CliConfigOverrides;
+
+use crate::cli::Cli;
+
+/// Name of the standalone exec binary the scripts and pages describe.
+const EXEC_BIN_NAME: &str = "code-exec";
+
+/// The exec CLI as the `code-exec` binary parses it, including the `-c`
+/// overrides that the binary flattens in at the top level.
+fn exec_command() -> clap::Command {
+    CliConfigOverrides::augment_args(Cli::command())
+        .name(EXEC_BIN_NAME)
+        .bin_name(EXEC_BIN_NAME)
+}
+
+fn write_completions<W: std::io::Write>(shell: Shell, out: &mut W) {
+    clap_complete::generate(shell, &mut exec_command(), EXEC_BIN_NAME, out);
+}
+
+pub(crate) fn print_completions(shell: Shell) {
+    write_completions(shell, &mut std::io::stdout());
+}
+
+pub(crate) fn write_man(output_dir: Option<PathBuf>) -> anyhow::Result<()> {
+    let command = exec_command();
+    match output_dir {
+        Some(dir) => {
+            std::fs::create_dir_all(&dir)
+                .with_context(|| format!("failed to create {}", dir.display()))?;
+            clap_mangen::generate_to(command, &dir)
+                .with_context(|| format!("failed to write man pages to {}", dir.display()))?;
+            eprintln!("man pages written to {}", dir.display());
+        }
+        None => clap_mangen::Man::new(command)
+            .render(&mut std::io::stdout())
+            .context("failed to write man page")?,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn completions_and_man_page_cover_exec_flags() {
+        let mut buf = Vec::new();
+        write_completions(Shell::Bash, &mut buf);
+        let script = String::from_utf8(buf).unwrap();
+        assert!(script.contains("_code-exec()"), "{script}");
+        assert!(script.contains("--output-schema"));
+        assert!(script.contains("--config"));
+
+        let mut page = Vec::new();
+        clap_mangen::Man::new(exec_command())
+            .render(&mut page)
+            .unwrap();
+        let page = String::from_utf8(page).unwrap();
+        assert!(page.contains(".TH"), "{page}");
+        assert!(page.contains("exec"));
+        assert!(page.contains("completions"));
+    }
+}
@@ -0,0 +1,501 @@
+//! Token usage and estimated cost reported when an exec run ends.
+//!
+//! Core reports the session's cumulative usage in every `TokenCount` event;
+//! [`UsageTrackingProcessor`] bills what each event adds to the model the
+//! turn ran on, so the CLI can price every model with `model_prices` from
+//! `config.toml` once the run is over. Auto Drive workstream sessions and the
+//! coordinator add their usage under their own models.
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use code_core::config::Config;
+use code_core::config_types::ModelPrice;
+use code_core::protocol::Event;
+use code_core::protocol::EventMsg;
+use code_core::protocol::TokenUsage;
+use code_protocol::num_format::format_with_separators;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::attachments::ImageStatus;
+use crate::event_processor::CodexStatus;
+use crate::event_processor::EventProcessor;
+use crate::exit_code::FailureClass;
+use crate::interrupt::Interruption;
+use crate::run_environment::RunEnvironment;
+use crate::run_guard::RunLimit;
+
+const TOKENS_PER_PRICE_UNIT: f64 = 1_000_000.0;
+
+/// Which part of the run spent a model's tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum UsageRole {
+    /// The exec session and Auto Drive workstream sessions.
+    Cli,
+    /// The Auto Drive coordinator.
+    Coordinator,
+}
+
+/// Usage of one model in one role, priced on its own.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct ModelCost {
+    pub model: String,
+    pub role: UsageRole,
+    pub input_tokens: u64,
+    pub cached_input_tokens: u64,
+    pub output_tokens: u64,
+    pub reasoning_output_tokens: u64,
+    pub total_tokens: u64,
+    /// `None` when `model_prices` has no entry for the model.
+    pub estimated_usd: Option<f64>,
+}
+
+impl ModelCost {
+    fn new(role: UsageRole, model: &str, usage: &TokenUsage, price: Option<&ModelPrice>) -> Self {
+        Self {
+            model: model.to_string(),
+            role,
+            input_tokens: usage.input_tokens,
+            cached_input_tokens: usage.cached_input_tokens,
+            output_tokens: usage.output_tokens,
+            reasoning_output_tokens: usage.reasoning_output_tokens,
+            total_tokens: usage.total_tokens,
+            estimated_usd: price.map(|price| estimate_usd(usage, price)),
+        }
+    }
+
+    fn label(&self) -> String {
+        match self.role {
+            UsageRole::Cli => self.model.clone(),
+            UsageRole::Coordinator => format!("{} (coordinator)", self.model),
+        }
+    }
+}
+
+/// Final usage of a run, emitted as the `cost_report` JSON event.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct CostReport {
+    pub input_tokens: u64,
+    pub cached_input_tokens: u64,
+    pub output_tokens: u64,
+    pub reasoning_output_tokens: u64,
+    pub total_tokens: u64,
+    /// Share of input served from the provider prompt cache; `None` when no
+    /// input was sent.
+    pub cache_hit_rate: Option<f64>,
+    /// Sum over `models`; `None` when any of them has no price.
+    pub estimated_usd: Option<f64>,
+    /// Usage per model, CLI models before coordinator models.
+    pub models: Vec<ModelCost>,
+}
+
+impl CostReport {
+    fn new(usage: &Buckets, prices: &HashMap<String, ModelPrice>) -> Self {
+        let mut total = TokenUsage::default();
+        let mut models = Vec::with_capacity(usage.len());
+        for ((role, model), usage) in usage {
+            total.add_assign(usage);
+            models.push(ModelCost::new(*role, model, usage, prices.get(model)));
+        }
+        Self {
+            input_tokens: total.input_tokens,
+            cached_input_tokens: total.cached_input_tokens,
+            output_tokens: total.output_tokens,
+            reasoning_output_tokens: total.reasoning_output_tokens,
+            total_tokens: total.total_tokens,
+            cache_hit_rate: total.cache_hit_rate(),
+            estimated_usd: models.iter().map(|model| model.estimated_usd).sum(),
+            models,
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut value = serde_json::json!({ "type": "cost_report" });
+        if let (Value::Object(map), Ok(Value::Object(fields))) =
+            (&mut value, serde_json::to_value(self))
+        {
+            map.extend(fields);
+        }
+        value
+    }
+
+    /// One-line summary for human-oriented output modes.
+    pub fn summary(&self) -> String {
+        let cache_hit = self
+            .cache_hit_rate
+            .map(|rate| format!(" ({:.0}% hit)", rate * 100.0))
+            .unwrap_or_default();
+        let models = match self.models.as_slice() {
+            [single] => single.label(),
+            models => models
+                .iter()
+                .map(|model| {
+                    format!(
+                        "{} ({} tokens {})",
+                        model.label(),
+                        format_with_separators(model.total_tokens),
+                        format_cost(model.estimated_usd)
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(", "),
+        };
+        format!(
+            "{} tokens (input {}, cached {}{cache_hit}, output {}, reasoning {}) {} for {models}",
+            format_with_separators(self.total_tokens),
+            format_with_separators(self.input_tokens),
+            format_with_separators(self.cached_input_tokens),
+            format_with_separators(self.output_tokens),
+            format_with_separators(self.reasoning_output_tokens),
+            format_cost(self.estimated_usd)
+        )
+    }
+}
+
+fn format_cost(usd: Option<f64>) -> String {
+    match usd {
+        Some(usd) => format!("≈ ${usd:.4}"),
+        None => "no price configured".to_string(),
+    }
+}
+
+/// Cached input is billed at its own rate; reasoning tokens are already part
+/// of `output_tokens`.
+fn estimate_usd(usage: &TokenUsage, price: &ModelPrice) -> f64 {
+    let cached = usage.cached_input_tokens.min(usage.input_tokens);
+    let uncached = usage.input_tokens - cached;
+    (uncached as f64 * price.input
+        + cached as f64 * price.cached_input.unwrap_or(price.input)
+        + usage.output_tokens as f64 * price.output)
+        / TOKENS_PER_PRICE_UNIT
+}
+
+/// Tokens used per role and model.
+type Buckets = BTreeMap<(UsageRole, String), TokenUsage>;
+
+/// Usage of the whole run per model: the main session, Auto Drive
+/// workstream sessions and the coordinator. Shared between the processors
+/// that record it and the CLI that reports it.
+#[derive(Clone)]
+pub(crate) struct RunUsage(Arc<Mutex<RunTotals>>);
+
+#[derive(Debug)]
+struct RunTotals {
+    /// Latest cumulative usage of the main session.
+    session: TokenUsage,
+    /// The session's configured model; workstreams run it too.
+    session_model: String,
+    /// Model the current turn was routed to, if not `session_model`.
+    turn_model: Option<String>,
+    buckets: Buckets,
+}
+
+impl RunTotals {
+    fn bucket(&mut self, role: UsageRole, model: &str) -> &mut TokenUsage {
+        self.buckets.entry((role, model.to_string())).or_default()
+    }
+}
+
+/// Run totals at one point in time; [`RunUsage::report_since`] measures
+/// what the run used after it.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct UsageBaseline(Buckets);
+
+impl RunUsage {
+    /// Bills the session to `model` until a turn is routed elsewhere.
+    pub fn new(model: &str) -> Self {
+        Self(Arc::new(Mutex::new(RunTotals {
+            session: TokenUsage::default(),
+            session_model: model.to_string(),
+            turn_model: None,
+            buckets: Buckets::new(),
+        })))
+    }
+
+    pub fn track(&self, inner: Box<dyn EventProcessor>) -> UsageTrackingProcessor {
+        UsageTrackingProcessor {
+            inner,
+            usage: self.clone(),
+        }
+    }
+
+    /// Records the latest cumulative usage of the main session and bills
+    /// what it adds to the model of the current turn.
+    pub fn set(&self, usage: &TokenUsage) {
+        if let Ok(mut totals) = self.0.lock() {
+            let added = usage_since(usage, &totals.session);
+            totals.session = usage.clone();
+            let model = totals
+                .turn_model
+                .clone()
+                .unwrap_or_else(|| totals.session_model.clone());
+            totals.bucket(UsageRole::Cli, &model).add_assign(&added);
+        }
+    }
+
+    /// Bills the main session to `model` until called again with `None`,
+    /// for turns routed to another model.
+    pub fn route_turn(&self, model: Option<String>) {
+        if let Ok(mut totals) = self.0.lock() {
+            totals.turn_model = model;
+        }
+    }
+
+    /// Latest cumulative usage of the main session.
+    pub fn session(&self) -> TokenUsage {
+        self.0
+            .lock()
+            .map(|totals| totals.session.clone())
+            .unwrap_or_default()
+    }
+
+    /// Main session usage since `before`, i.e. what the turns in between
+    /// used.
+    pub fn session_since(&self, before: &TokenUsage) -> TokenUsage {
+        usage_since(&self.session(), before)
+    }
+
+    /// Current totals, to measure a later stretch of the run against.
+    pub fn baseline(&self) -> UsageBaseline {
+        UsageBaseline(
+            self.0
+                .lock()
+                .map(|totals| totals.buckets.clone())
+                .unwrap_or_default(),
+        )
+    }
+
+    /// Adds the final usage of a workstream session, which runs the same
+    /// model as the main session.
+    pub fn add_worker(&self, usage: &TokenUsage) {
+        if let Ok(mut totals) = self.0.lock() {
+            let model = totals.session_model.clone();
+            totals.bucket(UsageRole::Cli, &model).add_assign(usage);
+        }
+    }
+
+    /// Adds coordinator usage spent on `model`.
+    pub fn add_coordinator(&self, model: &str, usage: &TokenUsage) {
+        if let Ok(mut totals) = self.0.lock() {
+            totals
+                .bucket(UsageRole::Coordinator, model)
+                .add_assign(usage);
+        }
+    }
+
+    pub fn report(&self, prices: &HashMap<String, ModelPrice>) -> CostReport {
+        self.report_since(prices, &UsageBaseline::default())
+    }
+
+    /// Usage of every model since `baseline`. The session's own model is
+    /// always listed; other models only once they used tokens.
+    pub fn report_since(
+        &self,
+        prices: &HashMap<String, ModelPrice>,
+        baseline: &UsageBaseline,
+    ) -> CostReport {
+        let mut usage = Buckets::new();
+        if let Ok(totals) = self.0.lock() {
+            usage.insert(
+                (UsageRole::Cli, totals.session_model.clone()),
+                TokenUsage::default(),
+            );
+            for (key, now) in &totals.buckets {
+                let since = match baseline.0.get(key) {
+                    Some(before) => usage_since(now, before),
+                    None => now.clone(),
+                };
+                if since.total_tokens > 0 {
+                    usage.insert(key.clone(), since);
+                }
+            }
+        }
+        CostReport::new(&usage, prices)
+    }
+}
+
+pub(crate) fn usage_since(now: &TokenUsage, before: &TokenUsage) -> TokenUsage {
+    TokenUsage {
+        input_tokens: now.input_tokens.saturating_sub(before.input_tokens),
+        cached_input_tokens: now
+            .cached_input_tokens
+            .saturating_sub(before.cached_input_tokens),
+        output_tokens: now.output_tokens.saturating_sub(before.output_tokens),
+        reasoning_output_tokens: now
+            .reasoning_output_tokens
+            .saturating_sub(before.reasoning_output_tokens),
+        total_tokens: now.total_tokens.saturating_sub(before.total_tokens),
+    }
+}
+
+/// Forwards events to the wrapped processor while recording token usage.
+pub(crate) struct UsageTrackingProcessor {
+    inner: Box<dyn EventProcessor>,
+    usage: RunUsage,
+}
+
+impl EventProcessor for UsageTrackingProcessor {
+    fn print_config_summary(&mut self, config: &Config, prompt: &str) {
+        self.inner.print_config_summary(config, prompt);
+    }
+
+    fn process_event(&mut self, event: Event) -> CodexStatus {
+        if let EventMsg::TokenCount(count) = &event.msg
+            && let Some(info) = &count.info
+        {
+            self.usage.set(&info.total_token_usage);
+        }
+        self.inner.process_event(event)
+    }
+
+    fn report_run_limit(&mut self, limit: RunLimit) {
+        self.inner.report_run_limit(limit);
+    }
+
+    fn report_interruption(&mut self, interruption: Interruption) {
+        self.inner.report_interruption(interruption);
+    }
+
+    fn report_environment(&mut self, environment: &RunEnvironment) {
+        self.inner.report_environment(environment);
+    }
+
+    fn report_image(&mut self, status: &ImageStatus) {
+        self.inner.report_image(status);
+    }
+
+    fn report_failure(&mut self, failure: FailureClass) {
+        self.inner.report_failure(failure);
+    }
+
+    fn report_cost(&mut self, report: &CostReport) {
+        self.inner.report_cost(report);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn usage(input: u64, cached: u64, output: u64) -> TokenUsage {
+        TokenUsage {
+            input_tokens: input,
+            cached_input_tokens: cached,
+            output_tokens: output,
+            reasoning_output_tokens: 0,
+            total_tokens: input + output,
+        }
+    }
+
+    fn price(input: f64, output: f64) -> ModelPrice {
+        ModelPrice {
+            input,
+            cached_input: None,
+            output,
+        }
+    }
+
+    #[test]
+    fn prices_cached_input_separately_and_serializes_event() {
+        let usage = TokenUsage {
+            input_tokens: 1_000_000,
+            cached_input_tokens: 400_000,
+            output_tokens: 100_000,
+            reasoning_output_tokens: 60_000,
+            total_tokens: 1_100_000,
+        };
+        let buckets = Buckets::from([((UsageRole::Cli, "gpt-5".to_string()), usage)]);
+        let mut prices = HashMap::new();
+        prices.insert(
+            "gpt-5".to_string(),
+            ModelPrice {
+                input: 1.25,
+                cached_input: Some(0.125),
+                output: 10.0,
+            },
+        );
+
+        let report = CostReport::new(&buckets, &prices);
+        // 600k * 1.25 + 400k * 0.125 + 100k * 10 per million tokens.
+        assert_eq!(report.estimated_usd, Some(1.8));
+        assert_eq!(
+            report.to_json(),
+            serde_json::json!({
+                "type": "cost_report",
+                "input_tokens": 1_000_000,
+                "cached_input_tokens": 400_000,
+                "output_tokens": 100_000,
+                "reasoning_output_tokens": 60_000,
+                "total_tokens": 1_100_000,
+                "cache_hit_rate": 0.4,
+                "estimated_usd": 1.8,
+                "models": [{
+                    "model": "gpt-5",
+                    "role": "cli",
+                    "input_tokens": 1_000_000,
+                    "cached_input_tokens": 400_000,
+                    "output_tokens": 100_000,
+                    "reasoning_output_tokens": 60_000,
+                    "total_tokens": 1_100_000,
+                    "estimated_usd": 1.8,
+                }],
+            })
+        );
+
+        let unpriced = CostReport::new(&buckets, &HashMap::new());
+        assert_eq!(
+            unpriced.summary(),
+            "1,100,000 tokens (input 1,000,000, cached 400,000 (40% hit), output 100,000, reasoning 60,000) no price configured for gpt-5"
+        );
+    }
+
+    #[test]
+    fn routed_turns_and_coordinator_are_priced_by_their_own_model() {
+        let run_usage = RunUsage::new("gpt-5");
+        run_usage.set(&usage(1_000_000, 0, 100_000));
+        run_usage.route_turn(Some("gpt-5-mini".to_string()));
+        run_usage.set(&usage(3_000_000, 0, 200_000));
+        run_usage.route_turn(None);
+        run_usage.add_worker(&usage(500_000, 0, 0));
+        run_usage.add_coordinator("coordinator", &usage(200_000, 0, 0));
+
+        let prices = HashMap::from([
+            ("gpt-5".to_string(), price(1.0, 10.0)),
+            ("gpt-5-mini".to_string(), price(0.25, 2.0)),
+        ]);
+        let report = run_usage.report(&prices);
+
+        let per_model: Vec<(UsageRole, &str, u64, Option<f64>)> = report
+            .models
+            .iter()
+            .map(|model| {
+                (
+                    model.role,
+                    model.model.as_str(),
+                    model.total_tokens,
+                    model.estimated_usd,
+                )
+            })
+            .collect();
+        assert_eq!(
+            per_model,
+            vec![
+                (UsageRole::Cli, "gpt-5", 1_600_000, Some(2.5)),
+                (UsageRole::Cli, "gpt-5-mini", 2_100_000, Some(0.7)),
+                (UsageRole::Coordinator, "coordinator", 200_000, None),
+            ]
+        );
+        assert_eq!(report.total_tokens, 3_900_000);
+        // The coordinator model has no price, so neither has the run.
+        assert_eq!(report.estimated_usd, None);
+        assert_eq!(
+            report.summary(),
+            "3,900,000 tokens (input 3,700,000, cached 0 (0% hit), output 200,000, reasoning 0) no price configured for gpt-5 (1,600,000 tokens ≈ $2.5000), gpt-5-mini (2,100,000 tokens ≈ $0.7000), coordinator (coordinator) (200,000 tokens no price configured)"
+        );
+    }
+}
@@ -0,0 +1,327 @@
+//! `--ephemeral-worktree`: run the task in a throwaway copy of the checkout.
+//!
+//! Inside a git repository the copy is a detached `git worktree` at HEAD with
+//! the uncommitted changes carried over; any other directory is copied
+//! (honoring ignore files) and initialized as a scratch repository. Either way
+//! a baseline tree is recorded so the agent's changes can be diffed. When the
+//! run succeeds the diff is applied back to the original directory; otherwise
+//! it is saved as a patch. The copy is removed in both cases, and also when
+//! the run ends early and drops the worktree without finishing it.
+
+use std::ffi::OsStr;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use anyhow::Context;
+use code_core::git_worktree::copy_uncommitted_to_worktree;
+use code_core::git_worktree::get_git_root_from;
+use ignore::WalkBuilder;
+use tokio::process::Command;
+
+pub(crate) struct EphemeralWorktree {
+    /// Directory the changes are applied back to: the repository root, or
+    /// the copied directory itself.
+    source_root: PathBuf,
+    root: PathBuf,
+    /// Working directory for the run; mirrors where the user started.
+    cwd: PathBuf,
+    /// Repository that owns the worktree; `None` for a copied directory.
+    git_root: Option<PathBuf>,
+    baseline: String,
+    /// Set once [`Self::finish`] removed the worktree.
+    removed: bool,
+}
+
+impl EphemeralWorktree {
+    pub async fn create(source: &Path) -> anyhow::Result<Self> {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_nanos())
+            .unwrap_or_default();
+        let root =
+            std::env::temp_dir().join(format!("code-exec-worktree-{}-{nanos}", std::process::id()));
+
+        let (source_root, git_root) = match get_git_root_from(source).await {
+            Ok(git_root) => {
+                git(
+                    &git_root,
+                    [
+                        OsStr::new("worktree"),
+                        OsStr::new("add"),
+                        OsStr::new("--detach"),
+                        root.as_os_str(),
+                        OsStr::new("HEAD"),
+                    ],
+                )
+                .await
+                .context("failed to create ephemeral worktree")?;
+                copy_uncommitted_to_worktree(&git_root, &root)
+                    .await
+                    .map_err(anyhow::Error::msg)?;
+                (git_root.clone(), Some(git_root))
+            }
+            Err(_) => {
+                copy_dir(source, &root)?;
+                git(&root, ["init", "--quiet"]).await?;
+                (source.to_path_buf(), None)
+            }
+        };
+
+        let relative = source.strip_prefix(&source_root).unwrap_or(Path::new(""));
+        let mut worktree = Self {
+            cwd: root.join(relative),
+            source_root,
+            root,
+            git_root,
+            baseline: String::new(),
+            removed: false,
+        };
+        worktree.baseline = worktree.stage_tree().await?;
+        Ok(worktree)
+    }
+
+    pub fn cwd(&self) -> &Path {
+        &self.cwd
+    }
+
+    /// Applies the run's changes to the source when `success`, otherwise
+    /// saves them as a patch, then removes the worktree.
+    pub async fn finish(mut self, success: bool) {
+        if let Err(err) = self.carry_back(success).await {
+            eprintln!("[worktree] {err:#}");
+        }
+        self.remove().await;
+        self.removed = true;
+    }
+
+    async fn carry_back(&self, success: bool) -> anyhow::Result<()> {
+        self.stage_tree().await?;
+        let stat = git(
+            &self.root,
+            ["diff", "--cached", "--stat", self.baseline.as_str()],
+        )
+        .await?;
+        if stat.is_empty() {
+            eprintln!("[worktree] no changes");
+            return Ok(());
+        }
+        eprint!("{}", String::from_utf8_lossy(&stat));
+
+        let patch = git(
+            &self.root,
+            ["diff", "--cached", "--binary", self.baseline.as_str()],
+        )
+        .await?;
+        let patch_path = self.root.with_extension("patch");
+        std::fs::write(&patch_path, patch)
+            .with_context(|| format!("failed to write {}", patch_path.display()))?;
+        if !success {
+            eprintln!(
+                "[worktree] run failed; changes not applied, patch saved to {}",
+                patch_path.display()
+            );
+            return Ok(());
+        }
+        match git(
+            &self.source_root,
+            [
+                OsStr::new("apply"),
+                OsStr::new("--binary"),
+                patch_path.as_os_str(),
+            ],
+        )
+        .await
+        {
+            Ok(_) => {
+                let _ = std::fs::remove_file(&patch_path);
+                eprintln!(
+                    "[worktree] applied changes to {}",
+                    self.source_root.display()
+                );
+                Ok(())
+            }
+            Err(err) => Err(err.context(format!(
+                "changes could not be applied; patch saved to {}",
+                patch_path.display()
+            ))),
+        }
+    }
+
+    /// Stages everything in the worktree and returns the resulting tree id.
+    async fn stage_tree(&self) -> anyhow::Result<String> {
+        git(&self.root, ["add", "--all"]).await?;
+        let tree = git(&self.root, ["write-tree"]).await?;
+        Ok(String::from_utf8_lossy(&tree).trim().to_string())
+    }
+
+    async fn remove(&self) {
+        if let Some(git_root) = &self.git_root
+            && let Err(err) = git(
+                git_root,
+                [
+                    OsStr::new("worktree"),
+                    OsStr::new("remove"),
+                    OsStr::new("--force"),
+                    self.root.as_os_str(),
+                ],
+            )
+            .await
+        {
+            tracing::warn!("failed to remove ephemeral worktree: {err:#}");
+        }
+        self.remove_dir();
+    }
+
+    fn remove_dir(&self) {
+        if self.root.exists()
+            && let Err(err) = std::fs::remove_dir_all(&self.root)
+        {
+            eprintln!("[worktree] failed to remove {}: {err}", self.root.display());
+        }
+    }
+}
+
+impl Drop for EphemeralWorktree {
+    /// Cleans up after runs that end without [`EphemeralWorktree::finish`]:
+    /// errors, or exits that drop the worktree first. The run's changes are
+    /// discarded.
+    fn drop(&mut self) {
+        if self.removed {
+            return;
+        }
+        if let Some(git_root) = &self.git_root {
+            let removed = std::process::Command::new("git")
+                .current_dir(git_root)
+                .args(["worktree", "remove", "--force"])
+                .arg(&self.root)
+                .output();
+            if !removed.is_ok_and(|output| output.status.success()) {
+                tracing::warn!(
+                    "failed to remove ephemeral worktree {}",
+                    self.root.display()
+                );
+            }
+        }
+        self.remove_dir();
+    }
+}
+
+/// Copies `source` into `dest`, skipping ignored files and any `.git`.
+fn copy_dir(source: &Path, dest: &Path) -> anyhow::Result<()> {
+    let mut builder = WalkBuilder::new(source);
+    builder
+        .hidden(false)
+        .require_git(false)
+        .filter_entry(|entry| entry.file_name() != ".git");
+    for entry in builder.build() {
+        let entry = entry?;
+        let Ok(relative) = entry.path().strip_prefix(source) else {
+            continue;
+        };
+        let target = dest.join(relative);
+        if entry.file_type().is_some_and(|kind| kind.is_dir()) {
+            std::fs::create_dir_all(&target)?;
+        } else if entry.file_type().is_some_and(|kind| kind.is_file()) {
+            std::fs::copy(entry.path(), &target)
+                .with_context(|| format!("failed to copy {}", entry.path().display()))?;
+        }
+    }
+    Ok(())
+}
+
+async fn git<I, S>(dir: &Path, args: I) -> anyhow::Result<Vec<u8>>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let output = Command::new("git")
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .await
+        .context("failed to run git (required for --ephemeral-worktree)")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "git failed in {}: {}",
+            dir.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(output.stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    /// A fresh repository with a committed `README.md`, built with the same
+    /// `git` helper the worktree uses.
+    async fn committed_repo() -> tempfile::TempDir {
+        let repo = tempfile::tempdir().unwrap();
+        git(repo.path(), ["init", "--quiet"]).await.unwrap();
+        std::fs::write(repo.path().join("README.md"), "readme\n").unwrap();
+        git(repo.path(), ["add", "README.md"]).await.unwrap();
+        git(
+            repo.path(),
+            [
+                "-c",
+                "user.name=test",
+                "-c",
+                "user.email=test@example.com",
+                "commit",
+                "--quiet",
+                "-m",
+                "init",
+            ],
+        )
+        .await
+        .unwrap();
+        repo
+    }
+
+    #[tokio::test]
+    async fn applies_changes_from_copied_directory() {
+        let source = tempfile::tempdir().unwrap();
+        std::fs::create_dir(source.path().join("src")).unwrap();
+        std::fs::write(source.path().join("src/lib.rs"), "fn a() {}\n").unwrap();
+
+        let worktree = EphemeralWorktree::create(source.path()).await.unwrap();
+        let root = worktree.root.clone();
+        assert_ne!(worktree.cwd(), source.path());
+        std::fs::write(worktree.cwd().join("src/lib.rs"), "fn b() {}\n").unwrap();
+        std::fs::write(worktree.cwd().join("NEW.md"), "new\n").unwrap();
+        assert_eq!(
+            std::fs::read_to_string(source.path().join("src/lib.rs")).unwrap(),
+            "fn a() {}\n"
+        );
+
+        worktree.finish(true).await;
+        assert_eq!(
+            std::fs::read_to_string(source.path().join("src/lib.rs")).unwrap(),
+            "fn b() {}\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(source.path().join("NEW.md")).unwrap(),
+            "new\n"
+        );
+        assert!(!root.exists());
+    }
+
+    #[tokio::test]
+    async fn dropping_an_unfinished_worktree_removes_it() {
+        let source = committed_repo().await;
+        let worktree = EphemeralWorktree::create(source.path()).await.unwrap();
+        let root = worktree.root.clone();
+        assert!(root.exists());
+        drop(worktree);
+
+        assert!(!root.exists());
+        let list = git(source.path(), ["worktree", "list", "--porcelain"])
+            .await
+            .unwrap();
+        assert!(!String::from_utf8_lossy(&list).contains(&*root.to_string_lossy()));
+    }
+}
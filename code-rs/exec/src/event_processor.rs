@@ -1,7 +1,20 @@
 use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
 
 use code_core::config::Config;
 use code_core::protocol::Event;
+use serde_json::Value;
+use serde_json::json;
+use tokio::runtime::Handle;
+use tokio::runtime::RuntimeFlavor;
+
+use crate::attachments::ImageStatus;
+use crate::cost_report::CostReport;
+use crate::exit_code::FailureClass;
+use crate::interrupt::Interruption;
+use crate::run_environment::RunEnvironment;
+use crate::run_guard::RunLimit;
 
 pub(crate) enum CodexStatus {
     Running,
@@ -13,27 +26,224 @@ pub(crate) trait EventProcessor {
     /// Print summary of effective configuration and user prompt.
     fn print_config_summary(&mut self, config: &Config, prompt: &str);
 
+    /// Report the resolved environment the run starts with, right after the
+    /// config summary.
+    fn report_environment(&mut self, environment: &RunEnvironment);
+
     /// Handle a single event emitted by the agent.
     fn process_event(&mut self, event: Event) -> CodexStatus;
 
-    // No exit_code method; CLI controls process exit based on core events.
+    /// Report that `--timeout` or `--max-turns` interrupted the run.
+    fn report_run_limit(&mut self, limit: RunLimit);
+
+    /// Report that Ctrl-C interrupted the run or forced it to exit.
+    fn report_interruption(&mut self, interruption: Interruption);
+
+    /// Report whether one `--image` attachment passed validation, before the
+    /// session starts.
+    fn report_image(&mut self, status: &ImageStatus);
+
+    /// Report the failure class that decides the process exit code, right
+    /// before the CLI exits. The CLI derives it from core events.
+    fn report_failure(&mut self, failure: FailureClass);
+
+    /// Report final token usage and estimated cost once the run is over.
+    fn report_cost(&mut self, report: &CostReport);
+}
+
+/// Attempts made on the `--output-last-message` path before falling back.
+const LAST_MESSAGE_WRITE_ATTEMPTS: i32 = 4;
+/// Delay before the first retry; doubled after each failed attempt.
+const LAST_MESSAGE_INITIAL_BACKOFF: Duration = Duration::from_millis(200);
+
+/// Result of writing the final agent message to `--output-last-message`.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum LastMessageWrite {
+    Written,
+    /// The target kept failing, so the message was written to `fallback`.
+    Fallback {
+        fallback: PathBuf,
+        error: String,
+    },
+    /// Neither the target nor the fallback could be written.
+    Failed {
+        error: String,
+    },
+}
+
+impl LastMessageWrite {
+    /// JSONL event announcing a fallback or failed write, for machine readers.
+    pub fn to_json(&self, path: &Path) -> Option<Value> {
+        match self {
+            Self::Written => None,
+            Self::Fallback { fallback, error } => Some(json!({
+                "type": "last_message.fallback",
+                "path": path,
+                "fallback_path": fallback,
+                "error": error,
+            })),
+            Self::Failed { error } => Some(json!({
+                "type": "last_message.write_failed",
+                "path": path,
+                "error": error,
+            })),
+        }
+    }
+}
+
+/// [`handle_last_message`] for event processors, which handle events
+/// synchronously on a runtime worker: the worker hands its other tasks off
+/// while the write retries.
+pub(crate) fn handle_last_message_blocking(
+    last_agent_message: Option<&str>,
+    output_file: &Path,
+) -> LastMessageWrite {
+    let write = handle_last_message(last_agent_message, output_file);
+    match Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+            tokio::task::block_in_place(|| handle.block_on(write))
+        }
+        // No worker to hand off; drive the write on a thread of its own.
+        _ => std::thread::scope(|scope| {
+            scope
+                .spawn(|| {
+                    tokio::runtime::Builder::new_current_thread()
+                        .enable_all()
+                        .build()
+                        .map(|runtime| runtime.block_on(write))
+                        .unwrap_or_else(|err| LastMessageWrite::Failed {
+                            error: err.to_string(),
+                        })
+                })
+                .join()
+                .unwrap_or_else(|_| LastMessageWrite::Failed {
+                    error: "last message writer panicked".to_string(),
+                })
+        }),
+    }
 }
 
-pub(crate) fn handle_last_message(last_agent_message: Option<&str>, output_file: &Path) {
+pub(crate) async fn handle_last_message(
+    last_agent_message: Option<&str>,
+    output_file: &Path,
+) -> LastMessageWrite {
     let message = last_agent_message.unwrap_or_default();
-    write_last_message_file(message, Some(output_file));
-    if last_agent_message.is_none() {
-        eprintln!(
-            "Warning: no last agent message; wrote empty content to {}",
+    let outcome = write_last_message_file(message, output_file, LAST_MESSAGE_INITIAL_BACKOFF).await;
+    match &outcome {
+        LastMessageWrite::Written => {
+            if last_agent_message.is_none() {
+                eprintln!(
+                    "Warning: no last agent message; wrote empty content to {}",
+                    output_file.display()
+                );
+            }
+        }
+        LastMessageWrite::Fallback { fallback, error } => eprintln!(
+            "Failed to write last message file {}: {error}; wrote it to {} instead",
+            output_file.display(),
+            fallback.display()
+        ),
+        LastMessageWrite::Failed { error } => eprintln!(
+            "Failed to write last message file {}: {error}; the final message was not saved",
             output_file.display()
-        );
+        ),
     }
+    outcome
 }
 
-fn write_last_message_file(contents: &str, last_message_path: Option<&Path>) {
-    if let Some(path) = last_message_path {
-        if let Err(e) = std::fs::write(path, contents) {
-            eprintln!("Failed to write last message file {path:?}: {e}");
+/// Writes `contents` to `path`, retrying with exponential backoff (network
+/// mounts in CI can be briefly unavailable) before falling back to a file in
+/// the system temp directory.
+async fn write_last_message_file(
+    contents: &str,
+    path: &Path,
+    backoff: Duration,
+) -> LastMessageWrite {
+    let mut delay = backoff;
+    let mut last_error = String::new();
+    for attempt in 1..=LAST_MESSAGE_WRITE_ATTEMPTS {
+        match tokio::fs::write(path, contents).await {
+            Ok(()) => return LastMessageWrite::Written,
+            Err(err) => last_error = err.to_string(),
+        }
+        if attempt < LAST_MESSAGE_WRITE_ATTEMPTS {
+            tokio::time::sleep(delay).await;
+            delay *= 2;
         }
     }
+
+    let file_name = path
+        .file_name()
+        .map_or_else(|| "last-message.txt".into(), |name| name.to_string_lossy());
+    let fallback = std::env::temp_dir().join(format!("code-{}-{file_name}", std::process::id()));
+    match tokio::fs::write(&fallback, contents).await {
+        Ok(()) => LastMessageWrite::Fallback {
+            fallback,
+            error: last_error,
+        },
+        Err(err) => LastMessageWrite::Failed {
+            error: format!("{last_error}; fallback {}: {err}", fallback.display()),
+        },
+    }
+}
+
+/// Ignores everything; the processor wrapped by wrapper processors under
+/// test.
+#[cfg(test)]
+pub(crate) struct NullProcessor;
+
+#[cfg(test)]
+impl EventProcessor for NullProcessor {
+    fn print_config_summary(&mut self, _config: &Config, _prompt: &str) {}
+
+    fn process_event(&mut self, _event: Event) -> CodexStatus {
+        CodexStatus::Running
+    }
+
+    fn report_run_limit(&mut self, _limit: RunLimit) {}
+
+    fn report_interruption(&mut self, _interruption: Interruption) {}
+
+    fn report_environment(&mut self, _environment: &RunEnvironment) {}
+
+    fn report_image(&mut self, _status: &ImageStatus) {}
+
+    fn report_failure(&mut self, _failure: FailureClass) {}
+
+    fn report_cost(&mut self, _report: &CostReport) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[tokio::test]
+    async fn falls_back_to_temp_dir_when_target_is_unwritable() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("missing").join("last.txt");
+
+        let outcome = write_last_message_file("done", &target, Duration::ZERO).await;
+
+        let LastMessageWrite::Fallback { fallback, .. } = &outcome else {
+            panic!("expected fallback, got {outcome:?}");
+        };
+        assert_eq!(std::fs::read_to_string(fallback).unwrap(), "done");
+        assert_eq!(
+            outcome.to_json(&target).unwrap()["type"],
+            "last_message.fallback"
+        );
+        std::fs::remove_file(fallback).unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn blocking_write_runs_on_a_runtime_worker() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("last.txt");
+
+        let outcome = handle_last_message_blocking(Some("done"), &target);
+
+        assert_eq!(outcome, LastMessageWrite::Written);
+        assert_eq!(std::fs::read_to_string(&target).unwrap(), "done");
+    }
 }
@@ -1,4 +1,5 @@
 use code_common::elapsed::format_duration;
+use code_common::elapsed::format_duration_digital;
 use code_common::elapsed::format_elapsed;
 use code_core::config::Config;
 use code_core::plan_tool::UpdatePlanArgs;
@@ -34,9 +35,16 @@ use std::io::Write;
 use std::path::PathBuf;
 use std::time::Instant;
 
+use crate::attachments::ImageStatus;
+use crate::cli::Timestamps;
+use crate::cost_report::CostReport;
 use crate::event_processor::CodexStatus;
 use crate::event_processor::EventProcessor;
-use crate::event_processor::handle_last_message;
+use crate::event_processor::handle_last_message_blocking;
+use crate::exit_code::FailureClass;
+use crate::interrupt::Interruption;
+use crate::run_environment::RunEnvironment;
+use crate::run_guard::RunLimit;
 use code_common::create_config_summary_entries;
 
 /// This should be configurable. When used in CI, users may not want to impose
@@ -70,6 +78,12 @@ pub(crate) struct EventProcessorWithHumanOutput {
     /// Auto Drive sessions keep running across multiple turns, so they leave
     /// this false and handle shutdown themselves.
     stop_on_task_complete: bool,
+
+    /// Prefix printed before each event line.
+    timestamps: Timestamps,
+    started_at: Instant,
+    turn_started_at: Option<Instant>,
+    turn_count: i32,
 }
 
 impl EventProcessorWithHumanOutput {
@@ -78,6 +92,7 @@ impl EventProcessorWithHumanOutput {
         config: &Config,
         last_message_path: Option<PathBuf>,
         stop_on_task_complete: bool,
+        timestamps: Timestamps,
     ) -> Self {
         let call_id_to_command = HashMap::new();
         let call_id_to_patch = HashMap::new();
@@ -100,6 +115,10 @@ impl EventProcessorWithHumanOutput {
                 raw_reasoning_started: false,
                 last_message_path,
                 stop_on_task_complete,
+                timestamps,
+                started_at: Instant::now(),
+                turn_started_at: None,
+                turn_count: 0,
             }
         } else {
             Self {
@@ -119,6 +138,19 @@ impl EventProcessorWithHumanOutput {
                 raw_reasoning_started: false,
                 last_message_path,
                 stop_on_task_complete,
+                timestamps,
+                started_at: Instant::now(),
+                turn_started_at: None,
+                turn_count: 0,
+            }
+        }
+    }
+
+    fn timestamp_prefix(&self) -> String {
+        match self.timestamps {
+            Timestamps::Wall => chrono::Utc::now().format("[%Y-%m-%dT%H:%M:%S]").to_string(),
+            Timestamps::Elapsed => {
+                format!("[+{}]", format_duration_digital(self.started_at.elapsed()))
             }
         }
     }
@@ -137,10 +169,8 @@ struct PatchApplyBegin {
 #[macro_export]
 macro_rules! ts_println {
     ($self:ident, $($arg:tt)*) => {{
-        let now = chrono::Utc::now();
-        let formatted = now.format("[%Y-%m-%dT%H:%M:%S]");
-        print!("{} ", formatted.style($self.dimmed));
-        println!($($arg)*);
+        out_print!("{} ", $self.timestamp_prefix().style($self.dimmed));
+        out_println!($($arg)*);
     }};
 }
 
@@ -159,10 +189,10 @@ impl EventProcessor for EventProcessorWithHumanOutput {
         let entries = create_config_summary_entries(config);
 
         for (key, value) in entries {
-            println!("{} {}", format!("{key}:").style(self.bold), value);
+            out_println!("{} {}", format!("{key}:").style(self.bold), value);
         }
 
-        println!("--------");
+        out_println!("--------");
 
         // Echo the prompt that will be sent to the agent so it is visible in the
         // transcript/logs before any events come in. Note the prompt may have been
@@ -193,11 +223,26 @@ impl EventProcessor for EventProcessorWithHumanOutput {
                 // does not surface them alongside the human-readable transcript.
             }
             EventMsg::TaskStarted => {
-                // Ignore.
+                self.turn_started_at = Some(Instant::now());
+                self.turn_count += 1;
             }
             EventMsg::TaskComplete(TaskCompleteEvent { last_agent_message }) => {
+                if self.timestamps == Timestamps::Elapsed
+                    && let Some(turn_started_at) = self.turn_started_at.take()
+                {
+                    ts_println!(
+                        self,
+                        "{}",
+                        format!(
+                            "turn {} completed in {}",
+                            self.turn_count,
+                            format_duration(turn_started_at.elapsed())
+                        )
+                        .style(self.dimmed)
+                    );
+                }
                 if let Some(output_file) = self.last_message_path.as_deref() {
-                    handle_last_message(last_agent_message.as_deref(), output_file);
+                    handle_last_message_blocking(last_agent_message.as_deref(), output_file);
                 }
                 if self.stop_on_task_complete {
                     return CodexStatus::InitiateShutdown;
@@ -218,7 +263,7 @@ impl EventProcessor for EventProcessorWithHumanOutput {
                     ts_println!(self, "{}\n", "codex".style(self.italic).style(self.magenta));
                     self.answer_started = true;
                 }
-                print!("{delta}");
+                out_print!("{delta}");
                 #[expect(clippy::expect_used)]
                 std::io::stdout().flush().expect("could not flush stdout");
             }
@@ -234,7 +279,7 @@ impl EventProcessor for EventProcessorWithHumanOutput {
                     );
                     self.reasoning_started = true;
                 }
-                print!("{delta}");
+                out_print!("{delta}");
                 #[expect(clippy::expect_used)]
                 std::io::stdout().flush().expect("could not flush stdout");
             }
@@ -242,7 +287,7 @@ impl EventProcessor for EventProcessorWithHumanOutput {
                 if !self.show_agent_reasoning {
                     return CodexStatus::Running;
                 }
-                println!();
+                out_println!();
                 #[expect(clippy::expect_used)]
                 std::io::stdout().flush().expect("could not flush stdout");
             }
@@ -251,11 +296,11 @@ impl EventProcessor for EventProcessorWithHumanOutput {
                     return CodexStatus::Running;
                 }
                 if !self.raw_reasoning_started {
-                    print!("{text}");
+                    out_print!("{text}");
                     #[expect(clippy::expect_used)]
                     std::io::stdout().flush().expect("could not flush stdout");
                 } else {
-                    println!();
+                    out_println!();
                     self.raw_reasoning_started = false;
                 }
             }
@@ -268,7 +313,7 @@ impl EventProcessor for EventProcessorWithHumanOutput {
                 if !self.raw_reasoning_started {
                     self.raw_reasoning_started = true;
                 }
-                print!("{delta}");
+                out_print!("{delta}");
                 #[expect(clippy::expect_used)]
                 std::io::stdout().flush().expect("could not flush stdout");
             }
@@ -283,7 +328,7 @@ impl EventProcessor for EventProcessorWithHumanOutput {
                         message,
                     );
                 } else {
-                    println!();
+                    out_println!();
                     self.answer_started = false;
                 }
             }
@@ -314,6 +359,7 @@ impl EventProcessor for EventProcessorWithHumanOutput {
                 stderr,
                 duration,
                 exit_code,
+                sandbox_denied: _,
             }) => {
                 let exec_command = self.call_id_to_command.remove(&call_id);
                 let (duration, call) = if let Some(ExecCommandBegin { command, .. }) = exec_command
@@ -341,19 +387,19 @@ impl EventProcessor for EventProcessorWithHumanOutput {
                         let title = format!("{call} succeeded{duration}:");
                         ts_println!(self, "{}", title.style(self.green));
                         if !truncated_stdout.is_empty() {
-                            println!("{}", truncated_stdout.style(self.dimmed));
+                            out_println!("{}", truncated_stdout.style(self.dimmed));
                         }
                     }
                     _ => {
                         let title = format!("{call} exited {exit_code}{duration}:");
                         ts_println!(self, "{}", title.style(self.red));
                         if !truncated_stdout.is_empty() {
-                            println!("{}", truncated_stdout.style(self.dimmed));
-                            println!();
+                            out_println!("{}", truncated_stdout.style(self.dimmed));
+                            out_println!();
                         }
-                        println!("ERROR");
+                        out_println!("ERROR");
                         if !truncated_stderr.is_empty() {
-                            println!("{}", truncated_stderr);
+                            out_println!("{}", truncated_stderr);
                         }
                     }
                 }
@@ -395,7 +441,7 @@ impl EventProcessor for EventProcessorWithHumanOutput {
                         serde_json::to_string_pretty(&val).unwrap_or_else(|_| val.to_string());
 
                     for line in pretty.lines().take(MAX_OUTPUT_LINES_FOR_EXEC_TOOL_CALL) {
-                        println!("{}", line.style(self.dimmed));
+                        out_println!("{}", line.style(self.dimmed));
                     }
                 }
             }
@@ -437,9 +483,9 @@ impl EventProcessor for EventProcessorWithHumanOutput {
                                 format_file_change(change),
                                 path.to_string_lossy()
                             );
-                            println!("{}", header.style(self.magenta));
+                            out_println!("{}", header.style(self.magenta));
                             for line in content.lines() {
-                                println!("{}", line.style(self.green));
+                                out_println!("{}", line.style(self.green));
                             }
                         }
                         FileChange::Delete => {
@@ -448,7 +494,7 @@ impl EventProcessor for EventProcessorWithHumanOutput {
                                 format_file_change(change),
                                 path.to_string_lossy()
                             );
-                            println!("{}", header.style(self.magenta));
+                            out_println!("{}", header.style(self.magenta));
                         }
                         FileChange::Update {
                             unified_diff,
@@ -465,20 +511,20 @@ impl EventProcessor for EventProcessorWithHumanOutput {
                             } else {
                                 format!("{} {}", format_file_change(change), path.to_string_lossy())
                             };
-                            println!("{}", header.style(self.magenta));
+                            out_println!("{}", header.style(self.magenta));
 
                             // Colorize diff lines. We keep file header lines
                             // (--- / +++) without extra coloring so they are
                             // still readable.
                             for diff_line in unified_diff.lines() {
                                 if diff_line.starts_with('+') && !diff_line.starts_with("+++") {
-                                    println!("{}", diff_line.style(self.green));
+                                    out_println!("{}", diff_line.style(self.green));
                                 } else if diff_line.starts_with('-')
                                     && !diff_line.starts_with("---")
                                 {
-                                    println!("{}", diff_line.style(self.red));
+                                    out_println!("{}", diff_line.style(self.red));
                                 } else {
-                                    println!("{diff_line}");
+                                    out_println!("{diff_line}");
                                 }
                             }
                         }
@@ -517,12 +563,12 @@ impl EventProcessor for EventProcessorWithHumanOutput {
                 let title = format!("{label} exited {exit_code}{duration}:");
                 ts_println!(self, "{}", title.style(title_style));
                 for line in output.lines() {
-                    println!("{}", line.style(self.dimmed));
+                    out_println!("{}", line.style(self.dimmed));
                 }
             }
             EventMsg::TurnDiff(TurnDiffEvent { unified_diff }) => {
                 ts_println!(self, "{}", "turn diff:".style(self.magenta));
-                println!("{unified_diff}");
+                out_println!("{unified_diff}");
             }
             EventMsg::ExecApprovalRequest(_) => {
                 // Should we exit?
@@ -540,7 +586,7 @@ impl EventProcessor for EventProcessorWithHumanOutput {
                             agent_reasoning_event.text,
                         );
                     } else {
-                        println!();
+                        out_println!();
                         self.reasoning_started = false;
                     }
                 }
@@ -560,7 +606,7 @@ impl EventProcessor for EventProcessorWithHumanOutput {
                 );
 
                 ts_println!(self, "model: {}", model);
-                println!();
+                out_println!();
             }
             EventMsg::PlanUpdate(plan_update_event) => {
                 let UpdatePlanArgs { name, plan } = plan_update_event;
@@ -570,6 +616,12 @@ impl EventProcessor for EventProcessorWithHumanOutput {
             EventMsg::GetHistoryEntryResponse(_) => {
                 // Currently ignored in exec output.
             }
+            EventMsg::ProviderHealth(_) => {
+                // Currently ignored in exec output.
+            }
+            EventMsg::TurnLatency(latency) => {
+                ts_println!(self, "{}", format!("latency: {latency}").style(self.dimmed));
+            }
             EventMsg::ReplayHistory(_) => {
                 // Replay is a TUI concern; ignore in headless output
             }
@@ -589,7 +641,7 @@ impl EventProcessor for EventProcessorWithHumanOutput {
                 if let Some(params) = &event.parameters {
                     if let Ok(formatted) = serde_json::to_string_pretty(params) {
                         for line in formatted.lines() {
-                            println!("{}", line.style(self.dimmed));
+                            out_println!("{}", line.style(self.dimmed));
                         }
                     }
                 }
@@ -611,30 +663,40 @@ impl EventProcessor for EventProcessorWithHumanOutput {
                     Ok(content) => {
                         if !content.is_empty() {
                             for line in content.lines() {
-                                println!("{}", line.style(self.dimmed));
+                                out_println!("{}", line.style(self.dimmed));
                             }
                         }
                     }
                     Err(err) => {
                         if !err.is_empty() {
                             for line in err.lines() {
-                                println!("{}", line.style(self.red));
+                                out_println!("{}", line.style(self.red));
                             }
                         }
                     }
                 }
             }
-            EventMsg::TurnAborted(abort_reason) => match abort_reason.reason {
-                TurnAbortReason::Interrupted => {
-                    ts_println!(self, "task interrupted");
-                }
-                TurnAbortReason::Replaced => {
-                    ts_println!(self, "task aborted: replaced by a new task");
+            EventMsg::TurnAborted(abort_reason) => {
+                match abort_reason.reason {
+                    TurnAbortReason::Interrupted => {
+                        ts_println!(self, "task interrupted");
+                    }
+                    TurnAbortReason::Replaced => {
+                        ts_println!(self, "task aborted: replaced by a new task");
+                    }
+                    TurnAbortReason::ReviewEnded => {
+                        ts_println!(self, "task aborted: review ended");
+                    }
                 }
-                TurnAbortReason::ReviewEnded => {
-                    ts_println!(self, "task aborted: review ended");
+                for call in &abort_reason.cancelled_tool_calls {
+                    out_println!(
+                        "{} {} ({})",
+                        "cancelled".style(self.red),
+                        call.tool.style(self.bold),
+                        call.call_id.style(self.dimmed)
+                    );
                 }
-            },
+            }
             EventMsg::ShutdownComplete => return CodexStatus::Shutdown,
             EventMsg::ConversationPath(_) => {}
             EventMsg::UserMessage(_) => {}
@@ -644,13 +706,49 @@ impl EventProcessor for EventProcessorWithHumanOutput {
         }
         CodexStatus::Running
     }
+
+    fn report_run_limit(&mut self, limit: RunLimit) {
+        let prefix = "run stopped:".style(self.red);
+        ts_println!(self, "{prefix} {}", limit.describe());
+    }
+
+    fn report_interruption(&mut self, interruption: Interruption) {
+        let prefix = "ctrl-c:".style(self.red);
+        ts_println!(self, "{prefix} {}", interruption.describe());
+    }
+
+    /// The config summary already shows these settings.
+    fn report_environment(&mut self, _environment: &RunEnvironment) {}
+
+    fn report_image(&mut self, status: &ImageStatus) {
+        let prefix = if status.is_ok() {
+            "image:".style(self.dimmed)
+        } else {
+            "image failed:".style(self.red)
+        };
+        ts_println!(self, "{prefix} {}", status.describe());
+    }
+
+    fn report_failure(&mut self, failure: FailureClass) {
+        let prefix = "run failed:".style(self.red);
+        ts_println!(
+            self,
+            "{prefix} {} (exit code {})",
+            failure.reason(),
+            failure.exit_code()
+        );
+    }
+
+    fn report_cost(&mut self, report: &CostReport) {
+        ts_println!(self, "{} {}", "cost:".style(self.dimmed), report.summary());
+    }
 }
 
-fn escape_command(command: &[String]) -> String {
+pub(crate) fn escape_command(command: &[String]) -> String {
     try_join(command.iter().map(String::as_str)).unwrap_or_else(|_| command.join(" "))
 }
 
-fn format_file_change(change: &FileChange) -> &'static str {
+pub(crate) fn format_file_change(change: &FileChange) -> &'static str {
     match change {
         FileChange::Add { .. } => "A",
         FileChange::Delete { .. } => "D",
@@ -663,7 +761,7 @@ fn format_file_change(change: &FileChange) -> &'static str {
     }
 }
 
-fn format_mcp_invocation(invocation: &McpInvocation) -> String {
+pub(crate) fn format_mcp_invocation(invocation: &McpInvocation) -> String {
     // Build fully-qualified tool name: server.tool
     let fq_tool_name = format!("{}.{}", invocation.server, invocation.tool);
 
@@ -7,9 +7,15 @@ use code_core::protocol::EventMsg;
 use code_core::protocol::TaskCompleteEvent;
 use serde_json::json;
 
+use crate::attachments::ImageStatus;
+use crate::cost_report::CostReport;
 use crate::event_processor::CodexStatus;
 use crate::event_processor::EventProcessor;
-use crate::event_processor::handle_last_message;
+use crate::event_processor::handle_last_message_blocking;
+use crate::exit_code::FailureClass;
+use crate::interrupt::Interruption;
+use crate::run_environment::RunEnvironment;
+use crate::run_guard::RunLimit;
 use code_common::create_config_summary_entries;
 
 pub(crate) struct EventProcessorWithJsonOutput {
@@ -35,12 +41,12 @@ impl EventProcessor for EventProcessorWithJsonOutput {
         #[expect(clippy::expect_used)]
         let config_json =
             serde_json::to_string(&entries).expect("Failed to serialize config summary to JSON");
-        println!("{config_json}");
+        out_println!("{config_json}");
 
         let prompt_json = json!({
             "prompt": prompt,
         });
-        println!("{prompt_json}");
+        out_println!("{prompt_json}");
     }
 
     fn process_event(&mut self, event: Event) -> CodexStatus {
@@ -54,20 +60,70 @@ impl EventProcessor for EventProcessorWithJsonOutput {
                 CodexStatus::Running
             }
             EventMsg::TaskComplete(TaskCompleteEvent { last_agent_message }) => {
-                if let Some(output_file) = self.last_message_path.as_deref() {
-                    handle_last_message(last_agent_message.as_deref(), output_file);
+                if let Some(output_file) = self.last_message_path.as_deref()
+                    && let Some(line) =
+                        handle_last_message_blocking(last_agent_message.as_deref(), output_file)
+                            .to_json(output_file)
+                {
+                    out_println!("{line}");
                 }
                 CodexStatus::InitiateShutdown
             }
             EventMsg::ShutdownComplete => CodexStatus::Shutdown,
             _ => {
                 if let Ok(line) = serde_json::to_string(&event) {
-                    println!("{line}");
+                    out_println!("{line}");
                 }
                 CodexStatus::Running
             }
         }
     }
 
-    // exit_code handled by CLI; suppress unused warnings by omitting method.
+    fn report_run_limit(&mut self, limit: RunLimit) {
+        let aborted = json!({
+            "type": "run.aborted",
+            "reason": limit.reason(),
+            "message": limit.describe(),
+        });
+        out_println!("{aborted}");
+    }
+
+    fn report_interruption(&mut self, interruption: Interruption) {
+        let interrupted = json!({
+            "type": interruption.reason(),
+            "message": interruption.describe(),
+        });
+        out_println!("{interrupted}");
+    }
+
+    fn report_environment(&mut self, environment: &RunEnvironment) {
+        if let Ok(line) = serde_json::to_string(environment) {
+            out_println!("{line}");
+        }
+    }
+
+    fn report_image(&mut self, status: &ImageStatus) {
+        let image = json!({
+            "type": "attachment.image",
+            "path": status.path,
+            "status": if status.is_ok() { "ok" } else { "failed" },
+            "mime": status.mime,
+            "bytes": status.bytes,
+            "error": status.error,
+        });
+        out_println!("{image}");
+    }
+
+    fn report_failure(&mut self, failure: FailureClass) {
+        let failed = json!({
+            "type": "run.failed",
+            "reason": failure.reason(),
+            "exit_code": failure.exit_code(),
+        });
+        out_println!("{failed}");
+    }
+
+    fn report_cost(&mut self, report: &CostReport) {
+        out_println!("{}", report.to_json());
+    }
 }
@@ -0,0 +1,355 @@
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::Instant;
+
+use code_core::config::Config;
+use code_core::protocol::ErrorEvent;
+use code_core::protocol::Event;
+use code_core::protocol::EventMsg;
+use code_core::protocol::ExecCommandBeginEvent;
+use code_core::protocol::ExecCommandEndEvent;
+use code_core::protocol::TaskCompleteEvent;
+use shlex::try_join;
+
+use crate::attachments::ImageStatus;
+use crate::cost_report::CostReport;
+use crate::event_processor::CodexStatus;
+use crate::event_processor::EventProcessor;
+use crate::event_processor::LastMessageWrite;
+use crate::event_processor::handle_last_message_blocking;
+use crate::exit_code::FailureClass;
+use crate::interrupt::Interruption;
+use crate::run_environment::RunEnvironment;
+use crate::run_guard::RunLimit;
+
+/// Maximum number of bytes of command output embedded in a failure body.
+const MAX_FAILURE_OUTPUT_BYTES: usize = 4096;
+
+/// Collects turns and exec commands as JUnit test cases and prints a single
+/// `<testsuites>` report to stdout when the session shuts down.
+pub(crate) struct EventProcessorWithJUnitOutput {
+    last_message_path: Option<PathBuf>,
+    suite_name: String,
+    started_at: Instant,
+    turn_started_at: Option<Instant>,
+    turn_errors: Vec<String>,
+    turn_count: i32,
+    pending_commands: HashMap<String, Vec<String>>,
+    cases: Vec<JUnitCase>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct JUnitCase {
+    classname: &'static str,
+    name: String,
+    duration: Duration,
+    failure: Option<JUnitFailure>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct JUnitFailure {
+    message: String,
+    body: String,
+}
+
+impl EventProcessorWithJUnitOutput {
+    pub fn new(last_message_path: Option<PathBuf>) -> Self {
+        Self {
+            last_message_path,
+            suite_name: "code exec".to_string(),
+            started_at: Instant::now(),
+            turn_started_at: None,
+            turn_errors: Vec::new(),
+            turn_count: 0,
+            pending_commands: HashMap::new(),
+            cases: Vec::new(),
+        }
+    }
+
+    fn finish_turn(&mut self) {
+        let Some(started_at) = self.turn_started_at.take() else {
+            return;
+        };
+        self.turn_count += 1;
+        let failure = if self.turn_errors.is_empty() {
+            None
+        } else {
+            let errors = std::mem::take(&mut self.turn_errors);
+            Some(JUnitFailure {
+                message: errors[0].clone(),
+                body: errors.join("\n"),
+            })
+        };
+        self.cases.push(JUnitCase {
+            classname: "turn",
+            name: format!("turn {}", self.turn_count),
+            duration: started_at.elapsed(),
+            failure,
+        });
+    }
+
+    fn render(&self) -> String {
+        render_report(&self.suite_name, self.started_at.elapsed(), &self.cases)
+    }
+}
+
+impl EventProcessor for EventProcessorWithJUnitOutput {
+    fn print_config_summary(&mut self, config: &Config, _prompt: &str) {
+        // The report is the only thing written to stdout; name the suite after
+        // the model so CI dashboards can tell runs apart.
+        self.suite_name = format!("code exec ({})", config.model);
+    }
+
+    fn process_event(&mut self, event: Event) -> CodexStatus {
+        match event.msg {
+            EventMsg::TaskStarted => {
+                self.turn_started_at = Some(Instant::now());
+                self.turn_errors.clear();
+                CodexStatus::Running
+            }
+            EventMsg::Error(ErrorEvent { message }) => {
+                if self.turn_started_at.is_some() {
+                    self.turn_errors.push(message);
+                } else {
+                    self.cases.push(JUnitCase {
+                        classname: "session",
+                        name: "error".to_string(),
+                        duration: Duration::ZERO,
+                        failure: Some(JUnitFailure {
+                            body: message.clone(),
+                            message,
+                        }),
+                    });
+                }
+                CodexStatus::Running
+            }
+            EventMsg::ExecCommandBegin(ExecCommandBeginEvent {
+                call_id, command, ..
+            }) => {
+                self.pending_commands.insert(call_id, command);
+                CodexStatus::Running
+            }
+            EventMsg::ExecCommandEnd(ExecCommandEndEvent {
+                call_id,
+                stdout,
+                stderr,
+                exit_code,
+                duration,
+                sandbox_denied: _,
+            }) => {
+                let name = self
+                    .pending_commands
+                    .remove(&call_id)
+                    .map(|command| {
+                        try_join(command.iter().map(String::as_str))
+                            .unwrap_or_else(|_| command.join(" "))
+                    })
+                    .unwrap_or_else(|| format!("exec('{call_id}')"));
+                let failure = (exit_code != 0).then(|| JUnitFailure {
+                    message: format!("exited with code {exit_code}"),
+                    body: truncate_output(if stderr.trim().is_empty() {
+                        &stdout
+                    } else {
+                        &stderr
+                    }),
+                });
+                self.cases.push(JUnitCase {
+                    classname: "exec",
+                    name,
+                    duration,
+                    failure,
+                });
+                CodexStatus::Running
+            }
+            EventMsg::TaskComplete(TaskCompleteEvent { last_agent_message }) => {
+                self.finish_turn();
+                if let Some(output_file) = self.last_message_path.as_deref()
+                    && let LastMessageWrite::Failed { error } =
+                        handle_last_message_blocking(last_agent_message.as_deref(), output_file)
+                {
+                    let message = format!(
+                        "failed to write last message file {}",
+                        output_file.display()
+                    );
+                    self.cases.push(JUnitCase {
+                        classname: "session",
+                        name: "last_message".to_string(),
+                        duration: Duration::ZERO,
+                        failure: Some(JUnitFailure {
+                            message,
+                            body: error,
+                        }),
+                    });
+                }
+                CodexStatus::InitiateShutdown
+            }
+            EventMsg::ShutdownComplete => {
+                self.finish_turn();
+                out_println!("{}", self.render());
+                CodexStatus::Shutdown
+            }
+            _ => CodexStatus::Running,
+        }
+    }
+
+    fn report_run_limit(&mut self, limit: RunLimit) {
+        let message = limit.describe();
+        self.cases.push(JUnitCase {
+            classname: "session",
+            name: limit.reason().to_string(),
+            duration: self.started_at.elapsed(),
+            failure: Some(JUnitFailure {
+                body: message.clone(),
+                message,
+            }),
+        });
+    }
+
+    /// The config summary already shows these settings.
+    fn report_environment(&mut self, _environment: &RunEnvironment) {}
+
+    fn report_image(&mut self, status: &ImageStatus) {
+        self.cases.push(JUnitCase {
+            classname: "attachments",
+            name: status.path.clone(),
+            duration: Duration::ZERO,
+            failure: status.error.as_ref().map(|error| JUnitFailure {
+                message: error.clone(),
+                body: status.describe(),
+            }),
+        });
+    }
+
+    fn report_interruption(&mut self, interruption: Interruption) {
+        let message = interruption.describe();
+        self.cases.push(JUnitCase {
+            classname: "session",
+            name: interruption.reason().to_string(),
+            duration: self.started_at.elapsed(),
+            failure: Some(JUnitFailure {
+                body: message.clone(),
+                message,
+            }),
+        });
+    }
+
+    fn report_failure(&mut self, _failure: FailureClass) {
+        // The report was already written at shutdown; its failed cases carry
+        // the details and the exit code carries the class.
+    }
+
+    fn report_cost(&mut self, report: &CostReport) {
+        // stdout holds only the XML report, so the cost goes to stderr.
+        eprintln!("cost: {}", report.summary());
+    }
+}
+
+fn render_report(suite_name: &str, total: Duration, cases: &[JUnitCase]) -> String {
+    let failures = cases.iter().filter(|case| case.failure.is_some()).count();
+    let tests = cases.len();
+    let time = total.as_secs_f64();
+    let suite_name = escape_xml(suite_name);
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        xml,
+        "<testsuites name=\"{suite_name}\" tests=\"{tests}\" failures=\"{failures}\" time=\"{time:.3}\">"
+    );
+    let _ = writeln!(
+        xml,
+        "  <testsuite name=\"{suite_name}\" tests=\"{tests}\" failures=\"{failures}\" errors=\"0\" skipped=\"0\" time=\"{time:.3}\">"
+    );
+    for case in cases {
+        let name = escape_xml(&case.name);
+        let classname = case.classname;
+        let time = case.duration.as_secs_f64();
+        match &case.failure {
+            None => {
+                let _ = writeln!(
+                    xml,
+                    "    <testcase classname=\"{classname}\" name=\"{name}\" time=\"{time:.3}\"/>"
+                );
+            }
+            Some(failure) => {
+                let message = escape_xml(&failure.message);
+                let body = escape_xml(&failure.body);
+                let _ = writeln!(
+                    xml,
+                    "    <testcase classname=\"{classname}\" name=\"{name}\" time=\"{time:.3}\">"
+                );
+                let _ = writeln!(xml, "      <failure message=\"{message}\">{body}</failure>");
+                let _ = writeln!(xml, "    </testcase>");
+            }
+        }
+    }
+    xml.push_str("  </testsuite>\n</testsuites>");
+    xml
+}
+
+fn truncate_output(output: &str) -> String {
+    if output.len() <= MAX_FAILURE_OUTPUT_BYTES {
+        return output.to_string();
+    }
+    let mut end = MAX_FAILURE_OUTPUT_BYTES;
+    while !output.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}\n[truncated]", &output[..end])
+}
+
+fn escape_xml(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for ch in value.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // XML 1.0 forbids most control characters even when escaped.
+            c if c.is_control() && !matches!(c, '\n' | '\r' | '\t') => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn renders_passing_and_failing_cases() {
+        let cases = vec![
+            JUnitCase {
+                classname: "exec",
+                name: "cargo test".to_string(),
+                duration: Duration::from_millis(1500),
+                failure: Some(JUnitFailure {
+                    message: "exited with code 101".to_string(),
+                    body: "assertion `left == right` failed <x>".to_string(),
+                }),
+            },
+            JUnitCase {
+                classname: "turn",
+                name: "turn 1".to_string(),
+                duration: Duration::from_secs(2),
+                failure: None,
+            },
+        ];
+        assert_eq!(
+            render_report("code exec", Duration::from_secs(3), &cases),
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <testsuites name=\"code exec\" tests=\"2\" failures=\"1\" time=\"3.000\">\n  \
+             <testsuite name=\"code exec\" tests=\"2\" failures=\"1\" errors=\"0\" skipped=\"0\" time=\"3.000\">\n    \
+             <testcase classname=\"exec\" name=\"cargo test\" time=\"1.500\">\n      \
+             <failure message=\"exited with code 101\">assertion `left == right` failed &lt;x&gt;</failure>\n    \
+             </testcase>\n    \
+             <testcase classname=\"turn\" name=\"turn 1\" time=\"2.000\"/>\n  \
+             </testsuite>\n</testsuites>"
+        );
+    }
+}
@@ -0,0 +1,324 @@
+//! Condensed human output (`--progress`, alias `--quiet`).
+//!
+//! While the run is in progress only a single status line is drawn on stderr
+//! (and only when stderr is a terminal, so CI logs stay clean). When the run
+//! ends the final agent message is printed to stdout followed by a short
+//! summary table.
+
+use std::collections::HashMap;
+use std::io::IsTerminal;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Instant;
+
+use code_common::elapsed::format_duration;
+use code_common::elapsed::format_duration_digital;
+use code_core::config::Config;
+use code_core::protocol::AgentMessageEvent;
+use code_core::protocol::ErrorEvent;
+use code_core::protocol::Event;
+use code_core::protocol::EventMsg;
+use code_core::protocol::ExecCommandBeginEvent;
+use code_core::protocol::ExecCommandEndEvent;
+use code_core::protocol::PatchApplyEndEvent;
+use code_core::protocol::TaskCompleteEvent;
+use code_protocol::num_format::format_with_separators;
+use owo_colors::OwoColorize;
+use owo_colors::Style;
+
+use crate::attachments::ImageStatus;
+use crate::cost_report::CostReport;
+use crate::event_processor::CodexStatus;
+use crate::event_processor::EventProcessor;
+use crate::event_processor::handle_last_message_blocking;
+use crate::event_processor_with_human_output::escape_command;
+use crate::exit_code::FailureClass;
+use crate::interrupt::Interruption;
+use crate::run_environment::RunEnvironment;
+use crate::run_guard::RunLimit;
+
+const SPINNER_FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+/// Widest status line drawn, so it never wraps on narrow terminals.
+const MAX_STATUS_CHARS: usize = 100;
+
+pub(crate) struct EventProcessorWithProgressOutput {
+    last_message_path: Option<PathBuf>,
+    stop_on_task_complete: bool,
+    /// Whether to draw the live status line on stderr.
+    draw_status: bool,
+    status_drawn: bool,
+    frame: usize,
+
+    bold: Style,
+    red: Style,
+
+    stats: RunStats,
+    running_commands: HashMap<String, String>,
+    last_agent_message: Option<String>,
+    stopped: Option<String>,
+}
+
+/// Counters shown in the status line and the final summary.
+#[derive(Debug)]
+struct RunStats {
+    started_at: Instant,
+    turns: i32,
+    commands: i32,
+    failed_commands: i32,
+    tool_calls: i32,
+    patches: i32,
+    errors: i32,
+    tokens: u64,
+}
+
+impl EventProcessorWithProgressOutput {
+    pub(crate) fn create_with_ansi(
+        with_ansi: bool,
+        last_message_path: Option<PathBuf>,
+        stop_on_task_complete: bool,
+    ) -> Self {
+        let styled = |style: Style| if with_ansi { style } else { Style::new() };
+        Self {
+            last_message_path,
+            stop_on_task_complete,
+            draw_status: std::io::stderr().is_terminal(),
+            status_drawn: false,
+            frame: 0,
+            bold: styled(Style::new().bold()),
+            red: styled(Style::new().red()),
+            stats: RunStats::new(),
+            running_commands: HashMap::new(),
+            last_agent_message: None,
+            stopped: None,
+        }
+    }
+
+    fn redraw_status(&mut self) {
+        if !self.draw_status {
+            return;
+        }
+        self.frame = (self.frame + 1) % SPINNER_FRAMES.len();
+        let activity = self.running_commands.values().next().map(String::as_str);
+        let line = self
+            .stats
+            .status_line(activity)
+            .chars()
+            .take(MAX_STATUS_CHARS)
+            .collect::<String>();
+        let mut stderr = std::io::stderr();
+        let _ = write!(stderr, "\r\x1b[2K{} {line}", SPINNER_FRAMES[self.frame]);
+        let _ = stderr.flush();
+        self.status_drawn = true;
+    }
+
+    fn clear_status(&mut self) {
+        if self.status_drawn {
+            let mut stderr = std::io::stderr();
+            let _ = write!(stderr, "\r\x1b[2K");
+            let _ = stderr.flush();
+            self.status_drawn = false;
+        }
+    }
+
+    fn print_final_report(&mut self) {
+        self.clear_status();
+        if let Some(message) = self.last_agent_message.take() {
+            out_println!("{}\n", message.trim_end());
+        }
+        if let Some(stopped) = self.stopped.take() {
+            out_println!("{} {stopped}", "run stopped:".style(self.red));
+        }
+        for (label, value) in self.stats.summary_rows() {
+            out_println!("{} {value}", format!("{label:<10}").style(self.bold));
+        }
+    }
+}
+
+impl RunStats {
+    fn new() -> Self {
+        Self {
+            started_at: Instant::now(),
+            turns: 0,
+            commands: 0,
+            failed_commands: 0,
+            tool_calls: 0,
+            patches: 0,
+            errors: 0,
+            tokens: 0,
+        }
+    }
+
+    fn status_line(&self, activity: Option<&str>) -> String {
+        let mut line = format!(
+            "turn {} · {} · {} tokens",
+            self.turns.max(1),
+            format_duration_digital(self.started_at.elapsed()),
+            format_with_separators(self.tokens)
+        );
+        if let Some(activity) = activity {
+            line.push_str(&format!(" · {activity}"));
+        }
+        line
+    }
+
+    fn summary_rows(&self) -> Vec<(&'static str, String)> {
+        let mut commands = self.commands.to_string();
+        if self.failed_commands > 0 {
+            commands.push_str(&format!(" ({} failed)", self.failed_commands));
+        }
+        let mut rows = vec![
+            ("turns", self.turns.to_string()),
+            ("elapsed", format_duration(self.started_at.elapsed())),
+            ("tokens", format_with_separators(self.tokens)),
+            ("commands", commands),
+            ("tool calls", self.tool_calls.to_string()),
+            ("patches", self.patches.to_string()),
+        ];
+        if self.errors > 0 {
+            rows.push(("errors", self.errors.to_string()));
+        }
+        rows
+    }
+}
+
+impl EventProcessor for EventProcessorWithProgressOutput {
+    fn print_config_summary(&mut self, _config: &Config, _prompt: &str) {
+        // The condensed mode skips the config banner; the prompt is the
+        // caller's own input.
+    }
+
+    fn process_event(&mut self, event: Event) -> CodexStatus {
+        match event.msg {
+            EventMsg::TaskStarted => self.stats.turns += 1,
+            EventMsg::TaskComplete(TaskCompleteEvent { last_agent_message }) => {
+                if let Some(output_file) = self.last_message_path.as_deref() {
+                    handle_last_message_blocking(last_agent_message.as_deref(), output_file);
+                }
+                if last_agent_message.is_some() {
+                    self.last_agent_message = last_agent_message;
+                }
+                if self.stop_on_task_complete {
+                    return CodexStatus::InitiateShutdown;
+                }
+            }
+            EventMsg::AgentMessage(AgentMessageEvent { message }) => {
+                self.last_agent_message = Some(message);
+            }
+            EventMsg::TokenCount(event) => {
+                if let Some(info) = event.info {
+                    self.stats.tokens = info.total_token_usage.blended_total();
+                }
+            }
+            EventMsg::ExecCommandBegin(ExecCommandBeginEvent {
+                call_id, command, ..
+            }) => {
+                self.running_commands
+                    .insert(call_id, escape_command(&command));
+            }
+            EventMsg::ExecCommandEnd(ExecCommandEndEvent {
+                call_id, exit_code, ..
+            }) => {
+                self.running_commands.remove(&call_id);
+                self.stats.commands += 1;
+                if exit_code != 0 {
+                    self.stats.failed_commands += 1;
+                }
+            }
+            EventMsg::McpToolCallEnd(_) | EventMsg::CustomToolCallEnd(_) => {
+                self.stats.tool_calls += 1;
+            }
+            EventMsg::PatchApplyEnd(PatchApplyEndEvent { success: true, .. }) => {
+                self.stats.patches += 1;
+            }
+            EventMsg::Error(ErrorEvent { message }) => {
+                // Errors are rare and important enough to surface immediately.
+                self.stats.errors += 1;
+                self.clear_status();
+                eprintln!("{} {message}", "ERROR:".style(self.red));
+            }
+            EventMsg::ShutdownComplete => {
+                self.print_final_report();
+                return CodexStatus::Shutdown;
+            }
+            _ => {}
+        }
+        self.redraw_status();
+        CodexStatus::Running
+    }
+
+    fn report_run_limit(&mut self, limit: RunLimit) {
+        self.stopped = Some(limit.describe());
+    }
+
+    fn report_interruption(&mut self, interruption: Interruption) {
+        self.clear_status();
+        self.stopped = Some(interruption.reason().to_string());
+        out_println!("{} {}", "ctrl-c:".style(self.red), interruption.describe());
+    }
+
+    /// The config summary already shows these settings.
+    fn report_environment(&mut self, _environment: &RunEnvironment) {}
+
+    fn report_image(&mut self, status: &ImageStatus) {
+        if !status.is_ok() {
+            self.clear_status();
+            out_println!("{} {}", "image failed:".style(self.red), status.describe());
+        }
+    }
+
+    fn report_failure(&mut self, failure: FailureClass) {
+        self.clear_status();
+        out_println!(
+            "{} {} (exit code {})",
+            "run failed:".style(self.red),
+            failure.reason(),
+            failure.exit_code()
+        );
+    }
+
+    fn report_cost(&mut self, report: &CostReport) {
+        self.clear_status();
+        let label = "cost";
+        out_println!(
+            "{} {}",
+            format!("{label:<10}").style(self.bold),
+            report.summary()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn status_line_and_summary_reflect_counters() {
+        let mut stats = RunStats::new();
+        stats.turns = 2;
+        stats.commands = 5;
+        stats.failed_commands = 1;
+        stats.tokens = 12_345;
+
+        let line = stats.status_line(Some("cargo test"));
+        assert!(line.starts_with("turn 2 · 00:0"), "{line}");
+        assert!(line.ends_with(" · 12,345 tokens · cargo test"), "{line}");
+
+        let rows: Vec<(&str, String)> = stats
+            .summary_rows()
+            .into_iter()
+            .filter(|(label, _)| *label != "elapsed")
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                ("turns", "2".to_string()),
+                ("tokens", "12,345".to_string()),
+                ("commands", "5 (1 failed)".to_string()),
+                ("tool calls", "0".to_string()),
+                ("patches", "0".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,208 @@
+//! Mirrors exec events as NDJSON to a socket (`--event-sink`).
+//!
+//! Every event handed to the event processor is serialized on one line and
+//! written to the sink before the processor renders it, so dashboards see the
+//! run in real time. Writes are synchronous; if the peer goes away the sink is
+//! dropped with a warning and the run carries on.
+
+use std::fmt;
+use std::io::Write;
+use std::net::TcpStream;
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use anyhow::Context;
+use code_core::config::Config;
+use code_core::protocol::Event;
+
+use crate::attachments::ImageStatus;
+use crate::cost_report::CostReport;
+use crate::event_processor::CodexStatus;
+use crate::event_processor::EventProcessor;
+use crate::exit_code::FailureClass;
+use crate::interrupt::Interruption;
+use crate::run_environment::RunEnvironment;
+use crate::run_guard::RunLimit;
+
+/// Destination parsed from `--event-sink`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EventSinkAddr {
+    /// `unix:///path/to/socket`
+    Unix(PathBuf),
+    /// `tcp://host:port`
+    Tcp(String),
+}
+
+impl FromStr for EventSinkAddr {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if let Some(path) = value.strip_prefix("unix://") {
+            if path.is_empty() {
+                return Err("unix:// sink requires a socket path".to_string());
+            }
+            return Ok(Self::Unix(PathBuf::from(path)));
+        }
+        if let Some(addr) = value.strip_prefix("tcp://") {
+            if addr
+                .rsplit_once(':')
+                .is_none_or(|(host, port)| host.is_empty() || port.parse::<u16>().is_err())
+            {
+                return Err(format!("tcp:// sink must be host:port, got `{addr}`"));
+            }
+            return Ok(Self::Tcp(addr.to_string()));
+        }
+        Err(format!(
+            "unsupported event sink `{value}`; expected unix:///path.sock or tcp://host:port"
+        ))
+    }
+}
+
+impl fmt::Display for EventSinkAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unix(path) => write!(f, "unix://{}", path.display()),
+            Self::Tcp(addr) => write!(f, "tcp://{addr}"),
+        }
+    }
+}
+
+/// Connects to `addr`, failing fast so a typo does not silently lose events.
+pub(crate) fn connect(addr: &EventSinkAddr) -> anyhow::Result<Box<dyn Write + Send>> {
+    let stream: Box<dyn Write + Send> = match addr {
+        #[cfg(unix)]
+        EventSinkAddr::Unix(path) => Box::new(UnixStream::connect(path)?),
+        #[cfg(not(unix))]
+        EventSinkAddr::Unix(_) => {
+            anyhow::bail!("unix socket event sinks are not supported on this platform")
+        }
+        EventSinkAddr::Tcp(host_port) => {
+            let stream = TcpStream::connect(host_port)?;
+            stream.set_nodelay(true)?;
+            Box::new(stream)
+        }
+    };
+    Ok(stream)
+}
+
+/// Forwards events to the wrapped processor after writing them to the sink.
+pub(crate) struct EventSinkProcessor {
+    inner: Box<dyn EventProcessor>,
+    addr: EventSinkAddr,
+    sink: Option<Box<dyn Write + Send>>,
+}
+
+impl EventSinkProcessor {
+    pub fn connect(inner: Box<dyn EventProcessor>, addr: EventSinkAddr) -> anyhow::Result<Self> {
+        let sink =
+            connect(&addr).with_context(|| format!("failed to connect event sink {addr}"))?;
+        Ok(Self {
+            inner,
+            addr,
+            sink: Some(sink),
+        })
+    }
+
+    fn mirror(&mut self, event: &Event) {
+        let Some(sink) = self.sink.as_mut() else {
+            return;
+        };
+        let mut line = match serde_json::to_vec(event) {
+            Ok(line) => line,
+            Err(err) => {
+                tracing::warn!("failed to serialize event for sink: {err}");
+                return;
+            }
+        };
+        line.push(b'\n');
+        if let Err(err) = sink.write_all(&line).and_then(|()| sink.flush()) {
+            eprintln!(
+                "event sink {} disconnected ({err}); no further events will be mirrored",
+                self.addr
+            );
+            self.sink = None;
+        }
+    }
+}
+
+impl EventProcessor for EventSinkProcessor {
+    fn print_config_summary(&mut self, config: &Config, prompt: &str) {
+        self.inner.print_config_summary(config, prompt);
+    }
+
+    fn process_event(&mut self, event: Event) -> CodexStatus {
+        self.mirror(&event);
+        self.inner.process_event(event)
+    }
+
+    fn report_run_limit(&mut self, limit: RunLimit) {
+        self.inner.report_run_limit(limit);
+    }
+
+    fn report_interruption(&mut self, interruption: Interruption) {
+        self.inner.report_interruption(interruption);
+    }
+
+    fn report_environment(&mut self, environment: &RunEnvironment) {
+        self.inner.report_environment(environment);
+    }
+
+    fn report_image(&mut self, status: &ImageStatus) {
+        self.inner.report_image(status);
+    }
+
+    fn report_failure(&mut self, failure: FailureClass) {
+        self.inner.report_failure(failure);
+    }
+
+    fn report_cost(&mut self, report: &CostReport) {
+        self.inner.report_cost(report);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event_processor::NullProcessor;
+    use code_core::protocol::EventMsg;
+    use pretty_assertions::assert_eq;
+    use std::io::BufRead;
+    use std::io::BufReader;
+    use std::net::TcpListener;
+
+    #[test]
+    fn parses_sink_addresses() {
+        assert_eq!(
+            "unix:///tmp/code.sock".parse::<EventSinkAddr>(),
+            Ok(EventSinkAddr::Unix(PathBuf::from("/tmp/code.sock")))
+        );
+        assert_eq!(
+            "tcp://127.0.0.1:9000".parse::<EventSinkAddr>(),
+            Ok(EventSinkAddr::Tcp("127.0.0.1:9000".to_string()))
+        );
+        assert!("tcp://localhost".parse::<EventSinkAddr>().is_err());
+        assert!("http://localhost:80".parse::<EventSinkAddr>().is_err());
+    }
+
+    #[test]
+    fn mirrors_events_as_ndjson_over_tcp() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = EventSinkAddr::Tcp(listener.local_addr().unwrap().to_string());
+        let mut processor = EventSinkProcessor::connect(Box::new(NullProcessor), addr).unwrap();
+        let (peer, _) = listener.accept().unwrap();
+
+        processor.process_event(Event {
+            id: "1".to_string(),
+            event_seq: 0,
+            msg: EventMsg::TaskStarted,
+            order: None,
+        });
+
+        let mut line = String::new();
+        BufReader::new(peer).read_line(&mut line).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(value["msg"]["type"], "task_started");
+    }
+}
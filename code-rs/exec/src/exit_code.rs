@@ -0,0 +1,132 @@
+//! Stable process exit codes for `code exec`, one per failure class.
+//!
+//! Anything not listed here (bad flags, config errors, I/O failures) keeps
+//! exiting with `1`.
+
+use code_core::protocol::EventMsg;
+use code_core::protocol::ExecCommandEndEvent;
+
+use crate::run_guard::RunLimit;
+
+/// Why a run failed. Variants are ordered by precedence: when several
+/// classes are observed, the greatest one decides the exit code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum FailureClass {
+    /// The session reported an error, typically from the model provider.
+    Model,
+    /// A command was blocked by the sandbox.
+    SandboxDenied,
+    /// The Auto Drive coordinator finished with a `failed` status.
+    AutoDriveFailed,
+    /// The final message did not satisfy `--output-schema`.
+    Schema,
+    /// `--timeout` or `--max-turns` stopped the run.
+    RunLimit,
+}
+
+impl FailureClass {
+    pub fn exit_code(self) -> i32 {
+        match self {
+            FailureClass::Model => 10,
+            FailureClass::SandboxDenied => 11,
+            FailureClass::RunLimit => 12,
+            FailureClass::Schema => 13,
+            FailureClass::AutoDriveFailed => 14,
+        }
+    }
+
+    /// Stable machine-readable reason used in JSON output.
+    pub fn reason(self) -> &'static str {
+        match self {
+            FailureClass::Model => "model_error",
+            FailureClass::SandboxDenied => "sandbox_denied",
+            FailureClass::RunLimit => "run_limit",
+            FailureClass::Schema => "schema_validation",
+            FailureClass::AutoDriveFailed => "auto_drive_failed",
+        }
+    }
+}
+
+impl From<RunLimit> for FailureClass {
+    fn from(_: RunLimit) -> Self {
+        FailureClass::RunLimit
+    }
+}
+
+/// Accumulates failure classes observed while a run is in progress.
+#[derive(Debug, Default)]
+pub(crate) struct ExitTracker {
+    failure: Option<FailureClass>,
+}
+
+impl ExitTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, class: FailureClass) {
+        self.failure = self.failure.max(Some(class));
+    }
+
+    /// Classifies a session event, recording any failure it signals. Only
+    /// commands core reports as blocked by the sandbox count as denials; the
+    /// exit status alone says nothing about why a command failed.
+    pub fn observe(&mut self, msg: &EventMsg) {
+        match msg {
+            EventMsg::Error(_) => self.record(FailureClass::Model),
+            EventMsg::ExecCommandEnd(ExecCommandEndEvent {
+                sandbox_denied: true,
+                ..
+            }) => self.record(FailureClass::SandboxDenied),
+            _ => {}
+        }
+    }
+
+    pub fn failure(&self) -> Option<FailureClass> {
+        self.failure
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use code_core::protocol::ErrorEvent;
+    use pretty_assertions::assert_eq;
+    use std::time::Duration;
+
+    fn exec_end(exit_code: i32, sandbox_denied: bool) -> EventMsg {
+        EventMsg::ExecCommandEnd(ExecCommandEndEvent {
+            call_id: "call-1".to_string(),
+            stdout: String::new(),
+            stderr: String::new(),
+            exit_code,
+            duration: Duration::ZERO,
+            sandbox_denied,
+        })
+    }
+
+    #[test]
+    fn highest_precedence_failure_decides_exit_code() {
+        let mut tracker = ExitTracker::new();
+        assert_eq!(tracker.failure(), None);
+
+        tracker.observe(&EventMsg::Error(ErrorEvent {
+            message: "stream disconnected".to_string(),
+        }));
+        assert_eq!(tracker.failure().map(FailureClass::exit_code), Some(10));
+
+        tracker.observe(&exec_end(1, true));
+        assert_eq!(tracker.failure().map(FailureClass::exit_code), Some(11));
+
+        tracker.record(FailureClass::RunLimit);
+        tracker.record(FailureClass::Schema);
+        assert_eq!(tracker.failure().map(FailureClass::exit_code), Some(12));
+    }
+
+    #[test]
+    fn commands_exiting_126_on_their_own_are_not_sandbox_denials() {
+        let mut tracker = ExitTracker::new();
+        tracker.observe(&exec_end(126, false));
+        assert_eq!(tracker.failure(), None);
+    }
+}
@@ -4,7 +4,7 @@ use std::sync::atomic::AtomicU64;
 
 use crate::event_processor::CodexStatus;
 use crate::event_processor::EventProcessor;
-use crate::event_processor::handle_last_message;
+use crate::event_processor::handle_last_message_blocking;
 use crate::exec_events::AssistantMessageItem;
 use crate::exec_events::CommandExecutionItem;
 use crate::exec_events::CommandExecutionStatus;
@@ -433,7 +433,7 @@ impl EventProcessor for ExperimentalEventProcessorWithJsonOutput {
         for conv_event in aggregated {
             match serde_json::to_string(&conv_event) {
                 Ok(line) => {
-                    println!("{line}");
+                    out_println!("{line}");
                 }
                 Err(e) => {
                     error!("Failed to serialize event: {e:?}");
@@ -445,7 +445,7 @@ impl EventProcessor for ExperimentalEventProcessorWithJsonOutput {
 
         if let EventMsg::TaskComplete(TaskCompleteEvent { last_agent_message }) = msg {
             if let Some(output_file) = self.last_message_path.as_deref() {
-                handle_last_message(last_agent_message.as_deref(), output_file);
+                handle_last_message_blocking(last_agent_message.as_deref(), output_file);
             }
             CodexStatus::InitiateShutdown
         } else {
@@ -0,0 +1,197 @@
+//! Continue a finished exec run in the interactive TUI (`--handoff-to-tui`).
+//!
+//! When the run needs a person (it failed, for example on a sandbox denial,
+//! or its final message ends with a question), a resume marker is written
+//! under `<code_home>/handoff/` and the `resume` command for the same session
+//! is either printed or launched. The TUI resumes from the session's rollout,
+//! so the full conversation carries over. Runs that finish cleanly are left
+//! alone.
+
+use std::io::IsTerminal;
+use std::path::Path;
+use std::path::PathBuf;
+
+use code_core::config::Config;
+use serde::Serialize;
+
+use crate::cli::HandoffMode;
+use crate::exit_code::FailureClass;
+
+/// Written to `<code_home>/handoff/<session_id>.json` for scripts and editors
+/// that want to offer "continue interactively".
+#[derive(Debug, Serialize)]
+struct ResumeMarker<'a> {
+    session_id: &'a str,
+    cwd: &'a Path,
+    /// `open_questions`, or the failure reason from [`FailureClass::reason`].
+    reason: &'a str,
+    command: String,
+}
+
+pub(crate) struct Handoff {
+    mode: HandoffMode,
+    session_id: String,
+    code_home: PathBuf,
+    cwd: PathBuf,
+}
+
+impl Handoff {
+    pub fn new(mode: HandoffMode, config: &Config, session_id: String) -> Self {
+        Self {
+            mode,
+            session_id,
+            code_home: config.code_home.clone(),
+            cwd: config.cwd.clone(),
+        }
+    }
+
+    /// Command that reopens this session in the TUI.
+    fn command(&self) -> Vec<String> {
+        vec![
+            resume_program(),
+            "resume".to_string(),
+            self.session_id.clone(),
+        ]
+    }
+
+    /// Records the resume marker, then prints or launches the resume command,
+    /// when [`handoff_reason`] says the run needs a person.
+    pub fn run(&self, failure: Option<FailureClass>, final_message: Option<&str>) {
+        let Some(reason) = handoff_reason(failure, final_message) else {
+            return;
+        };
+        let command = self.command().join(" ");
+        match self.write_marker(reason, &command) {
+            Ok(path) => eprintln!("[handoff] resume marker written to {}", path.display()),
+            Err(err) => eprintln!("[handoff] failed to write resume marker: {err}"),
+        }
+
+        let interactive = std::io::stdin().is_terminal() && std::io::stdout().is_terminal();
+        if self.mode == HandoffMode::Launch && interactive {
+            let argv = self.command();
+            let [program, args @ ..] = argv.as_slice() else {
+                return;
+            };
+            match std::process::Command::new(program)
+                .args(args)
+                .current_dir(&self.cwd)
+                .status()
+            {
+                Ok(_) => return,
+                Err(err) => eprintln!("[handoff] failed to launch `{command}`: {err}"),
+            }
+        } else if self.mode == HandoffMode::Launch {
+            eprintln!("[handoff] not launching the TUI without an interactive terminal");
+        }
+        eprintln!("[handoff] continue interactively with: {command}");
+    }
+
+    fn write_marker(&self, reason: &str, command: &str) -> std::io::Result<PathBuf> {
+        let dir = self.code_home.join("handoff");
+        std::fs::create_dir_all(&dir)?;
+        let path = dir.join(format!("{}.json", self.session_id));
+        let marker = ResumeMarker {
+            session_id: &self.session_id,
+            cwd: &self.cwd,
+            reason,
+            command: command.to_string(),
+        };
+        std::fs::write(&path, serde_json::to_vec_pretty(&marker)?)?;
+        Ok(path)
+    }
+}
+
+/// Why the run should continue interactively: its failure reason, or
+/// `open_questions` when the final message ends with a question. `None` for
+/// runs that finished with nothing left to answer.
+fn handoff_reason(
+    failure: Option<FailureClass>,
+    final_message: Option<&str>,
+) -> Option<&'static str> {
+    if let Some(failure) = failure {
+        return Some(failure.reason());
+    }
+    let last_line = final_message?
+        .lines()
+        .rev()
+        .map(str::trim)
+        .find(|line| !line.is_empty())?;
+    last_line.ends_with(['?', '？']).then_some("open_questions")
+}
+
+/// Name of the multitool binary, matching how the TUI prints its own resume
+/// hint.
+fn resume_program() -> String {
+    let invoked = std::env::args()
+        .next()
+        .and_then(|arg0| {
+            Path::new(&arg0)
+                .file_name()
+                .and_then(|name| name.to_str())
+                .map(str::to_string)
+        })
+        .unwrap_or_default();
+    if invoked.eq_ignore_ascii_case("coder") {
+        "coder".to_string()
+    } else {
+        "code".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn marker_records_session_reason_and_command() {
+        let dir = tempfile::tempdir().unwrap();
+        let handoff = Handoff {
+            mode: HandoffMode::Print,
+            session_id: "abc".to_string(),
+            code_home: dir.path().to_path_buf(),
+            cwd: PathBuf::from("/repo"),
+        };
+
+        let path = handoff
+            .write_marker(FailureClass::SandboxDenied.reason(), "code resume abc")
+            .unwrap();
+
+        assert_eq!(path, dir.path().join("handoff").join("abc.json"));
+        let marker: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(path).unwrap()).unwrap();
+        assert_eq!(
+            marker,
+            serde_json::json!({
+                "session_id": "abc",
+                "cwd": "/repo",
+                "reason": "sandbox_denied",
+                "command": "code resume abc",
+            })
+        );
+    }
+
+    #[test]
+    fn hands_off_failed_runs_and_open_questions() {
+        assert_eq!(
+            handoff_reason(Some(FailureClass::SandboxDenied), Some("Done.")),
+            Some("sandbox_denied")
+        );
+        assert_eq!(
+            handoff_reason(
+                None,
+                Some("I updated the parser.\nShould I also bump the version?\n")
+            ),
+            Some("open_questions")
+        );
+    }
+
+    #[test]
+    fn clean_runs_are_not_handed_off() {
+        assert_eq!(handoff_reason(None, None), None);
+        assert_eq!(
+            handoff_reason(None, Some("Why did it fail? A missing import.\nFixed it.")),
+            None
+        );
+    }
+}
@@ -0,0 +1,128 @@
+//! Ctrl-C handling for `code exec`.
+//!
+//! The first press interrupts the running turn and lets the session shut
+//! down cleanly, so whatever the agent produced so far is still printed. A
+//! second press within [`FORCE_KILL_WINDOW`] exits immediately.
+//!
+//! On Unix, SIGUSR1 and SIGUSR2 pause and resume an Auto Drive run without
+//! ending it; see [`forward_pause_signals`].
+
+use std::time::Duration;
+
+use code_auto_drive_core::AutoCoordinatorHandle;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+/// Second Ctrl-C within this window of the first forces an exit.
+pub(crate) const FORCE_KILL_WINDOW: Duration = Duration::from_secs(2);
+
+/// Exit status after a forced exit, matching shells' 128 + SIGINT.
+pub(crate) const FORCE_KILL_EXIT_CODE: i32 = 130;
+
+/// What a Ctrl-C press did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Interruption {
+    /// The current turn was interrupted; the run is shutting down.
+    Interrupted,
+    /// Pressed again within the window; the process exits without waiting.
+    ForceKilled,
+}
+
+impl Interruption {
+    /// Stable machine-readable event type used in JSON output.
+    pub fn reason(self) -> &'static str {
+        match self {
+            Interruption::Interrupted => "interrupted",
+            Interruption::ForceKilled => "force_killed",
+        }
+    }
+
+    pub fn describe(self) -> String {
+        match self {
+            Interruption::Interrupted => format!(
+                "interrupted; finishing the current step (press Ctrl-C again within {}s to force quit)",
+                FORCE_KILL_WINDOW.as_secs()
+            ),
+            Interruption::ForceKilled => "force quit".to_string(),
+        }
+    }
+}
+
+/// Classifies Ctrl-C presses.
+#[derive(Debug, Default)]
+pub(crate) struct CtrlC {
+    last_press: Option<Instant>,
+}
+
+impl CtrlC {
+    pub fn press(&mut self, now: Instant) -> Interruption {
+        match self.last_press {
+            Some(last) if now.duration_since(last) <= FORCE_KILL_WINDOW => {
+                Interruption::ForceKilled
+            }
+            _ => {
+                self.last_press = Some(now);
+                Interruption::Interrupted
+            }
+        }
+    }
+}
+
+/// Forwards SIGUSR1 to the coordinator as `Pause` and SIGUSR2 as `Resume`.
+/// The turn in flight finishes either way; abort the task when the run ends.
+#[cfg(unix)]
+pub(crate) fn forward_pause_signals(handle: AutoCoordinatorHandle) -> Option<JoinHandle<()>> {
+    use code_auto_drive_core::AutoCoordinatorCommand;
+    use tokio::signal::unix::SignalKind;
+    use tokio::signal::unix::signal;
+
+    let (mut pause, mut resume) = match (
+        signal(SignalKind::user_defined1()),
+        signal(SignalKind::user_defined2()),
+    ) {
+        (Ok(pause), Ok(resume)) => (pause, resume),
+        (Err(err), _) | (_, Err(err)) => {
+            tracing::warn!("failed to install pause/resume signal handlers: {err}");
+            return None;
+        }
+    };
+    Some(tokio::spawn(async move {
+        loop {
+            let command = tokio::select! {
+                Some(()) = pause.recv() => AutoCoordinatorCommand::Pause,
+                Some(()) = resume.recv() => AutoCoordinatorCommand::Resume,
+                else => break,
+            };
+            if handle.send(command).is_err() {
+                break;
+            }
+        }
+    }))
+}
+
+#[cfg(not(unix))]
+pub(crate) fn forward_pause_signals(_handle: AutoCoordinatorHandle) -> Option<JoinHandle<()>> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn second_press_within_window_forces_exit() {
+        let start = Instant::now();
+        let mut ctrl_c = CtrlC::default();
+
+        assert_eq!(ctrl_c.press(start), Interruption::Interrupted);
+        assert_eq!(
+            ctrl_c.press(start + Duration::from_secs(3)),
+            Interruption::Interrupted
+        );
+        assert_eq!(
+            ctrl_c.press(start + Duration::from_secs(4)),
+            Interruption::ForceKilled
+        );
+    }
+}
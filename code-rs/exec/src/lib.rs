@@ -1,19 +1,81 @@
+#[macro_use]
+mod tee;
+
+mod attachments;
+mod auto_checkpoint;
+mod auto_plan;
+mod auto_replay;
+mod auto_schedule;
+mod batch;
 mod cli;
+mod cli_workers;
+mod completions;
+mod cost_report;
+mod ephemeral_worktree;
 mod event_processor;
 mod event_processor_with_human_output;
 mod event_processor_with_json_output;
+mod event_processor_with_junit_output;
+mod event_processor_with_progress_output;
+mod event_sink;
+mod exit_code;
+mod handoff;
+mod interrupt;
+mod output_schema;
+mod prompt_template;
+mod provider_batch;
+mod replay;
+mod review_reply;
+mod rollout_replay;
+mod run_environment;
+mod run_guard;
+mod transcript;
+mod usage_stats;
+mod watch;
 
 pub use cli::Cli;
+use code_auto_drive_core::AttributedUsage;
 use code_auto_drive_core::AutoCoordinatorCommand;
 use code_auto_drive_core::AutoCoordinatorEvent;
 use code_auto_drive_core::AutoCoordinatorEventSender;
+use code_auto_drive_core::AutoCoordinatorHandle;
 use code_auto_drive_core::AutoCoordinatorStatus;
 use code_auto_drive_core::AutoDriveHistory;
 use code_auto_drive_core::AutoTurnAgentsAction;
 use code_auto_drive_core::AutoTurnAgentsTiming;
 use code_auto_drive_core::AutoTurnCliAction;
+use code_auto_drive_core::CompactionEstimate;
+use code_auto_drive_core::MIN_COMPACT_THRESHOLD;
 use code_auto_drive_core::MODEL_SLUG;
+use code_auto_drive_core::UsageSource;
+use code_auto_drive_core::agent_prompt_hash;
+use code_auto_drive_core::audit::AuditLogger;
+use code_auto_drive_core::audit::AuditOperation;
+use code_auto_drive_core::audit::AuditOutcome;
+use code_auto_drive_core::audit::AuditSummary;
+use code_auto_drive_core::audit::ChainVerification;
+use code_auto_drive_core::audit::TurnPrivileges;
+use code_auto_drive_core::audit::configured_audit_log_path;
+use code_auto_drive_core::audit::dispatch_agent_id;
+use code_auto_drive_core::audit::verify_audit_log;
+use code_auto_drive_core::backlog::BacklogReport;
+use code_auto_drive_core::backlog::GoalBacklog;
+use code_auto_drive_core::backlog::GoalResult;
+use code_auto_drive_core::backlog::GoalStatus;
+use code_auto_drive_core::backlog::carry_forward_context;
+use code_auto_drive_core::budget::BudgetSnapshot;
+use code_auto_drive_core::checkpoint::default_checkpoint_dir;
+use code_auto_drive_core::commit_message;
+use code_auto_drive_core::commit_message::ChangeSummary;
+use code_auto_drive_core::commit_message::CommitMessage;
+use code_auto_drive_core::event_log;
+use code_auto_drive_core::progress_log::ProgressEntry;
+use code_auto_drive_core::progress_log::ProgressLogger;
+use code_auto_drive_core::run_report::RunOutcome;
+use code_auto_drive_core::run_report::RunReport;
+use code_auto_drive_core::run_report::default_report_dir;
 use code_auto_drive_core::start_auto_coordinator;
+use code_auto_drive_core::turn_changes::TurnChangeTracker;
 use code_core::AuthManager;
 use code_core::BUILT_IN_OSS_MODEL_PROVIDER_ID;
 use code_core::CodexConversation;
@@ -22,6 +84,7 @@ use code_core::NewConversation;
 use code_core::config::Config;
 use code_core::config::ConfigOverrides;
 use code_core::config::set_default_originator;
+use code_core::config_types::ResponseCacheMode;
 use code_core::git_info::get_git_repo_root;
 use code_core::protocol::AskForApproval;
 use code_core::protocol::Event;
@@ -29,6 +92,9 @@ use code_core::protocol::EventMsg;
 use code_core::protocol::InputItem;
 use code_core::protocol::Op;
 use code_core::protocol::TaskCompleteEvent;
+use code_core::protocol::TokenUsage;
+use code_core::request_tap::RequestTap;
+use code_core::request_tap::is_dry_run_stop;
 use code_ollama::DEFAULT_OSS_MODEL;
 use code_protocol::config_types::SandboxMode;
 use code_protocol::models::ContentItem;
@@ -37,9 +103,12 @@ use code_protocol::protocol::SessionSource;
 use event_processor::handle_last_message;
 use event_processor_with_human_output::EventProcessorWithHumanOutput;
 use event_processor_with_json_output::EventProcessorWithJsonOutput;
+use event_processor_with_junit_output::EventProcessorWithJUnitOutput;
+use event_processor_with_progress_output::EventProcessorWithProgressOutput;
 use serde_json::Value;
 use std::io::IsTerminal;
 use std::io::Read;
+use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
 use supports_color::Stream;
@@ -50,9 +119,40 @@ use tracing_subscriber::EnvFilter;
 use tracing_subscriber::filter::filter_fn;
 use tracing_subscriber::prelude::*;
 
+use crate::auto_checkpoint::AutoCheckpointer;
+use crate::auto_plan::AutoPlan;
+use crate::auto_plan::SIMULATED_CLI_REPLY;
+use crate::cli::AutoArgs;
+use crate::cli::AutoAuditArgs;
+use crate::cli::AutoAuditCommand;
+use crate::cli::AutoCommand;
+use crate::cli::CacheMode;
 use crate::cli::Command as ExecCommand;
+use crate::cli::InterventionPolicy;
+use crate::cli::OutputFormat;
+use crate::cli::PrintPromptFormat;
+use crate::cli::ResumeArgs;
+use crate::cli::WriteApprovalPolicy;
+use crate::cli_workers::CliWorkerPool;
+use crate::cli_workers::workstream_results_section;
+use crate::cost_report::RunUsage;
+use crate::cost_report::UsageBaseline;
+use crate::cost_report::UsageRole;
+use crate::cost_report::usage_since;
+use crate::ephemeral_worktree::EphemeralWorktree;
 use crate::event_processor::CodexStatus;
 use crate::event_processor::EventProcessor;
+use crate::exit_code::ExitTracker;
+use crate::exit_code::FailureClass;
+use crate::handoff::Handoff;
+use crate::interrupt::CtrlC;
+use crate::interrupt::Interruption;
+use crate::interrupt::forward_pause_signals;
+use crate::run_environment::RunEnvironment;
+use crate::run_guard::RunGuard;
+use crate::run_guard::RunLimit;
+use crate::usage_stats::RunKind;
+use crate::usage_stats::StatsRecorder;
 use anyhow::Context;
 use code_core::SessionCatalog;
 use code_core::SessionQuery;
@@ -68,33 +168,212 @@ pub async fn run_main(cli: Cli, code_linux_sandbox_exe: Option<PathBuf>) -> anyh
     let Cli {
         command,
         images,
+        files,
         model: model_cli_arg,
         oss,
         config_profile,
         full_auto,
         dangerously_bypass_approvals_and_sandbox,
         cwd,
+        ephemeral_worktree,
         skip_git_repo_check,
         color,
+        timestamps,
+        progress,
         last_message_file,
         json: json_mode,
+        output_format,
+        event_sink,
+        transcript_file,
+        tee,
+        print_prompt,
+        template,
+        provider_batch,
+        vars: template_vars,
+        watch: watch_globs,
+        dry_run,
+        timeout,
+        max_turns,
         sandbox_mode: sandbox_mode_cli_arg,
         prompt,
         output_schema: output_schema_path,
+        output_schema_retries,
+        cache,
         include_plan_tool,
         config_overrides,
         auto_drive,
+        auto_backlog,
+        auto_confirm_first_write,
+        debug_coordinator,
+        record_decisions,
+        replay_decisions,
+        auto_plan_only,
+        on_intervention,
+        approve_writes,
+        schedule,
+        checkpoint_every,
+        checkpoint_dir,
+        auto_resume,
+        max_tokens,
+        max_cost,
+        max_duration,
+        compact_threshold,
+        generate_commit_message,
+        no_test_suffix,
+        goal_suffix_file,
+        handoff_to_tui,
         ..
     } = cli;
 
+    if template.is_some() && !matches!(command, None | Some(ExecCommand::Resume(_))) {
+        eprintln!("--template only applies to a new session or `resume`");
+        std::process::exit(1);
+    }
+
+    let command = match command {
+        Some(ExecCommand::Completions(args)) => {
+            completions::print_completions(args.shell);
+            return Ok(());
+        }
+        Some(ExecCommand::Man(args)) => return completions::write_man(args.output_dir),
+        Some(ExecCommand::AutoAudit(AutoAuditArgs {
+            command: AutoAuditCommand::Verify(args),
+        })) => return verify_audit_file(&args.file),
+        Some(ExecCommand::Batch(args)) => {
+            let cli_kv_overrides = match config_overrides.parse_overrides() {
+                Ok(v) => v,
+                Err(e) => {
+                    eprintln!("Error parsing -c overrides: {e}");
+                    std::process::exit(1);
+                }
+            };
+            let sandbox_mode = if full_auto {
+                Some(SandboxMode::WorkspaceWrite)
+            } else if dangerously_bypass_approvals_and_sandbox {
+                Some(SandboxMode::DangerFullAccess)
+            } else {
+                sandbox_mode_cli_arg.map(Into::<SandboxMode>::into)
+            };
+            let defaults = batch::BatchDefaults {
+                model: model_cli_arg.or_else(|| oss.then(|| DEFAULT_OSS_MODEL.to_owned())),
+                model_provider: oss.then(|| BUILT_IN_OSS_MODEL_PROVIDER_ID.to_string()),
+                config_profile,
+                sandbox_mode,
+                cwd,
+                code_linux_sandbox_exe,
+                include_plan_tool,
+                skip_git_repo_check,
+                cli_kv_overrides,
+            };
+            return batch::run_batch(args, defaults).await;
+        }
+        other => other,
+    };
+    let command = match (auto_resume, command) {
+        (Some(session_id), None) => Some(ExecCommand::Resume(ResumeArgs {
+            session_id: Some(session_id),
+            last: false,
+            fork: false,
+            from_checkpoint: true,
+            prompt: None,
+        })),
+        (Some(_), Some(_)) => {
+            eprintln!("--auto-resume cannot be combined with a subcommand.");
+            std::process::exit(1);
+        }
+        (None, command) => command,
+    };
+
+    let review_thread = match &command {
+        Some(ExecCommand::ReviewReply(args)) => {
+            let dir = match cwd.clone() {
+                Some(dir) => dir,
+                None => std::env::current_dir()?,
+            };
+            match review_reply::fetch_thread(args, &dir).await {
+                Ok(thread) => Some(thread),
+                Err(err) => {
+                    eprintln!("{err:#}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        _ => None,
+    };
+    let post_review_reply = matches!(&command, Some(ExecCommand::ReviewReply(args)) if args.post);
+
     // Determine the prompt source (parent or subcommand) and read from stdin if needed.
     let prompt_arg = match &command {
         // Allow prompt before the subcommand by falling back to the parent-level prompt
         // when the Resume subcommand did not provide its own prompt.
+        // Checkpoint resumes take the goal from the checkpoint instead.
+        Some(ExecCommand::Resume(args)) if args.from_checkpoint => Some(String::new()),
         Some(ExecCommand::Resume(args)) => args.prompt.clone().or(prompt),
-        None => prompt,
+        Some(ExecCommand::Batch(_)) | None => prompt,
+        Some(ExecCommand::ReviewReply(_)) => review_thread.as_ref().map(review_reply::build_prompt),
+        // Replays render recorded events and stats read the local aggregate,
+        // so neither sends a prompt; completions and man pages have already
+        // returned.
+        Some(ExecCommand::Auto(_))
+        | Some(ExecCommand::Replay(_))
+        | Some(ExecCommand::Stats(_))
+        | Some(ExecCommand::Completions(_))
+        | Some(ExecCommand::Man(_))
+        | Some(ExecCommand::AutoAudit(_))
+        | Some(ExecCommand::Scheduler(_)) => Some(String::new()),
     };
 
+    // `--batch` takes its prompts from the manifest.
+    let prompt_arg = if provider_batch.is_some() && command.is_none() {
+        Some(String::new())
+    } else {
+        prompt_arg
+    };
+
+    let prompt_arg = match template {
+        Some(path) => {
+            if prompt_arg.is_some() {
+                eprintln!("--template cannot be combined with a PROMPT argument");
+                std::process::exit(1);
+            }
+            match prompt_template::render_file(&path, &template_vars) {
+                Ok(rendered) => Some(rendered),
+                Err(err) => {
+                    eprintln!("{err:#}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        _ => prompt_arg,
+    };
+
+    let backlog = match auto_backlog.as_deref() {
+        Some(path) if command.is_none() => match GoalBacklog::load(path) {
+            Ok(backlog) => Some(backlog),
+            Err(err) => {
+                eprintln!("{err:#}");
+                std::process::exit(1);
+            }
+        },
+        Some(_) => {
+            eprintln!("--auto-backlog cannot be combined with a subcommand");
+            std::process::exit(1);
+        }
+        None => None,
+    };
+    // The first goal stands in for the prompt until the backlog takes over.
+    let auto_drive = auto_drive || backlog.is_some() || auto_plan_only.is_some();
+    let prompt_arg = match backlog.as_ref() {
+        Some(backlog) => backlog.goals.first().map(|goal| goal.goal.clone()),
+        None => prompt_arg,
+    };
+
+    let prompt_from_stdin = prompt_arg.as_deref().is_none_or(|p| p == "-");
+    if let Err(err) = attachments::check_stdin_usage(&images, &files, prompt_from_stdin) {
+        eprintln!("{err}");
+        std::process::exit(1);
+    }
+
     let prompt = match prompt_arg {
         Some(p) if p != "-" => p,
         // Either `-` was passed or no positional arg.
@@ -160,18 +439,33 @@ pub async fn run_main(cli: Cli, code_linux_sandbox_exe: Option<PathBuf>) -> anyh
         eprintln!("Auto Drive requires a goal. Provide one after /auto or --auto.");
         std::process::exit(1);
     }
-
-    if let Some(goal) = auto_drive_goal.as_mut() {
-        *goal = append_auto_drive_test_suffix(goal);
+    if schedule.is_some() && auto_drive_goal.is_none() {
+        eprintln!("--schedule requires Auto Drive (--auto or --auto-backlog).");
+        std::process::exit(1);
     }
 
-    let summary_prompt = if let Some(goal) = auto_drive_goal.as_ref() {
-        format!("/auto {goal}")
+    let goal_suffix_override = if no_test_suffix {
+        Some(String::new())
+    } else if let Some(path) = goal_suffix_file.as_deref() {
+        match std::fs::read_to_string(path) {
+            Ok(contents) => Some(contents),
+            Err(err) => {
+                eprintln!("Failed to read goal suffix file {}: {err}", path.display());
+                std::process::exit(1);
+            }
+        }
     } else {
-        prompt.clone()
+        None
     };
 
-    let _output_schema = load_output_schema(output_schema_path);
+    let output_schema = match review_thread.as_ref() {
+        Some(_) if output_schema_path.is_some() => {
+            eprintln!("--output-schema cannot be combined with review-reply");
+            std::process::exit(1);
+        }
+        Some(_) => Some(review_reply::output_schema()),
+        None => load_output_schema(output_schema_path),
+    };
 
     let (stdout_with_ansi, stderr_with_ansi) = match color {
         cli::Color::Always => (true, true),
@@ -190,7 +484,11 @@ pub async fn run_main(cli: Cli, code_linux_sandbox_exe: Option<PathBuf>) -> anyh
         .or_else(|_| EnvFilter::try_new(default_level))
         .unwrap_or_else(|_| EnvFilter::new(default_level));
 
-    let sandbox_mode = if full_auto {
+    // Review replies only read the checkout, whatever the sandbox flags say;
+    // the read-only policy also denies network access.
+    let sandbox_mode = if review_thread.is_some() {
+        Some(SandboxMode::ReadOnly)
+    } else if full_auto {
         Some(SandboxMode::WorkspaceWrite)
     } else if dangerously_bypass_approvals_and_sandbox {
         Some(SandboxMode::DangerFullAccess)
@@ -215,6 +513,30 @@ pub async fn run_main(cli: Cli, code_linux_sandbox_exe: Option<PathBuf>) -> anyh
         None // No specific model provider override.
     };
 
+    let mut ephemeral_worktree = if ephemeral_worktree {
+        let source = match cwd.clone() {
+            Some(dir) => dir,
+            None => std::env::current_dir()?,
+        };
+        let source = source.canonicalize().unwrap_or(source);
+        match EphemeralWorktree::create(&source).await {
+            Ok(worktree) => {
+                eprintln!("[worktree] running in {}", worktree.cwd().display());
+                Some(worktree)
+            }
+            Err(err) => {
+                eprintln!("{err:#}");
+                std::process::exit(1);
+            }
+        }
+    } else {
+        None
+    };
+    let cwd = match ephemeral_worktree.as_ref() {
+        Some(worktree) => Some(worktree.cwd().to_path_buf()),
+        None => cwd,
+    };
+
     // Load configuration and determine approval policy
     let overrides = ConfigOverrides {
         model,
@@ -234,7 +556,7 @@ pub async fn run_main(cli: Cli, code_linux_sandbox_exe: Option<PathBuf>) -> anyh
         disable_response_storage: None,
         debug: None,
         show_raw_agent_reasoning: oss.then_some(true),
-        tools_web_search_request: None,
+        tools_web_search_request: review_thread.is_some().then_some(false),
         mcp_servers: None,
         experimental_client_tools: None,
         compact_prompt_override: None,
@@ -246,11 +568,18 @@ pub async fn run_main(cli: Cli, code_linux_sandbox_exe: Option<PathBuf>) -> anyh
         Ok(v) => v,
         Err(e) => {
             eprintln!("Error parsing -c overrides: {e}");
-            std::process::exit(1);
+            exit_removing_worktree(ephemeral_worktree, 1);
         }
     };
 
-    let config = Config::load_with_cli_overrides(cli_kv_overrides, overrides)?;
+    let mut config = Config::load_with_cli_overrides(cli_kv_overrides, overrides)?;
+    if let Some(cache) = cache {
+        config.response_cache = match cache {
+            CacheMode::Off => ResponseCacheMode::Off,
+            CacheMode::Read => ResponseCacheMode::Read,
+            CacheMode::Write => ResponseCacheMode::Write,
+        };
+    }
 
     // Build tracing/OTEL subscribers now that config is available.
     let fmt_layer = tracing_subscriber::fmt::layer()
@@ -272,18 +601,132 @@ pub async fn run_main(cli: Cli, code_linux_sandbox_exe: Option<PathBuf>) -> anyh
             .try_init(),
         None => tracing_subscriber::registry().with(fmt_layer).try_init(),
     };
+    if let Some(path) = tee.as_deref() {
+        tee::install(path)?;
+    }
+
+    let checkpoint_dir =
+        checkpoint_dir.unwrap_or_else(|| default_checkpoint_dir(&config.code_home));
+    let restored_checkpoint = match &command {
+        Some(ExecCommand::Resume(args)) if args.from_checkpoint => {
+            match auto_checkpoint::load(&checkpoint_dir, args.session_id.as_deref()) {
+                Ok(checkpoint) => {
+                    auto_drive_goal = Some(checkpoint.goal.clone());
+                    Some(checkpoint)
+                }
+                Err(err) => {
+                    eprintln!("{err:#}");
+                    exit_removing_worktree(ephemeral_worktree, 1);
+                }
+            }
+        }
+        _ => None,
+    };
+    // Replays and `stats` itself are not runs worth counting.
+    let run_stats = match &command {
+        Some(
+            ExecCommand::Auto(_)
+            | ExecCommand::Replay(_)
+            | ExecCommand::Stats(_)
+            | ExecCommand::Scheduler(_),
+        ) => None,
+        _ => {
+            let kind = if backlog.is_some() {
+                RunKind::Backlog
+            } else if auto_drive_goal.is_some() {
+                RunKind::Auto
+            } else {
+                RunKind::Prompt
+            };
+            StatsRecorder::start(&config, kind)
+        }
+    };
+    let summary_prompt = if let Some(goal) = auto_drive_goal.as_ref() {
+        format!("/auto {goal}")
+    } else {
+        prompt.clone()
+    };
+
     let stop_on_task_complete = auto_drive_goal.is_none();
-    let mut event_processor: Box<dyn EventProcessor> = if json_mode {
-        Box::new(EventProcessorWithJsonOutput::new(last_message_file.clone()))
+    let output_format = if json_mode {
+        OutputFormat::Json
     } else {
-        Box::new(EventProcessorWithHumanOutput::create_with_ansi(
+        output_format
+    };
+    let mut event_processor: Box<dyn EventProcessor> = match output_format {
+        OutputFormat::Json => {
+            Box::new(EventProcessorWithJsonOutput::new(last_message_file.clone()))
+        }
+        OutputFormat::Junit => Box::new(EventProcessorWithJUnitOutput::new(
+            last_message_file.clone(),
+        )),
+        OutputFormat::Human if progress => {
+            Box::new(EventProcessorWithProgressOutput::create_with_ansi(
+                stdout_with_ansi,
+                last_message_file.clone(),
+                stop_on_task_complete,
+            ))
+        }
+        OutputFormat::Human => Box::new(EventProcessorWithHumanOutput::create_with_ansi(
             stdout_with_ansi,
             &config,
             last_message_file.clone(),
             stop_on_task_complete,
-        ))
+            timestamps,
+        )),
+    };
+    if let Some(path) = transcript_file {
+        event_processor = Box::new(transcript::TranscriptProcessor::create(
+            event_processor,
+            path,
+        )?);
+    }
+    if let Some(addr) = event_sink {
+        event_processor = Box::new(event_sink::EventSinkProcessor::connect(
+            event_processor,
+            addr,
+        )?);
+    }
+    let run_usage = RunUsage::new(&config.model);
+    event_processor = Box::new(run_usage.track(event_processor));
+
+    if print_prompt.is_some() || dry_run {
+        config.request_tap = Some(print_prompt_tap(
+            print_prompt.unwrap_or(PrintPromptFormat::Pretty),
+            dry_run,
+        ));
+    }
+
+    let command = match command {
+        Some(ExecCommand::Auto(AutoArgs {
+            command: AutoCommand::Replay(args),
+        })) => {
+            return auto_replay::run_replay(args, &config, event_processor).await;
+        }
+        Some(ExecCommand::Replay(args)) => {
+            return rollout_replay::run_rollout_replay(args, &config, event_processor).await;
+        }
+        Some(ExecCommand::Stats(args)) => return usage_stats::run_stats(&args, &config),
+        Some(ExecCommand::Scheduler(args)) => {
+            return auto_schedule::run_scheduler(args, &config).await;
+        }
+        other => other,
     };
 
+    if command.is_none()
+        && let Some(manifest) = provider_batch.as_deref()
+    {
+        return provider_batch::run_provider_batch(manifest, &config, event_processor).await;
+    }
+
+    if command.is_none()
+        && let Some(goal) = auto_drive_goal.as_deref()
+        && let Some(when) = auto_schedule::effective_schedule(schedule, &config)
+    {
+        let stdin_prompt = prompt_from_stdin.then(|| prompt.clone());
+        return auto_schedule::queue_run(&config, goal, &when, stdin_prompt);
+    }
+
     if oss {
         code_ollama::ensure_oss_ready(&config)
             .await
@@ -293,6 +736,16 @@ pub async fn run_main(cli: Cli, code_linux_sandbox_exe: Option<PathBuf>) -> anyh
     // Print the effective configuration and prompt so users can see what Codex
     // is using.
     event_processor.print_config_summary(&config, &summary_prompt);
+    event_processor.report_environment(&RunEnvironment::capture(&config).await);
+
+    let attachments =
+        match attachments::load_attachments(images, files, event_processor.as_mut()).await {
+            Ok(items) => items,
+            Err(err) => {
+                eprintln!("{err:#}");
+                exit_removing_worktree(ephemeral_worktree, 1);
+            }
+        };
 
     let default_cwd = config.cwd.to_path_buf();
     let _default_approval_policy = config.approval_policy;
@@ -303,7 +756,7 @@ pub async fn run_main(cli: Cli, code_linux_sandbox_exe: Option<PathBuf>) -> anyh
 
     if !skip_git_repo_check && get_git_repo_root(&default_cwd).is_none() {
         eprintln!("Not inside a trusted directory and --skip-git-repo-check was not specified.");
-        std::process::exit(1);
+        exit_removing_worktree(ephemeral_worktree, 1);
     }
 
     let auth_manager = AuthManager::shared_with_mode_and_originator(
@@ -311,24 +764,46 @@ pub async fn run_main(cli: Cli, code_linux_sandbox_exe: Option<PathBuf>) -> anyh
         code_protocol::mcp_protocol::AuthMode::ApiKey,
         config.responses_originator_header.clone(),
     );
-    let conversation_manager = ConversationManager::new(auth_manager.clone(), SessionSource::Exec);
+    let conversation_manager = Arc::new(ConversationManager::new(
+        auth_manager.clone(),
+        SessionSource::Exec,
+    ));
 
     // Handle resume subcommand by resolving a rollout path and using explicit resume API.
     let NewConversation {
-        conversation_id: _,
+        conversation_id,
         conversation,
         session_configured,
-    } = if let Some(ExecCommand::Resume(args)) = command {
+    } = if let Some(ExecCommand::Resume(mut args)) = command {
+        // Continue the CLI conversation the checkpointed run was driving.
+        if let Some(checkpoint) = restored_checkpoint.as_ref() {
+            args.session_id = Some(checkpoint.session_id.clone());
+            args.last = false;
+        }
         let resume_path = resolve_resume_path(&config, &args).await?;
 
-        if let Some(path) = resume_path {
-            conversation_manager
-                .resume_conversation_from_rollout(config.clone(), path, auth_manager.clone())
-                .await?
-        } else {
-            conversation_manager
-                .new_conversation(config.clone())
-                .await?
+        match resume_path {
+            Some(path) if args.fork => {
+                let forked = conversation_manager
+                    .fork_conversation(0, config.clone(), path.clone())
+                    .await?;
+                eprintln!(
+                    "Forked {} into new session {}",
+                    path.display(),
+                    forked.conversation_id
+                );
+                forked
+            }
+            Some(path) => {
+                conversation_manager
+                    .resume_conversation_from_rollout(config.clone(), path, auth_manager.clone())
+                    .await?
+            }
+            None => {
+                conversation_manager
+                    .new_conversation(config.clone())
+                    .await?
+            }
         }
     } else {
         conversation_manager
@@ -338,22 +813,141 @@ pub async fn run_main(cli: Cli, code_linux_sandbox_exe: Option<PathBuf>) -> anyh
     event_processor.print_config_summary(&config, &summary_prompt);
     info!("Codex initialized with event: {session_configured:?}");
 
+    let mut run_guard = RunGuard::new(timeout, max_turns);
+    let handoff =
+        handoff_to_tui.map(|mode| Handoff::new(mode, &config, conversation_id.to_string()));
+
     if let Some(goal) = auto_drive_goal {
+        let mut config = config;
+        config.auto_drive.confirm_first_write = auto_confirm_first_write;
+        config.auto_drive.debug_coordinator = debug_coordinator;
+        if let Some(dir) = replay_decisions.as_deref()
+            && !dir.is_dir()
+        {
+            eprintln!("--replay-decisions: {} is not a directory.", dir.display());
+            exit_removing_worktree(ephemeral_worktree, 1);
+        }
+        config.auto_drive.record_decisions = record_decisions;
+        config.auto_drive.replay_decisions = replay_decisions;
+        let cli_workers = CliWorkerPool::new(conversation_manager.clone(), &config);
+        config.auto_drive.cli_workers = cli_workers.is_some();
+        let on_intervention = on_intervention.unwrap_or(if std::io::stdin().is_terminal() {
+            InterventionPolicy::Pause
+        } else {
+            InterventionPolicy::Fail
+        });
+        if goal_suffix_override.is_some() {
+            config.auto_drive.goal_suffix = goal_suffix_override;
+        }
+        // A restored checkpoint already carries the suffixed goal.
+        let goal = if restored_checkpoint.is_some() {
+            goal
+        } else {
+            append_goal_suffix(&goal, auto_drive_goal_suffix(&config))
+        };
+        config.auto_drive.token_budget = max_tokens.or(config.auto_drive.token_budget);
+        config.auto_drive.cost_budget_usd = max_cost.or(config.auto_drive.cost_budget_usd);
+        config.auto_drive.duration_limit_seconds =
+            max_duration.or(config.auto_drive.duration_limit_seconds);
+        if let Some(threshold) = compact_threshold {
+            if !(MIN_COMPACT_THRESHOLD..=1.0).contains(&threshold) {
+                eprintln!(
+                    "--compact-threshold must be between {MIN_COMPACT_THRESHOLD} and 1.0, got {threshold}."
+                );
+                exit_removing_worktree(ephemeral_worktree, 1);
+            }
+            config.auto_drive.compact_threshold = threshold;
+        }
+        if config.auto_drive.cost_budget_usd.is_some()
+            && !config.model_prices.contains_key(&config.model)
+        {
+            eprintln!(
+                "A cost limit needs a [model_prices] entry for {} to estimate spend.",
+                config.model
+            );
+            exit_removing_worktree(ephemeral_worktree, 1);
+        }
+        if let Some(backlog) = backlog {
+            if backlog.goals.iter().any(|goal| goal.max_cost.is_some())
+                && !config.model_prices.contains_key(&config.model)
+            {
+                eprintln!(
+                    "A cost limit needs a [model_prices] entry for {} to estimate spend.",
+                    config.model
+                );
+                exit_removing_worktree(ephemeral_worktree, 1);
+            }
+            let options = BacklogRunOptions {
+                path: auto_backlog.unwrap_or_default(),
+                checkpoint_dir,
+                checkpoint_every,
+                generate_commit_message,
+            };
+            return run_auto_backlog(
+                backlog,
+                options,
+                attachments,
+                config,
+                conversation_id.to_string(),
+                conversation,
+                event_processor,
+                last_message_file,
+                run_guard,
+                handoff,
+                ephemeral_worktree,
+                run_usage,
+                cli_workers,
+                run_stats,
+                on_intervention,
+                approve_writes,
+            )
+            .await;
+        }
+        // A plan-only history holds stub replies, so it is not worth resuming.
+        let checkpoint_every = if auto_plan_only.is_some() {
+            0
+        } else {
+            checkpoint_every
+        };
+        let checkpoints = match restored_checkpoint {
+            Some(checkpoint) => {
+                AutoCheckpointer::resume(checkpoint_dir, checkpoint_every, checkpoint)
+            }
+            None => AutoCheckpointer::start(
+                checkpoint_dir,
+                checkpoint_every,
+                &goal,
+                &conversation_id.to_string(),
+            )?,
+        };
         return run_auto_drive_session(
             goal,
-            images,
+            attachments,
             config,
             conversation,
             event_processor,
             last_message_file,
+            run_guard,
+            checkpoints,
+            handoff,
+            ephemeral_worktree,
+            run_usage,
+            cli_workers,
+            run_stats,
+            generate_commit_message,
+            on_intervention,
+            approve_writes,
+            auto_plan_only.map(AutoPlan::new),
         )
         .await;
     }
 
     let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<Event>();
+    let (interrupt_tx, mut interrupt_rx) = tokio::sync::mpsc::unbounded_channel::<Interruption>();
     {
         let conversation = conversation.clone();
         tokio::spawn(async move {
+            let mut ctrl_c = CtrlC::default();
             #[cfg(unix)]
             let mut sigterm_stream =
                 match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
@@ -379,8 +973,12 @@ pub async fn run_main(cli: Cli, code_linux_sandbox_exe: Option<PathBuf>) -> anyh
                             }
                             _ = tokio::signal::ctrl_c() => {
                                 tracing::debug!("Keyboard interrupt");
+                                let interruption = ctrl_c.press(tokio::time::Instant::now());
+                                let _ = interrupt_tx.send(interruption);
+                                if interruption == Interruption::ForceKilled {
+                                    break;
+                                }
                                 conversation.submit(Op::Interrupt).await.ok();
-                                break;
                             }
                             res = conversation.next_event() => match res {
                                 Ok(event) => {
@@ -406,8 +1004,12 @@ pub async fn run_main(cli: Cli, code_linux_sandbox_exe: Option<PathBuf>) -> anyh
                         tokio::select! {
                             _ = tokio::signal::ctrl_c() => {
                                 tracing::debug!("Keyboard interrupt");
+                                let interruption = ctrl_c.press(tokio::time::Instant::now());
+                                let _ = interrupt_tx.send(interruption);
+                                if interruption == Interruption::ForceKilled {
+                                    break;
+                                }
                                 conversation.submit(Op::Interrupt).await.ok();
-                                break;
                             }
                             res = conversation.next_event() => match res {
                                 Ok(event) => {
@@ -436,8 +1038,12 @@ pub async fn run_main(cli: Cli, code_linux_sandbox_exe: Option<PathBuf>) -> anyh
                     tokio::select! {
                         _ = tokio::signal::ctrl_c() => {
                             tracing::debug!("Keyboard interrupt");
+                            let interruption = ctrl_c.press(tokio::time::Instant::now());
+                            let _ = interrupt_tx.send(interruption);
+                            if interruption == Interruption::ForceKilled {
+                                break;
+                            }
                             conversation.submit(Op::Interrupt).await.ok();
-                            break;
                         }
                         res = conversation.next_event() => match res {
                             Ok(event) => {
@@ -472,60 +1078,177 @@ pub async fn run_main(cli: Cli, code_linux_sandbox_exe: Option<PathBuf>) -> anyh
         });
     }
 
-    // Send images first, if any.
-    if !images.is_empty() {
-        let items: Vec<InputItem> = images
-            .into_iter()
-            .map(|path| InputItem::LocalImage { path })
-            .collect();
-        let initial_images_event_id = conversation.submit(Op::UserInput { items }).await?;
-        info!("Sent images with event ID: {initial_images_event_id}");
-        while let Ok(event) = conversation.next_event().await {
-            if event.id == initial_images_event_id
-                && matches!(
-                    event.msg,
-                    EventMsg::TaskComplete(TaskCompleteEvent {
-                        last_agent_message: _,
-                    })
-                )
-            {
-                break;
-            }
-        }
-    }
+    let mut file_watcher = if watch_globs.is_empty() {
+        None
+    } else {
+        Some(watch::FileWatcher::new(&config.cwd, &watch_globs).await?)
+    };
+    let watch_prompt = file_watcher.as_ref().map(|_| prompt.clone());
 
-    // Send the prompt.
-    let items: Vec<InputItem> = vec![InputItem::Text { text: prompt }];
-    // Fallback for older core protocol: send only user input items.
+    // Send attachments together with the prompt so they share a single turn.
+    let mut items = attachments;
+    items.push(InputItem::Text { text: prompt });
     let initial_prompt_task_id = conversation.submit(Op::UserInput { items }).await?;
     info!("Sent prompt with event ID: {initial_prompt_task_id}");
 
     // Run the loop until the task is complete.
-    // Track whether a fatal error was reported by the server so we can
-    // exit with a non-zero status for automation-friendly signaling.
-    let mut error_seen = false;
-    while let Some(event) = rx.recv().await {
-        if matches!(event.msg, EventMsg::Error(_)) {
-            error_seen = true;
+    // Track failures reported by the server so we can exit with a
+    // class-specific status for automation-friendly signaling.
+    let mut exit_tracker = ExitTracker::new();
+    let mut schema_attempts = 0;
+    let mut schema_failure: Option<output_schema::SchemaValidationReport> = None;
+    let mut limit_hit: Option<RunLimit> = None;
+    let mut final_message: Option<String> = None;
+    loop {
+        let event = tokio::select! {
+            event = rx.recv() => match event {
+                Some(event) => event,
+                None => break,
+            },
+            limit = run_guard.deadline_reached() => {
+                run_guard.disarm();
+                stop_for_run_limit(&conversation, event_processor.as_mut(), limit).await;
+                exit_tracker.record(limit.into());
+                limit_hit = Some(limit);
+                continue;
+            }
+            Some(interruption) = interrupt_rx.recv() => {
+                handle_interruption(&conversation, event_processor.as_mut(), interruption, &mut ephemeral_worktree).await;
+                continue;
+            }
+        };
+        // `--dry-run` ends the first turn with a sentinel error once the
+        // request has been printed.
+        if is_dry_run_stop(&event.msg) {
+            conversation.submit(Op::Shutdown).await?;
+            return Ok(());
+        }
+        exit_tracker.observe(&event.msg);
+        if matches!(event.msg, EventMsg::TaskStarted)
+            && limit_hit.is_none()
+            && let Some(limit) = run_guard.on_turn_started()
+        {
+            stop_for_run_limit(&conversation, event_processor.as_mut(), limit).await;
+            exit_tracker.record(limit.into());
+            limit_hit = Some(limit);
+        }
+        if let EventMsg::TaskComplete(TaskCompleteEvent { last_agent_message }) = &event.msg {
+            final_message = last_agent_message.clone();
+        }
+        if let (Some(schema), EventMsg::TaskComplete(TaskCompleteEvent { last_agent_message })) =
+            (output_schema.as_ref(), &event.msg)
+        {
+            schema_attempts += 1;
+            let errors = output_schema::validate_message(schema, last_agent_message.as_deref());
+            if !errors.is_empty() {
+                if schema_attempts <= output_schema_retries {
+                    eprintln!(
+                        "Final message does not match --output-schema ({} error(s)); retrying ({schema_attempts}/{output_schema_retries}).",
+                        errors.len()
+                    );
+                    let items = vec![InputItem::Text {
+                        text: output_schema::retry_prompt(&errors),
+                    }];
+                    conversation.submit(Op::UserInput { items }).await?;
+                    continue;
+                }
+                schema_failure = Some(output_schema::SchemaValidationReport::new(
+                    schema_attempts,
+                    errors,
+                ));
+            }
         }
         let shutdown: CodexStatus = event_processor.process_event(event);
         match shutdown {
             CodexStatus::Running => continue,
             CodexStatus::InitiateShutdown => {
-                conversation.submit(Op::Shutdown).await?;
+                if limit_hit.is_none() {
+                    if let (Some(watcher), Some(prompt)) =
+                        (file_watcher.as_mut(), watch_prompt.as_deref())
+                    {
+                        eprintln!("[watch] waiting for changes to {}", watch_globs.join(", "));
+                        tokio::select! {
+                            changed = watcher.wait_for_change() => {
+                                schema_attempts = 0;
+                                let items = vec![InputItem::Text {
+                                    text: watch::rerun_prompt(&changed, prompt),
+                                }];
+                                conversation.submit(Op::UserInput { items }).await?;
+                                continue;
+                            }
+                            limit = run_guard.deadline_reached() => {
+                                run_guard.disarm();
+                                stop_for_run_limit(&conversation, event_processor.as_mut(), limit).await;
+                                exit_tracker.record(limit.into());
+                                limit_hit = Some(limit);
+                                continue;
+                            }
+                            Some(interruption) = interrupt_rx.recv() => {
+                                handle_interruption(&conversation, event_processor.as_mut(), interruption, &mut ephemeral_worktree).await;
+                                continue;
+                            }
+                        }
+                    }
+                    conversation.submit(Op::Shutdown).await?;
+                }
             }
             CodexStatus::Shutdown => {
                 break;
             }
         }
     }
-    if error_seen {
+    if let Some(report) = schema_failure {
+        match serde_json::to_string(&report) {
+            Ok(json) => eprintln!("{json}"),
+            Err(err) => eprintln!("Final message does not match --output-schema: {err}"),
+        }
+        exit_tracker.record(FailureClass::Schema);
+    }
+    let mut reply_failed = false;
+    if let Some(thread) = review_thread.as_ref()
+        && exit_tracker.failure().is_none()
+    {
+        if let Err(err) =
+            review_reply::deliver(thread, final_message.as_deref(), post_review_reply).await
+        {
+            eprintln!("[review-reply] {err:#}");
+            reply_failed = true;
+        }
+    }
+    event_processor.report_cost(&run_usage.report(&config.model_prices));
+    if let Some(worktree) = ephemeral_worktree {
+        worktree.finish(exit_tracker.failure().is_none()).await;
+    }
+    if let Some(handoff) = handoff.as_ref() {
+        handoff.run(exit_tracker.failure(), final_message.as_deref());
+    }
+    if let Some(stats) = run_stats {
+        stats.finish(run_guard.turns_started(), exit_tracker.failure());
+    }
+    exit_on_failure(event_processor.as_mut(), &exit_tracker);
+    if reply_failed {
         std::process::exit(1);
     }
 
     Ok(())
 }
 
+/// Exits with `code`. `process::exit` skips destructors, so the ephemeral
+/// worktree is dropped (and removed) first.
+fn exit_removing_worktree(worktree: Option<EphemeralWorktree>, code: i32) -> ! {
+    drop(worktree);
+    std::process::exit(code);
+}
+
+/// Exits with the code of the highest-precedence failure, if any, after
+/// letting the event processor report it.
+fn exit_on_failure(event_processor: &mut dyn EventProcessor, exit_tracker: &ExitTracker) {
+    if let Some(failure) = exit_tracker.failure() {
+        event_processor.report_failure(failure);
+        std::process::exit(failure.exit_code());
+    }
+}
+
 async fn resolve_resume_path(
     config: &Config,
     args: &crate::cli::ResumeArgs,
@@ -554,6 +1277,7 @@ async fn resolve_resume_path(
             min_user_messages: 1,
             include_archived: false,
             include_deleted: false,
+            tag: None,
             limit: Some(1),
         };
         let entry = catalog
@@ -566,237 +1290,1194 @@ async fn resolve_resume_path(
     }
 }
 
+/// Prints the `[auto]` transcript lines for a coordinator event. Shared by
+/// live runs and `code exec auto replay`.
+fn print_auto_event(event: &AutoCoordinatorEvent) {
+    let non_empty = |text: &Option<String>| text.clone().filter(|s| !s.trim().is_empty());
+    match event {
+        AutoCoordinatorEvent::Thinking { delta, .. } => {
+            out_println!("[auto] {delta}");
+        }
+        AutoCoordinatorEvent::Action { message } => {
+            out_println!("[auto] {message}");
+        }
+        AutoCoordinatorEvent::TokenMetrics {
+            total_usage,
+            last_turn_usage,
+            turn_count,
+            ..
+        } => {
+            out_println!(
+                "[auto] turn {} tokens (turn/total): {}/{}",
+                turn_count,
+                last_turn_usage.blended_total(),
+                total_usage.blended_total()
+            );
+        }
+        AutoCoordinatorEvent::Decision {
+            status_title,
+            status_sent_to_user,
+            goal,
+            cli,
+            budget_snapshot,
+            ..
+        } => {
+            if let Some(title) = non_empty(status_title) {
+                out_println!("[auto] status: {title}");
+            }
+            if let Some(sent) = non_empty(status_sent_to_user) {
+                out_println!("[auto] update: {sent}");
+            }
+            if let Some(goal_text) = non_empty(goal) {
+                out_println!("[auto] goal: {goal_text}");
+            }
+            if let Some(AutoTurnCliAction {
+                complexity: Some(complexity),
+                model,
+                ..
+            }) = cli
+            {
+                let model = model.as_deref().unwrap_or("session model");
+                out_println!("[auto] cli turn: {complexity:?} complexity, {model}");
+            }
+            if let Some(runway) = format_budget_runway(budget_snapshot) {
+                out_println!("[auto] budget: {runway}");
+            }
+        }
+        // Enhanced Auto Drive events
+        AutoCoordinatorEvent::CheckpointSaved { session_id, turns } => {
+            out_println!("[auto] checkpoint saved: {session_id} ({turns} turns)");
+        }
+        AutoCoordinatorEvent::CheckpointRestored { session_id, turns } => {
+            out_println!("[auto] checkpoint restored: {session_id} ({turns} turns)");
+        }
+        AutoCoordinatorEvent::DiagnosticAlert {
+            alert_type,
+            message,
+        } => {
+            out_println!("[auto] diagnostic alert ({alert_type:?}): {message}");
+        }
+        AutoCoordinatorEvent::BudgetAlert {
+            alert_type,
+            message,
+        } => {
+            out_println!("[auto] budget alert ({alert_type:?}): {message}");
+        }
+        AutoCoordinatorEvent::InterventionRequired { reason } => {
+            out_println!("[auto] intervention required: {reason}");
+        }
+        AutoCoordinatorEvent::CoordinatorModelSwitched { from, to, reason } => {
+            out_println!("[auto] coordinator model switched: {from} -> {to} ({reason})");
+        }
+        AutoCoordinatorEvent::CoordinatorFailover { from, to, reason } => {
+            out_println!("[auto] coordinator model failover: {from} -> {to} ({reason})");
+        }
+        AutoCoordinatorEvent::CoordinatorDegraded { reason } => {
+            out_println!("[auto] coordinator unavailable; continuing in simple loop ({reason})");
+        }
+        AutoCoordinatorEvent::CompactedHistory {
+            estimate: Some(estimate),
+            ..
+        } => {
+            out_println!("[auto] {}", format_compaction_estimate(estimate));
+        }
+        AutoCoordinatorEvent::CompactedHistory { .. }
+        | AutoCoordinatorEvent::UserReply { .. }
+        | AutoCoordinatorEvent::StopAck => {}
+    }
+}
+
+/// Before/after token estimates of a history compaction.
+fn format_compaction_estimate(estimate: &CompactionEstimate) -> String {
+    let mut line = format!(
+        "history compacted: ~{} -> ~{} tokens",
+        estimate.before_tokens, estimate.after_tokens
+    );
+    if let Some(threshold) = estimate.threshold_tokens {
+        line.push_str(&format!(" (threshold ~{threshold})"));
+    }
+    line
+}
+
+/// Summarizes the remaining runway of a decision's budget snapshot. Returns
+/// `None` when no Auto Drive limit is configured.
+fn format_budget_runway(snapshot: &BudgetSnapshot) -> Option<String> {
+    let mut parts = Vec::new();
+    if let Some(left) = snapshot.tokens_remaining {
+        parts.push(format!("{} tokens used, {left} left", snapshot.tokens_used));
+    }
+    if let Some(left) = snapshot.turns_remaining {
+        parts.push(format!("{} turns used, {left} left", snapshot.turns_used));
+    }
+    if let Some(left) = snapshot.remaining_seconds {
+        parts.push(format!(
+            "{}s elapsed, {left}s left",
+            snapshot.elapsed_seconds
+        ));
+    }
+    (!parts.is_empty()).then(|| parts.join("; "))
+}
+
 struct TurnResult {
     last_agent_message: Option<String>,
-    error_seen: bool,
+    limit_hit: Option<RunLimit>,
 }
 
-async fn run_auto_drive_session(
-    goal: String,
-    images: Vec<PathBuf>,
-    config: Config,
-    conversation: Arc<CodexConversation>,
-    mut event_processor: Box<dyn EventProcessor>,
-    last_message_path: Option<PathBuf>,
-) -> anyhow::Result<()> {
-    let mut final_last_message: Option<String> = None;
-    let mut error_seen = false;
+/// Reports the limit, then interrupts the active turn and asks the session
+/// to shut down.
+async fn stop_for_run_limit(
+    conversation: &CodexConversation,
+    event_processor: &mut dyn EventProcessor,
+    limit: RunLimit,
+) {
+    event_processor.report_run_limit(limit);
+    let _ = conversation.submit(Op::Interrupt).await;
+    let _ = conversation.submit(Op::Shutdown).await;
+}
 
-    if !images.is_empty() {
-        let items: Vec<InputItem> = images
-            .into_iter()
-            .map(|path| InputItem::LocalImage { path })
-            .collect();
-        let initial_images_event_id = conversation.submit(Op::UserInput { items }).await?;
-        while let Ok(event) = conversation.next_event().await {
-            let is_complete = event.id == initial_images_event_id
-                && matches!(
-                    event.msg,
-                    EventMsg::TaskComplete(TaskCompleteEvent {
-                        last_agent_message: _,
-                    })
-                );
-            let status = event_processor.process_event(event);
-            if is_complete || matches!(status, CodexStatus::Shutdown) {
-                break;
-            }
+/// Reports a Ctrl-C press. The first one lets the interrupted turn wind down
+/// and then shuts the session down; a second one exits right away.
+async fn handle_interruption(
+    conversation: &CodexConversation,
+    event_processor: &mut dyn EventProcessor,
+    interruption: Interruption,
+    ephemeral_worktree: &mut Option<EphemeralWorktree>,
+) {
+    event_processor.report_interruption(interruption);
+    match interruption {
+        Interruption::Interrupted => {
+            let _ = conversation.submit(Op::Shutdown).await;
+        }
+        Interruption::ForceKilled => {
+            exit_removing_worktree(ephemeral_worktree.take(), interrupt::FORCE_KILL_EXIT_CODE)
         }
     }
+}
 
-    let mut history = AutoDriveHistory::new();
+/// Session-wide state of an exec Auto Drive run. A plain run drives one goal;
+/// `--auto-backlog` drives several goals through the same CLI conversation.
+struct AutoRun {
+    conversation: Arc<CodexConversation>,
+    event_processor: Box<dyn EventProcessor>,
+    run_guard: RunGuard,
+    exit_tracker: ExitTracker,
+    run_usage: RunUsage,
+    /// Runs coordinator workstreams in separate sessions; `None` unless
+    /// `parallel_instances` is above 1.
+    cli_workers: Option<CliWorkerPool>,
+    run_stats: Option<StatsRecorder>,
+    on_intervention: InterventionPolicy,
+    approve_writes: WriteApprovalPolicy,
+    event_log: Option<Arc<event_log::AutoEventLogWriter>>,
+    audit: AuditLogger,
+    /// CLI turns across every goal, numbering the audit log.
+    audit_turns: usize,
+    final_last_message: Option<String>,
+    /// Set by `--auto-plan-only`: decisions are recorded, not executed.
+    plan: Option<AutoPlan>,
+}
+
+/// What driving one goal produced.
+struct GoalRun {
+    cli_turns: usize,
+    coordinator_usage: TokenUsage,
+    failure: Option<FailureClass>,
+    /// `--timeout` or `--max-turns` ended the goal, and with it the run.
+    limit_hit: bool,
+    final_message: Option<String>,
+    report_path: Option<PathBuf>,
+}
 
-    let mut auto_config = config.clone();
-    auto_config.model = config.auto_drive.model.trim().to_string();
-    if auto_config.model.is_empty() {
-        auto_config.model = MODEL_SLUG.to_string();
+impl AutoRun {
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        config: &Config,
+        run_id: &str,
+        conversation: Arc<CodexConversation>,
+        mut event_processor: Box<dyn EventProcessor>,
+        run_guard: RunGuard,
+        run_usage: RunUsage,
+        cli_workers: Option<CliWorkerPool>,
+        run_stats: Option<StatsRecorder>,
+        on_intervention: InterventionPolicy,
+        approve_writes: WriteApprovalPolicy,
+    ) -> Self {
+        let event_log = if config.auto_drive.event_log_enabled {
+            create_event_log(config, run_id)
+        } else {
+            None
+        };
+        if let Some(log) = event_log.as_ref() {
+            event_processor = Box::new(auto_replay::RecordingEventProcessor::new(
+                event_processor,
+                log.clone(),
+            ));
+        }
+        // Entries are kept in memory for the privileges summary either way;
+        // they reach disk only with `auto_drive.audit_enabled`.
+        let mut audit = AuditLogger::new(run_id);
+        if let Some(path) = configured_audit_log_path(config, run_id) {
+            let logged = code_core::at_rest::cipher_for(config.encrypt_at_rest, &config.code_home)
+                .map_err(anyhow::Error::from)
+                .and_then(|cipher| {
+                    AuditLogger::new(run_id)
+                        .with_at_rest_cipher(cipher)
+                        .with_log_path(path)
+                });
+            match logged {
+                Ok(logged) => audit = logged,
+                Err(err) => eprintln!("[auto] audit log not written: {err:#}"),
+            }
+        }
+        Self {
+            conversation,
+            event_processor,
+            run_guard,
+            exit_tracker: ExitTracker::new(),
+            run_usage,
+            cli_workers,
+            run_stats,
+            on_intervention,
+            approve_writes,
+            event_log,
+            audit,
+            audit_turns: 0,
+            final_last_message: None,
+            plan: None,
+        }
     }
-    auto_config.model_reasoning_effort = config.auto_drive.model_reasoning_effort;
 
-    let (auto_tx, mut auto_rx) = tokio::sync::mpsc::unbounded_channel();
-    let sender = AutoCoordinatorEventSender::new(move |event| {
-        let _ = auto_tx.send(event);
-    });
+    /// Runs `goal` until the coordinator stops or a run limit is hit.
+    /// `carried_context` seeds the coordinator history of a backlog goal with
+    /// a summary of the goals before it.
+    async fn drive_goal(
+        &mut self,
+        goal: String,
+        config: &Config,
+        attachments: Vec<InputItem>,
+        mut checkpoints: AutoCheckpointer,
+        carried_context: Option<String>,
+        generate_commit_message: bool,
+    ) -> anyhow::Result<GoalRun> {
+        let session_id = checkpoints.session_id().to_string();
+        self.audit.log(
+            AuditOperation::SessionStart { goal: goal.clone() },
+            AuditOutcome::Success,
+        );
+        let mut cli_turns = checkpoints.turns_completed();
+        // Budgets apply per goal, so a backlog goal only counts what the
+        // session used after it started.
+        let usage_baseline = self.run_usage.baseline();
+        let mut token_usage = TokenUsage::default();
+        let mut final_last_message: Option<String> = None;
+        let mut exit_tracker = ExitTracker::new();
+        let mut limit_hit = false;
+        // Files and test commands seen in CLI turns; decisions come from history.
+        let mut observed_changes = generate_commit_message.then(ChangeSummary::default);
+        let mut report = RunReport::new(&session_id, goal_without_suffix(&goal, config));
+        // Patches and the progress log sit next to the report.
+        let artifacts_dir = default_report_dir(&config.code_home).join(&session_id);
+        let mut turn_changes = TurnChangeTracker::new(&config.cwd, &artifacts_dir);
+        let progress_log = ProgressLogger::new(artifacts_dir.join("progress.log"));
+
+        // Attachments ride along with the first CLI turn instead of costing a
+        // turn of their own.
+        let mut pending_attachments = attachments;
+
+        let mut history = AutoDriveHistory::new();
+        history.replace_all(checkpoints.history());
+        if let Some(context) = carried_context {
+            history.append_raw(&[make_user_message(context)]);
+        }
+        if let Some(event) = checkpoints.restored_event() {
+            emit_auto_event(self.event_log.as_deref(), &event);
+        }
 
-    let handle = start_auto_coordinator(
-        sender,
-        goal.clone(),
-        history.raw_snapshot(),
-        auto_config,
-        config.debug,
-        false,
-    )?;
-
-    while let Some(event) = auto_rx.recv().await {
-        match event {
-            AutoCoordinatorEvent::Thinking { delta, .. } => {
-                println!("[auto] {delta}");
-            }
-            AutoCoordinatorEvent::Action { message } => {
-                println!("[auto] {message}");
-            }
-            AutoCoordinatorEvent::TokenMetrics {
-                total_usage,
-                last_turn_usage,
-                turn_count,
-                ..
-            } => {
-                println!(
-                    "[auto] turn {} tokens (turn/total): {}/{}",
-                    turn_count,
-                    last_turn_usage.blended_total(),
-                    total_usage.blended_total()
-                );
-            }
-            AutoCoordinatorEvent::CompactedHistory { conversation, .. } => {
-                history.replace_all(conversation);
+        let mut auto_config = config.clone();
+        auto_config.model = config.auto_drive.model.trim().to_string();
+        if auto_config.model.is_empty() {
+            auto_config.model = MODEL_SLUG.to_string();
+        }
+        auto_config.model_reasoning_effort = config.auto_drive.model_reasoning_effort;
+        // Follows coordinator model switches so its usage is priced by the
+        // model that spent it.
+        let mut coordinator_model = auto_config.model.clone();
+
+        let (auto_tx, mut auto_rx) = tokio::sync::mpsc::unbounded_channel();
+        let sender = AutoCoordinatorEventSender::new(move |event| {
+            let _ = auto_tx.send(event);
+        });
+
+        let handle = start_auto_coordinator(
+            sender,
+            goal.clone(),
+            history.raw_snapshot(),
+            auto_config,
+            config.debug,
+            false,
+        )?;
+        let pause_signals = forward_pause_signals(handle.clone());
+
+        loop {
+            let event = tokio::select! {
+                event = auto_rx.recv() => match event {
+                    Some(event) => event,
+                    None => break,
+                },
+                limit = self.run_guard.deadline_reached() => {
+                    self.run_guard.disarm();
+                    stop_for_run_limit(&self.conversation, self.event_processor.as_mut(), limit)
+                        .await;
+                    exit_tracker.record(limit.into());
+                    limit_hit = true;
+                    break;
+                }
+            };
+            if let Some(log) = self.event_log.as_ref() {
+                log.record_coordinator(&event);
             }
-            AutoCoordinatorEvent::UserReply {
-                user_response,
-                cli_command,
-            } => {
-                if let Some(text) = user_response.filter(|s| !s.trim().is_empty()) {
-                    history.append_raw(&[make_assistant_message(text.clone())]);
-                    final_last_message = Some(text);
+            print_auto_event(&event);
+            report.observe(&event);
+            self.audit.observe(&event);
+            match event {
+                AutoCoordinatorEvent::TokenMetrics { total_usage, .. } => {
+                    self.run_usage.add_coordinator(
+                        &coordinator_model,
+                        &usage_since(&total_usage, &token_usage),
+                    );
+                    token_usage = total_usage;
                 }
-
-                if let Some(cmd) = cli_command {
-                    let prompt_text = cmd.trim();
-                    if !prompt_text.is_empty() {
-                        history.append_raw(&[make_user_message(prompt_text.to_string())]);
-                        let TurnResult {
-                            last_agent_message,
-                            error_seen: turn_error,
-                        } = submit_and_wait(
-                            &conversation,
-                            event_processor.as_mut(),
-                            prompt_text.to_string(),
-                        )
-                        .await?;
-                        error_seen |= turn_error;
-                        if let Some(text) = last_agent_message {
-                            history.append_raw(&[make_assistant_message(text.clone())]);
-                            final_last_message = Some(text);
+                AutoCoordinatorEvent::CoordinatorModelSwitched { to, .. }
+                | AutoCoordinatorEvent::CoordinatorFailover { to, .. } => {
+                    coordinator_model = to;
+                }
+                AutoCoordinatorEvent::Thinking { .. }
+                | AutoCoordinatorEvent::Action { .. }
+                | AutoCoordinatorEvent::CheckpointSaved { .. }
+                | AutoCoordinatorEvent::CheckpointRestored { .. }
+                | AutoCoordinatorEvent::DiagnosticAlert { .. }
+                | AutoCoordinatorEvent::BudgetAlert { .. }
+                | AutoCoordinatorEvent::CoordinatorDegraded { .. } => {}
+                AutoCoordinatorEvent::InterventionRequired { .. } => {
+                    // The coordinator asks for intervention at the first-write
+                    // checkpoint, which exec enables on request, and for goal
+                    // amendments under `goal_changes = "confirm"`. A plan-only
+                    // run writes nothing and only records the plan, so it
+                    // approves both.
+                    let reply = if self.plan.is_some() {
+                        InterventionReply::Approve
+                    } else {
+                        match self.on_intervention {
+                            InterventionPolicy::Pause => read_intervention_reply().await,
+                            InterventionPolicy::Fail => InterventionReply::Stop,
+                            InterventionPolicy::Continue => {
+                                eprintln!(
+                                    "[auto] continuing without approval (--on-intervention continue)"
+                                );
+                                InterventionReply::Approve
+                            }
+                        }
+                    };
+                    match reply {
+                        InterventionReply::Approve => {
+                            let _ = handle.send(AutoCoordinatorCommand::ApproveHeldDecision);
+                        }
+                        InterventionReply::Stop => {
+                            eprintln!("[auto] intervention not approved; stopping");
+                            exit_tracker.record(FailureClass::AutoDriveFailed);
+                            let _ = handle.send(AutoCoordinatorCommand::Stop);
+                        }
+                        InterventionReply::Guidance(text) => {
+                            history.append_raw(&[make_user_message(text.clone())]);
+                            let _ = handle.send(AutoCoordinatorCommand::HandleUserPrompt {
+                                _prompt: text,
+                                conversation: history.raw_snapshot(),
+                                attachments: Vec::new(),
+                            });
                         }
-                        let _ = handle.send(AutoCoordinatorCommand::UpdateConversation(
-                            history.raw_snapshot(),
-                        ));
                     }
                 }
-            }
-            AutoCoordinatorEvent::Decision {
-                seq,
-                status,
-                status_title,
-                status_sent_to_user,
-                goal: maybe_goal,
-                cli,
-                agents_timing,
-                agents,
-                transcript,
-            } => {
-                history.append_raw(&transcript);
-                let _ = handle.send(AutoCoordinatorCommand::AckDecision { seq });
-
-                if let Some(title) = status_title.filter(|s| !s.trim().is_empty()) {
-                    println!("[auto] status: {title}");
-                }
-                if let Some(sent) = status_sent_to_user.filter(|s| !s.trim().is_empty()) {
-                    println!("[auto] update: {sent}");
+                AutoCoordinatorEvent::CompactedHistory { conversation, .. } => {
+                    history.replace_all(conversation);
                 }
-                if let Some(goal_text) = maybe_goal.filter(|s| !s.trim().is_empty()) {
-                    println!("[auto] goal: {goal_text}");
+                AutoCoordinatorEvent::UserReply {
+                    user_response,
+                    cli_command,
+                    attachments,
+                } => {
+                    pending_attachments.extend(attachments);
+                    if let Some(text) = user_response.filter(|s| !s.trim().is_empty()) {
+                        history.append_raw(&[make_assistant_message(text.clone())]);
+                        final_last_message = Some(text);
+                    }
+
+                    if let Some(cmd) = cli_command {
+                        let prompt_text = cmd.trim();
+                        if !prompt_text.is_empty() {
+                            history.append_raw(&[make_user_message(prompt_text.to_string())]);
+                            cli_turns += 1;
+                            self.audit_turns += 1;
+                            audit_cli_turn(
+                                &mut self.audit,
+                                config,
+                                self.audit_turns,
+                                prompt_text,
+                                None,
+                            );
+                            let TurnResult {
+                                last_agent_message,
+                                limit_hit: turn_limit,
+                            } = submit_and_wait(
+                                &self.conversation,
+                                self.event_processor.as_mut(),
+                                &mut self.run_guard,
+                                &mut exit_tracker,
+                                &mut |msg| {
+                                    if let Some(changes) = observed_changes.as_mut() {
+                                        changes.observe(msg, &config.cwd);
+                                    }
+                                },
+                                std::mem::take(&mut pending_attachments),
+                                prompt_text.to_string(),
+                            )
+                            .await?;
+                            if let Some(limit) = turn_limit {
+                                exit_tracker.record(limit.into());
+                                limit_hit = true;
+                                break;
+                            }
+                            if let Some(text) = last_agent_message {
+                                history.append_raw(&[make_assistant_message(text.clone())]);
+                                final_last_message = Some(text);
+                            }
+                            if let Some(event) =
+                                checkpoints.after_turn(&history, cli_turns, &token_usage)
+                            {
+                                emit_auto_event(self.event_log.as_deref(), &event);
+                            }
+                            report_auto_usage(&handle, config, &self.run_usage, &usage_baseline);
+                            let _ = handle.send(AutoCoordinatorCommand::UpdateConversation(
+                                history.raw_snapshot(),
+                            ));
+                        }
+                    }
                 }
+                AutoCoordinatorEvent::Decision {
+                    seq,
+                    status,
+                    status_title,
+                    cli,
+                    agents_timing,
+                    mut agents,
+                    transcript,
+                    ..
+                } => {
+                    history.append_raw(&transcript);
+                    let _ = handle.send(AutoCoordinatorCommand::AckDecision { seq });
+                    if matches!(status, AutoCoordinatorStatus::Failed) {
+                        exit_tracker.record(FailureClass::AutoDriveFailed);
+                    }
+                    if let Some(plan) = self.plan.as_mut() {
+                        plan.record(
+                            status,
+                            status_title.as_deref(),
+                            cli.as_ref(),
+                            &agents,
+                            agents_timing,
+                        );
+                    }
+                    let plan_complete = self.plan.as_ref().is_some_and(AutoPlan::is_complete);
+
+                    let Some(mut cli_action) = cli else {
+                        if plan_complete
+                            || matches!(
+                                status,
+                                AutoCoordinatorStatus::Success | AutoCoordinatorStatus::Failed
+                            )
+                        {
+                            let _ = handle.send(AutoCoordinatorCommand::Stop);
+                        }
+                        continue;
+                    };
 
-                let Some(cli_action) = cli else {
-                    if matches!(
-                        status,
-                        AutoCoordinatorStatus::Success | AutoCoordinatorStatus::Failed
-                    ) {
-                        let _ = handle.send(AutoCoordinatorCommand::Stop);
+                    pending_attachments.append(&mut cli_action.attachments);
+                    if self.plan.is_none() {
+                        self.gate_agent_writes(seq, &mut agents).await;
                     }
-                    continue;
-                };
+                    let mut prompt_text = build_auto_prompt(&cli_action, &agents, agents_timing);
+                    if self.plan.is_some() {
+                        history.append_raw(&[
+                            make_user_message(prompt_text),
+                            make_assistant_message(SIMULATED_CLI_REPLY.to_string()),
+                        ]);
+                        let command = if plan_complete {
+                            AutoCoordinatorCommand::Stop
+                        } else {
+                            AutoCoordinatorCommand::UpdateConversation(history.raw_snapshot())
+                        };
+                        if handle.send(command).is_err() {
+                            break;
+                        }
+                        continue;
+                    }
+                    turn_changes.begin_turn();
+                    let mut turn_usage = Vec::new();
+                    // Workstreams run first in their own sessions; the CLI
+                    // prompt then merges and verifies their results.
+                    if let Some(pool) = self.cli_workers.as_ref()
+                        && !cli_action.workstreams.is_empty()
+                    {
+                        let results = tokio::select! {
+                            results = pool.run(&cli_action.workstreams) => results,
+                            limit = self.run_guard.deadline_reached() => {
+                                self.run_guard.disarm();
+                                stop_for_run_limit(
+                                    &self.conversation,
+                                    self.event_processor.as_mut(),
+                                    limit,
+                                )
+                                .await;
+                                exit_tracker.record(limit.into());
+                                limit_hit = true;
+                                break;
+                            }
+                        };
+                        for result in &results {
+                            self.run_usage.add_worker(&result.usage);
+                            turn_usage.push(AttributedUsage {
+                                seq,
+                                source: UsageSource::Agent {
+                                    prompt_hash: agent_prompt_hash(&result.prompt),
+                                },
+                                usage: result.usage.clone(),
+                            });
+                        }
+                        prompt_text.push_str("\n\n");
+                        prompt_text.push_str(&workstream_results_section(&results));
+                    }
+                    history.append_raw(&[make_user_message(prompt_text.clone())]);
+                    let turn_model = cli_action.model.take();
+                    if let Some(model) = turn_model.clone() {
+                        self.conversation
+                            .submit(Op::SetNextTurnModel { model })
+                            .await?;
+                        self.run_usage.route_turn(turn_model.clone());
+                    }
+                    cli_turns += 1;
+                    self.audit_turns += 1;
+                    audit_cli_turn(
+                        &mut self.audit,
+                        config,
+                        self.audit_turns,
+                        &prompt_text,
+                        turn_model.as_deref(),
+                    );
+
+                    let session_before = self.run_usage.session();
+                    let turn = submit_and_wait(
+                        &self.conversation,
+                        self.event_processor.as_mut(),
+                        &mut self.run_guard,
+                        &mut exit_tracker,
+                        &mut |msg| {
+                            if let Some(changes) = observed_changes.as_mut() {
+                                changes.observe(msg, &config.cwd);
+                            }
+                        },
+                        std::mem::take(&mut pending_attachments),
+                        prompt_text,
+                    )
+                    .await;
+                    self.run_usage.route_turn(None);
+                    let TurnResult {
+                        last_agent_message,
+                        limit_hit: turn_limit,
+                    } = turn?;
+                    if let Some(changes) = turn_changes.finish_turn(seq) {
+                        eprintln!("[auto] decision {seq}: {}", changes.summary());
+                        if let Err(err) = progress_log.append(ProgressEntry::from(&changes)) {
+                            tracing::warn!("failed to append auto drive progress log: {err:#}");
+                        }
+                        report.record_turn_changes(changes);
+                    }
+                    turn_usage.push(AttributedUsage {
+                        seq,
+                        source: UsageSource::CliTurn,
+                        usage: self.run_usage.session_since(&session_before),
+                    });
+                    let _ = handle.send(AutoCoordinatorCommand::ReportTurnUsage(turn_usage));
+                    if let Some(limit) = turn_limit {
+                        exit_tracker.record(limit.into());
+                        limit_hit = true;
+                        break;
+                    }
+                    if let Some(text) = last_agent_message {
+                        history.append_raw(&[make_assistant_message(text.clone())]);
+                        final_last_message = Some(text);
+                    }
+                    if let Some(event) = checkpoints.after_turn(&history, cli_turns, &token_usage) {
+                        emit_auto_event(self.event_log.as_deref(), &event);
+                    }
+                    report_auto_usage(&handle, config, &self.run_usage, &usage_baseline);
 
-                let prompt_text = build_auto_prompt(&cli_action, &agents, agents_timing);
-                history.append_raw(&[make_user_message(prompt_text.clone())]);
-
-                let TurnResult {
-                    last_agent_message,
-                    error_seen: turn_error,
-                } = submit_and_wait(&conversation, event_processor.as_mut(), prompt_text).await?;
-                error_seen |= turn_error;
-                if let Some(text) = last_agent_message {
-                    history.append_raw(&[make_assistant_message(text.clone())]);
-                    final_last_message = Some(text);
+                    if handle
+                        .send(AutoCoordinatorCommand::UpdateConversation(
+                            history.raw_snapshot(),
+                        ))
+                        .is_err()
+                    {
+                        break;
+                    }
                 }
-
-                if handle
-                    .send(AutoCoordinatorCommand::UpdateConversation(
-                        history.raw_snapshot(),
-                    ))
-                    .is_err()
-                {
+                AutoCoordinatorEvent::StopAck => {
                     break;
                 }
             }
-            AutoCoordinatorEvent::StopAck => {
-                break;
+        }
+        handle.cancel();
+        if let Some(task) = pause_signals {
+            task.abort();
+        }
+        if let Some(plan) = self.plan.as_ref() {
+            out_println!("{}", plan.render());
+        }
+
+        let failure = exit_tracker.failure();
+        if let Some(failure) = failure {
+            self.exit_tracker.record(failure);
+        }
+        if final_last_message.is_some() {
+            self.final_last_message = final_last_message.clone();
+        }
+        self.audit.log(
+            AuditOperation::SessionEnd {
+                turns: cli_turns,
+                success: failure.is_none(),
+            },
+            AuditOutcome::Success,
+        );
+        report.finish(RunOutcome {
+            success: failure.is_none(),
+            failure_reason: failure.map(|failure| failure.reason().to_string()),
+            cli_turns,
+            final_message: final_last_message.clone(),
+        });
+        let report_path = match report.write(&default_report_dir(&config.code_home)) {
+            Ok((json_path, markdown_path)) => {
+                eprintln!(
+                    "[auto] run report: {} ({})",
+                    json_path.display(),
+                    markdown_path.display()
+                );
+                Some(json_path)
+            }
+            Err(err) => {
+                tracing::warn!("failed to write auto drive run report: {err:#}");
+                None
+            }
+        };
+        if let Some(observed) = observed_changes {
+            let goal = goal_without_suffix(&goal, config);
+            let mut summary = ChangeSummary::from_history(goal, &history.raw_snapshot());
+            summary.merge(observed);
+            print_commit_message(&commit_message::generate_commit_message(&summary));
+        }
+        checkpoints.finish(&history, cli_turns, &token_usage, failure.is_none());
+
+        Ok(GoalRun {
+            cli_turns,
+            coordinator_usage: token_usage,
+            failure,
+            limit_hit,
+            final_message: final_last_message,
+            report_path,
+        })
+    }
+
+    /// Applies `--approve-writes` to the write-enabled agents of decision
+    /// `seq`. Rejected agents are downgraded to read-only before the CLI
+    /// prompt is built, and the rejection is audited.
+    async fn gate_agent_writes(&mut self, seq: u64, agents: &mut [AutoTurnAgentsAction]) {
+        let writers: Vec<usize> = agents
+            .iter()
+            .enumerate()
+            .filter(|(_, agent)| agent.write)
+            .map(|(index, _)| index)
+            .collect();
+        if writers.is_empty() {
+            return;
+        }
+        let approved = match self.approve_writes {
+            WriteApprovalPolicy::Always => true,
+            WriteApprovalPolicy::Never => false,
+            WriteApprovalPolicy::Ask => {
+                for &index in &writers {
+                    out_println!(
+                        "[auto] write-enabled agent: {}",
+                        agents[index].prompt.trim()
+                    );
+                }
+                read_write_approval(writers.len()).await
             }
-            // Enhanced Auto Drive events
-            AutoCoordinatorEvent::CheckpointSaved { session_id, turns } => {
-                println!("[auto] checkpoint saved: {session_id} ({turns} turns)");
+        };
+        if approved {
+            return;
+        }
+        eprintln!(
+            "[auto] write access rejected; {} agent(s) run read-only",
+            writers.len()
+        );
+        for index in writers {
+            agents[index].write = false;
+            self.audit.log_with_context(
+                AuditOperation::AgentDispatch {
+                    agent_id: dispatch_agent_id(seq, index),
+                    write_access: true,
+                },
+                AuditOutcome::Denied,
+                Some("write access rejected; runs read-only".to_string()),
+            );
+        }
+    }
+
+    /// Shuts the CLI session down and reports the run as a whole. Exits the
+    /// process with the failure's exit code when any goal failed.
+    async fn finish(
+        mut self,
+        config: &Config,
+        last_message_path: Option<&Path>,
+        ephemeral_worktree: Option<EphemeralWorktree>,
+        handoff: Option<Handoff>,
+    ) {
+        let _ = self.conversation.submit(Op::Shutdown).await;
+        while let Ok(event) = self.conversation.next_event().await {
+            if matches!(event.msg, EventMsg::ShutdownComplete) {
+                break;
             }
-            AutoCoordinatorEvent::CheckpointRestored { session_id, turns } => {
-                println!("[auto] checkpoint restored: {session_id} ({turns} turns)");
+            self.exit_tracker.observe(&event.msg);
+            let status = self.event_processor.process_event(event);
+            if matches!(status, CodexStatus::Shutdown) {
+                break;
             }
-            AutoCoordinatorEvent::DiagnosticAlert {
-                alert_type,
-                message,
-            } => {
-                println!("[auto] diagnostic alert ({alert_type:?}): {message}");
+        }
+
+        if let Some(path) = last_message_path {
+            handle_last_message(self.final_last_message.as_deref(), path).await;
+        }
+        print_turn_privileges(&self.audit.generate_summary());
+        self.event_processor
+            .report_cost(&self.run_usage.report(&config.model_prices));
+        if let Some(worktree) = ephemeral_worktree {
+            worktree.finish(self.exit_tracker.failure().is_none()).await;
+        }
+        if let Some(handoff) = handoff.as_ref() {
+            handoff.run(
+                self.exit_tracker.failure(),
+                self.final_last_message.as_deref(),
+            );
+        }
+        if let Some(stats) = self.run_stats.take() {
+            stats.finish(self.run_guard.turns_started(), self.exit_tracker.failure());
+        }
+        exit_on_failure(self.event_processor.as_mut(), &self.exit_tracker);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn run_auto_drive_session(
+    goal: String,
+    attachments: Vec<InputItem>,
+    config: Config,
+    conversation: Arc<CodexConversation>,
+    event_processor: Box<dyn EventProcessor>,
+    last_message_path: Option<PathBuf>,
+    run_guard: RunGuard,
+    checkpoints: AutoCheckpointer,
+    handoff: Option<Handoff>,
+    ephemeral_worktree: Option<EphemeralWorktree>,
+    run_usage: RunUsage,
+    cli_workers: Option<CliWorkerPool>,
+    run_stats: Option<StatsRecorder>,
+    generate_commit_message: bool,
+    on_intervention: InterventionPolicy,
+    approve_writes: WriteApprovalPolicy,
+    plan: Option<AutoPlan>,
+) -> anyhow::Result<()> {
+    let run_id = checkpoints.session_id().to_string();
+    let mut run = AutoRun::new(
+        &config,
+        &run_id,
+        conversation,
+        event_processor,
+        run_guard,
+        run_usage,
+        cli_workers,
+        run_stats,
+        on_intervention,
+        approve_writes,
+    );
+    run.plan = plan;
+    run.drive_goal(
+        goal,
+        &config,
+        attachments,
+        checkpoints,
+        None,
+        generate_commit_message,
+    )
+    .await?;
+    run.finish(
+        &config,
+        last_message_path.as_deref(),
+        ephemeral_worktree,
+        handoff,
+    )
+    .await;
+    Ok(())
+}
+
+/// Settings `--auto-backlog` applies to every goal of the backlog.
+struct BacklogRunOptions {
+    path: PathBuf,
+    checkpoint_dir: PathBuf,
+    checkpoint_every: usize,
+    generate_commit_message: bool,
+}
+
+/// Drives the goals of a backlog one after another through the same CLI
+/// conversation. Each goal gets a fresh coordinator seeded with a summary of
+/// the goals before it, its own budgets, checkpoint, and run report; a
+/// backlog report lists the status of every goal.
+#[allow(clippy::too_many_arguments)]
+async fn run_auto_backlog(
+    backlog: GoalBacklog,
+    options: BacklogRunOptions,
+    attachments: Vec<InputItem>,
+    config: Config,
+    run_id: String,
+    conversation: Arc<CodexConversation>,
+    event_processor: Box<dyn EventProcessor>,
+    last_message_path: Option<PathBuf>,
+    run_guard: RunGuard,
+    handoff: Option<Handoff>,
+    ephemeral_worktree: Option<EphemeralWorktree>,
+    run_usage: RunUsage,
+    cli_workers: Option<CliWorkerPool>,
+    run_stats: Option<StatsRecorder>,
+    on_intervention: InterventionPolicy,
+    approve_writes: WriteApprovalPolicy,
+) -> anyhow::Result<()> {
+    let mut run = AutoRun::new(
+        &config,
+        &run_id,
+        conversation,
+        event_processor,
+        run_guard,
+        run_usage,
+        cli_workers,
+        run_stats,
+        on_intervention,
+        approve_writes,
+    );
+    let mut report = BacklogReport::new(&options.path);
+    let mut attachments = Some(attachments);
+    let mut stopped = false;
+    let total = backlog.goals.len();
+    for (index, backlog_goal) in backlog.goals.iter().enumerate() {
+        if stopped {
+            report.goals.push(GoalResult::skipped(backlog_goal));
+            continue;
+        }
+        out_println!(
+            "[auto] backlog goal {}/{total} ({}): {}",
+            index + 1,
+            backlog_goal.id,
+            backlog_goal.goal
+        );
+        let mut goal_config = config.clone();
+        goal_config.auto_drive.token_budget = backlog_goal
+            .max_tokens
+            .or(goal_config.auto_drive.token_budget);
+        goal_config.auto_drive.cost_budget_usd = backlog_goal
+            .max_cost
+            .or(goal_config.auto_drive.cost_budget_usd);
+        goal_config.auto_drive.duration_limit_seconds = backlog_goal
+            .max_duration
+            .or(goal_config.auto_drive.duration_limit_seconds);
+        let goal = append_goal_suffix(&backlog_goal.goal, auto_drive_goal_suffix(&goal_config));
+        let checkpoints = AutoCheckpointer::start(
+            options.checkpoint_dir.clone(),
+            options.checkpoint_every,
+            &goal,
+            &format!("{run_id}-{}", backlog_goal.id),
+        )?;
+        let outcome = run
+            .drive_goal(
+                goal,
+                &goal_config,
+                attachments.take().unwrap_or_default(),
+                checkpoints,
+                carry_forward_context(&report.goals),
+                options.generate_commit_message,
+            )
+            .await?;
+        let status = if outcome.failure.is_none() {
+            GoalStatus::Succeeded
+        } else {
+            GoalStatus::Failed
+        };
+        out_println!(
+            "[auto] backlog goal {} {}",
+            backlog_goal.id,
+            match status {
+                GoalStatus::Succeeded => "succeeded",
+                GoalStatus::Failed | GoalStatus::Skipped => "failed",
             }
-            AutoCoordinatorEvent::BudgetAlert {
-                alert_type,
-                message,
-            } => {
-                println!("[auto] budget alert ({alert_type:?}): {message}");
+        );
+        stopped =
+            outcome.limit_hit || (status == GoalStatus::Failed && !backlog.continue_on_failure);
+        report.goals.push(GoalResult {
+            id: backlog_goal.id.clone(),
+            goal: backlog_goal.goal.clone(),
+            status,
+            failure_reason: outcome.failure.map(|failure| failure.reason().to_string()),
+            cli_turns: outcome.cli_turns,
+            coordinator_tokens: outcome.coordinator_usage.total_tokens,
+            final_message: outcome.final_message,
+            report: outcome.report_path,
+        });
+    }
+
+    match report.write(&default_report_dir(&config.code_home)) {
+        Ok((json_path, markdown_path)) => eprintln!(
+            "[auto] backlog report: {} ({})",
+            json_path.display(),
+            markdown_path.display()
+        ),
+        Err(err) => tracing::warn!("failed to write auto drive backlog report: {err:#}"),
+    }
+    run.finish(
+        &config,
+        last_message_path.as_deref(),
+        ephemeral_worktree,
+        handoff,
+    )
+    .await;
+    Ok(())
+}
+
+/// Opens the event log for `run_id` and prunes the oldest logs beyond
+/// `auto_drive.event_log_keep`.
+fn create_event_log(config: &Config, run_id: &str) -> Option<Arc<event_log::AutoEventLogWriter>> {
+    let path = event_log::event_log_path(&config.code_home, run_id);
+    let writer = match code_core::at_rest::cipher_for(config.encrypt_at_rest, &config.code_home)
+        .map_err(anyhow::Error::from)
+        .and_then(|cipher| event_log::AutoEventLogWriter::create(&path, cipher))
+    {
+        Ok(writer) => writer,
+        Err(err) => {
+            tracing::warn!("failed to create auto drive event log: {err:#}");
+            return None;
+        }
+    };
+    eprintln!(
+        "[auto] recording events to {} (replay with `code exec auto replay {run_id}`)",
+        path.display()
+    );
+    if let Err(err) =
+        event_log::prune_event_logs(&config.code_home, config.auto_drive.event_log_keep)
+    {
+        tracing::warn!("failed to prune auto drive event logs: {err}");
+    }
+    Some(Arc::new(writer))
+}
+
+/// Records and prints an Auto Drive event raised by exec rather than the
+/// coordinator.
+fn emit_auto_event(
+    event_log: Option<&event_log::AutoEventLogWriter>,
+    event: &AutoCoordinatorEvent,
+) {
+    if let Some(log) = event_log {
+        log.record_coordinator(event);
+    }
+    print_auto_event(event);
+}
+
+/// Tells the coordinator what the CLI agent has used and what the goal has
+/// cost so far, so it can enforce the token and cost budgets.
+fn report_auto_usage(
+    handle: &AutoCoordinatorHandle,
+    config: &Config,
+    run_usage: &RunUsage,
+    baseline: &UsageBaseline,
+) {
+    let (cli_tokens, cost_usd) = goal_spend(config, run_usage, baseline);
+    let _ = handle.send(AutoCoordinatorCommand::ReportUsage {
+        cli_tokens,
+        cost_usd,
+    });
+}
+
+/// CLI tokens and estimated spend of the current goal: session, workstream
+/// and coordinator usage since `baseline`, each model at its own price. The
+/// spend is `None` when a CLI model has no price; an unpriced coordinator
+/// model counts as free.
+fn goal_spend(
+    config: &Config,
+    run_usage: &RunUsage,
+    baseline: &UsageBaseline,
+) -> (u64, Option<f64>) {
+    let report = run_usage.report_since(&config.model_prices, baseline);
+    let mut cli_tokens = 0;
+    let mut cost_usd = Some(0.0);
+    for model in &report.models {
+        match model.role {
+            UsageRole::Cli => {
+                cli_tokens += model.total_tokens;
+                cost_usd = cost_usd.zip(model.estimated_usd).map(|(a, b)| a + b);
             }
-            AutoCoordinatorEvent::InterventionRequired { reason } => {
-                println!("[auto] intervention required: {reason}");
+            UsageRole::Coordinator => {
+                cost_usd = cost_usd.map(|usd| usd + model.estimated_usd.unwrap_or_default());
             }
         }
     }
+    (cli_tokens, cost_usd)
+}
 
-    handle.cancel();
-    let _ = conversation.submit(Op::Shutdown).await;
-    while let Ok(event) = conversation.next_event().await {
-        if matches!(event.msg, EventMsg::ShutdownComplete) {
-            break;
+/// Records the prompt CLI turn `turn` is about to run and its privileges.
+fn audit_cli_turn(
+    audit: &mut AuditLogger,
+    config: &Config,
+    turn: usize,
+    prompt: &str,
+    model_override: Option<&str>,
+) {
+    audit.log_with_context(
+        AuditOperation::CliTurn {
+            turn,
+            privileges: TurnPrivileges::for_turn(config, model_override),
+        },
+        AuditOutcome::Success,
+        Some(prompt.to_string()),
+    );
+}
+
+/// `code exec auto-audit verify`.
+fn verify_audit_file(path: &Path) -> anyhow::Result<()> {
+    let code_home = code_core::config::find_code_home()?;
+    match verify_audit_log(path, &code_home)? {
+        ChainVerification::Valid { entries, last_hash } => {
+            out_println!("{}: chain intact, {entries} entries", path.display());
+            out_println!("last hash: {last_hash}");
+            Ok(())
         }
-        let status = event_processor.process_event(event);
-        if matches!(status, CodexStatus::Shutdown) {
-            break;
+        ChainVerification::Broken { line, reason } => {
+            eprintln!("{}:{line}: chain broken: {reason}", path.display());
+            std::process::exit(1);
         }
     }
+}
 
-    if let Some(path) = last_message_path.as_deref() {
-        handle_last_message(final_last_message.as_deref(), path);
+/// Final report section listing what each CLI turn was allowed to do.
+fn print_turn_privileges(summary: &AuditSummary) {
+    for span in &summary.turn_privileges {
+        let turns = if span.first_turn == span.last_turn {
+            format!("turn {}", span.first_turn)
+        } else {
+            format!("turns {}-{}", span.first_turn, span.last_turn)
+        };
+        out_println!("[auto] {turns}: {}", span.privileges.describe());
     }
+}
 
-    if error_seen {
-        std::process::exit(1);
+/// Prints the `--generate-commit-message` output: the commit message, then
+/// the pull request body.
+fn print_commit_message(message: &CommitMessage) {
+    out_println!("[auto] commit message:");
+    out_println!("{}", message.full_message());
+    out_println!();
+    out_println!("[auto] pull request body:");
+    out_print!("{}", message.pr_body);
+}
+
+/// The operator's answer to an intervention request.
+#[derive(Debug, PartialEq)]
+enum InterventionReply {
+    Approve,
+    Stop,
+    /// Routed to the coordinator, which replans around it.
+    Guidance(String),
+}
+
+/// Reads one line from stdin in answer to an intervention request. End of
+/// input stops the run.
+async fn read_intervention_reply() -> InterventionReply {
+    tokio::task::spawn_blocking(|| {
+        eprint!("[auto] continue? [y/N, or type guidance for the coordinator] ");
+        let mut answer = String::new();
+        match std::io::stdin().read_line(&mut answer) {
+            Ok(0) | Err(_) => InterventionReply::Stop,
+            Ok(_) => parse_intervention_reply(&answer),
+        }
+    })
+    .await
+    .unwrap_or(InterventionReply::Stop)
+}
+
+/// `--approve-writes ask`: reads the operator's answer for `count`
+/// write-enabled agents. Only `y` approves.
+async fn read_write_approval(count: usize) -> bool {
+    tokio::task::spawn_blocking(move || {
+        eprint!("[auto] allow {count} agent(s) to write files? [y/N] ");
+        let mut answer = String::new();
+        match std::io::stdin().read_line(&mut answer) {
+            Ok(0) | Err(_) => false,
+            Ok(_) => matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes"),
+        }
+    })
+    .await
+    .unwrap_or(false)
+}
+
+fn parse_intervention_reply(answer: &str) -> InterventionReply {
+    let answer = answer.trim();
+    match answer.to_ascii_lowercase().as_str() {
+        "y" | "yes" => InterventionReply::Approve,
+        "" | "n" | "no" | "stop" => InterventionReply::Stop,
+        _ => InterventionReply::Guidance(answer.to_string()),
     }
+}
 
-    Ok(())
+/// Policy text appended to every Auto Drive goal: `auto_drive.goal_suffix`
+/// (possibly set from `--no-test-suffix` / `--goal-suffix-file`), or the
+/// built-in test-first instructions. Empty when disabled.
+fn auto_drive_goal_suffix(config: &Config) -> &str {
+    config
+        .auto_drive
+        .goal_suffix
+        .as_deref()
+        .unwrap_or(AUTO_DRIVE_TEST_SUFFIX)
+        .trim()
 }
 
-fn append_auto_drive_test_suffix(goal: &str) -> String {
+/// The operator's goal without the suffix appended by [`append_goal_suffix`].
+fn goal_without_suffix<'a>(goal: &'a str, config: &Config) -> &'a str {
+    goal.strip_suffix(auto_drive_goal_suffix(config))
+        .unwrap_or(goal)
+        .trim()
+}
+
+fn append_goal_suffix(goal: &str, suffix: &str) -> String {
     let trimmed_goal = goal.trim();
+    if suffix.is_empty() {
+        return trimmed_goal.to_string();
+    }
     if trimmed_goal.is_empty() {
-        return AUTO_DRIVE_TEST_SUFFIX.to_string();
+        return suffix.to_string();
     }
 
-    format!("{trimmed_goal}\n\n{AUTO_DRIVE_TEST_SUFFIX}")
+    format!("{trimmed_goal}\n\n{suffix}")
 }
 
 fn build_auto_prompt(
@@ -848,6 +2529,12 @@ fn build_auto_prompt(
             if let Some(models) = action.models.as_ref().filter(|list| !list.is_empty()) {
                 lines.push(format!("models: {}", models.join(", ")));
             }
+
+            if let Some(secs) = action.timeout_seconds {
+                lines.push(format!(
+                    "timeout_seconds: {secs} — pass it to agent.create; the agent is cancelled after that and its partial output becomes its result"
+                ));
+            }
         }
 
         let timing_line = match agents_timing {
@@ -887,15 +2574,15 @@ fn make_assistant_message(text: String) -> ResponseItem {
 async fn submit_and_wait(
     conversation: &Arc<CodexConversation>,
     event_processor: &mut dyn EventProcessor,
+    run_guard: &mut RunGuard,
+    exit_tracker: &mut ExitTracker,
+    observe: &mut (dyn FnMut(&EventMsg) + Send),
+    attachments: Vec<InputItem>,
     prompt_text: String,
 ) -> anyhow::Result<TurnResult> {
-    let mut error_seen = false;
-
-    let submit_id = conversation
-        .submit(Op::UserInput {
-            items: vec![InputItem::Text { text: prompt_text }],
-        })
-        .await?;
+    let mut items = attachments;
+    items.push(InputItem::Text { text: prompt_text });
+    let submit_id = conversation.submit(Op::UserInput { items }).await?;
 
     loop {
         tokio::select! {
@@ -903,11 +2590,27 @@ async fn submit_and_wait(
                 let _ = conversation.submit(Op::Interrupt).await;
                 return Err(anyhow::anyhow!("Interrupted"));
             }
+            limit = run_guard.deadline_reached() => {
+                run_guard.disarm();
+                stop_for_run_limit(conversation, event_processor, limit).await;
+                return Ok(TurnResult {
+                    last_agent_message: None,
+                    limit_hit: Some(limit),
+                });
+            }
             res = conversation.next_event() => {
                 let event = res?;
                 let event_id = event.id.clone();
-                if matches!(event.msg, EventMsg::Error(_)) {
-                    error_seen = true;
+                exit_tracker.observe(&event.msg);
+                observe(&event.msg);
+                if matches!(event.msg, EventMsg::TaskStarted)
+                    && let Some(limit) = run_guard.on_turn_started()
+                {
+                    stop_for_run_limit(conversation, event_processor, limit).await;
+                    return Ok(TurnResult {
+                        last_agent_message: None,
+                        limit_hit: Some(limit),
+                    });
                 }
 
                 let last_agent_message = if let EventMsg::TaskComplete(TaskCompleteEvent { last_agent_message }) = &event.msg {
@@ -921,14 +2624,14 @@ async fn submit_and_wait(
                 if matches!(status, CodexStatus::Shutdown) {
                     return Ok(TurnResult {
                         last_agent_message: None,
-                        error_seen,
+                        limit_hit: None,
                     });
                 }
 
                 if last_agent_message.is_some() && event_id == submit_id {
                     return Ok(TurnResult {
                         last_agent_message,
-                        error_seen,
+                        limit_hit: None,
                     });
                 }
             }
@@ -936,6 +2639,29 @@ async fn submit_and_wait(
     }
 }
 
+/// Prints the first request that core sends to the model and, for
+/// `--dry-run`, stops it before it is authorized or sent. The body goes to
+/// stdout so it can be piped; endpoint and headers go to stderr.
+fn print_prompt_tap(format: PrintPromptFormat, dry_run: bool) -> RequestTap {
+    let tap = RequestTap::new(move |request| {
+        let rendered = match format {
+            PrintPromptFormat::Pretty => serde_json::to_string_pretty(&request.body),
+            PrintPromptFormat::Raw => serde_json::to_string(&request.body),
+        };
+        match rendered {
+            Ok(rendered) => {
+                eprintln!("First request to {}:", request.endpoint);
+                for (name, value) in &request.headers {
+                    eprintln!("  {name}: {value}");
+                }
+                out_println!("{rendered}");
+            }
+            Err(err) => eprintln!("Failed to render request payload: {err}"),
+        }
+    });
+    if dry_run { tap.dry_run() } else { tap }
+}
+
 fn load_output_schema(path: Option<PathBuf>) -> Option<Value> {
     let path = path?;
 
@@ -950,8 +2676,8 @@ fn load_output_schema(path: Option<PathBuf>) -> Option<Value> {
         }
     };
 
-    match serde_json::from_str::<Value>(&schema_str) {
-        Ok(value) => Some(value),
+    let schema = match serde_json::from_str::<Value>(&schema_str) {
+        Ok(value) => value,
         Err(err) => {
             eprintln!(
                 "Output schema file {} is not valid JSON: {err}",
@@ -959,7 +2685,15 @@ fn load_output_schema(path: Option<PathBuf>) -> Option<Value> {
             );
             std::process::exit(1);
         }
+    };
+    if let Err(err) = output_schema::check_schema(&schema) {
+        eprintln!(
+            "Output schema file {} is not a valid JSON Schema: {err}",
+            path.display()
+        );
+        std::process::exit(1);
     }
+    Some(schema)
 }
 
 #[cfg(test)]
@@ -973,6 +2707,7 @@ mod tests {
 
     use code_core::config::ConfigOverrides;
     use code_core::config::ConfigToml;
+    use code_core::config_types::ModelPrice;
     use code_protocol::mcp_protocol::ConversationId;
     use code_protocol::models::ContentItem;
     use code_protocol::models::ResponseItem;
@@ -1116,6 +2851,8 @@ mod tests {
         let args = crate::cli::ResumeArgs {
             session_id: None,
             last: true,
+            fork: false,
+            from_checkpoint: false,
             prompt: None,
         };
         let path = resolve_resume_path(&config, &args)
@@ -1147,6 +2884,8 @@ mod tests {
         let args = crate::cli::ResumeArgs {
             session_id: Some("cccccccc".to_string()),
             last: false,
+            fork: false,
+            from_checkpoint: false,
             prompt: None,
         };
 
@@ -1201,6 +2940,8 @@ mod tests {
         let args = crate::cli::ResumeArgs {
             session_id: None,
             last: true,
+            fork: false,
+            from_checkpoint: false,
             prompt: None,
         };
         let path = resolve_resume_path(&config, &args)
@@ -1214,4 +2955,101 @@ mod tests {
             path_str
         );
     }
+
+    #[test]
+    fn intervention_replies_approve_stop_or_guide() {
+        assert_eq!(
+            parse_intervention_reply("Yes\n"),
+            InterventionReply::Approve
+        );
+        assert_eq!(parse_intervention_reply("\n"), InterventionReply::Stop);
+        assert_eq!(parse_intervention_reply("n"), InterventionReply::Stop);
+        assert_eq!(
+            parse_intervention_reply("  only touch src/lib.rs, skip the docs\n"),
+            InterventionReply::Guidance("only touch src/lib.rs, skip the docs".to_string())
+        );
+    }
+
+    #[test]
+    fn goal_suffix_follows_config_and_can_be_disabled() {
+        let temp = TempDir::new().unwrap();
+        let mut config = test_config(temp.path());
+        assert_eq!(auto_drive_goal_suffix(&config), AUTO_DRIVE_TEST_SUFFIX);
+        assert_eq!(
+            append_goal_suffix(" Ship it ", auto_drive_goal_suffix(&config)),
+            format!("Ship it\n\n{AUTO_DRIVE_TEST_SUFFIX}")
+        );
+
+        config.auto_drive.goal_suffix = Some("Only read files.\n".to_string());
+        assert_eq!(
+            append_goal_suffix("Audit the parser", auto_drive_goal_suffix(&config)),
+            "Audit the parser\n\nOnly read files."
+        );
+
+        config.auto_drive.goal_suffix = Some(String::new());
+        assert_eq!(
+            append_goal_suffix("Survey the codebase", auto_drive_goal_suffix(&config)),
+            "Survey the codebase"
+        );
+    }
+
+    #[test]
+    fn compaction_estimates_show_before_after_and_threshold() {
+        let mut estimate = CompactionEstimate {
+            before_tokens: 182_000,
+            after_tokens: 41_000,
+            threshold_tokens: Some(163_200),
+        };
+        assert_eq!(
+            format_compaction_estimate(&estimate),
+            "history compacted: ~182000 -> ~41000 tokens (threshold ~163200)"
+        );
+        estimate.threshold_tokens = None;
+        assert_eq!(
+            format_compaction_estimate(&estimate),
+            "history compacted: ~182000 -> ~41000 tokens"
+        );
+    }
+
+    #[test]
+    fn backlog_goals_are_budgeted_from_their_own_baseline() {
+        let code_home = TempDir::new().unwrap();
+        let mut config = test_config(code_home.path());
+        config.model = "gpt-5".to_string();
+        config.model_prices.insert(
+            "gpt-5".to_string(),
+            ModelPrice {
+                input: 1.0,
+                cached_input: None,
+                output: 10.0,
+            },
+        );
+        let usage = |input: u64, output: u64| TokenUsage {
+            input_tokens: input,
+            cached_input_tokens: 0,
+            output_tokens: output,
+            reasoning_output_tokens: 0,
+            total_tokens: input + output,
+        };
+        let run_usage = RunUsage::new(&config.model);
+
+        let first_goal = run_usage.baseline();
+        run_usage.set(&usage(1_000_000, 100_000));
+        run_usage.add_coordinator("coordinator", &usage(50_000, 0));
+        assert_eq!(
+            goal_spend(&config, &run_usage, &first_goal),
+            (1_100_000, Some(2.0))
+        );
+
+        // The second goal shares the session, whose totals keep growing, but
+        // is only charged for what it used.
+        let second_goal = run_usage.baseline();
+        run_usage.set(&usage(1_500_000, 150_000));
+        run_usage.add_worker(&usage(100_000, 0));
+        run_usage.add_coordinator("gpt-5", &usage(500_000, 0));
+        assert_eq!(
+            goal_spend(&config, &run_usage, &second_goal),
+            (650_000, Some(1.6))
+        );
+    }
 }
@@ -0,0 +1,191 @@
+//! Validation of the final agent message against `--output-schema`.
+//!
+//! Schemas are checked with the `jsonschema` crate, which implements the
+//! whole specification (the draft comes from `$schema`, defaulting to
+//! 2020-12). `$ref`s must point into the schema itself; remote references
+//! are not fetched.
+
+use serde::Serialize;
+use serde_json::Value;
+
+/// A single mismatch between the agent's output and the schema.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct SchemaViolation {
+    /// JSON pointer-like location of the offending value (`$` is the root).
+    pub path: String,
+    pub message: String,
+}
+
+/// Structured report printed when retries are exhausted.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct SchemaValidationReport {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub attempts: i32,
+    pub errors: Vec<SchemaViolation>,
+}
+
+impl SchemaValidationReport {
+    pub fn new(attempts: i32, errors: Vec<SchemaViolation>) -> Self {
+        Self {
+            kind: "output_schema.validation_failed",
+            attempts,
+            errors,
+        }
+    }
+}
+
+/// Parses `message` as JSON and validates it against `schema`.
+pub(crate) fn validate_message(schema: &Value, message: Option<&str>) -> Vec<SchemaViolation> {
+    let Some(message) = message.map(str::trim).filter(|m| !m.is_empty()) else {
+        return vec![violation("$", "agent produced no final message")];
+    };
+    let value = match serde_json::from_str::<Value>(strip_code_fence(message)) {
+        Ok(value) => value,
+        Err(err) => return vec![violation("$", format!("output is not valid JSON: {err}"))],
+    };
+    let validator = match jsonschema::validator_for(schema) {
+        Ok(validator) => validator,
+        Err(err) => return vec![violation("$", format!("invalid output schema: {err}"))],
+    };
+    let mut errors: Vec<SchemaViolation> = validator
+        .iter_errors(&value)
+        .map(|err| {
+            violation(
+                &display_path(&value, &err.instance_path.to_string()),
+                err.to_string(),
+            )
+        })
+        .collect();
+    // Object key order depends on serde_json features; keep reports stable.
+    errors.sort_by(|a, b| a.path.cmp(&b.path));
+    errors
+}
+
+/// Checks that `schema` is itself a valid JSON Schema.
+pub(crate) fn check_schema(schema: &Value) -> Result<(), String> {
+    jsonschema::validator_for(schema)
+        .map(|_| ())
+        .map_err(|err| err.to_string())
+}
+
+/// Builds the follow-up prompt that asks the agent to fix its output.
+pub(crate) fn retry_prompt(errors: &[SchemaViolation]) -> String {
+    let details = errors
+        .iter()
+        .map(|err| format!("- {}: {}", err.path, err.message))
+        .collect::<Vec<_>>()
+        .join("\n");
+    format!(
+        "Your final response did not match the required output JSON schema:\n{details}\n\nReply again with only the corrected JSON document."
+    )
+}
+
+pub(crate) fn strip_code_fence(message: &str) -> &str {
+    let Some(rest) = message.strip_prefix("```") else {
+        return message;
+    };
+    let rest = rest.split_once('\n').map_or("", |(_, body)| body);
+    rest.trim_end().strip_suffix("```").unwrap_or(rest).trim()
+}
+
+fn violation(path: &str, message: impl Into<String>) -> SchemaViolation {
+    SchemaViolation {
+        path: path.to_string(),
+        message: message.into(),
+    }
+}
+
+/// Renders a JSON pointer into `value` as `$.key[index]`.
+fn display_path(value: &Value, pointer: &str) -> String {
+    let mut path = "$".to_string();
+    let mut current = Some(value);
+    for token in pointer.split('/').skip(1) {
+        let token = token.replace("~1", "/").replace("~0", "~");
+        match (current, token.parse::<usize>()) {
+            (Some(Value::Array(items)), Ok(index)) => {
+                path.push_str(&format!("[{index}]"));
+                current = items.get(index);
+            }
+            _ => {
+                path.push('.');
+                path.push_str(&token);
+                current = current.and_then(|value| value.get(&token));
+            }
+        }
+    }
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "summary": { "type": "string", "minLength": 1 },
+                "files": { "type": "array", "items": { "$ref": "#/$defs/file" } },
+                "status": { "enum": ["ok", "failed"] }
+            },
+            "required": ["summary", "status"],
+            "additionalProperties": false,
+            "$defs": {
+                "file": { "type": "string" }
+            }
+        })
+    }
+
+    #[test]
+    fn accepts_matching_output_in_code_fence() {
+        let message = "```json\n{\"summary\":\"done\",\"files\":[\"a.rs\"],\"status\":\"ok\"}\n```";
+        assert_eq!(validate_message(&schema(), Some(message)), Vec::new());
+    }
+
+    #[test]
+    fn reports_every_violation_with_its_path() {
+        let message = r#"{"summary":"","files":["a.rs",3],"status":"maybe","extra":true}"#;
+        let errors = validate_message(&schema(), Some(message));
+        let paths: Vec<&str> = errors.iter().map(|err| err.path.as_str()).collect();
+        assert_eq!(paths, vec!["$", "$.files[1]", "$.status", "$.summary"]);
+        assert!(errors[0].message.contains("extra"), "{errors:?}");
+        assert!(errors[2].message.contains("maybe"), "{errors:?}");
+    }
+
+    #[test]
+    fn one_of_requires_exactly_one_matching_branch() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "value": { "oneOf": [{ "type": "integer" }, { "type": "number" }] }
+            }
+        });
+        assert_eq!(
+            validate_message(&schema, Some(r#"{"value":2.5}"#)),
+            Vec::new()
+        );
+        let errors = validate_message(&schema, Some(r#"{"value":3}"#));
+        let paths: Vec<&str> = errors.iter().map(|err| err.path.as_str()).collect();
+        assert_eq!(paths, vec!["$.value"]);
+    }
+
+    #[test]
+    fn rejects_invalid_schemas() {
+        assert!(check_schema(&schema()).is_ok());
+        assert!(check_schema(&json!({ "type": 12 })).is_err());
+    }
+
+    #[test]
+    fn rejects_non_json_and_missing_output() {
+        assert_eq!(
+            validate_message(&schema(), None),
+            vec![violation("$", "agent produced no final message")]
+        );
+        let errors = validate_message(&schema(), Some("all done!"));
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].message.starts_with("output is not valid JSON"));
+    }
+}
@@ -0,0 +1,174 @@
+//! Prompt templates (`--template FILE --var key=value`).
+//!
+//! Templates are rendered with minijinja, so the usual Jinja syntax applies:
+//! `{{ name }}` is the value passed with `--var name=...`, `{{ env.NAME }}`
+//! the environment variable `NAME`, and `{% if %}` / `{% for %}` blocks work
+//! on both. Rendering is strict: using an unknown variable or an unset
+//! environment variable is an error (test for them with `is defined`), so a
+//! typo never reaches the model as an empty string. Write `{{ "{{" }}` or
+//! wrap text in `{% raw %}` for a literal `{{`.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::Context;
+use minijinja::Environment;
+use minijinja::ErrorKind;
+use minijinja::UndefinedBehavior;
+use minijinja::Value;
+
+/// One `--var key=value` pair.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateVar {
+    pub key: String,
+    pub value: String,
+}
+
+impl FromStr for TemplateVar {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let Some((key, value)) = value.split_once('=') else {
+            return Err(format!("expected key=value, got `{value}`"));
+        };
+        let key = key.trim();
+        if key.is_empty() || key == "env" || key.starts_with("env.") {
+            return Err(format!("invalid template variable name `{key}`"));
+        }
+        Ok(Self {
+            key: key.to_string(),
+            value: value.to_string(),
+        })
+    }
+}
+
+/// Reads and renders the template at `path`.
+pub(crate) fn render_file(path: &Path, vars: &[TemplateVar]) -> anyhow::Result<String> {
+    let template = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read --template {}", path.display()))?;
+    let env = std::env::vars_os()
+        .filter_map(|(name, value)| Some((name.into_string().ok()?, value.into_string().ok()?)));
+    render(&path.display().to_string(), &template, vars, env)
+        .with_context(|| format!("failed to render --template {}", path.display()))
+}
+
+/// Later `--var` flags override earlier ones with the same key.
+fn render(
+    name: &str,
+    template: &str,
+    vars: &[TemplateVar],
+    env: impl IntoIterator<Item = (String, String)>,
+) -> anyhow::Result<String> {
+    let mut engine = Environment::new();
+    engine.set_undefined_behavior(UndefinedBehavior::Strict);
+    engine.set_keep_trailing_newline(true);
+    engine.add_template(name, template)?;
+    let template = engine.get_template(name)?;
+
+    let env: BTreeMap<String, String> = env.into_iter().collect();
+    let mut context: BTreeMap<String, Value> = vars
+        .iter()
+        .map(|var| (var.key.clone(), Value::from(var.value.as_str())))
+        .collect();
+    context.insert("env".to_string(), Value::from_serialize(&env));
+    match template.render(&context) {
+        Ok(rendered) => Ok(rendered),
+        Err(err) if err.kind() == ErrorKind::UndefinedError => {
+            // minijinja only says where the value is missing; name it.
+            let mut undeclared: Vec<String> =
+                template.undeclared_variables(true).into_iter().collect();
+            undeclared.sort();
+            for variable in undeclared {
+                match variable.split_once('.') {
+                    Some(("env", name)) if !env.contains_key(name) => {
+                        anyhow::bail!("environment variable `{name}` is not set");
+                    }
+                    Some(("env", _)) => {}
+                    _ => {
+                        let root = variable.split('.').next().unwrap_or(&variable);
+                        if !context.contains_key(root) {
+                            anyhow::bail!(
+                                "unknown template variable `{root}`; pass --var {root}=..."
+                            );
+                        }
+                    }
+                }
+            }
+            Err(err.into())
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn var(pair: &str) -> TemplateVar {
+        pair.parse().unwrap()
+    }
+
+    fn env(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn substitutes_vars_and_env() {
+        let vars = [var("crate=code-core"), var("issue=42"), var("issue=43")];
+
+        let rendered = render(
+            "t",
+            "Fix #{{ issue }} in {{crate}} for {{ env.USER }}. Keep {{ '{{' }} braces }}.\n",
+            &vars,
+            env(&[("USER", "ci")]),
+        )
+        .unwrap();
+
+        assert_eq!(
+            rendered,
+            "Fix #43 in code-core for ci. Keep {{ braces }}.\n"
+        );
+    }
+
+    #[test]
+    fn supports_conditionals_and_loops() {
+        let template = "{% if draft %}Draft. {% endif %}{% if env.CI is defined %}CI. {% endif %}{% for name in [first, env.SECOND] %}[{{ name }}]{% endfor %}";
+
+        let rendered = render(
+            "t",
+            template,
+            &[var("draft="), var("first=a")],
+            env(&[("SECOND", "b")]),
+        )
+        .unwrap();
+
+        assert_eq!(rendered, "[a][b]");
+    }
+
+    #[test]
+    fn reports_missing_values_and_bad_vars() {
+        let err = render("t", "line one\nFix {{ target }}", &[], env(&[])).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unknown template variable `target`; pass --var target=..."
+        );
+        let err = render("t", "{{ env.HOME }}", &[], env(&[])).unwrap_err();
+        assert_eq!(err.to_string(), "environment variable `HOME` is not set");
+        assert!(render("t", "{{ oops", &[], env(&[])).is_err());
+
+        assert_eq!(
+            "novalue".parse::<TemplateVar>(),
+            Err("expected key=value, got `novalue`".to_string())
+        );
+        assert_eq!(
+            "env.PATH=x".parse::<TemplateVar>(),
+            Err("invalid template variable name `env.PATH`".to_string())
+        );
+        assert_eq!(var("note=a=b").value, "a=b");
+    }
+}
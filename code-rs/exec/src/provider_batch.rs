@@ -0,0 +1,272 @@
+//! `code exec --batch MANIFEST`: send a manifest's prompts through the
+//! provider's Batch API instead of running one session per prompt.
+//!
+//! Batched requests cost half as much but are answered offline, so each
+//! prompt gets a single model response with no tool calls. The manifest is
+//! the same file `code exec batch` reads; only `id`, `prompt`, `model` and
+//! `output` apply here. Results are rendered through the selected event
+//! processor once the batch completes.
+
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use anyhow::Context;
+use code_core::AuthManager;
+use code_core::ModelClient;
+use code_core::Prompt;
+use code_core::batch::BatchJob;
+use code_core::batch::BatchRequest;
+use code_core::batch::BatchStatus;
+use code_core::config::Config;
+use code_core::debug_logger::DebugLogger;
+use code_core::protocol::AgentMessageEvent;
+use code_core::protocol::ErrorEvent;
+use code_core::protocol::Event;
+use code_core::protocol::EventMsg;
+use code_core::protocol::TaskCompleteEvent;
+use code_core::protocol::TokenCountEvent;
+use code_core::protocol::TokenUsageInfo;
+use code_protocol::models::ContentItem;
+use code_protocol::models::ResponseItem;
+use uuid::Uuid;
+
+use crate::batch::BatchManifest;
+use crate::batch::load_manifest;
+use crate::event_processor::CodexStatus;
+use crate::event_processor::EventProcessor;
+use crate::event_processor::handle_last_message;
+
+/// Delay between status checks. Batches usually take minutes to hours.
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+pub(crate) async fn run_provider_batch(
+    manifest_path: &Path,
+    config: &Config,
+    mut event_processor: Box<dyn EventProcessor>,
+) -> anyhow::Result<()> {
+    let manifest = load_manifest(manifest_path)?;
+    let base_dir = manifest_path
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    let requests = build_requests(&manifest, config);
+    let client = build_model_client(config)?;
+
+    let job = client
+        .submit_batch(&requests)
+        .await
+        .context("failed to submit batch")?;
+    eprintln!(
+        "[batch] submitted {} prompt(s) as {}",
+        requests.len(),
+        job.id
+    );
+
+    let poll = loop {
+        let poll = client
+            .poll_batch(&job.id)
+            .await
+            .with_context(|| format!("failed to poll batch {}", job.id))?;
+        if poll.job.status.is_terminal() {
+            break poll;
+        }
+        eprintln!("[batch] {}", progress_line(&poll.job));
+        tokio::time::sleep(POLL_INTERVAL).await;
+    };
+    eprintln!("[batch] {}", progress_line(&poll.job));
+    if poll.job.status != BatchStatus::Completed {
+        anyhow::bail!("batch {} ended as {:?}", poll.job.id, poll.job.status);
+    }
+
+    let mut token_info = None;
+    let mut last_message = None;
+    let mut failures = 0;
+    for (index, entry) in manifest.prompts.iter().enumerate() {
+        let custom_id = custom_id(index, entry.id.as_deref());
+        let result = poll
+            .results
+            .iter()
+            .find(|result| result.custom_id == custom_id);
+        let msg = match result.map(|result| &result.outcome) {
+            Some(Ok(output)) => {
+                let message = agent_message(&output.items);
+                if let Some(path) = entry.output.as_ref() {
+                    handle_last_message(Some(&message), &base_dir.join(path)).await;
+                }
+                token_info = TokenUsageInfo::new_or_append(
+                    &token_info,
+                    &output.token_usage,
+                    config.model_context_window,
+                );
+                last_message = Some(message.clone());
+                EventMsg::AgentMessage(AgentMessageEvent {
+                    message: format!("[{custom_id}]\n{message}"),
+                })
+            }
+            Some(Err(error)) => {
+                failures += 1;
+                EventMsg::Error(ErrorEvent {
+                    message: format!("[{custom_id}] {error}"),
+                })
+            }
+            None => {
+                failures += 1;
+                EventMsg::Error(ErrorEvent {
+                    message: format!("[{custom_id}] no result returned"),
+                })
+            }
+        };
+        if emit(&mut *event_processor, msg) {
+            return Ok(());
+        }
+    }
+
+    let _ = emit(
+        &mut *event_processor,
+        EventMsg::TokenCount(TokenCountEvent {
+            info: token_info,
+            rate_limits: None,
+        }),
+    );
+    let _ = emit(
+        &mut *event_processor,
+        EventMsg::TaskComplete(TaskCompleteEvent {
+            last_agent_message: last_message,
+        }),
+    );
+    let _ = emit(&mut *event_processor, EventMsg::ShutdownComplete);
+    if failures > 0 {
+        anyhow::bail!(
+            "{failures} of {} batch request(s) failed",
+            manifest.prompts.len()
+        );
+    }
+    Ok(())
+}
+
+fn build_requests(manifest: &BatchManifest, config: &Config) -> Vec<BatchRequest> {
+    manifest
+        .prompts
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| BatchRequest {
+            custom_id: custom_id(index, entry.id.as_deref()),
+            prompt: Prompt {
+                input: vec![ResponseItem::Message {
+                    id: None,
+                    role: "user".to_string(),
+                    content: vec![ContentItem::InputText {
+                        text: entry.prompt.clone(),
+                    }],
+                }],
+                user_instructions: config.user_instructions.clone(),
+                model_override: entry.model.clone(),
+                ..Prompt::default()
+            },
+        })
+        .collect()
+}
+
+fn build_model_client(config: &Config) -> anyhow::Result<ModelClient> {
+    let auth_manager = AuthManager::shared_with_mode_and_originator(
+        config.code_home.clone(),
+        code_protocol::mcp_protocol::AuthMode::ApiKey,
+        config.responses_originator_header.clone(),
+    );
+    let debug_logger = DebugLogger::new(config.debug)
+        .or_else(|_| DebugLogger::new(false))
+        .context("initializing debug logger")?;
+    Ok(ModelClient::new(
+        Arc::new(config.clone()),
+        Some(auth_manager),
+        None,
+        config.model_provider.clone(),
+        config.model_reasoning_effort,
+        config.model_reasoning_summary,
+        config.model_text_verbosity,
+        Uuid::new_v4(),
+        Arc::new(Mutex::new(debug_logger)),
+    ))
+}
+
+/// Manifest ids label results; unnamed entries use their 1-based index.
+fn custom_id(index: usize, id: Option<&str>) -> String {
+    id.map_or_else(|| (index + 1).to_string(), str::to_string)
+}
+
+/// Text of the assistant messages in a batched response.
+fn agent_message(items: &[ResponseItem]) -> String {
+    items
+        .iter()
+        .filter_map(|item| match item {
+            ResponseItem::Message { role, content, .. } if role == "assistant" => Some(content),
+            _ => None,
+        })
+        .flatten()
+        .filter_map(|content| match content {
+            ContentItem::OutputText { text } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn progress_line(job: &BatchJob) -> String {
+    let counts = &job.request_counts;
+    format!(
+        "{} {:?}: {}/{} done, {} failed",
+        job.id, job.status, counts.completed, counts.total, counts.failed
+    )
+}
+
+/// Feeds `msg` to the processor; returns true once it asks to stop.
+fn emit(event_processor: &mut dyn EventProcessor, msg: EventMsg) -> bool {
+    matches!(
+        event_processor.process_event(Event {
+            id: String::new(),
+            event_seq: 0,
+            msg,
+            order: None,
+        }),
+        CodexStatus::Shutdown
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn unnamed_entries_use_their_position() {
+        assert_eq!(custom_id(0, None), "1");
+        assert_eq!(custom_id(4, Some("docs")), "docs");
+    }
+
+    #[test]
+    fn joins_assistant_text_only() {
+        let items = vec![
+            ResponseItem::Reasoning {
+                id: "r1".to_string(),
+                summary: Vec::new(),
+                content: None,
+                encrypted_content: None,
+            },
+            ResponseItem::Message {
+                id: None,
+                role: "assistant".to_string(),
+                content: vec![
+                    ContentItem::OutputText {
+                        text: "first".to_string(),
+                    },
+                    ContentItem::OutputText {
+                        text: "second".to_string(),
+                    },
+                ],
+            },
+        ];
+        assert_eq!(agent_message(&items), "first\nsecond");
+    }
+}
@@ -0,0 +1,84 @@
+//! Paced playback shared by `code exec replay` and `code exec auto replay`.
+
+use std::ops::ControlFlow;
+use std::time::Duration;
+
+/// Hands each `(offset_ms, item)` to `emit` in order, waiting for the recorded
+/// gap between offsets scaled by `speed`. Stops as soon as `emit` breaks.
+pub(crate) async fn replay_paced<T>(
+    items: impl IntoIterator<Item = (i64, T)>,
+    speed: f64,
+    mut emit: impl FnMut(T) -> ControlFlow<()>,
+) -> ControlFlow<()> {
+    let mut previous_offset = 0;
+    for (offset_ms, item) in items {
+        let gap_ms = (offset_ms - previous_offset).max(0);
+        previous_offset = offset_ms;
+        if let Some(delay) = scaled_delay(gap_ms, speed) {
+            tokio::time::sleep(delay).await;
+        }
+        if emit(item).is_break() {
+            return ControlFlow::Break(());
+        }
+    }
+    ControlFlow::Continue(())
+}
+
+/// Parses `--speed`: a finite number, `0` or greater.
+pub(crate) fn parse_speed(raw: &str) -> Result<f64, String> {
+    let speed: f64 = raw.parse().map_err(|err| format!("{err}"))?;
+    if speed.is_finite() && speed >= 0.0 {
+        Ok(speed)
+    } else {
+        Err("speed must be a finite number, 0 or greater".to_string())
+    }
+}
+
+/// `gap_ms` divided by `speed`; `None` when there is nothing to wait for,
+/// `speed` is `0`, or the delay does not fit in a [`Duration`].
+fn scaled_delay(gap_ms: i64, speed: f64) -> Option<Duration> {
+    if speed <= 0.0 || gap_ms <= 0 {
+        return None;
+    }
+    Duration::try_from_secs_f64(gap_ms as f64 / 1000.0 / speed).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn delay_scales_with_speed() {
+        assert_eq!(scaled_delay(1000, 2.0), Some(Duration::from_millis(500)));
+        assert_eq!(scaled_delay(1000, 0.0), None);
+        assert_eq!(scaled_delay(0, 1.0), None);
+        assert_eq!(scaled_delay(1000, f64::NAN), None);
+        assert_eq!(scaled_delay(1000, 1e-300), None);
+    }
+
+    #[test]
+    fn speed_must_be_finite_and_not_negative() {
+        assert_eq!(parse_speed("0"), Ok(0.0));
+        assert_eq!(parse_speed("2.5"), Ok(2.5));
+        for raw in ["nan", "inf", "-1", "fast"] {
+            assert!(parse_speed(raw).is_err(), "{raw} should be rejected");
+        }
+    }
+
+    #[tokio::test]
+    async fn stops_when_emit_breaks() {
+        let mut seen = Vec::new();
+        let flow = replay_paced([(0, 1), (10, 2), (20, 3)], 0.0, |item| {
+            seen.push(item);
+            if item == 2 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        })
+        .await;
+        assert_eq!(flow, ControlFlow::Break(()));
+        assert_eq!(seen, vec![1, 2]);
+    }
+}
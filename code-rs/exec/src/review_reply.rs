@@ -0,0 +1,303 @@
+//! `code exec review-reply`: answer a pull request review thread.
+//!
+//! The thread is fetched from the GitHub REST API and turned into a prompt
+//! for an ordinary exec run whose final message must match [`output_schema`].
+//! The run is always read-only with no network access. The resulting reply,
+//! with the optional patch folded into it, is printed, and posted back to the
+//! thread only when `--post` is given.
+
+use std::path::Path;
+
+use anyhow::Context;
+use code_core::git_info::collect_git_info;
+use code_core::github::GitHubClient;
+use code_core::github::parse_owner_repo;
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::Value;
+use serde_json::json;
+
+use crate::cli::ReviewReplyArgs;
+use crate::output_schema::strip_code_fence;
+
+const PAGE_SIZE: usize = 100;
+
+/// A review thread and the pull request it belongs to.
+#[derive(Debug, Clone)]
+pub(crate) struct ReviewThread {
+    pub owner: String,
+    pub repo: String,
+    pub pr: u64,
+    pub pr_title: String,
+    pub pr_body: Option<String>,
+    /// Id of the comment that started the thread; replies are posted to it.
+    pub root_id: u64,
+    /// Comments in the order they were posted, root first.
+    pub comments: Vec<ReviewComment>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct ReviewComment {
+    pub id: u64,
+    #[serde(default)]
+    pub in_reply_to_id: Option<u64>,
+    #[serde(default)]
+    pub user: Option<GitHubUser>,
+    pub body: String,
+    #[serde(default)]
+    pub path: Option<String>,
+    #[serde(default)]
+    pub line: Option<u64>,
+    #[serde(default)]
+    pub original_line: Option<u64>,
+    #[serde(default)]
+    pub diff_hunk: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct GitHubUser {
+    pub login: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PullRequest {
+    title: String,
+    #[serde(default)]
+    body: Option<String>,
+}
+
+/// The agent's final message.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub(crate) struct SuggestedReply {
+    pub reply: String,
+    pub patch: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ReplyRequest<'a> {
+    body: &'a str,
+}
+
+#[derive(Deserialize)]
+struct PostedComment {
+    html_url: String,
+}
+
+/// Fetches the thread containing comment `--thread` on pull request `--pr`.
+pub(crate) async fn fetch_thread(
+    args: &ReviewReplyArgs,
+    cwd: &Path,
+) -> anyhow::Result<ReviewThread> {
+    let (owner, repo) = match args.repo.as_deref() {
+        Some(slug) => slug
+            .split_once('/')
+            .filter(|(owner, repo)| !owner.is_empty() && !repo.is_empty())
+            .map(|(owner, repo)| (owner.to_string(), repo.to_string()))
+            .with_context(|| format!("--repo must be OWNER/NAME, got `{slug}`"))?,
+        None => collect_git_info(cwd)
+            .await
+            .and_then(|info| info.repository_url)
+            .as_deref()
+            .and_then(parse_owner_repo)
+            .context(
+                "could not determine the GitHub repository from `origin`; pass --repo OWNER/NAME",
+            )?,
+    };
+
+    let github = GitHubClient::from_env()?;
+    let pr_url = format!("/repos/{owner}/{repo}/pulls/{}", args.pr);
+    let pull: PullRequest = github.get(&pr_url).await?;
+
+    let mut all = Vec::new();
+    for page in 1.. {
+        let batch: Vec<ReviewComment> = github
+            .get(&format!(
+                "{pr_url}/comments?per_page={PAGE_SIZE}&page={page}"
+            ))
+            .await?;
+        let done = batch.len() < PAGE_SIZE;
+        all.extend(batch);
+        if done {
+            break;
+        }
+    }
+
+    let (root_id, comments) = thread_comments(all, args.thread).with_context(|| {
+        format!(
+            "review comment {} not found on {owner}/{repo}#{}",
+            args.thread, args.pr
+        )
+    })?;
+    Ok(ReviewThread {
+        owner,
+        repo,
+        pr: args.pr,
+        pr_title: pull.title,
+        pr_body: pull.body.filter(|body| !body.trim().is_empty()),
+        root_id,
+        comments,
+    })
+}
+
+/// Picks the thread `comment_id` belongs to out of a pull request's review
+/// comments. Returns the root id and the thread in posting order.
+fn thread_comments(
+    comments: Vec<ReviewComment>,
+    comment_id: u64,
+) -> Option<(u64, Vec<ReviewComment>)> {
+    let target = comments.iter().find(|comment| comment.id == comment_id)?;
+    let root_id = target.in_reply_to_id.unwrap_or(target.id);
+    let mut thread: Vec<ReviewComment> = comments
+        .into_iter()
+        .filter(|comment| comment.id == root_id || comment.in_reply_to_id == Some(root_id))
+        .collect();
+    thread.sort_by_key(|comment| (comment.id != root_id, comment.id));
+    Some((root_id, thread))
+}
+
+/// Schema the run's final message is validated against.
+pub(crate) fn output_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "reply": { "type": "string", "minLength": 1 },
+            "patch": { "type": ["string", "null"] }
+        },
+        "required": ["reply", "patch"],
+        "additionalProperties": false
+    })
+}
+
+pub(crate) fn build_prompt(thread: &ReviewThread) -> String {
+    let mut prompt = format!(
+        "You are helping the author of pull request {}/{}#{} (\"{}\") answer a code review thread. \
+         The working directory is a checkout of the pull request. Read the code the thread refers to before answering.\n",
+        thread.owner, thread.repo, thread.pr, thread.pr_title
+    );
+    if let Some(body) = thread.pr_body.as_deref() {
+        prompt.push_str(&format!("\nPull request description:\n{}\n", body.trim()));
+    }
+    if let Some(root) = thread.comments.first() {
+        if let Some(path) = root.path.as_deref() {
+            match root.line.or(root.original_line) {
+                Some(line) => prompt.push_str(&format!("\nFile: {path}:{line}\n")),
+                None => prompt.push_str(&format!("\nFile: {path}\n")),
+            }
+        }
+        if let Some(hunk) = root.diff_hunk.as_deref() {
+            prompt.push_str(&format!("\n```diff\n{}\n```\n", hunk.trim_end()));
+        }
+    }
+    prompt.push_str("\nThread:\n");
+    for comment in &thread.comments {
+        let author = comment
+            .user
+            .as_ref()
+            .map_or("unknown", |user| user.login.as_str());
+        prompt.push_str(&format!("\n@{author}:\n{}\n", comment.body.trim()));
+    }
+    prompt.push_str(
+        "\nWrite a reply to the latest comment. Be concise and specific: agree and say what will change, \
+         or explain why the code is correct. Do not modify files. If a code change resolves the feedback, \
+         put it in `patch` as a unified diff relative to the repository root; otherwise set `patch` to null.\n\
+         Respond with only a JSON object: {\"reply\": string, \"patch\": string | null}.",
+    );
+    prompt
+}
+
+/// Parses the final message and posts it to the thread, or prints it when
+/// `post` is false.
+pub(crate) async fn deliver(
+    thread: &ReviewThread,
+    final_message: Option<&str>,
+    post: bool,
+) -> anyhow::Result<()> {
+    let message = final_message.context("the run produced no final message")?;
+    let suggestion: SuggestedReply = serde_json::from_str(strip_code_fence(message.trim()))
+        .context("final message is not a valid review reply")?;
+    let body = reply_body(&suggestion);
+    if !post {
+        eprintln!("[review-reply] not posted; pass --post to reply on the thread:");
+        eprintln!("{body}");
+        return Ok(());
+    }
+    let github = GitHubClient::from_env()?;
+    let url = format!(
+        "/repos/{}/{}/pulls/{}/comments/{}/replies",
+        thread.owner, thread.repo, thread.pr, thread.root_id
+    );
+    let posted: PostedComment = github.post(&url, &ReplyRequest { body: &body }).await?;
+    eprintln!("[review-reply] posted {}", posted.html_url);
+    Ok(())
+}
+
+/// The comment body: the reply, with any patch in a collapsed diff block.
+fn reply_body(suggestion: &SuggestedReply) -> String {
+    let reply = suggestion.reply.trim();
+    match suggestion
+        .patch
+        .as_deref()
+        .map(str::trim)
+        .filter(|patch| !patch.is_empty())
+    {
+        Some(patch) => format!(
+            "{reply}\n\n<details>\n<summary>Suggested patch</summary>\n\n```diff\n{patch}\n```\n\n</details>"
+        ),
+        None => reply.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn comment(id: u64, in_reply_to_id: Option<u64>, body: &str) -> ReviewComment {
+        ReviewComment {
+            id,
+            in_reply_to_id,
+            user: Some(GitHubUser {
+                login: "reviewer".to_string(),
+            }),
+            body: body.to_string(),
+            path: Some("src/lib.rs".to_string()),
+            line: Some(12),
+            original_line: None,
+            diff_hunk: None,
+        }
+    }
+
+    #[test]
+    fn collects_thread_from_any_comment_in_it() {
+        let comments = vec![
+            comment(30, Some(10), "second reply"),
+            comment(10, None, "root"),
+            comment(20, None, "other thread"),
+            comment(25, Some(10), "first reply"),
+        ];
+
+        let (root_id, thread) = thread_comments(comments, 30).unwrap();
+
+        assert_eq!(root_id, 10);
+        assert_eq!(
+            thread
+                .iter()
+                .map(|comment| comment.body.as_str())
+                .collect::<Vec<_>>(),
+            vec!["root", "first reply", "second reply"]
+        );
+    }
+
+    #[test]
+    fn folds_patch_into_reply_body() {
+        let suggestion: SuggestedReply = serde_json::from_str(strip_code_fence(
+            "```json\n{\"reply\": \"Good catch, fixed.\", \"patch\": \"--- a/x\\n+++ b/x\\n\"}\n```",
+        ))
+        .unwrap();
+
+        assert_eq!(
+            reply_body(&suggestion),
+            "Good catch, fixed.\n\n<details>\n<summary>Suggested patch</summary>\n\n```diff\n--- a/x\n+++ b/x\n```\n\n</details>"
+        );
+    }
+}
@@ -0,0 +1,177 @@
+//! Re-rendering of recorded sessions (`code exec replay`).
+//!
+//! Rollout files persist the events a session emitted (minus streaming
+//! deltas). Replaying one feeds those events back through the selected event
+//! processor in their original order, paced by the recorded timestamps, without
+//! creating a conversation or contacting a model.
+
+use std::fs::File;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::ops::ControlFlow;
+use std::path::Path;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use chrono::DateTime;
+use code_core::SessionCatalog;
+use code_core::at_rest;
+use code_core::config::Config;
+use code_core::entry_to_rollout_path;
+use code_core::protocol::Event;
+use code_core::protocol::EventMsg;
+use code_core::protocol::recorded_event_from_protocol;
+use code_protocol::protocol::RolloutItem;
+use code_protocol::protocol::RolloutLine;
+
+use crate::cli::RolloutReplayArgs;
+use crate::event_processor::CodexStatus;
+use crate::event_processor::EventProcessor;
+use crate::replay::replay_paced;
+
+/// An event read from a rollout together with its offset from the first
+/// recorded event.
+#[derive(Debug)]
+struct ReplayedEvent {
+    offset_ms: i64,
+    event: Event,
+}
+
+pub(crate) async fn run_rollout_replay(
+    args: RolloutReplayArgs,
+    config: &Config,
+    mut event_processor: Box<dyn EventProcessor>,
+) -> anyhow::Result<()> {
+    let path = resolve_rollout(config, &args.rollout).await?;
+    let events = read_rollout_events(&config.code_home, &path)?;
+    if events.is_empty() {
+        anyhow::bail!("rollout {} has no recorded events", path.display());
+    }
+    eprintln!(
+        "[replay] {} event(s) from {} at {}x",
+        events.len(),
+        path.display(),
+        args.speed
+    );
+
+    let mut shutdown_seen = false;
+    let events = events
+        .into_iter()
+        .map(|replayed| (replayed.offset_ms, replayed.event));
+    let flow = replay_paced(events, args.speed, |event| {
+        shutdown_seen |= matches!(event.msg, EventMsg::ShutdownComplete);
+        if matches!(event_processor.process_event(event), CodexStatus::Shutdown) {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    })
+    .await;
+    if flow.is_break() {
+        return Ok(());
+    }
+
+    // Rollouts rarely end with ShutdownComplete; send one so processors that
+    // summarize at shutdown (JUnit) still produce their report.
+    if !shutdown_seen {
+        event_processor.process_event(Event {
+            id: String::new(),
+            event_seq: 0,
+            msg: EventMsg::ShutdownComplete,
+            order: None,
+        });
+    }
+    Ok(())
+}
+
+/// Treats `target` as a rollout path when it exists on disk, otherwise as a
+/// session id (or unique prefix) looked up in the session catalog.
+async fn resolve_rollout(config: &Config, target: &str) -> anyhow::Result<PathBuf> {
+    let candidate = PathBuf::from(target);
+    if candidate.is_file() {
+        return Ok(candidate);
+    }
+    let entry = SessionCatalog::new(config.code_home.clone())
+        .find_by_id(target)
+        .await
+        .context("failed to look up session by id")?
+        .with_context(|| format!("no rollout file or recorded session matches `{target}`"))?;
+    Ok(entry_to_rollout_path(&config.code_home, &entry))
+}
+
+fn read_rollout_events(code_home: &Path, path: &Path) -> anyhow::Result<Vec<ReplayedEvent>> {
+    let file =
+        File::open(path).with_context(|| format!("failed to open rollout {}", path.display()))?;
+    let mut start_ms = None;
+    let mut events = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let line = at_rest::open_line_in(code_home, &line)
+            .with_context(|| format!("failed to decrypt rollout {}", path.display()))?;
+        let rollout_line = match serde_json::from_str::<RolloutLine>(&line) {
+            Ok(rollout_line) => rollout_line,
+            Err(err) => {
+                tracing::warn!("skipping malformed rollout line: {err}");
+                continue;
+            }
+        };
+        let RolloutItem::Event(recorded) = rollout_line.item else {
+            continue;
+        };
+        let Some(recorded) = recorded_event_from_protocol(recorded) else {
+            continue;
+        };
+        let timestamp_ms = DateTime::parse_from_rfc3339(&rollout_line.timestamp)
+            .map(|ts| ts.timestamp_millis())
+            .ok();
+        let offset_ms = match (timestamp_ms, *start_ms.get_or_insert(timestamp_ms)) {
+            (Some(ts), Some(start)) => ts - start,
+            _ => 0,
+        };
+        events.push(ReplayedEvent {
+            offset_ms,
+            event: Event {
+                id: recorded.id,
+                event_seq: recorded.event_seq,
+                msg: recorded.msg,
+                order: recorded.order,
+            },
+        });
+    }
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::io::Write;
+
+    #[test]
+    fn reads_events_with_offsets_and_skips_other_items() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        for line in [
+            r#"{"timestamp":"2025-01-01T00:00:00.000Z","type":"response_item","payload":{"type":"message","role":"user","content":[{"type":"input_text","text":"hi"}]}}"#,
+            r#"{"timestamp":"2025-01-01T00:00:01.000Z","type":"event","payload":{"id":"1","event_seq":0,"msg":{"type":"agent_message","message":"hello"}}}"#,
+            r#"{"timestamp":"2025-01-01T00:00:03.500Z","type":"event","payload":{"id":"1","event_seq":1,"msg":{"type":"task_complete","last_agent_message":"hello"}}}"#,
+        ] {
+            writeln!(file, "{line}").unwrap();
+        }
+
+        let events = read_rollout_events(file.path().parent().unwrap(), file.path()).unwrap();
+        let summary: Vec<(i64, String)> = events
+            .iter()
+            .map(|replayed| (replayed.offset_ms, replayed.event.msg.to_string()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (0, "agent_message".to_string()),
+                (2500, "task_complete".to_string())
+            ]
+        );
+    }
+}
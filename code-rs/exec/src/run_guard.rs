@@ -0,0 +1,116 @@
+//! Wall-clock and turn-count ceilings for `code exec --timeout/--max-turns`.
+
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+/// The limit that stopped a run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RunLimit {
+    Timeout { seconds: i64 },
+    MaxTurns { turns: i32 },
+}
+
+impl RunLimit {
+    /// Stable machine-readable reason used in JSON output.
+    pub fn reason(&self) -> &'static str {
+        match self {
+            RunLimit::Timeout { .. } => "timeout",
+            RunLimit::MaxTurns { .. } => "max_turns",
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            RunLimit::Timeout { seconds } => format!("timed out after {seconds}s"),
+            RunLimit::MaxTurns { turns } => format!("reached the limit of {turns} turn(s)"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct RunGuard {
+    timeout_seconds: Option<i64>,
+    deadline: Option<Instant>,
+    max_turns: Option<i32>,
+    turns_started: i32,
+}
+
+impl RunGuard {
+    /// A timeout too far away to represent as an instant means no deadline.
+    pub fn new(timeout_seconds: Option<i64>, max_turns: Option<i32>) -> Self {
+        let deadline = timeout_seconds
+            .filter(|secs| *secs > 0)
+            .and_then(|secs| Instant::now().checked_add(Duration::from_secs(secs.unsigned_abs())));
+        Self {
+            timeout_seconds: timeout_seconds.filter(|_| deadline.is_some()),
+            deadline,
+            max_turns: max_turns.filter(|turns| *turns > 0),
+            turns_started: 0,
+        }
+    }
+
+    /// Records that a new turn started. Returns the limit once more turns
+    /// than `--max-turns` allows have been started.
+    pub fn on_turn_started(&mut self) -> Option<RunLimit> {
+        self.turns_started += 1;
+        let max = self.max_turns?;
+        (self.turns_started > max).then_some(RunLimit::MaxTurns { turns: max })
+    }
+
+    /// Turns started so far in this run.
+    pub fn turns_started(&self) -> u32 {
+        self.turns_started.unsigned_abs()
+    }
+
+    /// Resolves when the wall-clock deadline passes; never resolves when no
+    /// `--timeout` was given.
+    pub async fn deadline_reached(&self) -> RunLimit {
+        match (self.deadline, self.timeout_seconds) {
+            (Some(deadline), Some(seconds)) => {
+                tokio::time::sleep_until(deadline).await;
+                RunLimit::Timeout { seconds }
+            }
+            _ => std::future::pending().await,
+        }
+    }
+
+    /// Stops the deadline from firing again once it has been handled.
+    pub fn disarm(&mut self) {
+        self.deadline = None;
+        self.timeout_seconds = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn max_turns_trips_on_the_first_extra_turn() {
+        let mut guard = RunGuard::new(None, Some(2));
+        assert_eq!(guard.on_turn_started(), None);
+        assert_eq!(guard.on_turn_started(), None);
+        assert_eq!(
+            guard.on_turn_started(),
+            Some(RunLimit::MaxTurns { turns: 2 })
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn deadline_fires_after_timeout() {
+        let guard = RunGuard::new(Some(5), None);
+        assert_eq!(
+            guard.deadline_reached().await,
+            RunLimit::Timeout { seconds: 5 }
+        );
+    }
+
+    #[test]
+    fn unrepresentable_timeout_means_no_deadline() {
+        let guard = RunGuard::new(Some(i64::MAX), None);
+        assert_eq!(guard.deadline, None);
+        assert_eq!(guard.timeout_seconds, None);
+    }
+}
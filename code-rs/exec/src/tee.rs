@@ -0,0 +1,96 @@
+//! `--tee <path>`: mirror everything exec writes to stdout into a file.
+//!
+//! Output modes print through [`out_print!`] and [`out_println!`] instead of
+//! `print!`/`println!`. Each chunk is written to stdout and the tee file while
+//! holding one lock, so both sinks see the same bytes (ANSI styling included)
+//! in the same order. The file is unbuffered so it is complete even when the
+//! process exits early.
+
+use std::fmt;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+use std::sync::OnceLock;
+
+use anyhow::Context;
+
+static TEE: OnceLock<Tee> = OnceLock::new();
+
+/// Prints to stdout and, when `--tee` is set, to the tee file.
+macro_rules! out_print {
+    ($($arg:tt)*) => {
+        $crate::tee::write_stdout(format_args!($($arg)*))
+    };
+}
+
+/// [`out_print!`] with a trailing newline.
+macro_rules! out_println {
+    () => {
+        $crate::tee::write_stdout(format_args!("\n"))
+    };
+    ($($arg:tt)*) => {
+        $crate::tee::write_stdout(format_args!("{}\n", format_args!($($arg)*)))
+    };
+}
+
+/// Creates (or truncates) `path` and starts mirroring stdout into it.
+pub(crate) fn install(path: &Path) -> anyhow::Result<()> {
+    TEE.set(Tee::create(path)?)
+        .map_err(|_| anyhow::anyhow!("--tee is already active"))
+}
+
+pub(crate) fn write_stdout(args: fmt::Arguments<'_>) {
+    match TEE.get() {
+        Some(tee) => tee.write(args),
+        None => print!("{args}"),
+    }
+}
+
+/// Stdout mirrored into a file.
+struct Tee {
+    file: Mutex<File>,
+}
+
+impl Tee {
+    fn create(path: &Path) -> anyhow::Result<Self> {
+        let file = File::create(path)
+            .with_context(|| format!("failed to create --tee {}", path.display()))?;
+        Ok(Self {
+            file: Mutex::new(file),
+        })
+    }
+
+    fn write(&self, args: fmt::Arguments<'_>) {
+        let text = args.to_string();
+        let mut file = match self.file.lock() {
+            Ok(file) => file,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        print!("{text}");
+        if let Err(err) = file.write_all(text.as_bytes()) {
+            tracing::warn!("failed to write --tee output: {err}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn mirrors_stdout_chunks_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.log");
+        let tee = Tee::create(&path).unwrap();
+
+        tee.write(format_args!("\u{1b}[1mtee-bold\u{1b}[0m "));
+        tee.write(format_args!("tee-line {}\n", 1));
+
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "\u{1b}[1mtee-bold\u{1b}[0m tee-line 1\n"
+        );
+    }
+}
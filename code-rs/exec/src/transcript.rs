@@ -0,0 +1,374 @@
+//! Markdown transcript of an exec run (`--transcript-file`).
+//!
+//! The transcript is appended to as events arrive, so a run that is killed
+//! midway still leaves a readable file. Command and tool output is folded
+//! into `<details>` blocks to keep the transcript short enough to paste into
+//! a PR description or ticket.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use code_common::elapsed::format_duration;
+use code_core::config::Config;
+use code_core::protocol::AgentMessageEvent;
+use code_core::protocol::ErrorEvent;
+use code_core::protocol::Event;
+use code_core::protocol::EventMsg;
+use code_core::protocol::ExecCommandBeginEvent;
+use code_core::protocol::ExecCommandEndEvent;
+use code_core::protocol::PatchApplyBeginEvent;
+use code_core::protocol::PatchApplyEndEvent;
+use code_core::protocol::TokenUsage;
+use code_protocol::num_format::format_with_separators;
+
+use crate::attachments::ImageStatus;
+use crate::cost_report::CostReport;
+use crate::event_processor::CodexStatus;
+use crate::event_processor::EventProcessor;
+use crate::event_processor_with_human_output::escape_command;
+use crate::event_processor_with_human_output::format_file_change;
+use crate::event_processor_with_human_output::format_mcp_invocation;
+use crate::exit_code::FailureClass;
+use crate::interrupt::Interruption;
+use crate::run_environment::RunEnvironment;
+use crate::run_guard::RunLimit;
+
+/// Lines of command or tool output kept in each folded block.
+const MAX_OUTPUT_LINES: usize = 200;
+
+/// Forwards events to the wrapped processor after appending them to the
+/// transcript.
+pub(crate) struct TranscriptProcessor {
+    inner: Box<dyn EventProcessor>,
+    path: PathBuf,
+    file: Option<File>,
+    commands: HashMap<String, Vec<String>>,
+    total_usage: Option<TokenUsage>,
+}
+
+impl TranscriptProcessor {
+    /// Creates (or truncates) the transcript file up front so a bad path
+    /// fails before the run starts.
+    pub fn create(inner: Box<dyn EventProcessor>, path: PathBuf) -> anyhow::Result<Self> {
+        let file = File::create(&path)
+            .with_context(|| format!("failed to create transcript file {}", path.display()))?;
+        Ok(Self {
+            inner,
+            path,
+            file: Some(file),
+            commands: HashMap::new(),
+            total_usage: None,
+        })
+    }
+
+    fn append(&mut self, markdown: &str) {
+        let Some(file) = self.file.as_mut() else {
+            return;
+        };
+        if let Err(err) = file
+            .write_all(markdown.as_bytes())
+            .and_then(|()| file.flush())
+        {
+            eprintln!(
+                "failed to write transcript {} ({err}); no further events will be recorded",
+                self.path.display()
+            );
+            self.file = None;
+        }
+    }
+
+    /// Renders the Markdown for `msg`, or `None` when it has no place in the
+    /// transcript.
+    fn render(&mut self, msg: &EventMsg) -> Option<String> {
+        match msg {
+            EventMsg::AgentMessage(AgentMessageEvent { message }) => {
+                Some(format!("## Assistant\n\n{}\n\n", message.trim_end()))
+            }
+            EventMsg::Error(ErrorEvent { message }) => Some(format!("> **Error:** {message}\n\n")),
+            EventMsg::ExecCommandBegin(ExecCommandBeginEvent {
+                call_id, command, ..
+            }) => {
+                self.commands.insert(call_id.clone(), command.clone());
+                None
+            }
+            EventMsg::ExecCommandEnd(ExecCommandEndEvent {
+                call_id,
+                stdout,
+                stderr,
+                exit_code,
+                duration,
+                sandbox_denied: _,
+            }) => {
+                let command = self
+                    .commands
+                    .remove(call_id)
+                    .map_or_else(|| format!("exec('{call_id}')"), |c| escape_command(&c));
+                let status = if *exit_code == 0 {
+                    "succeeded".to_string()
+                } else {
+                    format!("exited {exit_code}")
+                };
+                let output = [stdout.trim_end(), stderr.trim_end()]
+                    .into_iter()
+                    .filter(|part| !part.is_empty())
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                Some(fold(
+                    &format!(
+                        "<code>{}</code> {status} in {}",
+                        escape_html(&command),
+                        format_duration(*duration)
+                    ),
+                    &output,
+                    "text",
+                ))
+            }
+            EventMsg::McpToolCallEnd(event) => {
+                let status = if event.is_success() {
+                    "succeeded"
+                } else {
+                    "failed"
+                };
+                let output = match &event.result {
+                    Ok(result) => {
+                        let value: serde_json::Value = result.clone().into();
+                        serde_json::to_string_pretty(&value).unwrap_or_else(|_| value.to_string())
+                    }
+                    Err(err) => err.clone(),
+                };
+                Some(fold(
+                    &format!(
+                        "tool <code>{}</code> {status} in {}",
+                        escape_html(&format_mcp_invocation(&event.invocation)),
+                        format_duration(event.duration)
+                    ),
+                    &output,
+                    "json",
+                ))
+            }
+            EventMsg::CustomToolCallEnd(event) => {
+                let (status, output) = match &event.result {
+                    Ok(content) => ("succeeded", content),
+                    Err(err) => ("failed", err),
+                };
+                Some(fold(
+                    &format!(
+                        "tool <code>{}</code> {status} in {}",
+                        escape_html(&event.tool_name),
+                        format_duration(event.duration)
+                    ),
+                    output,
+                    "text",
+                ))
+            }
+            EventMsg::PatchApplyBegin(PatchApplyBeginEvent { changes, .. }) => {
+                let mut paths: Vec<String> = changes
+                    .iter()
+                    .map(|(path, change)| {
+                        format!("- `{}` {}", format_file_change(change), path.display())
+                    })
+                    .collect();
+                paths.sort();
+                Some(format!("**Patch**\n\n{}\n\n", paths.join("\n")))
+            }
+            EventMsg::PatchApplyEnd(PatchApplyEndEvent {
+                success, stderr, ..
+            }) => (!success).then(|| fold("patch failed", stderr.trim_end(), "text")),
+            EventMsg::TokenCount(event) => {
+                if let Some(info) = &event.info {
+                    self.total_usage = Some(info.total_token_usage.clone());
+                }
+                None
+            }
+            EventMsg::ShutdownComplete => Some(self.footer()),
+            _ => None,
+        }
+    }
+
+    fn footer(&self) -> String {
+        let Some(usage) = &self.total_usage else {
+            return "---\n\n".to_string();
+        };
+        format!(
+            "---\n\n**Tokens used:** {} (input {}, cached {}, output {}, reasoning {})\n\n",
+            format_with_separators(usage.blended_total()),
+            format_with_separators(usage.input_tokens),
+            format_with_separators(usage.cached_input_tokens),
+            format_with_separators(usage.output_tokens),
+            format_with_separators(usage.reasoning_output_tokens),
+        )
+    }
+}
+
+impl EventProcessor for TranscriptProcessor {
+    fn print_config_summary(&mut self, config: &Config, prompt: &str) {
+        let header = format!(
+            "# Transcript\n\n- model: `{}`\n- cwd: `{}`\n\n## User\n\n{}\n\n",
+            config.model,
+            config.cwd.display(),
+            prompt.trim_end()
+        );
+        self.append(&header);
+        self.inner.print_config_summary(config, prompt);
+    }
+
+    fn process_event(&mut self, event: Event) -> CodexStatus {
+        if let Some(markdown) = self.render(&event.msg) {
+            self.append(&markdown);
+        }
+        self.inner.process_event(event)
+    }
+
+    fn report_run_limit(&mut self, limit: RunLimit) {
+        self.append(&format!("> **Run stopped:** {}\n\n", limit.describe()));
+        self.inner.report_run_limit(limit);
+    }
+
+    fn report_interruption(&mut self, interruption: Interruption) {
+        self.append(&format!(
+            "> **Run {}:** {}\n\n",
+            interruption.reason(),
+            interruption.describe()
+        ));
+        self.inner.report_interruption(interruption);
+    }
+
+    fn report_environment(&mut self, environment: &RunEnvironment) {
+        self.inner.report_environment(environment);
+    }
+
+    fn report_image(&mut self, status: &ImageStatus) {
+        if let Some(error) = &status.error {
+            self.append(&format!(
+                "> **Image rejected:** {}: {error}\n\n",
+                status.path
+            ));
+        }
+        self.inner.report_image(status);
+    }
+
+    fn report_failure(&mut self, failure: FailureClass) {
+        self.append(&format!(
+            "> **Run failed:** {} (exit code {})\n\n",
+            failure.reason(),
+            failure.exit_code()
+        ));
+        self.inner.report_failure(failure);
+    }
+
+    fn report_cost(&mut self, report: &CostReport) {
+        if let Some(usd) = report.estimated_usd {
+            self.append(&format!("**Estimated cost:** ${usd:.4}\n\n"));
+        }
+        self.inner.report_cost(report);
+    }
+}
+
+/// A collapsed `<details>` block whose body is `output` in a code fence.
+fn fold(summary: &str, output: &str, language: &str) -> String {
+    if output.is_empty() {
+        return format!("<details>\n<summary>{summary}</summary>\n</details>\n\n");
+    }
+    let mut lines: Vec<&str> = output.lines().collect();
+    let omitted = lines.len().saturating_sub(MAX_OUTPUT_LINES);
+    lines.truncate(MAX_OUTPUT_LINES);
+    let mut body = lines.join("\n");
+    if omitted > 0 {
+        body.push_str(&format!("\n… {omitted} more line(s)"));
+    }
+    let fence = code_fence(&body);
+    format!(
+        "<details>\n<summary>{summary}</summary>\n\n{fence}{language}\n{body}\n{fence}\n\n</details>\n\n"
+    )
+}
+
+/// A backtick fence longer than any backtick run inside `body`.
+fn code_fence(body: &str) -> String {
+    let longest = body
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or_default();
+    "`".repeat(longest.max(2) + 1)
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event_processor::NullProcessor;
+    use code_core::protocol::TokenCountEvent;
+    use code_core::protocol::TokenUsageInfo;
+    use pretty_assertions::assert_eq;
+    use std::time::Duration;
+
+    fn event(msg: EventMsg) -> Event {
+        Event {
+            id: "1".to_string(),
+            event_seq: 0,
+            msg,
+            order: None,
+        }
+    }
+
+    #[test]
+    fn writes_messages_folded_command_output_and_usage_footer() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("run.md");
+        let mut processor =
+            TranscriptProcessor::create(Box::new(NullProcessor), path.clone()).unwrap();
+
+        for msg in [
+            EventMsg::ExecCommandBegin(ExecCommandBeginEvent {
+                call_id: "c1".to_string(),
+                command: vec!["echo".to_string(), "```".to_string()],
+                cwd: PathBuf::from("/repo"),
+                parsed_cmd: Vec::new(),
+            }),
+            EventMsg::ExecCommandEnd(ExecCommandEndEvent {
+                call_id: "c1".to_string(),
+                stdout: "```\n".to_string(),
+                stderr: String::new(),
+                exit_code: 0,
+                duration: Duration::from_millis(1500),
+                sandbox_denied: false,
+            }),
+            EventMsg::AgentMessage(AgentMessageEvent {
+                message: "Done.".to_string(),
+            }),
+            EventMsg::TokenCount(TokenCountEvent {
+                info: Some(TokenUsageInfo {
+                    total_token_usage: TokenUsage {
+                        input_tokens: 1200,
+                        cached_input_tokens: 200,
+                        output_tokens: 300,
+                        reasoning_output_tokens: 100,
+                        total_tokens: 1500,
+                    },
+                    last_token_usage: TokenUsage::default(),
+                    model_context_window: None,
+                }),
+                rate_limits: None,
+            }),
+            EventMsg::ShutdownComplete,
+        ] {
+            processor.process_event(event(msg));
+        }
+
+        assert_eq!(
+            std::fs::read_to_string(path).unwrap(),
+            "<details>\n<summary><code>echo '```'</code> succeeded in 1s</summary>\n\n\
+             ````text\n```\n````\n\n</details>\n\n\
+             ## Assistant\n\nDone.\n\n\
+             ---\n\n**Tokens used:** 1,300 (input 1,200, cached 200, output 300, reasoning 100)\n\n"
+        );
+    }
+}
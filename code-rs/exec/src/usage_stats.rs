@@ -0,0 +1,339 @@
+//! Opt-in, anonymized statistics about exec runs.
+//!
+//! With `[usage_stats] enabled = true` in `config.toml` every run appends one
+//! line to `$CODE_HOME/usage_stats.jsonl`: the day, how the run was driven,
+//! the model family, turns, duration, and exit class. Prompts, paths, model
+//! slugs outside the known families, and session ids are never recorded, and
+//! nothing leaves the machine. `code exec stats` prints or exports the
+//! aggregate.
+
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Instant;
+
+use anyhow::Context;
+use code_core::config::Config;
+use code_core::model_family::find_family_for_model;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::cli::StatsArgs;
+use crate::exit_code::FailureClass;
+
+const USAGE_STATS_FILE: &str = "usage_stats.jsonl";
+
+/// Family recorded for models outside the built-in families, so custom slugs
+/// never reach the file.
+const OTHER_MODEL_FAMILY: &str = "other";
+
+/// How a run was driven.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum RunKind {
+    Prompt,
+    Auto,
+    Backlog,
+}
+
+impl RunKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            RunKind::Prompt => "prompt",
+            RunKind::Auto => "auto",
+            RunKind::Backlog => "backlog",
+        }
+    }
+}
+
+/// One line of `usage_stats.jsonl`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct RunRecord {
+    /// UTC day the run ended, `YYYY-MM-DD`.
+    pub day: String,
+    pub kind: RunKind,
+    pub model_family: String,
+    pub turns: u32,
+    pub duration_secs: u64,
+    /// `success` or the failure reason exec reports, e.g. `timeout`.
+    pub exit_class: String,
+}
+
+/// Times a run and appends its record when it ends. Only exists when
+/// `[usage_stats]` is enabled.
+pub(crate) struct StatsRecorder {
+    path: PathBuf,
+    kind: RunKind,
+    model_family: String,
+    started: Instant,
+}
+
+impl StatsRecorder {
+    pub fn start(config: &Config, kind: RunKind) -> Option<Self> {
+        if !config.usage_stats.enabled {
+            return None;
+        }
+        Some(Self {
+            path: stats_path(&config.code_home),
+            kind,
+            model_family: anonymized_family(&config.model),
+            started: Instant::now(),
+        })
+    }
+
+    pub fn finish(self, turns: u32, failure: Option<FailureClass>) {
+        let record = RunRecord {
+            day: chrono::Utc::now().format("%Y-%m-%d").to_string(),
+            kind: self.kind,
+            model_family: self.model_family,
+            turns,
+            duration_secs: self.started.elapsed().as_secs(),
+            exit_class: failure.map_or("success", FailureClass::reason).to_string(),
+        };
+        if let Err(err) = append_record(&self.path, &record) {
+            tracing::warn!("failed to record usage stats: {err:#}");
+        }
+    }
+}
+
+fn stats_path(code_home: &Path) -> PathBuf {
+    code_home.join(USAGE_STATS_FILE)
+}
+
+fn anonymized_family(model: &str) -> String {
+    find_family_for_model(model)
+        .map(|family| family.family)
+        .unwrap_or_else(|| OTHER_MODEL_FAMILY.to_string())
+}
+
+fn append_record(path: &Path, record: &RunRecord) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    writeln!(file, "{}", serde_json::to_string(record)?)?;
+    Ok(())
+}
+
+/// Reads every record, skipping lines an older or newer version wrote in a
+/// shape this one does not understand.
+fn load_records(path: &Path) -> anyhow::Result<Vec<RunRecord>> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err).with_context(|| format!("failed to read {}", path.display())),
+    };
+    Ok(contents
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// Totals for one model family or run kind.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub(crate) struct GroupStats {
+    pub runs: u64,
+    pub successes: u64,
+    pub turns: u64,
+    pub duration_secs: u64,
+}
+
+impl GroupStats {
+    fn add(&mut self, record: &RunRecord) {
+        self.runs += 1;
+        if record.exit_class == "success" {
+            self.successes += 1;
+        }
+        self.turns += u64::from(record.turns);
+        self.duration_secs += record.duration_secs;
+    }
+
+    fn summary(&self) -> String {
+        let runs = self.runs.max(1) as f64;
+        format!(
+            "{} run(s), {:.0}% succeeded, {:.1} turns and {:.0}s on average",
+            self.runs,
+            self.successes as f64 * 100.0 / runs,
+            self.turns as f64 / runs,
+            self.duration_secs as f64 / runs
+        )
+    }
+}
+
+/// What `code exec stats` prints or exports.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub(crate) struct StatsAggregate {
+    pub first_day: Option<String>,
+    pub last_day: Option<String>,
+    pub total: GroupStats,
+    pub by_model_family: BTreeMap<String, GroupStats>,
+    pub by_kind: BTreeMap<String, GroupStats>,
+    pub exit_classes: BTreeMap<String, u64>,
+}
+
+impl StatsAggregate {
+    pub fn from_records(records: &[RunRecord]) -> Self {
+        let mut aggregate = Self::default();
+        for record in records {
+            aggregate.total.add(record);
+            aggregate
+                .by_model_family
+                .entry(record.model_family.clone())
+                .or_default()
+                .add(record);
+            aggregate
+                .by_kind
+                .entry(record.kind.as_str().to_string())
+                .or_default()
+                .add(record);
+            *aggregate
+                .exit_classes
+                .entry(record.exit_class.clone())
+                .or_default() += 1;
+            if aggregate
+                .first_day
+                .as_ref()
+                .is_none_or(|day| record.day < *day)
+            {
+                aggregate.first_day = Some(record.day.clone());
+            }
+            if aggregate
+                .last_day
+                .as_ref()
+                .is_none_or(|day| record.day > *day)
+            {
+                aggregate.last_day = Some(record.day.clone());
+            }
+        }
+        aggregate
+    }
+
+    pub fn to_text(&self) -> String {
+        let (Some(first), Some(last)) = (&self.first_day, &self.last_day) else {
+            return "No runs recorded.".to_string();
+        };
+        let mut lines = vec![
+            format!("Runs {first} to {last}: {}", self.total.summary()),
+            String::new(),
+            "By model family:".to_string(),
+        ];
+        for (family, stats) in &self.by_model_family {
+            lines.push(format!("  {family}: {}", stats.summary()));
+        }
+        lines.push("By run kind:".to_string());
+        for (kind, stats) in &self.by_kind {
+            lines.push(format!("  {kind}: {}", stats.summary()));
+        }
+        lines.push("Exit classes:".to_string());
+        for (class, count) in &self.exit_classes {
+            lines.push(format!("  {class}: {count}"));
+        }
+        lines.join("\n")
+    }
+}
+
+/// `code exec stats`.
+pub(crate) fn run_stats(args: &StatsArgs, config: &Config) -> anyhow::Result<()> {
+    let path = stats_path(&config.code_home);
+    if args.reset {
+        match std::fs::remove_file(&path) {
+            Ok(()) => eprintln!("Removed {}", path.display()),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => {
+                return Err(err).with_context(|| format!("failed to remove {}", path.display()));
+            }
+        }
+        return Ok(());
+    }
+    if !config.usage_stats.enabled {
+        eprintln!(
+            "Usage stats are disabled; set `[usage_stats] enabled = true` in config.toml to record runs."
+        );
+    }
+
+    let aggregate = StatsAggregate::from_records(&load_records(&path)?);
+    if let Some(output) = args.output.as_deref() {
+        std::fs::write(output, serde_json::to_string_pretty(&aggregate)?)
+            .with_context(|| format!("failed to write {}", output.display()))?;
+        eprintln!("Wrote {}", output.display());
+    } else if args.json {
+        out_println!("{}", serde_json::to_string_pretty(&aggregate)?);
+    } else {
+        out_println!("{}", aggregate.to_text());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn record(day: &str, kind: RunKind, family: &str, turns: u32, exit_class: &str) -> RunRecord {
+        RunRecord {
+            day: day.to_string(),
+            kind,
+            model_family: family.to_string(),
+            turns,
+            duration_secs: 60,
+            exit_class: exit_class.to_string(),
+        }
+    }
+
+    #[test]
+    fn aggregates_records_appended_to_the_stats_file() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = stats_path(dir.path());
+        append_record(
+            &path,
+            &record("2026-10-02", RunKind::Auto, "gpt-5", 6, "success"),
+        )
+        .expect("append");
+        append_record(
+            &path,
+            &record("2026-10-01", RunKind::Prompt, "gpt-5", 2, "timeout"),
+        )
+        .expect("append");
+        append_record(
+            &path,
+            &record("2026-10-03", RunKind::Prompt, "other", 1, "success"),
+        )
+        .expect("append");
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .expect("open");
+        writeln!(file, "not a record").expect("write");
+
+        let aggregate = StatsAggregate::from_records(&load_records(&path).expect("load"));
+
+        assert_eq!(aggregate.first_day.as_deref(), Some("2026-10-01"));
+        assert_eq!(aggregate.last_day.as_deref(), Some("2026-10-03"));
+        assert_eq!(
+            aggregate.by_model_family["gpt-5"],
+            GroupStats {
+                runs: 2,
+                successes: 1,
+                turns: 8,
+                duration_secs: 120,
+            }
+        );
+        assert_eq!(aggregate.by_kind["prompt"].runs, 2);
+        assert_eq!(aggregate.exit_classes["timeout"], 1);
+        assert_eq!(
+            aggregate.to_text().lines().next(),
+            Some(
+                "Runs 2026-10-01 to 2026-10-03: 3 run(s), 67% succeeded, 3.0 turns and 60s on average"
+            )
+        );
+    }
+
+    #[test]
+    fn unknown_models_are_recorded_as_other() {
+        assert_eq!(anonymized_family("acme-internal-finetune-7"), "other");
+    }
+}
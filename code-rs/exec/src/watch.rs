@@ -0,0 +1,169 @@
+//! `--watch <glob>`: re-run the prompt in the same session when files change.
+//!
+//! The watcher polls the working tree with the same walker ripgrep uses, so
+//! `.gitignore`d paths never trigger a run. Walks run on the blocking pool to
+//! keep large trees from stalling the runtime. The baseline is taken when a
+//! turn finishes, which keeps the agent's own edits from re-triggering it; a
+//! burst of saves is coalesced until the tree has been quiet for
+//! [`DEBOUNCE`].
+
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use anyhow::Context;
+use ignore::WalkBuilder;
+use ignore::overrides::Override;
+use ignore::overrides::OverrideBuilder;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+const DEBOUNCE: Duration = Duration::from_millis(750);
+/// Changed paths listed in the re-run preamble before the rest are counted.
+const MAX_LISTED_PATHS: usize = 20;
+
+type Snapshot = HashMap<PathBuf, (Option<SystemTime>, u64)>;
+
+pub(crate) struct FileWatcher {
+    root: PathBuf,
+    globs: Override,
+    snapshot: Snapshot,
+}
+
+impl FileWatcher {
+    pub async fn new(root: &Path, globs: &[String]) -> anyhow::Result<Self> {
+        let mut builder = OverrideBuilder::new(root);
+        for glob in globs {
+            builder
+                .add(glob)
+                .with_context(|| format!("invalid --watch glob `{glob}`"))?;
+        }
+        let mut watcher = Self {
+            root: root.to_path_buf(),
+            globs: builder.build().context("invalid --watch globs")?,
+            snapshot: Snapshot::new(),
+        };
+        watcher.snapshot = watcher.scan().await;
+        Ok(watcher)
+    }
+
+    /// Waits for matching files to change after the current state and returns
+    /// them relative to the root, sorted.
+    pub async fn wait_for_change(&mut self) -> Vec<PathBuf> {
+        self.snapshot = self.scan().await;
+        let mut changed = BTreeSet::new();
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            let batch = self.poll().await;
+            if batch.is_empty() {
+                continue;
+            }
+            changed.extend(batch);
+            loop {
+                tokio::time::sleep(DEBOUNCE).await;
+                let batch = self.poll().await;
+                if batch.is_empty() {
+                    return changed.into_iter().collect();
+                }
+                changed.extend(batch);
+            }
+        }
+    }
+
+    /// Paths added, modified, or removed since the last call.
+    async fn poll(&mut self) -> Vec<PathBuf> {
+        let current = self.scan().await;
+        let mut changed: Vec<PathBuf> = current
+            .iter()
+            .filter(|(path, stamp)| self.snapshot.get(*path) != Some(*stamp))
+            .map(|(path, _)| path.clone())
+            .chain(
+                self.snapshot
+                    .keys()
+                    .filter(|path| !current.contains_key(*path))
+                    .cloned(),
+            )
+            .collect();
+        changed.sort();
+        self.snapshot = current;
+        changed
+    }
+
+    /// Walks the tree on the blocking pool. A walk that panics reports no
+    /// changes rather than every file as removed.
+    async fn scan(&self) -> Snapshot {
+        let root = self.root.clone();
+        let globs = self.globs.clone();
+        tokio::task::spawn_blocking(move || scan(&root, globs))
+            .await
+            .unwrap_or_else(|_| self.snapshot.clone())
+    }
+}
+
+fn scan(root: &Path, globs: Override) -> Snapshot {
+    let mut builder = WalkBuilder::new(root);
+    builder.require_git(false).overrides(globs);
+    builder
+        .build()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_some_and(|kind| kind.is_file()))
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            let path = entry.path().strip_prefix(root).ok()?.to_path_buf();
+            Some((path, (metadata.modified().ok(), metadata.len())))
+        })
+        .collect()
+}
+
+/// The prompt submitted for a re-run: the changed files, then the original
+/// prompt.
+pub(crate) fn rerun_prompt(changed: &[PathBuf], prompt: &str) -> String {
+    let mut listed: Vec<String> = changed
+        .iter()
+        .take(MAX_LISTED_PATHS)
+        .map(|path| path.display().to_string())
+        .collect();
+    if changed.len() > MAX_LISTED_PATHS {
+        listed.push(format!("and {} more", changed.len() - MAX_LISTED_PATHS));
+    }
+    format!("Files changed: {}\n\n{prompt}", listed.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[tokio::test]
+    async fn reports_matching_changes_only() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src/lib.rs"), "fn a() {}").unwrap();
+        std::fs::write(dir.path().join("README.md"), "readme").unwrap();
+        let mut watcher = FileWatcher::new(dir.path(), &["src/**/*.rs".to_string()])
+            .await
+            .unwrap();
+
+        std::fs::write(dir.path().join("src/lib.rs"), "fn a() { todo!() }").unwrap();
+        std::fs::write(dir.path().join("src/main.rs"), "fn main() {}").unwrap();
+        std::fs::write(dir.path().join("README.md"), "changed readme").unwrap();
+        assert_eq!(
+            watcher.poll().await,
+            vec![PathBuf::from("src/lib.rs"), PathBuf::from("src/main.rs")]
+        );
+
+        std::fs::remove_file(dir.path().join("src/main.rs")).unwrap();
+        assert_eq!(watcher.poll().await, vec![PathBuf::from("src/main.rs")]);
+        assert_eq!(watcher.poll().await, Vec::<PathBuf>::new());
+    }
+
+    #[test]
+    fn rerun_prompt_lists_changed_files() {
+        let changed: Vec<PathBuf> = (0..22).map(|i| PathBuf::from(format!("f{i}.rs"))).collect();
+        let prompt = rerun_prompt(&changed[..2], "Fix the build");
+        assert_eq!(prompt, "Files changed: f0.rs, f1.rs\n\nFix the build");
+        assert!(rerun_prompt(&changed, "x").contains("f19.rs, and 2 more\n\nx"));
+    }
+}
@@ -0,0 +1,147 @@
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+//! `code exec resume --fork` against a mock Responses server.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use assert_cmd::Command;
+use code_core::spawn::CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR;
+use pretty_assertions::assert_eq;
+use serde_json::Value;
+use serde_json::json;
+use tempfile::TempDir;
+use wiremock::Mock;
+use wiremock::MockServer;
+use wiremock::ResponseTemplate;
+use wiremock::matchers::method;
+use wiremock::matchers::path_regex;
+
+fn sse_reply(id: &str, text: &str) -> String {
+    let message = json!({
+        "type": "response.output_item.done",
+        "item": {
+            "type": "message",
+            "role": "assistant",
+            "id": format!("msg-{id}"),
+            "content": [{ "type": "output_text", "text": text }],
+        },
+    });
+    let completed = json!({
+        "type": "response.completed",
+        "response": { "id": id, "output": [] },
+    });
+    format!(
+        "event: response.output_item.done\ndata: {message}\n\nevent: response.completed\ndata: {completed}\n\n"
+    )
+}
+
+async fn mount_reply(server: &MockServer, id: &str, text: &str) {
+    Mock::given(method("POST"))
+        .and(path_regex(".*/responses$"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("content-type", "text/event-stream")
+                .set_body_string(sse_reply(id, text)),
+        )
+        .up_to_n_times(1)
+        .mount(server)
+        .await;
+}
+
+fn rollouts(dir: &Path) -> Vec<PathBuf> {
+    let mut found = Vec::new();
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return found;
+    };
+    for entry in entries {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            found.extend(rollouts(&path));
+        } else if path.extension().is_some_and(|ext| ext == "jsonl")
+            && path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with("rollout-"))
+        {
+            found.push(path);
+        }
+    }
+    found.sort();
+    found
+}
+
+/// The session id at the end of `rollout-<timestamp>-<id>.jsonl`.
+fn session_id(rollout: &Path) -> String {
+    let stem = rollout.file_stem().unwrap().to_string_lossy();
+    stem[stem.len() - 36..].to_string()
+}
+
+fn exec(code_home: &Path, cwd: &Path) -> Command {
+    let mut cmd = Command::cargo_bin("code-exec").unwrap();
+    cmd.env("CODE_HOME", code_home)
+        .env_remove("OPENAI_BASE_URL")
+        .args(["--skip-git-repo-check", "-C"])
+        .arg(cwd);
+    cmd
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn fork_leaves_the_original_rollout_untouched() {
+    if std::env::var(CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR).is_ok() {
+        println!("Skipping test because network access is disabled inside the sandbox.");
+        return;
+    }
+
+    let server = MockServer::start().await;
+    mount_reply(&server, "resp-1", "First reply").await;
+    mount_reply(&server, "resp-2", "Forked reply").await;
+
+    let code_home = TempDir::new().unwrap();
+    let cwd = TempDir::new().unwrap();
+    std::fs::write(
+        code_home.path().join("config.toml"),
+        format!(
+            r#"model = "gpt-5"
+model_provider = "mock"
+
+[model_providers.mock]
+name = "mock"
+base_url = "{}/v1"
+wire_api = "responses"
+request_max_retries = 0
+stream_max_retries = 0
+"#,
+            server.uri()
+        ),
+    )
+    .unwrap();
+
+    exec(code_home.path(), cwd.path())
+        .arg("hello")
+        .assert()
+        .success();
+    let sessions = code_home.path().join("sessions");
+    let original = rollouts(&sessions);
+    assert_eq!(original.len(), 1, "{original:?}");
+    let original = original.into_iter().next().unwrap();
+    let recorded = std::fs::read(&original).unwrap();
+
+    exec(code_home.path(), cwd.path())
+        .args(["resume", "--fork", &session_id(&original), "again"])
+        .assert()
+        .success();
+
+    assert_eq!(std::fs::read(&original).unwrap(), recorded);
+    let after = rollouts(&sessions);
+    assert_eq!(after.len(), 2, "{after:?}");
+    let fork = after.iter().find(|path| **path != original).unwrap();
+    assert_ne!(session_id(fork), session_id(&original));
+
+    // The forked turn carries the original history.
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 2);
+    let body: Value = requests[1].body_json().unwrap();
+    let input = body["input"].to_string();
+    assert!(input.contains("hello"), "{input}");
+    assert!(input.contains("again"), "{input}");
+}
@@ -388,6 +388,8 @@ async fn run_code_tool_session_inner(
                     | EventMsg::EnvironmentContextFull(_)
                     | EventMsg::EnvironmentContextDelta(_)
                     | EventMsg::ListCustomPromptsResponse(_)
+                    | EventMsg::ProviderHealth(_)
+                    | EventMsg::TurnLatency(_)
                     | EventMsg::AgentStatusUpdate(_)
                     | EventMsg::CompactionCheckpointWarning(_)
                     | EventMsg::TurnAborted(_)
@@ -332,6 +332,55 @@ impl OtelEventManager {
         )
     }
 
+    /// Records a degenerate provider response (truncated stream, runaway
+    /// repetition, empty completion) that was turned into a retryable error.
+    pub fn sse_event_anomaly(&self, anomaly: &str, detail: &str) {
+        tracing::event!(
+            tracing::Level::INFO,
+            event.name = "codex.sse_anomaly",
+            event.timestamp = %timestamp(),
+            conversation.id = %self.metadata.conversation_id,
+            app.version = %self.metadata.app_version,
+            auth_mode = self.metadata.auth_mode,
+            user.account_id = self.metadata.account_id,
+            terminal.type = %self.metadata.terminal_type,
+            model = %self.metadata.model,
+            slug = %self.metadata.slug,
+            anomaly = %anomaly,
+            detail = %detail,
+        );
+    }
+
+    /// Records how a response stream was buffered: events sent, text deltas
+    /// merged while the consumer lagged, duplicates dropped, and how often and
+    /// how deep the event channel backed up.
+    pub fn sse_buffer_stats(
+        &self,
+        sent: u64,
+        coalesced: u64,
+        dropped: u64,
+        backpressure_waits: u64,
+        peak_queued: u64,
+    ) {
+        tracing::event!(
+            tracing::Level::INFO,
+            event.name = "codex.sse_buffer",
+            event.timestamp = %timestamp(),
+            conversation.id = %self.metadata.conversation_id,
+            app.version = %self.metadata.app_version,
+            auth_mode = self.metadata.auth_mode,
+            user.account_id = self.metadata.account_id,
+            terminal.type = %self.metadata.terminal_type,
+            model = %self.metadata.model,
+            slug = %self.metadata.slug,
+            sent = %sent,
+            coalesced = %coalesced,
+            dropped = %dropped,
+            backpressure_waits = %backpressure_waits,
+            peak_queued = %peak_queued,
+        );
+    }
+
     pub fn sse_event_completed(
         &self,
         input_token_count: u64,
@@ -510,6 +559,36 @@ impl OtelEventManager {
         );
     }
 
+    /// Timings for one model request that completed.
+    pub fn request_latency(
+        &self,
+        provider: &str,
+        model: &str,
+        ttfb: Duration,
+        first_token: Option<Duration>,
+        duration: Duration,
+        retries: u64,
+    ) {
+        tracing::event!(
+            tracing::Level::INFO,
+            event.name = "codex.request_latency",
+            event.timestamp = %timestamp(),
+            conversation.id = %self.metadata.conversation_id,
+            app.version = %self.metadata.app_version,
+            auth_mode = self.metadata.auth_mode,
+            user.account_id = self.metadata.account_id,
+            terminal.type = %self.metadata.terminal_type,
+            model = %self.metadata.model,
+            slug = %self.metadata.slug,
+            provider_name = %provider,
+            request.model = %model,
+            ttfb_ms = %ttfb.as_millis(),
+            first_token_ms = first_token.map(|first_token| first_token.as_millis() as u64),
+            duration_ms = %duration.as_millis(),
+            retries = retries,
+        );
+    }
+
     pub fn turn_latency_event(&self, payload: TurnLatencyPayload) {
         tracing::event!(
             tracing::Level::INFO,
@@ -1098,6 +1098,9 @@ pub struct TurnContextItem {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub effort: Option<ReasoningEffortConfig>,
     pub summary: ReasoningSummaryConfig,
+    /// `model_providers` key of the provider that served the turn.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_provider: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -1510,6 +1513,18 @@ pub struct BrowserSnapshotEvent {
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
 pub struct TurnAbortedEvent {
     pub reason: TurnAbortReason,
+    /// Tool invocations that were still in flight when the turn was aborted
+    /// and have been cancelled.
+    #[serde(default)]
+    pub cancelled_tool_calls: Vec<CancelledToolCall>,
+}
+
+/// A tool invocation cancelled because its turn was aborted.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, TS)]
+pub struct CancelledToolCall {
+    pub call_id: String,
+    /// Tool name as requested by the model (e.g. `shell`, `web_fetch`, `server__tool`).
+    pub tool: String,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, TS)]
@@ -1966,9 +1966,10 @@ impl App<'_> {
                 AppEvent::AutoCoordinatorUserReply {
                     user_response,
                     cli_command,
+                    attachments,
                 } => {
                     if let AppState::Chat { widget } = &mut self.app_state {
-                        widget.auto_handle_user_reply(user_response, cli_command);
+                        widget.auto_handle_user_reply(user_response, cli_command, attachments);
                     }
                 }
                 AppEvent::AutoCoordinatorThinking {
@@ -2039,9 +2040,10 @@ impl App<'_> {
                 AppEvent::AutoCoordinatorCompactedHistory {
                     conversation,
                     show_notice,
+                    estimate,
                 } => {
                     if let AppState::Chat { widget } = &mut self.app_state {
-                        widget.auto_handle_compacted_history(conversation, show_notice);
+                        widget.auto_handle_compacted_history(conversation, show_notice, estimate);
                     }
                 }
                 AppEvent::AutoCoordinatorCountdown {
@@ -2131,6 +2133,11 @@ impl App<'_> {
                                 widget.show_resume_picker();
                             }
                         }
+                        SlashCommand::Search => {
+                            if let AppState::Chat { widget } = &mut self.app_state {
+                                widget.show_session_search(command_args);
+                            }
+                        }
                         SlashCommand::New => {
                             if let AppState::Chat { widget } = &mut self.app_state {
                                 widget.abort_active_turn_for_new_chat();
@@ -2393,6 +2400,11 @@ impl App<'_> {
                         widget.present_resume_picker(cwd, candidates);
                     }
                 }
+                AppEvent::SessionSearchLoaded { query, candidates } => {
+                    if let AppState::Chat { widget } = &mut self.app_state {
+                        widget.present_session_search(query, candidates);
+                    }
+                }
                 AppEvent::ResumePickerLoadFailed { message } => {
                     if let AppState::Chat { widget } = &mut self.app_state {
                         widget.handle_resume_picker_load_failed(message);
@@ -5,6 +5,7 @@ use code_core::config_types::ThemeName;
 use code_core::git_info::CommitLogEntry;
 use code_core::protocol::ApprovedCommandMatchKind;
 use code_core::protocol::Event;
+use code_core::protocol::InputItem;
 use code_core::protocol::OrderMeta;
 use code_core::protocol::ReviewContextMetadata;
 use code_core::protocol::TokenUsage;
@@ -103,6 +104,7 @@ pub(crate) use code_auto_drive_core::AutoCoordinatorStatus;
 pub(crate) use code_auto_drive_core::AutoTurnAgentsAction;
 pub(crate) use code_auto_drive_core::AutoTurnAgentsTiming;
 pub(crate) use code_auto_drive_core::AutoTurnCliAction;
+pub(crate) use code_auto_drive_core::CompactionEstimate;
 
 #[allow(clippy::large_enum_variant)]
 #[derive(Debug)]
@@ -170,6 +172,7 @@ pub(crate) enum AppEvent {
     AutoCoordinatorUserReply {
         user_response: Option<String>,
         cli_command: Option<String>,
+        attachments: Vec<InputItem>,
     },
     AutoCoordinatorThinking {
         delta: String,
@@ -188,6 +191,7 @@ pub(crate) enum AppEvent {
     AutoCoordinatorCompactedHistory {
         conversation: Vec<ResponseItem>,
         show_notice: bool,
+        estimate: Option<CompactionEstimate>,
     },
     AutoCoordinatorStopAck,
     AutoCoordinatorCountdown {
@@ -251,6 +255,12 @@ pub(crate) enum AppEvent {
         candidates: Vec<ResumeCandidate>,
     },
 
+    /// `/search` results finished loading; shown in the resume picker
+    SessionSearchLoaded {
+        query: String,
+        candidates: Vec<ResumeCandidate>,
+    },
+
     /// Resume picker failed to load
     ResumePickerLoadFailed {
         message: String,
@@ -212,8 +212,10 @@ use code_auto_drive_core::AutoTurnAgentsAction;
 use code_auto_drive_core::AutoTurnAgentsTiming;
 use code_auto_drive_core::AutoTurnCliAction;
 use code_auto_drive_core::AutoTurnReviewState;
+use code_auto_drive_core::CompactionEstimate;
 use code_auto_drive_core::CoordinatorContext;
 use code_auto_drive_core::CoordinatorRouterResponse;
+use code_auto_drive_core::MIN_COMPACT_THRESHOLD;
 use code_auto_drive_core::TurnConfig;
 use code_auto_drive_core::TurnDescriptor;
 use code_auto_drive_core::route_user_message;
@@ -282,6 +284,7 @@ use code_core::codex::compact::COMPACTION_CHECKPOINT_MESSAGE;
 use code_core::protocol::Op;
 use code_core::protocol::PatchApplyBeginEvent;
 use code_core::protocol::PatchApplyEndEvent;
+use code_core::protocol::ProviderHealthEvent;
 use code_core::protocol::ReviewContextMetadata;
 use code_core::protocol::ReviewOutputEvent;
 use code_core::protocol::ReviewRequest;
@@ -967,6 +970,17 @@ fn auto_continue_to_config(mode: AutoContinueMode) -> AutoDriveContinueMode {
     }
 }
 
+/// Arguments of `/auto compact [RATIO]`; `None` for any other `/auto`
+/// argument, so goals like "compact the cache" still start a run.
+fn auto_compact_args(args: &str) -> Option<&str> {
+    let (command, rest) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+    if !command.eq_ignore_ascii_case("compact") {
+        return None;
+    }
+    let rest = rest.trim();
+    (rest.is_empty() || rest.parse::<f64>().is_ok()).then_some(rest)
+}
+
 fn status_field_prefix(label: &str) -> String {
     let padding =
         STATUS_LABEL_GAP.saturating_add(STATUS_LABEL_TARGET_WIDTH.saturating_sub(label.len()));
@@ -982,6 +996,37 @@ fn status_content_prefix() -> String {
     STATUS_CONTENT_PREFIX.to_string()
 }
 
+fn format_provider_health(event: &ProviderHealthEvent) -> String {
+    if event.endpoints.is_empty() {
+        return "Provider health: no model requests yet".to_string();
+    }
+    let mut lines = vec!["Provider health:".to_string()];
+    for endpoint in &event.endpoints {
+        let mut line = format!(
+            "  {} ({}): {}",
+            endpoint.provider, endpoint.endpoint, endpoint.circuit
+        );
+        if let Some(retry_in_ms) = endpoint.retry_in_ms {
+            line.push_str(&format!(", retry in {}s", retry_in_ms.div_ceil(1_000)));
+        }
+        line.push_str(&format!(
+            " · {} requests, {} failed",
+            endpoint.requests, endpoint.failures
+        ));
+        if endpoint.consecutive_failures > 0 {
+            line.push_str(&format!(" ({} in a row)", endpoint.consecutive_failures));
+        }
+        if let Some(latency_ms) = endpoint.latency_ms {
+            line.push_str(&format!(" · ~{latency_ms} ms to first event"));
+        }
+        if let Some(last_error) = &endpoint.last_error {
+            line.push_str(&format!(" · last error: {last_error}"));
+        }
+        lines.push(line);
+    }
+    lines.join("\n")
+}
+
 fn describe_cloud_error(err: &CloudTaskError) -> String {
     match err {
         CloudTaskError::Msg(message) => message.clone(),
@@ -3560,6 +3605,55 @@ impl ChatWidget<'_> {
         });
     }
 
+    /// Search past sessions for `query` and list the matches in the resume
+    /// picker.
+    pub(crate) fn show_session_search(&mut self, query: String) {
+        if query.is_empty() {
+            self.bottom_pane
+                .flash_footer_notice("Usage: /search <words>".to_string());
+            self.request_redraw();
+            return;
+        }
+        if self.resume_picker_loading {
+            self.bottom_pane
+                .flash_footer_notice("Still loading past sessions…".to_string());
+            return;
+        }
+        self.resume_picker_loading = true;
+        self.bottom_pane.flash_footer_notice_for(
+            "Searching past sessions…".to_string(),
+            std::time::Duration::from_secs(30),
+        );
+        self.request_redraw();
+
+        let code_home = self.config.code_home.clone();
+        let exclude_path = self.config.experimental_resume.clone();
+        let tx = self.app_event_tx.clone();
+
+        tokio::spawn(async move {
+            let search_query = query.clone();
+            let result = tokio::task::spawn_blocking(move || {
+                crate::resume::discovery::search_sessions(
+                    &search_query,
+                    &code_home,
+                    exclude_path.as_deref(),
+                )
+            })
+            .await;
+
+            match result {
+                Ok(candidates) => {
+                    tx.send(AppEvent::SessionSearchLoaded { query, candidates });
+                }
+                Err(err) => {
+                    tx.send(AppEvent::ResumePickerLoadFailed {
+                        message: format!("Failed to search past sessions: {}", err),
+                    });
+                }
+            }
+        });
+    }
+
     fn resume_rows_from_candidates(
         candidates: Vec<crate::resume::discovery::ResumeCandidate>,
     ) -> Vec<crate::bottom_pane::resume_selection_view::ResumeRow> {
@@ -3637,6 +3731,28 @@ impl ChatWidget<'_> {
         self.request_redraw();
     }
 
+    pub(crate) fn present_session_search(
+        &mut self,
+        query: String,
+        candidates: Vec<crate::resume::discovery::ResumeCandidate>,
+    ) {
+        self.resume_picker_loading = false;
+        if candidates.is_empty() {
+            self.bottom_pane
+                .flash_footer_notice(format!("No past sessions mention \"{query}\""));
+            self.request_redraw();
+            return;
+        }
+        let rows = Self::resume_rows_from_candidates(candidates);
+        let count = rows.len();
+        let title = format!("Search Sessions — \"{query}\"");
+        self.bottom_pane
+            .show_resume_selection(title, Some(String::new()), rows);
+        self.bottom_pane
+            .flash_footer_notice(format!("Found {} matching sessions.", count));
+        self.request_redraw();
+    }
+
     pub(crate) fn handle_resume_picker_load_failed(&mut self, message: String) {
         self.resume_picker_loading = false;
         self.bottom_pane.flash_footer_notice(message);
@@ -9317,7 +9433,17 @@ impl ChatWidget<'_> {
             let mut conversation = self.current_auto_history();
             if let Some(user_item) = Self::auto_drive_make_user_message(original_text.clone()) {
                 conversation.push(user_item.clone());
-                if self.auto_send_user_prompt_to_coordinator(original_text.clone(), conversation) {
+                let attachments: Vec<InputItem> = message
+                    .ordered_items
+                    .iter()
+                    .filter(|item| !matches!(item, InputItem::Text { .. }))
+                    .cloned()
+                    .collect();
+                if self.auto_send_user_prompt_to_coordinator(
+                    original_text.clone(),
+                    conversation,
+                    attachments,
+                ) {
                     self.finalize_sent_user_message(message);
                     self.consume_pending_prompt_for_ui_only_turn();
                     self.auto_history
@@ -12624,6 +12750,12 @@ impl ChatWidget<'_> {
                 debug!("received {len} custom prompts");
                 self.bottom_pane.set_custom_prompts(ev.custom_prompts);
             }
+            EventMsg::ProviderHealth(ev) => {
+                self.push_background_tail(format_provider_health(&ev));
+            }
+            EventMsg::TurnLatency(ev) => {
+                self.push_background_tail(format!("Latency {ev}"));
+            }
             EventMsg::ShutdownComplete => {
                 self.push_background_tail("🟡 ShutdownComplete".to_string());
                 self.app_event_tx.send(AppEvent::ExitRequest);
@@ -13474,6 +13606,7 @@ impl ChatWidget<'_> {
             &self.total_token_usage,
             &self.last_token_usage,
         ));
+        self.submit_op(Op::GetProviderHealth);
     }
 
     pub(crate) fn show_limits_settings_ui(&mut self) {
@@ -15228,6 +15361,7 @@ fi\n\
                     agents_timing,
                     agents,
                     transcript,
+                    ..
                 } => {
                     app_event_tx.send(AppEvent::AutoCoordinatorDecision {
                         seq,
@@ -15256,10 +15390,12 @@ fi\n\
                 AutoCoordinatorEvent::UserReply {
                     user_response,
                     cli_command,
+                    attachments,
                 } => {
                     app_event_tx.send(AppEvent::AutoCoordinatorUserReply {
                         user_response,
                         cli_command,
+                        attachments,
                     });
                 }
                 AutoCoordinatorEvent::TokenMetrics {
@@ -15268,6 +15404,7 @@ fi\n\
                     turn_count,
                     duplicate_items,
                     replay_updates,
+                    ..
                 } => {
                     app_event_tx.send(AppEvent::AutoCoordinatorTokenMetrics {
                         total_usage,
@@ -15280,10 +15417,12 @@ fi\n\
                 AutoCoordinatorEvent::CompactedHistory {
                     conversation,
                     show_notice,
+                    estimate,
                 } => {
                     app_event_tx.send(AppEvent::AutoCoordinatorCompactedHistory {
                         conversation,
                         show_notice,
+                        estimate,
                     });
                 }
                 AutoCoordinatorEvent::StopAck => {
@@ -15319,6 +15458,26 @@ fi\n\
                 AutoCoordinatorEvent::InterventionRequired { reason } => {
                     app_event_tx.send(AppEvent::AutoCoordinatorInterventionRequired { reason });
                 }
+                AutoCoordinatorEvent::CoordinatorModelSwitched { from, to, reason } => {
+                    app_event_tx.send(AppEvent::AutoCoordinatorDiagnosticAlert {
+                        alert_type: "ModelSwitched".to_string(),
+                        message: format!("Coordinator switched from {from} to {to}: {reason}"),
+                    });
+                }
+                AutoCoordinatorEvent::CoordinatorFailover { from, to, reason } => {
+                    app_event_tx.send(AppEvent::AutoCoordinatorDiagnosticAlert {
+                        alert_type: "ModelFailover".to_string(),
+                        message: format!("Coordinator failed over from {from} to {to}: {reason}"),
+                    });
+                }
+                AutoCoordinatorEvent::CoordinatorDegraded { reason } => {
+                    app_event_tx.send(AppEvent::AutoCoordinatorDiagnosticAlert {
+                        alert_type: "CoordinatorDegraded".to_string(),
+                        message: format!(
+                            "Coordinator unavailable; continuing with templated prompts: {reason}"
+                        ),
+                    });
+                }
             })
         };
 
@@ -15363,6 +15522,19 @@ fi\n\
             return;
         }
 
+        if let Some(args) = auto_compact_args(trimmed) {
+            self.handle_auto_compact_command(args);
+            return;
+        }
+
+        // Only an open intervention request claims `/auto approve`; otherwise
+        // it is a goal like any other.
+        if trimmed.eq_ignore_ascii_case("approve") && self.auto_state.intervention_reason.is_some()
+        {
+            self.auto_approve_intervention();
+            return;
+        }
+
         let full_auto_enabled = matches!(
             (&self.config.sandbox_policy, self.config.approval_policy),
             (SandboxPolicy::DangerFullAccess, AskForApproval::Never)
@@ -15496,6 +15668,56 @@ fi\n\
         self.request_redraw();
     }
 
+    /// `/auto compact` compacts the running coordinator's history before its
+    /// next decision; `/auto compact RATIO` sets `auto_drive.compact_threshold`.
+    fn handle_auto_compact_command(&mut self, args: &str) {
+        if args.is_empty() {
+            let message = match self.auto_handle.as_ref() {
+                Some(handle) if handle.send(AutoCoordinatorCommand::CompactNow).is_ok() => {
+                    "Auto Drive will compact its history before the next decision."
+                }
+                _ => "`/auto compact` — Auto Drive is not running.",
+            };
+            self.push_background_tail(message.to_string());
+            self.request_redraw();
+            return;
+        }
+
+        let Some(threshold) = args
+            .parse::<f64>()
+            .ok()
+            .filter(|ratio| (MIN_COMPACT_THRESHOLD..=1.0).contains(ratio))
+        else {
+            self.history_push_plain_state(history_cell::new_error_event(format!(
+                "`/auto compact {args}` — expected a ratio between {MIN_COMPACT_THRESHOLD} and 1.0."
+            )));
+            self.request_redraw();
+            return;
+        };
+        self.config.auto_drive.compact_threshold = threshold;
+        if let Ok(home) = code_core::config::find_code_home() {
+            if let Err(err) = code_core::config::set_auto_drive_settings(
+                &home,
+                &self.config.auto_drive,
+                self.config.auto_drive_use_chat_model,
+            ) {
+                tracing::warn!("Failed to persist Auto Drive compact threshold: {err}");
+            }
+        } else {
+            tracing::warn!("Could not locate config home to persist Auto Drive settings");
+        }
+        let percent = (threshold * 100.0).round();
+        let applies = if self.auto_handle.is_some() {
+            " from the next run"
+        } else {
+            ""
+        };
+        self.push_background_tail(format!(
+            "Auto Drive compacts its history at {percent}% of the context window{applies}."
+        ));
+        self.request_redraw();
+    }
+
     fn auto_send_conversation(&mut self) {
         if !self.auto_state.is_active() || self.auto_state.is_waiting_for_response() {
             return;
@@ -15579,6 +15801,7 @@ fi\n\
         &mut self,
         prompt: String,
         conversation: Vec<ResponseItem>,
+        attachments: Vec<InputItem>,
     ) -> bool {
         let Some(handle) = self.auto_handle.as_ref() else {
             return false;
@@ -15586,6 +15809,7 @@ fi\n\
         let command = AutoCoordinatorCommand::HandleUserPrompt {
             _prompt: prompt,
             conversation,
+            attachments,
         };
         match handle.send(command) {
             Ok(()) => {
@@ -15705,6 +15929,11 @@ fi\n\
         let cli_context_raw = cli.as_ref().and_then(|action| action.context.clone());
         let cli_context = Self::normalize_status_field(cli_context_raw);
         let cli_prompt = cli.as_ref().map(|action| action.prompt.clone());
+        self.auto_state.pending_cli_attachments = cli
+            .as_ref()
+            .map(|action| action.attachments.clone())
+            .unwrap_or_default();
+        self.auto_state.pending_cli_model = cli.as_ref().and_then(|action| action.model.clone());
 
         self.auto_state.current_cli_context = cli_context.clone();
         self.auto_state.hide_cli_context_in_ui = planning_turn;
@@ -15895,6 +16124,7 @@ Have we met every part of this goal and is there no further work to do?"#
         &mut self,
         user_response: Option<String>,
         cli_command: Option<String>,
+        attachments: Vec<InputItem>,
     ) {
         if let Some(text) = user_response.clone() {
             if let Some(item) = Self::auto_drive_make_assistant_message(text.clone()) {
@@ -15913,6 +16143,7 @@ Have we met every part of this goal and is there no further work to do?"#
             } else {
                 let mut message: UserMessage = command.into();
                 message.suppress_persistence = true;
+                message.ordered_items.extend(attachments);
                 self.submit_user_message(message);
             }
         } else {
@@ -15951,6 +16182,7 @@ Have we met every part of this goal and is there no further work to do?"#
         &mut self,
         conversation: Vec<ResponseItem>,
         show_notice: bool,
+        estimate: Option<CompactionEstimate>,
     ) {
         let (previous_items, previous_indices) = self.export_auto_drive_items_with_indices();
         self.auto_history.replace_all(conversation.clone());
@@ -15962,6 +16194,20 @@ Have we met every part of this goal and is there no further work to do?"#
                 [COMPACTION_CHECKPOINT_MESSAGE],
             );
         }
+        if let Some(estimate) = estimate {
+            let mut note = format!(
+                "Auto Drive history: ~{} → ~{} tokens",
+                format_with_separators(estimate.before_tokens),
+                format_with_separators(estimate.after_tokens)
+            );
+            if let Some(threshold) = estimate.threshold_tokens {
+                note.push_str(&format!(
+                    " (compacts at ~{})",
+                    format_with_separators(threshold)
+                ));
+            }
+            self.push_background_tail(note);
+        }
         self.auto_rebuild_live_ring();
         self.request_redraw();
     }
@@ -16010,12 +16256,28 @@ Have we met every part of this goal and is there no further work to do?"#
         self.auto_state.intervention_reason = Some(reason.to_string());
         self.history_push_plain_paragraphs(
             PlainMessageKind::Notice,
-            [format!("Intervention required: {reason}")],
+            [format!(
+                "Intervention required: {reason}\nRun `/auto approve` to continue, or reply with guidance."
+            )],
         );
         self.auto_rebuild_live_ring();
         self.request_redraw();
     }
 
+    fn auto_approve_intervention(&mut self) {
+        if let Some(handle) = self.auto_handle.as_ref()
+            && handle
+                .send(AutoCoordinatorCommand::ApproveHeldDecision)
+                .is_ok()
+        {
+            self.auto_state.intervention_reason = None;
+            self.auto_rebuild_live_ring();
+        } else {
+            self.push_background_tail("`/auto approve` — Auto Drive is not running.".to_string());
+        }
+        self.request_redraw();
+    }
+
     fn schedule_auto_cli_prompt(&mut self, decision_seq: u64, prompt_text: String) {
         self.schedule_auto_cli_prompt_with_override(decision_seq, prompt_text, None);
     }
@@ -16624,11 +16886,24 @@ Have we met every part of this goal and is there no further work to do?"#
         self.bottom_pane.set_task_running(false);
         let mut message: UserMessage = full_prompt.into();
         message.suppress_persistence = true;
+        message
+            .ordered_items
+            .append(&mut self.auto_state.pending_cli_attachments);
         if self.auto_state.pending_stop_message.is_some() {
             message.display_text.clear();
         } else if self.auto_state.suppress_next_cli_display {
             message.display_text.clear();
         }
+        if let Some(model) = self.auto_state.pending_cli_model.take() {
+            self.submit_op(Op::SetNextTurnModel { model });
+        }
+        if let Some(sampling) = self
+            .pending_auto_turn_config
+            .as_ref()
+            .and_then(|cfg| cfg.sampling)
+        {
+            self.submit_op(Op::SetNextTurnSampling { sampling });
+        }
         self.submit_user_message(message);
         self.auto_state.pending_agent_actions.clear();
         self.auto_state.pending_agent_timing = None;
@@ -16729,6 +17004,12 @@ Have we met every part of this goal and is there no further work to do?"#
                 if let Some(models) = action.models.as_ref().filter(|list| !list.is_empty()) {
                     agent_lines.push(format!("{LINE_PREFIX}Models: [{}]", models.join(", ")));
                 }
+
+                if let Some(secs) = action.timeout_seconds {
+                    agent_lines.push(format!(
+                        "{LINE_PREFIX}Timeout: pass timeout_seconds: {secs} to agent.create. The agent is cancelled after that and its partial output becomes its result."
+                    ));
+                }
             }
 
             agent_lines.push(String::new());
@@ -26105,6 +26386,10 @@ mod tests {
                     prompt: "echo ready".to_string(),
                     context: None,
                     suppress_ui_context: false,
+                    attachments: Vec::new(),
+                    workstreams: Vec::new(),
+                    complexity: None,
+                    model: None,
                 }),
                 None,
                 Vec::new(),
@@ -26144,6 +26429,10 @@ mod tests {
                     prompt: "echo start".to_string(),
                     context: None,
                     suppress_ui_context: false,
+                    attachments: Vec::new(),
+                    workstreams: Vec::new(),
+                    complexity: None,
+                    model: None,
                 }),
                 None,
                 Vec::new(),
@@ -26193,7 +26482,7 @@ mod tests {
                 .expect("assistant message"),
         ];
 
-        chat.auto_handle_compacted_history(conversation, false);
+        chat.auto_handle_compacted_history(conversation, false, None);
 
         let has_checkpoint = chat.history_cells.iter().any(|cell| {
             cell.display_lines_trimmed().iter().any(|line| {
@@ -26209,6 +26498,15 @@ mod tests {
         );
     }
 
+    #[test]
+    fn auto_compact_subcommand_only_claims_ratios() {
+        assert_eq!(auto_compact_args("compact"), Some(""));
+        assert_eq!(auto_compact_args("COMPACT  0.6"), Some("0.6"));
+        assert_eq!(auto_compact_args("compact 2"), Some("2"));
+        assert_eq!(auto_compact_args("compact the cache layer"), None);
+        assert_eq!(auto_compact_args("compaction"), None);
+    }
+
     #[test]
     fn auto_card_shows_status_title_in_state_detail() {
         let mut harness = ChatWidgetHarness::new();
@@ -26231,6 +26529,10 @@ mod tests {
                     prompt: "echo work".to_string(),
                     context: None,
                     suppress_ui_context: false,
+                    attachments: Vec::new(),
+                    workstreams: Vec::new(),
+                    complexity: None,
+                    model: None,
                 }),
                 None,
                 Vec::new(),
@@ -26567,6 +26869,7 @@ mod tests {
             read_only: false,
             complexity: Some(TurnComplexity::Low),
             text_format_override: None,
+            sampling: None,
         };
         chat.pending_auto_turn_config = Some(turn_config.clone());
         chat.pending_turn_descriptor = Some(TurnDescriptor {
@@ -26651,6 +26954,7 @@ mod tests {
             read_only: false,
             complexity: Some(TurnComplexity::Low),
             text_format_override: None,
+            sampling: None,
         };
         chat.pending_auto_turn_config = Some(turn_config.clone());
         chat.pending_turn_descriptor = Some(TurnDescriptor {
@@ -26915,6 +27219,10 @@ mod tests {
                 prompt: "Run cargo test".to_string(),
                 context: Some("use --all-features".to_string()),
                 suppress_ui_context: false,
+                attachments: Vec::new(),
+                workstreams: Vec::new(),
+                complexity: None,
+                model: None,
             }),
             Some(AutoTurnAgentsTiming::Parallel),
             vec![AutoTurnAgentsAction {
@@ -26923,6 +27231,7 @@ mod tests {
                 write: false,
                 write_requested: Some(false),
                 models: None,
+                timeout_seconds: None,
             }],
             Vec::new(),
         );
@@ -26973,6 +27282,7 @@ mod tests {
             chat.auto_handle_user_reply(
                 Some("Two active agents reporting steady progress.".to_string()),
                 None,
+                Vec::new(),
             );
         }
 
@@ -27018,7 +27328,7 @@ mod tests {
 
         {
             let chat = harness.chat();
-            chat.auto_handle_user_reply(None, Some("/plan".to_string()));
+            chat.auto_handle_user_reply(None, Some("/plan".to_string()), Vec::new());
         }
 
         let events = harness.drain_events();
@@ -27073,6 +27383,7 @@ mod tests {
                 "claude-sonnet-4.5".to_string(),
                 "gemini-3-pro".to_string(),
             ]),
+            timeout_seconds: None,
         }];
         chat.auto_state.pending_agent_timing = Some(AutoTurnAgentsTiming::Blocking);
 
@@ -27112,6 +27423,7 @@ mod tests {
             read_only: false,
             complexity: Some(TurnComplexity::Low),
             text_format_override: None,
+            sampling: None,
         };
         chat.pending_auto_turn_config = Some(turn_config.clone());
         chat.pending_turn_descriptor = Some(TurnDescriptor {
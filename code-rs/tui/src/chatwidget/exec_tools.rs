@@ -1182,6 +1182,7 @@ pub(super) fn handle_exec_end_now(
         duration,
         stdout,
         stderr,
+        sandbox_denied: _,
     } = ev;
     let cmd = chat
         .exec
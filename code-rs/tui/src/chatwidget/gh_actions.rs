@@ -2,41 +2,8 @@ use crate::app_event_sender::AppEventSender;
 use crate::chatwidget::BackgroundOrderTicket;
 use code_core::config::Config;
 use code_core::git_info::collect_git_info;
-use std::process::Command;
-
-/// Source of a GitHub API token used by the watcher.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub(super) enum TokenSource {
-    /// `GITHUB_TOKEN` or `GH_TOKEN` environment variable.
-    Env,
-    /// Fetched via `gh auth token` from the GitHub CLI.
-    GhCli,
-}
-
-/// Obtain a GitHub token, preferring environment variables and falling back to `gh`.
-/// Returns the token string and its source if available.
-pub(super) fn get_github_token() -> Option<(String, TokenSource)> {
-    if let Ok(t) = std::env::var("GITHUB_TOKEN") {
-        if !t.is_empty() {
-            return Some((t, TokenSource::Env));
-        }
-    }
-    if let Ok(t) = std::env::var("GH_TOKEN") {
-        if !t.is_empty() {
-            return Some((t, TokenSource::Env));
-        }
-    }
-    // Fallback: use GitHub CLI if installed and logged in.
-    if let Ok(out) = Command::new("gh").args(["auth", "token"]).output() {
-        if out.status.success() {
-            let token = String::from_utf8_lossy(&out.stdout).trim().to_string();
-            if !token.is_empty() {
-                return Some((token, TokenSource::GhCli));
-            }
-        }
-    }
-    None
-}
+use code_core::github::get_github_token;
+use code_core::github::parse_owner_repo;
 
 /// Start a background task to watch GitHub Actions for the latest push and
 /// surface a failure message if any run for the pushed commit completes with a
@@ -229,26 +196,6 @@ pub(super) fn maybe_watch_after_push(
     });
 }
 
-fn parse_owner_repo(url: &str) -> Option<(String, String)> {
-    // git@github.com:owner/repo.git or https://github.com/owner/repo(.git)
-    if let Some(rest) = url.strip_prefix("git@github.com:") {
-        let s = rest.trim_end_matches(".git");
-        let mut parts = s.splitn(2, '/');
-        let owner = parts.next()?.to_string();
-        let repo = parts.next()?.to_string();
-        return Some((owner, repo));
-    }
-    if let Some(pos) = url.find("github.com/") {
-        let s = &url[pos + "github.com/".len()..];
-        let s = s.trim_end_matches(".git");
-        let mut parts = s.splitn(2, '/');
-        let owner = parts.next()?.to_string();
-        let repo = parts.next()?.to_string();
-        return Some((owner, repo));
-    }
-    None
-}
-
 fn surface_failure(
     tx: &AppEventSender,
     ticket: &BackgroundOrderTicket,
@@ -5426,6 +5426,14 @@ pub(crate) fn new_status_output(
         "  • Session total: ".into(),
         format_with_separators(total_usage.blended_total()).into(),
     ]));
+    // Cache hit rate: <session>% (all sessions <global>%)
+    if let Some(session_rate) = total_usage.cache_hit_rate() {
+        let mut cache_line = format!("  • Cache hit rate: {:.0}%", session_rate * 100.0);
+        if let Some(global_rate) = code_core::prompt_cache::global_usage().cache_hit_rate() {
+            cache_line.push_str(&format!(" (all sessions {:.0}%)", global_rate * 100.0));
+        }
+        lines.push(Line::from(cache_line));
+    }
 
     // 📐 Model Limits
     let context_window = config.model_context_window;
@@ -47,6 +47,7 @@ pub fn list_sessions_for_cwd(
             min_user_messages: 1,
             include_archived: false,
             include_deleted: false,
+            tag: None,
             limit: Some(MAX_RESULTS),
         };
 
@@ -73,7 +74,61 @@ pub fn list_sessions_for_cwd(
         }
     };
 
-    // Execute the async fetch, reusing an existing runtime when available.
+    run_catalog_fetch(fetch)
+}
+
+/// Return sessions whose messages match `query` (any folder), best match
+/// first, with the matching excerpt as the snippet.
+pub fn search_sessions(
+    query: &str,
+    code_home: &Path,
+    exclude_path: Option<&Path>,
+) -> Vec<ResumeCandidate> {
+    const MAX_RESULTS: usize = 50;
+
+    let code_home = code_home.to_path_buf();
+    let text = query.to_string();
+    let exclude_path = exclude_path.map(|p| p.to_path_buf());
+
+    let fetch = async move {
+        let catalog = SessionCatalog::new(code_home.clone());
+        let query = SessionQuery {
+            sources: vec![
+                SessionSource::Cli,
+                SessionSource::VSCode,
+                SessionSource::Exec,
+            ],
+            limit: Some(MAX_RESULTS),
+            ..SessionQuery::default()
+        };
+
+        match catalog.search(&text, &query).await {
+            Ok(hits) => hits
+                .into_iter()
+                .filter(|hit| {
+                    exclude_path.as_deref().is_none_or(|exclude| {
+                        entry_to_rollout_path(&code_home, &hit.entry) != exclude
+                    })
+                })
+                .map(|hit| ResumeCandidate {
+                    snippet: Some(hit.snippet),
+                    ..entry_to_candidate(&code_home, hit.entry)
+                })
+                .collect(),
+            Err(err) => {
+                tracing::warn!("failed to search session catalog: {err}");
+                Vec::new()
+            }
+        }
+    };
+
+    run_catalog_fetch(fetch)
+}
+
+/// Execute the async fetch, reusing an existing runtime when available.
+fn run_catalog_fetch(
+    fetch: impl Future<Output = Vec<ResumeCandidate>> + Send + 'static,
+) -> Vec<ResumeCandidate> {
     match Handle::try_current() {
         Ok(handle) => {
             let handle = handle.clone();
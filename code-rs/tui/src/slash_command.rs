@@ -80,6 +80,7 @@ pub enum SlashCommand {
     Validation,
     Mcp,
     Resume,
+    Search,
     Login,
     // Prompt-expanding commands
     Plan,
@@ -98,6 +99,7 @@ impl SlashCommand {
             SlashCommand::Chrome => "connect to your Chrome browser",
             SlashCommand::Browser => "open internal browser",
             SlashCommand::Resume => "resume a past session for this folder",
+            SlashCommand::Search => "search past sessions by message text",
             SlashCommand::Plan => "create a comprehensive plan (multiple agents)",
             SlashCommand::Solve => "solve a challenging problem (multiple agents)",
             SlashCommand::Code => "perform a coding task (multiple agents)",
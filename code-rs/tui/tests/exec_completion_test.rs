@@ -52,6 +52,7 @@ fn exec_command_completes_properly() {
             stderr: String::new(),
             exit_code: 0,
             duration: Duration::from_millis(50),
+            sandbox_denied: false,
         }),
         order: Some(next_order_meta(1, &mut seq)),
     });
@@ -281,6 +281,7 @@ fn exec_history_small_viewport_keeps_last_line() {
             stderr: String::new(),
             exit_code: 0,
             duration: Duration::from_millis(120),
+            sandbox_denied: false,
         }),
         order: Some(order(&mut seq)),
     });
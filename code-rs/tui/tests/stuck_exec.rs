@@ -63,6 +63,7 @@ fn exec_cell_clears_after_patch_flow() {
             stderr: String::new(),
             exit_code: 0,
             duration: Duration::from_millis(50),
+            sandbox_denied: false,
         }),
         order: Some(next_order_meta(1, &mut seq)),
     });
@@ -162,6 +163,7 @@ fn synthetic_end_clears_cancelled_exec_spinner() {
             stderr: "Command cancelled by user.".to_string(),
             exit_code: 130,
             duration: Duration::ZERO,
+            sandbox_denied: false,
         }),
         order: Some(next_order_meta(1, &mut seq)),
     });
@@ -219,6 +221,7 @@ fn exec_begin_upgrades_running_tool_cell() {
             stderr: String::new(),
             exit_code: 0,
             duration: Duration::from_millis(5),
+            sandbox_denied: false,
         }),
         order: Some(next_order_meta(1, &mut seq)),
     });
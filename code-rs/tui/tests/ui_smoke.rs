@@ -577,6 +577,7 @@ fn smoke_exec_begin_end_consistency() {
         stderr: String::new(),
         exit_code: 0,
         duration: Duration::from_secs(2),
+        sandbox_denied: false,
     };
 
     assert_eq!(end.call_id, begin.call_id);
@@ -764,6 +765,7 @@ fn smoke_exec_command_stream() {
             stderr: String::new(),
             exit_code: 0,
             duration: Duration::from_millis(20),
+            sandbox_denied: false,
         }),
         order: Some(OrderMeta {
             request_ordinal: 1,